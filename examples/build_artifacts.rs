@@ -0,0 +1,67 @@
+//! Builds the package whose manifest is given on the command line and lists
+//! the files the build produced, using cargo as a library.
+//!
+//!     build_artifacts path/to/Cargo.toml
+
+extern crate cargo;
+
+use std::io::stderr;
+use std::io::util::NullWriter;
+use std::os;
+
+use cargo::core::{Shell, MultiShell, ShellConfig};
+use cargo::core::shell::Auto;
+use cargo::ops;
+
+fn main() {
+    let args = os::args();
+    if args.len() != 2 {
+        println!("usage: {} <manifest-path>", args[0]);
+        os::set_exit_status(1);
+        return
+    }
+    let manifest = os::make_absolute(&Path::new(args[1].as_slice()));
+
+    // Status messages are discarded, while errors and warnings still go to
+    // stderr.
+    let out = Shell::create(box NullWriter as Box<Writer + Send>, config());
+    let err = Shell::create(box stderr() as Box<Writer + Send>, config());
+    let mut shell = MultiShell::new(out, err, false);
+
+    let result = {
+        let mut opts = ops::CompileOptions {
+            env: "compile",
+            shell: &mut shell,
+            jobs: None,
+            target: None,
+            dev_deps: false,
+            features: [],
+            no_default_features: false,
+            all_features: false,
+            spec: None,
+            keep_going: false,
+            timings: false,
+            examples: ops::NoExamples,
+            all_targets: false,
+            skip_targets: &[],
+            extra_examples: &[],
+            target_rustc_args: None,
+        };
+        match ops::facade::build(&manifest, &mut opts) {
+            Ok(result) => result,
+            Err(e) => {
+                let _ = shell.error(e.to_string());
+                os::set_exit_status(101);
+                return
+            }
+        }
+    };
+
+    for &(ref pkgid, ref target, ref path) in result.artifacts.iter() {
+        println!("{} {} {}", pkgid, target.get_name(), path.display());
+    }
+}
+
+fn config() -> ShellConfig {
+    ShellConfig { color_config: Auto, verbose: false, tty: false }
+}
@@ -0,0 +1,59 @@
+use cargo::ops;
+use cargo::core::MultiShell;
+use cargo::util::{CliResult, CliError};
+use cargo::util::important_paths::find_root_manifest_for_cwd;
+
+#[deriving(Decodable)]
+struct Options {
+    flag_vers: Option<String>,
+    flag_dev: bool,
+    flag_set_version: Option<String>,
+    flag_manifest_path: Option<String>,
+    flag_verbose: bool,
+    flag_color: Option<String>,
+    arg_crate: Option<String>,
+}
+
+pub const USAGE: &'static str = "
+Add a dependency to a Cargo.toml manifest file
+
+Usage:
+    cargo add [options] <crate>
+    cargo add [options] --set-version VERSION
+    cargo add -h | --help
+
+Options:
+    -h, --help              Print this message
+    --vers REQ              Version requirement of the dependency, `*` if absent
+    --dev                   Add the dependency to `dev-dependencies`
+    --set-version VERSION   Set the version of the package instead
+    --manifest-path PATH    Path to the manifest to edit
+    -v, --verbose           Use verbose output
+    --color WHEN            Coloring: auto, always, never
+
+Only the affected lines of the manifest are rewritten; comments, ordering and
+formatting elsewhere in the file are left alone. If the dependency is already
+listed, its version requirement is updated.
+";
+
+pub fn execute(options: Options, shell: &mut MultiShell) -> CliResult<Option<()>> {
+    shell.set_verbose(options.flag_verbose);
+    try!(shell.set_color_config(options.flag_color.as_ref().map(|s| s.as_slice())));
+    let root = try!(find_root_manifest_for_cwd(options.flag_manifest_path.clone()));
+
+    let res = match options.arg_crate {
+        Some(ref name) => {
+            let opts = ops::AddOptions {
+                name: name.as_slice(),
+                version: options.flag_vers.as_ref().map(|s| s.as_slice()),
+                dev: options.flag_dev,
+            };
+            ops::add(&root, &opts, shell)
+        }
+        None => {
+            let version = options.flag_set_version.as_ref().unwrap();
+            ops::set_version(&root, version.as_slice(), shell)
+        }
+    };
+    res.map(|_| None).map_err(|err| CliError::from_boxed(err, 1))
+}
@@ -1,24 +1,20 @@
 use std::os;
 
 use cargo::core::MultiShell;
-use cargo::ops::CompileOptions;
 use cargo::ops;
-use cargo::util::important_paths::{find_root_manifest_for_cwd};
 use cargo::util::{CliResult, CliError};
 
-#[deriving(Decodable)]
-struct Options {
-    flag_package: Option<String>,
-    flag_jobs: Option<uint>,
-    flag_features: Vec<String>,
-    flag_no_default_features: bool,
-    flag_target: Option<String>,
-    flag_manifest_path: Option<String>,
-    flag_verbose: bool,
-    flag_release: bool,
-}
+compile_options!(Options {
+    flag_profile: Option<String>,
+    flag_all_targets: bool,
+    flag_print_artifact: bool,
+    flag_print: Option<String>,
+    flag_lib: bool,
+    flag_bin: Vec<String>,
+    flag_example: Vec<String>
+})
 
-pub const USAGE: &'static str = "
+pub const USAGE: &'static str = concat!("
 Compile a local package and all of its dependencies
 
 Usage:
@@ -26,45 +22,148 @@ Usage:
 
 Options:
     -h, --help               Print this message
-    -p SPEC, --package SPEC  Package to build
-    -j N, --jobs N           The number of jobs to run in parallel
-    --release                Build artifacts in release mode, with optimizations
-    --features FEATURES      Space-separated list of features to also build
-    --no-default-features    Do not build the `default` feature
-    --target TRIPLE          Build for the target triple
-    --manifest-path PATH     Path to the manifest to compile
-    -v, --verbose            Use verbose output
-
+    --profile NAME           Build with the given profile: dev, release, test,
+                             bench or doc
+    --all-targets            Also build all tests, examples and benchmarks
+    --print-artifact         Print the path of each selected target's artifact
+    --print INFO             Print information instead of building: cfg
+    --lib                    Select the library
+    --bin NAME               Select a binary (repeatable)
+    --example NAME           Select and build an example (repeatable)
+", compile_options_usage!(), "
 If the --package argument is given, then SPEC is a package id specification
 which indicates which package should be built. If it is not given, then the
 current package is built. For more information on SPEC and its format, see the
 `cargo help pkgid` command.
-";
+
+With --all-targets the tests, examples and benchmarks of the package are
+compiled as well, without being run, in the same build as the rest of the
+package. Tests and examples are built with the test profile and benchmarks are
+compiled as tests. With --release they are all optimized instead: tests and
+examples are built like `cargo test --release` builds them and benchmarks with
+the bench profile.
+
+With --profile the targets are compiled the way the command using that profile
+would, without running anything: `test` builds the test executables of
+`cargo test` and `bench` those of `cargo bench`, and their paths are printed
+once the build has finished. `dev` is the default and `release` is the same as
+--release.
+
+With --print-artifact the absolute path of the file built for each target
+selected with --lib, --bin and --example is printed on its own line once the
+build has finished, and nothing else is written to stdout. Without any of those
+flags the library is selected, or the binary if there is no library. Without
+--print-artifact the build fails if a selected target produced no file.
+
+Selected examples are built along with the package, with the test profile like
+with --all-targets, or with their release profile under --release.
+
+With --print cfg nothing is built. Instead the cfg values which the
+`[target.cfg(...).dependencies]` sections of the package are checked against
+are printed, one per line: those rustc sets for the target selected with
+--target or the `build.target` configuration, followed by the enabled features
+of the package.
+
+With --timings a table of how long each unit of work took is printed once the
+build has finished, and the same data is written to target/cargo-timings.json.
+");
 
 pub fn execute(options: Options, shell: &mut MultiShell) -> CliResult<Option<()>> {
     debug!("executing; cmd=cargo-build; args={}", os::args());
-    shell.set_verbose(options.flag_verbose);
 
-    let root = try!(find_root_manifest_for_cwd(options.flag_manifest_path));
-
-    let env = if options.flag_release {
-        "release"
-    } else {
-        "compile"
+    let profile = match options.flag_profile {
+        Some(ref profile) if options.flag_release => {
+            return Err(CliError::new(format!("`--release` can't be combined \
+                                              with `--profile {}`", profile),
+                                     1))
+        }
+        Some(ref profile) => profile.as_slice(),
+        None if options.flag_release => "release",
+        None => "dev",
     };
-
-    let mut opts = CompileOptions {
-        env: env,
-        shell: shell,
-        jobs: options.flag_jobs,
-        target: options.flag_target.as_ref().map(|t| t.as_slice()),
-        dev_deps: false,
-        features: options.flag_features.as_slice(),
-        no_default_features: options.flag_no_default_features,
-        spec: options.flag_package.as_ref().map(|s| s.as_slice()),
+    let env = match profile {
+        "dev" => "compile",
+        "release" => "release",
+        "test" => "test",
+        "bench" => "bench",
+        "doc" => "doc",
+        other => {
+            return Err(CliError::new(format!("unknown profile `{}`, expected \
+                                              one of: dev, release, test, \
+                                              bench, doc", other), 1))
+        }
     };
 
-    ops::compile(&root, &mut opts).map(|_| None).map_err(|err| {
-        CliError::from_boxed(err, 101)
-    })
+    match options.flag_print {
+        Some(ref info) if info.as_slice() == "cfg" => {
+            let (root, mut opts) = try!(options.compile_options(env, shell));
+            let cfg = try!(ops::target_cfg(&root, &mut opts).map_err(|err| {
+                CliError::from_boxed(err, 1)
+            }));
+            for cfg in cfg.iter() {
+                println!("{}", cfg);
+            }
+            return Ok(None)
+        }
+        Some(ref info) => {
+            return Err(CliError::new(format!("unknown value `{}` for \
+                                              `--print`, expected: cfg",
+                                             info), 1))
+        }
+        None => {}
+    }
+
+    let mut selections = Vec::new();
+    if options.flag_lib { selections.push(ops::SelectLib) }
+    for name in options.flag_bin.iter() {
+        selections.push(ops::SelectBin(name.as_slice()));
+    }
+    for name in options.flag_example.iter() {
+        selections.push(ops::SelectExample(name.as_slice()));
+    }
+    let selected_default = selections.len() == 0;
+    if selected_default { selections.push(ops::DefaultTarget) }
+
+    // The paths are meant to be consumed by other programs, so the status
+    // output gets out of their way.
+    if options.flag_print_artifact { shell.redirect_out_to_stderr() }
+
+    let (root, mut opts) = try!(options.compile_options(env, shell));
+    let builds_tests = env == "test" || env == "bench";
+    opts.dev_deps = builds_tests || options.flag_all_targets ||
+                    options.flag_example.len() > 0;
+    if env == "test" { opts.examples = ops::TestedExamples }
+    opts.all_targets = options.flag_all_targets;
+    opts.extra_examples = options.flag_example.as_slice();
+
+    let compilation = try!(ops::compile(&root, &mut opts).map_err(|err| {
+        CliError::from_boxed(err, 1)
+    }));
+
+    // The executables `cargo test` or `cargo bench` would have run.
+    if builds_tests {
+        let mut tests = compilation.tests.iter().map(|&(_, ref target,
+                                                         ref exe)| {
+            (target.get_name(), exe)
+        }).collect::<Vec<_>>();
+        tests.sort();
+        for &(_, exe) in tests.iter() {
+            try!(opts.shell.status("Executable", exe.display()).map_err(|e| {
+                CliError::from_error(e, 1)
+            }));
+        }
+    }
+
+    // Targets selected explicitly must have been built even if their paths
+    // aren't printed.
+    if options.flag_print_artifact || !selected_default {
+        let paths = ops::artifact_paths(&compilation, selections.as_slice());
+        let paths = try!(paths.map_err(|err| CliError::from_boxed(err, 1)));
+        if options.flag_print_artifact {
+            for path in paths.iter() {
+                println!("{}", path.display());
+            }
+        }
+    }
+    Ok(None)
 }
@@ -16,10 +16,16 @@ use cargo::{execute_main_without_stdin, handle_error, shell};
 use cargo::core::MultiShell;
 use cargo::util::{CliError, CliResult};
 
+#[macro_escape]
+mod compile_options;
+
 #[deriving(Decodable)]
 struct Flags {
     flag_list: bool,
     flag_verbose: bool,
+    flag_color: Option<String>,
+    flag_yes: bool,
+    flag_no_confirm: bool,
     arg_command: String,
     arg_args: Vec<String>,
 }
@@ -28,7 +34,7 @@ const USAGE: &'static str = "
 Rust's package manager
 
 Usage:
-    cargo <command> [<args>...]
+    cargo [options] <command> [<args>...]
     cargo [options]
 
 Options:
@@ -36,6 +42,9 @@ Options:
     -V, --version    Print version info and exit
     --list           List installed commands
     -v, --verbose    Use verbose output
+    --color WHEN     Coloring: auto, always, never
+    -y, --yes        Answer yes to every confirmation instead of asking
+    --no-confirm     Same as `--yes`
 
 Some common cargo commands are:
     build       Compile the current project
@@ -48,6 +57,9 @@ Some common cargo commands are:
     update      Update dependencies listed in Cargo.lock
 
 See 'cargo help <command>' for more information on a specific command.
+
+External `cargo-*` subcommands are told about `--yes` through the
+CARGO_ASSUME_YES environment variable, which is set to 1.
 ";
 
 fn main() {
@@ -55,6 +67,7 @@ fn main() {
 }
 
 macro_rules! each_subcommand( ($macro:ident) => ({
+    $macro!(add)
     $macro!(bench)
     $macro!(build)
     $macro!(clean)
@@ -74,6 +87,7 @@ macro_rules! each_subcommand( ($macro:ident) => ({
     $macro!(publish)
     $macro!(read_manifest)
     $macro!(run)
+    $macro!(rustc)
     $macro!(test)
     $macro!(update)
     $macro!(verify_project)
@@ -89,6 +103,11 @@ macro_rules! each_subcommand( ($macro:ident) => ({
 fn execute(flags: Flags, shell: &mut MultiShell) -> CliResult<Option<()>> {
     debug!("executing; cmd=cargo; args={}", os::args());
     shell.set_verbose(flags.flag_verbose);
+    try!(shell.set_color_config(flags.flag_color.as_ref().map(|s| s.as_slice())));
+    // External subcommands learn about `--yes` through `CARGO_ASSUME_YES`,
+    // which cargo itself honors as well when one of them calls back into it.
+    shell.set_assume_yes(flags.flag_yes || flags.flag_no_confirm ||
+                         os::getenv("CARGO_ASSUME_YES").is_some());
 
     if flags.flag_list {
         println!("Installed Commands:");
@@ -160,12 +179,15 @@ fn execute_subcommand(cmd: &str, args: &[String], shell: &mut MultiShell) {
             return handle_error(CliError::new(msg, 127), shell)
         }
     };
-    let status = Command::new(command)
-                         .args(args)
-                         .stdin(InheritFd(0))
-                         .stdout(InheritFd(1))
-                         .stderr(InheritFd(2))
-                         .status();
+    let mut command = Command::new(command);
+    command.args(args)
+           .stdin(InheritFd(0))
+           .stdout(InheritFd(1))
+           .stderr(InheritFd(2));
+    if shell.assume_yes() {
+        command.env("CARGO_ASSUME_YES", "1");
+    }
+    let status = command.status();
 
     match status {
         Ok(ExitStatus(0)) => (),
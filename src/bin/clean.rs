@@ -9,8 +9,11 @@ use cargo::util::important_paths::{find_root_manifest_for_cwd};
 struct Options {
     flag_package: Option<String>,
     flag_target: Option<String>,
+    flag_release: bool,
+    flag_doc: bool,
     flag_manifest_path: Option<String>,
     flag_verbose: bool,
+    flag_color: Option<String>,
 }
 
 pub const USAGE: &'static str = "
@@ -24,16 +27,24 @@ Options:
     -p SPEC, --package SPEC  Package to clean artifacts for
     --manifest-path PATH     Path to the manifest to the package to clean
     --target TRIPLE          Target triple to clean output for (default all)
+    --release                Only remove artifacts of the release profile
+    --doc                    Only remove generated documentation
     -v, --verbose            Use verbose output
+    --color WHEN             Coloring: auto, always, never
 
 If the --package argument is given, then SPEC is a package id specification
 which indicates which package's artifacts should be cleaned out. If it is not
 given, then all packages' artifacts are removed. For more information on SPEC
 and its format, see the `cargo help pkgid` command.
+
+By default both the target directory and the documentation output are removed.
+The --release and --doc flags restrict cleaning to release artifacts or to
+documentation respectively, and may not be given together.
 ";
 
 pub fn execute(options: Options, shell: &mut MultiShell) -> CliResult<Option<()>> {
     shell.set_verbose(options.flag_verbose);
+    try!(shell.set_color_config(options.flag_color.as_ref().map(|s| s.as_slice())));
     debug!("executing; cmd=cargo-clean; args={}", os::args());
 
     let root = try!(find_root_manifest_for_cwd(options.flag_manifest_path));
@@ -41,8 +52,10 @@ pub fn execute(options: Options, shell: &mut MultiShell) -> CliResult<Option<()>
         shell: shell,
         spec: options.flag_package.as_ref().map(|s| s.as_slice()),
         target: options.flag_target.as_ref().map(|s| s.as_slice()),
+        release: options.flag_release,
+        doc: options.flag_doc,
     };
     ops::clean(&root, &mut opts).map(|_| None).map_err(|err| {
-      CliError::from_boxed(err, 101)
+      CliError::from_boxed(err, 1)
     })
 }
@@ -0,0 +1,107 @@
+//! The options shared by the commands which compile the local package:
+//! `build`, `test`, `bench`, `doc` and `run`.
+//!
+//! A command declares its `Options` with `compile_options!`, which adds the
+//! fields of the shared flags to its own, and splices
+//! `compile_options_usage!()` into its usage so that every command documents
+//! and accepts exactly the same flags. The shared flags are then turned into
+//! `CompileOptions` by the `compile_options` method of the struct, which the
+//! command adjusts for what it does.
+//!
+//! `--release` is one of the shared flags: each command names the environment
+//! it compiles in without it, and `release_env` picks the optimized
+//! counterpart of that environment when it's given.
+
+/// The environment to compile in instead of `env` with `--release`.
+///
+/// Benchmarks are always optimized and documentation has no release build, so
+/// `--release` leaves their environments alone.
+pub fn release_env(env: &'static str) -> &'static str {
+    match env {
+        "compile" => "release",
+        "test" => "release-test",
+        env => env,
+    }
+}
+
+macro_rules! compile_options_usage( () => (
+"    -p SPEC, --package SPEC  Package to build
+    -j N, --jobs N           The number of jobs to run in parallel
+    --release                Build artifacts in release mode, with optimizations
+    --features FEATURES      Space-separated list of features to also build
+    --no-default-features    Do not build the `default` feature
+    --all-features           Build every feature and optional dependency
+    --target TRIPLE          Build for the target triple
+    --manifest-path PATH     Path to the manifest of the package
+    --keep-going             Build as much as possible after a failure
+    --timings                Report how long each crate took to build
+    --skip-target NAME       Don't build the target named NAME (repeatable)
+    -v, --verbose ...        Use verbose output (-vv also shows build script
+                             output)
+    --color WHEN             Coloring: auto, always, never
+"
+) )
+
+macro_rules! compile_options( ($name:ident { $($field:ident: $t:ty),* }) => (
+    #[deriving(Decodable)]
+    struct $name {
+        flag_package: Option<String>,
+        flag_jobs: Option<uint>,
+        flag_release: bool,
+        flag_features: Vec<String>,
+        flag_no_default_features: bool,
+        flag_all_features: bool,
+        flag_target: Option<String>,
+        flag_manifest_path: Option<String>,
+        flag_keep_going: bool,
+        flag_timings: bool,
+        flag_skip_target: Vec<String>,
+        flag_verbose: uint,
+        flag_color: Option<String>,
+        $($field: $t),*
+    }
+
+    impl $name {
+        /// Configures `shell` according to the shared flags, and returns the
+        /// manifest to operate on along with the options to compile it in
+        /// `env`, or in its release counterpart with `--release`, as
+        /// `cargo build` would without any flags of its own.
+        fn compile_options<'a>(&'a self, env: &'static str,
+                               shell: &'a mut ::cargo::core::MultiShell)
+                               -> ::cargo::util::CliResult<(Path,
+                                   ::cargo::ops::CompileOptions<'a>)> {
+            shell.set_verbosity(self.flag_verbose);
+            try!(shell.set_color_config(self.flag_color.as_ref().map(|s| {
+                s.as_slice()
+            })));
+            let root = try!(::cargo::util::important_paths::
+                            find_root_manifest_for_cwd(self.flag_manifest_path
+                                                           .clone()));
+            let env = if self.flag_release {
+                ::compile_options::release_env(env)
+            } else {
+                env
+            };
+            Ok((root, ::cargo::ops::CompileOptions {
+                env: env,
+                shell: shell,
+                jobs: self.flag_jobs,
+                target: self.flag_target.as_ref().map(|s| s.as_slice()),
+                dev_deps: false,
+                features: self.flag_features.as_slice(),
+                no_default_features: self.flag_no_default_features,
+                all_features: self.flag_all_features,
+                spec: self.flag_package.as_ref().map(|s| s.as_slice()),
+                keep_going: self.flag_keep_going,
+                timings: self.flag_timings,
+                examples: ::cargo::ops::AllExamples,
+                all_targets: false,
+                skip_targets: self.flag_skip_target.as_slice(),
+                extra_examples: &[],
+                only_targets: None,
+                target_rustc_args: None,
+                fs: None,
+            }))
+        }
+    }
+) )
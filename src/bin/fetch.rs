@@ -7,6 +7,7 @@ use cargo::util::important_paths::find_root_manifest_for_cwd;
 struct Options {
     flag_manifest_path: Option<String>,
     flag_verbose: bool,
+    flag_color: Option<String>,
 }
 
 pub const USAGE: &'static str = "
@@ -19,6 +20,7 @@ Options:
     -h, --help              Print this message
     --manifest-path PATH    Path to the manifest to fetch dependencies for
     -v, --verbose           Use verbose output
+    --color WHEN            Coloring: auto, always, never
 
 If a lockfile is available, this command will ensure that all of the git
 dependencies and/or registries dependencies are downloaded and locally
@@ -32,9 +34,10 @@ all updated.
 
 pub fn execute(options: Options, shell: &mut MultiShell) -> CliResult<Option<()>> {
     shell.set_verbose(options.flag_verbose);
+    try!(shell.set_color_config(options.flag_color.as_ref().map(|s| s.as_slice())));
     let root = try!(find_root_manifest_for_cwd(options.flag_manifest_path));
     try!(ops::fetch(&root, shell).map_err(|e| {
-        CliError::from_boxed(e, 101)
+        CliError::from_boxed(e, 1)
     }));
     Ok(None)
 }
@@ -9,6 +9,7 @@ use cargo::util::important_paths::find_root_manifest_for_cwd;
 struct Options {
     flag_manifest_path: Option<String>,
     flag_verbose: bool,
+    flag_color: Option<String>,
 }
 
 pub const USAGE: &'static str = "
@@ -21,13 +22,19 @@ Options:
     -h, --help              Print this message
     --manifest-path PATH    Path to the manifest to generate a lockfile for
     -v, --verbose           Use verbose output
+    --color WHEN            Coloring: auto, always, never
+
+All dependencies are resolved from scratch and written to `Cargo.lock` without
+building anything. If a lock file already existed, the packages which were
+added, removed or updated are reported.
 ";
 
 pub fn execute(options: Options, shell: &mut MultiShell) -> CliResult<Option<()>> {
     debug!("executing; cmd=cargo-generate-lockfile; args={}", os::args());
     shell.set_verbose(options.flag_verbose);
+    try!(shell.set_color_config(options.flag_color.as_ref().map(|s| s.as_slice())));
     let root = try!(find_root_manifest_for_cwd(options.flag_manifest_path));
 
     ops::generate_lockfile(&root, shell)
-        .map(|_| None).map_err(|err| CliError::from_boxed(err, 101))
+        .map(|_| None).map_err(|err| CliError::from_boxed(err, 1))
 }
@@ -8,6 +8,7 @@ struct Options {
     flag_url: String,
     flag_reference: String,
     flag_verbose: bool,
+    flag_color: Option<String>,
 }
 
 pub const USAGE: &'static str = "
@@ -18,10 +19,12 @@ Usage:
 Options:
     -h, --help              Print this message
     -v, --verbose           Use verbose output
+    --color WHEN            Coloring: auto, always, never
 ";
 
 pub fn execute(options: Options, shell: &mut MultiShell) -> CliResult<Option<()>> {
     shell.set_verbose(options.flag_verbose);
+    try!(shell.set_color_config(options.flag_color.as_ref().map(|s| s.as_slice())));
     let Options { flag_url: url, flag_reference: reference, .. } = options;
 
     let url = try!(url.as_slice().to_url().map_err(|e| {
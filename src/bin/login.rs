@@ -10,6 +10,7 @@ struct Options {
     flag_host: Option<String>,
     arg_token: Option<String>,
     flag_verbose: bool,
+    flag_color: Option<String>,
 }
 
 pub const USAGE: &'static str = "
@@ -22,11 +23,13 @@ Options:
     -h, --help              Print this message
     --host HOST             Host to set the token for
     -v, --verbose           Use verbose output
+    --color WHEN            Coloring: auto, always, never
 
 ";
 
 pub fn execute(options: Options, shell: &mut MultiShell) -> CliResult<Option<()>> {
     shell.set_verbose(options.flag_verbose);
+    try!(shell.set_color_config(options.flag_color.as_ref().map(|s| s.as_slice())));
     let token = match options.arg_token.clone() {
         Some(token) => token,
         None => {
@@ -34,14 +37,14 @@ pub fn execute(options: Options, shell: &mut MultiShell) -> CliResult<Option<()>
             let host = options.flag_host.unwrap_or(default);
             println!("please visit {}/me and paste the API Token below", host);
             try!(io::stdin().read_line().map_err(|e| {
-                CliError::from_boxed(box e, 101)
+                CliError::from_boxed(box e, 1)
             }))
         }
     };
 
     let token = token.as_slice().trim().to_string();
     try!(ops::registry_login(shell, token).map_err(|e| {
-        CliError::from_boxed(e, 101)
+        CliError::from_boxed(e, 1)
     }));
     Ok(None)
 }
@@ -2,16 +2,22 @@ use std::os;
 
 use cargo::ops;
 use cargo::core::MultiShell;
+use cargo::ops::{VersionControl, Git, Hg, NoVcs};
 use cargo::util::{CliResult, CliError};
 
 #[deriving(Decodable)]
 struct Options {
     flag_verbose: bool,
+    flag_color: Option<String>,
     flag_bin: bool,
     flag_travis: bool,
     flag_hg: bool,
     flag_git: bool,
     flag_no_git: bool,
+    flag_vcs: Option<String>,
+    flag_name: Option<String>,
+    flag_yes: bool,
+    flag_no_confirm: bool,
     arg_path: String,
 }
 
@@ -24,34 +30,71 @@ Usage:
 
 Options:
     -h, --help          Print this message
-    --no-git            Don't initialize a new git repository
-    --git               Initialize a new git repository, overriding a
+    --vcs VCS           Initialize a new repository for the given version
+                        control system (git, hg or none), overriding a
                         global `git = false` configuration
-    --hg                Initialize a new hg repository
+    --no-git            Same as `--vcs none`
+    --git               Same as `--vcs git`
+    --hg                Same as `--vcs hg`
+    --name NAME         Set the package name, which defaults to the name
+                        of the directory
     --travis            Create a .travis.yml file
     --bin               Use a binary instead of a library template
+    -y, --yes           Create the package even if <path> is an existing
+                        directory, without asking for confirmation. Files
+                        which are already there are kept
+    --no-confirm        Same as `--yes`
     -v, --verbose       Use verbose output
+    --color WHEN        Coloring: auto, always, never
 ";
 
 pub fn execute(options: Options, shell: &mut MultiShell) -> CliResult<Option<()>> {
     debug!("executing; cmd=cargo-new; args={}", os::args());
     shell.set_verbose(options.flag_verbose);
+    try!(shell.set_color_config(options.flag_color.as_ref().map(|s| s.as_slice())));
+    // `--yes` may also have been passed to `cargo` itself.
+    if options.flag_yes || options.flag_no_confirm {
+        shell.set_assume_yes(true);
+    }
 
-    let Options { flag_no_git, flag_travis,
-                  flag_bin,arg_path, flag_git, flag_hg, .. } = options;
+    let version_control = try!(version_control(&options));
+    let Options { flag_travis, flag_bin, arg_path, flag_name, .. } = options;
 
     let opts = ops::NewOptions {
-        no_git: flag_no_git,
-        git: flag_git,
-        hg: flag_hg,
+        version_control: version_control,
         travis: flag_travis,
         path: arg_path.as_slice(),
         bin: flag_bin,
+        name: flag_name.as_ref().map(|s| s.as_slice()),
     };
 
     ops::new(opts, shell).map(|_| None).map_err(|err| {
-        CliError::from_boxed(err, 101)
+        CliError::from_boxed(err, 1)
     })
 }
 
+fn version_control(options: &Options) -> CliResult<Option<VersionControl>> {
+    let mut chosen = Vec::new();
+    match options.flag_vcs.as_ref().map(|s| s.as_slice()) {
+        Some("git") => chosen.push(Git),
+        Some("hg") => chosen.push(Hg),
+        Some("none") => chosen.push(NoVcs),
+        Some(other) => {
+            return Err(CliError::new(format!("unknown version control \
+                                              system `{}`, expected one of \
+                                              `git`, `hg` or `none`", other),
+                                     1))
+        }
+        None => {}
+    }
+    if options.flag_git { chosen.push(Git) }
+    if options.flag_hg { chosen.push(Hg) }
+    if options.flag_no_git { chosen.push(NoVcs) }
 
+    match chosen.as_slice() {
+        [] => Ok(None),
+        [ref vcs] => Ok(Some(vcs.clone())),
+        _ => Err(CliError::new("only one of --vcs, --git, --hg and --no-git \
+                                may be given", 1)),
+    }
+}
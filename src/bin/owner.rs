@@ -11,6 +11,7 @@ struct Options {
     flag_remove: Option<Vec<String>>,
     flag_index: Option<String>,
     flag_verbose: bool,
+    flag_color: Option<String>,
 }
 
 pub const USAGE: &'static str = "
@@ -26,6 +27,7 @@ Options:
     --index INDEX           Registry index to modify owners for
     --token TOKEN           API token to use when authenticating
     -v, --verbose           Use verbose output
+    --color WHEN            Coloring: auto, always, never
 
 This command will modify the owners for a package on the specified registry (or
 default). Note that owners of a package can upload new versions, yank old
@@ -34,6 +36,7 @@ versions, and also modify the set of owners, so take caution!
 
 pub fn execute(options: Options, shell: &mut MultiShell) -> CliResult<Option<()>> {
     shell.set_verbose(options.flag_verbose);
+    try!(shell.set_color_config(options.flag_color.as_ref().map(|s| s.as_slice())));
     let root = try!(find_root_manifest_for_cwd(None));
     try!(ops::modify_owners(&root, shell,
                             options.arg_crate,
@@ -41,7 +44,7 @@ pub fn execute(options: Options, shell: &mut MultiShell) -> CliResult<Option<()>
                             options.flag_index,
                             options.flag_add,
                             options.flag_remove).map_err(|e| {
-        CliError::from_boxed(e, 101)
+        CliError::from_boxed(e, 1)
     }));
     Ok(None)
 }
@@ -6,6 +6,7 @@ use cargo::util::important_paths::find_root_manifest_for_cwd;
 #[deriving(Decodable)]
 struct Options {
     flag_verbose: bool,
+    flag_color: Option<String>,
     flag_manifest_path: Option<String>,
     flag_no_verify: bool,
 }
@@ -21,13 +22,18 @@ Options:
     --manifest-path PATH    Path to the manifest to compile
     --no-verify             Don't verify the contents by building them
     -v, --verbose           Use verbose output
+    --color WHEN            Coloring: auto, always, never
 
+Every `git` and `path` dependency must also specify a `version`, which the
+packaged manifest uses in place of the `git` or `path` key. This is required
+even with --no-verify.
 ";
 
 pub fn execute(options: Options, shell: &mut MultiShell) -> CliResult<Option<()>> {
     shell.set_verbose(options.flag_verbose);
+    try!(shell.set_color_config(options.flag_color.as_ref().map(|s| s.as_slice())));
     let root = try!(find_root_manifest_for_cwd(options.flag_manifest_path));
     ops::package(&root, shell, !options.flag_no_verify).map(|_| None).map_err(|err| {
-        CliError::from_boxed(err, 101)
+        CliError::from_boxed(err, 1)
     })
 }
@@ -6,6 +6,7 @@ use cargo::util::important_paths::{find_root_manifest_for_cwd};
 #[deriving(Decodable)]
 struct Options {
     flag_verbose: bool,
+    flag_color: Option<String>,
     flag_manifest_path: Option<String>,
     arg_spec: Option<String>,
 }
@@ -20,6 +21,7 @@ Options:
     -h, --help              Print this message
     --manifest-path PATH    Path to the manifest to the package to clean
     -v, --verbose           Use verbose output
+    --color WHEN            Coloring: auto, always, never
 
 Given a <spec> argument, print out the fully qualified package id specifier.
 This command will generate an error if <spec> is ambiguous as to which package
@@ -45,11 +47,12 @@ Example Package IDs
 pub fn execute(options: Options,
                shell: &mut MultiShell) -> CliResult<Option<()>> {
     shell.set_verbose(options.flag_verbose);
+    try!(shell.set_color_config(options.flag_color.as_ref().map(|s| s.as_slice())));
     let root = try!(find_root_manifest_for_cwd(options.flag_manifest_path.clone()));
 
     let spec = options.arg_spec.as_ref().map(|s| s.as_slice());
     let spec = try!(ops::pkgid(&root, spec, shell).map_err(|err| {
-      CliError::from_boxed(err, 101)
+      CliError::from_boxed(err, 1)
     }));
     println!("{}", spec);
     Ok(None)
@@ -9,7 +9,10 @@ struct Options {
     flag_token: Option<String>,
     flag_manifest_path: Option<String>,
     flag_verbose: bool,
+    flag_color: Option<String>,
     flag_no_verify: bool,
+    flag_dry_run: bool,
+    flag_skip_if_published: bool,
 }
 
 pub const USAGE: &'static str = "
@@ -23,23 +26,30 @@ Options:
     --host HOST             Host to upload the package to
     --token TOKEN           Token to use when uploading
     --no-verify             Don't verify package tarball before publish
+    --dry-run               Perform all checks without uploading
+    --skip-if-published     Do nothing if this version is already published
     --manifest-path PATH    Path to the manifest to compile
     -v, --verbose           Use verbose output
+    --color WHEN            Coloring: auto, always, never
 
 ";
 
 pub fn execute(options: Options, shell: &mut MultiShell) -> CliResult<Option<()>> {
     shell.set_verbose(options.flag_verbose);
+    try!(shell.set_color_config(options.flag_color.as_ref().map(|s| s.as_slice())));
     let Options {
         flag_token: token,
         flag_host: host,
         flag_manifest_path,
         flag_no_verify: no_verify,
+        flag_dry_run: dry_run,
+        flag_skip_if_published: skip_if_published,
         ..
     } = options;
 
     let root = try!(find_root_manifest_for_cwd(flag_manifest_path.clone()));
-    ops::publish(&root, shell, token, host, !no_verify).map(|_| None).map_err(|err| {
-        CliError::from_boxed(err, 101)
+    ops::publish(&root, shell, token, host, !no_verify, dry_run,
+                 skip_if_published).map(|_| None).map_err(|err| {
+        CliError::from_boxed(err, 1)
     })
 }
@@ -1,32 +1,101 @@
-use cargo::core::{MultiShell, Package, Source};
-use cargo::util::{CliResult, CliError};
+use std::io;
+use std::os;
+use serialize::json;
+
+use cargo::core::{MultiShell, Package, Source, SourceId};
+use cargo::ops;
+use cargo::util::{CliResult, CliError, ErrorLayer, error_chain, human};
 use cargo::sources::{PathSource};
 
 #[deriving(Decodable)]
 struct Options {
-    flag_manifest_path: String,
+    flag_manifest_path: Option<String>,
+    flag_stdin: bool,
+    flag_root: Option<String>,
+    flag_message_format: Option<String>,
+}
+
+/// How an error is written to standard output with `--message-format json`.
+#[deriving(Encodable)]
+struct ErrorMessage {
+    error: Vec<ErrorLayer>,
 }
 
 pub const USAGE: &'static str = "
 Usage:
     cargo read-manifest [options] --manifest-path=PATH
+    cargo read-manifest [options] --stdin --root=DIR
     cargo read-manifest -h | --help
 
 Options:
     -h, --help              Print this message
     -v, --verbose           Use verbose output
+    --stdin                 Read the manifest from standard input
+    --root DIR              Directory of the package whose manifest is read
+                            from standard input
+    --message-format FMT    Error format: human, json [default: human]
+
+With --stdin the manifest is read from standard input instead of a file, and
+the targets and relative paths it contains are resolved as if it were the
+manifest of the package in the directory given by --root. Errors refer to the
+manifest as `<stdin>`.
+
+With `--message-format json` an error is written to standard output as a JSON
+object instead, whose `error` key lists the error and each of its causes in
+turn. Every one of them has a `message`, the `file` it is about and the `kind`
+of failure, the latter two of which may be null.
 ";
 
-pub fn execute(options: Options, _: &mut MultiShell) -> CliResult<Option<Package>> {
-    let path = Path::new(options.flag_manifest_path.as_slice());
-    let mut source = try!(PathSource::for_path(&path).map_err(|e| {
-        CliError::new(e.description(), 1)
-    }));
+pub fn execute(options: Options, shell: &mut MultiShell) -> CliResult<Option<Package>> {
+    let json = match options.flag_message_format.as_ref().map(|s| s.as_slice()) {
+        None | Some("human") => false,
+        Some("json") => true,
+        Some(s) => {
+            return Err(CliError::new(format!("invalid message format `{}`, \
+                                              expected `human` or `json`", s),
+                                     1))
+        }
+    };
+
+    match read(options, shell) {
+        Err(ref err) if json => {
+            let message = ErrorMessage { error: error_chain(&*err.error) };
+            println!("{}", json::encode(&message));
+            Err(CliError::with_exit_code(human(""), err.exit_code))
+        }
+        result => result.map(Some),
+    }
+}
+
+fn read(options: Options, shell: &mut MultiShell) -> CliResult<Package> {
+    let pkg = if options.flag_stdin {
+        let root = Path::new(options.flag_root.unwrap());
+        let root = os::make_absolute(&root);
+        let contents = try!(io::stdin().read_to_end().map_err(|e| {
+            CliError::new(format!("failed to read standard input: {}", e), 1)
+        }));
+        let source_id = try!(SourceId::for_path(&root).map_err(|e| {
+            CliError::from_boxed(e, 1)
+        }));
+        try!(ops::read_package_from(contents.as_slice(), "<stdin>", &root,
+                                    &source_id).map_err(|err| {
+            CliError::from_boxed(err, 1)
+        }))
+    } else {
+        let path = Path::new(options.flag_manifest_path.unwrap());
+        let mut source = try!(PathSource::for_path(&path).map_err(|e| {
+            CliError::from_boxed(e, 1)
+        }));
+
+        try!(source.update().map_err(|err| CliError::from_boxed(err, 1)));
 
-    try!(source.update().map_err(|err| CliError::new(err.description(), 1)));
+        try!(source.get_root_package().map_err(|err| {
+            CliError::from_boxed(err, 1)
+        }))
+    };
 
-    source
-        .get_root_package()
-        .map(|pkg| Some(pkg))
-        .map_err(|err| CliError::from_boxed(err, 1))
+    for warning in pkg.get_manifest().get_warnings().iter() {
+        try!(shell.warn(warning).map_err(|e| CliError::from_error(e, 1)));
+    }
+    Ok(pkg)
 }
@@ -4,72 +4,48 @@ use cargo::ops;
 use cargo::core::{MultiShell};
 use cargo::core::manifest::{BinTarget, ExampleTarget};
 use cargo::util::{CliResult, CliError, human};
-use cargo::util::important_paths::{find_root_manifest_for_cwd};
 
-#[deriving(Decodable)]
-struct Options {
+compile_options!(Options {
     flag_name: Option<String>,
     flag_example: Option<String>,
-    flag_jobs: Option<uint>,
-    flag_features: Vec<String>,
-    flag_no_default_features: bool,
-    flag_target: Option<String>,
-    flag_manifest_path: Option<String>,
-    flag_verbose: bool,
-    flag_release: bool,
-    arg_args: Vec<String>,
-}
+    arg_args: Vec<String>
+})
 
-pub const USAGE: &'static str = "
+pub const USAGE: &'static str = concat!("
 Run the main binary of the local package (src/main.rs)
 
 Usage:
     cargo run [options] [--] [<args>...]
 
 Options:
-    -h, --help              Print this message
-    --name NAME             Name of the bin target to run
-    --example NAME          Name of the example target to run
-    -j N, --jobs N          The number of jobs to run in parallel
-    --release               Build artifacts in release mode, with optimizations
-    --features FEATURES     Space-separated list of features to also build
-    --no-default-features   Do not build the `default` feature
-    --target TRIPLE         Build for the target triple
-    --manifest-path PATH    Path to the manifest to execute
-    -v, --verbose           Use verbose output
-
+    -h, --help               Print this message
+    --name NAME              Name of the bin target to run
+    --example NAME           Name of the example target to run
+", compile_options_usage!(), "
 If neither `--name` or `--example` are given, then if the project only has one
 bin target it will be run. Otherwise `--name` specifies the bin target to run,
 and `--example` specifies the example target to run. At most one of `--name` or
 `--example` can be provided.
 
 All of the trailing arguments are passed as to the binary to run.
-";
 
-pub fn execute(options: Options, shell: &mut MultiShell) -> CliResult<Option<()>> {
-    shell.set_verbose(options.flag_verbose);
-    let root = try!(find_root_manifest_for_cwd(options.flag_manifest_path));
+The binary runs in the directory cargo was invoked from. The absolute path of
+the directory containing the package's Cargo.toml is available in the
+CARGO_MANIFEST_DIR environment variable.
 
-    let env = if options.flag_example.is_some() {
-        "test"
-    } else if options.flag_release {
-        "release"
-    } else {
-        "compile"
-    };
+If the --package argument is given, then SPEC is a package id specification
+which indicates which package's binary should be run. If it is not given, then
+a binary of the current package is run.
+");
 
-    let mut compile_opts = ops::CompileOptions {
-        env: env,
-        shell: shell,
-        jobs: options.flag_jobs,
-        target: options.flag_target.as_ref().map(|t| t.as_slice()),
-        dev_deps: true,
-        features: options.flag_features.as_slice(),
-        no_default_features: options.flag_no_default_features,
-        spec: None,
-    };
+pub fn execute(options: Options, shell: &mut MultiShell) -> CliResult<Option<()>> {
+    let env = if options.flag_example.is_some() {"test"} else {"compile"};
+
+    let (root, mut compile_opts) = try!(options.compile_options(env, shell));
+    compile_opts.dev_deps = true;
 
-    let (target_kind, name) = match (options.flag_name, options.flag_example) {
+    let (target_kind, name) = match (options.flag_name.clone(),
+                                     options.flag_example.clone()) {
         (Some(bin), None) => (BinTarget, Some(bin)),
         (None, Some(example)) => (ExampleTarget, Some(example)),
         (None, None) => (BinTarget, None),
@@ -82,13 +58,14 @@ pub fn execute(options: Options, shell: &mut MultiShell) -> CliResult<Option<()>
                             name,
                             &mut compile_opts,
                             options.arg_args.as_slice()).map_err(|err| {
-        CliError::from_boxed(err, 101)
+        CliError::from_boxed(err, 1)
     }));
     match err {
         None => Ok(None),
         Some(err) => {
             Err(match err.exit {
-                Some(ExitStatus(i)) => CliError::from_boxed(box err, i as uint),
+                Some(ExitStatus(i)) => CliError::with_exit_code(box err,
+                                                                i as uint),
                 _ => CliError::from_boxed(box err, 101),
             })
         }
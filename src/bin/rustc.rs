@@ -0,0 +1,115 @@
+use std::os;
+
+use cargo::core::MultiShell;
+use cargo::ops::CompileOptions;
+use cargo::ops;
+use cargo::util::important_paths::{find_root_manifest_for_cwd};
+use cargo::util::{CliResult, CliError};
+
+#[deriving(Decodable)]
+struct Options {
+    arg_opts: Vec<String>,
+    flag_jobs: Option<uint>,
+    flag_features: Vec<String>,
+    flag_no_default_features: bool,
+    flag_target: Option<String>,
+    flag_manifest_path: Option<String>,
+    flag_verbose: uint,
+    flag_color: Option<String>,
+    flag_release: bool,
+    flag_bin: Option<String>,
+    flag_test: Option<String>,
+    flag_example: Option<String>,
+    flag_bench: Option<String>,
+}
+
+pub const USAGE: &'static str = "
+Compile a package and all of its dependencies, passing extra flags to rustc
+
+Usage:
+    cargo rustc [options] [--] [<opts>...]
+
+Options:
+    -h, --help               Print this message
+    -j N, --jobs N           The number of jobs to run in parallel
+    --bin NAME               Pass the flags to the specified binary
+    --test NAME              Pass the flags to the specified test
+    --example NAME           Pass the flags to the specified example
+    --bench NAME             Pass the flags to the specified benchmark
+    --release                Build artifacts in release mode, with optimizations
+    --features FEATURES      Space-separated list of features to also build
+    --no-default-features    Do not build the `default` feature
+    --target TRIPLE          Build for the target triple
+    --manifest-path PATH     Path to the manifest to compile
+    -v, --verbose ...        Use verbose output (-vv also shows build script
+                             output)
+    --color WHEN             Coloring: auto, always, never
+
+Dependencies are compiled as usual, then a single target of the current package
+is compiled with all of the trailing arguments appended to its rustc invocation.
+Only that target is built, and the extra arguments are not passed to any of its
+dependencies.
+
+By default the library is selected, or the binary if the package has no library
+and exactly one binary. Any other target has to be selected with one of the
+`--bin`, `--test`, `--example` or `--bench` flags.
+";
+
+pub fn execute(options: Options, shell: &mut MultiShell) -> CliResult<Option<()>> {
+    debug!("executing; cmd=cargo-rustc; args={}", os::args());
+    shell.set_verbosity(options.flag_verbose);
+    try!(shell.set_color_config(options.flag_color.as_ref().map(|s| s.as_slice())));
+
+    let root = try!(find_root_manifest_for_cwd(options.flag_manifest_path));
+
+    let mut selections = Vec::new();
+    match options.flag_bin {
+        Some(ref name) => selections.push(("compile", ops::SelectBin(name.as_slice()))),
+        None => {}
+    }
+    match options.flag_test {
+        Some(ref name) => selections.push(("test", ops::SelectTest(name.as_slice()))),
+        None => {}
+    }
+    match options.flag_example {
+        Some(ref name) => {
+            selections.push(("test", ops::SelectExample(name.as_slice())))
+        }
+        None => {}
+    }
+    match options.flag_bench {
+        Some(ref name) => selections.push(("bench", ops::SelectBench(name.as_slice()))),
+        None => {}
+    }
+    if selections.len() > 1 {
+        return Err(CliError::new("only one of `--bin`, `--test`, `--example` \
+                                  or `--bench` may be given", 1))
+    }
+    let (env, selection) = selections.pop().unwrap_or(("compile", ops::DefaultTarget));
+    let env = if env == "compile" && options.flag_release {"release"} else {env};
+
+    let mut opts = CompileOptions {
+        env: env,
+        shell: shell,
+        jobs: options.flag_jobs,
+        target: options.flag_target.as_ref().map(|t| t.as_slice()),
+        dev_deps: env == "test" || env == "bench",
+        features: options.flag_features.as_slice(),
+        no_default_features: options.flag_no_default_features,
+        all_features: false,
+        spec: None,
+        keep_going: false,
+        timings: false,
+        examples: ops::AllExamples,
+        all_targets: false,
+        skip_targets: &[],
+        extra_examples: &[],
+        only_targets: None,
+        target_rustc_args: Some((selection, options.arg_opts.as_slice())),
+        fs: None,
+    };
+
+    ops::compile(&root, &mut opts).map(|_| None).map_err(|err| {
+        CliError::from_boxed(err, 1)
+    })
+}
@@ -2,24 +2,19 @@ use std::io::process::ExitStatus;
 
 use cargo::ops;
 use cargo::core::MultiShell;
-use cargo::util::{CliResult, CliError, CargoError};
-use cargo::util::important_paths::{find_root_manifest_for_cwd};
+use cargo::util::{CliResult, CliError, CargoError, BuildFailure};
 
-#[deriving(Decodable)]
-struct Options {
+compile_options!(Options {
     arg_args: Vec<String>,
-    flag_features: Vec<String>,
-    flag_jobs: Option<uint>,
-    flag_manifest_path: Option<String>,
     flag_name: Option<String>,
-    flag_no_default_features: bool,
+    flag_lib: bool,
+    flag_test: Vec<String>,
+    flag_doc: bool,
     flag_no_run: bool,
-    flag_package: Option<String>,
-    flag_target: Option<String>,
-    flag_verbose: bool,
-}
+    flag_no_examples: bool
+})
 
-pub const USAGE: &'static str = "
+pub const USAGE: &'static str = concat!("
 Execute all unit and integration tests of a local package
 
 Usage:
@@ -28,15 +23,12 @@ Usage:
 Options:
     -h, --help               Print this message
     --name NAME              Name of the test executable to run
+    --lib                    Run only the library's unit tests
+    --test NAME              Run only the named integration test (repeatable)
+    --doc                    Run only the library's documentation tests
     --no-run                 Compile, but don't run tests
-    -p SPEC, --package SPEC  Package to run tests for
-    -j N, --jobs N           The number of jobs to run in parallel
-    --features FEATURES      Space-separated list of features to also build
-    --no-default-features    Do not build the `default` feature
-    --target TRIPLE          Build for the target triple
-    --manifest-path PATH     Path to the manifest to build tests for
-    -v, --verbose            Use verbose output
-
+    --no-examples            Don't compile the examples
+", compile_options_usage!(), "
 All of the trailing arguments are passed to the test binaries generated for
 filtering tests and generally providing options configuring how they run. For
 example, this will run all tests with the name `foo` in their name:
@@ -47,37 +39,71 @@ If the --package argument is given, then SPEC is a package id specification
 which indicates which package should be tested. If it is not given, then the
 current package is tested. For more information on SPEC and its format, see the
 `cargo help pkgid` command.
-";
+
+By default the unit and integration tests of every target are run, followed by
+the documentation tests of the library. The `--lib`, `--test`, `--name` and
+`--doc` flags each select one of these phases, and when any of them is given
+only the selected phases are run, and only the targets they need are built:
+`--doc` alone builds just the library. `--name` may match a target of any kind,
+so everything is still built with it. Documentation tests are never run for a
+library with `doctest = false`.
+
+All examples are compiled (but not run) to make sure they keep building, unless
+`--no-examples` is given. An example can opt out of this with `test = false` in
+its `[[example]]` section.
+
+With `--release` everything is built in target/release with optimizations: the
+tests with the `[profile.bench]` settings, and the library, binaries, examples
+and dependencies they use with the `[profile.release]` settings.
+
+Test binaries run with the package root (the directory containing Cargo.toml)
+as their working directory, wherever cargo is invoked from, so fixtures can be
+opened with paths relative to it. The absolute path of that directory is also
+available in the CARGO_MANIFEST_DIR environment variable.
+");
 
 pub fn execute(options: Options, shell: &mut MultiShell) -> CliResult<Option<()>> {
-    let root = try!(find_root_manifest_for_cwd(options.flag_manifest_path));
-    shell.set_verbose(options.flag_verbose);
+    let mut selections = Vec::new();
+    if options.flag_lib { selections.push(ops::SelectLib) }
+    for name in options.flag_test.iter() {
+        selections.push(ops::SelectTest(name.as_slice()));
+    }
+    let selected = options.flag_lib || options.flag_doc ||
+                   options.flag_test.len() > 0;
+
+    let (root, mut compile_opts) = try!(options.compile_options("test", shell));
+    compile_opts.dev_deps = true;
+    compile_opts.examples = if options.flag_no_examples {
+        ops::NoExamples
+    } else {
+        ops::TestedExamples
+    };
+    if selected && options.flag_name.is_none() {
+        compile_opts.only_targets = Some(selections.as_slice());
+    }
 
     let mut ops = ops::TestOptions {
         name: options.flag_name.as_ref().map(|s| s.as_slice()),
         no_run: options.flag_no_run,
-        compile_opts: ops::CompileOptions {
-            env: "test",
-            shell: shell,
-            jobs: options.flag_jobs,
-            target: options.flag_target.as_ref().map(|s| s.as_slice()),
-            dev_deps: true,
-            features: options.flag_features.as_slice(),
-            no_default_features: options.flag_no_default_features,
-            spec: options.flag_package.as_ref().map(|s| s.as_slice()),
-        },
+        lib: options.flag_lib,
+        tests: options.flag_test.as_slice(),
+        doc: options.flag_doc,
+        compile_opts: compile_opts,
     };
 
     let err = try!(ops::run_tests(&root, &mut ops,
                                   options.arg_args.as_slice()).map_err(|err| {
-        CliError::from_boxed(err, 101)
+        CliError::from_boxed(err, 1)
     }));
     match err {
         None => Ok(None),
         Some(err) => {
             Err(match err.exit {
-                Some(ExitStatus(i)) => CliError::new("", i as uint),
-                _ => CliError::from_boxed(err.concrete().mark_human(), 101)
+                Some(ExitStatus(..)) => {
+                    CliError::new("", BuildFailure.exit_code())
+                }
+                _ => CliError::from_boxed(err.concrete().of_kind(BuildFailure)
+                                             .mark_human(), 1)
             })
         }
     }
@@ -11,8 +11,10 @@ struct Options {
     flag_package: Option<String>,
     flag_aggressive: bool,
     flag_precise: Option<String>,
+    flag_dry_run: bool,
     flag_manifest_path: Option<String>,
     flag_verbose: bool,
+    flag_color: Option<String>,
 }
 
 pub const USAGE: &'static str = "
@@ -27,8 +29,10 @@ Options:
     -p SPEC, --package SPEC  Package to update
     --aggressive             Force updating all dependencies of <name> as well
     --precise PRECISE        Update a single dependency to exactly PRECISE
+    --dry-run                Print the changes without updating the lock file
     --manifest-path PATH     Path to the manifest to compile
     -v, --verbose            Use verbose output
+    --color WHEN             Coloring: auto, always, never
 
 This command requires that a `Cargo.lock` already exists as generated by
 `cargo build` or related commands.
@@ -48,12 +52,16 @@ be updated to.
 If SPEC is not given, then all dependencies will be re-resolved and
 updated.
 
+With --dry-run the packages which would be added, removed or updated are
+printed, but the lock file is left untouched.
+
 For more information about package id specifications, see `cargo help pkgid`.
 ";
 
 pub fn execute(options: Options, shell: &mut MultiShell) -> CliResult<Option<()>> {
     debug!("executing; cmd=cargo-update; args={}", os::args());
     shell.set_verbose(options.flag_verbose);
+    try!(shell.set_color_config(options.flag_color.as_ref().map(|s| s.as_slice())));
     let root = try!(find_root_manifest_for_cwd(options.flag_manifest_path));
 
     let spec = if options.arg_spec.is_some() {
@@ -69,10 +77,11 @@ pub fn execute(options: Options, shell: &mut MultiShell) -> CliResult<Option<()>
         aggressive: options.flag_aggressive,
         precise: options.flag_precise.as_ref().map(|s| s.as_slice()),
         to_update: spec.map(|s| s.as_slice()),
+        dry_run: options.flag_dry_run,
         shell: shell,
     };
 
     ops::update_lockfile(&root, &mut update_opts)
-        .map(|_| None).map_err(|err| CliError::from_boxed(err, 101))
+        .map(|_| None).map_err(|err| CliError::from_boxed(err, 1))
 }
 
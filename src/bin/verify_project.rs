@@ -1,45 +1,52 @@
-extern crate toml;
-
 use std::collections::HashMap;
-use std::io::File;
 use std::os;
 
 use cargo::core::MultiShell;
+use cargo::ops;
 use cargo::util::CliResult;
+use cargo::util::important_paths::find_root_manifest_for_cwd;
 
 pub type Error = HashMap<String, String>;
 
 #[deriving(Decodable)]
 struct Flags {
-    flag_manifest_path: String,
+    flag_manifest_path: Option<String>,
     flag_verbose: bool,
+    flag_color: Option<String>,
 }
 
 pub const USAGE: &'static str = "
+Check whether the package is one Cargo can load
+
 Usage:
-    cargo verify-project [options] --manifest-path PATH
+    cargo verify-project [options]
     cargo verify-project -h | --help
 
 Options:
     -h, --help              Print this message
     --manifest-path PATH    Path to the manifest to verify
     -v, --verbose           Use verbose output
+    --color WHEN            Coloring: auto, always, never
+
+The manifest is parsed and validated and the package's targets are inferred,
+but its dependencies aren't resolved. A single line of JSON is printed, either
+`{\"success\":\"true\"}`, or `{\"invalid\":\"<reason>\"}` along with an exit
+status of 1.
 ";
 
 pub fn execute(args: Flags,
                shell: &mut MultiShell) -> CliResult<Option<Error>> {
     shell.set_verbose(args.flag_verbose);
+    try!(shell.set_color_config(args.flag_color.as_ref().map(|s| s.as_slice())));
 
-    let file = Path::new(args.flag_manifest_path);
-    let contents = match File::open(&file).read_to_string() {
-        Ok(s) => s,
-        Err(e) => return fail("invalid", format!("error reading file: {}",
-                                                 e).as_slice())
-    };
-    match toml::Parser::new(contents.as_slice()).parse() {
-        None => return fail("invalid", "invalid-format"),
-        Some(..) => {}
+    let root = match find_root_manifest_for_cwd(args.flag_manifest_path) {
+        Ok(root) => root,
+        Err(e) => return fail("invalid", e.error.to_string().as_slice()),
     };
+    match ops::verify_project(&root) {
+        Ok(()) => {}
+        Err(e) => return fail("invalid", e.to_string().as_slice()),
+    }
 
     let mut h = HashMap::new();
     h.insert("success".to_string(), "true".to_string());
@@ -10,6 +10,7 @@ struct Options {
     flag_vers: Option<String>,
     flag_index: Option<String>,
     flag_verbose: bool,
+    flag_color: Option<String>,
     flag_undo: bool,
 }
 
@@ -26,6 +27,7 @@ Options:
     --index INDEX           Registry index to yank from
     --token TOKEN           API token to use when authenticating
     -v, --verbose           Use verbose output
+    --color WHEN            Coloring: auto, always, never
 
 The yank command removes a previously pushed crate's version from the server's
 index. This command does not delete any data, and the crate will still be
@@ -38,6 +40,7 @@ crates to be locked to any yanked version.
 
 pub fn execute(options: Options, shell: &mut MultiShell) -> CliResult<Option<()>> {
     shell.set_verbose(options.flag_verbose);
+    try!(shell.set_color_config(options.flag_color.as_ref().map(|s| s.as_slice())));
     let root = try!(find_root_manifest_for_cwd(None));
     try!(ops::yank(&root, shell,
                    options.arg_crate,
@@ -45,7 +48,7 @@ pub fn execute(options: Options, shell: &mut MultiShell) -> CliResult<Option<()>
                    options.flag_token,
                    options.flag_index,
                    options.flag_undo).map_err(|e| {
-        CliError::from_boxed(e, 101)
+        CliError::from_boxed(e, 1)
     }));
     Ok(None)
 }
@@ -2,6 +2,7 @@ use semver::VersionReq;
 
 use core::{SourceId, Summary, PackageId};
 use util::CargoResult;
+use util::cfg::{Cfg, CfgExpr};
 
 /// Informations about a dependency requested by a Cargo manifest.
 #[deriving(PartialEq,Clone,Show)]
@@ -113,6 +114,12 @@ impl Dependency {
         self
     }
 
+    /// Set the name of the package this dependency refers to
+    pub fn name(mut self, name: &str) -> Dependency {
+        self.name = name.to_string();
+        self
+    }
+
     /// Set the source id for this dependency
     pub fn source_id(mut self, id: SourceId) -> Dependency {
         self.source_id = id;
@@ -172,27 +179,93 @@ impl Dependency {
         self.only_for_platform.as_ref().map(|s| s.as_slice())
     }
 
-    /// Returns true if the dependency should be built for this platform.
-    pub fn is_active_for_platform(&self, platform: &str) -> bool {
-        match self.only_for_platform {
-            None => true,
-            Some(ref p) if p.as_slice() == platform => true,
-            _ => false
+    /// Returns true if the dependency should be built for this platform,
+    /// which is the target triple `platform` with the cfg values returned by
+    /// `cfg`. Those are only asked for if the dependency is keyed on a cfg
+    /// expression.
+    pub fn is_active_for_platform(&self, platform: &str,
+                                  cfg: || -> CargoResult<Vec<Cfg>>)
+                                  -> CargoResult<bool> {
+        let only_for_platform = match self.only_for_platform {
+            None => return Ok(true),
+            Some(ref p) => p.as_slice(),
+        };
+        // Manifests have their cfg expressions checked when they are loaded,
+        // so an invalid one can only come from a registry and never matches.
+        match CfgExpr::parse_platform(only_for_platform) {
+            Some(Ok(expr)) => Ok(expr.matches(try!(cfg()).as_slice())),
+            Some(Err(..)) => Ok(false),
+            None => Ok(only_for_platform == platform),
         }
     }
 }
 
+/// The form of a dependency in the JSON emitted by `cargo read-manifest`.
+///
+/// External tools consume this, so the field names must stay stable.
 #[deriving(PartialEq,Clone,Encodable)]
 pub struct SerializedDependency {
     name: String,
-    req: String
+    req: String,
+    /// One of `normal`, `dev` or `build`.
+    kind: String,
+    optional: bool,
+    uses_default_features: bool,
+    features: Vec<String>,
+    /// The platform the dependency is restricted to, if any.
+    target: Option<String>,
 }
 
 impl SerializedDependency {
     pub fn from_dependency(dep: &Dependency) -> SerializedDependency {
         SerializedDependency {
             name: dep.get_name().to_string(),
-            req: dep.get_version_req().to_string()
+            req: dep.get_version_req().to_string(),
+            kind: match dep.kind {
+                Normal => "normal",
+                Development => "dev",
+                Build => "build",
+            }.to_string(),
+            optional: dep.is_optional(),
+            uses_default_features: dep.uses_default_features(),
+            features: dep.get_features().to_vec(),
+            target: dep.get_only_for_platform().map(|s| s.to_string()),
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use serialize::json;
+
+    use super::{Dependency, SerializedDependency, Development};
+    use core::source::SourceId;
+    use util::ToUrl;
+
+    #[test]
+    fn serialized_fields() {
+        let url = "http://example.com".to_url().unwrap();
+        let dep = Dependency::parse("foo", None,
+                                    &SourceId::for_registry(&url)).unwrap()
+                             .kind(Development)
+                             .optional(true)
+                             .default_features(false)
+                             .features(vec!["bar".to_string()])
+                             .only_for_platform(Some("i686-pc-windows-gnu"
+                                                         .to_string()));
+        let json = json::encode(&SerializedDependency::from_dependency(&dep));
+        assert_eq!(json.as_slice(),
+                   "{\"name\":\"foo\",\"req\":\"*\",\"kind\":\"dev\",\
+                    \"optional\":true,\"uses_default_features\":false,\
+                    \"features\":[\"bar\"],\
+                    \"target\":\"i686-pc-windows-gnu\"}");
+
+        let dep = Dependency::parse("foo", None,
+                                    &SourceId::for_registry(&url)).unwrap();
+        let json = json::encode(&SerializedDependency::from_dependency(&dep));
+        assert_eq!(json.as_slice(),
+                   "{\"name\":\"foo\",\"req\":\"*\",\"kind\":\"normal\",\
+                    \"optional\":false,\"uses_default_features\":true,\
+                    \"features\":[],\"target\":null}");
+    }
+}
@@ -7,7 +7,7 @@ use serialize::{Encoder,Encodable};
 use core::{Dependency, PackageId, Summary};
 use core::package_id::Metadata;
 use core::dependency::SerializedDependency;
-use util::{CargoResult, human};
+use util::{CargoResult, human, short_hash};
 
 /// Contains all the informations about a package, as loaded from a Cargo.toml.
 #[deriving(PartialEq,Clone)]
@@ -18,6 +18,7 @@ pub struct Manifest {
     doc_dir: Path,
     build: Vec<String>,         // TODO: deprecated, remove
     links: Option<String>,
+    build_per_profile: bool,
     warnings: Vec<String>,
     exclude: Vec<String>,
     metadata: ManifestMetadata,
@@ -36,15 +37,17 @@ impl Show for Manifest {
 /// registry.
 ///
 /// Note that many of these fields can contain invalid values such as the
-/// homepage, repository, documentation, or license. These fields are not
-/// validated by cargo itself, but rather it is up to the registry when uploaded
-/// to validate these fields. Cargo will itself accept any valid TOML
-/// specification for these values.
+/// homepage, repository, or documentation. These fields are not validated by
+/// cargo itself, but rather it is up to the registry when uploaded to validate
+/// these fields. The license is the exception, it is normalized against the
+/// SPDX license list unless a license file is given, and only a license which
+/// isn't on the list is kept as written.
 #[deriving(PartialEq, Clone)]
 pub struct ManifestMetadata {
     pub authors: Vec<String>,
     pub keywords: Vec<String>,
     pub license: Option<String>,
+    pub license_file: Option<String>,   // file, not contents
     pub description: Option<String>,    // not markdown
     pub readme: Option<String>,         // file, not contents
     pub homepage: Option<String>,       // url
@@ -88,19 +91,27 @@ pub enum LibKind {
     StaticLib
 }
 
+/// All kinds of libraries which can be requested via `crate-type`.
+static LIB_KINDS: [LibKind, ..4] = [Lib, Rlib, Dylib, StaticLib];
+
 impl LibKind {
     pub fn from_str(string: &str) -> CargoResult<LibKind> {
-        match string {
-            "lib" => Ok(Lib),
-            "rlib" => Ok(Rlib),
-            "dylib" => Ok(Dylib),
-            "staticlib" => Ok(StaticLib),
-            _ => Err(human(format!("{} was not one of lib|rlib|dylib|staticlib",
-                                   string)))
+        match LIB_KINDS.iter().find(|k| k.crate_type() == string) {
+            Some(kind) => Ok(kind.clone()),
+            None => {
+                let valid = LIB_KINDS.iter().map(|k| {
+                    format!("`{}`", k.crate_type())
+                }).collect::<Vec<String>>();
+                Err(human(format!("unknown crate type `{}`, expected one of {}",
+                                  string, valid.connect(", "))))
+            }
         }
     }
 
     pub fn from_strs<S: Str>(strings: Vec<S>) -> CargoResult<Vec<LibKind>> {
+        if strings.len() == 0 {
+            return Err(human("at least one crate type must be specified"))
+        }
         strings.iter().map(|s| LibKind::from_str(s.as_slice())).collect()
     }
 
@@ -127,7 +138,7 @@ pub struct Profile {
     env: String, // compile, test, dev, bench, etc.
     opt_level: uint,
     codegen_units: Option<uint>,    // None = use rustc default
-    debug: bool,
+    debuginfo: uint,                // 0 = none, 1 = line tables, 2 = full
     rpath: bool,
     test: bool,
     doctest: bool,
@@ -136,6 +147,8 @@ pub struct Profile {
     for_host: bool,
     harness: bool, // whether to use the test harness (--test)
     custom_build: bool,
+    tested: bool, // whether `cargo test` builds this (examples may opt out,
+                  // benchmarks compiled as tests never are)
 }
 
 impl Profile {
@@ -144,7 +157,7 @@ impl Profile {
             env: String::new(),
             opt_level: 0,
             codegen_units: None,
-            debug: false,
+            debuginfo: 0,
             rpath: false,
             test: false,
             doc: false,
@@ -153,6 +166,7 @@ impl Profile {
             doctest: false,
             custom_build: false,
             harness: true,
+            tested: true,
         }
     }
 
@@ -160,7 +174,8 @@ impl Profile {
         Profile {
             env: "compile".to_string(), // run in the default environment only
             opt_level: 0,
-            debug: true,
+            debuginfo: 2,
+            dest: Some("debug".to_string()),
             .. Profile::default()
         }
     }
@@ -168,9 +183,9 @@ impl Profile {
     pub fn default_test() -> Profile {
         Profile {
             env: "test".to_string(),
-            debug: true,
+            debuginfo: 2,
             test: true,
-            dest: None,
+            dest: Some("debug".to_string()),
             .. Profile::default()
         }
     }
@@ -185,6 +200,18 @@ impl Profile {
         }
     }
 
+    /// The profile of the tests built by `cargo test --release`, which are
+    /// optimized like benchmarks but otherwise run as regular tests.
+    pub fn default_release_test() -> Profile {
+        Profile {
+            env: "release-test".to_string(),
+            opt_level: 3,
+            test: true,
+            dest: Some("release".to_string()),
+            .. Profile::default()
+        }
+    }
+
     pub fn default_release() -> Profile {
         Profile {
             env: "release".to_string(),
@@ -197,7 +224,7 @@ impl Profile {
     pub fn default_doc() -> Profile {
         Profile {
             env: "doc".to_string(),
-            dest: None,
+            dest: Some("debug".to_string()),
             doc: true,
             .. Profile::default()
         }
@@ -227,6 +254,10 @@ impl Profile {
         self.custom_build
     }
 
+    pub fn is_tested(&self) -> bool {
+        self.tested
+    }
+
     /// Returns true if the target must be built for the host instead of the target.
     pub fn is_for_host(&self) -> bool {
         self.for_host
@@ -240,8 +271,14 @@ impl Profile {
         self.codegen_units
     }
 
+    /// Whether any debug info is generated, which also leaves debug
+    /// assertions enabled.
     pub fn get_debug(&self) -> bool {
-        self.debug
+        self.debuginfo > 0
+    }
+
+    pub fn get_debuginfo(&self) -> uint {
+        self.debuginfo
     }
 
     pub fn get_rpath(&self) -> bool {
@@ -266,8 +303,8 @@ impl Profile {
         self
     }
 
-    pub fn debug(mut self, debug: bool) -> Profile {
-        self.debug = debug;
+    pub fn debuginfo(mut self, level: uint) -> Profile {
+        self.debuginfo = level;
         self
     }
 
@@ -307,6 +344,12 @@ impl Profile {
         self.custom_build = custom_build;
         self
     }
+
+    /// Sets whether the `Target` is built by `cargo test`.
+    pub fn tested(mut self, tested: bool) -> Profile {
+        self.tested = tested;
+        self
+    }
 }
 
 impl<H: hash::Writer> hash::Hash<H> for Profile {
@@ -316,7 +359,7 @@ impl<H: hash::Writer> hash::Hash<H> for Profile {
         let Profile {
             opt_level,
             codegen_units,
-            debug,
+            debuginfo,
             rpath,
             for_host,
             ref dest,
@@ -331,8 +374,10 @@ impl<H: hash::Writer> hash::Hash<H> for Profile {
             doctest: _,
 
             custom_build: _,
+            tested: _,
         } = *self;
-        (opt_level, codegen_units, debug, rpath, for_host, dest, harness).hash(into)
+        (opt_level, codegen_units, debuginfo, rpath, for_host, dest,
+         harness).hash(into)
     }
 }
 
@@ -395,6 +440,7 @@ impl Manifest {
             warnings: Vec::new(),
             exclude: exclude,
             links: links,
+            build_per_profile: true,
             metadata: metadata,
         }
     }
@@ -439,6 +485,12 @@ impl Manifest {
         self.links.as_ref().map(|s| s.as_slice())
     }
 
+    /// Whether the output of the build script depends on the profile, in
+    /// which case each profile runs the script with its own `OUT_DIR`.
+    pub fn is_build_per_profile(&self) -> bool {
+        self.build_per_profile
+    }
+
     pub fn add_warning(&mut self, s: String) {
         self.warnings.push(s)
     }
@@ -453,6 +505,25 @@ impl Manifest {
 
     pub fn get_metadata(&self) -> &ManifestMetadata { &self.metadata }
 
+    /// Hashes the parts of the manifest which affect how the package is built.
+    ///
+    /// Fields of `ManifestMetadata` (description, keywords, ...) are left out
+    /// so editing them doesn't rebuild the package. The version is the only
+    /// metadata exported to the compiler (as `CARGO_PKG_VERSION_*`), so it is
+    /// included.
+    pub fn build_hash(&self) -> String {
+        let mut deps = self.get_dependencies().iter().map(|d| {
+            d.to_string()
+        }).collect::<Vec<String>>();
+        deps.sort();
+        let mut features = self.summary.get_features().iter()
+                               .collect::<Vec<(&String, &Vec<String>)>>();
+        features.sort();
+        short_hash(&(self.get_version().to_string(), deps, features,
+                     &self.targets, &self.links, &self.build,
+                     self.build_per_profile))
+    }
+
     pub fn set_summary(&mut self, summary: Summary) {
         self.summary = summary;
     }
@@ -460,6 +531,10 @@ impl Manifest {
     pub fn set_target_dir(&mut self, target_dir: Path) {
         self.target_dir = target_dir;
     }
+
+    pub fn set_build_per_profile(&mut self, per_profile: bool) {
+        self.build_per_profile = per_profile;
+    }
 }
 
 impl Target {
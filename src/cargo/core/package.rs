@@ -35,6 +35,7 @@ struct SerializedPackage {
     version: String,
     dependencies: Vec<SerializedDependency>,
     targets: Vec<Target>,
+    links: Option<String>,
     manifest_path: String,
 }
 
@@ -51,6 +52,7 @@ impl<E, S: Encoder<E>> Encodable<S, E> for Package {
                 SerializedDependency::from_dependency(d)
             }).collect(),
             targets: manifest.get_targets().to_vec(),
+            links: manifest.get_links().map(|s| s.to_string()),
             manifest_path: self.manifest_path.display().to_string()
         }.encode(s)
     }
@@ -67,6 +69,23 @@ impl Package {
         }
     }
 
+    pub fn add_warning(&mut self, warning: String) {
+        self.manifest.add_warning(warning)
+    }
+
+    /// Makes this virtual workspace root depend on the packages of its
+    /// `members`.
+    pub fn set_workspace_members(&mut self, members: Vec<Dependency>) {
+        let summary = self.get_summary().clone().workspace_root(members);
+        self.manifest.set_summary(summary)
+    }
+
+    /// Whether this is a virtual workspace root, which has no targets of its
+    /// own and stands for all of its members.
+    pub fn is_workspace_root(&self) -> bool {
+        self.get_summary().is_workspace_root()
+    }
+
     pub fn get_manifest(&self) -> &Manifest {
         &self.manifest
     }
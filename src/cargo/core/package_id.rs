@@ -128,7 +128,7 @@ impl PackageId {
         let metadata = short_hash(
             &(self.inner.name.as_slice(), self.inner.version.to_string(),
               &self.inner.source_id));
-        let extra_filename = format!("-{}", metadata);
+        let extra_filename = extra_filename(metadata.as_slice());
 
         Metadata { metadata: metadata, extra_filename: extra_filename }
     }
@@ -147,11 +147,23 @@ impl PackageId {
 impl Metadata {
     pub fn mix<T: Hash>(&mut self, t: &T) {
         let new_metadata = short_hash(&(self.metadata.as_slice(), t));
-        self.extra_filename = format!("-{}", new_metadata);
+        self.extra_filename = extra_filename(new_metadata.as_slice());
         self.metadata = new_metadata;
     }
 }
 
+/// The suffix appended to artifact names for some metadata.
+///
+/// Deep project directories easily run into the path length limit on Windows,
+/// so only half of the hash is used in file names there.
+fn extra_filename(metadata: &str) -> String {
+    if cfg!(windows) {
+        format!("-{}", metadata.slice_to(metadata.len() / 2))
+    } else {
+        format!("-{}", metadata)
+    }
+}
+
 static CENTRAL_REPO: &'static str = "http://rust-lang.org/central-repo";
 
 impl Show for PackageId {
@@ -81,6 +81,8 @@ impl<'a> PackageRegistry<'a> {
         }
     }
 
+    pub fn config(&self) -> &'a Config<'a> { self.config }
+
     pub fn get(&mut self, package_ids: &[PackageId]) -> CargoResult<Vec<Package>> {
         log!(5, "getting packags; sources={}; ids={}", self.sources.len(),
              package_ids);
@@ -136,8 +138,22 @@ impl<'a> PackageRegistry<'a> {
         Ok(())
     }
 
+    /// Adds `source`, which is already up to date, as the source of `id`.
+    ///
+    /// It's treated like a source added with `add_sources`, which then doesn't
+    /// update it again.
+    pub fn preload(&mut self, id: &SourceId, source: Box<Source + 'a>) {
+        self.sources.insert(id, source);
+        self.source_ids.insert(id.clone(), (id.clone(), Locked));
+    }
+
     pub fn add_sources(&mut self, ids: &[SourceId]) -> CargoResult<()> {
         for id in ids.iter() {
+            match self.source_ids.get(id) {
+                Some(&(ref previous, Locked))
+                    if previous.get_precise() == id.get_precise() => continue,
+                _ => {}
+            }
             try!(self.load(id, Locked));
         }
         Ok(())
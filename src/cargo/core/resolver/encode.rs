@@ -12,11 +12,16 @@ use super::Resolve;
 pub struct EncodableResolve {
     package: Option<Vec<EncodableDependency>>,
     root: EncodableDependency,
+    root_dependencies: Option<Requirements>,
     metadata: Option<Metadata>,
 }
 
 pub type Metadata = TreeMap<String, String>;
 
+/// The version requirements of the root package's dependencies, keyed by
+/// dependency name.
+pub type Requirements = TreeMap<String, String>;
+
 impl EncodableResolve {
     pub fn to_resolve(&self, default: &SourceId) -> CargoResult<Resolve> {
         let mut g = Graph::new();
@@ -71,6 +76,7 @@ impl EncodableResolve {
             graph: g,
             root: try!(self.root.to_package_id(default)),
             features: HashMap::new(),
+            requirements: self.root_dependencies.clone(),
             metadata: self.metadata.clone(),
         })
     }
@@ -159,6 +165,7 @@ impl<E, S: Encoder<E>> Encodable<S, E> for Resolve {
         EncodableResolve {
             package: Some(encodable),
             root: encodable_resolve_node(&self.root, &self.root, &self.graph),
+            root_dependencies: self.requirements.clone(),
             metadata: self.metadata.clone(),
         }.encode(s)
     }
@@ -1,4 +1,6 @@
+use std::ascii::AsciiExt;
 use std::cell::RefCell;
+use std::cmp::Equal;
 use std::collections::HashSet;
 use std::collections::hash_map::{HashMap, Occupied, Vacant};
 use std::fmt;
@@ -9,10 +11,11 @@ use core::{PackageId, Registry, SourceId, Summary, Dependency};
 use core::PackageIdSpec;
 use util::{CargoResult, Graph, human, ChainError};
 use util::profile;
+use util::cfg::{Cfg, CfgKeyPair};
 use util::graph::{Nodes, Edges};
 
 pub use self::encode::{EncodableResolve, EncodableDependency, EncodablePackageId};
-pub use self::encode::Metadata;
+pub use self::encode::{Metadata, Requirements};
 
 mod encode;
 
@@ -26,6 +29,7 @@ pub struct Resolve {
     graph: Graph<PackageId>,
     features: HashMap<PackageId, HashSet<String>>,
     root: PackageId,
+    requirements: Option<Requirements>,
     metadata: Option<Metadata>,
 }
 
@@ -33,21 +37,51 @@ pub enum ResolveMethod<'a> {
     ResolveEverything,
     ResolveRequired(/* dev_deps = */ bool,
                     /* features = */ &'a [String],
+                    /* all_features = */ bool,
                     /* uses_default_features = */ bool,
-                    /* target_platform = */ Option<&'a str>),
+                    /* platform, for_host = */
+                    Option<(&'a (Platforms + 'a), bool)>),
+}
+
+/// The platforms packages are built for, which dependencies only needed on
+/// other platforms are filtered out for.
+pub trait Platforms {
+    /// The target triple packages are built for, or the host's if `host`.
+    fn triple(&self, host: bool) -> &str;
+
+    /// The cfg values of the platform packages are built for, or of the host
+    /// if `host`. They may have to be probed by running rustc, so this is
+    /// only called once a dependency keyed on a cfg expression is evaluated.
+    fn cfg(&self, host: bool) -> CargoResult<Vec<Cfg>>;
 }
 
 impl Resolve {
     fn new(root: PackageId) -> Resolve {
         let mut g = Graph::new();
         g.add(root.clone(), []);
-        Resolve { graph: g, root: root, features: HashMap::new(), metadata: None }
+        Resolve {
+            graph: g,
+            root: root,
+            features: HashMap::new(),
+            requirements: None,
+            metadata: None,
+        }
     }
 
     pub fn copy_metadata(&mut self, other: &Resolve) {
         self.metadata = other.metadata.clone();
     }
 
+    /// Returns the requirements of the root package's dependencies at the time
+    /// this resolve was created, if they are known.
+    pub fn requirements(&self) -> Option<&Requirements> {
+        self.requirements.as_ref()
+    }
+
+    pub fn set_requirements(&mut self, requirements: Requirements) {
+        self.requirements = Some(requirements);
+    }
+
     pub fn iter(&self) -> Nodes<PackageId> {
         self.graph.iter()
     }
@@ -65,8 +99,20 @@ impl Resolve {
         let mut ids = self.iter().filter(|p| spec.matches(*p));
         let ret = match ids.next() {
             Some(id) => id,
-            None => return Err(human(format!("package id specification `{}` \
-                                              matched no packages", spec))),
+            None => {
+                let mut msg = format!("package id specification `{}` \
+                                       matched no packages", spec);
+                let mut similar = self.iter().map(|p| p.get_name())
+                                      .filter(|n| {
+                    *n != spec.get_name() && names_match(*n, spec.get_name())
+                }).collect::<Vec<_>>();
+                similar.sort();
+                similar.dedup();
+                if similar.len() > 0 {
+                    msg.push_str(did_you_mean(similar.as_slice()).as_slice());
+                }
+                return Err(human(msg))
+            }
         };
         return match ids.next() {
             Some(other) => {
@@ -154,10 +200,17 @@ fn activate<R: Registry>(mut cx: Context,
                          -> CargoResult<CargoResult<Context>> {
     // Extracting the platform request.
     let platform = match method {
-        ResolveRequired(_, _, _, platform) => platform,
+        ResolveRequired(_, _, _, _, platform) => platform,
         ResolveEverything => None,
     };
 
+    // The members of a virtual workspace are each built like a root package,
+    // so their dev-dependencies are needed whenever the root's would be.
+    let dev_deps = parent.is_workspace_root() && match method {
+        ResolveRequired(dev_deps, _, _, _, _) => dev_deps,
+        ResolveEverything => true,
+    };
+
     // First, figure out our set of dependencies based on the requsted set of
     // features. This also calculates what features we're going to enable for
     // our own dependencies.
@@ -179,24 +232,33 @@ fn activate<R: Registry>(mut cx: Context,
     // When we recurse, attempt to resolve dependencies with fewer candidates
     // before recursing on dependencies with more candidates. This way if the
     // dependency with only one candidate can't be resolved we don't have to do
-    // a bunch of work before we figure that out.
-    deps.as_mut_slice().sort_by(|&(_, ref a, _), &(_, ref b, _)| {
-        a.len().cmp(&b.len())
+    // a bunch of work before we figure that out. Ties are broken by name so
+    // the order, and hence any error reported, doesn't change from run to run.
+    deps.as_mut_slice().sort_by(|&(a, ref a_cands, _), &(b, ref b_cands, _)| {
+        match a_cands.len().cmp(&b_cands.len()) {
+            Equal => a.get_name().cmp(b.get_name()),
+            ord => ord,
+        }
     });
 
-    activate_deps(cx, registry, parent, platform, deps.as_slice(), 0)
+    activate_deps(cx, registry, parent, platform, dev_deps, deps.as_slice(), 0)
 }
 
 fn activate_deps<'a, R: Registry>(cx: Context,
                                   registry: &mut R,
                                   parent: &Summary,
-                                  platform: Option<&'a str>,
+                                  platform: Option<(&'a (Platforms + 'a), bool)>,
+                                  dev_deps: bool,
                                   deps: &'a [(&Dependency, Vec<Rc<Summary>>, Vec<String>)],
                                   cur: uint) -> CargoResult<CargoResult<Context>> {
     if cur == deps.len() { return Ok(Ok(cx)) }
     let (dep, ref candidates, ref features) = deps[cur];
-    let method = ResolveRequired(false, features.as_slice(),
-                                  dep.uses_default_features(), platform);
+    // Build dependencies, and everything they depend on, run on the host.
+    let dep_platform = platform.map(|(platforms, host)| {
+        (platforms, host || dep.is_build())
+    });
+    let method = ResolveRequired(dev_deps, features.as_slice(), false,
+                                  dep.uses_default_features(), dep_platform);
 
     let key = (dep.get_name().to_string(), dep.get_source_id().clone());
     let prev_active = cx.activations.get(&key)
@@ -243,13 +305,17 @@ fn activate_deps<'a, R: Registry>(cx: Context,
                 Occupied(e) => e.into_mut(),
                 Vacant(e) => e.set(Vec::new()),
             };
+            // A workspace member which was already activated as the
+            // dependency of another member still needs its dev-dependencies.
             if prev.iter().any(|c| c == candidate) {
-                match cx.resolve.features(candidate.get_package_id()) {
+                let id = candidate.get_package_id();
+                let has_features = match cx.resolve.features(id) {
                     Some(prev_features) => {
                         features.iter().all(|f| prev_features.contains(f))
                     }
                     None => features.len() == 0,
-                }
+                };
+                has_features && !dev_deps
             } else {
                 my_cx.resolve.graph.add(candidate.get_package_id().clone(), []);
                 prev.push(candidate.clone());
@@ -278,7 +344,8 @@ fn activate_deps<'a, R: Registry>(cx: Context,
                 Err(e) => { last_err = Some(e); continue }
             }
         };
-        match try!(activate_deps(my_cx, registry, parent, platform, deps, cur + 1)) {
+        match try!(activate_deps(my_cx, registry, parent, platform,
+                                 dev_deps, deps, cur + 1)) {
             Ok(cx) => return Ok(Ok(cx)),
             Err(e) => { last_err = Some(e); }
         }
@@ -290,47 +357,183 @@ fn activate_deps<'a, R: Registry>(cx: Context,
     Ok(match last_err {
         Some(e) => Err(e),
         None if candidates.len() > 0 => {
+            let path = path_to_root(&cx, parent.get_package_id());
             let mut msg = format!("failed to select a version for `{}` \
                                    (required by `{}`):\n\
                                    all possible versions conflict with \
-                                   previously selected versions of `{}`",
+                                   previously selected versions of `{}`\n  \
+                                   required by: {} -> {} ({})",
                                   dep.get_name(), parent.get_name(),
-                                  dep.get_name());
-            'outer: for v in prev_active.iter() {
+                                  dep.get_name(),
+                                  describe_path(&cx, path.as_slice()),
+                                  dep.get_name(), dep.get_version_req());
+
+            // Each package which depends on a previously selected version
+            // introduced a conflicting constraint, so explain where all of
+            // them came from.
+            let mut in_use = Vec::new();
+            for v in prev_active.iter() {
                 for node in cx.resolve.graph.iter() {
                     let mut edges = match cx.resolve.graph.edges(node) {
                         Some(edges) => edges,
                         None => continue,
                     };
-                    for edge in edges {
-                        if edge != v.get_package_id() { continue }
-
-                        msg.push_str(format!("\n  version {} in use by {}",
-                                             v.get_version(), edge).as_slice());
-                        continue 'outer;
+                    if !edges.any(|edge| edge == v.get_package_id()) {
+                        continue
                     }
+                    let mut path = path_to_root(&cx, node);
+                    path.push(v.get_package_id().clone());
+                    in_use.push(format!("\n  version {} in use by: {}",
+                                        v.get_version(),
+                                        describe_path(&cx, path.as_slice())));
                 }
-                msg.push_str(format!("\n  version {} in use by ??",
-                                     v.get_version()).as_slice());
+            }
+            in_use.sort();
+            for line in in_use.iter() {
+                msg.push_str(line.as_slice());
             }
 
+            let versions = candidates.iter().map(|v| {
+                v.get_version().to_string()
+            }).collect::<Vec<_>>();
             msg.push_str(format!("\n  possible versions to select: {}",
-                                 candidates.iter().map(|v| v.get_version())
-                                           .collect::<Vec<_>>()).as_slice());
+                                 versions.connect(", ")).as_slice());
 
             Err(human(msg))
         }
         None => {
-            Err(human(format!("no package named `{}` found (required by `{}`)\n\
-                               location searched: {}\n\
-                               version required: {}",
-                              dep.get_name(), parent.get_name(),
-                              dep.get_source_id(),
-                              dep.get_version_req())))
+            // Look for any version at all so we can tell the difference
+            // between a package which doesn't exist and one which merely has
+            // no version matching the requirement.
+            let any = dep.clone().version_req(semver::VersionReq::any());
+            let mut all = try!(registry.query(&any));
+            if all.len() == 0 {
+                let mut msg = format!("no package named `{}` found \
+                                       (required by `{}`)\n\
+                                       location searched: {}\n\
+                                       version required: {}",
+                                      dep.get_name(), parent.get_name(),
+                                      dep.get_source_id(),
+                                      dep.get_version_req());
+                let similar = try!(similar_names(registry, &any));
+                if similar.len() > 0 {
+                    msg.push_str(did_you_mean(similar.as_slice()).as_slice());
+                }
+                Err(human(msg))
+            } else {
+                all.as_mut_slice().sort_by(|a, b| {
+                    a.get_version().cmp(b.get_version())
+                });
+                let versions = all.iter().map(|s| {
+                    s.get_version().to_string()
+                }).collect::<Vec<_>>();
+                Err(human(format!("no matching version of `{}` found \
+                                   (required by `{}`)\n\
+                                   location searched: {}\n\
+                                   version required: {}\n\
+                                   versions found: {}",
+                                  dep.get_name(), parent.get_name(),
+                                  dep.get_source_id(),
+                                  dep.get_version_req(),
+                                  versions.connect(", "))))
+            }
         }
     })
 }
 
+// Package names are compared ignoring ASCII case and treating `-` and `_` as
+// the same character, as those are the most common ways of misspelling the
+// name of a package which does exist.
+fn normalize_name(name: &str) -> String {
+    name.to_ascii_lower().replace("-", "_")
+}
+
+fn names_match(a: &str, b: &str) -> bool {
+    normalize_name(a) == normalize_name(b)
+}
+
+// Sources can only be queried by exact name, so try the spellings of `dep`'s
+// name which differ only in case or in `-` versus `_`, and return those which
+// name an existing package.
+fn similar_names<R: Registry>(registry: &mut R, dep: &Dependency)
+                              -> CargoResult<Vec<String>> {
+    let name = dep.get_name();
+    let mut variants = Vec::new();
+    for base in [name.to_string(), name.to_ascii_lower()].iter() {
+        variants.push(base.clone());
+        variants.push(base.replace("-", "_"));
+        variants.push(base.replace("_", "-"));
+    }
+    variants.sort();
+    variants.dedup();
+
+    let mut ret = Vec::new();
+    for variant in variants.iter().filter(|v| v.as_slice() != name) {
+        let candidates = try!(registry.query(&dep.clone()
+                                                 .name(variant.as_slice())));
+        for summary in candidates.iter() {
+            let found = summary.get_name();
+            if found != name && names_match(found, name) &&
+               !ret.iter().any(|s: &String| s.as_slice() == found) {
+                ret.push(found.to_string());
+            }
+        }
+    }
+    ret.sort();
+    Ok(ret)
+}
+
+fn did_you_mean<S: Str>(names: &[S]) -> String {
+    let names = names.iter().map(|n| format!("`{}`", n.as_slice()))
+                     .collect::<Vec<_>>();
+    format!("\ndid you mean {}?", names.connect(" or "))
+}
+
+// Reconstructs a chain of packages from the root of the resolution graph
+// down to `pkg`, following the first parent found at each step.
+fn path_to_root(cx: &Context, pkg: &PackageId) -> Vec<PackageId> {
+    let mut path = vec![pkg.clone()];
+    let mut visited = HashSet::new();
+    loop {
+        let cur = path.last().unwrap().clone();
+        if cur == cx.resolve.root || !visited.insert(cur.clone()) { break }
+        let parent = cx.resolve.graph.iter().find(|node| {
+            match cx.resolve.graph.edges(*node) {
+                Some(mut edges) => edges.any(|edge| *edge == cur),
+                None => false,
+            }
+        });
+        match parent {
+            Some(parent) => path.push(parent.clone()),
+            None => break,
+        }
+    }
+    path.reverse();
+    path
+}
+
+// Renders a chain of packages as returned by `path_to_root`, along with the
+// version requirement each package declared on the next one.
+fn describe_path(cx: &Context, path: &[PackageId]) -> String {
+    let mut ret = String::new();
+    for (i, id) in path.iter().enumerate() {
+        if i == 0 {
+            ret.push_str(format!("{} v{}", id.get_name(),
+                                 id.get_version()).as_slice());
+            continue
+        }
+        let parent = cx.activations.values().flat_map(|v| v.iter()).find(|s| {
+            s.get_package_id() == &path[i - 1]
+        });
+        let req = parent.and_then(|s| {
+            s.get_dependencies().iter().find(|d| d.get_name() == id.get_name())
+        }).map(|d| d.get_version_req().to_string());
+        ret.push_str(format!(" -> {} v{} ({})", id.get_name(), id.get_version(),
+                             req.unwrap_or("?".to_string())).as_slice());
+    }
+    ret
+}
+
 // Returns if `a` and `b` are compatible in the semver sense. This is a
 // commutative operation.
 //
@@ -350,24 +553,38 @@ fn resolve_features<'a>(cx: &mut Context, parent: &'a Summary,
                                                (&'a Dependency, Vec<String>)>> {
     let dev_deps = match method {
         ResolveEverything => true,
-        ResolveRequired(dev_deps, _, _, _) => dev_deps,
+        ResolveRequired(dev_deps, _, _, _, _) => dev_deps,
     };
 
     // First, filter by dev-dependencies
     let deps = parent.get_dependencies();
     let deps = deps.iter().filter(|d| d.is_transitive() || dev_deps);
 
-    // Second, ignoring dependencies that should not be compiled for this platform
-    let mut deps = deps.filter(|d| {
-        match method {
-            ResolveRequired(_, _, _, Some(ref platform)) => {
-                d.is_active_for_platform(platform.as_slice())
-            },
-            _ => true
-        }
-    });
-
     let (mut feature_deps, used_features) = try!(build_features(parent, method));
+
+    // Second, ignoring dependencies that should not be compiled for this
+    // platform, which is the host's for build dependencies. Their cfg
+    // expressions also see the package's own features.
+    let platform = match method {
+        ResolveRequired(_, _, _, _, platform) => platform,
+        ResolveEverything => None,
+    };
+    let mut active = Vec::new();
+    for dep in deps {
+        let is_active = match platform {
+            None => true,
+            Some((platforms, host)) => {
+                let host = host || dep.is_build();
+                try!(dep.is_active_for_platform(platforms.triple(host), || {
+                    let mut cfg = try!(platforms.cfg(host));
+                    cfg.extend(feature_cfg(&used_features).into_iter());
+                    Ok(cfg)
+                }))
+            }
+        };
+        if is_active { active.push(dep) }
+    }
+    let deps = active.into_iter();
     let mut ret = HashMap::new();
 
     // Next, sanitize all requested features by whitelisting all the requested
@@ -417,6 +634,22 @@ fn resolve_features<'a>(cx: &mut Context, parent: &'a Summary,
     Ok(ret)
 }
 
+/// Returns the features of the package `s` which are enabled by `method`.
+pub fn enabled_features(s: &Summary, method: ResolveMethod)
+                        -> CargoResult<HashSet<String>> {
+    let (_, used) = try!(build_features(s, method));
+    Ok(used)
+}
+
+/// Returns the `feature="..."` cfg values of `features`, in sorted order.
+pub fn feature_cfg(features: &HashSet<String>) -> Vec<Cfg> {
+    let mut features = features.iter().collect::<Vec<&String>>();
+    features.sort();
+    features.into_iter().map(|f| {
+        CfgKeyPair("feature".to_string(), f.clone())
+    }).collect()
+}
+
 // Returns a pair of (feature dependencies, all used features)
 //
 // The feature dependencies map is a mapping of package name to list of features
@@ -432,7 +665,7 @@ fn build_features(s: &Summary, method: ResolveMethod)
     let mut used = HashSet::new();
     let mut visited = HashSet::new();
     match method {
-        ResolveEverything => {
+        ResolveEverything | ResolveRequired(_, _, true, _, _) => {
             for key in s.get_features().keys() {
                 try!(add_feature(s, key.as_slice(), &mut deps, &mut used,
                                  &mut visited));
@@ -442,7 +675,7 @@ fn build_features(s: &Summary, method: ResolveMethod)
                                  &mut visited));
             }
         }
-        ResolveRequired(_, requested_features, _, _) =>  {
+        ResolveRequired(_, requested_features, false, _, _) =>  {
             for feat in requested_features.iter() {
                 try!(add_feature(s, feat.as_slice(), &mut deps, &mut used,
                                  &mut visited));
@@ -450,7 +683,7 @@ fn build_features(s: &Summary, method: ResolveMethod)
         }
     }
     match method {
-        ResolveEverything | ResolveRequired(_, _, true, _) => {
+        ResolveEverything | ResolveRequired(_, _, _, true, _) => {
             if s.get_features().find_equiv("default").is_some() &&
                !visited.contains_equiv("default") {
                 try!(add_feature(s, "default", &mut deps, &mut used,
@@ -1,11 +1,28 @@
+use std::ascii::AsciiExt;
 use term::{Terminal, TerminfoTerminal, color};
 use term::color::{Color, BLACK, RED, GREEN, YELLOW};
 use term::attr::{Attr, Bold};
-use std::io::{IoResult, stderr};
+use std::io::{mod, IoResult, MemWriter, stderr, stdin};
 use std::fmt::Show;
+use std::mem;
+use std::os;
+
+use util::{CargoResult, human};
+use util::config;
+
+/// Whether the shell should emit color escape codes.
+#[deriving(Clone, PartialEq, Show)]
+pub enum ColorConfig {
+    /// Use color only if the output stream is a TTY.
+    Auto,
+    /// Always use color, even if the output is being piped somewhere.
+    Always,
+    /// Never use color.
+    Never,
+}
 
 pub struct ShellConfig {
-    pub color: bool,
+    pub color_config: ColorConfig,
     pub verbose: bool,
     pub tty: bool
 }
@@ -23,7 +40,13 @@ pub struct Shell {
 pub struct MultiShell {
     out: Shell,
     err: Shell,
-    verbose: bool
+    verbose: bool,
+    very_verbose: bool,
+    interactive: bool,
+    assume_yes: bool,
+    /// Whether a `--color` flag was given, on the top-level command or a
+    /// subcommand.
+    color_flag: bool,
 }
 
 pub type Callback<'a> = |&mut MultiShell|:'a -> IoResult<()>;
@@ -34,7 +57,15 @@ struct UghWhyIsThisNecessary {
 
 impl MultiShell {
     pub fn new(out: Shell, err: Shell, verbose: bool) -> MultiShell {
-        MultiShell { out: out, err: err, verbose: verbose }
+        MultiShell {
+            out: out,
+            err: err,
+            verbose: verbose,
+            very_verbose: false,
+            interactive: false,
+            assume_yes: false,
+            color_flag: false,
+        }
     }
 
     pub fn out(&mut self) -> &mut Shell {
@@ -74,26 +105,174 @@ impl MultiShell {
     pub fn set_verbose(&mut self, verbose: bool) {
         self.verbose = verbose;
     }
+
+    /// Sets the verbosity from the number of times `-v` was given. The
+    /// second level (`-vv`) also echoes the output of build scripts.
+    pub fn set_verbosity(&mut self, level: uint) {
+        self.verbose = level > 0;
+        self.very_verbose = level > 1;
+    }
+
+    pub fn is_very_verbose(&self) -> bool {
+        self.very_verbose
+    }
+
+    /// Records whether stdin is a terminal someone can answer prompts on.
+    pub fn set_interactive(&mut self, interactive: bool) {
+        self.interactive = interactive;
+    }
+
+    pub fn is_interactive(&self) -> bool {
+        self.interactive
+    }
+
+    /// Answers every confirmation with yes, as `--yes` asks for.
+    pub fn set_assume_yes(&mut self, assume_yes: bool) {
+        self.assume_yes = assume_yes;
+    }
+
+    pub fn assume_yes(&self) -> bool {
+        self.assume_yes
+    }
+
+    /// Asks a yes/no question, reading the answer from stdin.
+    ///
+    /// With `--yes` no question is asked and the answer is yes. When stdin
+    /// isn't a terminal nobody could answer, so the answer is no without
+    /// prompting; callers should then point at `--yes` in their error.
+    pub fn confirm(&mut self, question: &str) -> CargoResult<bool> {
+        if self.assume_yes { return Ok(true) }
+        if !self.interactive { return Ok(false) }
+        Ok(try!(prompt(&mut stdin(), self.err(), question)))
+    }
+
+    /// Sends everything written to the output stream to stderr instead,
+    /// leaving stdout free for output which is meant to be consumed by
+    /// other programs.
+    pub fn redirect_out_to_stderr(&mut self) {
+        let config = ShellConfig {
+            color_config: self.err.config.color_config.clone(),
+            verbose: self.err.config.verbose,
+            tty: self.err.config.tty,
+        };
+        self.out = Shell::create(box stderr(), config);
+    }
+
+    /// Configures whether color is used for both output streams.
+    ///
+    /// The `color` argument is the value of a `--color` flag, if one was given.
+    /// Otherwise the `term.color` configuration key is consulted, falling back
+    /// to `auto` if it isn't present either. The configuration is only used
+    /// if no `--color` flag was given to any command so far, so that the flag
+    /// of the top-level command isn't overridden when a subcommand without
+    /// its own flag configures the shell again.
+    pub fn set_color_config(&mut self, color: Option<&str>) -> CargoResult<()> {
+        let color = match color {
+            Some(color) => {
+                self.color_flag = true;
+                color.to_string()
+            }
+            None if self.color_flag => return Ok(()),
+            None => {
+                let term = match config::get_config(os::getcwd(), "term") {
+                    Ok(term) => term,
+                    Err(..) => return Ok(()),
+                };
+                let term = try!(term.table());
+                match term.find_equiv("color") {
+                    Some(color) => try!(color.string()).val0().to_string(),
+                    None => return Ok(()),
+                }
+            }
+        };
+        let color_config = try!(ColorConfig::from_str(color.as_slice()));
+        self.out.set_color_config(color_config.clone());
+        self.err.set_color_config(color_config);
+        Ok(())
+    }
+
+    pub fn color_config(&self) -> ColorConfig {
+        self.err.config.color_config.clone()
+    }
+}
+
+impl ColorConfig {
+    pub fn from_str(s: &str) -> CargoResult<ColorConfig> {
+        match s {
+            "auto" => Ok(Auto),
+            "always" => Ok(Always),
+            "never" => Ok(Never),
+            _ => Err(human(format!("argument for --color must be auto, always, \
+                                    or never, but found `{}`", s))),
+        }
+    }
+}
+
+/// Writes `question` to `output` and reads a single line of `input` as the
+/// answer. Only `y` and `yes` count as a yes, and end of input is a no.
+pub fn prompt<R: Buffer, W: Writer>(input: &mut R, output: &mut W,
+                                    question: &str) -> IoResult<bool> {
+    try!(write!(output, "{} [y/N] ", question));
+    try!(output.flush());
+    let line = match input.read_line() {
+        Ok(line) => line,
+        Err(ref e) if e.kind == io::EndOfFile => return Ok(false),
+        Err(e) => return Err(e),
+    };
+    let answer = line.as_slice().trim().to_ascii_lower();
+    Ok(answer.as_slice() == "y" || answer.as_slice() == "yes")
 }
 
 pub type ShellCallback<'a> = |&mut Shell|:'a -> IoResult<()>;
 
 impl Shell {
     pub fn create(out: Box<Writer + Send>, config: ShellConfig) -> Shell {
-        let out = UghWhyIsThisNecessary { inner: out };
-        if config.tty && config.color {
-            let term = TerminfoTerminal::new(out);
-            term.map(|t| Shell {
-                terminal: Colored(t),
-                config: config
-            }).unwrap_or_else(|| {
-                Shell { terminal: NoColor(box stderr()), config: config }
-            })
-        } else {
-            Shell { terminal: NoColor(out.inner), config: config }
+        let mut shell = Shell { terminal: NoColor(out), config: config };
+        if shell.colored() {
+            shell.upgrade_terminal();
+        }
+        shell
+    }
+
+    pub fn set_color_config(&mut self, color_config: ColorConfig) {
+        self.config.color_config = color_config;
+        if self.colored() {
+            self.upgrade_terminal();
         }
     }
 
+    pub fn is_tty(&self) -> bool {
+        self.config.tty
+    }
+
+    /// Returns whether color escape codes should currently be emitted.
+    fn colored(&self) -> bool {
+        match self.config.color_config {
+            Auto => self.config.tty,
+            Always => true,
+            Never => false,
+        }
+    }
+
+    /// Swaps a plain writer out for a terminfo terminal so colors can be
+    /// emitted.
+    ///
+    /// Terminals without a terminfo description (such as Windows consoles)
+    /// keep writing plain output.
+    fn upgrade_terminal(&mut self) {
+        if TerminfoTerminal::new(MemWriter::new()).is_none() { return }
+        let out = match mem::replace(&mut self.terminal,
+                                     NoColor(box stderr())) {
+            NoColor(out) => out,
+            colored => { self.terminal = colored; return }
+        };
+        let out = UghWhyIsThisNecessary { inner: out };
+        self.terminal = match TerminfoTerminal::new(out) {
+            Some(t) => Colored(t),
+            None => NoColor(box stderr()),
+        };
+    }
+
     pub fn verbose(&mut self, callback: ShellCallback) -> IoResult<()> {
         if self.config.verbose { return callback(self) }
         Ok(())
@@ -126,6 +305,7 @@ impl Shell {
     }
 
     fn fg(&mut self, color: color::Color) -> IoResult<bool> {
+        if !self.colored() { return Ok(false) }
         match self.terminal {
             Colored(ref mut c) => c.fg(color),
             NoColor(_) => Ok(false)
@@ -133,6 +313,7 @@ impl Shell {
     }
 
     fn attr(&mut self, attr: Attr) -> IoResult<bool> {
+        if !self.colored() { return Ok(false) }
         match self.terminal {
             Colored(ref mut c) => c.attr(attr),
             NoColor(_) => Ok(false)
@@ -140,6 +321,7 @@ impl Shell {
     }
 
     fn supports_attr(&self, attr: Attr) -> bool {
+        if !self.colored() { return false }
         match self.terminal {
             Colored(ref c) => c.supports_attr(attr),
             NoColor(_) => false
@@ -147,6 +329,7 @@ impl Shell {
     }
 
     fn reset(&mut self) -> IoResult<()> {
+        if !self.colored() { return Ok(()) }
         match self.terminal {
             Colored(ref mut c) => c.reset(),
             NoColor(_) => Ok(())
@@ -175,3 +358,44 @@ impl Writer for UghWhyIsThisNecessary {
         self.inner.write(bytes)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::io::{MemReader, MemWriter};
+    use super::prompt;
+
+    fn answer(input: &str) -> (bool, String) {
+        let mut input = MemReader::new(input.as_bytes().to_vec());
+        let mut output = MemWriter::new();
+        let yes = prompt(&mut input, &mut output, "Continue?").unwrap();
+        (yes, String::from_utf8(output.unwrap()).unwrap())
+    }
+
+    #[test]
+    fn yes_answers() {
+        assert_eq!(answer("y\n"), (true, "Continue? [y/N] ".to_string()));
+        assert!(answer("Yes\n").val0());
+        assert!(answer("  y  \n").val0());
+        assert!(answer("y").val0());
+    }
+
+    #[test]
+    fn anything_else_is_no() {
+        assert!(!answer("n\n").val0());
+        assert!(!answer("\n").val0());
+        assert!(!answer("yep\n").val0());
+    }
+
+    #[test]
+    fn end_of_input_is_no() {
+        assert_eq!(answer(""), (false, "Continue? [y/N] ".to_string()));
+    }
+
+    #[test]
+    fn reads_a_single_line() {
+        let mut input = MemReader::new(b"n\ny\n".to_vec());
+        let mut output = MemWriter::new();
+        assert!(!prompt(&mut input, &mut output, "First?").unwrap());
+        assert_eq!(input.read_to_string().unwrap().as_slice(), "y\n");
+    }
+}
@@ -1,3 +1,4 @@
+use std::cmp;
 use std::collections::hash_map::{HashMap, Values, MutEntries};
 use std::fmt::{mod, Show, Formatter};
 use std::hash;
@@ -41,6 +42,12 @@ pub trait Source: Registry {
     /// The `pkg` argument is the package which this fingerprint should only be
     /// interested in for when this source may contain multiple packages.
     fn fingerprint(&self, pkg: &Package) -> CargoResult<String>;
+
+    /// Lists the files of `pkg` whose modification times its fingerprint
+    /// depends on. Sources whose contents can't change have none.
+    fn fingerprint_files(&self, _pkg: &Package) -> CargoResult<Vec<Path>> {
+        Ok(Vec::new())
+    }
 }
 
 #[deriving(Encodable, Decodable, Show, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
@@ -267,9 +274,12 @@ impl Show for SourceId {
                     try!(write!(f, "?ref={}", reference));
                 }
 
+                // Only show the abbreviated revision, like `git log --oneline`
+                // does; the full revision is still part of the URL form.
                 match *precise {
                     Some(ref s) => {
-                        try!(write!(f, "#{}", s.as_slice().slice_to(8)));
+                        let len = cmp::min(s.len(), 7);
+                        try!(write!(f, "#{}", s.as_slice().slice_to(len)));
                     }
                     None => {}
                 }
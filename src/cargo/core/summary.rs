@@ -15,6 +15,7 @@ pub struct Summary {
     package_id: PackageId,
     dependencies: Vec<Dependency>,
     features: HashMap<String, Vec<String>>,
+    workspace_root: bool,
 }
 
 impl Summary {
@@ -65,6 +66,7 @@ impl Summary {
             package_id: pkg_id,
             dependencies: dependencies,
             features: features,
+            workspace_root: false,
         })
     }
 
@@ -92,6 +94,37 @@ impl Summary {
         &self.features
     }
 
+    /// Returns the names of the optional dependencies which no feature
+    /// refers to, sorted. They can still be enabled on their own with the
+    /// implicit feature named after them, but that's often an oversight.
+    pub fn unreferenced_optional_dependencies(&self) -> Vec<&str> {
+        let mut names = self.dependencies.iter().filter(|d| {
+            d.is_optional()
+        }).map(|d| d.get_name()).filter(|name| {
+            !self.features.values().any(|list| list.iter().any(|f| {
+                f.as_slice().splitn(1, '/').next() == Some(*name)
+            }))
+        }).collect::<Vec<&str>>();
+        names.sort();
+        names.dedup();
+        names
+    }
+
+    /// Whether this is the summary of a virtual workspace root, see
+    /// `workspace_root`.
+    pub fn is_workspace_root(&self) -> bool {
+        self.workspace_root
+    }
+
+    /// Turns this into the summary of a virtual workspace root, which depends
+    /// on each of the workspace `members`. The resolver treats the members
+    /// like root packages, whose dev-dependencies are resolved too.
+    pub fn workspace_root(mut self, members: Vec<Dependency>) -> Summary {
+        self.dependencies = members;
+        self.workspace_root = true;
+        self
+    }
+
     pub fn override_id(mut self, id: PackageId) -> Summary {
         self.package_id = id;
         self
@@ -102,6 +135,12 @@ impl Summary {
         self.dependencies = deps.into_iter().map(f).collect();
         self
     }
+
+    pub fn filter_dependencies(mut self, f: |&Dependency| -> bool) -> Summary {
+        let deps = mem::replace(&mut self.dependencies, Vec::new());
+        self.dependencies = deps.into_iter().filter(|d| f(d)).collect();
+        self
+    }
 }
 
 impl PartialEq for Summary {
@@ -27,12 +27,13 @@ extern crate url;
 extern crate registry;
 
 use std::os;
-use std::io::stdio::{stdout_raw, stderr_raw};
+use std::io::stdio::{stdin_raw, stdout_raw, stderr_raw};
 use std::io::{mod, stdout, stderr};
 use serialize::{Decoder, Encoder, Decodable, Encodable, json};
 use docopt::Docopt;
 
 use core::{Shell, MultiShell, ShellConfig};
+use core::shell::Auto;
 use term::color::{BLACK};
 
 pub use util::{CargoError, CliError, CliResult, human};
@@ -153,16 +154,18 @@ pub fn shell(verbose: bool) -> MultiShell {
     let tty = stderr_raw().isatty();
     let stderr = box stderr() as Box<Writer + Send>;
 
-    let config = ShellConfig { color: true, verbose: verbose, tty: tty };
+    let config = ShellConfig { color_config: Auto, verbose: verbose, tty: tty };
     let err = Shell::create(stderr, config);
 
     let tty = stdout_raw().isatty();
     let stdout = box stdout() as Box<Writer + Send>;
 
-    let config = ShellConfig { color: true, verbose: verbose, tty: tty };
+    let config = ShellConfig { color_config: Auto, verbose: verbose, tty: tty };
     let out = Shell::create(stdout, config);
 
-    MultiShell::new(out, err, verbose)
+    let mut shell = MultiShell::new(out, err, verbose);
+    shell.set_interactive(stdin_raw().isatty());
+    shell
 }
 
 pub fn handle_error(err: CliError, shell: &mut MultiShell) {
@@ -176,7 +179,9 @@ pub fn handle_error(err: CliError, shell: &mut MultiShell) {
         let _ = shell.error(error.to_string());
     }
 
-    if error.cause().is_some() || unknown {
+    // Causes which are part of the message already aren't repeated.
+    let cause = util::errors::reported_cause(&*error);
+    if cause.is_some() || unknown {
         let _ = shell.concise(|shell| {
             shell.err().say("\nTo learn more, run the command again with --verbose.", BLACK)
         });
@@ -189,20 +194,23 @@ pub fn handle_error(err: CliError, shell: &mut MultiShell) {
         if let Some(detail) = error.detail() {
             let _ = shell.err().say(format!("{}", detail), BLACK);
         }
-        if let Some(err) = error.cause() {
+        if let Some(err) = cause {
             let _ = handle_cause(err, shell);
         }
         Ok(())
       });
 
+    // Follow the shell convention of 128 + SIGINT for interrupted builds.
+    let exit_code = if util::interrupt::interrupted() {130} else {exit_code};
     std::os::set_exit_status(exit_code as int);
 }
 
-fn handle_cause(err: &CargoError, shell: &mut MultiShell) {
+/// Reports the causes of an error which haven't been shown yet.
+pub fn handle_cause(err: &CargoError, shell: &mut MultiShell) {
     let _ = shell.err().say("\nCaused by:", BLACK);
     let _ = shell.err().say(format!("  {}", err.description()), BLACK);
 
-    if let Some(e) = err.cause() {
+    if let Some(e) = util::errors::reported_cause(err) {
         handle_cause(e, shell)
     }
 }
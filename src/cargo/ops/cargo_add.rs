@@ -0,0 +1,52 @@
+use std::io::File;
+
+use semver::VersionReq;
+
+use core::MultiShell;
+use util::{CargoResult, ChainError, human, ToSemver};
+use util::manifest_edit::ManifestEditor;
+
+pub struct AddOptions<'a> {
+    pub name: &'a str,
+    pub version: Option<&'a str>,
+    pub dev: bool,
+}
+
+/// Adds a dependency to the manifest at `manifest_path`, or updates its
+/// version requirement if it is already listed.
+pub fn add(manifest_path: &Path, opts: &AddOptions,
+           shell: &mut MultiShell) -> CargoResult<()> {
+    let req = opts.version.unwrap_or("*");
+    try!(VersionReq::parse(req).map_err(|e| {
+        human(format!("invalid version requirement `{}`: {}", req, e))
+    }));
+    let section = if opts.dev {"dev-dependencies"} else {"dependencies"};
+    try!(edit(manifest_path, |m| m.set_dependency(section, opts.name, req)));
+    try!(shell.status("Adding", format!("{} {} to {}", opts.name, req,
+                                        section)));
+    Ok(())
+}
+
+/// Sets the version of the package whose manifest is at `manifest_path`.
+pub fn set_version(manifest_path: &Path, version: &str,
+                   shell: &mut MultiShell) -> CargoResult<()> {
+    try!(version.to_semver().map_err(|e| {
+        human(format!("invalid version `{}`: {}", version, e))
+    }));
+    try!(edit(manifest_path, |m| m.set_version(version)));
+    try!(shell.status("Updating", format!("package version to {}", version)));
+    Ok(())
+}
+
+fn edit(manifest_path: &Path,
+        f: |&mut ManifestEditor| -> CargoResult<()>) -> CargoResult<()> {
+    let contents = try!(File::open(manifest_path).read_to_string());
+    let editor = ManifestEditor::new(contents.as_slice());
+    let mut editor = try!(editor.chain_error(|| {
+        human(format!("failed to parse manifest at `{}`",
+                      manifest_path.display()))
+    }));
+    try!(f(&mut editor));
+    try!(File::create(manifest_path).write_str(editor.contents()));
+    Ok(())
+}
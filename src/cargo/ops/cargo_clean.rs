@@ -1,15 +1,17 @@
 use std::default::Default;
-use std::io::fs::{mod, PathExtensions};
 
-use core::{MultiShell, PackageSet};
+use core::{MultiShell, Package, PackageSet};
 use core::source::{Source, SourceMap};
 use sources::PathSource;
 use util::{CargoResult, human, ChainError, Config};
+use util::{config, paths};
 use ops::{mod, Layout, Context};
 
 pub struct CleanOptions<'a> {
     pub spec: Option<&'a str>,
     pub target: Option<&'a str>,
+    pub release: bool,
+    pub doc: bool,
     pub shell: &'a mut MultiShell,
 }
 
@@ -18,13 +20,16 @@ pub fn clean(manifest_path: &Path, opts: &mut CleanOptions) -> CargoResult<()> {
     let mut src = try!(PathSource::for_path(&manifest_path.dir_path()));
     try!(src.update());
     let root = try!(src.get_root_package());
-    let manifest = root.get_manifest();
+
+    if opts.doc && opts.release {
+        return Err(human("--doc and --release cannot be specified together"))
+    }
 
     // If we have a spec, then we need to delete some package,s otherwise, just
-    // remove the whole target directory and be done with it!
+    // remove the whole output directories and be done with it!
     let spec = match opts.spec {
         Some(spec) => spec,
-        None => return rm_rf(manifest.get_target_dir()),
+        None => return clean_root(&root, opts),
     };
 
     // Load the lockfile (if one's available), and resolve spec to a pkgid
@@ -53,9 +58,18 @@ pub fn clean(manifest_path: &Path, opts: &mut CleanOptions) -> CargoResult<()> {
                                None, &pkg, Default::default()));
 
     // And finally, clean everything out!
+    let triple = opts.target.map(config::target_name);
+    let triple = triple.as_ref().map(|t| t.as_slice());
     for target in pkg.get_targets().iter() {
-        let layout = Layout::new(&root, opts.target,
-                                 target.get_profile().get_dest());
+        let dest = target.get_profile().get_dest();
+        let layout = Layout::new(&root, triple, dest);
+        if opts.doc {
+            if target.is_lib() {
+                try!(rm_rf(&layout.doc().join(target.get_name())));
+            }
+            continue
+        }
+        if opts.release && dest != Some("release") { continue }
         try!(rm_rf(&layout.native(&pkg)));
         try!(rm_rf(&layout.fingerprint(&pkg)));
         for filename in try!(cx.target_filenames(target)).iter() {
@@ -63,20 +77,37 @@ pub fn clean(manifest_path: &Path, opts: &mut CleanOptions) -> CargoResult<()> {
             try!(rm_rf(&layout.dest().join(filename)));
             try!(rm_rf(&layout.deps().join(filename)));
         }
+        for filename in try!(cx.target_final_filenames(target)).iter() {
+            try!(rm_rf(&layout.dest().join(filename.as_slice())));
+        }
     }
 
     Ok(())
 }
 
-fn rm_rf(path: &Path) -> CargoResult<()> {
-    if path.is_dir() {
-        try!(fs::rmdir_recursive(path).chain_error(|| {
-            human("could not remove build directory")
-        }));
-    } else if path.exists() {
-        try!(fs::unlink(path).chain_error(|| {
-            human("failed to remove build artifact")
-        }));
+// Removes the output of the root package as a whole. The target directory
+// and doc directory are taken from the manifest so an overridden target
+// directory is cleaned rather than the default one.
+fn clean_root(root: &Package, opts: &CleanOptions) -> CargoResult<()> {
+    let manifest = root.get_manifest();
+    let triple = opts.target.map(config::target_name);
+    let triple = triple.as_ref().map(|t| t.as_slice());
+    if opts.doc {
+        try!(rm_rf(Layout::new(root, triple, None).doc()));
+        if opts.target.is_none() {
+            try!(rm_rf(manifest.get_doc_dir()));
+        }
+    } else if opts.release {
+        try!(rm_rf(Layout::new(root, triple, Some("release")).dest()));
+    } else {
+        try!(rm_rf(manifest.get_target_dir()));
+        try!(rm_rf(manifest.get_doc_dir()));
     }
     Ok(())
 }
+
+fn rm_rf(path: &Path) -> CargoResult<()> {
+    paths::remove_all(path).chain_error(|| {
+        human(format!("failed to remove `{}`", path.display()))
+    })
+}
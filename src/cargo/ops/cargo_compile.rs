@@ -25,14 +25,18 @@
 use std::os;
 use std::collections::HashMap;
 use std::default::Default;
+use std::sync::Arc;
 
 use core::registry::PackageRegistry;
 use core::{MultiShell, Source, SourceId, PackageSet, Package, Target, PackageId};
-use core::resolver;
-use ops::{mod, BuildOutput};
+use core::resolver::{mod, Platforms};
+use ops::{mod, BuildOutput, FreshnessFs, RealFs};
 use sources::{PathSource};
 use util::config::{Config, ConfigValue};
 use util::{CargoResult, Wrap, config, internal, human, ChainError, profile};
+use util::Require;
+use util::expand_home;
+use util::cfg::Cfg;
 
 /// Contains informations about how a package should be compiled.
 pub struct CompileOptions<'a> {
@@ -46,7 +50,58 @@ pub struct CompileOptions<'a> {
     pub dev_deps: bool,
     pub features: &'a [String],
     pub no_default_features: bool,
+    /// Activate every feature of the package and every optional dependency,
+    /// instead of listing them in `features`.
+    pub all_features: bool,
     pub spec: Option<&'a str>,
+    /// Keep building independent packages after one of them fails.
+    pub keep_going: bool,
+    /// Print how long each unit of work took at the end of the build.
+    pub timings: bool,
+    /// Which examples to build along with the tests.
+    pub examples: Examples,
+    /// Also compile the tests, examples and benchmarks of the package.
+    pub all_targets: bool,
+    /// Names of targets of the package which are not built, whatever their
+    /// kind.
+    pub skip_targets: &'a [String],
+    /// Names of examples which are built in the `test` environment even
+    /// though `all_targets` isn't set.
+    pub extra_examples: &'a [String],
+    /// Restricts the targets of the package which are built to the selected
+    /// ones, along with the library and build script which they may need.
+    /// With no selections only the library and build script are built.
+    pub only_targets: Option<&'a [TargetSelection<'a>]>,
+    /// Extra arguments to pass to rustc for a single target of the package,
+    /// which is then the only target of the package that is built.
+    pub target_rustc_args: Option<(TargetSelection<'a>, &'a [String])>,
+    /// Replaces the filesystem operations deciding whether the build is
+    /// fresh, only meant for tests to observe them.
+    #[doc(hidden)]
+    pub fs: Option<Arc<Box<FreshnessFs>>>,
+}
+
+/// Selects a single target of a package, such as the one that `cargo rustc`
+/// compiles.
+pub enum TargetSelection<'a> {
+    /// The library, or the only binary of a package without a library.
+    DefaultTarget,
+    SelectLib,
+    SelectBin(&'a str),
+    SelectTest(&'a str),
+    SelectExample(&'a str),
+    SelectBench(&'a str),
+}
+
+/// Which example targets are compiled in the `test` environment.
+#[deriving(PartialEq)]
+pub enum Examples {
+    /// Don't build any examples.
+    NoExamples,
+    /// Build all examples other than those marked with `test = false`.
+    TestedExamples,
+    /// Build every example.
+    AllExamples,
 }
 
 pub fn compile(manifest_path: &Path,
@@ -54,41 +109,136 @@ pub fn compile(manifest_path: &Path,
                -> CargoResult<ops::Compilation> {
     log!(4, "compile; manifest-path={}", manifest_path.display());
 
-    let mut source = try!(PathSource::for_path(&manifest_path.dir_path()));
-    try!(source.update());
+    // A member of a workspace is built from the root of the workspace, so it
+    // shares the lockfile and target directory of the other members.
+    let workspace_root = try!(ops::find_workspace_root(manifest_path));
+    let (root_path, member) = match workspace_root {
+        Some(root) => {
+            let source_id = try!(SourceId::for_path(&root.dir_path()));
+            let (member, _) = try!(ops::read_package(manifest_path, &source_id));
+            (root, Some(member))
+        }
+        None => (manifest_path.clone(), None),
+    };
 
-    // TODO: Move this into PathSource
+    let source = try!(path_source(&root_path.dir_path(), options));
     let package = try!(source.get_root_package());
     debug!("loaded package; package={}", package);
 
     for key in package.get_manifest().get_warnings().iter() {
         try!(options.shell.warn(key))
     }
-    compile_pkg(&package, options)
+    match member {
+        Some(ref member) => compile_member(&package, member, source, options),
+        None => compile_ws(&package, None, source, options),
+    }
 }
 
+/// Compiles `package`, or all the members of the workspace if it's a virtual
+/// workspace root.
 pub fn compile_pkg(package: &Package, options: &mut CompileOptions)
                    -> CargoResult<ops::Compilation> {
+    let source = try!(path_source(&package.get_root(), options));
+    compile_ws(package, None, source, options)
+}
+
+/// Compiles the `member` of the workspace whose root is `root`.
+fn compile_member(root: &Package, member: &Package, source: PathSource,
+                  options: &mut CompileOptions)
+                  -> CargoResult<ops::Compilation> {
+    for key in member.get_manifest().get_warnings().iter() {
+        try!(options.shell.warn(key))
+    }
+    compile_ws(root, Some(member.get_name()), source, options)
+}
+
+/// Reads the packages in the directory `path`, through the filesystem
+/// operations of `options` if it has any.
+fn path_source(path: &Path, options: &CompileOptions)
+               -> CargoResult<PathSource> {
+    let id = try!(SourceId::for_path(path));
+    let packages = try!(match options.fs {
+        Some(ref fs) => fs.read_packages(path, &id),
+        None => RealFs.read_packages(path, &id),
+    });
+    Ok(PathSource::preloaded(path, &id, packages))
+}
+
+/// Compiles `package`, whose packages `source` has already read, or the
+/// members of the workspace it's the root of.
+fn compile_ws(package: &Package, member: Option<&str>, source: PathSource,
+              options: &mut CompileOptions)
+              -> CargoResult<ops::Compilation> {
     let CompileOptions { env, ref mut shell, jobs, target, spec,
-                         dev_deps, features, no_default_features } = *options;
+                         dev_deps, features, no_default_features,
+                         all_features, keep_going, timings, examples, all_targets,
+                         skip_targets, extra_examples, only_targets,
+                         ref target_rustc_args, ref fs } = *options;
     let target = target.map(|s| s.to_string());
     let features = features.iter().flat_map(|s| {
         s.as_slice().split(' ')
     }).map(|s| s.to_string()).collect::<Vec<String>>();
 
-    if spec.is_some() && (no_default_features || features.len() > 0) {
+    if spec.is_some() && (no_default_features || all_features ||
+                          features.len() > 0) {
         return Err(human("features cannot be modified when the main package \
                           is not being built"))
     }
+    if all_features && (no_default_features || features.len() > 0) {
+        return Err(human("`--all-features` cannot be combined with \
+                          `--features` or `--no-default-features`"))
+    }
+
+    // The features of a workspace member are requested through the
+    // dependency of the root on it, as the root has none of its own.
+    let all_members = package.is_workspace_root() && spec.is_none() &&
+                      member.is_none();
+    if all_members && (no_default_features || all_features ||
+                       features.len() > 0) {
+        return Err(human("features cannot be modified when every member of \
+                          a workspace is built, run the command in the \
+                          directory of a member instead"))
+    }
+    let mut root = package.clone();
+    match member {
+        Some(name) if all_features => {
+            return Err(human(format!("`--all-features` is not supported \
+                                      for workspace members yet, list the \
+                                      features of `{}` with `--features`",
+                                     name)))
+        }
+        Some(name) => {
+            let members = package.get_dependencies().iter().map(|d| {
+                if d.get_name() != name { return d.clone() }
+                d.clone().features(features.clone())
+                 .default_features(!no_default_features)
+            }).collect();
+            root.set_workspace_members(members);
+        }
+        None => {}
+    }
+    let (features, no_default_features) = if root.is_workspace_root() {
+        (Vec::new(), false)
+    } else {
+        (features, no_default_features)
+    };
 
     let user_configs = try!(config::all_configs(os::getcwd()));
     let override_ids = try!(source_ids_from_config(&user_configs,
                                                    package.get_root()));
+
+    // An explicit `--target` always wins, otherwise fall back to the
+    // `build.target` key of the configuration (if any).
+    let target = match target {
+        Some(target) => Some(target),
+        None => try!(target_from_config(&user_configs)),
+    };
     let config = try!(Config::new(*shell, jobs, target.clone()));
 
     let (packages, resolve_with_overrides, sources) = {
-        let rustc_host = config.rustc_host().to_string();
         let mut registry = PackageRegistry::new(&config);
+        registry.preload(package.get_package_id().get_source_id(),
+                         box source as Box<Source>);
 
         // First, resolve the package's *listed* dependencies, as well as
         // downloading and updating all remotes and such.
@@ -101,12 +251,15 @@ pub fn compile_pkg(package: &Package, options: &mut CompileOptions)
 
         try!(registry.add_overrides(override_ids));
 
-        let platform = target.as_ref().map(|e| e.as_slice()).or(Some(rustc_host.as_slice()));
+        // The cfg values of the platforms are only probed if a dependency
+        // keyed on a cfg expression needs them.
         let method = resolver::ResolveRequired(dev_deps, features.as_slice(),
+                                               all_features,
                                                !no_default_features,
-                                               platform);
+                                               Some((&config as &Platforms,
+                                                     false)));
         let resolved_with_overrides =
-                try!(ops::resolve_with_previous(&mut registry, package, method,
+                try!(ops::resolve_with_previous(&mut registry, &root, method,
                                                 Some(&resolve), None));
 
         let req: Vec<PackageId> = resolved_with_overrides.iter().map(|r| {
@@ -121,33 +274,416 @@ pub fn compile_pkg(package: &Package, options: &mut CompileOptions)
 
     debug!("packages={}", packages);
 
-    let to_build = match spec {
-        Some(spec) => {
+    let to_build = match (spec, member) {
+        (Some(spec), _) => {
             let pkgid = try!(resolve_with_overrides.query(spec));
-            packages.iter().find(|p| p.get_package_id() == pkgid).unwrap()
+            vec![packages.iter().find(|p| p.get_package_id() == pkgid).unwrap()]
         }
-        None => package,
+        (None, Some(name)) => {
+            let member = packages.iter().find(|p| {
+                is_member(&root, *p) && p.get_name() == name
+            });
+            vec![try!(member.require(|| {
+                human(format!("package `{}` is not a member of the workspace \
+                               at `{}`", name,
+                              package.get_manifest_path().display()))
+            }))]
+        }
+        (None, None) if root.is_workspace_root() => {
+            packages.iter().filter(|p| is_member(&root, *p)).collect()
+        }
+        (None, None) => vec![package],
+    };
+    if to_build.len() > 1 && target_rustc_args.is_some() {
+        return Err(human("extra arguments to `rustc` can only be passed to \
+                          one package, run the command in the directory of a \
+                          workspace member"))
+    }
+
+    // `--all-targets` also compiles (without running) the tests, examples and
+    // benchmarks, and extra examples are compiled along with only the library
+    // and build script. Everything is built at once in the environment of
+    // `cargo test`, so the library and dependencies are shared: tests and
+    // examples get the test profile and benchmarks are compiled as tests.
+    // With `--release` they get the profiles of `cargo test --release` and
+    // `cargo bench` instead.
+    let only_examples = !all_targets && extra_examples.len() > 0;
+    let release = env == "release" || env == "release-test" || env == "bench";
+    let (build_env, extra_envs) = if !all_targets && !only_examples {
+        (env, vec![])
+    } else if release && all_targets {
+        ("release-test", vec!["release-test", "bench"])
+    } else if release {
+        ("release-test", vec!["release-test"])
+    } else {
+        ("test", vec!["test"])
     };
 
-    let targets = to_build.get_targets().iter().filter(|target| {
+    let targets = to_build.iter().map(|pkg| {
+        let mut targets = targets_for_env(*pkg, env, examples);
+        for extra_env in extra_envs.iter() {
+            let extra = targets_for_env(*pkg, *extra_env, examples);
+            let extra = if only_examples {
+                extra.into_iter().filter(|t| {
+                    t.is_example() && extra_examples.iter().any(|name| {
+                        name.as_slice() == t.get_name()
+                    })
+                }).collect()
+            } else {
+                extra
+            };
+            add_targets(&mut targets, extra);
+        }
+        if all_targets && !release {
+            add_targets(&mut targets, benches_as_tests(*pkg));
+        }
+        targets
+    }).collect::<Vec<Vec<&Target>>>();
+
+    // `--skip-target` drops targets before anything is planned, and a name
+    // which matches nothing is most likely a typo. When several members of a
+    // workspace are built it only needs to match in one of them.
+    for name in skip_targets.iter() {
+        let found = targets.iter().any(|targets| {
+            targets.iter().any(|t| is_skipped(*t, name.as_slice()))
+        });
+        if !found && to_build.len() == 1 {
+            return Err(human(format!("no target named `{}` to skip in \
+                                      package `{}`", name,
+                                     to_build[0].get_package_id())))
+        } else if !found {
+            return Err(human(format!("no target named `{}` to skip in any \
+                                      member of the workspace", name)))
+        }
+    }
+
+    // A selected target which doesn't exist is most likely a typo as well.
+    for selection in only_targets.unwrap_or(&[]).iter() {
+        let found = targets.iter().any(|targets| {
+            targets.iter().any(|t| is_selected(*t, selection))
+        });
+        if !found {
+            return Err(human(match *selection {
+                SelectBin(name) => format!("no bin target named `{}`", name),
+                SelectTest(name) => format!("no test target named `{}`", name),
+                SelectExample(name) => {
+                    format!("no example target named `{}`", name)
+                }
+                SelectBench(name) => {
+                    format!("no bench target named `{}`", name)
+                }
+                SelectLib | DefaultTarget => {
+                    "the package has no library".to_string()
+                }
+            }))
+        }
+    }
+
+    // Every package is built in the same `compile_targets` call so they share
+    // one build directory: preparing it again for each package would move
+    // aside (and eventually delete) what the previous ones produced.
+    let mut members = Vec::new();
+    let mut rustc_args = None;
+    for (to_build, targets) in to_build.iter().zip(targets.iter()) {
+        let targets = targets.iter().map(|t| *t).filter(|t| {
+            !skip_targets.iter().any(|name| is_skipped(*t, name.as_slice()))
+        }).filter(|t| {
+            match only_targets {
+                None => true,
+                Some(selections) => {
+                    t.get_profile().is_custom_build() ||
+                        (t.is_lib() && !t.get_profile().is_test()) ||
+                        selections.iter().any(|s| is_selected(*t, s))
+                }
+            }
+        }).collect::<Vec<&Target>>();
+
+        // `cargo rustc` builds only the selected target, along with the
+        // package's library and build script which it may need. Dependencies
+        // are built as usual.
+        let targets = match *target_rustc_args {
+            None => targets,
+            Some((ref selection, args)) => {
+                let selected = try!(select_target(targets.as_slice(),
+                                                  selection));
+                rustc_args = Some((selected.clone(), args.to_vec()));
+                targets.iter().map(|t| *t).filter(|t| {
+                    t.get_profile().is_custom_build() || *t == selected ||
+                        (t.is_lib() && !t.get_profile().is_test())
+                }).collect()
+            }
+        };
+        members.push((*to_build, targets));
+    }
+
+    let mut ret = {
+        let _p = profile::start(format!("compiling: {}", build_env));
+        let mut lib_overrides = try!(scrape_build_config(&config,
+                                                         &user_configs));
+        lib_overrides.keep_going = keep_going;
+        lib_overrides.timings = timings;
+        lib_overrides.target_rustc_args = rustc_args;
+        lib_overrides.fs = fs.clone();
+
+        try!(ops::compile_targets(build_env, members.as_slice(),
+                                  &PackageSet::new(packages.as_slice()),
+                                  &resolve_with_overrides, &sources,
+                                  &config, lib_overrides))
+    };
+    if all_members { ret.package = package.clone() }
+
+    if timings {
+        let dst = package.get_absolute_target_dir().join("cargo-timings.json");
+        try!(ops::report_timings(&ret.timings, &config, &dst));
+    }
+    return Ok(ret);
+}
+
+/// Whether `pkg` is one of the members of the virtual workspace root `root`.
+fn is_member(root: &Package, pkg: &Package) -> bool {
+    root.is_workspace_root() && root.get_dependencies().iter().any(|d| {
+        d.matches_id(pkg.get_package_id())
+    })
+}
+
+/// Returns the targets of `pkg` which are compiled in the environment `env`.
+fn targets_for_env<'a>(pkg: &'a Package, env: &str,
+                       examples: Examples) -> Vec<&'a Target> {
+    pkg.get_targets().iter().filter(|target| {
+        let profile = target.get_profile();
+        if target.is_example() && !profile.is_doc() {
+            match examples {
+                NoExamples => return false,
+                TestedExamples if !profile.is_tested() => return false,
+                TestedExamples | AllExamples => {}
+            }
+        } else if !profile.is_tested() {
+            return false
+        }
         target.get_profile().is_custom_build() || match env {
             // doc-all == document everything, so look for doc targets
             "doc" | "doc-all" => target.get_profile().get_env() == "doc",
             env => target.get_profile().get_env() == env,
         }
+    }).collect()
+}
+
+/// Returns the benchmarks of `pkg` compiled as tests, which only
+/// `--all-targets` does.
+fn benches_as_tests<'a>(pkg: &'a Package) -> Vec<&'a Target> {
+    pkg.get_targets().iter().filter(|target| {
+        let profile = target.get_profile();
+        !target.is_example() && !profile.is_tested() &&
+            profile.get_env() == "test"
+    }).collect()
+}
+
+/// Adds `extra` to `targets`, except for the targets which would produce the
+/// same artifact as one already there, e.g. the library which the tests of
+/// another environment link to.
+fn add_targets<'a>(targets: &mut Vec<&'a Target>, extra: Vec<&'a Target>) {
+    for target in extra.into_iter() {
+        let profile = target.get_profile();
+        let built = targets.iter().any(|t| {
+            t.get_name() == target.get_name() &&
+                t.is_lib() == target.is_lib() &&
+                t.is_example() == target.is_example() &&
+                t.get_profile().is_test() == profile.is_test() &&
+                t.get_profile().is_doc() == profile.is_doc() &&
+                t.get_profile().is_custom_build() == profile.is_custom_build()
+        });
+        if !built { targets.push(target) }
+    }
+}
+
+fn is_skipped(target: &Target, name: &str) -> bool {
+    !target.get_profile().is_custom_build() && target.get_name() == name
+}
+
+/// Whether `target` is one of the targets `selection` refers to, in any
+/// profile.
+fn is_selected(target: &Target, selection: &TargetSelection) -> bool {
+    if target.get_profile().is_custom_build() { return false }
+    match *selection {
+        SelectLib | DefaultTarget => target.is_lib(),
+        SelectBin(name) => target.is_bin() && target.get_name() == name,
+        SelectExample(name) => {
+            target.is_example() && target.get_name() == name
+        }
+        SelectTest(name) | SelectBench(name) => {
+            target.get_profile().is_test() && !target.is_lib() &&
+                target.get_name() == name
+        }
+    }
+}
+
+fn select_target<'a>(targets: &[&'a Target], selection: &TargetSelection)
+                     -> CargoResult<&'a Target> {
+    let targets = targets.iter().map(|t| *t).filter(|t| {
+        !t.get_profile().is_custom_build()
     }).collect::<Vec<&Target>>();
 
-    let ret = {
-        let _p = profile::start("compiling");
-        let lib_overrides = try!(scrape_build_config(&config, &user_configs));
+    let (kind, name) = match *selection {
+        SelectLib => {
+            return match targets.iter().find(|t| t.is_lib()) {
+                Some(lib) => Ok(*lib),
+                None => Err(human("the package has no library")),
+            }
+        }
+        DefaultTarget => {
+            match targets.iter().find(|t| t.is_lib()) {
+                Some(lib) => return Ok(*lib),
+                None => {}
+            }
+            let bins = targets.iter().map(|t| *t).filter(|t| t.is_bin())
+                              .collect::<Vec<&Target>>();
+            return match bins.len() {
+                0 => Err(human("the package has no library or binary to pass \
+                                extra arguments to `rustc` for")),
+                1 => Ok(bins[0]),
+                _ => Err(human(format!("extra arguments to `rustc` can only be \
+                                        passed to one target, select one with \
+                                        `--bin NAME`\ncandidates: {}",
+                                       target_names(bins.as_slice())))),
+            }
+        }
+        SelectBin(name) => ("bin", name),
+        SelectTest(name) => ("test", name),
+        SelectExample(name) => ("example", name),
+        SelectBench(name) => ("bench", name),
+    };
 
-        try!(ops::compile_targets(env.as_slice(), targets.as_slice(), to_build,
-                                  &PackageSet::new(packages.as_slice()),
-                                  &resolve_with_overrides, &sources,
-                                  &config, lib_overrides))
+    let matches = targets.iter().map(|t| *t).filter(|t| {
+        t.get_name() == name && match *selection {
+            SelectBin(..) => t.is_bin(),
+            SelectExample(..) => t.is_example(),
+            _ => t.get_profile().is_test() && !t.is_lib(),
+        }
+    }).collect::<Vec<&Target>>();
+    match matches.len() {
+        0 => Err(human(format!("no {} target named `{}`", kind, name))),
+        1 => Ok(matches[0]),
+        _ => Err(human(format!("extra arguments to `rustc` can only be passed \
+                                to one target, but `--{} {}` is ambiguous\n\
+                                candidates: {}", kind, name,
+                               target_names(matches.as_slice())))),
+    }
+}
+
+/// Returns the path of the file produced for each of `selections` among the
+/// targets of the package that `compilation` was for.
+///
+/// A selection which produced no file, or more than one, is an error.
+pub fn artifact_paths(compilation: &ops::Compilation,
+                      selections: &[TargetSelection]) -> CargoResult<Vec<Path>> {
+    let pkgid = compilation.package.get_package_id();
+    let artifacts = compilation.artifacts.iter().filter(|&&(ref id, _, _)| {
+        id == pkgid
+    }).map(|&(_, ref t, ref path)| (t, path)).collect::<Vec<(&Target, &Path)>>();
+    // A library can be built in several environments with the same profile,
+    // in which case it's at the same location each time.
+    let find = |f: |&Target| -> bool| -> Vec<&Path> {
+        let mut paths = Vec::new();
+        for &(t, path) in artifacts.iter() {
+            if f(t) && !paths.contains(&path) { paths.push(path) }
+        }
+        paths
     };
 
-    return Ok(ret);
+    selections.iter().map(|selection| {
+        let (what, matches) = match *selection {
+            DefaultTarget => {
+                let lib = find(|t| t.is_lib() && !t.get_profile().is_test());
+                let bins = find(|t| t.is_bin() && !t.get_profile().is_test());
+                if lib.len() == 0 && bins.len() > 1 {
+                    return Err(human("the package has no library and more \
+                                      than one binary, select one with \
+                                      `--bin NAME`"))
+                }
+                if lib.len() > 0 {
+                    ("the library".to_string(), lib)
+                } else {
+                    ("the library or binary of the package".to_string(), bins)
+                }
+            }
+            SelectLib => {
+                ("the library".to_string(),
+                 find(|t| t.is_lib() && !t.get_profile().is_test()))
+            }
+            SelectBin(name) => {
+                (format!("binary `{}`", name), find(|t| {
+                    t.is_bin() && !t.get_profile().is_test() &&
+                        t.get_name() == name
+                }))
+            }
+            SelectExample(name) => {
+                (format!("example `{}`", name), find(|t| {
+                    t.is_example() && t.get_name() == name
+                }))
+            }
+            SelectTest(name) | SelectBench(name) => {
+                (format!("`{}`", name), find(|t| {
+                    t.get_profile().is_test() && !t.is_lib() &&
+                        t.get_name() == name
+                }))
+            }
+        };
+        match matches.len() {
+            0 => Err(human(format!("no artifact was produced for {}", what))),
+            1 => Ok(os::make_absolute(matches[0])),
+            _ => {
+                let paths = matches.iter().map(|path| {
+                    format!("  {}", path.display())
+                }).collect::<Vec<String>>();
+                Err(human(format!("{} produced more than one artifact:\n{}",
+                                  what, paths.connect("\n"))))
+            }
+        }
+    }).collect()
+}
+
+/// Returns the cfg values which the `[target.cfg(...)]` dependencies of the
+/// package at `manifest_path` are evaluated against when it's compiled with
+/// `options`: those rustc sets for the target, followed by the `feature`
+/// values of the features enabled in the package.
+pub fn target_cfg(manifest_path: &Path, options: &mut CompileOptions)
+                  -> CargoResult<Vec<Cfg>> {
+    let mut source = try!(PathSource::for_path(&manifest_path.dir_path()));
+    try!(source.update());
+    let package = try!(source.get_root_package());
+
+    let features = options.features.iter().flat_map(|s| {
+        s.as_slice().split(' ')
+    }).map(|s| s.to_string()).collect::<Vec<String>>();
+    if options.all_features && (options.no_default_features ||
+                                features.len() > 0) {
+        return Err(human("`--all-features` cannot be combined with \
+                          `--features` or `--no-default-features`"))
+    }
+
+    let user_configs = try!(config::all_configs(os::getcwd()));
+    let target = match options.target {
+        Some(target) => Some(target.to_string()),
+        None => try!(target_from_config(&user_configs)),
+    };
+    let config = try!(Config::new(&mut *options.shell, options.jobs, target));
+    let mut cfg = try!(config.target_cfg(config.target()));
+
+    let method = resolver::ResolveRequired(false, features.as_slice(),
+                                           options.all_features,
+                                           !options.no_default_features,
+                                           None);
+    let enabled = try!(resolver::enabled_features(package.get_summary(),
+                                                  method));
+    cfg.extend(resolver::feature_cfg(&enabled).into_iter());
+    Ok(cfg)
+}
+
+fn target_names(targets: &[&Target]) -> String {
+    let names = targets.iter().map(|t| {
+        format!("`{}` ({})", t.get_name(), t.get_src_path().display())
+    }).collect::<Vec<String>>();
+    names.connect(", ")
 }
 
 fn source_ids_from_config(configs: &HashMap<String, config::ConfigValue>,
@@ -162,11 +698,15 @@ fn source_ids_from_config(configs: &HashMap<String, config::ConfigValue>,
         internal("invalid configuration for the key `paths`")
     }));
 
+    let home = os::homedir();
     paths.iter().map(|&(ref s, ref p)| {
+        let expanded = home.as_ref().and_then(|home| {
+            expand_home(s.as_slice(), home)
+        });
         // The path listed next to the string is the config file in which the
         // key was located, so we want to pop off the `.cargo/config` component
         // to get the directory containing the `.cargo` folder.
-        p.dir_path().dir_path().join(s.as_slice())
+        expanded.unwrap_or_else(|| p.dir_path().dir_path().join(s.as_slice()))
     }).filter(|p| {
         // Make sure we don't override the local package, even if it's in the
         // list of override paths.
@@ -174,6 +714,25 @@ fn source_ids_from_config(configs: &HashMap<String, config::ConfigValue>,
     }).map(|p| SourceId::for_path(&p)).collect()
 }
 
+fn target_from_config(configs: &HashMap<String, config::ConfigValue>)
+                      -> CargoResult<Option<String>> {
+    let build = match configs.find_equiv("build") {
+        None => return Ok(None),
+        Some(build) => try!(build.table().chain_error(|| {
+            internal("invalid configuration for the key `build`")
+        })),
+    };
+    match build.find_equiv("target") {
+        None => Ok(None),
+        Some(target) => {
+            let target = try!(target.string().chain_error(|| {
+                internal("invalid configuration for the key `build.target`")
+            }));
+            Ok(Some(target.val0().to_string()))
+        }
+    }
+}
+
 fn scrape_build_config(config: &Config,
                        configs: &HashMap<String, config::ConfigValue>)
                        -> CargoResult<ops::BuildConfig> {
@@ -184,16 +743,25 @@ fn scrape_build_config(config: &Config,
         })),
     };
 
-    let host = try!(scrape_target_config(target, config.rustc_host()));
-    let target = match config.target() {
-        Some(triple) => try!(scrape_target_config(target, triple)),
+    let host = try!(scrape_target_config(target, config.rustc_host(),
+                                         config.home()));
+    let target = match config.target_triple() {
+        Some(triple) => try!(scrape_target_config(target, triple,
+                                                  config.home())),
         None => host.clone(),
     };
-    Ok(ops::BuildConfig { host: host, target: target })
+    Ok(ops::BuildConfig {
+        host: host,
+        target: target,
+        keep_going: false,
+        timings: false,
+        target_rustc_args: None,
+        fs: None,
+    })
 }
 
 fn scrape_target_config(target: &HashMap<String, config::ConfigValue>,
-                        triple: &str)
+                        triple: &str, home: &Path)
                         -> CargoResult<ops::TargetConfig> {
     let target = match target.get(&triple.to_string()) {
         None => return Ok(Default::default()),
@@ -207,19 +775,48 @@ fn scrape_target_config(target: &HashMap<String, config::ConfigValue>,
         ar: None,
         linker: None,
         overrides: HashMap::new(),
+        link_libs: Vec::new(),
+        link_paths: Vec::new(),
     };
     for (k, v) in target.iter() {
         match k.as_slice() {
             "ar" | "linker" => {
                 let v = try!(v.string().chain_error(|| {
                     internal(format!("invalid configuration for key `{}`", k))
-                })).ref0().to_string();
+                })).val0();
+                // Tools are commonly installed under the home directory, so
+                // allow `~/` to refer to it.
+                let v = match expand_home(v, home) {
+                    Some(path) => path.display().to_string(),
+                    None => v.to_string(),
+                };
                 if k.as_slice() == "linker" {
                     ret.linker = Some(v);
                 } else {
                     ret.ar = Some(v);
                 }
             }
+            "rustc-link-lib" => {
+                let libs = try!(v.list().chain_error(|| {
+                    internal(format!("invalid configuration for the key \
+                                      `target.{}.rustc-link-lib`", triple))
+                }));
+                ret.link_libs.extend(libs.iter().map(|&(ref lib, _)| {
+                    lib.clone()
+                }));
+            }
+            "rustc-flags" => {
+                let (v, def) = try!(v.string().chain_error(|| {
+                    internal(format!("invalid configuration for the key \
+                                      `target.{}.rustc-flags`", triple))
+                }));
+                let whence = format!("in `target.{}.rustc-flags`", triple);
+                let (paths, links) = try!(
+                    BuildOutput::parse_rustc_flags(v, whence.as_slice())
+                );
+                ret.link_paths.extend(config_relative(paths, def).into_iter());
+                ret.link_libs.extend(links.into_iter());
+            }
             lib_name => {
                 let table = try!(v.table().chain_error(|| {
                     internal(format!("invalid configuration for the key \
@@ -228,14 +825,16 @@ fn scrape_target_config(target: &HashMap<String, config::ConfigValue>,
                 let mut output = BuildOutput {
                     library_paths: Vec::new(),
                     library_links: Vec::new(),
+                    cfgs: Vec::new(),
                     metadata: Vec::new(),
+                    rerun_if_changed: Vec::new(),
                 };
                 for (k, v) in table.iter() {
-                    let v = try!(v.string().chain_error(|| {
+                    let (v, def) = try!(v.string().chain_error(|| {
                         internal(format!("invalid configuration for the key \
                                           `target.{}.{}.{}`", triple, lib_name,
                                           k))
-                    })).val0();
+                    }));
                     if k.as_slice() == "rustc-flags" {
                         let whence = format!("in `target.{}.{}.rustc-flags`",
                                              triple, lib_name);
@@ -243,6 +842,7 @@ fn scrape_target_config(target: &HashMap<String, config::ConfigValue>,
                         let (paths, links) = try!(
                             BuildOutput::parse_rustc_flags(v.as_slice(), whence)
                         );
+                        let paths = config_relative(paths, def);
                         output.library_paths.extend(paths.into_iter());
                         output.library_links.extend(links.into_iter());
                     } else {
@@ -256,3 +856,11 @@ fn scrape_target_config(target: &HashMap<String, config::ConfigValue>,
 
     Ok(ret)
 }
+
+/// Resolves relative `-L` paths from the configuration file `def` against the
+/// directory containing its `.cargo` directory, as for `paths`, rather than
+/// whichever directory rustc happens to run in.
+fn config_relative(paths: Vec<Path>, def: &Path) -> Vec<Path> {
+    let base = def.dir_path().dir_path();
+    paths.into_iter().map(|p| base.join(p)).collect()
+}
@@ -35,14 +35,23 @@ pub fn doc(manifest_path: &Path,
         }
     }
 
-    try!(ops::compile(manifest_path, &mut options.compile_opts));
+    let compilation = try!(ops::compile(manifest_path,
+                                        &mut options.compile_opts));
 
     if options.open_result {
         use std::io::fs::PathExtensions;
 
-        match lib_names.iter().nth(0).map(|l| package.get_absolute_target_dir()
-                                                     .join("doc").join(*l).join("index.html"))
-        {
+        let mut doc_dir = package.get_absolute_target_dir();
+        match options.compile_opts.target {
+            Some(triple) => doc_dir.push(triple),
+            None => {}
+        }
+        // The package selected with `--package` may not be the local one.
+        let lib = compilation.package.get_targets().iter().find(|t| {
+            t.is_lib() && t.get_profile().is_doc()
+        });
+        match lib.map(|l| doc_dir.join("doc").join(l.get_name())
+                                 .join("index.html")) {
             Some(ref path) if path.exists() => open_docs(path),
             _ => ()
         }
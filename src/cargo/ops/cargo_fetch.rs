@@ -8,6 +8,9 @@ use util::{CargoResult, Config};
 /// Executes `cargo fetch`.
 pub fn fetch(manifest_path: &Path,
              shell: &mut MultiShell) -> CargoResult<()> {
+    // The lockfile of a workspace member is the one of the whole workspace.
+    let manifest_path = try!(ops::find_workspace_root(manifest_path))
+                            .unwrap_or(manifest_path.clone());
     let mut source = try!(PathSource::for_path(&manifest_path.dir_path()));
     try!(source.update());
     let package = try!(source.get_root_package());
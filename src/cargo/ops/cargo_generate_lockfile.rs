@@ -13,25 +13,50 @@ pub struct UpdateOptions<'a> {
     pub to_update: Option<&'a str>,
     pub precise: Option<&'a str>,
     pub aggressive: bool,
+    pub dry_run: bool,
+}
+
+/// A difference between two resolutions of the same package.
+#[deriving(PartialEq, Show)]
+pub enum LockfileChange {
+    Added(PackageId),
+    Removed(PackageId),
+    /// A package changed its version or source, from the first id to the
+    /// second.
+    Updated(PackageId, PackageId),
 }
 
 pub fn generate_lockfile(manifest_path: &Path,
                          shell: &mut MultiShell)
                          -> CargoResult<()> {
+    // The lockfile of a workspace member is the one of the whole workspace.
+    let manifest_path = try!(ops::find_workspace_root(manifest_path))
+                            .unwrap_or(manifest_path.clone());
     let mut source = try!(PathSource::for_path(&manifest_path.dir_path()));
     try!(source.update());
     let package = try!(source.get_root_package());
-    let mut config = try!(Config::new(shell, None, None));
-    let mut registry = PackageRegistry::new(&mut config);
-    let resolve = try!(ops::resolve_with_previous(&mut registry, &package,
-                                                  resolver::ResolveEverything,
-                                                  None, None));
+    let previous_resolve = try!(ops::load_pkg_lockfile(&package));
+    let resolve = {
+        let mut config = try!(Config::new(shell, None, None));
+        let mut registry = PackageRegistry::new(&mut config);
+        try!(ops::resolve_with_previous(&mut registry, &package,
+                                        resolver::ResolveEverything,
+                                        None, None))
+    };
+    match previous_resolve {
+        Some(ref previous) => try!(print_lockfile_changes(shell, previous,
+                                                          &resolve)),
+        None => {}
+    }
     try!(ops::write_pkg_lockfile(&package, &resolve));
     Ok(())
 }
 
 pub fn update_lockfile(manifest_path: &Path,
                        opts: &mut UpdateOptions) -> CargoResult<()> {
+    // The lockfile of a workspace member is the one of the whole workspace.
+    let manifest_path = try!(ops::find_workspace_root(manifest_path))
+                            .unwrap_or(manifest_path.clone());
     let mut source = try!(PathSource::for_path(&manifest_path.dir_path()));
     try!(source.update());
     let package = try!(source.get_root_package());
@@ -46,37 +71,44 @@ pub fn update_lockfile(manifest_path: &Path,
                           simultaneously"))
     }
 
-    let mut config = try!(Config::new(opts.shell, None, None));
-    let mut registry = PackageRegistry::new(&mut config);
     let mut to_avoid = HashSet::new();
+    let resolve = {
+        let mut config = try!(Config::new(opts.shell, None, None));
+        let mut registry = PackageRegistry::new(&mut config);
 
-    match opts.to_update {
-        Some(name) => {
-            let dep = try!(previous_resolve.query(name));
-            if opts.aggressive {
-                fill_with_deps(&previous_resolve, dep, &mut to_avoid,
-                               &mut HashSet::new());
-            } else {
-                to_avoid.insert(dep);
-                match opts.precise {
-                    Some(precise) => {
-                        let precise = dep.get_source_id().clone()
-                                         .with_precise(Some(precise.to_string()));
-                        try!(registry.add_sources(&[precise]));
+        match opts.to_update {
+            Some(name) => {
+                let dep = try!(previous_resolve.query(name));
+                if opts.aggressive {
+                    fill_with_deps(&previous_resolve, dep, &mut to_avoid,
+                                   &mut HashSet::new());
+                } else {
+                    to_avoid.insert(dep);
+                    match opts.precise {
+                        Some(precise) => {
+                            let precise = dep.get_source_id().clone()
+                                             .with_precise(Some(precise.to_string()));
+                            try!(registry.add_sources(&[precise]));
+                        }
+                        None => {}
                     }
-                    None => {}
                 }
             }
+            None => to_avoid.extend(previous_resolve.iter()),
         }
-        None => to_avoid.extend(previous_resolve.iter()),
-    }
 
-    let resolve = try!(ops::resolve_with_previous(&mut registry,
-                                                  &package,
-                                                  resolver::ResolveEverything,
-                                                  Some(&previous_resolve),
-                                                  Some(&to_avoid)));
-    try!(ops::write_pkg_lockfile(&package, &resolve));
+        try!(ops::resolve_with_previous(&mut registry,
+                                        &package,
+                                        resolver::ResolveEverything,
+                                        Some(&previous_resolve),
+                                        Some(&to_avoid)))
+    };
+    try!(print_lockfile_changes(opts.shell, &previous_resolve, &resolve));
+    if opts.dry_run {
+        try!(opts.shell.warn("not updating Cargo.lock, this was a dry run"));
+    } else {
+        try!(ops::write_pkg_lockfile(&package, &resolve));
+    }
     return Ok(());
 
     fn fill_with_deps<'a>(resolve: &'a Resolve, dep: &'a PackageId,
@@ -94,3 +126,65 @@ pub fn update_lockfile(manifest_path: &Path,
         }
     }
 }
+
+/// Computes the packages which were added, removed or changed between two
+/// resolutions of the same package, in the order of the package ids.
+pub fn lockfile_changes(previous: &Resolve,
+                        resolve: &Resolve) -> Vec<LockfileChange> {
+    // Source ids ignore the precise revision when compared, but a new
+    // revision of a git dependency is a change worth reporting.
+    fn same(a: &PackageId, b: &PackageId) -> bool {
+        a == b && a.get_source_id().get_precise() ==
+                  b.get_source_id().get_precise()
+    }
+
+    let mut removed: Vec<&PackageId> = previous.iter().filter(|old| {
+        !resolve.iter().any(|new| same(*old, new))
+    }).collect();
+    let mut added: Vec<&PackageId> = resolve.iter().filter(|new| {
+        !previous.iter().any(|old| same(old, *new))
+    }).collect();
+    removed.sort();
+    added.sort();
+
+    let mut changes = Vec::new();
+    for new in added.iter() {
+        match removed.iter().find(|old| old.get_name() == new.get_name()) {
+            Some(old) => changes.push(Updated((*old).clone(), (*new).clone())),
+            None => changes.push(Added((*new).clone())),
+        }
+    }
+    for old in removed.iter() {
+        if !added.iter().any(|new| old.get_name() == new.get_name()) {
+            changes.push(Removed((*old).clone()));
+        }
+    }
+    changes
+}
+
+/// Reports the packages which were added, removed or changed between two
+/// resolutions of the same package.
+///
+/// This is shared between `cargo generate-lockfile` and `cargo update` so that
+/// both report changes to the lock file in the same way.
+fn print_lockfile_changes(shell: &mut MultiShell, previous: &Resolve,
+                          resolve: &Resolve) -> CargoResult<()> {
+    for change in lockfile_changes(previous, resolve).iter() {
+        match *change {
+            Updated(ref old, ref new) => {
+                let msg = if old.get_version() != new.get_version() {
+                    format!("{} v{} -> v{}", new.get_name(),
+                            old.get_version(), new.get_version())
+                } else {
+                    format!("{} v{} ({}) -> ({})", new.get_name(),
+                            new.get_version(), old.get_source_id(),
+                            new.get_source_id())
+                };
+                try!(shell.status("Updating", msg));
+            }
+            Added(ref new) => try!(shell.status("Adding", new)),
+            Removed(ref old) => try!(shell.status("Removing", old)),
+        }
+    }
+    Ok(())
+}
@@ -4,16 +4,24 @@ use std::io::fs::PathExtensions;
 
 use git2::Config;
 
-use util::{GitRepo, HgRepo, CargoResult, human, ChainError, config, internal};
+use util::{GitRepo, HgRepo, CargoResult, Require, human, ChainError,
+           config, internal};
+use util::toml::validate_package_name;
 use core::shell::MultiShell;
 
+#[deriving(Clone, PartialEq, Show)]
+pub enum VersionControl { Git, Hg, NoVcs }
+
 pub struct NewOptions<'a> {
-    pub no_git: bool,
-    pub git: bool,
-    pub hg: bool,
+    /// The repository to initialize, if one was explicitly requested.
+    /// Otherwise a git repository is created unless `cargo-new.git` is
+    /// `false` or the package lands inside an existing git repository.
+    pub version_control: Option<VersionControl>,
     pub travis: bool,
     pub bin: bool,
     pub path: &'a str,
+    /// The package name, when it shouldn't be taken from the directory.
+    pub name: Option<&'a str>,
 }
 
 struct CargoNewConfig {
@@ -22,25 +30,49 @@ struct CargoNewConfig {
     git: Option<bool>,
 }
 
-pub fn new(opts: NewOptions, _shell: &mut MultiShell) -> CargoResult<()> {
+pub fn new(opts: NewOptions, shell: &mut MultiShell) -> CargoResult<()> {
     let path = os::getcwd().join(opts.path);
     if path.exists() {
-        return Err(human(format!("Destination `{}` already exists",
-                                 path.display())))
-    }
-    let name = path.filename_str().unwrap();
-    for c in name.chars() {
-        if c.is_alphanumeric() { continue }
-        if c == '_' || c == '-' { continue }
-        return Err(human(format!("Invalid character `{}` in crate name: `{}`",
-                                 c, name).as_slice()));
+        try!(confirm_existing(&path, shell));
     }
+    let name = match opts.name {
+        Some(name) => name,
+        None => try!(path.filename_str().require(|| {
+            human(format!("cannot infer a package name from `{}`, pass one \
+                           with --name", path.display()))
+        })),
+    };
+    try!(validate_package_name(name));
     mk(&path, name, &opts).chain_error(|| {
         human(format!("Failed to create project `{}` at `{}`",
                       name, path.display()))
     })
 }
 
+/// A package may be created in an existing directory which isn't a package
+/// already, once that's confirmed. Files of the package which are already
+/// there are kept as they are.
+fn confirm_existing(path: &Path, shell: &mut MultiShell) -> CargoResult<()> {
+    let exists = format!("Destination `{}` already exists", path.display());
+    if !path.is_dir() || path.join("Cargo.toml").exists() {
+        return Err(human(exists))
+    }
+    let question = format!("{}, create the package in it? Files which are \
+                            already there are kept", exists);
+    if try!(shell.confirm(question.as_slice())) { return Ok(()) }
+    if shell.is_interactive() { return Err(human(exists)) }
+    Err(human(format!("{}\n\npass `--yes` to create the package in it \
+                       anyway, keeping any files which are already there",
+                      exists)))
+}
+
+/// Writes a file of the new package, unless a file is already there.
+fn write_new(path: &Path, contents: &str) -> CargoResult<()> {
+    if path.exists() { return Ok(()) }
+    try!(File::create(path).write_str(contents));
+    Ok(())
+}
+
 fn existing_git_repo(path: &Path) -> bool {
     GitRepo::discover(path).is_ok()
 }
@@ -48,38 +80,39 @@ fn existing_git_repo(path: &Path) -> bool {
 fn mk(path: &Path, name: &str, opts: &NewOptions) -> CargoResult<()> {
     let cfg = try!(global_config());
     let mut ignore = "/target\n".to_string();
-    let no_git = !opts.git && (opts.no_git || cfg.git == Some(false));
-    let in_existing_git_repo = existing_git_repo(&path.dir_path());
     if !opts.bin {
         ignore.push_str("/Cargo.lock\n");
     }
 
-    if opts.hg {
-        try!(HgRepo::init(path));
-        try!(File::create(&path.join(".hgignore")).write(ignore.as_bytes()));
-    } else if no_git || in_existing_git_repo {
-        try!(fs::mkdir(path, io::USER_RWX));
-    } else {
-        try!(GitRepo::init(path));
-        try!(File::create(&path.join(".gitignore")).write(ignore.as_bytes()));
+    let vcs = match opts.version_control {
+        Some(ref vcs) => vcs.clone(),
+        None if cfg.git == Some(false) => NoVcs,
+        None if existing_git_repo(&path.dir_path()) => NoVcs,
+        None => Git,
+    };
+    match vcs {
+        Git => {
+            try!(GitRepo::init(path));
+            try!(write_new(&path.join(".gitignore"), ignore.as_slice()));
+        }
+        Hg => {
+            try!(HgRepo::init(path));
+            try!(write_new(&path.join(".hgignore"), ignore.as_slice()));
+        }
+        NoVcs if path.is_dir() => {}
+        NoVcs => try!(fs::mkdir(path, io::USER_RWX)),
     }
 
-    let (author_name, email) = try!(discover_author());
-    // Hoo boy, sure glad we've got exhaustivenes checking behind us.
-    let author = match (cfg.name, cfg.email, author_name, email) {
-        (Some(name), Some(email), _, _) |
-        (Some(name), None, _, Some(email)) |
-        (None, Some(email), name, _) |
-        (None, None, name, Some(email)) => format!("{} <{}>", name, email),
-        (Some(name), None, _, None) |
-        (None, None, name, None) => name,
+    let author = match try!(discover_author(&cfg)) {
+        (name, Some(email)) => format!("{} <{}>", name, email),
+        (name, None) => name,
     };
 
     if opts.travis {
-        try!(File::create(&path.join(".travis.yml")).write_str("language: rust\n"));
+        try!(write_new(&path.join(".travis.yml"), "language: rust\n"));
     }
 
-    try!(File::create(&path.join("Cargo.toml")).write_str(format!(
+    try!(write_new(&path.join("Cargo.toml"), format!(
 r#"[package]
 
 name = "{}"
@@ -87,16 +120,18 @@ version = "0.0.1"
 authors = ["{}"]
 "#, name, author).as_slice()));
 
-    try!(fs::mkdir(&path.join("src"), io::USER_RWX));
+    if !path.join("src").is_dir() {
+        try!(fs::mkdir(&path.join("src"), io::USER_RWX));
+    }
 
     if opts.bin {
-        try!(File::create(&path.join("src/main.rs")).write_str("\
+        try!(write_new(&path.join("src/main.rs"), "\
 fn main() {
     println!(\"Hello, world!\")
 }
 "));
     } else {
-        try!(File::create(&path.join("src/lib.rs")).write_str("\
+        try!(write_new(&path.join("src/lib.rs"), "\
 #[test]
 fn it_works() {
 }
@@ -106,13 +141,21 @@ fn it_works() {
     Ok(())
 }
 
-fn discover_author() -> CargoResult<(String, Option<String>)> {
+/// Each half of the author is looked up on its own, first in the `cargo-new`
+/// configuration, then in `$CARGO_NAME`/`$CARGO_EMAIL`, then in git's
+/// configuration. The name finally falls back to the current user.
+fn discover_author(cfg: &CargoNewConfig)
+                   -> CargoResult<(String, Option<String>)> {
     let git_config = Config::open_default().ok();
     let git_config = git_config.as_ref();
-    let name = git_config.and_then(|g| g.get_str("user.name").ok())
-                         .map(|s| s.to_string())
-                         .or_else(|| os::getenv("USER"))      // unix
-                         .or_else(|| os::getenv("USERNAME")); // windows
+    let name = cfg.name.clone()
+                  .or_else(|| os::getenv("CARGO_NAME"))
+                  .or_else(|| {
+                      git_config.and_then(|g| g.get_str("user.name").ok())
+                                .map(|s| s.to_string())
+                  })
+                  .or_else(|| os::getenv("USER"))      // unix
+                  .or_else(|| os::getenv("USERNAME")); // windows
     let name = match name {
         Some(name) => name,
         None => {
@@ -121,7 +164,12 @@ fn discover_author() -> CargoResult<(String, Option<String>)> {
                                       user, please set ${}", username_var)))
         }
     };
-    let email = git_config.and_then(|g| g.get_str("user.email").ok());
+    let email = cfg.email.clone()
+                   .or_else(|| os::getenv("CARGO_EMAIL"))
+                   .or_else(|| {
+                       git_config.and_then(|g| g.get_str("user.email").ok())
+                                 .map(|s| s.to_string())
+                   });
 
     let name = name.as_slice().trim().to_string();
     let email = email.map(|s| s.as_slice().trim().to_string());
@@ -7,9 +7,10 @@ use flate2::{GzBuilder, BestCompression};
 use flate2::reader::GzDecoder;
 
 use core::source::{Source, SourceId};
-use core::{Package, MultiShell};
+use core::{Package, MultiShell, Dependency};
 use sources::PathSource;
 use util::{CargoResult, human, internal, ChainError, Require};
+use util::toml::{project_layout, to_normalized_manifest};
 use ops;
 
 struct Bomb { path: Option<Path> }
@@ -38,7 +39,19 @@ pub fn package(manifest_path: &Path,
     let mut bomb = Bomb { path: Some(dst.clone()) };
 
     try!(shell.status("Packaging", pkg.get_package_id().to_string()));
-    try!(tar(&pkg, &src, shell, &dst).chain_error(|| {
+    let stripped = pkg.get_dependencies().iter().filter(|d| is_stripped(*d))
+                      .map(|d| format!("`{}`", d.get_name()))
+                      .collect::<Vec<String>>();
+    if stripped.len() > 0 {
+        try!(shell.status("Stripping", format!("dev-dependencies without a \
+                                                version: {}",
+                                               stripped.connect(", "))));
+    }
+    let (files, warnings) = try!(src.list_package_files(&pkg));
+    for warning in warnings.iter() {
+        try!(shell.warn(warning));
+    }
+    try!(tar(&pkg, files, shell, &dst).chain_error(|| {
         human("failed to prepare local package for uploading")
     }));
     if verify {
@@ -49,7 +62,34 @@ pub fn package(manifest_path: &Path,
     Ok(bomb.path.take().unwrap())
 }
 
-fn tar(pkg: &Package, src: &PathSource, shell: &mut MultiShell,
+/// Returns whether `dep` is left out of the package uploaded to the registry.
+///
+/// A dev-dependency on a path without a version can't be downloaded by anyone
+/// using the package, but it also isn't needed to build it, so it's dropped
+/// rather than rejected.
+pub fn is_stripped(dep: &Dependency) -> bool {
+    !dep.is_transitive() && dep.get_source_id().is_path() &&
+        dep.get_specified_req().is_none()
+}
+
+/// Writes the normalized manifest of `pkg`, which is shipped in the tarball
+/// in place of the original, next to `dst` and returns where it was written.
+fn write_normalized_manifest(pkg: &Package, dst: &Path) -> CargoResult<Path> {
+    let contents = try!(File::open(pkg.get_manifest_path()).read_to_end());
+    let summary = pkg.get_summary().clone().filter_dependencies(|d| {
+        !is_stripped(d)
+    });
+    let mut manifest = pkg.get_manifest().clone();
+    manifest.set_summary(summary);
+    let layout = project_layout(&pkg.get_root());
+    let normalized = try!(to_normalized_manifest(contents.as_slice(),
+                                                 &manifest, &layout));
+    let path = dst.with_extension("toml");
+    try!(File::create(&path).write_str(normalized.as_slice()));
+    Ok(path)
+}
+
+fn tar(pkg: &Package, src_files: Vec<Path>, shell: &mut MultiShell,
        dst: &Path) -> CargoResult<()> {
 
     if dst.exists() {
@@ -68,13 +108,25 @@ fn tar(pkg: &Package, src: &PathSource, shell: &mut MultiShell,
     // Put all package files into a compressed archive
     let ar = Archive::new(encoder);
     let root = pkg.get_manifest_path().dir_path();
-    for file in try!(src.list_files(pkg)).iter() {
-        if file == dst { continue }
+    // The manifest is replaced by its normalized form, and the original is
+    // kept alongside it.
+    let normalized = try!(write_normalized_manifest(pkg, dst));
+    let mut files = Vec::new();
+    for file in src_files.into_iter() {
+        if file == *dst { continue }
         let relative = file.path_relative_from(&root).unwrap();
         let relative = try!(relative.as_str().require(|| {
             human(format!("non-utf8 path in source directory: {}",
                           relative.display()))
-        }));
+        })).to_string();
+        if file == *pkg.get_manifest_path() {
+            files.push((format!("{}.orig", relative), file));
+            files.push((relative, normalized.clone()));
+        } else {
+            files.push((relative, file));
+        }
+    }
+    for &(ref relative, ref file) in files.iter() {
         let mut file = try!(File::open(file));
         try!(shell.verbose(|shell| {
             shell.status("Archiving", relative.as_slice())
@@ -86,6 +138,7 @@ fn tar(pkg: &Package, src: &PathSource, shell: &mut MultiShell,
         }));
     }
     try!(ar.finish());
+    try!(fs::unlink(&normalized));
     Ok(())
 }
 
@@ -96,6 +149,7 @@ fn run_verify(pkg: &Package, shell: &mut MultiShell, tar: &Path)
     let f = try!(GzDecoder::new(try!(File::open(tar))));
     let dst = pkg.get_root().join(format!("target/package/{}-{}",
                                           pkg.get_name(), pkg.get_version()));
+    try!(ops::mark_build_dir(&pkg.get_absolute_target_dir()));
     if dst.exists() {
         try!(fs::rmdir_recursive(&dst));
     }
@@ -103,20 +157,22 @@ fn run_verify(pkg: &Package, shell: &mut MultiShell, tar: &Path)
     try!(archive.unpack(&dst.dir_path()));
     let manifest_path = dst.join("Cargo.toml");
 
-    // When packages are uploaded to the registry, all path dependencies are
-    // implicitly converted to registry-based dependencies, so we rewrite those
-    // dependencies here.
-    let registry = try!(SourceId::for_central());
-    let new_summary = pkg.get_summary().clone().map_dependencies(|d| {
-        if !d.get_source_id().is_path() { return d }
-        d.source_id(registry.clone())
-    });
-    let mut new_manifest = pkg.get_manifest().clone();
-    new_manifest.set_summary(new_summary);
-    let new_pkg = Package::new(new_manifest, &manifest_path,
-                               pkg.get_package_id().get_source_id());
+    // The package is built from the normalized manifest in the tarball, in
+    // which all dependencies already refer to the registry.
+    let source_id = try!(SourceId::for_path(&dst));
+    let (new_pkg, _) = try!(ops::read_package(&manifest_path, &source_id));
+    let original = pkg.get_manifest().get_warnings();
+    let new_warnings = new_pkg.get_manifest().get_warnings().iter()
+                              .filter(|w| !original.contains(*w))
+                              .map(|w| w.as_slice())
+                              .collect::<Vec<&str>>();
+    if new_warnings.len() > 0 {
+        return Err(human(format!("the normalized manifest has warnings \
+                                  which the original doesn't:\n{}",
+                                 new_warnings.connect("\n"))))
+    }
 
-    // Now that we've rewritten all our path dependencies, compile it!
+    // Now that we've checked the manifest, compile it!
     try!(ops::compile_pkg(&new_pkg, &mut ops::CompileOptions {
         env: "compile",
         shell: shell,
@@ -125,7 +181,17 @@ fn run_verify(pkg: &Package, shell: &mut MultiShell, tar: &Path)
         dev_deps: false,
         features: [],
         no_default_features: false,
+        all_features: false,
         spec: None,
+        keep_going: false,
+        timings: false,
+        examples: ops::AllExamples,
+        all_targets: false,
+        skip_targets: &[],
+        extra_examples: &[],
+        only_targets: None,
+        target_rustc_args: None,
+        fs: None,
     }));
 
     Ok(())
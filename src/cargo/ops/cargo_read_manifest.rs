@@ -1,15 +1,28 @@
 use std::collections::HashSet;
 use std::io::{mod, File, fs};
 use std::io::fs::PathExtensions;
+use glob::Pattern;
 
-use core::{Package,Manifest,SourceId};
-use util::{mod, CargoResult, human, FromError};
-use util::important_paths::find_project_manifest_exact;
-use util::toml::{Layout, project_layout};
+use core::{Package,Manifest,SourceId,Dependency};
+use ops;
+use util::{mod, CargoResult, CargoError, human, human_of_kind, FromError};
+use util::InvalidManifest;
+use util::important_paths::{find_project_manifest_exact, misnamed_manifest};
+use util::toml::{Layout, project_layout, find_workspace};
 
 pub fn read_manifest(contents: &[u8], layout: Layout, source_id: &SourceId)
     -> CargoResult<(Manifest, Vec<Path>)> {
-    util::toml::to_manifest(contents, source_id, layout).map_err(human)
+    util::toml::to_manifest(contents, source_id, layout).map_err(humanize)
+}
+
+// Manifest errors are always shown to the user, but keep their kind for the
+// exit code. Those which are already human keep their chain of causes.
+fn humanize(err: Box<CargoError + Send>) -> Box<CargoError + Send> {
+    if err.is_human() { return err }
+    match err.kind() {
+        Some(kind) => human_of_kind(kind, err),
+        None => human(err),
+    }
 }
 
 pub fn read_package(path: &Path, source_id: &SourceId)
@@ -19,19 +32,58 @@ pub fn read_package(path: &Path, source_id: &SourceId)
     let data = try!(file.read_to_end());
 
     let layout = project_layout(&path.dir_path());
-    let (manifest, nested) =
+    let (mut manifest, nested) =
         try!(read_manifest(data.as_slice(), layout, source_id));
 
+    // Only reachable on case-insensitive filesystems, where the misnamed
+    // file was opened as `Cargo.toml`.
+    match misnamed_manifest(&path.dir_path(), "Cargo.toml") {
+        Some(name) => {
+            manifest.add_warning(format!("the manifest of this package is \
+                                          named `{}`, rename it to \
+                                          `Cargo.toml` so that it can be \
+                                          found on case-sensitive \
+                                          filesystems", name));
+        }
+        None => {}
+    }
+
     Ok((Package::new(manifest, path, source_id), nested))
 }
 
+/// Reads a package from manifest `contents` which need not be on disk, as if
+/// they were the `Cargo.toml` of the package rooted at `root`. Errors refer
+/// to the contents as `name`.
+pub fn read_package_from(contents: &[u8], name: &str, root: &Path,
+                         source_id: &SourceId) -> CargoResult<Package> {
+    log!(5, "read_package_from; name={}; root={}", name, root.display());
+    let layout = project_layout(root);
+    let (manifest, _) = try!(util::toml::to_named_manifest(contents,
+                                                           &Path::new(name),
+                                                           source_id, layout)
+                                        .map_err(humanize));
+    Ok(Package::new(manifest, &root.join("Cargo.toml"), source_id))
+}
+
 pub fn read_packages(path: &Path,
                      source_id: &SourceId) -> CargoResult<Vec<Package>> {
     let mut all_packages = Vec::new();
     let mut visited = HashSet::<Path>::new();
+    let mut broken = Vec::new();
 
     log!(5, "looking for root package: {}, source_id={}", path.display(), source_id);
-    try!(process_possible_package(path, &mut all_packages, source_id, &mut visited));
+    try!(process_possible_package(path, &mut all_packages, source_id,
+                                  &mut visited, &mut broken));
+
+    // Subtrees excluded by the root package are never searched for packages,
+    // they may contain unrelated projects with manifests we can't read.
+    let excludes = all_packages.iter().find(|p| {
+        p.get_manifest_path().dir_path() == *path
+    }).map(|p| {
+        p.get_manifest().get_exclude().iter().map(|e| {
+            Pattern::new(e.as_slice())
+        }).collect::<Vec<Pattern>>()
+    }).unwrap_or(Vec::new());
 
     try!(walk(path, true, |root, dir| {
         log!(5, "looking for child package: {}", dir.display());
@@ -39,19 +91,137 @@ pub fn read_packages(path: &Path,
         if root { return Ok(true) }
         if dir.filename_str() == Some(".git") { return Ok(false); }
         if dir.join(".git").exists() { return Ok(false); }
+        if is_build_dir(dir, all_packages.as_slice()) { return Ok(false); }
+        let relative = dir.path_relative_from(path).unwrap();
+        if excludes.iter().any(|p| p.matches_path(&relative)) {
+            return Ok(false);
+        }
         try!(process_possible_package(dir, &mut all_packages, source_id,
-                                      &mut visited));
+                                      &mut visited, &mut broken));
         Ok(true)
     }));
 
+    if broken.len() > 0 {
+        return Err(broken_error(path, broken));
+    }
+
     if all_packages.is_empty() {
         Err(human(format!("Could not find Cargo.toml in `{}`", path.display())))
     } else {
-        log!(5, "all packages: {}", all_packages);
-        Ok(all_packages)
+        if source_id.is_path() {
+            try!(duplicate_ids(path, all_packages.as_slice()));
+        }
+        let warnings = duplicate_names(path, all_packages.as_slice());
+        let mut packages = Vec::new();
+        push_all(&mut packages, all_packages);
+        match packages.iter_mut().find(|p| {
+            p.get_manifest_path().dir_path() == *path
+        }) {
+            Some(pkg) => {
+                for warning in warnings.into_iter() { pkg.add_warning(warning) }
+            }
+            None => {}
+        }
+        log!(5, "all packages: {}", packages);
+        Ok(packages)
+    }
+}
+
+/// A path dependency whose manifest could not be read, along with the names
+/// of the packages leading to it from the package which was being read.
+struct Broken {
+    chain: Vec<String>,
+    dir: Path,
+    error: Box<CargoError + Send>,
+}
+
+// All broken path dependencies are reported at once, each as
+// `root -> a -> b: b/Cargo.toml: <error>`. The exit code is the one of the
+// first of them.
+fn broken_error(root: &Path, broken: Vec<Broken>) -> Box<CargoError + Send> {
+    let mut msg = String::from_str("failed to read the manifests of path \
+                                    dependencies:");
+    let kind = broken[0].error.kind();
+    for b in broken.iter() {
+        let manifest = b.dir.join("Cargo.toml");
+        let manifest = manifest.path_relative_from(root).unwrap_or(manifest);
+        let name = b.dir.filename_str().unwrap_or("");
+        let error = b.error.to_string().replace("\n", "\n    ");
+        msg.push_str(format!("\n  {} -> {}: {}: {}", b.chain.connect(" -> "),
+                             name, manifest.display(), error).as_slice());
+    }
+    match kind {
+        Some(kind) => human_of_kind(kind, msg),
+        None => human(msg),
     }
 }
 
+// Packages of a path source are identified by their name and version alone,
+// so two directories declaring both the same can't be told apart and which of
+// them gets used would depend on the order they were found in. Packages of
+// other sources are identified by their source as well.
+fn duplicate_ids(root: &Path, packages: &[Package]) -> CargoResult<()> {
+    let mut duplicates = Vec::new();
+    for (i, a) in packages.iter().enumerate() {
+        if packages.slice_to(i).iter().any(|b| {
+            a.get_package_id() == b.get_package_id()
+        }) {
+            continue
+        }
+        let mut manifests = packages.slice_from(i).iter().filter(|b| {
+            a.get_package_id() == b.get_package_id()
+        }).map(|b| {
+            let manifest = b.get_manifest_path();
+            manifest.path_relative_from(root).unwrap_or(manifest.clone())
+                    .display().to_string()
+        }).collect::<Vec<String>>();
+        if manifests.len() < 2 { continue }
+        manifests.sort();
+        duplicates.push((format!("{} v{}", a.get_name(), a.get_version()),
+                         manifests));
+    }
+    if duplicates.is_empty() { return Ok(()) }
+
+    duplicates.sort();
+    let mut msg = String::new();
+    for &(ref id, ref manifests) in duplicates.iter() {
+        msg.push_str(format!("package `{}` is declared by more than one \
+                              manifest:\n", id).as_slice());
+        for manifest in manifests.iter() {
+            msg.push_str(format!("  {}\n", manifest).as_slice());
+        }
+    }
+    msg.push_str("bump the version of one of them, or remove the stale copy");
+    Err(human_of_kind(InvalidManifest, msg))
+}
+
+// Path dependencies are identified by their name and version, so of two
+// packages with the same name at different paths only one will ever be used.
+fn duplicate_names(root: &Path, packages: &[Package]) -> Vec<String> {
+    let relative = |p: &Package| {
+        let dir = p.get_root();
+        dir.path_relative_from(root).unwrap_or(dir).display().to_string()
+    };
+    let mut warnings = Vec::new();
+    for (i, a) in packages.iter().enumerate() {
+        for b in packages.slice_to(i).iter() {
+            if a.get_name() != b.get_name() { continue }
+            warnings.push(format!("package `{}` is found at both `{}` and \
+                                   `{}`, only one of them will be used",
+                                  a.get_name(), relative(b), relative(a)));
+        }
+    }
+    warnings
+}
+
+// Build output is never searched for packages, verifying a package for
+// instance extracts its manifest in there. That's the target directory of any
+// package found so far, or a directory which Cargo created and marked.
+fn is_build_dir(dir: &Path, packages: &[Package]) -> bool {
+    packages.iter().any(|p| p.get_absolute_target_dir() == *dir) ||
+        dir.join(ops::BUILD_DIR_MARKER).exists()
+}
+
 fn walk(path: &Path, is_root: bool,
         callback: |bool, &Path| -> CargoResult<bool>) -> CargoResult<()> {
     if path.is_dir() {
@@ -79,12 +249,14 @@ fn walk(path: &Path, is_root: bool,
 fn process_possible_package(dir: &Path,
                             all_packages: &mut Vec<Package>,
                             source_id: &SourceId,
-                            visited: &mut HashSet<Path>) -> CargoResult<()> {
+                            visited: &mut HashSet<Path>,
+                            broken: &mut Vec<Broken>) -> CargoResult<()> {
 
     if !has_manifest(dir) { return Ok(()); }
 
-    let packages = try!(read_nested_packages(dir, source_id, visited));
-    push_all(all_packages, packages);
+    let packages = try!(read_nested_packages(dir, source_id, visited,
+                                             &mut Vec::new(), broken));
+    all_packages.extend(packages.into_iter());
 
     Ok(())
 }
@@ -93,28 +265,65 @@ fn has_manifest(path: &Path) -> bool {
     find_project_manifest_exact(path, "Cargo.toml").is_ok()
 }
 
+// Every manifest reachable through `path=` dependencies is read up front, so
+// a broken one is found even if resolution would never get to it. `chain`
+// holds the names of the packages leading to `path`, and the path
+// dependencies which can't be read are collected in `broken`.
 fn read_nested_packages(path: &Path, source_id: &SourceId,
-                 visited: &mut HashSet<Path>) -> CargoResult<Vec<Package>> {
+                        visited: &mut HashSet<Path>,
+                        chain: &mut Vec<String>,
+                        broken: &mut Vec<Broken>)
+                        -> CargoResult<Vec<Package>> {
     if !visited.insert(path.clone()) { return Ok(Vec::new()) }
 
     let manifest = try!(find_project_manifest_exact(path, "Cargo.toml"));
 
-    let (pkg, nested) = try!(read_package(&manifest, source_id));
-    let mut ret = vec![pkg];
+    let (mut pkg, nested) = try!(read_package(&manifest, source_id));
+    chain.push(pkg.get_name().to_string());
+    let mut found = Vec::new();
 
     // Registry sources are not allowed to have `path=` dependencies because
     // they're all translated to actual registry dependencies.
     if !source_id.is_registry() {
-        for p in nested.iter() {
-            ret.extend(try!(read_nested_packages(&path.join(p),
-                                            source_id,
-                                            visited)).into_iter());
+        for dir in nested.iter() {
+            let packages = read_nested_packages(dir, source_id, visited,
+                                                chain, broken);
+            match packages {
+                Ok(packages) => found.extend(packages.into_iter()),
+                Err(error) => broken.push(Broken {
+                    chain: chain.clone(),
+                    dir: dir.clone(),
+                    error: error,
+                }),
+            }
         }
     }
+    chain.pop();
 
+    // The nested paths of a virtual workspace root are the directories of its
+    // members, which it depends on once their names are known.
+    if pkg.is_workspace_root() {
+        let members = try!(found.iter().filter(|p| {
+            nested.iter().any(|dir| *dir == p.get_root())
+        }).map(|p| {
+            Dependency::parse(p.get_name(), None, source_id)
+        }).collect::<CargoResult<Vec<Dependency>>>());
+        pkg.set_workspace_members(members);
+    }
+
+    let mut ret = vec![pkg];
+    ret.extend(found.into_iter());
     Ok(ret)
 }
 
+/// Returns the manifest of the virtual workspace root which lists the package
+/// of `manifest_path` among its members, if any. The parent directories of
+/// the package are searched up to the root of the filesystem.
+pub fn find_workspace_root(manifest_path: &Path) -> CargoResult<Option<Path>> {
+    let workspace = try!(find_workspace(&manifest_path.dir_path()));
+    Ok(workspace.map(|workspace| workspace.manifest))
+}
+
 fn push_all(set: &mut Vec<Package>, packages: Vec<Package>) {
     for package in packages.into_iter() {
         if set.contains(&package) { continue; }
@@ -1,8 +1,9 @@
 use std::os;
 
 use ops;
-use util::{CargoResult, human, process, ProcessError, Require};
-use core::manifest::{TargetKind, LibTarget, BinTarget, ExampleTarget};
+use util::{CargoResult, human, internal, process, ProcessError, Require};
+use core::Package;
+use core::manifest::{Target, TargetKind, LibTarget, BinTarget, ExampleTarget};
 use core::source::Source;
 use sources::PathSource;
 
@@ -15,8 +16,53 @@ pub fn run(manifest_path: &Path,
     try!(src.update());
     let root = try!(src.get_root_package());
     let env = options.env;
-    let mut bins = root.get_manifest().get_targets().iter().filter(|a| {
-        let matches_kind = match target_kind {
+
+    // The package selected with `--package` is only known once it's been
+    // built, so its binary is looked up afterwards.
+    let bin = match options.spec {
+        Some(..) => None,
+        None => Some(try!(find_bin(&root, &target_kind, &name, env)).clone()),
+    };
+
+    let compile = try!(ops::compile(manifest_path, options));
+    let bin = match bin {
+        Some(bin) => bin,
+        None => try!(find_bin(&compile.package, &target_kind, &name,
+                              env)).clone(),
+    };
+
+    // The root output directory already accounts for the target triple (which
+    // may have come from the configuration) as well as the profile's dest.
+    // Binaries of other packages aren't linked there, so they're run from
+    // wherever they were built.
+    let exe = if options.spec.is_some() {
+        try!(compile.artifacts.iter().find(|&&(ref id, ref t, _)| {
+            id == compile.package.get_package_id() && *t == bin
+        }).map(|&(_, _, ref path)| path.clone()).require(|| {
+            internal(format!("no artifact was built for `{}`", bin.get_name()))
+        }))
+    } else if bin.is_example() {
+        compile.root_output.join("examples").join(bin.get_name())
+    } else {
+        compile.root_output.join(bin.get_name())
+    };
+    let exe = match exe.path_relative_from(&os::getcwd()) {
+        Some(path) => path,
+        None => exe,
+    };
+    let process = try!(compile.process(exe, &compile.package))
+                              .args(args).cwd(os::getcwd());
+
+    try!(options.shell.status("Running", process.to_string()));
+    Ok(process.exec().err())
+}
+
+/// Returns the only target of `pkg` of kind `target_kind` which is built in
+/// `env`, or the one called `name`.
+fn find_bin<'a>(pkg: &'a Package, target_kind: &TargetKind,
+                name: &Option<String>, env: &str) -> CargoResult<&'a Target> {
+    let mut bins = pkg.get_manifest().get_targets().iter().filter(|a| {
+        let matches_kind = match *target_kind {
             BinTarget => a.is_bin(),
             ExampleTarget => a.is_example(),
             LibTarget(_) => false,
@@ -34,24 +80,5 @@ pub fn run(manifest_path: &Path,
                    Use the `--name` option to specify which one to run")),
         None => {}
     }
-
-    let compile = try!(ops::compile(manifest_path, options));
-    let dst = manifest_path.dir_path().join("target");
-    let dst = match options.target {
-        Some(target) => dst.join(target),
-        None => if bin.is_example() { dst.join("examples") } else { dst },
-    };
-    let exe = match bin.get_profile().get_dest() {
-        Some(s) => dst.join(s).join(bin.get_name()),
-        None => dst.join(bin.get_name()),
-    };
-    let exe = match exe.path_relative_from(&os::getcwd()) {
-        Some(path) => path,
-        None => exe,
-    };
-    let process = try!(compile.process(exe, &root))
-                              .args(args).cwd(os::getcwd());
-
-    try!(options.shell.status("Running", process.to_string()));
-    Ok(process.exec().err())
+    Ok(bin)
 }
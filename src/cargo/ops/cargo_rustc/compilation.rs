@@ -1,10 +1,12 @@
-use std::collections::HashMap;
+use std::collections::{HashMap, TreeSet};
 use std::dynamic_lib::DynamicLibrary;
 use semver::Version;
 
-use core::{PackageId, Package};
+use core::{PackageId, Package, Target};
 use util::{mod, CargoResult};
 
+use super::Timings;
+
 /// A structure returning the result of a compilation.
 pub struct Compilation {
     /// All libraries which were built for a package.
@@ -13,12 +15,19 @@ pub struct Compilation {
     /// on.
     pub libraries: HashMap<PackageId, Vec<Path>>,
 
-    /// An array of all tests created during this compilation.
-    pub tests: Vec<(String, Path)>,
+    /// An array of all tests created during this compilation, along with the
+    /// package and target each one was built from.
+    pub tests: Vec<(Package, Target, Path)>,
 
     /// An array of all binaries created.
     pub binaries: Vec<Path>,
 
+    /// Every file produced by the compilation, along with the package and
+    /// target it was built from, at the location it should be used from.
+    ///
+    /// Build scripts and documentation are not included.
+    pub artifacts: Vec<(PackageId, Target, Path)>,
+
     /// All directires for the output of native build commands.
     ///
     /// This is currently used to drive some entries which are added to the
@@ -26,18 +35,39 @@ pub struct Compilation {
     // TODO: deprecated, remove
     pub native_dirs: HashMap<PackageId, Path>,
 
+    /// Library search paths which build scripts asked to be passed to rustc
+    /// with `-L`, as dynamic libraries built there are needed at runtime too.
+    pub library_paths: Vec<Path>,
+
     /// Root output directory (for the local package's artifacts)
     pub root_output: Path,
 
     /// Output directory for rust dependencies
     pub deps_output: Path,
 
+    /// Features enabled for each of the packages being built.
+    ///
+    /// This is used to pass the same `--cfg` flags to rustdoc tests as were
+    /// used to compile the library.
+    pub features: HashMap<PackageId, TreeSet<String>>,
+
+    /// Cfgs emitted by the build script of each of the packages being built,
+    /// passed to rustdoc tests like the features are.
+    pub cfgs: HashMap<PackageId, Vec<String>>,
+
     /// Extra environment variables that were passed to compilations and should
     /// be passed to future invocations of programs.
     pub extra_env: HashMap<String, Option<String>>,
 
     /// Top-level package that was compiled
     pub package: Package,
+
+    /// Every package which was built, rather than only compiled as a
+    /// dependency: the top-level package, or the members of a workspace.
+    pub packages: Vec<Package>,
+
+    /// How long each unit took, if `--timings` was passed.
+    pub timings: Timings,
 }
 
 impl Compilation {
@@ -45,12 +75,18 @@ impl Compilation {
         Compilation {
             libraries: HashMap::new(),
             native_dirs: HashMap::new(),  // TODO: deprecated, remove
+            library_paths: Vec::new(),
             root_output: Path::new("/"),
             deps_output: Path::new("/"),
+            features: HashMap::new(),
+            cfgs: HashMap::new(),
             tests: Vec::new(),
             binaries: Vec::new(),
+            artifacts: Vec::new(),
             extra_env: HashMap::new(),
             package: pkg.clone(),
+            packages: vec![pkg.clone()],
+            timings: Timings::new(),
         }
     }
 
@@ -59,14 +95,18 @@ impl Compilation {
     ///
     /// The package argument is also used to configure environment variables as
     /// well as the working directory of the child process.
+    ///
+    /// The output directories are put in front of the dynamic library search
+    /// path so that dylib dependencies are found when the program runs.
     pub fn process<T: ToCStr>(&self, cmd: T, pkg: &Package)
                               -> CargoResult<util::ProcessBuilder> {
-        let mut search_path = DynamicLibrary::search_path();
+        let mut search_path = self.library_paths.clone();
+        search_path.push(self.root_output.clone());
+        search_path.push(self.deps_output.clone());
         for dir in self.native_dirs.values() {
             search_path.push(dir.clone());
         }
-        search_path.push(self.root_output.clone());
-        search_path.push(self.deps_output.clone());
+        search_path.push_all(DynamicLibrary::search_path().as_slice());
         let search_path = try!(util::join_paths(search_path.as_slice(),
                                                 DynamicLibrary::envvar()));
         let mut cmd = util::process(cmd).env(DynamicLibrary::envvar(),
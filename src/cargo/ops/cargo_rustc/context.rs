@@ -9,8 +9,11 @@ use util::human;
 
 use super::{Kind, KindHost, KindTarget, Compilation, BuildConfig};
 use super::TargetConfig;
-use super::layout::{Layout, LayoutProxy};
+use super::layout::{mod, Layout, LayoutProxy};
 use super::custom_build::BuildState;
+use super::fingerprint::{FreshnessFs, RealFs};
+
+static REAL_FS: RealFs = RealFs;
 
 #[deriving(Show)]
 pub enum PlatformRequirement {
@@ -54,7 +57,7 @@ impl<'a, 'b: 'a> Context<'a, 'b> {
         } else {
             try!(Context::filename_parts(None))
         };
-        let target_triple = config.target().map(|s| s.to_string());
+        let target_triple = config.target_triple().map(|s| s.to_string());
         let target_triple = target_triple.unwrap_or(config.rustc_host().to_string());
         Ok(Context {
             target_triple: target_triple,
@@ -114,11 +117,18 @@ impl<'a, 'b: 'a> Context<'a, 'b> {
         Ok((dylib, exe_suffix.to_string()))
     }
 
-    /// Prepare this context, ensuring that all filesystem directories are in
-    /// place.
-    pub fn prepare(&mut self, pkg: &'a Package) -> CargoResult<()> {
+    /// Prepare this context for building `pkgs`, ensuring that all filesystem
+    /// directories are in place.
+    ///
+    /// The compilation's outputs are those of the first package.
+    pub fn prepare(&mut self, pkgs: &[&'a Package]) -> CargoResult<()> {
         let _p = profile::start("preparing layout");
+        let pkg = pkgs[0];
 
+        try!(layout::mark_build_dir(&pkg.get_absolute_target_dir()).chain_error(|| {
+            internal(format!("couldn't prepare build directories for `{}`",
+                             pkg.get_name()))
+        }));
         try!(self.host.prepare().chain_error(|| {
             internal(format!("couldn't prepare build directories for `{}`",
                              pkg.get_name()))
@@ -133,10 +143,12 @@ impl<'a, 'b: 'a> Context<'a, 'b> {
             None => {}
         }
 
-        let targets = pkg.get_targets().iter();
-        for target in targets.filter(|t| t.get_profile().is_compile()) {
-            self.build_requirements(pkg, target, PlatformTarget,
-                                    &mut HashSet::new());
+        for pkg in pkgs.iter() {
+            let targets = pkg.get_targets().iter();
+            for target in targets.filter(|t| t.get_profile().is_compile()) {
+                self.build_requirements(*pkg, target, PlatformTarget,
+                                        &mut HashSet::new());
+            }
         }
 
         self.compilation.extra_env.insert("NUM_JOBS".to_string(),
@@ -145,6 +157,14 @@ impl<'a, 'b: 'a> Context<'a, 'b> {
                 self.layout(pkg, KindTarget).proxy().dest().clone();
         self.compilation.deps_output =
                 self.layout(pkg, KindTarget).proxy().deps().clone();
+        self.compilation.packages = pkgs.iter().map(|p| (*p).clone()).collect();
+        for pkg in pkgs.iter() {
+            let features = self.features(pkg.get_package_id());
+            self.compilation.features.insert(pkg.get_package_id().clone(),
+                                             features.iter().map(|f| {
+                f.to_string()
+            }).collect());
+        }
 
         return Ok(());
     }
@@ -187,14 +207,35 @@ impl<'a, 'b: 'a> Context<'a, 'b> {
             .map(|a| *a).unwrap_or(default)
     }
 
+    /// Returns whether units for the host and for the target are distinct.
+    ///
+    /// This is only the case when the target triple differs from the host's.
+    /// Otherwise (including an explicit `--target` naming the host) a unit
+    /// needed by both is compiled once, and the host shares the target's
+    /// artifacts.
+    pub fn is_cross(&self) -> bool {
+        self.target_triple.as_slice() != self.config.rustc_host()
+    }
+
+    /// Returns whether `pkg` is being built for its own sake rather than as a
+    /// dependency: it's the root package, or a member of the workspace whose
+    /// virtual root is the root package.
+    pub fn is_primary(&self, pkg: &Package) -> bool {
+        let id = pkg.get_package_id();
+        if id == self.resolve.root() { return true }
+        let root = self.get_package(self.resolve.root());
+        root.is_workspace_root() && root.get_dependencies().iter().any(|d| {
+            d.matches_id(id)
+        })
+    }
+
     /// Returns the appropriate directory layout for either a plugin or not.
     pub fn layout(&self, pkg: &Package, kind: Kind) -> LayoutProxy {
-        let primary = pkg.get_package_id() == self.resolve.root();
+        let primary = self.is_primary(pkg);
         match kind {
-            KindHost => LayoutProxy::new(&self.host, primary),
-            KindTarget =>  LayoutProxy::new(self.target.as_ref()
-                                                .unwrap_or(&self.host),
-                                            primary),
+            KindHost if self.is_cross() => LayoutProxy::new(&self.host, primary),
+            _ => LayoutProxy::new(self.target.as_ref().unwrap_or(&self.host),
+                                  primary),
         }
     }
 
@@ -222,8 +263,85 @@ impl<'a, 'b: 'a> Context<'a, 'b> {
 
     /// Return the exact filename of the target.
     pub fn target_filenames(&self, target: &Target) -> CargoResult<Vec<String>> {
+        self.filenames(target, target.file_stem().as_slice())
+    }
+
+    /// Return the filename of a library target which dependents are linked
+    /// against with `--extern`.
+    ///
+    /// A library producing several crate types is linked as an rlib, unless
+    /// it's a plugin in which case the dylib is needed to load it. Static
+    /// libraries are never passed to rustc.
+    pub fn target_link_filenames(&self, target: &Target)
+                                 -> CargoResult<Vec<String>> {
         let stem = target.file_stem();
+        let plugin = target.get_profile().is_for_host();
+        if target.is_rlib() && !(plugin && target.is_dylib()) {
+            Ok(vec![format!("lib{}.rlib", stem)])
+        } else if target.is_dylib() {
+            let kind = if plugin {KindHost} else {KindTarget};
+            let (prefix, suffix) = try!(self.dylib(kind));
+            Ok(vec![format!("{}{}{}", prefix, stem, suffix)])
+        } else {
+            Ok(Vec::new())
+        }
+    }
+
+    /// Return the filenames under which the artifacts of a target are made
+    /// available at the top of the output directory.
+    ///
+    /// This only applies to the libraries and binaries of the top-level
+    /// package, which are compiled into `deps` and then linked to their final
+    /// location. Libraries lose their metadata hash in the process.
+    pub fn target_final_filenames(&self, target: &Target)
+                                  -> CargoResult<Vec<String>> {
+        let profile = target.get_profile();
+        if !(target.is_lib() || target.is_bin()) || target.is_example() ||
+           profile.is_test() || profile.is_doc() || profile.is_custom_build() {
+            return Ok(Vec::new())
+        }
+        self.filenames(target, target.get_name())
+    }
 
+    /// Returns the (old, new) directories rustc places a target's output in.
+    ///
+    /// Libraries and binaries always go to `deps` (libraries with their
+    /// metadata hash in the file name), even for the top-level package. The
+    /// `old` directory is where the previous build's artifacts live until they
+    /// are known to be fresh.
+    pub fn out_dirs(&self, pkg: &Package, target: &Target,
+                    kind: Kind) -> (Path, Path) {
+        let layout = self.layout(pkg, kind);
+        let profile = target.get_profile();
+        if profile.is_custom_build() {
+            (layout.old_build(pkg), layout.build(pkg))
+        } else if target.is_example() {
+            (layout.old_examples().clone(), layout.examples().clone())
+        } else if (target.is_lib() || target.is_bin()) && !profile.is_test() {
+            (layout.proxy().old_deps().clone(), layout.deps().clone())
+        } else {
+            (layout.old_root().clone(), layout.root().clone())
+        }
+    }
+
+    /// Returns the user-facing paths of a target's artifacts.
+    ///
+    /// For libraries of the top-level package these are the unhashed copies at
+    /// the top of the output directory, otherwise they are the artifacts as
+    /// produced by rustc.
+    pub fn target_final_paths(&self, pkg: &Package, target: &Target,
+                              kind: Kind) -> CargoResult<Vec<Path>> {
+        let finals = try!(self.target_final_filenames(target));
+        if self.is_primary(pkg) && finals.len() > 0 {
+            let root = self.layout(pkg, kind).root().clone();
+            return Ok(finals.iter().map(|f| root.join(f.as_slice())).collect())
+        }
+        let (_, dir) = self.out_dirs(pkg, target, kind);
+        let names = try!(self.target_filenames(target));
+        Ok(names.iter().map(|f| dir.join(f.as_slice())).collect())
+    }
+
+    fn filenames(&self, target: &Target, stem: &str) -> CargoResult<Vec<String>> {
         let mut ret = Vec::new();
         if target.is_example() || target.is_bin() ||
            target.get_profile().is_test() {
@@ -280,6 +398,29 @@ impl<'a, 'b: 'a> Context<'a, 'b> {
             .expect("Should have found package")
     }
 
+    /// Returns the extra arguments `cargo rustc` asked to pass when compiling
+    /// `target` of `pkg`, if any.
+    pub fn rustc_args_for(&self, pkg: &Package, target: &Target)
+                          -> Option<&[String]> {
+        match self.build_config.target_rustc_args {
+            Some((ref t, ref args)) if t == target && self.is_primary(pkg) => {
+                Some(args.as_slice())
+            }
+            _ => None,
+        }
+    }
+
+    /// Returns the features enabled for a package, sorted so the command lines
+    /// and fingerprints built from them don't depend on hash ordering.
+    pub fn features(&self, id: &PackageId) -> Vec<&'a str> {
+        let mut features = match self.resolve.features(id) {
+            Some(features) => features.iter().map(|f| f.as_slice()).collect(),
+            None => Vec::new(),
+        };
+        features.sort();
+        features
+    }
+
     pub fn env(&self) -> &str {
         // The "doc-all" environment just means to document everything (see
         // below), but we want to canonicalize that the the "doc" profile
@@ -290,6 +431,8 @@ impl<'a, 'b: 'a> Context<'a, 'b> {
     pub fn is_relevant_target(&self, target: &Target) -> bool {
         target.is_lib() && match self.env {
             "doc" | "test" => target.get_profile().is_compile(),
+            // `cargo test --release` links the tests to release builds
+            "release-test" => target.get_profile().get_env() == "release",
             // doc-all == document everything, so look for doc targets and
             //            compile targets in dependencies
             "doc-all" => target.get_profile().is_compile() ||
@@ -300,6 +443,15 @@ impl<'a, 'b: 'a> Context<'a, 'b> {
         }
     }
 
+    /// Returns the filesystem operations deciding whether build scripts are
+    /// fresh, those the build was configured with if any.
+    pub fn freshness_fs(&self) -> &FreshnessFs {
+        match self.build_config.fs {
+            Some(ref fs) => &***fs,
+            None => &REAL_FS as &FreshnessFs,
+        }
+    }
+
     /// Get the user-specified linker for a particular host or target
     pub fn linker(&self, kind: Kind) -> Option<&str> {
         self.target_config(kind).linker.as_ref().map(|s| s.as_slice())
@@ -310,6 +462,28 @@ impl<'a, 'b: 'a> Context<'a, 'b> {
         self.target_config(kind).ar.as_ref().map(|s| s.as_slice())
     }
 
+    /// Get the `-L` and `-l` arguments which `[target.$triple]` in the
+    /// configuration adds when compiling `target` of `pkg`. They only apply to
+    /// the packages being built, not to their dependencies or build scripts.
+    pub fn config_link_args(&self, pkg: &Package, target: &Target,
+                            kind: Kind) -> Vec<String> {
+        let mut args = Vec::new();
+        if kind != KindTarget || !self.is_primary(pkg) ||
+           target.get_profile().is_custom_build() {
+            return args
+        }
+        let config = self.target_config(kind);
+        for path in config.link_paths.iter() {
+            args.push("-L".to_string());
+            args.push(path.display().to_string());
+        }
+        for lib in config.link_libs.iter() {
+            args.push("-l".to_string());
+            args.push(lib.clone());
+        }
+        args
+    }
+
     /// Get the target configuration for a particular host or target
     fn target_config(&self, kind: Kind) -> &TargetConfig {
         match kind {
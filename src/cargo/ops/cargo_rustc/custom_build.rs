@@ -1,13 +1,14 @@
 use std::collections::HashMap;
+use std::dynamic_lib::DynamicLibrary;
 use std::fmt;
 use std::io::fs::PathExtensions;
 use std::io::{fs, USER_RWX, File};
 use std::str;
 use std::sync::Mutex;
 
-use core::{Package, Target, PackageId, PackageSet};
+use core::{Package, Target, PackageId, PackageSet, Profile};
 use util::{CargoResult, CargoError, human};
-use util::{internal, ChainError, Require};
+use util::{internal, ChainError, Require, BuildFailure, join_paths};
 
 use super::job::Work;
 use super::{fingerprint, process, KindTarget, KindHost, Kind, Context};
@@ -20,22 +21,44 @@ pub struct BuildOutput {
     pub library_paths: Vec<Path>,
     /// Names and link kinds of libraries, suitable for the `-l` flag
     pub library_links: Vec<String>,
+    /// Names to pass to rustc and rustdoc with the `--cfg` flag
+    pub cfgs: Vec<String>,
     /// Metadata to pass to the immediate dependencies
     pub metadata: Vec<(String, String)>,
+    /// Files the script read, relative to the root of the package, which make
+    /// the script run again when they change and are listed as inputs of the
+    /// build in the dependency files Cargo emits
+    pub rerun_if_changed: Vec<Path>,
 }
 
 pub struct BuildState {
     pub outputs: Mutex<HashMap<(PackageId, Kind), BuildOutput>>,
 }
 
+/// Returns the profile that `pkg` is being built with for the current
+/// environment, falling back to the build script's own profile.
+pub fn active_profile<'a>(cx: &Context, pkg: &'a Package, build_script: &'a Target)
+                          -> &'a Profile {
+    pkg.get_targets().iter().map(|t| t.get_profile()).find(|p| {
+        !p.is_custom_build() && p.get_env() == cx.env()
+    }).unwrap_or(build_script.get_profile())
+}
+
 /// Prepares a `Work` that executes the target as a custom build script.
 pub fn prepare(pkg: &Package, target: &Target, kind: Kind, cx: &mut Context)
                -> CargoResult<(Work, Work, Freshness)> {
+    // Output shared between profiles isn't moved aside at the start of a
+    // build, its old location is where it already is.
+    let per_profile = pkg.get_manifest().is_build_per_profile();
     let (script_output, build_output, old_build_output) = {
         let target = cx.layout(pkg, kind);
-        (cx.layout(pkg, KindHost).build(pkg),
-         target.build_out(pkg),
-         target.proxy().old_build(pkg).join("out"))
+        let build_output = target.build_out(pkg);
+        let old_build_output = if per_profile {
+            target.proxy().old_build(pkg).join("out")
+        } else {
+            build_output.clone()
+        };
+        (cx.layout(pkg, KindHost).build(pkg), build_output, old_build_output)
     };
 
     // Building the command to execute
@@ -43,8 +66,10 @@ pub fn prepare(pkg: &Package, target: &Target, kind: Kind, cx: &mut Context)
     let to_exec = script_output.join(to_exec);
 
     // Start preparing the process to execute, starting out with some
-    // environment variables.
-    let profile = target.get_profile();
+    // environment variables. The profile described to the script is the one
+    // the package is being built with, not the one the script itself was
+    // compiled with.
+    let profile = active_profile(cx, pkg, target);
     let mut p = try!(super::process(to_exec, pkg, target, cx))
                      .env("OUT_DIR", Some(&build_output))
                      .env("CARGO_MANIFEST_DIR", Some(pkg.get_manifest_path()
@@ -55,21 +80,18 @@ pub fn prepare(pkg: &Package, target: &Target, kind: Kind, cx: &mut Context)
                          KindHost => cx.config.rustc_host(),
                          KindTarget => cx.target_triple(),
                      }))
+                     .env("HOST", Some(cx.config.rustc_host()))
+                     .env("TARGET_LINKER", cx.linker(kind))
+                     .env("TARGET_AR", cx.ar(kind))
                      .env("DEBUG", Some(profile.get_debug().to_string()))
                      .env("OPT_LEVEL", Some(profile.get_opt_level().to_string()))
-                     .env("PROFILE", Some(profile.get_env()));
+                     .env("PROFILE", Some(profile.get_dest().unwrap_or("debug")));
 
     // Be sure to pass along all enabled features for this package, this is the
     // last piece of statically known information that we have.
-    match cx.resolve.features(pkg.get_package_id()) {
-        Some(features) => {
-            for feat in features.iter() {
-                p = p.env(format!("CARGO_FEATURE_{}",
-                                  super::envify(feat.as_slice())).as_slice(),
-                          Some("1"));
-            }
-        }
-        None => {}
+    for feat in cx.features(pkg.get_package_id()).iter() {
+        p = p.env(format!("CARGO_FEATURE_{}", super::envify(*feat)).as_slice(),
+                  Some("1"));
     }
 
     // Gather the set of native dependencies that this package has along with
@@ -87,7 +109,9 @@ pub fn prepare(pkg: &Package, target: &Target, kind: Kind, cx: &mut Context)
             })
         }).collect::<Vec<_>>()
     };
+    let search_path = super::host_search_path(pkg, target, cx);
     let pkg_name = pkg.to_string();
+    let very_verbose = cx.config.shell().is_very_verbose();
     let build_state = cx.build_state.clone();
     let id = pkg.get_package_id().clone();
     let all = (id.clone(), pkg_name.clone(), build_state.clone(),
@@ -96,6 +120,7 @@ pub fn prepare(pkg: &Package, target: &Target, kind: Kind, cx: &mut Context)
 
     try!(fs::mkdir_recursive(&cx.layout(pkg, KindTarget).build(pkg), USER_RWX));
     try!(fs::mkdir_recursive(&cx.layout(pkg, KindHost).build(pkg), USER_RWX));
+    try!(fs::mkdir_recursive(&build_output.dir_path(), USER_RWX));
 
     // Prepare the unit of "dirty work" which will actually run the custom build
     // command.
@@ -107,7 +132,9 @@ pub fn prepare(pkg: &Package, target: &Target, kind: Kind, cx: &mut Context)
         //
         // If we have an old build directory, then just move it into place,
         // otherwise create it!
-        try!(if old_build_output.exists() {
+        try!(if !per_profile && build_output.exists() {
+            Ok(())
+        } else if old_build_output.exists() {
             fs::rename(&old_build_output, &build_output)
         } else {
             fs::mkdir(&build_output, USER_RWX)
@@ -117,10 +144,22 @@ pub fn prepare(pkg: &Package, target: &Target, kind: Kind, cx: &mut Context)
         }));
 
         // For all our native lib dependencies, pick up their metadata to pass
-        // along to this custom build command.
+        // along to this custom build command, and put their library paths in
+        // front of the dynamic library search path in case the script runs
+        // tools they built.
         let mut p = p;
         {
             let build_state = build_state.outputs.lock();
+            let mut paths = Vec::new();
+            for &(_, ref id) in lib_deps.iter() {
+                let output = &build_state[(id.clone(), kind)];
+                paths.push_all(output.library_paths.as_slice());
+            }
+            paths.push_all(search_path.as_slice());
+            let paths = try!(join_paths(paths.as_slice(),
+                                        DynamicLibrary::envvar()));
+            p = p.env(DynamicLibrary::envvar(), Some(paths.as_slice()));
+
             for &(ref name, ref id) in lib_deps.iter() {
                 let data = &build_state[(id.clone(), kind)].metadata;
                 for &(ref key, ref value) in data.iter() {
@@ -133,11 +172,18 @@ pub fn prepare(pkg: &Package, target: &Target, kind: Kind, cx: &mut Context)
         }
 
         // And now finally, run the build command itself!
-        desc_tx.send_opt(p.to_string()).ok();
-        let output = try!(p.exec_with_output().map_err(|mut e| {
+        desc_tx.send_opt(format!("`{}`", p.to_shell_string())).ok();
+        // With `-vv` the output of the script is echoed as it runs.
+        let output = if very_verbose {
+            let prefix = format!("[{} {}]", id.get_name(), id.get_version());
+            p.exec_with_streaming(|line| println!("{} {}", prefix, line))
+        } else {
+            p.exec_with_output()
+        };
+        let output = try!(output.map_err(|mut e| {
             e.msg = format!("Failed to run custom build command for `{}`\n{}",
                             pkg_name, e.msg);
-            e.concrete().mark_human()
+            e.concrete().of_kind(BuildFailure).mark_human()
         }));
 
         // After the build command has finished running, we need to be sure to
@@ -177,8 +223,11 @@ pub fn prepare(pkg: &Package, target: &Target, kind: Kind, cx: &mut Context)
     let fresh = proc(tx) {
         let (id, pkg_name, build_state, old_build_output, build_output) = all;
         let new_loc = build_output.dir_path().join("output");
-        try!(fs::rename(&old_build_output.dir_path().join("output"), &new_loc));
-        try!(fs::rename(&old_build_output, &build_output));
+        if per_profile {
+            try!(fs::rename(&old_build_output.dir_path().join("output"),
+                            &new_loc));
+            try!(fs::rename(&old_build_output, &build_output));
+        }
         let mut f = try!(File::open(&new_loc).map_err(|e| {
             human(format!("failed to read cached build command output: {}", e))
         }));
@@ -193,6 +242,32 @@ pub fn prepare(pkg: &Package, target: &Target, kind: Kind, cx: &mut Context)
     Ok((dirty, fresh, freshness))
 }
 
+/// Returns the cfgs the build script of `pkg` emitted the last time it ran,
+/// read from its cached output before this build moves anything around.
+pub fn cached_cfgs(cx: &Context, pkg: &Package) -> CargoResult<Vec<String>> {
+    Ok(match try!(cached_output(cx, pkg)) {
+        Some(output) => output.cfgs,
+        None => Vec::new(),
+    })
+}
+
+/// Returns the output of the last run of the build script of `pkg`, if it ran
+/// before, read before this build moves anything around.
+pub fn cached_output(cx: &Context, pkg: &Package)
+                     -> CargoResult<Option<BuildOutput>> {
+    let layout = cx.layout(pkg, KindTarget);
+    let loc = if pkg.get_manifest().is_build_per_profile() {
+        layout.proxy().old_build(pkg).join("output")
+    } else {
+        layout.build_out(pkg).dir_path().join("output")
+    };
+    if !loc.exists() { return Ok(None) }
+    let contents = try!(File::open(&loc).read_to_string());
+    let pkg_name = pkg.to_string();
+    Ok(Some(try!(BuildOutput::parse(contents.as_slice(),
+                                    pkg_name.as_slice()))))
+}
+
 impl BuildState {
     pub fn new(config: super::BuildConfig,
                packages: &PackageSet) -> BuildState {
@@ -228,7 +303,9 @@ impl BuildOutput {
     pub fn parse(input: &str, pkg_name: &str) -> CargoResult<BuildOutput> {
         let mut library_paths = Vec::new();
         let mut library_links = Vec::new();
+        let mut cfgs = Vec::new();
         let mut metadata = Vec::new();
+        let mut rerun_if_changed = Vec::new();
         let whence = format!("build script of `{}`", pkg_name);
 
         for line in input.lines() {
@@ -260,6 +337,10 @@ impl BuildOutput {
                 );
                 library_links.extend(links.into_iter());
                 library_paths.extend(libs.into_iter());
+            } else if key == "rustc-cfg" {
+                cfgs.push(value.to_string());
+            } else if key == "rerun-if-changed" {
+                rerun_if_changed.push(Path::new(value));
             } else {
                 metadata.push((key.to_string(), value.to_string()))
             }
@@ -268,7 +349,9 @@ impl BuildOutput {
         Ok(BuildOutput {
             library_paths: library_paths,
             library_links: library_links,
+            cfgs: cfgs,
             metadata: metadata,
+            rerun_if_changed: rerun_if_changed,
         })
     }
 
@@ -308,7 +391,8 @@ impl BuildOutput {
 
 impl fmt::Show for BuildOutput {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        write!(f, "BuildOutput {{ paths: [..], libs: {}, metadata: {} }}",
-               self.library_links, self.metadata)
+        write!(f, "BuildOutput {{ paths: [..], libs: {}, cfgs: {}, \
+                   metadata: {} }}",
+               self.library_links, self.cfgs, self.metadata)
     }
 }
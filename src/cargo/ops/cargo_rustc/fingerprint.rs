@@ -1,14 +1,17 @@
+use std::collections::HashSet;
 use std::collections::hash_map::{Occupied, Vacant};
 use std::hash::{Hash, Hasher};
 use std::hash::sip::SipHasher;
-use std::io::{fs, File, USER_RWX, BufferedReader};
+use std::io::{fs, File, USER_RWX, BufferedReader, IoResult, FileStat};
+use std::io::fs::PathExtensions;
 
-use core::{Package, Target};
+use core::{Package, Source, SourceId, Target};
+use ops;
 use util;
 use util::{CargoResult, Fresh, Dirty, Freshness, internal, Require, profile};
 
-use super::{Kind, KindTarget};
-use super::job::Work;
+use super::{Kind, KindHost, KindTarget, custom_build};
+use super::job::{Work, OutputCheck};
 use super::context::Context;
 
 /// A tuple result of the `prepare_foo` functions in this module.
@@ -63,60 +66,158 @@ pub fn prepare_target(cx: &mut Context, pkg: &Package, target: &Target,
     // First bit of the freshness calculation, whether the dep-info file
     // indicates that the target is fresh.
     let (old_dep_info, new_dep_info) = dep_info_loc(cx, pkg, target, kind);
+    let build_dirs = {
+        let layout = cx.layout(pkg, kind);
+        (layout.build(pkg).dir_path(), layout.old_build(pkg).dir_path())
+    };
     let are_files_fresh = use_pkg ||
-                          try!(calculate_target_fresh(pkg, &old_dep_info));
+                          try!(calculate_target_fresh(pkg, &old_dep_info,
+                                                      &build_dirs));
 
     // Second bit of the freshness calculation, whether rustc itself, the
     // target are fresh, and the enabled set of features are all fresh.
-    let features = cx.resolve.features(pkg.get_package_id());
-    let features = features.map(|s| {
-        let mut v = s.iter().collect::<Vec<&String>>();
-        v.sort();
-        v
-    });
+    let features = cx.features(pkg.get_package_id());
+    // The triple being compiled for is also part of the fingerprint so
+    // switching the default target (e.g. via `build.target`) is a rebuild, as
+    // is changing the configured linker, `ar` or native libraries for target
+    // units, or editing a custom target specification.
+    let toolchain = match kind {
+        KindHost => (cx.config.rustc_host(), None, None, None),
+        KindTarget => (cx.target_triple(), cx.linker(kind), cx.ar(kind),
+                       cx.config.target_spec()),
+    };
+    let rustc_args = cx.rustc_args_for(pkg, target);
+    let link_args = cx.config_link_args(pkg, target, kind);
     let rustc_fingerprint = if use_pkg {
         mk_fingerprint(cx, &(target, try!(calculate_pkg_fingerprint(cx, pkg)),
-                             features))
+                             features, toolchain, rustc_args, link_args))
     } else {
-        mk_fingerprint(cx, &(target, features))
+        mk_fingerprint(cx, &(target, features, toolchain, rustc_args,
+                             link_args))
     };
-    let is_rustc_fresh = try!(is_fresh(&old_loc, rustc_fingerprint.as_slice()));
 
-    let (old_root, root) = {
-        let layout = cx.layout(pkg, kind);
-        if target.get_profile().is_custom_build() {
-            (layout.old_build(pkg), layout.build(pkg))
-        } else if target.is_example() {
-            (layout.old_examples().clone(), layout.examples().clone())
-        } else {
-            (layout.old_root().clone(), layout.root().clone())
-        }
-    };
+    // The libraries we depend on contribute the hash of their artifacts, so if
+    // one of them was rebuilt since we were last built against it, we're
+    // dirty. Whether they get rebuilt during this build is taken care of by
+    // the job queue, but the hashes on disk at that time are what is recorded.
+    let dep_hashes = cx.dep_targets(pkg, target).iter().filter(|&&(_, t)| {
+        has_output_hash(t)
+    }).map(|&(dep, t)| {
+        let kind = match kind {
+            KindTarget if !t.get_profile().is_for_host() => KindTarget,
+            _ => KindHost,
+        };
+        output_hash_loc(cx, dep, t, kind)
+    }).collect::<Vec<(Path, Path)>>();
+    let old_hashes = dep_hashes.iter().map(|&(ref old, _)| old.clone())
+                               .collect::<Vec<Path>>();
+    let new_hashes = dep_hashes.into_iter().map(|(_, new)| new).collect();
+    let old_fingerprint = format!("{}{}", rustc_fingerprint,
+                                  read_hashes(old_hashes.as_slice()));
+    let mut is_rustc_fresh = try!(is_fresh(&old_loc,
+                                           old_fingerprint.as_slice()));
+
+    let (old_root, root) = cx.out_dirs(pkg, target, kind);
     let mut pairs = vec![(old_loc, new_loc.clone())];
+
+    // Documentation is also stale if the cfgs the build script last emitted
+    // aren't the ones it was generated with. A build script rerunning during
+    // this build dirties it anyway.
+    if target.get_profile().is_doc() && has_build_script(pkg) {
+        let (old_cfgs, new_cfgs) = doc_cfg_loc(cx, pkg, target);
+        let cfgs = try!(custom_build::cached_cfgs(cx, pkg));
+        is_rustc_fresh = is_rustc_fresh &&
+                         try!(is_fresh(&old_cfgs, cfgs.connect("\n").as_slice()));
+        if old_cfgs.exists() {
+            pairs.push((old_cfgs, new_cfgs));
+        }
+    }
+
     if !target.get_profile().is_doc() {
         pairs.push((old_dep_info, new_dep_info));
 
+        let (old_hash, new_hash) = output_hash_loc(cx, pkg, target, kind);
+        if has_output_hash(target) && old_hash.exists() {
+            pairs.push((old_hash, new_hash));
+        }
+
+        if cx.is_primary(pkg) {
+            let layout = cx.layout(pkg, kind);
+            for filename in try!(cx.target_final_filenames(target)).iter() {
+                let filename = filename.as_slice();
+                pairs.push((layout.old_root().join(filename),
+                            layout.root().join(filename)));
+            }
+        }
+
         for filename in try!(cx.target_filenames(target)).iter() {
             let filename = filename.as_slice();
             let dst = root.join(filename);
             pairs.push((old_root.join(filename), root.join(filename)));
 
             if target.get_profile().is_test() {
-                cx.compilation.tests.push((target.get_name().into_string(), dst.clone()));
-            } else if target.is_bin() {
-                cx.compilation.binaries.push(dst.clone());
-            } else if target.is_lib() {
-                let pkgid = pkg.get_package_id().clone();
-                match cx.compilation.libraries.entry(pkgid) {
-                    Occupied(entry) => entry.into_mut(),
-                    Vacant(entry) => entry.set(Vec::new()),
-                }.push(root.join(filename));
+                cx.compilation.tests.push((pkg.clone(), target.clone(),
+                                           dst.clone()));
+            }
+        }
+
+        // Only the libraries which dependents would be linked against are
+        // recorded, e.g. not static libraries.
+        if target.is_lib() && !target.get_profile().is_test() {
+            let pkgid = pkg.get_package_id().clone();
+            let filenames = try!(cx.target_link_filenames(target));
+            let libs = match cx.compilation.libraries.entry(pkgid) {
+                Occupied(entry) => entry.into_mut(),
+                Vacant(entry) => entry.set(Vec::new()),
+            };
+            libs.extend(filenames.iter().map(|f| root.join(f.as_slice())));
+        }
+
+        // Binaries are reported at their stable location rather than where
+        // rustc placed them.
+        if target.is_bin() && !target.get_profile().is_test() {
+            let paths = try!(cx.target_final_paths(pkg, target, kind));
+            cx.compilation.binaries.extend(paths.into_iter());
+        }
+
+        if !target.get_profile().is_custom_build() {
+            let pkgid = pkg.get_package_id();
+            for path in try!(cx.target_final_paths(pkg, target, kind)).into_iter() {
+                cx.compilation.artifacts.push((pkgid.clone(), target.clone(),
+                                               path));
             }
         }
     }
 
     Ok(prepare(is_rustc_fresh && are_files_fresh, new_loc, rustc_fingerprint,
-               pairs))
+               new_hashes, pairs))
+}
+
+/// Prepare the check run after a library is rebuilt, recording a hash of its
+/// artifacts and returning whether they're identical to the previous build's.
+///
+/// Dependents are only rebuilt if the artifacts changed, so for example
+/// touching a comment in a deeply nested dependency doesn't rebuild the world.
+pub fn prepare_output_check(cx: &Context, pkg: &Package, target: &Target,
+                            kind: Kind) -> CargoResult<Option<OutputCheck>> {
+    if !has_output_hash(target) { return Ok(None) }
+
+    let (old_loc, new_loc) = output_hash_loc(cx, pkg, target, kind);
+    let (_, root) = cx.out_dirs(pkg, target, kind);
+    let outputs = try!(cx.target_filenames(target)).iter().map(|f| {
+        root.join(f.as_slice())
+    }).collect::<Vec<Path>>();
+
+    Ok(Some(proc() {
+        let mut contents = Vec::new();
+        for output in outputs.iter() {
+            contents.push(try!(File::open(output).read_to_end()));
+        }
+        let hasher = SipHasher::new_with_keys(0, 0);
+        let hash = util::to_hex(hasher.hash(&contents));
+        try!(File::create(&new_loc).write_str(hash.as_slice()));
+        Ok(read_hashes(&[old_loc]) == hash)
+    }))
 }
 
 /// Prepare the necessary work for the fingerprint of a build command.
@@ -147,17 +248,69 @@ pub fn prepare_build_cmd(cx: &mut Context, pkg: &Package,
     if pkg.get_manifest().get_build().len() == 0 && target.is_none() {
         return Ok((Fresh, proc(_) Ok(()), proc(_) Ok(())))
     }
-    let (old, new) = dirs(cx, pkg, kind);
-    let old_loc = old.join("build");
-    let new_loc = new.join("build");
+    // A build script shared between profiles keeps its fingerprint next to
+    // its output, where it stays put across builds.
+    let shared = target.is_some() && !pkg.get_manifest().is_build_per_profile();
+    let (old_loc, new_loc) = if shared {
+        let loc = cx.layout(pkg, kind).build_out(pkg).dir_path()
+                    .join("fingerprint");
+        (loc.clone(), loc)
+    } else {
+        let (old, new) = dirs(cx, pkg, kind);
+        (old.join("build"), new.join("build"))
+    };
 
     info!("fingerprint at: {}", new_loc.display());
 
-    let new_fingerprint = try!(calculate_build_cmd_fingerprint(cx, pkg));
+    let new_fingerprint = try!(calculate_build_cmd_fingerprint(cx, pkg, target));
     let new_fingerprint = mk_fingerprint(cx, &new_fingerprint);
 
-    let is_fresh = try!(is_fresh(&old_loc, new_fingerprint.as_slice()));
-    let mut pairs = vec![(old_loc, new_loc.clone())];
+    let mut is_fresh = try!(is_fresh(&old_loc, new_fingerprint.as_slice()));
+
+    // The files the script said it read with `rerun-if-changed` may be
+    // anywhere, not only in the package, so they're always checked.
+    let rerun_if_changed = if target.is_some() {
+        match try!(custom_build::cached_output(cx, pkg)) {
+            Some(output) => output.rerun_if_changed.iter().map(|p| {
+                pkg.get_root().join(p)
+            }).collect(),
+            None => Vec::new(),
+        }
+    } else {
+        Vec::new()
+    };
+
+    // The inputs of the script are listed next to its fingerprint, so that
+    // checking just them tells whether the package changed. The package is
+    // only walked again when the list is missing or out of date.
+    let (old_inputs_loc, new_inputs_loc) = (old_loc.with_extension("inputs"),
+                                            new_loc.with_extension("inputs"));
+    let mut inputs = None;
+    if is_path(pkg) || rerun_if_changed.len() > 0 {
+        if is_fresh {
+            inputs = try!(read_inputs(&old_inputs_loc));
+            is_fresh = match inputs {
+                Some(ref inputs) => {
+                    try!(are_inputs_fresh(cx, pkg, inputs,
+                                          rerun_if_changed.as_slice()))
+                }
+                None => false,
+            };
+        }
+        if !is_fresh {
+            inputs = Some(try!(list_inputs(cx, pkg,
+                                           rerun_if_changed.as_slice())));
+        }
+    }
+
+    let mut pairs = if shared {
+        Vec::new()
+    } else {
+        vec![(old_loc, new_loc.clone())]
+    };
+    if inputs.is_some() && !shared {
+        pairs.push((old_inputs_loc, new_inputs_loc.clone()));
+    }
 
     // The new custom build command infrastructure handles its own output
     // directory as part of freshness.
@@ -168,7 +321,146 @@ pub fn prepare_build_cmd(cx: &mut Context, pkg: &Package,
                                           native_dir);
     }
 
-    Ok(prepare(is_fresh, new_loc, new_fingerprint, pairs))
+    let (freshness, write_fingerprint, move_old) =
+        prepare(is_fresh, new_loc, new_fingerprint, Vec::new(), pairs);
+    let write_fingerprint = match inputs {
+        Some(inputs) => proc(desc_tx) {
+            try!(write_fingerprint(desc_tx));
+            write_inputs(&new_inputs_loc, &inputs)
+        },
+        None => write_fingerprint,
+    };
+    Ok((freshness, write_fingerprint, move_old))
+}
+
+/// The filesystem operations which decide whether a build is fresh: reading
+/// the packages of the local directory and checking the inputs of build
+/// scripts. Tests provide their own through `CompileOptions::fs` to observe
+/// them.
+pub trait FreshnessFs: Send + Sync {
+    /// Reads the packages of the path source with the id `source_id`
+    /// rooted at `path`, which walks its directories.
+    fn read_packages(&self, path: &Path, source_id: &SourceId)
+                     -> CargoResult<Vec<Package>>;
+
+    /// Lists the files of the path package `pkg` of `source`, which walks
+    /// its directories.
+    fn list_files(&self, source: &Source, pkg: &Package)
+                  -> CargoResult<Vec<Path>>;
+
+    /// Returns the metadata of the file or directory at `path`.
+    fn stat(&self, path: &Path) -> IoResult<FileStat>;
+}
+
+/// The `FreshnessFs` used unless another one is provided, which just asks
+/// the source and the filesystem.
+pub struct RealFs;
+
+impl FreshnessFs for RealFs {
+    fn read_packages(&self, path: &Path, source_id: &SourceId)
+                     -> CargoResult<Vec<Package>> {
+        ops::read_packages(path, source_id)
+    }
+
+    fn list_files(&self, source: &Source, pkg: &Package)
+                  -> CargoResult<Vec<Path>> {
+        source.fingerprint_files(pkg)
+    }
+
+    fn stat(&self, path: &Path) -> IoResult<FileStat> {
+        fs::stat(path)
+    }
+}
+
+/// The inputs of a build script, as recorded next to its fingerprint.
+struct Inputs {
+    /// The newest mtime among `files` when they were listed, which is before
+    /// the script ran.
+    mtime: u64,
+    files: Vec<Path>,
+}
+
+/// Lists the inputs of the build script of `pkg`: the files of a path
+/// package, along with the directories containing them so that adding or
+/// removing a file is noticed as well, and the files it last said it read.
+///
+/// Their newest mtime is taken now, before the script runs, so a file which
+/// changes while it's running makes it run again on the next build.
+fn list_inputs(cx: &Context, pkg: &Package, rerun_if_changed: &[Path])
+               -> CargoResult<Inputs> {
+    let fs = cx.freshness_fs();
+    let mut files = if is_path(pkg) {
+        let source = cx.sources
+            .get(pkg.get_package_id().get_source_id())
+            .expect("BUG: Missing package source");
+        try!(fs.list_files(source, pkg))
+    } else {
+        Vec::new()
+    };
+
+    let root = pkg.get_root();
+    let mut dirs = HashSet::new();
+    for file in files.iter() {
+        let mut dir = file.dir_path();
+        while root.is_ancestor_of(&dir) && dirs.insert(dir.clone()) {
+            dir.pop();
+        }
+    }
+    files.extend(dirs.into_iter());
+    files.extend(rerun_if_changed.iter().map(|p| p.clone()));
+
+    // A file which can't be stat'd, e.g. a broken symlink, is stale the next
+    // time it's checked anyway.
+    let mtime = files.iter().filter_map(|file| fs.stat(file).ok()).map(|stat| {
+        stat.modified
+    }).max().unwrap_or(0);
+    Ok(Inputs { mtime: mtime, files: files })
+}
+
+/// Writes the inputs of a build script, the mtime on the first line followed
+/// by one file per line.
+fn write_inputs(loc: &Path, inputs: &Inputs) -> CargoResult<()> {
+    let mut file = try!(File::create(loc));
+    try!(file.write_line(inputs.mtime.to_string().as_slice()));
+    for input in inputs.files.iter() {
+        try!(file.write_line(input.display().to_string().as_slice()));
+    }
+    Ok(())
+}
+
+/// Reads the inputs of a build script written by `write_inputs`, or returns
+/// `None` if there are none.
+fn read_inputs(loc: &Path) -> CargoResult<Option<Inputs>> {
+    let mut lines = BufferedReader::new(File::open(loc)).lines();
+    let mtime = match lines.next() {
+        Some(Ok(line)) => line,
+        _ => return Ok(None),
+    };
+    let mtime = try!(from_str(mtime.as_slice().trim()).require(|| {
+        internal(format!("build script inputs not in an understood format: \
+                          {}", loc.display()))
+    }));
+    let mut files = Vec::new();
+    for line in lines {
+        let line = try!(line);
+        files.push(Path::new(line.as_slice().trim_right_chars('\n')));
+    }
+    Ok(Some(Inputs { mtime: mtime, files: files }))
+}
+
+/// Checks that none of the recorded `inputs` of the build script of `pkg`,
+/// nor the files it said it read, changed since they were listed.
+fn are_inputs_fresh(cx: &Context, pkg: &Package, inputs: &Inputs,
+                    rerun_if_changed: &[Path]) -> CargoResult<bool> {
+    let fs = cx.freshness_fs();
+    for input in inputs.files.iter().chain(rerun_if_changed.iter()) {
+        match fs.stat(input) {
+            Ok(stat) if stat.modified <= inputs.mtime => {}
+            _ => { info!("stale: {}", input.display()); return Ok(false) }
+        }
+    }
+    info!("inputs of {} are fresh", pkg.get_package_id());
+    Ok(true)
 }
 
 /// Prepare work for when a package starts to build
@@ -185,11 +477,21 @@ pub fn prepare_init(cx: &mut Context, pkg: &Package, kind: Kind)
 
 /// Given the data to build and write a fingerprint, generate some Work
 /// instances to actually perform the necessary work.
+///
+/// The artifact hashes found at `dep_hashes` are appended to the fingerprint
+/// when it's written, as dependencies may have been rebuilt in the meantime.
 fn prepare(is_fresh: bool, loc: Path, fingerprint: String,
-           to_copy: Vec<(Path, Path)>) -> Preparation {
+           dep_hashes: Vec<Path>, to_copy: Vec<(Path, Path)>) -> Preparation {
     let write_fingerprint = proc(desc_tx) {
         drop(desc_tx);
-        try!(File::create(&loc).write_str(fingerprint.as_slice()));
+        let fingerprint = format!("{}{}", fingerprint,
+                                  read_hashes(dep_hashes.as_slice()));
+        // Write to a temporary file first so an interrupted build never leaves
+        // a truncated (or a complete, but premature) fingerprint behind.
+        let tmp = loc.with_filename(format!("{}.tmp",
+                                            loc.filename_str().unwrap()));
+        try!(File::create(&tmp).write_str(fingerprint.as_slice()));
+        try!(fs::rename(&tmp, &loc));
         Ok(())
     };
 
@@ -211,6 +513,25 @@ pub fn dirs(cx: &Context, pkg: &Package, kind: Kind) -> (Path, Path) {
     (layout.old_fingerprint(pkg), layout.fingerprint(pkg))
 }
 
+/// Returns the (old, new) location of the cfgs a doc target was last
+/// generated with.
+pub fn doc_cfg_loc(cx: &Context, pkg: &Package, target: &Target) -> (Path, Path) {
+    let (old, new) = dirs(cx, pkg, KindTarget);
+    let filename = format!("cfg-{}", filename(target));
+    (old.join(filename.as_slice()), new.join(filename))
+}
+
+/// Records the cfgs a doc target is being generated with.
+pub fn write_doc_cfgs(loc: &Path, cfgs: &[String]) -> CargoResult<()> {
+    try!(File::create(loc).write_str(cfgs.connect("\n").as_slice()));
+    Ok(())
+}
+
+/// Whether `pkg` has a build script, whose output may change what it builds.
+pub fn has_build_script(pkg: &Package) -> bool {
+    pkg.get_targets().iter().any(|t| t.get_profile().is_custom_build())
+}
+
 /// Returns the (old, new) location for the dep info file of a target.
 pub fn dep_info_loc(cx: &Context, pkg: &Package, target: &Target,
                     kind: Kind) -> (Path, Path) {
@@ -219,6 +540,28 @@ pub fn dep_info_loc(cx: &Context, pkg: &Package, target: &Target,
     (old.join(filename.as_slice()), new.join(filename))
 }
 
+/// Returns the (old, new) location for the hash of a library's artifacts.
+pub fn output_hash_loc(cx: &Context, pkg: &Package, target: &Target,
+                       kind: Kind) -> (Path, Path) {
+    let (old, new) = dirs(cx, pkg, kind);
+    let filename = format!("hash-{}", filename(target));
+    (old.join(filename.as_slice()), new.join(filename))
+}
+
+/// Only the artifacts which dependents are compiled against are hashed.
+fn has_output_hash(target: &Target) -> bool {
+    let profile = target.get_profile();
+    target.is_lib() && !profile.is_test() && !profile.is_doc()
+}
+
+/// Concatenates the artifact hashes stored at `locs`, where a missing hash is
+/// the empty string.
+fn read_hashes(locs: &[Path]) -> String {
+    locs.iter().map(|loc| {
+        File::open(loc).read_to_string().unwrap_or(String::new())
+    }).collect::<Vec<String>>().connect(":")
+}
+
 fn is_fresh(loc: &Path, new_fingerprint: &str) -> CargoResult<bool> {
     let mut file = match File::open(loc) {
         Ok(file) => file,
@@ -240,19 +583,59 @@ fn mk_fingerprint<T: Hash>(cx: &Context, data: &T) -> String {
     util::to_hex(hasher.hash(&(cx.config.rustc_version(), data)))
 }
 
-fn calculate_target_fresh(pkg: &Package, dep_info: &Path) -> CargoResult<bool> {
+/// Checks that none of the files listed in a dep-info file changed since it
+/// was written.
+///
+/// Files generated by build scripts (e.g. under `OUT_DIR`) are listed with
+/// absolute paths into the build directory. At this point of the build that
+/// directory has been moved aside, so they are looked up in the old build
+/// directory instead. If a build script runs again it may regenerate them,
+/// but that makes everything after it dirty in the job queue anyway.
+fn calculate_target_fresh(pkg: &Package, dep_info: &Path,
+                          &(ref build, ref old_build): &(Path, Path))
+                          -> CargoResult<bool> {
+    let files = match try!(parse_dep_info(dep_info)) {
+        Some(files) => files,
+        None => return Ok(false),
+    };
+    let mtime = try!(fs::stat(dep_info)).modified;
+
+    for file in files.iter() {
+        let mut path = pkg.get_root().join(file.as_slice());
+        if build.is_ancestor_of(&path) {
+            path = old_build.join(path.path_relative_from(build).unwrap());
+        }
+        match fs::stat(&path) {
+            Ok(stat) if stat.modified <= mtime => {}
+            Ok(stat) => {
+                info!("stale: {} -- {} vs {}", file, stat.modified, mtime);
+                return Ok(false)
+            }
+            _ => { info!("stale: {} -- missing", file); return Ok(false) }
+        }
+    }
+
+    Ok(true)
+}
+
+/// Returns the input files listed in the dep-info file written by rustc, or
+/// `None` if there is no such file.
+///
+/// The files are listed as rustc was given them, i.e. relative to the root of
+/// the package unless they are absolute.
+pub fn parse_dep_info(dep_info: &Path) -> CargoResult<Option<Vec<String>>> {
     let line = match BufferedReader::new(File::open(dep_info)).lines().next() {
         Some(Ok(line)) => line,
-        _ => return Ok(false),
+        _ => return Ok(None),
     };
     let line = line.as_slice();
-    let mtime = try!(fs::stat(dep_info)).modified;
     let pos = try!(line.find_str(": ").require(|| {
         internal(format!("dep-info not in an understood format: {}",
                          dep_info.display()))
     }));
     let deps = line.slice_from(pos + 2);
 
+    let mut files = Vec::new();
     let mut deps = deps.split(' ').map(|s| s.trim()).filter(|s| !s.is_empty());
     loop {
         let mut file = match deps.next() {
@@ -264,24 +647,53 @@ fn calculate_target_fresh(pkg: &Package, dep_info: &Path) -> CargoResult<bool> {
             file.push(' ');
             file.push_str(deps.next().unwrap())
         }
-        match fs::stat(&pkg.get_root().join(file.as_slice())) {
-            Ok(stat) if stat.modified <= mtime => {}
-            Ok(stat) => {
-                info!("stale: {} -- {} vs {}", file, stat.modified, mtime);
-                return Ok(false)
-            }
-            _ => { info!("stale: {} -- missing", file); return Ok(false) }
-        }
+        files.push(file);
     }
-
-    Ok(true)
+    Ok(Some(files))
 }
 
-fn calculate_build_cmd_fingerprint(cx: &Context, pkg: &Package)
+fn calculate_build_cmd_fingerprint(cx: &Context, pkg: &Package,
+                                   target: Option<&Target>)
                                    -> CargoResult<String> {
     // TODO: this should be scoped to just the `build` directory, not the entire
     // package.
-    calculate_pkg_fingerprint(cx, pkg)
+    //
+    // The files of a path package are checked separately, by their mtimes.
+    let fingerprint = if is_path(pkg) {
+        pkg.get_manifest().build_hash()
+    } else {
+        try!(calculate_pkg_fingerprint(cx, pkg))
+    };
+
+    // Build scripts are told about the configured linker and `ar`, so they need
+    // to be rerun if either of them changes. The fields are separated so that
+    // one of them growing can't be mistaken for its neighbour shrinking.
+    let mut fields = vec![fingerprint,
+                          cx.linker(KindTarget).unwrap_or("").to_string(),
+                          cx.ar(KindTarget).unwrap_or("").to_string(),
+                          cx.config.target_spec().unwrap_or("").to_string()];
+
+    // Likewise for the triples and profile they are told about, unless the
+    // package declared that the profile doesn't matter. `NUM_JOBS` is
+    // deliberately left out, changing `-j` shouldn't rerun build scripts.
+    match target {
+        Some(target) => {
+            fields.push(cx.target_triple().to_string());
+            fields.push(cx.config.rustc_host().to_string());
+            if pkg.get_manifest().is_build_per_profile() {
+                let profile = custom_build::active_profile(cx, pkg, target);
+                fields.push(profile.get_dest().unwrap_or("debug").to_string());
+                fields.push(profile.get_opt_level().to_string());
+                fields.push(profile.get_debuginfo().to_string());
+            }
+        }
+        None => {}
+    }
+    Ok(fields.connect("\0"))
+}
+
+fn is_path(pkg: &Package) -> bool {
+    pkg.get_summary().get_source_id().is_path()
 }
 
 fn calculate_pkg_fingerprint(cx: &Context, pkg: &Package) -> CargoResult<String> {
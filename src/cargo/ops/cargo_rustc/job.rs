@@ -1,16 +1,27 @@
 use util::{CargoResult, Fresh, Dirty, Freshness};
 
-pub struct Job { dirty: Work, fresh: Work }
+use super::timings::Unit;
+
+pub struct Job {
+    dirty: Work,
+    fresh: Work,
+    check: Option<OutputCheck>,
+    unit: Option<Unit>,
+}
 
 /// Each proc should send its description before starting.
 /// It should send either once or close immediatly.
 pub type Work = proc(Sender<String>):Send -> CargoResult<()>;
 
+/// Run after the dirty work of a job, returning whether the artifacts it
+/// produced are identical to those of the previous build.
+pub type OutputCheck = proc():Send -> CargoResult<bool>;
+
 impl Job {
     /// Create a new job representing a unit of work.
     pub fn new(dirty: Work,
                fresh: Work) -> Job {
-        Job { dirty: dirty, fresh: fresh }
+        Job { dirty: dirty, fresh: fresh, check: None, unit: None }
     }
 
     /// Create a new job which will run `fresh` if the job is fresh and
@@ -20,15 +31,42 @@ impl Job {
     /// describe itself to the console.
     pub fn noop(_dirty: Work,
                 fresh: Work) -> Job {
-        Job { dirty: proc(_) Ok(()), fresh: fresh }
+        Job { dirty: proc(_) Ok(()), fresh: fresh, check: None, unit: None }
+    }
+
+    /// Attach a check of whether running the dirty work actually changed the
+    /// output of this job. If it didn't, the job is reported as fresh so its
+    /// dependents aren't rebuilt on its account.
+    pub fn check_output(mut self, check: Option<OutputCheck>) -> Job {
+        self.check = check;
+        self
+    }
+
+    /// Describe what this job builds, so that it is listed in the timings
+    /// report. Jobs without a description are only bookkeeping.
+    pub fn unit(mut self, unit: Unit) -> Job {
+        self.unit = Some(unit);
+        self
+    }
+
+    pub fn get_unit(&self) -> Option<&Unit> {
+        self.unit.as_ref()
     }
 
-    /// Consumes this job by running it, returning the result of the
-    /// computation.
-    pub fn run(self, fresh: Freshness, tx: Sender<String>) -> CargoResult<()> {
+    /// Consumes this job by running it, returning the freshness of the output
+    /// it leaves behind.
+    pub fn run(self, fresh: Freshness,
+               tx: Sender<String>) -> CargoResult<Freshness> {
         match fresh {
-            Fresh => (self.fresh)(tx),
-            Dirty => (self.dirty)(tx),
+            Fresh => { try!((self.fresh)(tx)); Ok(Fresh) }
+            Dirty => {
+                try!((self.dirty)(tx));
+                let unchanged = match self.check {
+                    Some(check) => try!(check()),
+                    None => false,
+                };
+                Ok(if unchanged {Fresh} else {Dirty})
+            }
         }
     }
 }
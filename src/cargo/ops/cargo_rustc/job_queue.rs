@@ -1,12 +1,16 @@
+use std::cmp;
 use std::collections::HashSet;
 use std::collections::hash_map::{HashMap, Occupied, Vacant};
 use term::color::YELLOW;
+use time;
 
 use core::{Package, PackageId, Resolve, PackageSet};
 use util::{Config, TaskPool, DependencyQueue, Fresh, Dirty, Freshness};
-use util::{CargoResult, Dependency, profile};
+use util::{mod, CargoResult, Dependency, Progress, human, interrupt, profile};
+use util::{human_with_cause, BuildFailure};
 
 use super::job::Job;
+use super::timings::{mod, Unit, UnitTime, Timings};
 
 /// A management structure of the entire dependency graph to compile.
 ///
@@ -26,15 +30,42 @@ pub struct JobQueue<'a, 'b> {
     state: HashMap<&'a PackageId, Freshness>,
     ignored: HashSet<&'a PackageId>,
     printed: HashSet<&'a PackageId>,
+    keep_going: bool,
+
+    // Bookkeeping for the progress line: the total number of units of work in
+    // the compile plan, how many have finished, and how many are currently
+    // running for each package.
+    progress: Progress,
+    total: uint,
+    finished: uint,
+    in_flight: HashMap<&'a PackageId, uint>,
+
+    // When the build started, and how long each unit of work took.
+    start: u64,
+    end: u64,
+    units: Vec<UnitTime>,
 }
 
 /// A helper structure for metadata about the state of a building package.
 struct PendingBuild {
     /// Number of jobs currently active
     amt: uint,
-    /// Current freshness state of this package. Any dirty target within a
-    /// package will cause the entire package to become dirty.
+    /// Freshness of the output of this stage. Any job whose output changed
+    /// makes the whole stage dirty, and a stage without jobs passes on the
+    /// freshness of its dependencies.
+    fresh: Freshness,
+    /// Whether the jobs of this stage count towards the progress total.
+    counted: bool,
+    /// Duration of the longest job of this stage, in nanoseconds.
+    longest: u64,
+}
+
+/// When a job ran, and what it was building.
+struct JobTiming {
+    unit: Option<Unit>,
     fresh: Freshness,
+    start: u64,
+    end: u64,
 }
 
 /// Current stage of compilation for an individual package.
@@ -57,11 +88,11 @@ pub enum TargetStage {
     StageBinaryTests,
 }
 
-type Message = (PackageId, TargetStage, Freshness, CargoResult<()>);
+type Message = (PackageId, TargetStage, JobTiming, CargoResult<Freshness>);
 
 impl<'a, 'b> JobQueue<'a, 'b> {
     pub fn new(resolve: &'a Resolve, packages: &'a PackageSet,
-               config: &Config) -> JobQueue<'a, 'b> {
+               config: &Config, keep_going: bool) -> JobQueue<'a, 'b> {
         let (tx, rx) = channel();
         JobQueue {
             pool: TaskPool::new(config.jobs()),
@@ -75,6 +106,14 @@ impl<'a, 'b> JobQueue<'a, 'b> {
             state: HashMap::new(),
             ignored: HashSet::new(),
             printed: HashSet::new(),
+            keep_going: keep_going,
+            progress: Progress::new("Building", &mut **config.shell()),
+            total: 0,
+            finished: 0,
+            in_flight: HashMap::new(),
+            start: 0,
+            end: 0,
+            units: Vec::new(),
         }
     }
 
@@ -88,6 +127,12 @@ impl<'a, 'b> JobQueue<'a, 'b> {
             Vacant(entry) => { entry.set(fresh); }
         };
 
+        // Everything other than preparing the fingerprint directories is a unit
+        // of work as far as progress is concerned, including build scripts.
+        if stage != StageStart && !self.ignored.contains(&pkg.get_package_id()) {
+            self.total += jobs.len();
+        }
+
         // Add the package to the dependency graph
         self.queue.enqueue(&(self.resolve, self.packages), Fresh,
                            (pkg.get_package_id(), stage),
@@ -103,8 +148,16 @@ impl<'a, 'b> JobQueue<'a, 'b> {
     /// This function will spawn off `config.jobs()` workers to build all of the
     /// necessary dependencies, in order. Freshness is propagated as far as
     /// possible along each dependency chain.
+    ///
+    /// If the queue was created with `keep_going`, a failing job does not stop
+    /// the build. Instead the stage it belongs to is never finished (so nothing
+    /// depending on it is scheduled) and everything else continues to run. All
+    /// failures are then reported together once no more work can be done.
     pub fn execute(&mut self, config: &Config) -> CargoResult<()> {
         let _p = profile::start("executing the job graph");
+        let _interrupt = interrupt::install();
+        let mut errors = Vec::new();
+        self.start = time::precise_time_ns();
 
         // Iteratively execute the dependency graph. Each turn of this loop will
         // schedule as much work as possible and then wait for one job to finish,
@@ -120,23 +173,57 @@ impl<'a, 'b> JobQueue<'a, 'b> {
                 }
             }
 
+            // With `keep_going`, the only way for nothing to be running is for
+            // all remaining work to depend on something which failed.
+            if self.active == 0 {
+                assert!(errors.len() > 0);
+                break
+            }
+            try!(self.tick(config));
+
             // Now that all possible work has been scheduled, wait for a piece
             // of work to finish. If any package fails to build then we stop
-            // scheduling work as quickly as possibly.
-            let (id, stage, fresh, result) = self.rx.recv();
+            // scheduling work as quickly as possibly (unless asked to keep
+            // going).
+            let (id, stage, timing, result) = self.rx.recv();
             info!("  end: {} {}", id, stage);
             let id = *self.state.keys().find(|&k| *k == &id).unwrap();
+            self.record(id, stage, timing);
             self.active -= 1;
+
+            // On Ctrl-C our children have been signalled as well, so wait for
+            // them to exit rather than leaving them orphaned. Nothing further
+            // is scheduled and no more fingerprints are recorded.
+            if interrupt::interrupted() {
+                try!(self.progress.clear(&mut **config.shell()));
+                for _ in self.rx.iter().take(self.active) {}
+                return Err(human("build interrupted"))
+            }
             match result {
-                Ok(()) => {
+                Ok(fresh) => {
                     let state = &mut self.pending[(id, stage)];
                     state.amt -= 1;
                     state.fresh = state.fresh.combine(fresh);
+                    if state.counted {
+                        self.finished += 1;
+                        *self.in_flight.get_mut(&id).unwrap() -= 1;
+                    }
                     if state.amt == 0 {
                         self.queue.finish(&(id, stage), state.fresh);
                     }
                 }
                 Err(e) => {
+                    if self.keep_going {
+                        // Leave `amt` untouched so this stage never finishes
+                        // and its dependents are never scheduled.
+                        if self.pending[(id, stage)].counted {
+                            self.finished += 1;
+                            *self.in_flight.get_mut(&id).unwrap() -= 1;
+                        }
+                        errors.push(e);
+                        continue
+                    }
+                    try!(self.progress.clear(&mut **config.shell()));
                     if self.active > 0 {
                         try!(config.shell().say(
                                     "Build failed, waiting for other \
@@ -148,7 +235,36 @@ impl<'a, 'b> JobQueue<'a, 'b> {
             }
         }
 
+        try!(self.progress.clear(&mut **config.shell()));
         log!(5, "rustc jobs completed");
+        self.end = time::precise_time_ns();
+
+        // Every failure but the last is reported here along with its causes.
+        // The last one is returned as the cause of the overall error, so its
+        // causes are reported the same way.
+        match errors.pop() {
+            Some(last) => {
+                let failed = errors.len() + 1;
+                for e in errors.iter() {
+                    let mut shell = config.shell();
+                    try!(shell.error(e.description()));
+                    match util::errors::reported_cause(&**e) {
+                        Some(cause) => try!(shell.verbose(|shell| {
+                            ::handle_cause(cause, shell);
+                            Ok(())
+                        })),
+                        None => {}
+                    }
+                }
+                return Err(human_with_cause(BuildFailure,
+                                            format!("build failed; {} job{} \
+                                                     failed", failed,
+                                                    if failed == 1 {""}
+                                                    else {"s"}),
+                                            last))
+            }
+            None => {}
+        }
 
         Ok(())
     }
@@ -167,11 +283,21 @@ impl<'a, 'b> JobQueue<'a, 'b> {
         // While the jobs are all running, we maintain some metadata about how
         // many are running, the current state of freshness (of all the combined
         // jobs), and the stage to pass to finish() later on.
+        let counted = njobs > 0 && stage != StageStart &&
+                      !self.ignored.contains(&pkg.get_package_id());
         self.active += amt;
         self.pending.insert((pkg.get_package_id(), stage), PendingBuild {
             amt: amt,
-            fresh: fresh,
+            fresh: if njobs == 0 {fresh} else {Fresh},
+            counted: counted,
+            longest: 0,
         });
+        if counted {
+            match self.in_flight.entry(pkg.get_package_id()) {
+                Occupied(mut entry) => { *entry.get_mut() += njobs; }
+                Vacant(entry) => { entry.set(njobs); }
+            };
+        }
 
         let mut total_fresh = fresh.combine(self.state[pkg.get_package_id()]);
         let mut running = Vec::new();
@@ -181,8 +307,17 @@ impl<'a, 'b> JobQueue<'a, 'b> {
             let my_tx = self.tx.clone();
             let id = id.clone();
             let (desc_tx, desc_rx) = channel();
+            let unit = job.get_unit().map(|u| u.clone());
             self.pool.execute(proc() {
-                my_tx.send((id, stage, fresh, job.run(fresh, desc_tx)));
+                let start = time::precise_time_ns();
+                let result = job.run(fresh, desc_tx);
+                let timing = JobTiming {
+                    unit: unit,
+                    fresh: fresh,
+                    start: start,
+                    end: time::precise_time_ns(),
+                };
+                my_tx.send((id, stage, timing, result));
             });
             // only the first message of each job is processed
             match desc_rx.recv_opt() {
@@ -194,7 +329,10 @@ impl<'a, 'b> JobQueue<'a, 'b> {
         // If no work was scheduled, make sure that a message is actually send
         // on this channel.
         if njobs == 0 {
-            self.tx.send((id, stage, fresh, Ok(())));
+            let now = time::precise_time_ns();
+            let timing = JobTiming { unit: None, fresh: fresh, start: now,
+                                     end: now };
+            self.tx.send((id, stage, timing, Ok(fresh)));
         }
 
         // Print out some nice progress information
@@ -212,19 +350,133 @@ impl<'a, 'b> JobQueue<'a, 'b> {
         let print = print && !self.printed.contains(&pkg.get_package_id());
         if print && (stage == StageLibraries ||
                      (total_fresh == Dirty && running.len() > 0)) {
+            try!(self.progress.clear(&mut **config.shell()));
             self.printed.insert(pkg.get_package_id());
+            // In verbose output the activated features are listed as well,
+            // after they were unified across the whole dependency graph.
+            let features = self.features(pkg.get_package_id());
             match total_fresh {
                 Fresh => try!(config.shell().verbose(|c| {
-                    c.status("Fresh", pkg)
+                    c.status("Fresh", format!("{}{}", pkg, features))
                 })),
-                Dirty => try!(config.shell().status("Compiling", pkg))
+                Dirty => {
+                    try!(config.shell().verbose(|c| {
+                        c.status("Compiling", format!("{}{}", pkg, features))
+                    }));
+                    try!(config.shell().concise(|c| {
+                        c.status("Compiling", pkg)
+                    }));
+                }
             }
         }
         for msg in running.iter() {
+            try!(self.progress.clear(&mut **config.shell()));
             try!(config.shell().verbose(|c| c.status("Running", msg)));
         }
         Ok(())
     }
+
+    /// The features activated for `id`, as listed after its name in verbose
+    /// output, e.g. ` (features: bar, baz)`.
+    fn features(&self, id: &PackageId) -> String {
+        let mut features = match self.resolve.features(id) {
+            Some(features) => features.iter().map(|f| f.as_slice())
+                                      .collect::<Vec<&str>>(),
+            None => return String::new(),
+        };
+        if features.len() == 0 { return String::new() }
+        features.sort();
+        format!(" (features: {})", features.connect(", "))
+    }
+
+    /// Record how long a job of `stage` took, listing it in the timings report
+    /// if it describes what it built.
+    fn record(&mut self, id: &'a PackageId, stage: TargetStage,
+              timing: JobTiming) {
+        let duration = timing.end - timing.start;
+        {
+            let state = &mut self.pending[(id, stage)];
+            state.longest = cmp::max(state.longest, duration);
+        }
+        if self.ignored.contains(&id) { return }
+        match timing.unit {
+            Some(unit) => self.units.push(UnitTime {
+                id: id.clone(),
+                unit: unit,
+                fresh: timing.fresh,
+                start: timing.start - self.start,
+                duration: duration,
+            }),
+            None => {}
+        }
+    }
+
+    /// How long `execute` took, in nanoseconds.
+    pub fn elapsed(&self) -> u64 {
+        self.end - self.start
+    }
+
+    /// The number of units of work run by `execute`, and how many of them
+    /// were fresh.
+    pub fn unit_counts(&self) -> (uint, uint) {
+        let fresh = self.units.iter().filter(|u| u.fresh == Fresh).count();
+        (self.units.len(), fresh)
+    }
+
+    /// The packages whose build script took longer to run than the package
+    /// took to compile, along with both durations.
+    pub fn slow_build_scripts(&self) -> Vec<(&PackageId, u64, u64)> {
+        timings::slow_build_scripts(self.units.as_slice())
+    }
+
+    /// The timings of the units of work run by `execute`.
+    ///
+    /// The critical path is found by walking the graph of stages: a stage
+    /// can't start before all the stages it depends on have finished, and its
+    /// jobs run in parallel, so it adds the duration of its longest job.
+    pub fn timings(&mut self) -> Timings {
+        let mut memo = HashMap::new();
+        let keys = self.pending.keys().map(|k| *k).collect::<Vec<_>>();
+        let critical_path = keys.iter().fold(0, |max, key| {
+            cmp::max(max, self.path_length(*key, &mut memo))
+        });
+        Timings {
+            units: self.units.drain().collect(),
+            wall: self.end - self.start,
+            critical_path: critical_path,
+        }
+    }
+
+    fn path_length(&self, key: (&'a PackageId, TargetStage),
+                   memo: &mut HashMap<(&'a PackageId, TargetStage), u64>)
+                   -> u64 {
+        match memo.get(&key) {
+            Some(&length) => return length,
+            None => {}
+        }
+        let longest = match self.pending.get(&key) {
+            Some(state) => state.longest,
+            None => 0,
+        };
+        let deps = key.dependencies(&(self.resolve, self.packages));
+        let before = deps.into_iter().fold(0, |max, dep| {
+            cmp::max(max, self.path_length(dep, memo))
+        });
+        memo.insert(key, before + longest);
+        before + longest
+    }
+
+    /// Redraw the progress line with the packages which are currently being
+    /// worked on.
+    fn tick(&mut self, config: &Config) -> CargoResult<()> {
+        let mut names = self.in_flight.iter().filter(|&(_, &n)| n > 0)
+                            .map(|(id, _)| id.get_name())
+                            .collect::<Vec<&str>>();
+        names.sort();
+        try!(self.progress.tick(&mut **config.shell(), self.finished, self.total,
+                                names.connect(", ").as_slice()));
+        Ok(())
+    }
 }
 
 impl<'a> Dependency<(&'a Resolve, &'a PackageSet)>
@@ -282,8 +534,11 @@ impl<'a> Dependency<(&'a Resolve, &'a PackageSet)>
             }
 
             // Binaries only depend on libraries being available. Note that they
-            // do not depend on dev-dependencies.
-            StageBinaries => vec![(id, StageLibraries)],
+            // do not depend on dev-dependencies. They also depend on our own
+            // custom build command directly: if it ran again, anything it
+            // generated may have changed even if our libraries did not.
+            StageBinaries => vec![(id, StageRunCustomBuild),
+                                  (id, StageLibraries)],
 
             // Tests depend on all dependencies (including dev-dependencies) in
             // addition to the library stage for this package. Note, however,
@@ -291,7 +546,7 @@ impl<'a> Dependency<(&'a Resolve, &'a PackageSet)>
             // being run, not the libraries themselves.
             StageBinaryTests | StageLibraryTests => {
                 let mut base = if stage == StageBinaryTests {
-                    vec![(id, StageLibraries)]
+                    vec![(id, StageRunCustomBuild), (id, StageLibraries)]
                 } else {
                     vec![(id, StageRunCustomBuild)]
                 };
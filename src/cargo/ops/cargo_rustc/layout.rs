@@ -4,11 +4,16 @@
 //! house this logic. The current layout looks like this:
 //!
 //! ```ignore
-//! # This is the root directory for all output, the top-level package
-//! # places all of its output here.
-//! target/
+//! # Each profile gets its own root directory for all output, so that `cargo
+//! # build` and `cargo build --release` don't clobber one another. The
+//! # top-level package places its binaries here, along with copies of its
+//! # libraries under their plain names (e.g. `libfoo.rlib`).
+//! target/debug/
+//! target/release/
 //!
-//!     # This is the root directory for all output of *dependencies*
+//!     # This is the root directory for all output of *dependencies*, and
+//!     # for the libraries of the top-level package (named with their
+//!     # metadata hash).
 //!     deps/
 //!
 //!     # Root directory for all compiled examples
@@ -63,21 +68,55 @@
 //!     old-build/
 //!     old-fingerprint/
 //!     old-examples/
+//!
+//!     # Whatever is still in the old directories at the end of the build is
+//!     # deleted. This is also how the artifacts of targets which were renamed
+//!     # or removed from the manifest disappear, so the files at the top of
+//!     # this directory always belong to current targets.
+//!
+//! # Documentation is shared among all profiles and lives next to them.
+//! target/doc/
+//!
+//! # As is the output of build scripts of packages with `build-per-profile =
+//! # false`. These directories are never moved to an `old-*` directory.
+//! target/shared-build/$pkg/
+//!
+//! # Every directory created for build output is marked with this empty file,
+//! # so that packages are never discovered inside of them.
+//! target/.cargo-build-dir
 //! ```
 
-use std::io::{mod, fs, IoResult};
+use std::io::{mod, fs, File, IoResult};
 use std::io::fs::PathExtensions;
 
 use core::Package;
 use util::hex::short_hash;
 
+/// The name of the file which marks a directory as created by Cargo for build
+/// output.
+pub const BUILD_DIR_MARKER: &'static str = ".cargo-build-dir";
+
+/// Create `dir` if it doesn't exist yet and mark it as a build directory.
+pub fn mark_build_dir(dir: &Path) -> IoResult<()> {
+    if !dir.exists() {
+        try!(fs::mkdir_recursive(dir, io::USER_RWX));
+    }
+    let marker = dir.join(BUILD_DIR_MARKER);
+    if !marker.exists() {
+        try!(File::create(&marker));
+    }
+    Ok(())
+}
+
 pub struct Layout {
     root: Path,
+    doc: Path,
     deps: Path,
     native: Path,
     build: Path,
     fingerprint: Path,
     examples: Path,
+    shared_build: Path,
 
     old_deps: Path,
     old_root: Path,
@@ -99,20 +138,28 @@ impl Layout {
             Some(s) => path.push(s),
             None => {}
         }
+        // Documentation is shared between all profiles
+        let doc = path.join("doc");
+        let shared_build = path.join("shared-build");
         match dest {
             Some(s) => path.push(s),
             None => {}
         }
-        Layout::at(path)
+        let mut layout = Layout::at(path);
+        layout.doc = doc;
+        layout.shared_build = shared_build;
+        layout
     }
 
     pub fn at(root: Path) -> Layout {
         Layout {
+            doc: root.join("doc"),
             deps: root.join("deps"),
             native: root.join("native"),
             build: root.join("build"),
             fingerprint: root.join(".fingerprint"),
             examples: root.join("examples"),
+            shared_build: root.join("shared-build"),
             old_deps: root.join("old-deps"),
             old_root: root.join("old-root"),
             old_native: root.join("old-native"),
@@ -124,9 +171,7 @@ impl Layout {
     }
 
     pub fn prepare(&mut self) -> IoResult<()> {
-        if !self.root.exists() {
-            try!(fs::mkdir_recursive(&self.root, io::USER_RWX));
-        }
+        try!(mark_build_dir(&self.root));
 
         try!(old(&[
             (&self.old_deps, &self.deps),
@@ -143,6 +188,7 @@ impl Layout {
 
         for file in try!(fs::readdir(&self.root)).iter() {
             if !file.is_file() { continue }
+            if file.filename_str() == Some(BUILD_DIR_MARKER) { continue }
 
             try!(fs::rename(file, &self.old_root.join(file.filename().unwrap())));
         }
@@ -164,6 +210,7 @@ impl Layout {
     }
 
     pub fn dest<'a>(&'a self) -> &'a Path { &self.root }
+    pub fn doc<'a>(&'a self) -> &'a Path { &self.doc }
     pub fn deps<'a>(&'a self) -> &'a Path { &self.deps }
     pub fn examples<'a>(&'a self) -> &'a Path { &self.examples }
 
@@ -179,8 +226,14 @@ impl Layout {
         self.build.join(self.pkg_dir(package))
     }
 
+    /// The `OUT_DIR` of the build script of `package`, which is shared by all
+    /// profiles if the package opted into it.
     pub fn build_out(&self, package: &Package) -> Path {
-        self.build(package).join("out")
+        if package.get_manifest().is_build_per_profile() {
+            self.build(package).join("out")
+        } else {
+            self.shared_build.join(self.pkg_dir(package)).join("out")
+        }
     }
 
     pub fn old_dest<'a>(&'a self) -> &'a Path { &self.old_root }
@@ -2,21 +2,29 @@ use std::collections::{HashSet, HashMap};
 use std::dynamic_lib::DynamicLibrary;
 use std::io::{fs, USER_RWX};
 use std::io::fs::PathExtensions;
+use std::mem;
+use std::sync::{Arc, Once, ONCE_INIT};
 
 use core::{SourceMap, Package, PackageId, PackageSet, Target, Resolve};
+use core::shell::{Auto, Always, Never};
 use util::{mod, CargoResult, ProcessBuilder, CargoError, human, caused_human};
 use util::{Require, Config, internal, ChainError, Fresh, profile, join_paths};
+use util::{human_of_kind, BuildFailure};
+use util::cfg::Cfg;
 
 use self::job::{Job, Work};
 use self::job_queue as jq;
 use self::job_queue::JobQueue;
+use self::timings::Unit;
 
 pub use self::compilation::Compilation;
 pub use self::context::Context;
 pub use self::context::{PlatformPlugin, PlatformPluginAndTarget};
 pub use self::context::{PlatformRequirement, PlatformTarget};
-pub use self::layout::{Layout, LayoutProxy};
+pub use self::layout::{Layout, LayoutProxy, BUILD_DIR_MARKER, mark_build_dir};
 pub use self::custom_build::BuildOutput;
+pub use self::fingerprint::{FreshnessFs, RealFs};
+pub use self::timings::{Timings, report_timings};
 
 mod context;
 mod compilation;
@@ -26,6 +34,8 @@ mod job;
 mod job_queue;
 mod layout;
 mod links;
+mod output_depinfo;
+mod timings;
 
 #[deriving(PartialEq, Eq, Hash, Show)]
 pub enum Kind { KindHost, KindTarget }
@@ -34,6 +44,17 @@ pub enum Kind { KindHost, KindTarget }
 pub struct BuildConfig {
     pub host: TargetConfig,
     pub target: TargetConfig,
+    /// Continue building everything which doesn't depend on a failed job.
+    pub keep_going: bool,
+    /// Report how long each unit of work took once the build has finished.
+    pub timings: bool,
+    /// Extra arguments passed to rustc when compiling one target of the root
+    /// package (`cargo rustc`).
+    pub target_rustc_args: Option<(Target, Vec<String>)>,
+    /// Replaces the filesystem operations deciding whether build scripts are
+    /// fresh, only meant for tests.
+    #[doc(hidden)]
+    pub fs: Option<Arc<Box<FreshnessFs>>>,
 }
 
 #[deriving(Clone, Default)]
@@ -41,13 +62,42 @@ pub struct TargetConfig {
     pub ar: Option<String>,
     pub linker: Option<String>,
     pub overrides: HashMap<String, BuildOutput>,
+    /// Native libraries and search paths which the packages being built are
+    /// linked with, from `rustc-link-lib` and `rustc-flags`.
+    pub link_libs: Vec<String>,
+    pub link_paths: Vec<Path>,
 }
 
 /// Run `rustc` to figure out what its current version string is.
 ///
 /// The second element of the tuple returned is the target triple that rustc
 /// is a host for.
+///
+/// Spawning processes is slow on some platforms, so `rustc` is only probed
+/// the first time this is called and the answer is reused for every `Config`
+/// created afterwards in the same process. A failed probe isn't remembered.
 pub fn rustc_version() -> CargoResult<(String, String)> {
+    static mut CACHED: *const (String, String) = 0 as *const (String, String);
+    static PROBE: Once = ONCE_INIT;
+
+    let mut error = None;
+    PROBE.doit(|| {
+        match probe_rustc() {
+            Ok(info) => unsafe { CACHED = mem::transmute(box info) },
+            Err(e) => error = Some(e),
+        }
+    });
+    match error {
+        Some(e) => return Err(e),
+        None => {}
+    }
+    unsafe {
+        if CACHED.is_null() { return probe_rustc() }
+        Ok((*CACHED).clone())
+    }
+}
+
+fn probe_rustc() -> CargoResult<(String, String)> {
     let output = try!(util::process("rustc").arg("-v").arg("verbose")
                            .exec_with_output());
     let output = try!(String::from_utf8(output.output).map_err(|_| {
@@ -65,6 +115,28 @@ pub fn rustc_version() -> CargoResult<(String, String)> {
     Ok((output, triple))
 }
 
+/// Run `rustc --print cfg` to find the cfg values set when compiling for
+/// `target`, or for the host if it's `None`.
+pub fn rustc_cfg(target: Option<&str>) -> CargoResult<Vec<Cfg>> {
+    let process = util::process("rustc").arg("--print").arg("cfg");
+    let process = match target {
+        Some(target) => process.arg("--target").arg(target),
+        None => process,
+    };
+    let output = try!(process.exec_with_output());
+    let output = try!(String::from_utf8(output.output).map_err(|_| {
+        internal("rustc --print cfg didn't return utf8 output")
+    }));
+    output.as_slice().lines().map(|l| l.trim()).filter(|l| {
+        !l.is_empty()
+    }).map(|l| {
+        Cfg::parse(l).chain_error(|| {
+            internal(format!("rustc --print cfg printed an invalid line: {}",
+                             l))
+        })
+    }).collect()
+}
+
 // This is a temporary assert that ensures the consistency of the arguments
 // given the current limitations of Cargo. The long term fix is to have each
 // Target know the absolute path to the build location.
@@ -85,45 +157,60 @@ fn uniq_target_dest<'a>(targets: &[&'a Target]) -> Option<&'a str> {
 
 // Returns a mapping of the root package plus its immediate dependencies to
 // where the compiled libraries are all located.
-pub fn compile_targets<'a>(env: &str, targets: &[&'a Target], pkg: &'a Package,
+//
+// Each package of `to_build` is compiled along with the given targets, e.g.
+// every member of a workspace, all sharing one build directory. The returned
+// compilation describes the first of them.
+pub fn compile_targets<'a>(env: &str, to_build: &[(&'a Package, Vec<&'a Target>)],
                            deps: &PackageSet, resolve: &'a Resolve,
                            sources: &'a SourceMap,
                            config: &'a Config<'a>,
                            build_config: BuildConfig)
                            -> CargoResult<Compilation> {
+    let &(pkg, _) = &to_build[0];
+    let targets = to_build.iter().flat_map(|&(_, ref targets)| {
+        targets.iter().map(|t| *t)
+    }).collect::<Vec<&Target>>();
     if targets.is_empty() {
         return Ok(Compilation::new(pkg))
     }
+    let pkgs = to_build.iter().map(|&(pkg, _)| pkg).collect::<Vec<&Package>>();
 
-    debug!("compile_targets; targets={}; pkg={}; deps={}", targets, pkg, deps);
+    debug!("compile_targets; targets={}; pkgs={}; deps={}", targets, pkgs,
+           deps);
 
     try!(links::validate(deps));
 
-    let dest = uniq_target_dest(targets);
+    let dest = uniq_target_dest(targets.as_slice());
     let root = deps.iter().find(|p| p.get_package_id() == resolve.root()).unwrap();
     let host_layout = Layout::new(root, None, dest);
-    let target_layout = config.target().map(|target| {
+    let target_layout = config.target_triple().map(|target| {
         layout::Layout::new(root, Some(target), dest)
     });
 
+    let keep_going = build_config.keep_going;
+    let timings = build_config.timings;
     let mut cx = try!(Context::new(env, resolve, sources, deps, config,
                                    host_layout, target_layout, pkg,
                                    build_config));
-    let mut queue = JobQueue::new(cx.resolve, deps, cx.config);
+    let mut queue = JobQueue::new(cx.resolve, deps, cx.config, keep_going);
 
-    // First ensure that the destination directory exists
-    try!(cx.prepare(pkg));
+    // First ensure that the destination directory exists. This happens only
+    // once, as preparing the layout moves aside whatever it already holds.
+    try!(cx.prepare(pkgs.as_slice()));
 
     // Build up a list of pending jobs, each of which represent compiling a
     // particular package. No actual work is executed as part of this, that's
     // all done later as part of the `execute` function which will run
     // everything in order with proper parallelism.
     let mut compiled = HashSet::new();
-    each_dep(pkg, &cx, |dep| {
-        compiled.insert(dep.get_package_id().clone());
-    });
+    for pkg in pkgs.iter() {
+        each_dep(*pkg, &cx, |dep| {
+            compiled.insert(dep.get_package_id().clone());
+        });
+    }
     for dep in deps.iter() {
-        if dep == pkg { continue }
+        if pkgs.iter().any(|pkg| *pkg == dep) { continue }
 
         // Only compile lib targets for dependencies
         let targets = dep.get_targets().iter().filter(|target| {
@@ -131,7 +218,7 @@ pub fn compile_targets<'a>(env: &str, targets: &[&'a Target], pkg: &'a Package,
                 cx.is_relevant_target(*target)
         }).collect::<Vec<&Target>>();
 
-        if targets.len() == 0 && dep.get_package_id() != resolve.root() {
+        if targets.len() == 0 && !cx.is_primary(dep) {
             return Err(human(format!("Package `{}` has no library targets", dep)))
         }
 
@@ -139,14 +226,86 @@ pub fn compile_targets<'a>(env: &str, targets: &[&'a Target], pkg: &'a Package,
         try!(compile(targets.as_slice(), dep, compiled, &mut cx, &mut queue));
     }
 
-    try!(compile(targets, pkg, true, &mut cx, &mut queue));
+    for &(pkg, ref targets) in to_build.iter() {
+        try!(compile(targets.as_slice(), pkg, true, &mut cx, &mut queue));
+    }
 
     // Now that we've figured out everything that we're going to do, do it!
     try!(queue.execute(cx.config));
 
+    for &(pkg, ref targets) in to_build.iter() {
+        for target in targets.iter() {
+            try!(output_depinfo::output_depinfo(&cx, pkg, *target));
+        }
+    }
+
+    // Programs run against the build need the library paths of build scripts
+    // to find any dynamic libraries built there.
+    for output in cx.build_state.outputs.lock().values() {
+        for path in output.library_paths.iter() {
+            if !cx.compilation.library_paths.contains(path) {
+                cx.compilation.library_paths.push(path.clone());
+            }
+        }
+    }
+    for pkg in pkgs.iter() {
+        let id = pkg.get_package_id().clone();
+        match cx.build_state.outputs.lock().get(&(id.clone(), KindTarget)) {
+            Some(output) => {
+                cx.compilation.cfgs.insert(id, output.cfgs.clone());
+            }
+            None => {}
+        }
+    }
+
+    // A build script which is slower than the crate it builds is usually
+    // doing work which could be done once, or not at all.
+    for &(id, script, build) in queue.slow_build_scripts().iter() {
+        try!(cx.config.shell().warn(format!("the build script of `{}` took {} \
+                                             to run, longer than the {} it \
+                                             took to compile the package",
+                                            id, timings::elapsed(script),
+                                            timings::elapsed(build))));
+    }
+
+    try!(print_finished(targets.as_slice(), &queue, cx.config));
+
+    // The report itself is left to the caller, which may compile several
+    // times in one command.
+    if timings {
+        cx.compilation.timings = queue.timings();
+    }
+
     Ok(cx.compilation)
 }
 
+/// Prints the line summarizing a build, e.g. `Finished debug [unoptimized +
+/// debuginfo] target(s) in 2m 13s (87 units, 12 fresh)`. The profile is the
+/// one the requested targets are built with.
+fn print_finished(targets: &[&Target], queue: &JobQueue,
+                  config: &Config) -> CargoResult<()> {
+    let profile = match targets.iter().find(|t| {
+        !t.get_profile().is_custom_build()
+    }).or(targets.head()) {
+        Some(target) => target.get_profile(),
+        None => return Ok(()),
+    };
+    let mut flags = vec![if profile.get_opt_level() > 0 {"optimized"}
+                         else {"unoptimized"}];
+    if profile.get_debuginfo() > 0 { flags.push("debuginfo") }
+
+    let (units, fresh) = queue.unit_counts();
+    try!(config.shell().status("Finished",
+                               format!("{} [{}] target(s) in {} ({} unit{}, \
+                                        {} fresh)",
+                                       profile.get_dest().unwrap_or("debug"),
+                                       flags.connect(" + "),
+                                       timings::elapsed(queue.elapsed()),
+                                       units, if units == 1 {""} else {"s"},
+                                       fresh)));
+    Ok(())
+}
+
 fn compile<'a, 'b>(targets: &[&'a Target], pkg: &'a Package,
                    compiled: bool,
                    cx: &mut Context<'a, 'b>,
@@ -168,7 +327,7 @@ fn compile<'a, 'b>(targets: &[&'a Target], pkg: &'a Package,
     // Prepare the fingerprint directory as the first step of building a package
     let (target1, target2) = fingerprint::prepare_init(cx, pkg, KindTarget);
     let mut init = vec![(Job::new(target1, target2), Fresh)];
-    if cx.config.target().is_some() {
+    if cx.is_cross() {
         let (plugin1, plugin2) = fingerprint::prepare_init(cx, pkg, KindHost);
         init.push((Job::new(plugin1, plugin2), Fresh));
     }
@@ -199,18 +358,44 @@ fn compile<'a, 'b>(targets: &[&'a Target], pkg: &'a Package,
             (true, true, _) => &mut lib_tests,
             (false, true, _) => &mut bin_tests,
             (true, false, _) => &mut libs,
-            (false, false, _) if target.get_profile().get_env() == "test" => &mut bin_tests,
+            (false, false, _) if target.get_profile().get_env() == "test" ||
+                                 target.get_profile().get_env() == "release-test"
+                => &mut bin_tests,
             (false, false, _) => &mut bins,
         };
         for (work, kind) in work.into_iter() {
             let (freshness, dirty, fresh) =
                 try!(fingerprint::prepare_target(cx, pkg, target, kind));
+            let check = if compiled {
+                try!(fingerprint::prepare_output_check(cx, pkg, target, kind))
+            } else {
+                None
+            };
+
+            // Libraries and binaries of the top-level package are linked to a
+            // stable location under their plain name, e.g. `target/libfoo.rlib`.
+            let mut links = Vec::new();
+            if cx.is_primary(pkg) {
+                let (_, src) = cx.out_dirs(pkg, target, kind);
+                let dst = cx.layout(pkg, kind).root().clone();
+                let names = try!(cx.target_filenames(target));
+                let finals = try!(cx.target_final_filenames(target));
+                for (name, plain) in names.iter().zip(finals.iter()) {
+                    links.push((src.join(name.as_slice()),
+                                dst.join(plain.as_slice())));
+                }
+            }
 
             let dirty = proc(desc_tx: Sender<String>) {
                 try!(work(desc_tx.clone()));
+                for &(ref src, ref dst) in links.iter() {
+                    try!(util::link_or_copy(src, dst));
+                }
                 dirty(desc_tx)
             };
-            dst.push((job(dirty, fresh), freshness));
+            let work = job(dirty, fresh).check_output(check)
+                                       .unit(Unit::for_target(target));
+            dst.push((work, freshness));
         }
 
         // If this is a custom build command, we need to not only build the
@@ -230,7 +415,7 @@ fn compile<'a, 'b>(targets: &[&'a Target], pkg: &'a Package,
             PlatformPlugin => kinds.push(KindHost),
             PlatformPluginAndTarget => {
                 kinds.push(KindTarget);
-                if cx.config.target().is_some() {
+                if cx.is_cross() {
                     kinds.push(KindHost);
                 }
             }
@@ -244,7 +429,9 @@ fn compile<'a, 'b>(targets: &[&'a Target], pkg: &'a Package,
             }
             let (dirty, fresh, freshness) =
                     try!(custom_build::prepare(pkg, target, kind, cx));
-            run_custom.push((job(dirty, fresh), freshness));
+            run_custom.push((job(dirty, fresh)
+                                 .unit(Unit::build_script_run(target)),
+                             freshness));
         }
 
         // If no build scripts were run, no need to compile the build script!
@@ -268,7 +455,8 @@ fn compile<'a, 'b>(targets: &[&'a Target], pkg: &'a Package,
         }
         let (freshness, dirty, fresh) =
             try!(fingerprint::prepare_build_cmd(cx, pkg, None));
-        let desc = match build_cmds.len() {
+        let build_cmds_len = build_cmds.len();
+        let desc = match build_cmds_len {
             0 => String::new(),
             1 => pkg.get_manifest().get_build()[0].to_string(),
             _ => format!("custom build commands"),
@@ -280,9 +468,10 @@ fn compile<'a, 'b>(targets: &[&'a Target], pkg: &'a Package,
             for cmd in build_cmds.into_iter() { try!(cmd(desc_tx.clone())) }
             dirty(desc_tx)
         };
+        let mut run = job(dirty, fresh);
+        if build_cmds_len > 0 { run = run.unit(Unit::build_command()) }
         jobs.enqueue(pkg, jq::StageBuildCustomBuild, vec![]);
-        jobs.enqueue(pkg, jq::StageRunCustomBuild, vec![(job(dirty, fresh),
-                                                         freshness)]);
+        jobs.enqueue(pkg, jq::StageRunCustomBuild, vec![(run, freshness)]);
     }
 
     jobs.enqueue(pkg, jq::StageLibraries, libs);
@@ -324,15 +513,9 @@ fn compile_custom_old(pkg: &Package, cmd: &str,
     for arg in cmd {
         p = p.arg(arg);
     }
-    match cx.resolve.features(pkg.get_package_id()) {
-        Some(features) => {
-            for feat in features.iter() {
-                p = p.env(format!("CARGO_FEATURE_{}",
-                                  envify(feat.as_slice())).as_slice(),
-                          Some("1"));
-            }
-        }
-        None => {}
+    for feat in cx.features(pkg.get_package_id()).iter() {
+        p = p.env(format!("CARGO_FEATURE_{}", envify(*feat)).as_slice(),
+                  Some("1"));
     }
 
 
@@ -349,7 +532,7 @@ fn compile_custom_old(pkg: &Package, cmd: &str,
     let pkg = pkg.to_string();
 
     Ok(proc(desc_tx: Sender<String>) {
-        desc_tx.send_opt(p.to_string()).ok();
+        desc_tx.send_opt(format!("`{}`", p.to_shell_string())).ok();
         if first {
             try!(if old_output.exists() {
                 fs::rename(&old_output, &output)
@@ -362,7 +545,7 @@ fn compile_custom_old(pkg: &Package, cmd: &str,
         try!(p.exec_with_output().map(|_| ()).map_err(|mut e| {
             e.msg = format!("Failed to run custom build command for `{}`\n{}",
                             pkg, e.msg);
-            e.concrete().mark_human()
+            e.concrete().of_kind(BuildFailure).mark_human()
         }));
         Ok(())
     })
@@ -377,8 +560,7 @@ fn rustc(package: &Package, target: &Target,
     Ok(rustcs.into_iter().map(|(rustc, kind)| {
         let name = package.get_name().to_string();
         let is_path_source = package.get_package_id().get_source_id().is_path();
-        let show_warnings = package.get_package_id() == cx.resolve.root() ||
-                            is_path_source;
+        let show_warnings = cx.is_primary(package) || is_path_source;
         let rustc = if show_warnings {rustc} else {rustc.arg("-Awarnings")};
 
         // Prepare the native lib state (extra -L and -l flags)
@@ -423,13 +605,17 @@ fn rustc(package: &Package, target: &Target,
                         for name in output.library_links.iter() {
                             rustc = rustc.arg("-l").arg(name.as_slice());
                         }
+                        for cfg in output.cfgs.iter() {
+                            rustc = rustc.arg("--cfg").arg(cfg.as_slice());
+                        }
                     }
                 }
             }
 
-            desc_tx.send_opt(rustc.to_string()).ok();
+            desc_tx.send_opt(format!("`{}`", rustc.to_shell_string())).ok();
             try!(rustc.exec().chain_error(|| {
-                human(format!("Could not compile `{}`.", name))
+                human_of_kind(BuildFailure,
+                              format!("Could not compile `{}`.", name))
             }));
 
             Ok(())
@@ -446,15 +632,25 @@ fn prepare_rustc(package: &Package, target: &Target, crate_types: Vec<&str>,
 
     let target_cmd = build_plugin_args(base.clone(), cx, package, target, KindTarget);
     let plugin_cmd = build_plugin_args(base, cx, package, target, KindHost);
-    let target_cmd = try!(build_deps_args(target_cmd, target, package, cx,
-                                          KindTarget));
-    let plugin_cmd = try!(build_deps_args(plugin_cmd, target, package, cx,
-                                          KindHost));
+    let mut target_cmd = try!(build_deps_args(target_cmd, target, package, cx,
+                                              KindTarget));
+    let mut plugin_cmd = try!(build_deps_args(plugin_cmd, target, package, cx,
+                                              KindHost));
+    target_cmd = target_cmd.args(cx.config_link_args(package, target,
+                                                     KindTarget).as_slice());
+
+    match cx.rustc_args_for(package, target) {
+        Some(args) => {
+            target_cmd = target_cmd.args(args);
+            plugin_cmd = plugin_cmd.args(args);
+        }
+        None => {}
+    }
 
     Ok(match req {
         PlatformTarget => vec![(target_cmd, KindTarget)],
         PlatformPlugin => vec![(plugin_cmd, KindHost)],
-        PlatformPluginAndTarget if cx.config.target().is_none() =>
+        PlatformPluginAndTarget if !cx.is_cross() =>
             vec![(target_cmd, KindTarget)],
         PlatformPluginAndTarget => vec![(target_cmd, KindTarget),
                                         (plugin_cmd, KindHost)],
@@ -466,33 +662,58 @@ fn rustdoc(package: &Package, target: &Target,
            cx: &mut Context) -> CargoResult<Work> {
     let kind = KindTarget;
     let pkg_root = package.get_root();
-    let cx_root = cx.layout(package, kind).proxy().dest().join("doc");
+    let cx_root = cx.layout(package, kind).proxy().doc().clone();
     let rustdoc = try!(process("rustdoc", package, target, cx)).cwd(pkg_root.clone());
     let mut rustdoc = rustdoc.arg(target.get_src_path())
                          .arg("-o").arg(cx_root)
                          .arg("--crate-name").arg(target.get_name());
 
-    match cx.resolve.features(package.get_package_id()) {
-        Some(features) => {
-            for feat in features.iter() {
-                rustdoc = rustdoc.arg("--cfg").arg(format!("feature=\"{}\"", feat));
-            }
-        }
+    match cx.config.target() {
+        Some(triple) => rustdoc = rustdoc.arg("--target").arg(triple),
         None => {}
     }
 
+    for feat in cx.features(package.get_package_id()).iter() {
+        rustdoc = rustdoc.arg("--cfg").arg(format!("feature=\"{}\"", feat));
+    }
+
     let rustdoc = try!(build_deps_args(rustdoc, target, package, cx, kind));
 
     log!(5, "commands={}", rustdoc);
 
-    let primary = package.get_package_id() == cx.resolve.root();
+    // The cfgs of the package's build script are only known once it has run.
+    // The ones documented with are recorded so that the documentation is
+    // rebuilt when they change.
+    let build_state = cx.build_state.clone();
+    let id = package.get_package_id().clone();
+    let cfg_stamp = if fingerprint::has_build_script(package) {
+        Some(fingerprint::doc_cfg_loc(cx, package, target).val1())
+    } else {
+        None
+    };
+
+    let primary = cx.is_primary(package);
     let name = package.get_name().to_string();
-    let desc = rustdoc.to_string();
     Ok(proc(desc_tx: Sender<String>) {
-        desc_tx.send(desc);
+        let mut rustdoc = rustdoc;
+        let cfgs = match build_state.outputs.lock().get(&(id, kind)) {
+            Some(output) => output.cfgs.clone(),
+            None => Vec::new(),
+        };
+        for cfg in cfgs.iter() {
+            rustdoc = rustdoc.arg("--cfg").arg(cfg.as_slice());
+        }
+        match cfg_stamp {
+            Some(ref loc) => try!(fingerprint::write_doc_cfgs(loc,
+                                                              cfgs.as_slice())),
+            None => {}
+        }
+
+        desc_tx.send(format!("`{}`", rustdoc.to_shell_string()));
         if primary {
             try!(rustdoc.exec().chain_error(|| {
-                human(format!("Could not document `{}`.", name))
+                human_of_kind(BuildFailure,
+                              format!("Could not document `{}`.", name))
             }))
         } else {
             try!(rustdoc.exec_with_output().and(Ok(())).map_err(|err| {
@@ -500,9 +721,11 @@ fn rustdoc(package: &Package, target: &Target,
                     Some(output) => {
                         caused_human(format!("Could not document `{}`.\n{}",
                                              name, output), err)
+                            .concrete().of_kind(BuildFailure).mark_human()
                     }
                     None => {
                         caused_human("Failed to run rustdoc", err)
+                            .concrete().of_kind(BuildFailure).mark_human()
                     }
                 }
             }))
@@ -535,13 +758,13 @@ fn build_base_args(cx: &Context,
         let root_profile = target.get_profile();
         if root_profile.get_env() != profile.get_env() { continue }
         profile = profile.opt_level(root_profile.get_opt_level())
-                         .debug(root_profile.get_debug())
+                         .debuginfo(root_profile.get_debuginfo())
                          .rpath(root_profile.get_rpath())
     }
 
     let prefer_dynamic = profile.is_for_host() ||
                          (crate_types.contains(&"dylib") &&
-                          pkg.get_package_id() != cx.resolve.root());
+                          !cx.is_primary(pkg));
     if prefer_dynamic {
         cmd = cmd.arg("-C").arg("prefer-dynamic");
     }
@@ -556,7 +779,7 @@ fn build_base_args(cx: &Context,
     }
 
     if profile.get_debug() {
-        cmd = cmd.arg("-g");
+        cmd = cmd.arg("-C").arg(format!("debuginfo={}", profile.get_debuginfo()));
     } else {
         cmd = cmd.args(["--cfg", "ndebug"]);
     }
@@ -565,13 +788,16 @@ fn build_base_args(cx: &Context,
         cmd = cmd.arg("--test");
     }
 
-    match cx.resolve.features(pkg.get_package_id()) {
-        Some(features) => {
-            for feat in features.iter() {
-                cmd = cmd.arg("--cfg").arg(format!("feature=\"{}\"", feat));
-            }
-        }
-        None => {}
+    // By default rustc figures out on its own whether its output is a TTY, so
+    // only an explicit choice needs to be forwarded.
+    match cx.config.shell().color_config() {
+        Always => cmd = cmd.arg("--color").arg("always"),
+        Never => cmd = cmd.arg("--color").arg("never"),
+        Auto => {}
+    }
+
+    for feat in cx.features(pkg.get_package_id()).iter() {
+        cmd = cmd.arg("--cfg").arg(format!("feature=\"{}\"", feat));
     }
 
     match metadata {
@@ -592,14 +818,7 @@ fn build_base_args(cx: &Context,
 
 fn build_plugin_args(mut cmd: ProcessBuilder, cx: &Context, pkg: &Package,
                      target: &Target, kind: Kind) -> ProcessBuilder {
-    let out_dir = cx.layout(pkg, kind);
-    let out_dir = if target.get_profile().is_custom_build() {
-        out_dir.build(pkg)
-    } else if target.is_example() {
-        out_dir.examples().clone()
-    } else {
-        out_dir.root().clone()
-    };
+    let (_, out_dir) = cx.out_dirs(pkg, target, kind);
 
     cmd = cmd.arg("--out-dir");
     cmd = cmd.arg(out_dir);
@@ -677,17 +896,17 @@ fn build_deps_args(mut cmd: ProcessBuilder, target: &Target, package: &Package,
         // If this target is itself a plugin *or* if it's being linked to a
         // plugin, then we want the plugin directory. Otherwise we want the
         // target directory (hence the || here).
-        let layout = cx.layout(pkg, match kind {
+        let (_, dir) = cx.out_dirs(pkg, target, match kind {
             KindHost => KindHost,
             KindTarget if target.get_profile().is_for_host() => KindHost,
             KindTarget => KindTarget,
         });
 
-        for filename in try!(cx.target_filenames(target)).iter() {
+        for filename in try!(cx.target_link_filenames(target)).iter() {
             let mut v = Vec::new();
             v.push_all(target.get_name().as_bytes());
             v.push(b'=');
-            v.push_all(layout.root().as_vec());
+            v.push_all(dir.as_vec());
             v.push(b'/');
             v.push_all(filename.as_bytes());
             cmd = cmd.arg("--extern").arg(v.as_slice());
@@ -698,11 +917,23 @@ fn build_deps_args(mut cmd: ProcessBuilder, target: &Target, package: &Package,
 
 pub fn process<T: ToCStr>(cmd: T, pkg: &Package, target: &Target,
                           cx: &Context) -> CargoResult<ProcessBuilder> {
+    // We want to use the same environment and such as normal processes, but we
+    // want to override the dylib search path with one for the host.
+    let search_path = try!(join_paths(host_search_path(pkg, target, cx)
+                                          .as_slice(),
+                                      DynamicLibrary::envvar()));
+    Ok(try!(cx.compilation.process(cmd, pkg))
+              .env(DynamicLibrary::envvar(), Some(search_path.as_slice())))
+}
+
+/// The dynamic library search path for tools run on the host while building
+/// `target`, such as build scripts.
+pub fn host_search_path(pkg: &Package, target: &Target,
+                        cx: &Context) -> Vec<Path> {
     // When invoking a tool, we need the *host* deps directory in the dynamic
     // library search path for plugins and such which have dynamic dependencies.
     let layout = cx.layout(pkg, KindHost);
-    let mut search_path = DynamicLibrary::search_path();
-    search_path.push(layout.deps().clone());
+    let mut search_path = vec![layout.deps().clone()];
 
     // OLD-BUILD: to-remove
     // Also be sure to pick up any native build directories required by plugins
@@ -717,13 +948,8 @@ pub fn process<T: ToCStr>(cmd: T, pkg: &Package, target: &Target,
         });
     }
     search_path.extend(native_search_paths.into_iter());
-
-    // We want to use the same environment and such as normal processes, but we
-    // want to override the dylib search path with the one we just calculated.
-    let search_path = try!(join_paths(search_path.as_slice(),
-                                      DynamicLibrary::envvar()));
-    Ok(try!(cx.compilation.process(cmd, pkg))
-              .env(DynamicLibrary::envvar(), Some(search_path.as_slice())))
+    search_path.push_all(DynamicLibrary::search_path().as_slice());
+    search_path
 }
 
 fn each_dep<'a>(pkg: &Package, cx: &'a Context, f: |&'a Package|) {
@@ -0,0 +1,109 @@
+use std::collections::HashSet;
+use std::io::File;
+
+use core::{Package, Target};
+use util::{CargoResult, human, ChainError};
+
+use super::{Kind, KindHost, KindTarget, fingerprint};
+use super::context::Context;
+
+/// Writes a Makefile-style dependency file next to the final artifacts of
+/// `target`, e.g. `target/libfoo.d` for `target/libfoo.rlib`.
+///
+/// The file lists everything which, when changed, makes Cargo rebuild the
+/// artifacts: the sources rustc read for the target and for every target it's
+/// built from, the manifests of their packages, and the files their build
+/// scripts declared with `cargo:rerun-if-changed`. This lets an outer build
+/// system know when it needs to invoke Cargo again.
+pub fn output_depinfo(cx: &Context, pkg: &Package,
+                      target: &Target) -> CargoResult<()> {
+    let profile = target.get_profile();
+    if profile.is_doc() || profile.is_custom_build() { return Ok(()) }
+
+    let kind = if profile.is_for_host() {KindHost} else {KindTarget};
+    let artifacts = try!(cx.target_final_filenames(target));
+    if artifacts.len() == 0 { return Ok(()) }
+
+    let mut visited = HashSet::new();
+    let mut deps = Vec::new();
+    try!(add_deps(cx, pkg, target, kind, &mut visited, &mut deps));
+    let mut deps = deps.iter().map(|d| escape(d)).collect::<Vec<String>>();
+    deps.sort();
+    deps.dedup();
+
+    let root = cx.layout(pkg, kind).root().clone();
+    let loc = root.join(artifacts[0].as_slice()).with_extension("d");
+    let artifacts = artifacts.iter().map(|a| {
+        escape(&root.join(a.as_slice()))
+    }).collect::<Vec<String>>();
+    File::create(&loc).write_str(format!("{}: {}\n", artifacts.connect(" "),
+                                         deps.connect(" ")).as_slice())
+        .chain_error(|| {
+            human(format!("failed to write dependency file `{}`", loc.display()))
+        })
+}
+
+/// Adds the inputs of `target` and of everything it's built from to `deps`.
+fn add_deps(cx: &Context, pkg: &Package, target: &Target, kind: Kind,
+            visited: &mut HashSet<Path>, deps: &mut Vec<Path>)
+            -> CargoResult<()> {
+    let (_, dep_info) = fingerprint::dep_info_loc(cx, pkg, target, kind);
+    if !visited.insert(dep_info.clone()) { return Ok(()) }
+
+    match try!(fingerprint::parse_dep_info(&dep_info)) {
+        Some(files) => {
+            deps.extend(files.iter().map(|f| pkg.get_root().join(f.as_slice())));
+        }
+        None => {}
+    }
+    deps.push(pkg.get_manifest_path().clone());
+    match cx.build_state.outputs.lock().get(&(pkg.get_package_id().clone(),
+                                              kind)) {
+        Some(output) => {
+            deps.extend(output.rerun_if_changed.iter().map(|f| {
+                pkg.get_root().join(f)
+            }));
+        }
+        None => {}
+    }
+
+    // Binaries and tests are built from the library of their own package, and
+    // every target from the build script of its package.
+    let profile = target.get_profile();
+    if !target.is_lib() || profile.is_test() {
+        let lib = pkg.get_targets().iter().find(|t| {
+            t.is_lib() && !t.get_profile().is_custom_build() &&
+                cx.is_relevant_target(*t)
+        });
+        match lib {
+            Some(lib) => try!(add_deps(cx, pkg, lib, kind, visited, deps)),
+            None => {}
+        }
+    }
+    if !profile.is_custom_build() {
+        let build = pkg.get_targets().iter().find(|t| {
+            t.get_profile().is_custom_build()
+        });
+        match build {
+            Some(build) => try!(add_deps(cx, pkg, build, KindHost, visited,
+                                         deps)),
+            None => {}
+        }
+    }
+
+    for &(dep, t) in cx.dep_targets(pkg, target).iter() {
+        let kind = match kind {
+            KindTarget if !t.get_profile().is_for_host() => KindTarget,
+            _ => KindHost,
+        };
+        try!(add_deps(cx, dep, t, kind, visited, deps));
+    }
+    Ok(())
+}
+
+/// Escapes a path for a Makefile rule, where a space separates two paths, `$`
+/// starts a variable reference and `#` a comment.
+fn escape(path: &Path) -> String {
+    path.display().to_string().replace("$", "$$").replace("#", "\\#")
+        .replace(" ", "\\ ")
+}
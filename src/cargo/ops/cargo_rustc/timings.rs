@@ -0,0 +1,226 @@
+//! Bookkeeping for `--timings`: how long each unit of work in a build took.
+//!
+//! The job queue records a `UnitTime` for every job which describes itself
+//! with a `Unit`. The timings of a build are collected in its `Compilation`,
+//! and once the command has built everything `report_timings` prints them as
+//! a table and writes the raw data out as JSON for other tools to consume.
+
+use std::cmp::Equal;
+use std::io::File;
+use serialize::json;
+use term::color::BLACK;
+
+use core::{PackageId, Target};
+use util::{CargoResult, Config, Freshness, Fresh, Dirty, human, ChainError};
+
+/// Describes what a job builds, as listed in the timings report.
+#[deriving(Clone)]
+pub struct Unit {
+    /// The kind and name of the target, e.g. `lib`, `bin foo` or `test foo`.
+    pub target: String,
+    /// The profile the target is built with, e.g. `compile` or `test`.
+    pub profile: String,
+}
+
+impl Unit {
+    /// The unit compiling (or documenting) `target`.
+    pub fn for_target(target: &Target) -> Unit {
+        let profile = target.get_profile();
+        let name = if profile.is_custom_build() {
+            "build script".to_string()
+        } else if profile.is_test() {
+            let kind = if profile.get_env() == "bench" {"bench"} else {"test"};
+            format!("{} {}", kind, target.get_name())
+        } else if target.is_lib() {
+            "lib".to_string()
+        } else if target.is_example() {
+            format!("example {}", target.get_name())
+        } else {
+            format!("bin {}", target.get_name())
+        };
+        Unit { target: name, profile: profile.get_env().to_string() }
+    }
+
+    /// The unit running the build script `target`.
+    pub fn build_script_run(target: &Target) -> Unit {
+        Unit {
+            target: "build script (run)".to_string(),
+            profile: target.get_profile().get_env().to_string(),
+        }
+    }
+
+    /// Whether this unit runs a build script.
+    pub fn is_build_script_run(&self) -> bool {
+        self.target.as_slice() == "build script (run)"
+    }
+
+    /// Whether this unit compiles or runs a build script or build command.
+    pub fn is_build_script(&self) -> bool {
+        self.target.as_slice().starts_with("build ")
+    }
+
+    /// The unit running the old-style `build` commands of a package.
+    pub fn build_command() -> Unit {
+        Unit { target: "build command".to_string(), profile: String::new() }
+    }
+}
+
+/// How long one unit took to build.
+pub struct UnitTime {
+    pub id: PackageId,
+    pub unit: Unit,
+    /// Whether the unit was fresh, in which case nothing was rebuilt.
+    pub fresh: Freshness,
+    /// When the unit started, in nanoseconds since the build started.
+    pub start: u64,
+    /// How long the unit took, in nanoseconds.
+    pub duration: u64,
+}
+
+/// The timings of a whole build.
+pub struct Timings {
+    pub units: Vec<UnitTime>,
+    /// Wall-clock time from the start to the end of the build.
+    pub wall: u64,
+    /// Length of the longest chain of units which had to run one after
+    /// another, which bounds the build time however many jobs are used.
+    pub critical_path: u64,
+}
+
+impl Timings {
+    pub fn new() -> Timings {
+        Timings { units: Vec::new(), wall: 0, critical_path: 0 }
+    }
+}
+
+/// Build scripts which ran for at least this long, in nanoseconds, are
+/// compared with the crate they build. Shorter runs are never worth a warning.
+static SLOW_BUILD_SCRIPT: u64 = 1_000_000_000;
+
+/// Finds the packages whose build script took longer to run than compiling
+/// the targets of the package itself, returning how long each of the two took.
+/// Only units which actually ran are considered.
+pub fn slow_build_scripts(units: &[UnitTime]) -> Vec<(&PackageId, u64, u64)> {
+    units.iter().filter(|u| {
+        u.fresh == Dirty && u.unit.is_build_script_run() &&
+            u.duration >= SLOW_BUILD_SCRIPT
+    }).filter_map(|script| {
+        let build = units.iter().filter(|u| {
+            u.id == script.id && u.fresh == Dirty && !u.unit.is_build_script()
+        }).fold(0, |sum, u| sum + u.duration);
+        if build > 0 && script.duration > build {
+            Some((&script.id, script.duration, build))
+        } else {
+            None
+        }
+    }).collect()
+}
+
+#[deriving(Encodable)]
+struct EncodableTimings {
+    wall_time: f64,
+    unit_time: f64,
+    critical_path: f64,
+    units: Vec<EncodableUnitTime>,
+}
+
+#[deriving(Encodable)]
+struct EncodableUnitTime {
+    package: String,
+    target: String,
+    profile: String,
+    fresh: bool,
+    start: f64,
+    duration: f64,
+}
+
+/// Prints the timings of a build, slowest units first, and writes them to
+/// `dst` as JSON. Times in the JSON file are in seconds.
+///
+/// Units run in parallel, so the sum of their durations, which is reported
+/// along with the wall time, is typically much larger than it.
+pub fn report_timings(timings: &Timings, config: &Config, dst: &Path)
+                      -> CargoResult<()> {
+    let mut units = timings.units.iter().collect::<Vec<&UnitTime>>();
+    units.sort_by(|a, b| {
+        match b.duration.cmp(&a.duration) {
+            Equal => (&a.id, &a.unit.target).cmp(&(&b.id, &b.unit.target)),
+            ord => ord,
+        }
+    });
+    let unit_time = timings.units.iter().fold(0, |sum, u| sum + u.duration);
+
+    let ids = units.iter().map(|u| u.id.to_string()).collect::<Vec<String>>();
+    let id_width = ids.iter().fold(0, |w, s| if s.len() > w {s.len()} else {w});
+    let target_width = units.iter().fold(0, |w, u| {
+        if u.unit.target.len() > w {u.unit.target.len()} else {w}
+    });
+
+    let mut shell = config.shell();
+    try!(shell.say("Timings:", BLACK));
+    for (unit, id) in units.iter().zip(ids.iter()) {
+        let line = format!("{:>9}  {}  {}  {}{}", secs(unit.duration),
+                           pad(id.as_slice(), id_width),
+                           pad(unit.unit.target.as_slice(), target_width),
+                           unit.unit.profile,
+                           if unit.fresh == Fresh {" (fresh)"} else {""});
+        try!(shell.say(line.as_slice().trim_right(), BLACK));
+    }
+    try!(shell.say(format!("total wall time: {}, sum of unit times: {}, \
+                            critical path: {}", secs(timings.wall),
+                           secs(unit_time), secs(timings.critical_path)),
+                   BLACK));
+
+    let encodable = EncodableTimings {
+        wall_time: seconds(timings.wall),
+        unit_time: seconds(unit_time),
+        critical_path: seconds(timings.critical_path),
+        units: timings.units.iter().map(|u| {
+            EncodableUnitTime {
+                package: u.id.to_string(),
+                target: u.unit.target.clone(),
+                profile: u.unit.profile.clone(),
+                fresh: u.fresh == Fresh,
+                start: seconds(u.start),
+                duration: seconds(u.duration),
+            }
+        }).collect(),
+    };
+    try!(File::create(dst).write_str(json::encode(&encodable).as_slice())
+                          .chain_error(|| {
+        human(format!("failed to write build timings to `{}`", dst.display()))
+    }));
+    Ok(())
+}
+
+/// Formats a duration for people, e.g. `0.52s`, `2m 13s` or `1h 05m`.
+pub fn elapsed(ns: u64) -> String {
+    let total = ns / 1_000_000_000;
+    if total < 60 {
+        secs(ns)
+    } else if total < 3600 {
+        format!("{}m {:02}s", total / 60, total % 60)
+    } else {
+        format!("{}h {:02}m", total / 3600, total % 3600 / 60)
+    }
+}
+
+fn seconds(ns: u64) -> f64 { ns as f64 / 1e9 }
+
+fn secs(ns: u64) -> String { format!("{:.2}s", seconds(ns)) }
+
+fn pad(s: &str, width: uint) -> String {
+    format!("{}{}", s, " ".repeat(width - s.len()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::elapsed;
+
+    #[test]
+    fn elapsed_is_readable() {
+        assert_eq!(elapsed(520_000_000).as_slice(), "0.52s");
+        assert_eq!(elapsed(133_000_000_000).as_slice(), "2m 13s");
+        assert_eq!(elapsed(3_900_000_000_000).as_slice(), "1h 05m");
+    }
+}
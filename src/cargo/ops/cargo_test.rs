@@ -1,6 +1,8 @@
+use std::io::{stdio, File, TempDir};
 use std::os;
+use term::color::BLACK;
 
-use core::Source;
+use core::{MultiShell, Source};
 use sources::PathSource;
 use ops;
 use util::{CargoResult, ProcessError};
@@ -9,93 +11,293 @@ pub struct TestOptions<'a> {
     pub compile_opts: ops::CompileOptions<'a>,
     pub no_run: bool,
     pub name: Option<&'a str>,
+    pub lib: bool,
+    /// Names of the integration tests to run.
+    pub tests: &'a [String],
+    pub doc: bool,
+}
+
+/// The outcome of running a single test target.
+struct TargetSummary {
+    name: String,
+    success: bool,
+    passed: uint,
+    failed: uint,
+    ignored: uint,
+    failures: Vec<String>,
+}
+
+impl TargetSummary {
+    fn new(name: String, success: bool) -> TargetSummary {
+        TargetSummary {
+            name: name,
+            success: success,
+            passed: 0,
+            failed: 0,
+            ignored: 0,
+            failures: Vec::new(),
+        }
+    }
+
+    /// Fills in the counts from a libtest `--logfile`. Targets which never
+    /// wrote a log (a custom harness, or a harness which crashed) are counted
+    /// as a single test which passed or failed with the process.
+    fn read_log(&mut self, log: Option<&Path>) {
+        let contents = match log {
+            Some(log) => File::open(log).read_to_string().ok(),
+            None => None,
+        };
+        let contents = match contents {
+            Some(contents) => contents,
+            None => {
+                if self.success { self.passed += 1 } else { self.failed += 1 }
+                return
+            }
+        };
+
+        for line in contents.as_slice().lines() {
+            let mut parts = line.splitn(1, ' ');
+            match (parts.next(), parts.next()) {
+                (Some(result), Some(test)) => self.add(result, test),
+                _ => {}
+            }
+        }
+
+        if !self.success && self.failed == 0 { self.failed += 1 }
+    }
+
+    /// Fills in the counts from what libtest printed, e.g.
+    /// `test foo ... FAILED`, for a target which couldn't be given a log.
+    fn read_output(&mut self, lines: &[String]) {
+        for line in lines.iter() {
+            let line = line.as_slice();
+            if !line.starts_with("test ") { continue }
+            let line = line.slice_from(5);
+            let at = match line.find_str(" ... ") {
+                Some(at) => at,
+                None => continue,
+            };
+            let result = match line.slice_from(at + 5) {
+                "FAILED" => "failed",
+                "ignored" => "ignored",
+                _ => "ok",
+            };
+            self.add(result, line.slice_to(at));
+        }
+
+        if !self.success && self.failed == 0 { self.failed += 1 }
+    }
+
+    fn add(&mut self, result: &str, test: &str) {
+        match result {
+            "failed" => {
+                self.failed += 1;
+                self.failures.push(test.to_string());
+            }
+            "ignored" => self.ignored += 1,
+            // "ok", along with the measurements printed for benchmarks
+            _ => self.passed += 1,
+        }
+    }
 }
 
 pub fn run_tests(manifest_path: &Path,
                  options: &mut TestOptions,
                  test_args: &[String]) -> CargoResult<Option<ProcessError>> {
+    run(manifest_path, options, test_args, &[])
+}
+
+/// Builds and runs the test targets, passing `test_args` to all of them and
+/// `harness_args` only to those built with the libtest harness.
+fn run(manifest_path: &Path,
+       options: &mut TestOptions,
+       test_args: &[String],
+       harness_args: &[String]) -> CargoResult<Option<ProcessError>> {
     let mut source = try!(PathSource::for_path(&manifest_path.dir_path()));
     try!(source.update());
 
     let mut compile = try!(ops::compile(manifest_path, &mut options.compile_opts));
     if options.no_run { return Ok(None) }
-    compile.tests.sort();
-
-    let target_name = options.name;
-    let mut tests_to_run = compile.tests.iter().filter(|&&(ref test_name, _)| {
-        target_name.map_or(true, |target_name| target_name == test_name.as_slice())
+    compile.tests.sort_by(|&(_, ref a, ref a_exe), &(_, ref b, ref b_exe)| {
+        (a.get_name(), a_exe).cmp(&(b.get_name(), b_exe))
     });
 
+    // Selecting any phase explicitly runs only the selected phases, otherwise
+    // everything is run.
+    let filtered = options.lib || options.doc || options.name.is_some() ||
+                   options.tests.len() > 0;
+    let (target_name, lib, tests) = (options.name, options.lib, options.tests);
+    let tests_to_run = compile.tests.iter().filter(|&&(_, ref target, _)| {
+        !filtered || (lib && target.is_lib()) ||
+            target_name == Some(target.get_name()) ||
+            (!target.is_lib() && tests.iter().any(|name| {
+                name.as_slice() == target.get_name()
+            }))
+    }).collect::<Vec<_>>();
+
+    // Benchmarks have no doc tests to fall back on, so running none at all is
+    // worth pointing out.
+    if tests_to_run.is_empty() && options.compile_opts.env == "bench" {
+        let msg = match target_name {
+            Some(name) => format!("no benchmarks to run, no benchmark matches \
+                                   the filter `{}`", name),
+            None => format!("no benchmarks to run, no target of `{}` is \
+                             benchmarked", compile.package.get_name()),
+        };
+        try!(options.compile_opts.shell.warn(msg));
+    }
+
+    let logs = try!(TempDir::new("cargo-test"));
+    let mut summaries = Vec::new();
+    let mut first_error = None;
+
     let cwd = os::getcwd();
-    for &(_, ref exe) in tests_to_run {
+    // Each test runs in the directory of the package it belongs to, which
+    // differs between the members of a workspace.
+    for &&(ref pkg, ref target, ref exe) in tests_to_run.iter() {
+        let harness = target.get_profile().uses_test_harness();
         let to_display = match exe.path_relative_from(&cwd) {
             Some(path) => path,
             None => exe.clone(),
         };
-        let cmd = try!(compile.process(exe, &compile.package)).args(test_args);
+        let log = logs.path().join(format!("{}.log", summaries.len()));
+        let mut cmd = try!(compile.process(exe, pkg)).args(test_args);
+        if harness {
+            cmd = cmd.args(harness_args).arg("--logfile").arg(&log);
+        }
         try!(options.compile_opts.shell.concise(|shell| {
             shell.status("Running", to_display.display().to_string())
         }));
         try!(options.compile_opts.shell.verbose(|shell| {
             shell.status("Running", cmd.to_string())
         }));
-        match cmd.exec() {
-            Ok(()) => {}
-            Err(e) => return Ok(Some(e))
-        }
+        let result = cmd.exec();
+        let mut summary = TargetSummary::new(target.get_name().to_string(),
+                                             result.is_ok());
+        summary.read_log(if harness {Some(&log)} else {None});
+        summaries.push(summary);
+        if first_error.is_none() { first_error = result.err() }
     }
 
-    if options.name.is_some() { return Ok(None) }
+    let run_doctests = !filtered || options.doc;
+    if run_doctests && options.compile_opts.env != "bench" {
+        // Every package which was built has its library's doc tests run,
+        // e.g. each member of a workspace.
+        let mut libs = Vec::new();
+        for pkg in compile.packages.iter() {
+            for target in pkg.get_targets().iter() {
+                if target.get_profile().is_doctest() && target.is_lib() {
+                    libs.push((pkg, target));
+                }
+            }
+        }
 
-    if options.compile_opts.env == "bench" { return Ok(None) }
+        for &(pkg, target) in libs.iter() {
+            let name = target.get_name();
+            try!(options.compile_opts.shell.status("Doc-tests", name));
+            let log = logs.path().join(format!("{}.log", summaries.len()));
+            let mut p = try!(compile.process("rustdoc", pkg))
+                               .arg("--test").arg(target.get_src_path())
+                               .arg("--crate-name").arg(name)
+                               .arg("-L").arg(&compile.root_output)
+                               .arg("-L").arg(&compile.deps_output);
 
-    let mut libs = compile.package.get_targets().iter().filter_map(|target| {
-        if !target.get_profile().is_doctest() || !target.is_lib() {
-            return None
-        }
-        Some((target.get_src_path(), target.get_name()))
-    });
+            let id = pkg.get_package_id();
+            for feat in compile.features.get(id).into_iter()
+                               .flat_map(|f| f.iter()) {
+                p = p.arg("--cfg").arg(format!("feature=\"{}\"", feat));
+            }
+            for cfg in compile.cfgs.get(id).into_iter()
+                              .flat_map(|c| c.iter()) {
+                p = p.arg("--cfg").arg(cfg.as_slice());
+            }
 
-    for (lib, name) in libs {
-        try!(options.compile_opts.shell.status("Doc-tests", name));
-        let mut p = try!(compile.process("rustdoc", &compile.package))
-                           .arg("--test").arg(lib)
-                           .arg("--crate-name").arg(name)
-                           .arg("-L").arg(&compile.root_output)
-                           .arg("-L").arg(&compile.deps_output)
-                           .cwd(compile.package.get_root());
-
-        // FIXME(rust-lang/rust#16272): this should just always be passed.
-        if test_args.len() > 0 {
-            p = p.arg("--test-args").arg(test_args.connect(" "));
-        }
+            // FIXME(rust-lang/rust#16272): this should just always be passed.
+            if test_args.len() > 0 {
+                p = p.arg("--test-args").arg(test_args.connect(" "));
+            }
 
-        for (pkg, libs) in compile.libraries.iter() {
-            for lib in libs.iter() {
-                let mut arg = pkg.get_name().as_bytes().to_vec();
-                arg.push(b'=');
-                arg.push_all(lib.as_vec());
-                p = p.arg("--extern").arg(arg.as_slice());
+            // rustdoc splits its test arguments on whitespace, so a log can
+            // only be asked for if its path has none. Otherwise the results
+            // are read from the output of the tests.
+            let log_path = log.display().to_string();
+            let spaces = log_path.as_slice().chars().any(|c| c.is_whitespace());
+            let log = if spaces {
+                None
+            } else {
+                p = p.arg("--test-args").arg("--logfile")
+                     .arg("--test-args").arg(log_path);
+                Some(log)
+            };
+
+            for (pkg, libs) in compile.libraries.iter() {
+                for lib in libs.iter() {
+                    let mut arg = pkg.get_name().as_bytes().to_vec();
+                    arg.push(b'=');
+                    arg.push_all(lib.as_vec());
+                    p = p.arg("--extern").arg(arg.as_slice());
+                }
             }
-        }
 
-        try!(options.compile_opts.shell.verbose(|shell| {
-            shell.status("Running", p.to_string())
-        }));
-        match p.exec() {
-            Ok(()) => {}
-            Err(e) => return Ok(Some(e)),
+            try!(options.compile_opts.shell.verbose(|shell| {
+                shell.status("Running", format!("`{}`", p.to_shell_string()))
+            }));
+            let mut lines = Vec::new();
+            let result = match log {
+                Some(..) => p.exec(),
+                None => p.exec_with_streaming(|line| {
+                    println!("{}", line);
+                    lines.push(line.to_string());
+                }).map(|output| {
+                    stdio::stderr().write(output.error.as_slice()).ok();
+                }),
+            };
+            let mut summary = TargetSummary::new(format!("doc-tests {}", name),
+                                                 result.is_ok());
+            match log {
+                Some(ref log) => summary.read_log(Some(log)),
+                None => summary.read_output(lines.as_slice()),
+            }
+            summaries.push(summary);
+            if first_error.is_none() { first_error = result.err() }
         }
     }
 
-    Ok(None)
+    if summaries.len() > 0 {
+        try!(print_summary(&mut *options.compile_opts.shell, summaries.as_slice()));
+    }
+
+    Ok(first_error)
 }
 
+fn print_summary(shell: &mut MultiShell, summaries: &[TargetSummary])
+                 -> CargoResult<()> {
+    let failed = summaries.iter().filter(|s| !s.success).count();
+    try!(shell.say("", BLACK));
+    try!(shell.status("Summary", if failed == 0 {
+        format!("PASS, all {} test targets passed", summaries.len())
+    } else {
+        format!("FAIL, {} of {} test targets failed", failed, summaries.len())
+    }));
+    for summary in summaries.iter() {
+        let status = if summary.success {"Passed"} else {"Failed"};
+        try!(shell.status(status, format!("{} ({} passed; {} failed; {} ignored)",
+                                          summary.name, summary.passed,
+                                          summary.failed, summary.ignored)));
+    }
+    for summary in summaries.iter() {
+        for test in summary.failures.iter() {
+            try!(shell.status("Failing", format!("{}: {}", summary.name, test)));
+        }
+    }
+    Ok(())
+}
+
+/// Runs the benchmarks like `run_tests` runs tests. Benchmarks without the
+/// libtest harness are run with only the arguments given on the command line,
+/// as libtest's `--bench` flag would mean nothing to them.
 pub fn run_benches(manifest_path: &Path,
                    options: &mut TestOptions,
                    args: &[String]) -> CargoResult<Option<ProcessError>> {
-    let mut args = args.to_vec();
-    args.push("--bench".to_string());
-
-    run_tests(manifest_path, options, args.as_slice())
+    run(manifest_path, options, args, &["--bench".to_string()])
 }
@@ -0,0 +1,24 @@
+use core::SourceId;
+use ops;
+use util::{CargoResult, human_of_kind, InvalidManifest};
+
+/// Checks that the manifest at `manifest_path` describes a package Cargo can
+/// load, without resolving its dependencies or touching the network.
+///
+/// The manifest is parsed, decoded and validated, and its targets are
+/// inferred from the layout of the package, which is all that is read of it.
+pub fn verify_project(manifest_path: &Path) -> CargoResult<()> {
+    let source_id = try!(SourceId::for_path(&manifest_path.dir_path()));
+    let (pkg, _) = try!(ops::read_package(manifest_path, &source_id));
+
+    let has_targets = pkg.get_targets().iter().any(|t| {
+        !t.get_profile().is_custom_build()
+    });
+    if !pkg.is_workspace_root() && !has_targets {
+        return Err(human_of_kind(InvalidManifest,
+                                 "no targets specified in the manifest\n\
+                                  either src/lib.rs, src/main.rs, a [lib] \
+                                  section, or [[bin]] section must be present"))
+    }
+    Ok(())
+}
@@ -0,0 +1,94 @@
+//! A facade over the operations for programs which drive cargo as a library.
+//!
+//! Each function takes the path to a manifest along with an options structure
+//! or the shell to report progress to, and returns a structured description
+//! of what it did instead of printing it. Human output only ever goes through
+//! the `MultiShell`, so an embedder can capture or discard it by creating the
+//! shell around writers of its own.
+
+use core::{MultiShell, PackageId, Resolve, Source, Target};
+use core::registry::PackageRegistry;
+use ops::{mod, CompileOptions};
+use sources::PathSource;
+use util::{CargoResult, Config};
+
+/// What `build` produced.
+pub struct CompileResult {
+    /// Every file the build produced, along with the package and target it
+    /// was built from, at the location it should be used from. Build scripts
+    /// are not included.
+    pub artifacts: Vec<(PackageId, Target, Path)>,
+}
+
+impl CompileResult {
+    /// Returns the paths of the files built for the package `name`.
+    pub fn artifacts_of(&self, name: &str) -> Vec<&Path> {
+        self.artifacts.iter().filter(|&&(ref id, _, _)| {
+            id.get_name() == name
+        }).map(|&(_, _, ref path)| path).collect()
+    }
+}
+
+/// What `resolve` found.
+pub struct ResolveResult {
+    /// The package whose manifest was resolved.
+    pub root: PackageId,
+    /// Every package in the dependency graph, the root included, sorted.
+    pub packages: Vec<PackageId>,
+    /// The dependency graph itself, as written to the lockfile.
+    pub resolve: Resolve,
+}
+
+/// What `package` created.
+pub struct PackageResult {
+    /// The package which was packaged.
+    pub package: PackageId,
+    /// The path of the `.crate` tarball.
+    pub tarball: Path,
+}
+
+/// Builds the package at `manifest_path`, as `cargo build` does.
+pub fn build(manifest_path: &Path, options: &mut CompileOptions)
+             -> CargoResult<CompileResult> {
+    let compilation = try!(ops::compile(manifest_path, options));
+    Ok(CompileResult { artifacts: compilation.artifacts })
+}
+
+/// Resolves the dependencies of the package at `manifest_path` and updates
+/// its lockfile, as `cargo generate-lockfile` does. The resolved packages are
+/// not downloaded; use `compile` (or `cargo fetch`) for that.
+pub fn resolve(manifest_path: &Path, shell: &mut MultiShell)
+               -> CargoResult<ResolveResult> {
+    let manifest_path = try!(ops::find_workspace_root(manifest_path))
+                            .unwrap_or(manifest_path.clone());
+    let mut source = try!(PathSource::for_path(&manifest_path.dir_path()));
+    try!(source.update());
+    let package = try!(source.get_root_package());
+
+    let mut config = try!(Config::new(shell, None, None));
+    let mut registry = PackageRegistry::new(&mut config);
+    let resolve = try!(ops::resolve_pkg(&mut registry, &package));
+
+    let mut packages = resolve.iter().map(|id| id.clone()).collect::<Vec<_>>();
+    packages.sort();
+    Ok(ResolveResult {
+        root: resolve.root().clone(),
+        packages: packages,
+        resolve: resolve,
+    })
+}
+
+/// Packages the package at `manifest_path` for uploading, as `cargo package`
+/// does, verifying that the tarball builds if `verify` is set.
+pub fn package(manifest_path: &Path, shell: &mut MultiShell, verify: bool)
+               -> CargoResult<PackageResult> {
+    let mut source = try!(PathSource::for_path(&manifest_path.dir_path()));
+    try!(source.update());
+    let package = try!(source.get_root_package());
+
+    let tarball = try!(ops::package(manifest_path, shell, verify));
+    Ok(PackageResult {
+        package: package.get_package_id().clone(),
+        tarball: tarball,
+    })
+}
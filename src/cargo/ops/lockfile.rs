@@ -4,9 +4,14 @@ use serialize::{Encodable, Decodable};
 use toml::{mod, Encoder};
 
 use core::{Resolve, resolver, Package, SourceId};
-use util::CargoResult;
+use util::{CargoResult, human_of_kind, InvalidManifest};
 use util::toml as cargo_toml;
 
+/// The format version written to the top of new lockfiles. Lockfiles without
+/// a `version` key predate it and are read as version 1, which has the same
+/// layout.
+static LOCKFILE_VERSION: i64 = 1;
+
 pub fn load_pkg_lockfile(pkg: &Package) -> CargoResult<Option<Resolve>> {
     let lockfile = pkg.get_manifest_path().dir_path().join("Cargo.lock");
     let source_id = pkg.get_package_id().get_source_id();
@@ -22,12 +27,40 @@ pub fn load_lockfile(path: &Path, sid: &SourceId) -> CargoResult<Option<Resolve>
 
     let s = try!(f.read_to_string());
 
-    let table = toml::Table(try!(cargo_toml::parse(s.as_slice(), path)));
-    let mut d = toml::Decoder::new(table);
-    let v: resolver::EncodableResolve = Decodable::decode(&mut d).unwrap();
+    let mut table = try!(cargo_toml::parse(s.as_slice(), path));
+    try!(check_version(table.remove(&"version".to_string()), path));
+    let mut d = toml::Decoder::new(toml::Table(table));
+    let v: resolver::EncodableResolve = match Decodable::decode(&mut d) {
+        Ok(v) => v,
+        Err(e) => return Err(human_of_kind(InvalidManifest,
+                                           format!("failed to parse lock file \
+                                                    at: {}\n\n{}",
+                                                   path.display(), e))),
+    };
     Ok(Some(try!(v.to_resolve(sid))))
 }
 
+fn check_version(version: Option<toml::Value>, path: &Path) -> CargoResult<()> {
+    match version {
+        None => Ok(()),
+        Some(toml::Integer(n)) if n >= 1 && n <= LOCKFILE_VERSION => Ok(()),
+        Some(toml::Integer(n)) if n > LOCKFILE_VERSION => {
+            Err(human_of_kind(InvalidManifest,
+                              format!("lockfile at {} was created by a newer \
+                                       cargo (format version {}, but only \
+                                       versions up to {} are supported)\n\
+                                       upgrade cargo or delete the lockfile \
+                                       to regenerate it",
+                                      path.display(), n, LOCKFILE_VERSION)))
+        }
+        Some(v) => {
+            Err(human_of_kind(InvalidManifest,
+                              format!("lockfile at {} has an invalid format \
+                                       version: {}", path.display(), v)))
+        }
+    }
+}
+
 pub fn write_pkg_lockfile(pkg: &Package, resolve: &Resolve) -> CargoResult<()> {
     let loc = pkg.get_root().join("Cargo.lock");
     write_lockfile(&loc, resolve)
@@ -44,9 +77,19 @@ pub fn write_lockfile(dst: &Path, resolve: &Resolve) -> CargoResult<()> {
     // lockfile.
     let root = e.toml.get(&"root".to_string()).unwrap();
 
+    out.push_str(format!("version = {}\n\n", LOCKFILE_VERSION).as_slice());
     out.push_str("[root]\n");
     emit_package(root.as_table().unwrap(), &mut out);
 
+    match e.toml.get(&"root_dependencies".to_string()) {
+        Some(requirements) if requirements.as_table().unwrap().len() > 0 => {
+            out.push_str("[root-dependencies]\n");
+            out.push_str(requirements.to_string().as_slice());
+            out.push_str("\n");
+        }
+        _ => {}
+    }
+
     let deps = e.toml.get(&"package".to_string()).unwrap().as_slice().unwrap();
     for dep in deps.iter() {
         let dep = dep.as_table().unwrap();
@@ -1,28 +1,56 @@
+//! The operations behind each cargo subcommand.
+//!
+//! These are also the entry points for driving cargo as a library. Each
+//! operation takes the path to a manifest along with an options structure,
+//! and returns what it produced rather than printing it: `compile` returns a
+//! `Compilation` listing the artifacts built, `resolve_pkg` the `Resolve`
+//! graph and `package` the path of the tarball created.
+//!
+//! Progress and warnings are written to the `MultiShell` passed in, so an
+//! embedder can capture or discard them by creating the shell around
+//! writers of its own. The `facade` module wraps the most common operations
+//! in functions returning plain result structures.
+
+pub use self::cargo_add::{add, set_version, AddOptions};
 pub use self::cargo_clean::{clean, CleanOptions};
 pub use self::cargo_compile::{compile, compile_pkg, CompileOptions};
+pub use self::cargo_compile::{artifact_paths, target_cfg};
+pub use self::cargo_compile::{Examples, NoExamples, TestedExamples, AllExamples};
+pub use self::cargo_compile::{TargetSelection, DefaultTarget, SelectLib};
+pub use self::cargo_compile::SelectBin;
+pub use self::cargo_compile::{SelectTest, SelectExample, SelectBench};
 pub use self::cargo_read_manifest::{read_manifest,read_package,read_packages};
+pub use self::cargo_read_manifest::{read_package_from, find_workspace_root};
 pub use self::cargo_rustc::{compile_targets, Compilation, Layout, Kind, rustc_version};
+pub use self::cargo_rustc::rustc_cfg;
 pub use self::cargo_rustc::{KindTarget, KindHost, Context, LayoutProxy};
 pub use self::cargo_rustc::{PlatformRequirement, PlatformTarget};
 pub use self::cargo_rustc::{PlatformPlugin, PlatformPluginAndTarget};
 pub use self::cargo_rustc::{BuildOutput, BuildConfig, TargetConfig};
+#[doc(hidden)] pub use self::cargo_rustc::{FreshnessFs, RealFs};
+pub use self::cargo_rustc::{Timings, report_timings};
+pub use self::cargo_rustc::{BUILD_DIR_MARKER, mark_build_dir};
 pub use self::cargo_run::run;
-pub use self::cargo_new::{new, NewOptions};
+pub use self::cargo_new::{new, NewOptions, VersionControl, Git, Hg, NoVcs};
 pub use self::cargo_doc::{doc, DocOptions};
 pub use self::cargo_generate_lockfile::{generate_lockfile};
 pub use self::cargo_generate_lockfile::{update_lockfile};
 pub use self::cargo_generate_lockfile::UpdateOptions;
+pub use self::cargo_generate_lockfile::{lockfile_changes, LockfileChange};
+pub use self::cargo_generate_lockfile::{Added, Removed, Updated};
 pub use self::lockfile::{load_lockfile, load_pkg_lockfile};
 pub use self::lockfile::{write_lockfile, write_pkg_lockfile};
 pub use self::cargo_test::{run_tests, run_benches, TestOptions};
-pub use self::cargo_package::package;
+pub use self::cargo_package::{package, is_stripped};
 pub use self::registry::{publish, registry_configuration, RegistryConfig};
 pub use self::registry::{registry_login, http_proxy, http_handle};
 pub use self::registry::{modify_owners, yank};
 pub use self::cargo_fetch::{fetch};
 pub use self::cargo_pkgid::pkgid;
+pub use self::cargo_verify_project::verify_project;
 pub use self::resolve::{resolve_pkg, resolve_with_previous};
 
+mod cargo_add;
 mod cargo_clean;
 mod cargo_compile;
 mod cargo_doc;
@@ -35,6 +63,8 @@ mod cargo_read_manifest;
 mod cargo_run;
 mod cargo_rustc;
 mod cargo_test;
+mod cargo_verify_project;
+pub mod facade;
 mod lockfile;
 mod registry;
 mod resolve;
@@ -1,50 +1,179 @@
 use std::collections::HashMap;
-use std::io::File;
+use std::io::{fs, File};
 use std::os;
 
 use curl::http;
 use git2;
-use registry::{Registry, NewCrate, NewCrateDependency};
+use registry;
+use registry::{Registry, NewCrate, NewCrateDependency, publish_header};
+use serialize::hex::ToHex;
 
 use core::source::Source;
-use core::{Package, MultiShell, SourceId};
+use core::{Package, PackageId, MultiShell, SourceId};
 use core::manifest::ManifestMetadata;
 use ops;
 use sources::{PathSource, RegistrySource};
 use util::config;
-use util::{CargoResult, human, internal, ChainError, Require, ToUrl};
+use util::{CargoResult, human, internal, ChainError, Require, ToUrl, Sha256};
+use util::normalize_license;
 use util::config::{Config, Table};
 
+/// How many times an upload is attempted before a connection failure is
+/// reported as an error.
+static UPLOAD_ATTEMPTS: uint = 3;
+
 pub struct RegistryConfig {
     pub index: Option<String>,
     pub token: Option<String>,
+    /// Directory of `.crate` files used instead of the central registry.
+    pub local: Option<Path>,
 }
 
 pub fn publish(manifest_path: &Path,
                shell: &mut MultiShell,
                token: Option<String>,
                index: Option<String>,
-               verify: bool) -> CargoResult<()> {
+               verify: bool,
+               dry_run: bool,
+               skip_if_published: bool) -> CargoResult<()> {
     let mut src = try!(PathSource::for_path(&manifest_path.dir_path()));
     try!(src.update());
     let pkg = try!(src.get_root_package());
 
+    try!(verify_name(&pkg));
+    try!(verify_license(&pkg));
+
     let (mut registry, reg_id) = try!(registry(shell, token, index));
     try!(verify_dependencies(&pkg, &reg_id));
 
+    // Fail fast if this version is already in the index rather than after
+    // uploading the whole tarball.
+    let id = pkg.get_package_id();
+    match is_published(shell, &reg_id, id) {
+        Ok(true) if skip_if_published => {
+            try!(shell.status("Skipping", format!("{}, it is already \
+                                                   published", id)));
+            return Ok(())
+        }
+        Ok(true) => {
+            return Err(human(format!("version {} of {} is already published",
+                                     id.get_version(), id.get_name())))
+        }
+        Ok(false) => {}
+        Err(e) => {
+            try!(shell.warn(format!("could not check whether {} is already \
+                                     published, uploading anyway: {}",
+                                    id, e)));
+        }
+    }
+
     // Prepare a tarball
     let tarball = try!(ops::package(manifest_path, shell, verify));
+    let krate = try!(new_crate(&pkg));
+
+    if dry_run {
+        // Build the request exactly as it would be sent, but stop short of
+        // sending it.
+        let (_, size) = try!(publish_header(&krate, &tarball).map_err(|e| {
+            human(e.to_string())
+        }));
+        let files = try!(src.list_files(&pkg)).iter().filter(|f| {
+            **f != tarball
+        }).count();
+        let tarball_size = try!(fs::stat(&tarball)).size;
+        try!(shell.status("Dry run", format!("not uploading {} v{}: {} files, \
+                                              {} byte tarball, {} byte request",
+                                             krate.name, krate.vers, files,
+                                             tarball_size, size)));
+        return Ok(())
+    }
 
-    // Upload said tarball to the specified destination
+    // Upload said tarball to the specified destination. Connection failures
+    // are retried, the registry recognizes an upload which made it through
+    // anyway by its checksum.
+    let cksum = {
+        let mut state = Sha256::new();
+        state.update(try!(File::open(&tarball).read_to_end()).as_slice());
+        state.finish().as_slice().to_hex()
+    };
     try!(shell.status("Uploading", pkg.get_package_id().to_string()));
-    try!(transmit(&pkg, &tarball, &mut registry));
+    let mut attempt = 1;
+    loop {
+        let err = match registry.publish(&krate, &tarball, cksum.as_slice()) {
+            Ok(()) => return Ok(()),
+            Err(err) => err,
+        };
+        let retry = match err {
+            registry::CurlError(..) => attempt < UPLOAD_ATTEMPTS,
+            _ => false,
+        };
+        if retry {
+            attempt += 1;
+            try!(shell.warn(format!("failed to upload {}: {}\nretrying \
+                                     (attempt {} of {})", id, err, attempt,
+                                    UPLOAD_ATTEMPTS)));
+            continue
+        }
+        return match err {
+            registry::AlreadyUploaded(ref existing) if *existing == cksum => {
+                shell.status("Skipping", format!("{}, it was already \
+                                                  uploaded with the same \
+                                                  contents", id))
+            }
+            registry::AlreadyUploaded(..) => {
+                Err(human(format!("version {} of {} is already published \
+                                   with different contents",
+                                  id.get_version(), id.get_name())))
+            }
+            err => Err(human(err.to_string())),
+        }
+    }
+}
+
+/// Consults the local copy of the index of `reg_id`, which `registry` has
+/// just updated, for the version of `pkg`.
+fn is_published(shell: &mut MultiShell, reg_id: &SourceId, pkg: &PackageId)
+                -> CargoResult<bool> {
+    let config = try!(Config::new(shell, None, None));
+    let mut src = RegistrySource::remote(reg_id, &config);
+    src.is_published(pkg)
+}
+
+/// The registry is stricter about names than manifest loading: names must be
+/// ASCII and start with a letter.
+fn verify_name(pkg: &Package) -> CargoResult<()> {
+    let name = pkg.get_name();
+    if !name.bytes().all(|b| b < 128) {
+        return Err(human(format!("package names must be ASCII to be \
+                                  uploaded to the registry: `{}`", name)))
+    }
+    if !name.char_at(0).is_alphabetic() {
+        return Err(human(format!("package names must start with a letter to \
+                                  be uploaded to the registry: `{}`", name)))
+    }
+    Ok(())
+}
 
+/// Building only warns about a license which isn't on the SPDX list, but one
+/// can't be published. A license file stands in for the list.
+fn verify_license(pkg: &Package) -> CargoResult<()> {
+    let metadata = pkg.get_manifest().get_metadata();
+    match (&metadata.license, &metadata.license_file) {
+        (&Some(ref license), &None) => {
+            try!(normalize_license(license.as_slice()).map_err(|msg| {
+                human(format!("{}\nuse `license-file` instead for a license \
+                               which isn't on the SPDX list", msg))
+            }));
+        }
+        _ => {}
+    }
     Ok(())
 }
 
 fn verify_dependencies(pkg: &Package, registry_src: &SourceId)
                        -> CargoResult<()> {
     for dep in pkg.get_dependencies().iter() {
+        if ops::is_stripped(dep) { continue }
         if dep.get_source_id().is_path() {
             if dep.get_specified_req().is_none() {
                 return Err(human(format!("all path dependencies must have \
@@ -63,9 +192,12 @@ fn verify_dependencies(pkg: &Package, registry_src: &SourceId)
     Ok(())
 }
 
-fn transmit(pkg: &Package, tarball: &Path, registry: &mut Registry)
-            -> CargoResult<()> {
-    let deps = pkg.get_dependencies().iter().map(|dep| {
+/// Assembles the metadata which is uploaded to the registry along with the
+/// tarball of `pkg`.
+fn new_crate(pkg: &Package) -> CargoResult<NewCrate> {
+    let deps = pkg.get_dependencies().iter().filter(|dep| {
+        !ops::is_stripped(*dep)
+    }).map(|dep| {
         NewCrateDependency {
             optional: dep.is_optional(),
             default_features: dep.uses_default_features(),
@@ -79,6 +211,7 @@ fn transmit(pkg: &Package, tarball: &Path, registry: &mut Registry)
     let ManifestMetadata {
         ref authors, ref description, ref homepage, ref documentation,
         ref keywords, ref readme, ref repository, ref license,
+        ref license_file,
     } = *manifest.get_metadata();
     let readme = match *readme {
         Some(ref readme) => {
@@ -89,7 +222,7 @@ fn transmit(pkg: &Package, tarball: &Path, registry: &mut Registry)
         }
         None => None,
     };
-    registry.publish(&NewCrate {
+    Ok(NewCrate {
         name: pkg.get_name().to_string(),
         vers: pkg.get_version().to_string(),
         deps: deps,
@@ -102,15 +235,15 @@ fn transmit(pkg: &Package, tarball: &Path, registry: &mut Registry)
         readme: readme,
         repository: repository.clone(),
         license: license.clone(),
-    }, tarball).map_err(|e| {
-        human(e.to_string())
+        license_file: license_file.clone(),
     })
 }
 
 pub fn registry_configuration() -> CargoResult<RegistryConfig> {
     let configs = try!(config::all_configs(os::getcwd()));
     let registry = match configs.find_equiv("registry") {
-        None => return Ok(RegistryConfig { index: None, token: None }),
+        None => return Ok(RegistryConfig { index: None, token: None,
+                                           local: None }),
         Some(registry) => try!(registry.table().chain_error(|| {
             internal("invalid configuration for the key `registry`")
         })),
@@ -131,7 +264,18 @@ pub fn registry_configuration() -> CargoResult<RegistryConfig> {
             })).ref0().to_string())
         }
     };
-    Ok(RegistryConfig { index: index, token: token })
+    // A relative path is relative to the directory containing the `.cargo`
+    // directory the key was set in.
+    let local = match registry.find_equiv("local") {
+        None => None,
+        Some(local) => {
+            let (local, def) = try!(local.string().chain_error(|| {
+                internal("invalid configuration for key `local`")
+            }));
+            Some(def.dir_path().dir_path().join(local))
+        }
+    };
+    Ok(RegistryConfig { index: index, token: token, local: local })
 }
 
 pub fn registry(shell: &mut MultiShell,
@@ -141,6 +285,7 @@ pub fn registry(shell: &mut MultiShell,
     let RegistryConfig {
         token: token_config,
         index: index_config,
+        ..
     } = try!(registry_configuration());
     let token = try!(token.or(token_config).require(|| {
         human("no upload token found, please run `cargo login`")
@@ -150,7 +295,7 @@ pub fn registry(shell: &mut MultiShell,
     let sid = SourceId::for_registry(&index);
     let api_host = {
         let mut config = try!(Config::new(shell, None, None));
-        let mut src = RegistrySource::new(&sid, &mut config);
+        let mut src = RegistrySource::remote(&sid, &mut config);
         try!(src.update().chain_error(|| {
             human(format!("Failed to update registry {}", index))
         }));
@@ -204,7 +349,7 @@ pub fn http_proxy() -> CargoResult<Option<String>> {
 
 pub fn registry_login(shell: &mut MultiShell, token: String) -> CargoResult<()> {
     let config = try!(Config::new(shell, None, None));
-    let RegistryConfig { index, token: _ } = try!(registry_configuration());
+    let RegistryConfig { index, .. } = try!(registry_configuration());
     let mut map = HashMap::new();
     let p = os::getcwd();
     match index {
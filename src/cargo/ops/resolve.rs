@@ -1,8 +1,8 @@
-use std::collections::{HashMap, HashSet};
+use std::collections::{HashMap, HashSet, TreeMap};
 
 use core::{Package, PackageId, SourceId};
 use core::registry::PackageRegistry;
-use core::resolver::{mod, Resolve};
+use core::resolver::{mod, Resolve, Requirements};
 use ops;
 use util::CargoResult;
 
@@ -30,6 +30,10 @@ pub fn resolve_pkg(registry: &mut PackageRegistry, package: &Package)
 ///
 /// The previous resolve normally comes from a lockfile. This function does not
 /// read or write lockfiles from the filesystem.
+///
+/// If the requirement on a dependency in the manifest differs from the one
+/// recorded in the previous resolve, that dependency is re-resolved as if it
+/// had been passed to `cargo update`.
 pub fn resolve_with_previous<'a>(registry: &mut PackageRegistry,
                                  package: &Package,
                                  method: resolver::ResolveMethod,
@@ -39,6 +43,18 @@ pub fn resolve_with_previous<'a>(registry: &mut PackageRegistry,
     let root = package.get_package_id().get_source_id().clone();
     try!(registry.add_sources(&[root]));
 
+    let mut avoid = to_avoid.map(|set| set.clone()).unwrap_or(HashSet::new());
+    match previous {
+        Some(r) => {
+            let changed = try!(changed_requirements(registry, package, r));
+            avoid.extend(r.iter().filter(|p| {
+                changed.contains_equiv(p.get_name())
+            }));
+        }
+        None => {}
+    }
+    let to_avoid = Some(&avoid);
+
     // Here we place an artificial limitation that all non-registry sources
     // cannot be locked at more than one revision. This means that if a git
     // repository provides more than one package, they must all be updated in
@@ -83,6 +99,10 @@ pub fn resolve_with_previous<'a>(registry: &mut PackageRegistry,
             //    ranges. To deal with this, we only actually lock a dependency
             //    to the previously resolved version if the dependency listed
             //    still matches the locked version.
+            //
+            //    Dependencies newly added to the manifest are also locked if
+            //    the package was already somewhere in the graph, so adding a
+            //    direct dependency on a transitive dependency doesn't move it.
             for node in r.iter().filter(|p| keep(p, to_avoid, &to_avoid_sources)) {
                 let deps = r.deps(node).into_iter().flat_map(|i| i)
                             .filter(|p| keep(p, to_avoid, &to_avoid_sources))
@@ -90,15 +110,23 @@ pub fn resolve_with_previous<'a>(registry: &mut PackageRegistry,
                 registry.register_lock(node.clone(), deps);
             }
 
-            let map = r.deps(r.root()).into_iter().flat_map(|i| i).filter(|p| {
+            let direct = r.deps(r.root()).into_iter().flat_map(|i| i).filter(|p| {
                 keep(p, to_avoid, &to_avoid_sources)
             }).map(|d| {
                 (d.get_name(), d)
             }).collect::<HashMap<_, _>>();
             summary.map_dependencies(|d| {
-                match map.find_equiv(d.get_name()) {
-                    Some(&lock) if d.matches_id(lock) => d.lock_to(lock),
-                    _ => d,
+                match direct.find_equiv(d.get_name()) {
+                    Some(&lock) if d.matches_id(lock) => return d.lock_to(lock),
+                    Some(..) => return d,
+                    None => {}
+                }
+                let lock = r.iter().filter(|p| {
+                    keep(p, to_avoid, &to_avoid_sources)
+                }).find(|p| d.matches_id(*p));
+                match lock {
+                    Some(lock) => d.lock_to(lock),
+                    None => d,
                 }
             })
         }
@@ -110,6 +138,7 @@ pub fn resolve_with_previous<'a>(registry: &mut PackageRegistry,
         Some(r) => resolved.copy_metadata(r),
         None => {}
     }
+    resolved.set_requirements(root_requirements(package));
     return Ok(resolved);
 
     fn keep<'a>(p: &&'a PackageId,
@@ -122,3 +151,57 @@ pub fn resolve_with_previous<'a>(registry: &mut PackageRegistry,
         }
     }
 }
+
+/// Returns the version requirement of each dependency of `package`.
+fn root_requirements(package: &Package) -> Requirements {
+    let mut ret = TreeMap::new();
+    for dep in package.get_dependencies().iter() {
+        let name = dep.get_name().to_string();
+        let req = dep.get_version_req().to_string();
+        // The same package may be depended on more than once, for example as
+        // both a normal and a dev dependency.
+        let req = match ret.remove(&name) {
+            Some(ref prev) if *prev != req => format!("{}, {}", prev, req),
+            _ => req,
+        };
+        ret.insert(name, req);
+    }
+    ret
+}
+
+/// Compares the dependencies of `package` with the requirements recorded in
+/// `previous`, warning about and returning the names of those which changed.
+fn changed_requirements(registry: &PackageRegistry, package: &Package,
+                        previous: &Resolve) -> CargoResult<HashSet<String>> {
+    let mut changed = HashSet::new();
+    let old = match previous.requirements() {
+        Some(old) => old,
+        None => return Ok(changed),
+    };
+    let new = root_requirements(package);
+
+    let mut msg = String::new();
+    for (name, req) in new.iter() {
+        match old.get(name) {
+            Some(prev) if prev != req => {
+                msg.push_str(format!("\n  `{}` changed from `{}` to `{}`",
+                                     name, prev, req).as_slice());
+                changed.insert(name.clone());
+            }
+            _ => {}
+        }
+    }
+    for name in old.keys() {
+        if !new.contains_key(name) {
+            msg.push_str(format!("\n  `{}` was removed", name).as_slice());
+        }
+    }
+
+    if msg.len() > 0 {
+        try!(registry.config().shell().warn(format!("dependencies changed \
+                                                     since Cargo.lock was \
+                                                     written, re-resolving:{}",
+                                                    msg)));
+    }
+    Ok(changed)
+}
@@ -245,6 +245,13 @@ mod test {
         assert_eq!(ident1, ident2);
     }
 
+    #[test]
+    fn test_canonicalize_idents_scp_and_ssh() {
+        let ident1 = ident(&url("git@example.com:user/repo.git"));
+        let ident2 = ident(&url("ssh://git@example.com/user/repo.git"));
+        assert_eq!(ident1, ident2);
+    }
+
     fn url(s: &str) -> Url {
         s.to_url().unwrap()
     }
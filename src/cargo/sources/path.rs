@@ -6,7 +6,7 @@ use git2;
 
 use core::{Package, PackageId, Summary, SourceId, Source, Dependency, Registry};
 use ops;
-use util::{CargoResult, internal, internal_error};
+use util::{CargoResult, human, internal, internal_error};
 
 pub struct PathSource {
     id: SourceId,
@@ -24,6 +24,18 @@ impl PathSource {
         Ok(PathSource::new(path, &try!(SourceId::for_path(path))))
     }
 
+    /// Creates a source for the directory at `path` whose packages have
+    /// already been read, so that it doesn't need to be updated.
+    pub fn preloaded(path: &Path, id: &SourceId,
+                     packages: Vec<Package>) -> PathSource {
+        PathSource {
+            id: id.clone(),
+            path: path.clone(),
+            updated: true,
+            packages: packages,
+        }
+    }
+
     /// Invoked with an absolute path to a directory that contains a Cargo.toml.
     /// The source will read the manifest and find any other packages contained
     /// in the directory structure reachable by the root manifest.
@@ -69,6 +81,62 @@ impl PathSource {
     /// are relevant for building this package, but it also contains logic to
     /// use other methods like .gitignore to filter the list of files.
     pub fn list_files(&self, pkg: &Package) -> CargoResult<Vec<Path>> {
+        Ok(try!(self.select_files(pkg)).val0())
+    }
+
+    /// Lists the files of `pkg` which go into its package, like `list_files`,
+    /// along with warnings for the `exclude` patterns which match no file.
+    ///
+    /// It's an error for a file which is the source of a target or the build
+    /// script to be left out, as the package couldn't be built without it.
+    pub fn list_package_files(&self, pkg: &Package)
+                              -> CargoResult<(Vec<Path>, Vec<String>)> {
+        let (files, excluded) = try!(self.select_files(pkg));
+        let root = pkg.get_manifest_path().dir_path();
+        let patterns = pkg.get_manifest().get_exclude();
+
+        let mut missing = Vec::new();
+        for target in pkg.get_targets().iter() {
+            let src = root.join(target.get_src_path());
+            if files.contains(&src) || !root.is_ancestor_of(&src) ||
+               !src.is_file() || missing.iter().any(|&(ref s, _)| *s == src) {
+                continue
+            }
+            let relative = src.path_relative_from(&root).unwrap();
+            let reason = match excluded.iter().find(|&&(ref f, _)| *f == src) {
+                Some(&(_, ref matched)) => format!("it matches the `exclude` \
+                                                    pattern `{}`",
+                                                   patterns[matched[0]]),
+                None => format!("it isn't part of the package's files, e.g. \
+                                 because it's ignored by version control"),
+            };
+            let what = if target.get_profile().is_custom_build() {
+                "the build script".to_string()
+            } else {
+                format!("the source of target `{}`", target.get_name())
+            };
+            missing.push((src, format!("`{}`, {}, would be left out of the \
+                                        package: {}", relative.display(),
+                                       what, reason)));
+        }
+        if missing.len() > 0 {
+            let msgs = missing.into_iter().map(|(_, msg)| msg)
+                              .collect::<Vec<String>>();
+            return Err(human(msgs.connect("\n")))
+        }
+
+        let warnings = patterns.iter().enumerate().filter(|&(i, _)| {
+            !excluded.iter().any(|&(_, ref matched)| matched.contains(&i))
+        }).map(|(_, pattern)| {
+            format!("the `exclude` pattern `{}` doesn't match any file", pattern)
+        }).collect();
+        Ok((files, warnings))
+    }
+
+    /// Returns the files of `pkg` along with those left out by its `exclude`
+    /// patterns, each with the indices of all the patterns which matched it.
+    fn select_files(&self, pkg: &Package)
+                    -> CargoResult<(Vec<Path>, Vec<(Path, Vec<uint>)>)> {
         let root = pkg.get_manifest_path().dir_path();
 
         // Check whether the package itself is a git repository.
@@ -90,11 +158,21 @@ impl PathSource {
             Pattern::new(p.as_slice())
         }).collect::<Vec<Pattern>>();
 
-        Ok(candidates.into_iter().filter(|candidate| {
+        let mut files = Vec::new();
+        let mut excluded = Vec::new();
+        for candidate in candidates.into_iter() {
+            if !candidate.is_file() { continue }
             let relative_path = candidate.path_relative_from(&root).unwrap();
-            !pats.iter().any(|p| p.matches_path(&relative_path)) &&
-                candidate.is_file()
-        }).collect())
+            let matched = pats.iter().enumerate().filter(|&(_, p)| {
+                p.matches_path(&relative_path)
+            }).map(|(i, _)| i).collect::<Vec<uint>>();
+            if matched.is_empty() {
+                files.push(candidate);
+            } else {
+                excluded.push((candidate, matched));
+            }
+        }
+        Ok((files, excluded))
     }
 
     fn list_files_git(&self, pkg: &Package, repo: git2::Repository)
@@ -106,6 +184,7 @@ impl PathSource {
             None => return Err(internal_error("Can't list files on a bare repository.", "")),
         };
         let pkg_path = pkg.get_manifest_path().dir_path();
+        let target_dir = pkg.get_absolute_target_dir();
 
         let mut ret = Vec::new();
         'outer: for i in range(0, index.len()) {
@@ -119,6 +198,7 @@ impl PathSource {
             // Filter out Cargo.lock and target always
             if fname == b"Cargo.lock" { continue }
             if fname == b"target" { continue }
+            if target_dir.is_ancestor_of(&file_path) { continue }
 
             // Filter out sub-packages of this package
             for other_pkg in self.packages.iter().filter(|p| *p != pkg) {
@@ -139,18 +219,22 @@ impl PathSource {
         let mut ret = Vec::new();
         for pkg in self.packages.iter().filter(|p| *p == pkg) {
             let loc = pkg.get_manifest_path().dir_path();
-            try!(walk(&loc, &mut ret, true));
+            try!(walk(&loc, &mut ret, true, &pkg.get_absolute_target_dir()));
         }
         return Ok(ret);
 
         fn walk(path: &Path, ret: &mut Vec<Path>,
-                is_root: bool) -> CargoResult<()> {
+                is_root: bool, target_dir: &Path) -> CargoResult<()> {
             if !path.is_dir() {
                 ret.push(path.clone());
                 return Ok(())
             }
             // Don't recurse into any sub-packages that we have
             if !is_root && path.join("Cargo.toml").exists() { return Ok(()) }
+            // Nor into build output
+            if path == target_dir || path.join(ops::BUILD_DIR_MARKER).exists() {
+                return Ok(())
+            }
             for dir in try!(fs::readdir(path)).iter() {
                 match (is_root, dir.filename_str()) {
                     (_,    Some(".git")) |
@@ -158,7 +242,7 @@ impl PathSource {
                     (true, Some("Cargo.lock")) => continue,
                     _ => {}
                 }
-                try!(walk(dir, ret, false));
+                try!(walk(dir, ret, false, target_dir));
             }
             return Ok(())
         }
@@ -206,12 +290,8 @@ impl Source for PathSource {
     }
 
     fn fingerprint(&self, pkg: &Package) -> CargoResult<String> {
-        if !self.updated {
-            return Err(internal_error("BUG: source was not updated", ""));
-        }
-
         let mut max = 0;
-        for file in try!(self.list_files(pkg)).iter() {
+        for file in try!(self.fingerprint_files(pkg)).iter() {
             // An fs::stat error here is either because path is a
             // broken symlink, a permissions error, or a race
             // condition where this path was rm'ed - either way,
@@ -220,7 +300,20 @@ impl Source for PathSource {
             warn!("{} {}", file.stat().map(|s| s.modified).unwrap_or(0), file.display());
             max = cmp::max(max, file.stat().map(|s| s.modified).unwrap_or(0));
         }
-        log!(5, "fingerprint {}: {}", self.path.display(), max);
-        Ok(max.to_string())
+        let fingerprint = format!("{}-{}", max, pkg.get_manifest().build_hash());
+        log!(5, "fingerprint {}: {}", self.path.display(), fingerprint);
+        Ok(fingerprint)
+    }
+
+    fn fingerprint_files(&self, pkg: &Package) -> CargoResult<Vec<Path>> {
+        if !self.updated {
+            return Err(internal_error("BUG: source was not updated", ""));
+        }
+
+        // The manifest itself is accounted for by hashing only the parts of it
+        // which affect the build, rather than by its mtime.
+        Ok(try!(self.list_files(pkg)).into_iter().filter(|file| {
+            file != pkg.get_manifest_path()
+        }).collect())
     }
 }
@@ -126,6 +126,32 @@
 //! already been downloaded and unpacked. This caching allows us to only
 //! download a package when absolutely necessary.
 //!
+//! # Local Registries
+//!
+//! A registry can also be read from a directory on the filesystem, which is
+//! useful for building without network access. Such a directory contains an
+//! `index` subdirectory, laid out exactly like the index described above
+//! (though it needn't be a git repository), next to the tarballs themselves:
+//!
+//! ```notrust
+//! index/
+//!     3/u/url
+//!     li/bg/libgit2
+//!     ...
+//! url-0.1.0.crate
+//! libgit2-0.1.2.crate
+//! ...
+//! ```
+//!
+//! Nothing is downloaded or fetched for a local registry: tarballs are read in
+//! place, verified against the checksums listed in the index, and unpacked
+//! into the same `src` directory as those of a remote registry. A local
+//! registry is used instead of the central registry when the `registry.local`
+//! configuration key names its directory. It only replaces where the central
+//! registry is read from: packages keep the central registry's source ID, so
+//! lockfiles are the same whether or not (and wherever) a local registry is
+//! used.
+//!
 //! # Filesystem Hierarchy
 //!
 //! Overall, the `$HOME/.cargo` looks like this when talking about the registry:
@@ -145,14 +171,16 @@
 //!         ...
 //!
 //!     # This folder is a cache for all downloaded tarballs from a registry.
-//!     # Once downloaded and verified, a tarball never changes.
+//!     # Once downloaded and verified, a tarball never changes. Tarballs are
+//!     # written to `<pkg>-<version>.crate.part` and renamed once complete.
 //!     cache/
 //!         registry1-<hash>/<pkg>-<version>.crate
 //!         ...
 //!
 //!     # Location in which all tarballs are unpacked. Each tarball is known to
 //!     # be frozen after downloading, so transitively this folder is also
-//!     # frozen once its unpacked (it's never unpacked again)
+//!     # frozen once its unpacked (it's never unpacked again). Tarballs are
+//!     # unpacked into `.<pkg>-<version>.tmp` first and then moved into place.
 //!     src/
 //!         registry1-<hash>/<pkg>-<version>/...
 //!         ...
@@ -174,7 +202,7 @@ use core::{Source, SourceId, PackageId, Package, Summary, Registry};
 use core::Dependency;
 use sources::{PathSource, git};
 use util::{CargoResult, Config, internal, ChainError, ToUrl, human};
-use util::{hex, Require, Sha256};
+use util::{hex, Require, Sha256, human_of_kind, IoFailure};
 use ops;
 
 static CENTRAL: &'static str = "https://example.com";
@@ -182,6 +210,8 @@ static CENTRAL: &'static str = "https://example.com";
 pub struct RegistrySource<'a, 'b:'a> {
     source_id: SourceId,
     checkout_path: Path,
+    /// The local registry read in place of this registry, if any.
+    local: Option<Path>,
     cache_path: Path,
     src_path: Path,
     config: &'a Config<'b>,
@@ -227,11 +257,31 @@ struct RegistryDependency {
 impl<'a, 'b> RegistrySource<'a, 'b> {
     pub fn new(source_id: &SourceId,
                config: &'a Config<'b>) -> RegistrySource<'a, 'b> {
+        let local = config.local_registry(source_id).map(|p| p.clone());
+        RegistrySource::with_local(source_id, config, local)
+    }
+
+    /// Creates a source for the registry itself even if a local registry is
+    /// configured in its place, for operations like publishing which have to
+    /// talk to the real thing.
+    pub fn remote(source_id: &SourceId,
+                  config: &'a Config<'b>) -> RegistrySource<'a, 'b> {
+        RegistrySource::with_local(source_id, config, None)
+    }
+
+    fn with_local(source_id: &SourceId, config: &'a Config<'b>,
+                  local: Option<Path>) -> RegistrySource<'a, 'b> {
         let hash = hex::short_hash(source_id);
         let ident = source_id.get_url().host().unwrap().to_string();
         let part = format!("{}-{}", ident, hash);
+        // A local registry's index is read where it is rather than checked out.
+        let checkout_path = match local {
+            Some(ref dir) => dir.join("index"),
+            None => config.registry_index_path().join(part.as_slice()),
+        };
         RegistrySource {
-            checkout_path: config.registry_index_path().join(part.as_slice()),
+            checkout_path: checkout_path,
+            local: local,
             cache_path: config.registry_cache_path().join(part.as_slice()),
             src_path: config.registry_source_path().join(part.as_slice()),
             config: config,
@@ -295,9 +345,7 @@ impl<'a, 'b> RegistrySource<'a, 'b> {
     /// No action is taken if the package is already downloaded.
     fn download_package(&mut self, pkg: &PackageId, url: &Url)
                         -> CargoResult<Path> {
-        // TODO: should discover from the S3 redirect
-        let filename = format!("{}-{}.crate", pkg.get_name(), pkg.get_version());
-        let dst = self.cache_path.join(filename);
+        let dst = self.cache_file(pkg);
         if dst.exists() { return Ok(dst) }
         try!(self.config.shell().status("Downloading", pkg));
 
@@ -312,11 +360,73 @@ impl<'a, 'b> RegistrySource<'a, 'b> {
         // TODO: don't download into memory (curl-rust doesn't expose it)
         let resp = try!(handle.get(url.to_string()).follow_redirects(true).exec());
         if resp.get_code() != 200 && resp.get_code() != 0 {
-            return Err(internal(format!("Failed to get 200 reponse from {}\n{}",
-                                        url, resp)))
+            return Err(human_of_kind(IoFailure,
+                                     format!("Failed to get 200 reponse from \
+                                              {}\n{}", url, resp)))
         }
 
         // Verify what we just downloaded
+        try!(self.verify(pkg, resp.get_body()));
+
+        // The tarball is only moved into the cache once it's complete, so an
+        // interrupted download never leaves a truncated one behind.
+        let tmp = dst.with_extension("crate.part");
+        try!(File::create(&tmp).write(resp.get_body()));
+        try!(fs::rename(&tmp, &dst));
+        Ok(dst)
+    }
+
+    /// Downloads and unpacks `pkg`, returning the location of its source.
+    ///
+    /// A tarball cached by an earlier run may be truncated (for example if it
+    /// was downloaded by an older Cargo), in which case it's deleted and
+    /// downloaded again once.
+    fn fetch_package(&mut self, pkg: &PackageId, url: &Url)
+                     -> CargoResult<Path> {
+        let mut retry = self.cache_file(pkg).exists();
+        loop {
+            let tarball = try!(self.download_package(pkg, url).chain_error(|| {
+                internal(format!("Failed to download package `{}` from {}",
+                                 pkg, url))
+            }));
+            match self.unpack_package(pkg, &tarball) {
+                Ok(path) => return Ok(path),
+                Err(..) if retry => {
+                    try!(self.config.shell().warn(format!(
+                        "failed to unpack the cached tarball of `{}`, \
+                         downloading it again", pkg)));
+                    try!(fs::unlink(&tarball));
+                    retry = false;
+                }
+                Err(e) => return Err(e).chain_error(|| {
+                    internal(format!("Failed to unpack package `{}`", pkg))
+                }),
+            }
+        }
+    }
+
+    /// Returns where the downloaded tarball of `pkg` is cached.
+    fn cache_file(&self, pkg: &PackageId) -> Path {
+        // TODO: should discover from the S3 redirect
+        self.cache_path.join(format!("{}-{}.crate", pkg.get_name(),
+                                     pkg.get_version()))
+    }
+
+    /// Locate the tarball of the given package in a local registry, checking
+    /// that its contents match the index.
+    fn local_package(&self, pkg: &PackageId) -> CargoResult<Path> {
+        let path = self.local.as_ref().unwrap();
+        let path = path.join(format!("{}-{}.crate", pkg.get_name(),
+                                     pkg.get_version()));
+        let contents = try!(File::open(&path).read_to_end().chain_error(|| {
+            human(format!("failed to read `{}`", path.display()))
+        }));
+        try!(self.verify(pkg, contents.as_slice()));
+        Ok(path)
+    }
+
+    /// Verify that `contents` has the checksum listed in the index for `pkg`.
+    fn verify(&self, pkg: &PackageId, contents: &[u8]) -> CargoResult<()> {
         let expected = self.hashes.get(&(pkg.get_name().to_string(),
                                          pkg.get_version().to_string()));
         let expected = try!(expected.require(|| {
@@ -324,37 +434,54 @@ impl<'a, 'b> RegistrySource<'a, 'b> {
         }));
         let actual = {
             let mut state = Sha256::new();
-            state.update(resp.get_body());
+            state.update(contents);
             state.finish()
         };
         if actual.as_slice().to_hex() != *expected {
             return Err(human(format!("Failed to verify the checksum of `{}`",
                                      pkg)))
         }
-
-        try!(File::create(&dst).write(resp.get_body()));
-        Ok(dst)
+        Ok(())
     }
 
     /// Unpacks a downloaded package into a location where it's ready to be
     /// compiled.
     ///
     /// No action is taken if the source looks like it's already unpacked.
-    fn unpack_package(&self, pkg: &PackageId, tarball: Path)
+    /// Otherwise the tarball is unpacked into a temporary directory which is
+    /// only renamed into place once complete, so a crash can't leave a
+    /// half-unpacked source behind.
+    fn unpack_package(&self, pkg: &PackageId, tarball: &Path)
                       -> CargoResult<Path> {
-        let dst = self.src_path.join(format!("{}-{}", pkg.get_name(),
-                                             pkg.get_version()));
+        let name = format!("{}-{}", pkg.get_name(), pkg.get_version());
+        let dst = self.src_path.join(name.as_slice());
         if dst.join(".cargo-ok").exists() { return Ok(dst) }
 
-        try!(fs::mkdir_recursive(&dst.dir_path(), io::USER_DIR));
-        let f = try!(File::open(&tarball));
+        let tmp = self.src_path.join(format!(".{}.tmp", name));
+        if tmp.exists() { try!(fs::rmdir_recursive(&tmp)) }
+        try!(fs::mkdir_recursive(&tmp, io::USER_DIR));
+        let f = try!(File::open(tarball));
         let gz = try!(GzDecoder::new(f));
         let mut tar = Archive::new(gz);
-        try!(tar.unpack(&dst.dir_path()));
-        try!(File::create(&dst.join(".cargo-ok")));
+        try!(tar.unpack(&tmp));
+        let unpacked = tmp.join(name.as_slice());
+        try!(File::create(&unpacked.join(".cargo-ok")));
+
+        if dst.exists() { try!(fs::rmdir_recursive(&dst)) }
+        try!(fs::rename(&unpacked, &dst));
+        try!(fs::rmdir_recursive(&tmp));
         Ok(dst)
     }
 
+    /// Returns whether the local copy of the index lists the version of `pkg`,
+    /// regardless of whether that version has since been yanked.
+    pub fn is_published(&mut self, pkg: &PackageId) -> CargoResult<bool> {
+        let summaries = try!(self.summaries(pkg.get_name()));
+        Ok(summaries.iter().any(|&(ref s, _)| {
+            s.get_version() == pkg.get_version()
+        }))
+    }
+
     /// Parse the on-disk metadata for the package provided
     fn summaries(&mut self, name: &str) -> CargoResult<&Vec<(Summary, bool)>> {
         if self.cache.contains_key_equiv(name) {
@@ -428,6 +555,24 @@ impl<'a, 'b> RegistrySource<'a, 'b> {
     fn do_update(&mut self) -> CargoResult<()> {
         if self.updated { return Ok(()) }
 
+        // The index of a local registry is read in place.
+        match self.local {
+            Some(ref dir) => {
+                if !self.checkout_path.is_dir() {
+                    return Err(human(format!("local registry `{}` has no \
+                                              index directory at `{}`",
+                                             dir.display(),
+                                             self.checkout_path.display())))
+                }
+                try!(self.config.shell().status("Using",
+                     format!("local registry `{}` for registry `{}`",
+                             dir.display(), self.source_id.get_url())));
+                self.updated = true;
+                return Ok(())
+            }
+            None => {}
+        }
+
         try!(self.config.shell().status("Updating",
              format!("registry `{}`", self.source_id.get_url())));
         let repo = try!(self.open());
@@ -484,22 +629,35 @@ impl<'a, 'b> Source for RegistrySource<'a, 'b> {
     }
 
     fn download(&mut self, packages: &[PackageId]) -> CargoResult<()> {
-        let config = try!(self.config());
-        let url = try!(config.dl.as_slice().to_url().map_err(internal));
+        let dl = if self.local.is_some() {
+            None
+        } else {
+            let config = try!(self.config());
+            Some(try!(config.dl.as_slice().to_url().map_err(internal)))
+        };
         for package in packages.iter() {
             if self.source_id != *package.get_source_id() { continue }
 
-            let mut url = url.clone();
-            url.path_mut().unwrap().push(package.get_name().to_string());
-            url.path_mut().unwrap().push(package.get_version().to_string());
-            url.path_mut().unwrap().push("download".to_string());
-            let path = try!(self.download_package(package, &url).chain_error(|| {
-                internal(format!("Failed to download package `{}` from {}",
-                                 package, url))
-            }));
-            let path = try!(self.unpack_package(package, path).chain_error(|| {
-                internal(format!("Failed to unpack package `{}`", package))
-            }));
+            let path = match dl {
+                Some(ref url) => {
+                    let mut url = url.clone();
+                    url.path_mut().unwrap().push(package.get_name().to_string());
+                    url.path_mut().unwrap().push(package.get_version()
+                                                        .to_string());
+                    url.path_mut().unwrap().push("download".to_string());
+                    try!(self.fetch_package(package, &url))
+                }
+                None => {
+                    // There's nothing to download again from a local
+                    // registry, so the tarball has to be fixed by hand.
+                    let tarball = try!(self.local_package(package));
+                    try!(self.unpack_package(package, &tarball).chain_error(|| {
+                        human(format!("Failed to unpack package `{}`, the \
+                                       tarball at `{}` may be corrupt",
+                                      package, tarball.display()))
+                    }))
+                }
+            };
             let mut src = PathSource::new(&path, &self.source_id);
             try!(src.update());
             self.sources.push(src);
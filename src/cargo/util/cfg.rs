@@ -0,0 +1,242 @@
+//! The cfg values of a compilation target and the `cfg(...)` expressions of
+//! `[target.cfg(...).dependencies]` sections which are evaluated against
+//! them.
+
+use std::fmt;
+
+use util::{CargoResult, CargoError, human};
+
+/// A single cfg value, in the form printed by `rustc --print cfg`.
+#[deriving(PartialEq, Eq, Clone)]
+pub enum Cfg {
+    /// A name such as `unix`.
+    CfgName(String),
+    /// A key and value such as `target_os = "linux"`.
+    CfgKeyPair(String, String),
+}
+
+/// A `cfg(...)` expression, without the surrounding `cfg(` and `)`.
+#[deriving(PartialEq, Eq, Clone, Show)]
+pub enum CfgExpr {
+    CfgNot(Box<CfgExpr>),
+    CfgAll(Vec<CfgExpr>),
+    CfgAny(Vec<CfgExpr>),
+    CfgValue(Cfg),
+}
+
+#[deriving(PartialEq, Clone)]
+enum Token {
+    LeftParen,
+    RightParen,
+    Comma,
+    Equals,
+    Ident(String),
+    Literal(String),
+}
+
+impl Cfg {
+    /// Parses one line of the output of `rustc --print cfg`.
+    pub fn parse(s: &str) -> CargoResult<Cfg> {
+        let mut parser = try!(Parser::new(s));
+        let cfg = try!(parser.cfg());
+        try!(parser.finish());
+        Ok(cfg)
+    }
+}
+
+impl fmt::Show for Cfg {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            CfgName(ref name) => write!(f, "{}", name),
+            CfgKeyPair(ref key, ref value) => write!(f, "{}=\"{}\"", key, value),
+        }
+    }
+}
+
+impl CfgExpr {
+    /// Parses the name of a `[target.<name>]` section if it is a cfg
+    /// expression such as `cfg(unix)`, or returns `None` if it names a
+    /// target triple instead.
+    pub fn parse_platform(platform: &str) -> Option<CargoResult<CfgExpr>> {
+        if !platform.starts_with("cfg(") { return None }
+        Some(Parser::new(platform).and_then(|mut parser| {
+            try!(parser.expect(Ident("cfg".to_string())));
+            try!(parser.expect(LeftParen));
+            let expr = try!(parser.expr());
+            try!(parser.expect(RightParen));
+            try!(parser.finish());
+            Ok(expr)
+        }))
+    }
+
+    /// Whether the expression holds for a target with the cfg values `cfg`.
+    pub fn matches(&self, cfg: &[Cfg]) -> bool {
+        match *self {
+            CfgNot(ref e) => !e.matches(cfg),
+            CfgAll(ref es) => es.iter().all(|e| e.matches(cfg)),
+            CfgAny(ref es) => es.iter().any(|e| e.matches(cfg)),
+            CfgValue(ref c) => cfg.contains(c),
+        }
+    }
+}
+
+struct Parser<'a> {
+    src: &'a str,
+    tokens: Vec<Token>,
+    pos: uint,
+}
+
+impl<'a> Parser<'a> {
+    fn new(src: &'a str) -> CargoResult<Parser<'a>> {
+        Ok(Parser { src: src, tokens: try!(tokenize(src)), pos: 0 })
+    }
+
+    fn next(&mut self) -> Option<Token> {
+        self.pos += 1;
+        self.tokens.get(self.pos - 1).map(|t| t.clone())
+    }
+
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.pos)
+    }
+
+    fn expect(&mut self, token: Token) -> CargoResult<()> {
+        if self.next() == Some(token) { return Ok(()) }
+        Err(self.error())
+    }
+
+    fn finish(&self) -> CargoResult<()> {
+        if self.pos >= self.tokens.len() { return Ok(()) }
+        Err(self.error())
+    }
+
+    fn error(&self) -> Box<CargoError + Send> {
+        human(format!("`{}` is not a valid cfg expression", self.src))
+    }
+
+    fn expr(&mut self) -> CargoResult<CfgExpr> {
+        let op = match self.peek() {
+            Some(&Ident(ref name)) => name.clone(),
+            _ => return Err(self.error()),
+        };
+        if self.tokens.get(self.pos + 1) != Some(&LeftParen) {
+            return Ok(CfgValue(try!(self.cfg())))
+        }
+        self.pos += 2;
+        let mut args = Vec::new();
+        while self.peek() != Some(&RightParen) {
+            args.push(try!(self.expr()));
+            if self.peek() == Some(&Comma) { self.pos += 1 } else { break }
+        }
+        try!(self.expect(RightParen));
+        match op.as_slice() {
+            "not" if args.len() == 1 => Ok(CfgNot(box args.pop().unwrap())),
+            "all" => Ok(CfgAll(args)),
+            "any" => Ok(CfgAny(args)),
+            _ => Err(self.error()),
+        }
+    }
+
+    fn cfg(&mut self) -> CargoResult<Cfg> {
+        let name = match self.next() {
+            Some(Ident(name)) => name,
+            _ => return Err(self.error()),
+        };
+        if self.peek() != Some(&Equals) { return Ok(CfgName(name)) }
+        self.pos += 1;
+        match self.next() {
+            Some(Literal(value)) => Ok(CfgKeyPair(name, value)),
+            _ => Err(self.error()),
+        }
+    }
+}
+
+fn tokenize(src: &str) -> CargoResult<Vec<Token>> {
+    let chars = src.chars().collect::<Vec<char>>();
+    let mut tokens = Vec::new();
+    let mut i = 0;
+    while i < chars.len() {
+        let start = i;
+        i += 1;
+        match chars[start] {
+            c if c.is_whitespace() => {}
+            '(' => tokens.push(LeftParen),
+            ')' => tokens.push(RightParen),
+            ',' => tokens.push(Comma),
+            '=' => tokens.push(Equals),
+            '"' => {
+                while i < chars.len() && chars[i] != '"' { i += 1 }
+                if i == chars.len() {
+                    return Err(human(format!("unterminated string in cfg \
+                                              expression `{}`", src)))
+                }
+                i += 1;
+                tokens.push(Literal(collect(chars.slice(start + 1, i - 1))));
+            }
+            c if c.is_alphabetic() || c == '_' => {
+                while i < chars.len() &&
+                      (chars[i].is_alphanumeric() || chars[i] == '_') {
+                    i += 1
+                }
+                tokens.push(Ident(collect(chars.slice(start, i))));
+            }
+            c => {
+                return Err(human(format!("unexpected character `{}` in cfg \
+                                          expression `{}`", c, src)))
+            }
+        }
+    }
+    Ok(tokens)
+}
+
+fn collect(chars: &[char]) -> String {
+    chars.iter().map(|c| *c).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Cfg, CfgExpr, CfgName, CfgKeyPair};
+
+    fn cfg() -> Vec<Cfg> {
+        vec![CfgName("unix".to_string()),
+             CfgKeyPair("target_os".to_string(), "linux".to_string()),
+             CfgKeyPair("feature".to_string(), "foo".to_string())]
+    }
+
+    fn matches(platform: &str) -> bool {
+        CfgExpr::parse_platform(platform).unwrap().unwrap()
+                .matches(cfg().as_slice())
+    }
+
+    #[test]
+    fn parse_rustc_output() {
+        assert_eq!(Cfg::parse("unix").unwrap(), CfgName("unix".to_string()));
+        assert_eq!(Cfg::parse("target_os=\"linux\"").unwrap(),
+                   CfgKeyPair("target_os".to_string(), "linux".to_string()));
+        assert_eq!(Cfg::parse("target_os=\"linux\"").unwrap().to_string(),
+                   "target_os=\"linux\"".to_string());
+        assert!(Cfg::parse("target_os=").is_err());
+    }
+
+    #[test]
+    fn evaluate() {
+        assert!(matches("cfg(unix)"));
+        assert!(!matches("cfg(windows)"));
+        assert!(matches("cfg(target_os = \"linux\")"));
+        assert!(matches("cfg(not(windows))"));
+        assert!(matches("cfg(all(unix, feature = \"foo\"))"));
+        assert!(!matches("cfg(all(unix, feature = \"bar\"))"));
+        assert!(matches("cfg(any(windows, target_os = \"linux\"))"));
+        assert!(!matches("cfg(any())"));
+    }
+
+    #[test]
+    fn invalid_expressions() {
+        assert!(CfgExpr::parse_platform("x86_64-unknown-linux-gnu").is_none());
+        assert!(CfgExpr::parse_platform("cfg(unix").unwrap().is_err());
+        assert!(CfgExpr::parse_platform("cfg(not(unix, windows))").unwrap()
+                        .is_err());
+        assert!(CfgExpr::parse_platform("cfg(foo(unix))").unwrap().is_err());
+        assert!(CfgExpr::parse_platform("cfg(unix) x").unwrap().is_err());
+    }
+}
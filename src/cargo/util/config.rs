@@ -3,12 +3,16 @@ use std::cell::{RefCell, RefMut};
 use std::collections::hash_map::{HashMap, Occupied, Vacant};
 use std::io::fs::{PathExtensions, File};
 use std::string;
+use std::sync::{Once, ONCE_INIT};
 
 use serialize::{Encodable,Encoder};
 use toml;
-use core::MultiShell;
+use core::{MultiShell, SourceId};
+use core::resolver::Platforms;
 use ops;
 use util::{CargoResult, ChainError, Require, internal, human};
+use util::{human_of_kind, InvalidManifest};
+use util::cfg::Cfg;
 
 use util::toml as cargo_toml;
 
@@ -17,9 +21,18 @@ pub struct Config<'a> {
     shell: RefCell<&'a mut MultiShell>,
     jobs: uint,
     target: Option<string::String>,
+    /// The name of `target`, see `target_triple`
+    target_triple: Option<string::String>,
+    /// The contents of `target` if it's a custom target specification
+    target_spec: Option<string::String>,
     rustc_version: string::String,
     /// The current host and default target of rustc
     rustc_host: string::String,
+    /// The cfg values of each target probed so far, see `target_cfg`
+    target_cfgs: RefCell<HashMap<string::String, Vec<Cfg>>>,
+    /// The registry replaced by a local registry and the local registry's
+    /// directory, see `local_registry`
+    local_registry: Option<(SourceId, Path)>,
 }
 
 impl<'a> Config<'a> {
@@ -32,6 +45,31 @@ impl<'a> Config<'a> {
 
         let (rustc_version, rustc_host) = try!(ops::rustc_version());
 
+        // Target specifications are passed to processes running in other
+        // directories, so they're always referred to by absolute path.
+        let target = target.map(|t| {
+            if is_target_spec(t.as_slice()) {
+                os::make_absolute(&Path::new(t)).display().to_string()
+            } else {
+                t
+            }
+        });
+        let target_spec = match target {
+            Some(ref t) if is_target_spec(t.as_slice()) => {
+                Some(try!(File::open(&Path::new(t.as_slice())).read_to_string()
+                               .chain_error(|| {
+                    human(format!("failed to read the target specification \
+                                   `{}`", t))
+                })))
+            }
+            _ => None,
+        };
+
+        let local_registry = match try!(local_registry_dir()) {
+            Some(dir) => Some((try!(SourceId::for_central()), dir)),
+            None => None,
+        };
+
         Ok(Config {
             home_path: try!(os::homedir().require(|| {
                 human("Cargo couldn't find your home directory. \
@@ -39,9 +77,13 @@ impl<'a> Config<'a> {
             })),
             shell: RefCell::new(shell),
             jobs: jobs.unwrap_or(os::num_cpus()),
+            target_triple: target.as_ref().map(|t| target_name(t.as_slice())),
             target: target,
+            target_spec: target_spec,
             rustc_version: rustc_version,
             rustc_host: rustc_host,
+            target_cfgs: RefCell::new(HashMap::new()),
+            local_registry: local_registry,
         })
     }
 
@@ -75,10 +117,33 @@ impl<'a> Config<'a> {
         self.jobs
     }
 
+    /// Return the target passed to rustc, either a target triple or the
+    /// absolute path to a custom target specification.
     pub fn target(&self) -> Option<&str> {
         self.target.as_ref().map(|t| t.as_slice())
     }
 
+    /// Return the name of the target, which is the target triple itself or
+    /// the file stem of a custom target specification. This is what names the
+    /// target's output directory and `[target.<name>]` sections.
+    pub fn target_triple(&self) -> Option<&str> {
+        self.target_triple.as_ref().map(|t| t.as_slice())
+    }
+
+    /// Return the contents of the custom target specification, if any.
+    pub fn target_spec(&self) -> Option<&str> {
+        self.target_spec.as_ref().map(|t| t.as_slice())
+    }
+
+    /// Return the directory of the local registry used in place of the
+    /// registry `id`, if any.
+    pub fn local_registry(&self, id: &SourceId) -> Option<&Path> {
+        match self.local_registry {
+            Some((ref replaced, ref dir)) if replaced == id => Some(dir),
+            _ => None,
+        }
+    }
+
     /// Return the output of `rustc -v verbose`
     pub fn rustc_version(&self) -> &str {
         self.rustc_version.as_slice()
@@ -88,6 +153,81 @@ impl<'a> Config<'a> {
     pub fn rustc_host(&self) -> &str {
         self.rustc_host.as_slice()
     }
+
+    /// Return the cfg values rustc sets when compiling for `target`, a target
+    /// triple or custom target specification as passed to rustc, or for the
+    /// host if it's `None`.
+    ///
+    /// Each target is only probed once, the first time its values are needed.
+    pub fn target_cfg(&self, target: Option<&str>) -> CargoResult<Vec<Cfg>> {
+        let key = target.unwrap_or(self.rustc_host()).to_string();
+        match self.target_cfgs.borrow().get(&key) {
+            Some(cfg) => return Ok(cfg.clone()),
+            None => {}
+        }
+        let cfg = try!(ops::rustc_cfg(target));
+        self.target_cfgs.borrow_mut().insert(key, cfg.clone());
+        Ok(cfg)
+    }
+}
+
+impl<'a> Platforms for Config<'a> {
+    fn triple(&self, host: bool) -> &str {
+        if host {
+            self.rustc_host()
+        } else {
+            self.target_triple().unwrap_or(self.rustc_host())
+        }
+    }
+
+    fn cfg(&self, host: bool) -> CargoResult<Vec<Cfg>> {
+        if host {
+            self.target_cfg(None)
+        } else {
+            self.target_cfg(self.target())
+        }
+    }
+}
+
+/// Return the `registry.local` directory from the configuration. The
+/// configuration is only read the first time; every `Config` created after
+/// that reuses the answer.
+fn local_registry_dir() -> CargoResult<Option<Path>> {
+    static mut CACHED: *const Option<Path> = 0 as *const Option<Path>;
+    static READ: Once = ONCE_INIT;
+
+    let mut error = None;
+    READ.doit(|| {
+        match ops::registry_configuration() {
+            Ok(config) => unsafe { CACHED = mem::transmute(box config.local) },
+            Err(e) => error = Some(e),
+        }
+    });
+    match error {
+        Some(e) => return Err(e),
+        None => {}
+    }
+    unsafe {
+        if CACHED.is_null() {
+            return Ok(try!(ops::registry_configuration()).local)
+        }
+        Ok((*CACHED).clone())
+    }
+}
+
+/// Whether `target` is the path to a custom target specification rather than
+/// a target triple.
+pub fn is_target_spec(target: &str) -> bool {
+    target.ends_with(".json")
+}
+
+/// Return the name of `target`, see `Config::target_triple`.
+pub fn target_name(target: &str) -> string::String {
+    if is_target_spec(target) {
+        Path::new(target).filestem_str().unwrap_or(target).to_string()
+    } else {
+        target.to_string()
+    }
 }
 
 #[deriving(Eq,PartialEq,Clone,Encodable,Decodable)]
@@ -102,6 +242,7 @@ pub enum ConfigValue {
     List(Vec<(string::String, Path)>),
     Table(HashMap<string::String, ConfigValue>),
     Boolean(bool, Path),
+    Integer(i64, Path),
 }
 
 impl fmt::Show for ConfigValue {
@@ -120,6 +261,7 @@ impl fmt::Show for ConfigValue {
             }
             Table(ref table) => write!(f, "{}", table),
             Boolean(b, ref path) => write!(f, "{} (from {})", b, path.display()),
+            Integer(i, ref path) => write!(f, "{} (from {})", i, path.display()),
         }
     }
 }
@@ -134,6 +276,7 @@ impl<E, S: Encoder<E>> Encodable<S, E> for ConfigValue {
             }
             Table(ref table) => table.encode(s),
             Boolean(b, _) => b.encode(s),
+            Integer(i, _) => i.encode(s),
         }
     }
 }
@@ -143,6 +286,7 @@ impl ConfigValue {
         match toml {
             toml::String(val) => Ok(String(val, path.clone())),
             toml::Boolean(b) => Ok(Boolean(b, path.clone())),
+            toml::Integer(i) => Ok(Integer(i, path.clone())),
             toml::Array(val) => {
                 Ok(List(try!(val.into_iter().map(|toml| {
                     match toml {
@@ -164,7 +308,8 @@ impl ConfigValue {
     fn merge(&mut self, from: ConfigValue) -> CargoResult<()> {
         match (self, from) {
             (&String(..), String(..)) |
-            (&Boolean(..), Boolean(..)) => {}
+            (&Boolean(..), Boolean(..)) |
+            (&Integer(..), Integer(..)) => {}
             (&List(ref mut old), List(ref mut new)) => {
                 let new = mem::replace(new, Vec::new());
                 old.extend(new.into_iter());
@@ -219,18 +364,28 @@ impl ConfigValue {
         }
     }
 
+    pub fn integer(&self) -> CargoResult<(i64, &Path)> {
+        match *self {
+            Integer(i, ref p) => Ok((i, p)),
+            _ => Err(internal(format!("expected an integer, but found a {}",
+                                      self.desc()))),
+        }
+    }
+
     pub fn desc(&self) -> &'static str {
         match *self {
             Table(..) => "table",
             List(..) => "array",
             String(..) => "string",
             Boolean(..) => "boolean",
+            Integer(..) => "integer",
         }
     }
 
     fn into_toml(self) -> toml::Value {
         match self {
             Boolean(s, _) => toml::Boolean(s),
+            Integer(i, _) => toml::Integer(i),
             String(s, _) => toml::String(s),
             List(l) => toml::Array(l.into_iter().map(|(s, _)| toml::String(s))
                                     .collect()),
@@ -259,7 +414,9 @@ pub fn all_configs(pwd: Path) -> CargoResult<HashMap<string::String, ConfigValue
         let value = try!(ConfigValue::from_toml(&path, toml::Table(table)));
         try!(cfg.merge(value));
         Ok(())
-    }).chain_error(|| human("Couldn't load Cargo configuration")));
+    }).chain_error(|| {
+        human_of_kind(InvalidManifest, "Couldn't load Cargo configuration")
+    }));
 
 
     match cfg {
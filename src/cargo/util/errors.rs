@@ -17,16 +17,112 @@ pub trait CargoError: Send {
     fn cause(&self) -> Option<&CargoError> { None }
     fn is_human(&self) -> bool { false }
 
+    /// The message of this error alone. It differs from the description
+    /// only for errors which show their cause as part of the description.
+    fn message(&self) -> String { self.description() }
+
+    /// The file this error is about, if any.
+    fn file(&self) -> Option<Path> { None }
+
+    /// Whether the description already includes the cause, so that it isn't
+    /// repeated when the causes of the error are reported.
+    fn shows_cause(&self) -> bool { false }
+
+    /// The kind of failure this is, which is taken from the cause if the
+    /// error itself doesn't say.
+    fn kind(&self) -> Option<ErrorKind> {
+        self.cause().and_then(|c| c.kind())
+    }
+
     fn concrete(&self) -> ConcreteCargoError {
         ConcreteCargoError {
-            description: self.description(),
+            description: self.message(),
             detail: self.detail(),
             cause: self.cause().map(|c| box c.concrete() as Box<CargoError + Send>),
-            is_human: self.is_human()
+            is_human: self.is_human(),
+            kind: self.kind(),
+            file: self.file(),
+            shows_cause: self.shows_cause(),
+        }
+    }
+}
+
+/// Classifies errors for the exit code of cargo, so that scripts can tell
+/// apart failures which retrying won't fix from those which it might.
+///
+/// | exit code | meaning                                           |
+/// |-----------|---------------------------------------------------|
+/// | 0         | success                                           |
+/// | 1         | any other error, e.g. invalid command line usage  |
+/// | 2         | `BuildFailure`                                    |
+/// | 3         | `InvalidManifest`                                 |
+/// | 4         | `IoFailure`                                       |
+/// | 101       | an internal error or a panic within cargo         |
+#[deriving(PartialEq, Clone, Show)]
+pub enum ErrorKind {
+    /// A crate failed to compile or document, or a test or benchmark failed.
+    BuildFailure,
+    /// A manifest, lockfile or configuration file is invalid.
+    InvalidManifest,
+    /// A network or filesystem operation failed.
+    IoFailure,
+}
+
+impl ErrorKind {
+    pub fn exit_code(&self) -> uint {
+        match *self {
+            BuildFailure => 2,
+            InvalidManifest => 3,
+            IoFailure => 4,
+        }
+    }
+
+    /// The name of the kind in machine readable output.
+    pub fn name(&self) -> &'static str {
+        match *self {
+            BuildFailure => "build-failure",
+            InvalidManifest => "invalid-manifest",
+            IoFailure => "io-failure",
         }
     }
 }
 
+/// One layer of the chain of an error, outermost first, as it's written in
+/// machine readable output.
+#[deriving(Encodable, Show)]
+pub struct ErrorLayer {
+    pub message: String,
+    pub file: Option<String>,
+    pub kind: Option<String>,
+}
+
+/// Returns the layers of `err` and of all of its causes.
+pub fn error_chain(err: &CargoError) -> Vec<ErrorLayer> {
+    let mut ret = vec![ErrorLayer {
+        message: err.message(),
+        file: err.file().map(|f| f.display().to_string()),
+        kind: err.kind().map(|k| k.name().to_string()),
+    }];
+    match err.cause() {
+        Some(cause) => ret.extend(error_chain(cause).into_iter()),
+        None => {}
+    }
+    ret
+}
+
+/// Returns the first cause of `err` which isn't already shown as part of its
+/// description.
+pub fn reported_cause(err: &CargoError) -> Option<&CargoError> {
+    let mut err = err;
+    while err.shows_cause() {
+        match err.cause() {
+            Some(cause) => err = cause,
+            None => return None,
+        }
+    }
+    err.cause()
+}
+
 pub trait FromError<E> {
     fn from_error(error: E) -> Self;
 }
@@ -59,6 +155,10 @@ impl CargoError for Box<CargoError + Send> {
     fn detail(&self) -> Option<String> { (**self).detail() }
     fn cause(&self) -> Option<&CargoError> { (**self).cause() }
     fn is_human(&self) -> bool { (**self).is_human() }
+    fn kind(&self) -> Option<ErrorKind> { (**self).kind() }
+    fn message(&self) -> String { (**self).message() }
+    fn file(&self) -> Option<Path> { (**self).file() }
+    fn shows_cause(&self) -> bool { (**self).shows_cause() }
 }
 
 impl CargoError for semver::ReqParseError {
@@ -97,12 +197,14 @@ impl<T, E: CargoError + Send> ChainError<T> for Result<T, E> {
 
 impl CargoError for IoError {
     fn description(&self) -> String { self.to_string() }
+    fn kind(&self) -> Option<ErrorKind> { Some(IoFailure) }
 }
 
 from_error!(IoError)
 
 impl CargoError for TomlError {
     fn description(&self) -> String { self.to_string() }
+    fn kind(&self) -> Option<ErrorKind> { Some(InvalidManifest) }
 }
 
 from_error!(TomlError)
@@ -117,6 +219,7 @@ from_error!(FormatError)
 
 impl CargoError for curl::ErrCode {
     fn description(&self) -> String { self.to_string() }
+    fn kind(&self) -> Option<ErrorKind> { Some(IoFailure) }
 }
 
 from_error!(curl::ErrCode)
@@ -193,7 +296,10 @@ pub struct ConcreteCargoError {
     description: String,
     detail: Option<String>,
     cause: Option<Box<CargoError + Send>>,
-    is_human: bool
+    is_human: bool,
+    kind: Option<ErrorKind>,
+    file: Option<Path>,
+    shows_cause: bool,
 }
 
 impl ConcreteCargoError {
@@ -207,19 +313,41 @@ impl ConcreteCargoError {
         self.is_human = true;
         box self as Box<CargoError + Send>
     }
+
+    pub fn of_kind(mut self, kind: ErrorKind) -> ConcreteCargoError {
+        self.kind = Some(kind);
+        self
+    }
 }
 
 impl Show for ConcreteCargoError {
     fn fmt(&self, f: &mut Formatter) -> fmt::Result {
-        write!(f, "{}", self.description)
+        write!(f, "{}", self.description())
     }
 }
 
 impl CargoError for ConcreteCargoError {
     fn description(&self) -> String {
+        match self.cause {
+            Some(ref cause) if self.shows_cause => {
+                format!("{}\n\n{}", self.description, cause.description())
+            }
+            _ => self.description.clone(),
+        }
+    }
+
+    fn message(&self) -> String {
         self.description.clone()
     }
 
+    fn file(&self) -> Option<Path> {
+        self.file.clone()
+    }
+
+    fn shows_cause(&self) -> bool {
+        self.shows_cause
+    }
+
     fn detail(&self) -> Option<String> {
         self.detail.clone()
     }
@@ -231,6 +359,10 @@ impl CargoError for ConcreteCargoError {
     fn is_human(&self) -> bool {
         self.is_human
     }
+
+    fn kind(&self) -> Option<ErrorKind> {
+        self.kind.clone().or_else(|| self.cause().and_then(|c| c.kind()))
+    }
 }
 
 pub type CliResult<T> = Result<T, CliError>;
@@ -250,6 +382,12 @@ impl CargoError for CliError {
 
 from_error!(CliError)
 
+impl FromError<Box<CargoError + Send>> for CliError {
+    fn from_error(error: Box<CargoError + Send>) -> CliError {
+        CliError::from_boxed(error, 1)
+    }
+}
+
 impl CargoError for docopt::Error {
     fn description(&self) -> String {
         match *self {
@@ -280,6 +418,7 @@ from_error!(url::ParseError)
 
 impl CargoError for git2::Error {
     fn description(&self) -> String { self.to_string() }
+    fn kind(&self) -> Option<ErrorKind> { Some(IoFailure) }
 }
 
 from_error!(git2::Error)
@@ -295,8 +434,23 @@ impl CliError {
         CliError::from_boxed(error, code)
     }
 
+    /// Wraps `error`, exiting with the code of its kind if it has one, with
+    /// 101 if it is an internal error and with `code` otherwise.
     pub fn from_boxed(error: Box<CargoError + Send>, code: uint) -> CliError {
         let human = error.is_human();
+        let code = match error.kind() {
+            Some(kind) => kind.exit_code(),
+            None if !human => 101,
+            None => code,
+        };
+        CliError { error: error, exit_code: code, unknown: !human }
+    }
+
+    /// Wraps `error`, exiting with exactly `code` whatever kind of error it
+    /// is, e.g. to pass on the exit status of a program run by cargo.
+    pub fn with_exit_code(error: Box<CargoError + Send>,
+                          code: uint) -> CliError {
+        let human = error.is_human();
         CliError { error: error, exit_code: code, unknown: !human }
     }
 }
@@ -320,7 +474,10 @@ pub fn internal_error<S1: Str, S2: Str>(error: S1,
         description: error.as_slice().to_string(),
         detail: Some(detail.as_slice().to_string()),
         cause: None,
-        is_human: false
+        is_human: false,
+        kind: None,
+        file: None,
+        shows_cause: false,
     } as Box<CargoError + Send>
 }
 
@@ -329,7 +486,10 @@ pub fn internal<S: Show>(error: S) -> Box<CargoError + Send> {
         description: error.to_string(),
         detail: None,
         cause: None,
-        is_human: false
+        is_human: false,
+        kind: None,
+        file: None,
+        shows_cause: false,
     } as Box<CargoError + Send>
 }
 
@@ -338,7 +498,10 @@ pub fn human<S: Show>(error: S) -> Box<CargoError + Send> {
         description: error.to_string(),
         detail: None,
         cause: None,
-        is_human: true
+        is_human: true,
+        kind: None,
+        file: None,
+        shows_cause: false,
     } as Box<CargoError + Send>
 }
 
@@ -347,6 +510,57 @@ pub fn caused_human<S: Show, E: CargoError + Send>(error: S, cause: E) -> Box<Ca
         description: error.to_string(),
         detail: None,
         cause: Some(box cause as Box<CargoError + Send>),
-        is_human: true
+        is_human: true,
+        kind: None,
+        file: None,
+        shows_cause: false,
+    } as Box<CargoError + Send>
+}
+
+/// A human error which is also classified as a `kind` of failure.
+pub fn human_of_kind<S: Show>(kind: ErrorKind,
+                              error: S) -> Box<CargoError + Send> {
+    box ConcreteCargoError {
+        description: error.to_string(),
+        detail: None,
+        cause: None,
+        is_human: true,
+        kind: Some(kind),
+        file: None,
+        shows_cause: false,
+    } as Box<CargoError + Send>
+}
+
+/// A human error of `kind` which is shown followed by the description of
+/// `cause`, whose own causes are reported after it as usual.
+pub fn human_with_cause<S: Show, E: CargoError + Send>(kind: ErrorKind,
+                                                       error: S, cause: E)
+                                                       -> Box<CargoError + Send> {
+    box ConcreteCargoError {
+        description: error.to_string(),
+        detail: None,
+        cause: Some(box cause as Box<CargoError + Send>),
+        is_human: true,
+        kind: Some(kind),
+        file: None,
+        shows_cause: true,
+    } as Box<CargoError + Send>
+}
+
+/// A human error about `file`, which adds the context `error` to `cause`.
+/// Both are shown to humans as a single message, while they are kept as two
+/// layers of the error for machine readable output.
+pub fn human_context<S: Show, E: CargoError + Send>(kind: ErrorKind,
+                                                    file: &Path, error: S,
+                                                    cause: E)
+                                                    -> Box<CargoError + Send> {
+    box ConcreteCargoError {
+        description: error.to_string(),
+        detail: None,
+        cause: Some(box cause as Box<CargoError + Send>),
+        is_human: true,
+        kind: Some(kind),
+        file: Some(file.clone()),
+        shows_cause: true,
     } as Box<CargoError + Send>
 }
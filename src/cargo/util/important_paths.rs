@@ -1,4 +1,6 @@
+use std::ascii::AsciiExt;
 use std::os;
+use std::io::fs;
 use std::io::fs::PathExtensions;
 use util::{CargoResult, CliError, CliResult, human};
 
@@ -16,29 +18,60 @@ pub fn find_project(pwd: &Path, file: &str) -> CargoResult<Path> {
 /// Iteratively search for `file` in `pwd` and its parents, returning
 /// the path to the file.
 pub fn find_project_manifest(pwd: &Path, file: &str) -> CargoResult<Path> {
+    search_upwards(pwd, file).map_err(|searched| {
+        match misnamed_in(searched.as_slice(), file) {
+            Some(msg) => human(msg),
+            None => human(format!("Could not find `{}` in `{}` or any parent \
+                                   directory", file, pwd.display())),
+        }
+    })
+}
+
+/// Search for `file` in `pwd` and its parents, stopping after the user's home
+/// directory (if `pwd` is inside of it) or the root of the filesystem.
+///
+/// On failure the list of directories which were searched is returned.
+fn search_upwards(pwd: &Path, file: &str) -> Result<Path, Vec<Path>> {
+    let home = os::homedir();
     let mut current = pwd.clone();
+    let mut searched = Vec::new();
 
     loop {
         let manifest = current.join(file);
         if manifest.exists() {
             return Ok(manifest)
         }
+        searched.push(current.clone());
 
-        if !current.pop() { break; }
+        if Some(&current) == home.as_ref() || !current.pop() { break }
     }
 
-    Err(human(format!("Could not find `{}` in `{}` or any parent directory",
-                      file, pwd.display())))
+    Err(searched)
 }
 
 /// Find the root Cargo.toml
+///
+/// An explicit `--manifest-path` is used as-is, otherwise the current
+/// directory and its parents are searched.
 pub fn find_root_manifest_for_cwd(manifest_path: Option<String>) -> CliResult<Path> {
     match manifest_path {
-        Some(path) => Ok(Path::new(path)),
-        None => match find_project_manifest(&os::getcwd(), "Cargo.toml") {
+        Some(path) => Ok(os::make_absolute(&Path::new(path))),
+        None => match search_upwards(&os::getcwd(), "Cargo.toml") {
             Ok(x) => Ok(x),
-            Err(_) => Err(CliError::new("Could not find Cargo.toml in this \
-                                         directory or any parent directory", 102))
+            Err(searched) => {
+                match misnamed_in(searched.as_slice(), "Cargo.toml") {
+                    Some(msg) => return Err(CliError::new(msg, 102)),
+                    None => {}
+                }
+                let mut msg = String::from_str("Could not find Cargo.toml in \
+                                                this directory or any parent \
+                                                directory\ndirectories searched:");
+                for dir in searched.iter() {
+                    msg.push_str(format!("\n    {}", dir.display()).as_slice());
+                }
+                msg.push_str("\nTo create a new package, run `cargo new`");
+                Err(CliError::new(msg, 102))
+            }
         }
     }.map(|path| os::make_absolute(&path))
 }
@@ -50,7 +83,40 @@ pub fn find_project_manifest_exact(pwd: &Path, file: &str) -> CargoResult<Path>
     if manifest.exists() {
         Ok(manifest)
     } else {
-        Err(human(format!("Could not find `{}` in `{}`",
-                          file, pwd.display())))
+        match misnamed_in(&[pwd.clone()], file) {
+            Some(msg) => Err(human(msg)),
+            None => Err(human(format!("Could not find `{}` in `{}`",
+                                      file, pwd.display()))),
+        }
     }
 }
+
+/// Return the name of a file in `dir` which is `file` spelled with different
+/// case, such as `cargo.toml` for `Cargo.toml`, if there is no file spelled
+/// exactly `file` next to it.
+///
+/// On a case-insensitive filesystem such a file is found as `file`, but the
+/// same project can't be built on a case-sensitive one.
+pub fn misnamed_manifest(dir: &Path, file: &str) -> Option<String> {
+    let names = match fs::readdir(dir) {
+        Ok(entries) => entries,
+        Err(..) => return None,
+    };
+    let names = names.iter().filter_map(|p| p.filename_str())
+                     .collect::<Vec<&str>>();
+    if names.iter().any(|name| *name == file) { return None }
+    names.iter().find(|name| name.eq_ignore_ascii_case(file))
+         .map(|name| name.to_string())
+}
+
+// An error for the first of `dirs` which contains a misnamed `file`.
+fn misnamed_in(dirs: &[Path], file: &str) -> Option<String> {
+    dirs.iter().filter_map(|dir| {
+        misnamed_manifest(dir, file).map(|name| {
+            format!("Could not find `{}` in `{}`, but found `{}`\n\
+                     rename it to `{}`, manifest file names are case \
+                     sensitive on most platforms",
+                    file, dir.display(), name, file)
+        })
+    }).next()
+}
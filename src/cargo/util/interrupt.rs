@@ -0,0 +1,178 @@
+//! Handling of Ctrl-C while the job queue is running.
+//!
+//! While a `Guard` is alive an interrupt no longer kills cargo outright.
+//! Instead it is recorded (see `interrupted`) and forwarded to the child
+//! processes cargo spawned (see `register`), and the job queue is responsible
+//! for waiting for those children to exit before unwinding. This ensures that
+//! cargo never records success for an artifact which was only partially
+//! written.
+
+use std::sync::atomic::{AtomicBool, INIT_ATOMIC_BOOL, SeqCst};
+
+pub use self::imp::{install, forwarding, reserve, Registration};
+
+static INTERRUPTED: AtomicBool = INIT_ATOMIC_BOOL;
+
+/// Returns whether an interrupt has been received while a `Guard` was alive.
+pub fn interrupted() -> bool {
+    INTERRUPTED.load(SeqCst)
+}
+
+/// Restores the default interrupt behavior when dropped.
+pub struct Guard {
+    _priv: (),
+}
+
+#[cfg(unix)]
+mod imp {
+    use std::sync::atomic::{AtomicBool, AtomicInt, INIT_ATOMIC_BOOL};
+    use std::sync::atomic::{INIT_ATOMIC_INT, SeqCst};
+    use libc;
+
+    use super::{Guard, INTERRUPTED};
+
+    const SIGINT: libc::c_int = 2;
+    const SIG_DFL: libc::size_t = 0;
+
+    extern {
+        fn signal(signum: libc::c_int, handler: libc::size_t) -> libc::size_t;
+        fn kill(pid: libc::pid_t, sig: libc::c_int) -> libc::c_int;
+    }
+
+    static FORWARDING: AtomicBool = INIT_ATOMIC_BOOL;
+
+    // The pids of the running children an interrupt is forwarded to, with 0
+    // marking a free slot and -1 a slot reserved for a child which is being
+    // spawned. The signal handler may not allocate or take locks, so this is
+    // a fixed table of atomics.
+    static CHILDREN: [AtomicInt, ..32] = [
+        INIT_ATOMIC_INT, INIT_ATOMIC_INT, INIT_ATOMIC_INT, INIT_ATOMIC_INT,
+        INIT_ATOMIC_INT, INIT_ATOMIC_INT, INIT_ATOMIC_INT, INIT_ATOMIC_INT,
+        INIT_ATOMIC_INT, INIT_ATOMIC_INT, INIT_ATOMIC_INT, INIT_ATOMIC_INT,
+        INIT_ATOMIC_INT, INIT_ATOMIC_INT, INIT_ATOMIC_INT, INIT_ATOMIC_INT,
+        INIT_ATOMIC_INT, INIT_ATOMIC_INT, INIT_ATOMIC_INT, INIT_ATOMIC_INT,
+        INIT_ATOMIC_INT, INIT_ATOMIC_INT, INIT_ATOMIC_INT, INIT_ATOMIC_INT,
+        INIT_ATOMIC_INT, INIT_ATOMIC_INT, INIT_ATOMIC_INT, INIT_ATOMIC_INT,
+        INIT_ATOMIC_INT, INIT_ATOMIC_INT, INIT_ATOMIC_INT, INIT_ATOMIC_INT,
+    ];
+
+    /// Installs the interrupt handler, returning a guard which uninstalls it.
+    pub fn install() -> Guard {
+        FORWARDING.store(true, SeqCst);
+        unsafe { signal(SIGINT, handler as libc::size_t); }
+        Guard { _priv: () }
+    }
+
+    impl Drop for Guard {
+        fn drop(&mut self) {
+            unsafe { signal(SIGINT, SIG_DFL); }
+            FORWARDING.store(false, SeqCst);
+        }
+    }
+
+    /// Returns whether interrupts are currently forwarded to children.
+    ///
+    /// Children spawned meanwhile should reserve a slot with `reserve`, and
+    /// if they got one be put in a process group of their own and registered,
+    /// so that a Ctrl-C from the terminal reaches them exactly once, through
+    /// cargo.
+    pub fn forwarding() -> bool {
+        FORWARDING.load(SeqCst)
+    }
+
+    /// Keeps a slot reserved with `reserve` until dropped.
+    pub struct Registration {
+        slot: uint,
+    }
+
+    /// Reserves a slot for a child which is about to be spawned.
+    ///
+    /// `None` is returned when every slot is taken. Interrupts can't be
+    /// forwarded to such a child, so it must be left in cargo's process group
+    /// to receive a Ctrl-C from the terminal directly.
+    pub fn reserve() -> Option<Registration> {
+        CHILDREN.iter().position(|c| {
+            c.compare_and_swap(0, -1, SeqCst) == 0
+        }).map(|slot| Registration { slot: slot })
+    }
+
+    impl Registration {
+        /// Registers the running child `pid` to have interrupts forwarded to
+        /// it. The child must be waited for before the registration is
+        /// dropped.
+        pub fn register(&mut self, pid: libc::pid_t) {
+            CHILDREN[self.slot].store(pid as int, SeqCst);
+            // An interrupt received before the child was registered has not
+            // been forwarded to it yet.
+            if super::interrupted() {
+                unsafe { kill(pid, SIGINT); }
+            }
+        }
+    }
+
+    impl Drop for Registration {
+        fn drop(&mut self) {
+            CHILDREN[self.slot].store(0, SeqCst);
+        }
+    }
+
+    extern fn handler(_signum: libc::c_int) {
+        if INTERRUPTED.swap(true, SeqCst) { return }
+
+        for child in CHILDREN.iter() {
+            let pid = child.load(SeqCst);
+            if pid > 0 {
+                unsafe { kill(pid as libc::pid_t, SIGINT); }
+            }
+        }
+    }
+}
+
+#[cfg(windows)]
+mod imp {
+    use std::sync::atomic::SeqCst;
+    use libc::{mod, BOOL, DWORD, TRUE, FALSE};
+
+    use super::{Guard, INTERRUPTED};
+
+    const CTRL_C_EVENT: DWORD = 0;
+
+    #[allow(non_snake_case)]
+    extern "system" {
+        fn SetConsoleCtrlHandler(HandlerRoutine: extern "system" fn(DWORD) -> BOOL,
+                                 Add: BOOL) -> BOOL;
+    }
+
+    /// Installs the interrupt handler, returning a guard which uninstalls it.
+    ///
+    /// Children share cargo's console, so they receive the Ctrl-C event
+    /// themselves and nothing needs to be forwarded.
+    pub fn install() -> Guard {
+        unsafe { SetConsoleCtrlHandler(handler, TRUE); }
+        Guard { _priv: () }
+    }
+
+    impl Drop for Guard {
+        fn drop(&mut self) {
+            unsafe { SetConsoleCtrlHandler(handler, FALSE); }
+        }
+    }
+
+    /// Children share cargo's console on Windows, so interrupts are never
+    /// forwarded.
+    pub fn forwarding() -> bool { false }
+
+    pub struct Registration;
+
+    pub fn reserve() -> Option<Registration> { None }
+
+    impl Registration {
+        pub fn register(&mut self, _pid: libc::pid_t) {}
+    }
+
+    extern "system" fn handler(event: DWORD) -> BOOL {
+        if event != CTRL_C_EVENT { return FALSE }
+        INTERRUPTED.store(true, SeqCst);
+        TRUE
+    }
+}
@@ -0,0 +1,108 @@
+//! Validation of the `license` manifest key against the SPDX license list.
+
+/// The SPDX license identifiers, one per line, as printed by
+/// `src/etc/print-spdx-licenses.py`.
+static LICENSES: &'static str = include_str!("../../etc/spdx-licenses.txt");
+
+fn known_licenses() -> Vec<&'static str> {
+    LICENSES.lines().map(|l| l.trim()).filter(|l| {
+        !l.is_empty() && !l.starts_with("#")
+    }).collect()
+}
+
+/// Checks that `expr` is a known SPDX license identifier, or several of them
+/// combined with `/`, `OR` or `AND`, and returns it with every identifier and
+/// operator in its canonical case.
+///
+/// An error names the first identifier which isn't known along with the
+/// known one closest to it.
+pub fn normalize_license(expr: &str) -> Result<String, String> {
+    let known = known_licenses();
+    let mut normalized = String::new();
+    let mut words = Vec::new();
+    for token in tokens(expr).into_iter() {
+        let op = match lower(token).as_slice() {
+            "/" => "/",
+            "or" => " OR ",
+            "and" => " AND ",
+            _ => { words.push(token); continue }
+        };
+        normalized.push_str(try!(identifier(expr, words.as_slice(),
+                                            known.as_slice())));
+        normalized.push_str(op);
+        words.clear();
+    }
+    normalized.push_str(try!(identifier(expr, words.as_slice(),
+                                        known.as_slice())));
+    Ok(normalized)
+}
+
+/// Splits `expr` into words, with each `/` a word of its own.
+fn tokens(expr: &str) -> Vec<&str> {
+    let mut ret = Vec::new();
+    for word in expr.words() {
+        for (i, part) in word.split('/').enumerate() {
+            if i > 0 { ret.push("/") }
+            if !part.is_empty() { ret.push(part) }
+        }
+    }
+    ret
+}
+
+/// Finds the known identifier spelled by `words`, which are the words
+/// between two operators of `expr`.
+fn identifier(expr: &str, words: &[&str],
+              known: &[&'static str]) -> Result<&'static str, String> {
+    if words.is_empty() {
+        return Err(format!("`{}` is not a valid SPDX license expression, a \
+                            license identifier is missing", expr))
+    }
+    let name = words.connect(" ");
+    let lowered = lower(name.as_slice());
+    match known.iter().find(|k| lower(**k) == lowered) {
+        Some(k) => return Ok(*k),
+        None => {}
+    }
+    let closest = known.iter().min_by(|k| {
+        lower(**k).as_slice().lev_distance(lowered.as_slice())
+    }).unwrap();
+    Err(format!("`{}` is not a valid SPDX license expression: `{}` is not a \
+                 known license identifier, did you mean `{}`?",
+                expr, name, closest))
+}
+
+fn lower(s: &str) -> String {
+    s.chars().map(|c| c.to_lowercase()).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::normalize_license;
+
+    #[test]
+    fn compound_expressions() {
+        assert_eq!(normalize_license("MIT/Apache-2.0"),
+                   Ok("MIT/Apache-2.0".to_string()));
+        assert_eq!(normalize_license("MIT OR Apache-2.0"),
+                   Ok("MIT OR Apache-2.0".to_string()));
+        assert_eq!(normalize_license("MIT AND BSD-3-Clause / Zlib"),
+                   Ok("MIT AND BSD-3-Clause/Zlib".to_string()));
+    }
+
+    #[test]
+    fn case_is_normalized() {
+        assert_eq!(normalize_license("mit"), Ok("MIT".to_string()));
+        assert_eq!(normalize_license("apache-2.0 or mit"),
+                   Ok("Apache-2.0 OR MIT".to_string()));
+    }
+
+    #[test]
+    fn unknown_licenses() {
+        assert_eq!(normalize_license("MIT/Apache 2.0"),
+                   Err("`MIT/Apache 2.0` is not a valid SPDX license \
+                        expression: `Apache 2.0` is not a known license \
+                        identifier, did you mean `Apache-2.0`?".to_string()));
+        assert!(normalize_license("MIT/").is_err());
+        assert!(normalize_license("").is_err());
+    }
+}
@@ -0,0 +1,588 @@
+//! Targeted edits of a `Cargo.toml` which leave the rest of the file alone.
+//!
+//! The manifest is scanned just far enough to learn where each table header,
+//! entry and value lives. An edit then splices new text into the original
+//! contents, so comments, ordering and whitespace outside of the edited span
+//! are preserved byte for byte.
+
+use util::{CargoResult, CargoError, human};
+
+pub struct ManifestEditor {
+    contents: String,
+}
+
+/// A `key = value` entry of a table.
+struct Entry {
+    key: String,
+    /// From the start of the entry's line through its trailing newline.
+    line: (uint, uint),
+    /// Just the value.
+    value: (uint, uint),
+}
+
+/// A table along with the entries directly below its header. Entries which
+/// precede the first header belong to a table with an empty name.
+struct Table {
+    name: Vec<String>,
+    /// From the start of the header's line through its trailing newline.
+    header: (uint, uint),
+    /// From the start of the header through the end of the last entry.
+    span: (uint, uint),
+    entries: Vec<Entry>,
+}
+
+impl Table {
+    fn is(&self, name: &[&str]) -> bool {
+        self.name.len() == name.len() &&
+            self.name.iter().zip(name.iter()).all(|(a, b)| a.as_slice() == *b)
+    }
+
+    fn entry(&self, key: &str) -> Option<&Entry> {
+        self.entries.iter().find(|e| e.key.as_slice() == key)
+    }
+}
+
+impl ManifestEditor {
+    pub fn new(contents: &str) -> CargoResult<ManifestEditor> {
+        try!(parse(contents));
+        Ok(ManifestEditor { contents: contents.to_string() })
+    }
+
+    pub fn contents(&self) -> &str { self.contents.as_slice() }
+
+    /// Sets the `version` key of the `[package]` (or `[project]`) section.
+    pub fn set_version(&mut self, version: &str) -> CargoResult<()> {
+        let tables = try!(parse(self.contents.as_slice()));
+        let span = tables.iter().filter(|t| {
+            t.is(["package"]) || t.is(["project"])
+        }).filter_map(|t| t.entry("version")).map(|e| e.value).next();
+        match span {
+            Some(span) => Ok(self.splice(span, quote(version).as_slice())),
+            None => Err(human("no `version` key found in the `[package]` \
+                               section")),
+        }
+    }
+
+    /// Sets the version requirement of dependency `name` in `section` (for
+    /// example `dependencies` or `dev-dependencies`), adding the dependency
+    /// if it is not listed yet.
+    pub fn set_dependency(&mut self, section: &str, name: &str,
+                          req: &str) -> CargoResult<()> {
+        let tables = try!(parse(self.contents.as_slice()));
+        let req = quote(req);
+
+        // The dependency has a table of its own, `[dependencies.name]`
+        match tables.iter().find(|t| t.is([section, name])) {
+            Some(t) => {
+                match t.entry("version") {
+                    Some(e) => self.splice(e.value, req.as_slice()),
+                    None => {
+                        let (_, end) = t.header;
+                        let indent = self.indent(t.entries.as_slice().head());
+                        self.insert(end, format!("{}version = {}\n", indent,
+                                                 req).as_slice());
+                    }
+                }
+                return Ok(())
+            }
+            None => {}
+        }
+
+        let line = format!("{} = {}\n", name, req);
+        match tables.iter().find(|t| t.is([section])) {
+            Some(t) => match t.entry(name) {
+                Some(e) => {
+                    let (lo, _) = e.value;
+                    if self.contents.as_bytes()[lo] == b'{' {
+                        return Err(human(format!("dependency `{}` is an \
+                                                  inline table, which cannot \
+                                                  be edited", name)))
+                    }
+                    self.splice(e.value, req.as_slice());
+                }
+                None => {
+                    let (_, end) = t.span;
+                    let indent = self.indent(t.entries.last());
+                    self.insert(end, format!("{}{}", indent,
+                                             line).as_slice());
+                }
+            },
+            None => self.append_table(section, line.as_slice()),
+        }
+        Ok(())
+    }
+
+    /// Removes dependency `name` from `section`, returning whether it was
+    /// listed at all.
+    pub fn remove_dependency(&mut self, section: &str,
+                             name: &str) -> CargoResult<bool> {
+        let tables = try!(parse(self.contents.as_slice()));
+        match tables.iter().find(|t| t.is([section, name])) {
+            Some(t) => { self.splice(t.span, ""); return Ok(true) }
+            None => {}
+        }
+        let entry = tables.iter().find(|t| t.is([section])).and_then(|t| {
+            t.entry(name)
+        });
+        match entry {
+            Some(e) => { self.splice(e.line, ""); Ok(true) }
+            None => Ok(false),
+        }
+    }
+
+    /// Sets feature `name` to enable `features`, adding it to the
+    /// `[features]` section if necessary.
+    pub fn set_feature(&mut self, name: &str,
+                       features: &[String]) -> CargoResult<()> {
+        let tables = try!(parse(self.contents.as_slice()));
+        let list = features.iter().map(|f| quote(f.as_slice()))
+                           .collect::<Vec<String>>();
+        let list = format!("[{}]", list.connect(", "));
+        let line = format!("{} = {}\n", name, list);
+        match tables.iter().find(|t| t.is(["features"])) {
+            Some(t) => match t.entry(name) {
+                Some(e) => self.splice(e.value, list.as_slice()),
+                None => {
+                    let (_, end) = t.span;
+                    let indent = self.indent(t.entries.last());
+                    self.insert(end, format!("{}{}", indent,
+                                             line).as_slice());
+                }
+            },
+            None => self.append_table("features", line.as_slice()),
+        }
+        Ok(())
+    }
+
+    /// The whitespace which `entry`'s line starts with, so that new entries
+    /// line up with their neighbours.
+    fn indent(&self, entry: Option<&Entry>) -> String {
+        let (lo, hi) = match entry {
+            Some(e) => e.line,
+            None => return String::new(),
+        };
+        let line = self.contents.as_slice().slice(lo, hi);
+        let end = line.find(|c: char| c != ' ' && c != '\t').unwrap_or(0);
+        line.slice_to(end).to_string()
+    }
+
+    fn splice(&mut self, (lo, hi): (uint, uint), text: &str) {
+        let mut s = self.contents.as_slice().slice_to(lo).to_string();
+        s.push_str(text);
+        s.push_str(self.contents.as_slice().slice_from(hi));
+        self.contents = s;
+    }
+
+    /// Inserts `line` at `at`, which is the end of an existing line (or of
+    /// the file, which may lack a trailing newline).
+    fn insert(&mut self, at: uint, line: &str) {
+        let mut text = String::new();
+        if at > 0 && self.contents.as_bytes()[at - 1] != b'\n' {
+            text.push_str("\n");
+        }
+        text.push_str(line);
+        self.splice((at, at), text.as_slice());
+    }
+
+    fn append_table(&mut self, name: &str, line: &str) {
+        let mut text = String::new();
+        if self.contents.len() > 0 {
+            if !self.contents.as_slice().ends_with("\n") {
+                text.push_str("\n");
+            }
+            text.push_str("\n");
+        }
+        text.push_str(format!("[{}]\n{}", name, line).as_slice());
+        let end = self.contents.len();
+        self.splice((end, end), text.as_slice());
+    }
+}
+
+fn quote(s: &str) -> String {
+    format!("\"{}\"", s.replace("\\", "\\\\").replace("\"", "\\\""))
+}
+
+fn parse(s: &str) -> CargoResult<Vec<Table>> {
+    let mut p = Parser { bytes: s.as_bytes(), pos: 0 };
+    let mut tables = vec![Table {
+        name: Vec::new(),
+        header: (0, 0),
+        span: (0, 0),
+        entries: Vec::new(),
+    }];
+    loop {
+        p.skip_trivia();
+        if p.eof() { break }
+        let start = p.line_start();
+        if p.peek() == b'[' {
+            let name = try!(p.header());
+            try!(p.end_of_line());
+            tables.push(Table {
+                name: name,
+                header: (start, p.pos),
+                span: (start, p.pos),
+                entries: Vec::new(),
+            });
+        } else {
+            let key = try!(p.key());
+            p.skip_ws();
+            try!(p.expect(b'='));
+            p.skip_ws();
+            let lo = p.pos;
+            try!(p.value());
+            let hi = p.pos;
+            try!(p.end_of_line());
+            let table = tables.last_mut().unwrap();
+            let (table_start, _) = table.span;
+            table.span = (table_start, p.pos);
+            table.entries.push(Entry {
+                key: key,
+                line: (start, p.pos),
+                value: (lo, hi),
+            });
+        }
+    }
+    Ok(tables)
+}
+
+struct Parser<'a> {
+    bytes: &'a [u8],
+    pos: uint,
+}
+
+impl<'a> Parser<'a> {
+    fn eof(&self) -> bool { self.pos >= self.bytes.len() }
+
+    fn peek(&self) -> u8 { self.bytes[self.pos] }
+
+    fn error(&self, msg: &str) -> Box<CargoError> {
+        let line = self.bytes.slice_to(self.pos).iter()
+                       .filter(|&&b| b == b'\n').count() + 1;
+        human(format!("{} on line {} of the manifest", msg, line))
+    }
+
+    fn expect(&mut self, b: u8) -> CargoResult<()> {
+        if self.eof() || self.peek() != b {
+            return Err(self.error(format!("expected `{}`",
+                                          b as char).as_slice()))
+        }
+        self.pos += 1;
+        Ok(())
+    }
+
+    fn line_start(&self) -> uint {
+        match self.bytes.slice_to(self.pos).iter().rposition(|&b| b == b'\n') {
+            Some(i) => i + 1,
+            None => 0,
+        }
+    }
+
+    /// Skips spaces and tabs.
+    fn skip_ws(&mut self) {
+        while !self.eof() && (self.peek() == b' ' || self.peek() == b'\t') {
+            self.pos += 1;
+        }
+    }
+
+    /// Skips whitespace, newlines and comments.
+    fn skip_trivia(&mut self) {
+        loop {
+            self.skip_ws();
+            if self.eof() { return }
+            match self.peek() {
+                b'\n' | b'\r' => self.pos += 1,
+                b'#' => self.skip_comment(),
+                _ => return,
+            }
+        }
+    }
+
+    fn skip_comment(&mut self) {
+        while !self.eof() && self.peek() != b'\n' {
+            self.pos += 1;
+        }
+    }
+
+    /// Skips trailing whitespace and a comment, then consumes the newline.
+    fn end_of_line(&mut self) -> CargoResult<()> {
+        self.skip_ws();
+        if !self.eof() && self.peek() == b'#' { self.skip_comment() }
+        if !self.eof() && self.peek() == b'\r' { self.pos += 1 }
+        if self.eof() { return Ok(()) }
+        self.expect(b'\n')
+    }
+
+    fn header(&mut self) -> CargoResult<Vec<String>> {
+        try!(self.expect(b'['));
+        let array = !self.eof() && self.peek() == b'[';
+        if array { self.pos += 1 }
+        let mut name = Vec::new();
+        loop {
+            self.skip_ws();
+            name.push(try!(self.key()));
+            self.skip_ws();
+            if self.eof() || self.peek() != b'.' { break }
+            self.pos += 1;
+        }
+        try!(self.expect(b']'));
+        if array { try!(self.expect(b']')) }
+        Ok(name)
+    }
+
+    fn key(&mut self) -> CargoResult<String> {
+        if !self.eof() && self.peek() == b'"' {
+            let start = self.pos + 1;
+            try!(self.string());
+            return Ok(self.text(start, self.pos - 1))
+        }
+        let start = self.pos;
+        while !self.eof() {
+            match self.peek() {
+                b'a'...b'z' | b'A'...b'Z' | b'0'...b'9' | b'_' | b'-' => {
+                    self.pos += 1
+                }
+                _ => break,
+            }
+        }
+        if start == self.pos {
+            return Err(self.error("expected a key"))
+        }
+        Ok(self.text(start, self.pos))
+    }
+
+    fn text(&self, lo: uint, hi: uint) -> String {
+        String::from_utf8_lossy(self.bytes.slice(lo, hi)).into_string()
+    }
+
+    fn value(&mut self) -> CargoResult<()> {
+        if self.eof() { return Err(self.error("expected a value")) }
+        match self.peek() {
+            b'"' | b'\'' => self.string(),
+            b'[' => {
+                self.pos += 1;
+                loop {
+                    self.skip_trivia();
+                    if self.eof() {
+                        return Err(self.error("unterminated array"))
+                    }
+                    if self.peek() == b']' { self.pos += 1; return Ok(()) }
+                    try!(self.value());
+                    self.skip_trivia();
+                    if !self.eof() && self.peek() == b',' { self.pos += 1 }
+                }
+            }
+            b'{' => {
+                self.pos += 1;
+                loop {
+                    self.skip_ws();
+                    if self.eof() {
+                        return Err(self.error("unterminated inline table"))
+                    }
+                    if self.peek() == b'}' { self.pos += 1; return Ok(()) }
+                    try!(self.key());
+                    self.skip_ws();
+                    try!(self.expect(b'='));
+                    self.skip_ws();
+                    try!(self.value());
+                    self.skip_ws();
+                    if !self.eof() && self.peek() == b',' { self.pos += 1 }
+                }
+            }
+            _ => {
+                let start = self.pos;
+                while !self.eof() {
+                    match self.peek() {
+                        b' ' | b'\t' | b'\r' | b'\n' | b'#' | b',' | b']' |
+                        b'}' => break,
+                        _ => self.pos += 1,
+                    }
+                }
+                if start == self.pos {
+                    return Err(self.error("expected a value"))
+                }
+                Ok(())
+            }
+        }
+    }
+
+    /// Consumes a basic or literal string, either of which may be multi-line.
+    fn string(&mut self) -> CargoResult<()> {
+        let quote = self.peek();
+        let multi = self.bytes.slice_from(self.pos).starts_with([quote, quote,
+                                                                 quote]);
+        self.pos += if multi { 3 } else { 1 };
+        loop {
+            if self.eof() || (!multi && self.peek() == b'\n') {
+                return Err(self.error("unterminated string"))
+            }
+            let b = self.peek();
+            if b == b'\\' && quote == b'"' {
+                self.pos += 2;
+            } else if b == quote && !multi {
+                self.pos += 1;
+                return Ok(())
+            } else if b == quote &&
+                      self.bytes.slice_from(self.pos).starts_with([quote, quote,
+                                                                   quote]) {
+                self.pos += 3;
+                return Ok(())
+            } else {
+                self.pos += 1;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::ManifestEditor;
+
+    static MANIFEST: &'static str = "\
+# The best package
+[package]
+name = \"foo\"   # no really
+version = \"0.1.0\"
+authors = [
+    \"a\",   # first
+    \"b\",
+]
+
+[dependencies]
+# serialization
+bar = \"0.2\"
+baz = { version = \"1\", optional = true }
+
+[dependencies.qux]
+git = 'https://example.com/qux'
+
+[features]
+default = [\"bar\"]
+";
+
+    fn edit(f: |&mut ManifestEditor|) -> String {
+        let mut m = ManifestEditor::new(MANIFEST).unwrap();
+        f(&mut m);
+        m.contents().to_string()
+    }
+
+    #[test]
+    fn untouched_manifest_round_trips() {
+        let m = ManifestEditor::new(MANIFEST).unwrap();
+        assert_eq!(m.contents(), MANIFEST);
+    }
+
+    #[test]
+    fn set_version() {
+        let s = edit(|m| m.set_version("0.2.0").unwrap());
+        assert_eq!(s, MANIFEST.replace("version = \"0.1.0\"",
+                                       "version = \"0.2.0\""));
+    }
+
+    #[test]
+    fn set_version_requires_version_key() {
+        let mut m = ManifestEditor::new("[package]\nname = \"foo\"\n").unwrap();
+        assert!(m.set_version("0.2.0").is_err());
+    }
+
+    #[test]
+    fn update_dependency() {
+        let s = edit(|m| {
+            m.set_dependency("dependencies", "bar", "0.3").unwrap()
+        });
+        assert_eq!(s, MANIFEST.replace("bar = \"0.2\"", "bar = \"0.3\""));
+    }
+
+    #[test]
+    fn update_dependency_table() {
+        let s = edit(|m| {
+            m.set_dependency("dependencies", "qux", "1.0").unwrap()
+        });
+        assert_eq!(s, MANIFEST.replace("[dependencies.qux]\n",
+                                       "[dependencies.qux]\n\
+                                        version = \"1.0\"\n"));
+    }
+
+    #[test]
+    fn inline_tables_are_not_edited() {
+        let mut m = ManifestEditor::new(MANIFEST).unwrap();
+        assert!(m.set_dependency("dependencies", "baz", "2").is_err());
+        assert_eq!(m.contents(), MANIFEST);
+    }
+
+    #[test]
+    fn insert_dependency() {
+        let s = edit(|m| m.set_dependency("dependencies", "new", "*").unwrap());
+        assert_eq!(s, MANIFEST.replace("optional = true }\n",
+                                       "optional = true }\nnew = \"*\"\n"));
+    }
+
+    #[test]
+    fn insert_dependency_section() {
+        let s = edit(|m| {
+            m.set_dependency("dev-dependencies", "new", "1.0").unwrap()
+        });
+        assert_eq!(s, format!("{}\n[dev-dependencies]\nnew = \"1.0\"\n",
+                              MANIFEST));
+    }
+
+    #[test]
+    fn insert_without_trailing_newline() {
+        let mut m = ManifestEditor::new("[dependencies]\nbar = \"1\"").unwrap();
+        m.set_dependency("dependencies", "baz", "2").unwrap();
+        assert_eq!(m.contents(), "[dependencies]\nbar = \"1\"\nbaz = \"2\"\n");
+    }
+
+    #[test]
+    fn insert_dependency_with_indentation() {
+        let toml = "[dependencies]\n    bar = \"1\"\n\t\n[dependencies.baz]\n\
+                    \tgit = 'https://example.com/baz'\n";
+        let mut m = ManifestEditor::new(toml).unwrap();
+        m.set_dependency("dependencies", "new", "2").unwrap();
+        m.set_dependency("dependencies", "baz", "3").unwrap();
+        assert_eq!(m.contents(), "[dependencies]\n    bar = \"1\"\n    \
+                                  new = \"2\"\n\t\n[dependencies.baz]\n\
+                                  \tversion = \"3\"\n\
+                                  \tgit = 'https://example.com/baz'\n");
+    }
+
+    #[test]
+    fn remove_dependency() {
+        let s = edit(|m| {
+            assert!(m.remove_dependency("dependencies", "bar").unwrap());
+        });
+        assert_eq!(s, MANIFEST.replace("bar = \"0.2\"\n", ""));
+
+        let s = edit(|m| {
+            assert!(m.remove_dependency("dependencies", "qux").unwrap());
+        });
+        assert_eq!(s, MANIFEST.replace("[dependencies.qux]\n\
+                                        git = 'https://example.com/qux'\n",
+                                       ""));
+
+        let s = edit(|m| {
+            assert!(!m.remove_dependency("dependencies", "nope").unwrap());
+        });
+        assert_eq!(s.as_slice(), MANIFEST);
+    }
+
+    #[test]
+    fn set_feature() {
+        let s = edit(|m| {
+            m.set_feature("default", ["bar".to_string(),
+                                      "baz".to_string()]).unwrap()
+        });
+        assert_eq!(s, MANIFEST.replace("default = [\"bar\"]",
+                                       "default = [\"bar\", \"baz\"]"));
+
+        let s = edit(|m| m.set_feature("extra", []).unwrap());
+        assert_eq!(s, format!("{}extra = []\n", MANIFEST));
+    }
+
+    #[test]
+    fn invalid_manifests_are_rejected() {
+        assert!(ManifestEditor::new("[package\n").is_err());
+        assert!(ManifestEditor::new("name = \"foo\n").is_err());
+        assert!(ManifestEditor::new("a = [1, 2\n").is_err());
+        assert!(ManifestEditor::new("a = 1 b = 2\n").is_err());
+    }
+}
@@ -1,12 +1,18 @@
 pub use self::config::Config;
-pub use self::process_builder::{process, ProcessBuilder};
+pub use self::process_builder::{process, ProcessBuilder, shell_escape};
 pub use self::result::{Wrap, Require};
 pub use self::errors::{CargoResult, CargoError, BoxError, ChainError, CliResult};
 pub use self::errors::{CliError, FromError, ProcessError};
 pub use self::errors::{process_error, internal_error, internal, human, caused_human};
-pub use self::paths::{realpath, join_paths};
+pub use self::errors::{human_of_kind, ErrorKind, BuildFailure, InvalidManifest};
+pub use self::errors::{IoFailure, human_context, error_chain, ErrorLayer};
+pub use self::errors::human_with_cause;
+pub use self::paths::{realpath, join_paths, link_or_copy, remove_all};
+pub use self::paths::{expand_home, is_home_relative};
 pub use self::hex::{to_hex, short_hash};
+pub use self::license::normalize_license;
 pub use self::pool::TaskPool;
+pub use self::progress::Progress;
 pub use self::dependency_queue::{DependencyQueue, Fresh, Dirty, Freshness};
 pub use self::dependency_queue::Dependency;
 pub use self::graph::Graph;
@@ -15,11 +21,15 @@ pub use self::to_semver::ToSemver;
 pub use self::vcs::{GitRepo, HgRepo};
 pub use self::sha256::Sha256;
 
+pub mod cfg;
 pub mod config;
 pub mod errors;
 pub mod graph;
 pub mod hex;
 pub mod important_paths;
+pub mod interrupt;
+pub mod license;
+pub mod manifest_edit;
 pub mod paths;
 pub mod process_builder;
 pub mod profile;
@@ -29,5 +39,6 @@ pub mod to_url;
 pub mod toml;
 mod dependency_queue;
 mod pool;
+mod progress;
 mod sha256;
 mod vcs;
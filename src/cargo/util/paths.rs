@@ -1,8 +1,9 @@
 use std::{io,os};
 use std::io::fs;
+use std::io::fs::PathExtensions;
 use std::path::BytesContainer;
 
-use util::{human, CargoResult};
+use util::{human, CargoResult, ChainError};
 
 pub fn realpath(original: &Path) -> io::IoResult<Path> {
     static MAX_LINKS_FOLLOWED: uint = 256;
@@ -48,3 +49,152 @@ pub fn join_paths<T: BytesContainer>(paths: &[T], env: &str)
                        Does ${} have an unterminated quote character?", e, env))
     })
 }
+
+/// Returns whether `path` starts with a `~` referring to the home directory,
+/// either as `~` on its own or as a leading `~/` component.
+pub fn is_home_relative(path: &str) -> bool {
+    path == "~" || path.starts_with("~/")
+}
+
+/// Expands a leading `~` or `~/` in `path` to `home`. Returns `None` if the
+/// path does not refer to the home directory, in which case the caller
+/// should interpret it as it would have otherwise.
+pub fn expand_home(path: &str, home: &Path) -> Option<Path> {
+    if path == "~" {
+        Some(home.clone())
+    } else if path.starts_with("~/") {
+        Some(home.join(path.slice_from(2)))
+    } else {
+        None
+    }
+}
+
+/// Hard links `src` to `dst`, atomically replacing `dst` if it exists. If a
+/// hard link can't be created (e.g. on filesystems which don't support them)
+/// the file is copied instead.
+pub fn link_or_copy(src: &Path, dst: &Path) -> CargoResult<()> {
+    return replace(src, dst).chain_error(|| {
+        human(format!("failed to link or copy `{}` to `{}`",
+                      src.display(), dst.display()))
+    });
+
+    fn replace(src: &Path, dst: &Path) -> io::IoResult<()> {
+        let tmp = dst.with_filename(format!("{}.tmp",
+                                            dst.filename_str().unwrap()));
+        if tmp.exists() {
+            try!(fs::unlink(&tmp));
+        }
+        try!(fs::link(src, &tmp).or_else(|_| fs::copy(src, &tmp)));
+        try!(move_aside(dst));
+        fs::rename(&tmp, dst)
+    }
+}
+
+/// Removes `path` and, if it is a directory, everything beneath it. Symbolic
+/// links are removed rather than followed, so nothing outside of `path` is
+/// touched, and read-only files (which Windows refuses to delete) are made
+/// writable before being removed. A nonexistent `path` is not an error.
+pub fn remove_all(path: &Path) -> io::IoResult<()> {
+    let stat = match fs::lstat(path) {
+        Ok(stat) => stat,
+        Err(ref e) if e.kind == io::FileNotFound => return Ok(()),
+        Err(e) => return Err(e),
+    };
+    if stat.kind == io::TypeDirectory {
+        for child in try!(fs::readdir(path)).iter() {
+            try!(remove_all(child));
+        }
+        try!(make_writable(path, &stat));
+        fs::rmdir(path)
+    } else {
+        if stat.kind != io::TypeSymlink {
+            try!(make_writable(path, &stat));
+        }
+        fs::unlink(path)
+    }
+}
+
+fn make_writable(path: &Path, stat: &io::FileStat) -> io::IoResult<()> {
+    if stat.perm.contains(io::USER_WRITE) { return Ok(()) }
+    fs::chmod(path, stat.perm | io::USER_WRITE)
+}
+
+// Windows refuses to replace an executable which is currently running, but it
+// can still be renamed, so move it out of the way first.
+#[cfg(windows)]
+fn move_aside(dst: &Path) -> io::IoResult<()> {
+    if !dst.exists() { return Ok(()) }
+    let old = dst.with_filename(format!("{}.old", dst.filename_str().unwrap()));
+    let _ = fs::unlink(&old);
+    fs::rename(dst, &old)
+}
+
+#[cfg(not(windows))]
+fn move_aside(_dst: &Path) -> io::IoResult<()> { Ok(()) }
+
+#[cfg(test)]
+mod tests {
+    use std::io::{fs, File, USER_READ, USER_EXEC, USER_RWX};
+    use std::io::fs::PathExtensions;
+    use std::io::TempDir;
+
+    use super::{expand_home, is_home_relative, remove_all};
+
+    #[test]
+    fn expand_home_expands_leading_tilde() {
+        let home = Path::new("/home/foo");
+        assert_eq!(expand_home("~", &home), Some(Path::new("/home/foo")));
+        assert_eq!(expand_home("~/", &home), Some(Path::new("/home/foo")));
+        assert_eq!(expand_home("~/src/bar", &home),
+                   Some(Path::new("/home/foo/src/bar")));
+    }
+
+    #[test]
+    fn expand_home_leaves_other_paths_alone() {
+        let home = Path::new("/home/foo");
+        assert_eq!(expand_home("src/bar", &home), None);
+        assert_eq!(expand_home("/src/~/bar", &home), None);
+        assert_eq!(expand_home("~bar/src", &home), None);
+        assert_eq!(expand_home("$HOME/src", &home), None);
+    }
+
+    #[test]
+    fn home_relative_paths() {
+        assert!(is_home_relative("~"));
+        assert!(is_home_relative("~/src/foo"));
+        assert!(!is_home_relative("~foo"));
+        assert!(!is_home_relative("src/~/foo"));
+        assert!(!is_home_relative(""));
+    }
+
+    #[test]
+    fn remove_all_removes_read_only_files() {
+        let td = TempDir::new("cargo-remove-all").unwrap();
+        let dir = td.path().join("target");
+        fs::mkdir_recursive(&dir.join("debug"), USER_RWX).unwrap();
+        let file = dir.join("debug").join("foo");
+        File::create(&file).write_str("foo").unwrap();
+        fs::chmod(&file, USER_READ).unwrap();
+        fs::chmod(&dir.join("debug"), USER_READ | USER_EXEC).unwrap();
+
+        remove_all(&dir).unwrap();
+        assert!(!dir.exists());
+        remove_all(&dir).unwrap();
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn remove_all_does_not_follow_symlinks() {
+        let td = TempDir::new("cargo-remove-all").unwrap();
+        let outside = td.path().join("outside");
+        fs::mkdir(&outside, USER_RWX).unwrap();
+        File::create(&outside.join("keep")).write_str("keep").unwrap();
+        let dir = td.path().join("target");
+        fs::mkdir(&dir, USER_RWX).unwrap();
+        fs::symlink(&outside, &dir.join("link")).unwrap();
+
+        remove_all(&dir).unwrap();
+        assert!(!dir.exists());
+        assert!(outside.join("keep").exists());
+    }
+}
@@ -1,10 +1,12 @@
 use std::fmt::{mod, Show, Formatter};
 use std::os;
 use std::c_str::CString;
-use std::io::process::{Command, ProcessOutput, InheritFd};
+use std::io::{BufferedReader, IoResult};
+use std::io::process::{Command, Process, ProcessOutput, InheritFd};
+use std::task;
 use std::collections::HashMap;
 
-use util::{ProcessError, process_error};
+use util::{ProcessError, interrupt, process_error};
 
 #[deriving(Clone,PartialEq)]
 pub struct ProcessBuilder {
@@ -16,13 +18,7 @@ pub struct ProcessBuilder {
 
 impl Show for ProcessBuilder {
     fn fmt(&self, f: &mut Formatter) -> fmt::Result {
-        try!(write!(f, "`{}", String::from_utf8_lossy(self.program.as_bytes_no_nul())));
-
-        for arg in self.args.iter() {
-            try!(write!(f, " {}", String::from_utf8_lossy(arg.as_bytes_no_nul())));
-        }
-
-        write!(f, "`")
+        write!(f, "`{}`", self.command_line())
     }
 }
 
@@ -58,7 +54,7 @@ impl ProcessBuilder {
                .stderr(InheritFd(2))
                .stdin(InheritFd(0));
 
-        let exit = try!(command.status().map_err(|e| {
+        let exit = try!(spawn(&mut command, |mut p| p.wait()).map_err(|e| {
             process_error(format!("Could not execute process `{}`",
                                   self.debug_string()),
                           Some(e), None, None)
@@ -74,9 +70,44 @@ impl ProcessBuilder {
     }
 
     pub fn exec_with_output(&self) -> Result<ProcessOutput, ProcessError> {
-        let command = self.build_command();
+        let mut command = self.build_command();
+        let output = spawn(&mut command, |p| p.wait_with_output());
+        self.check_output(output)
+    }
 
-        let output = try!(command.output().map_err(|e| {
+    /// Like `exec_with_output`, but also hands each line the process prints
+    /// to stdout to `on_stdout_line` as soon as it is printed.
+    pub fn exec_with_streaming(&self, on_stdout_line: |&str|)
+                               -> Result<ProcessOutput, ProcessError> {
+        let mut command = self.build_command();
+        let output = spawn(&mut command, |mut p| {
+            drop(p.stdin.take());
+
+            // Drain stderr on the side so the process can't block on it while
+            // stdout is being read.
+            let mut stderr = p.stderr.take().unwrap();
+            let (tx, rx) = channel();
+            task::spawn(proc() { tx.send(stderr.read_to_end()) });
+
+            let mut out = Vec::new();
+            {
+                let mut stdout = BufferedReader::new(p.stdout.take().unwrap());
+                for line in stdout.lines() {
+                    let line = try!(line);
+                    on_stdout_line(line.as_slice().trim_right_chars('\n'));
+                    out.push_all(line.as_bytes());
+                }
+            }
+            let err = try!(rx.recv());
+            let status = try!(p.wait());
+            Ok(ProcessOutput { status: status, output: out, error: err })
+        });
+        self.check_output(output)
+    }
+
+    fn check_output(&self, output: IoResult<ProcessOutput>)
+                    -> Result<ProcessOutput, ProcessError> {
+        let output = try!(output.map_err(|e| {
             process_error(format!("Could not execute process `{}`",
                                   self.debug_string()),
                           Some(e), None, None)
@@ -107,6 +138,52 @@ impl ProcessBuilder {
         command
     }
 
+    /// Renders this process as a line which can be pasted into a shell to run
+    /// it again, including the working directory and any changes made to the
+    /// environment, e.g. `cd dir && FOO=bar rustc src/lib.rs`.
+    pub fn to_shell_string(&self) -> String {
+        let mut ret = format!("cd {} && ",
+                              shell_escape(self.cwd.display().to_string()
+                                                   .as_slice()));
+
+        let mut env = self.env.iter().collect::<Vec<_>>();
+        env.sort_by(|&(a, _), &(b, _)| a.cmp(b));
+
+        let removed = env.iter().filter(|&&(_, v)| v.is_none())
+                         .map(|&(k, _)| k.as_slice()).collect::<Vec<_>>();
+        if removed.len() > 0 {
+            ret.push_str("env");
+            for k in removed.iter() {
+                ret.push_str(format!(" -u {}", k).as_slice());
+            }
+            ret.push(' ');
+        }
+        for &(k, v) in env.iter() {
+            match *v {
+                Some(ref v) => {
+                    let v = String::from_utf8_lossy(v.as_bytes_no_nul());
+                    ret.push_str(format!("{}={} ", k,
+                                         shell_escape(v.as_slice())).as_slice());
+                }
+                None => {}
+            }
+        }
+
+        ret.push_str(self.command_line().as_slice());
+        ret
+    }
+
+    fn command_line(&self) -> String {
+        let program = String::from_utf8_lossy(self.program.as_bytes_no_nul());
+        let mut ret = shell_escape(program.as_slice());
+        for arg in self.args.iter() {
+            let arg = String::from_utf8_lossy(arg.as_bytes_no_nul());
+            ret.push(' ');
+            ret.push_str(shell_escape(arg.as_slice()).as_slice());
+        }
+        ret
+    }
+
     fn debug_string(&self) -> String {
         let program = String::from_utf8_lossy(self.program.as_bytes_no_nul());
         let mut program = program.into_string();
@@ -119,6 +196,30 @@ impl ProcessBuilder {
     }
 }
 
+/// Spawns `command` and waits for it with `wait`.
+///
+/// While interrupts are forwarded to children (see `util::interrupt`) the
+/// child is registered for the duration of the wait and gets a process group
+/// of its own, so it isn't signalled twice by a Ctrl-C from the terminal. A
+/// child which can't be registered stays in cargo's process group instead.
+fn spawn<T>(command: &mut Command, wait: |Process| -> IoResult<T>)
+            -> IoResult<T> {
+    let mut registration = if interrupt::forwarding() {
+        interrupt::reserve()
+    } else {
+        None
+    };
+    if registration.is_some() {
+        command.detached(true);
+    }
+    let process = try!(command.spawn());
+    match registration {
+        Some(ref mut registration) => registration.register(process.id()),
+        None => {}
+    }
+    wait(process)
+}
+
 pub fn process<T: ToCStr>(cmd: T) -> ProcessBuilder {
     ProcessBuilder {
         program: cmd.to_c_str(),
@@ -127,3 +228,51 @@ pub fn process<T: ToCStr>(cmd: T) -> ProcessBuilder {
         env: HashMap::new(),
     }
 }
+
+/// Quotes `s` so that a POSIX shell will see it as a single word.
+///
+/// Strings which consist only of characters with no special meaning to the
+/// shell are returned unchanged to keep command lines readable.
+pub fn shell_escape(s: &str) -> String {
+    let safe = !s.is_empty() && s.chars().all(|c| {
+        c.is_alphanumeric() || "-_=/.,:+@%".contains_char(c) ||
+            (cfg!(windows) && c == '\\')
+    });
+    if safe {
+        s.to_string()
+    } else {
+        format!("'{}'", s.replace("'", "'\\''"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::shell_escape;
+
+    #[test]
+    fn shell_escape_leaves_plain_words_alone() {
+        assert_eq!(shell_escape("rustc").as_slice(), "rustc");
+        assert_eq!(shell_escape("--crate-name").as_slice(), "--crate-name");
+        assert_eq!(shell_escape("/a/b-c/d_e.rs").as_slice(), "/a/b-c/d_e.rs");
+        assert_eq!(shell_escape("metadata=abc123").as_slice(),
+                   "metadata=abc123");
+    }
+
+    #[test]
+    fn shell_escape_quotes_special_characters() {
+        assert_eq!(shell_escape("").as_slice(), "''");
+        assert_eq!(shell_escape("a b").as_slice(), "'a b'");
+        assert_eq!(shell_escape("/my projects/foo").as_slice(),
+                   "'/my projects/foo'");
+        assert_eq!(shell_escape("feature=\"foo\"").as_slice(),
+                   "'feature=\"foo\"'");
+        assert_eq!(shell_escape("$HOME").as_slice(), "'$HOME'");
+        assert_eq!(shell_escape("a;b").as_slice(), "'a;b'");
+    }
+
+    #[test]
+    fn shell_escape_single_quotes() {
+        assert_eq!(shell_escape("it's").as_slice(), "'it'\\''s'");
+        assert_eq!(shell_escape("'").as_slice(), "''\\'''");
+    }
+}
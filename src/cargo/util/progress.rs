@@ -0,0 +1,74 @@
+use std::cmp;
+use std::io::IoResult;
+
+use core::MultiShell;
+
+/// Maximum width of the progress line, including the name and bar.
+const MAX_WIDTH: uint = 80;
+
+/// Width of the `[=====>    ]` part of the progress line.
+const BAR_WIDTH: uint = 25;
+
+/// A single updating line on stderr showing how far along some work is.
+///
+/// The line is only drawn if stderr is a TTY. It is redrawn in place (via a
+/// carriage return) and must be cleared before printing any other status so
+/// that the regular output is not mangled.
+pub struct Progress {
+    name: String,
+    enabled: bool,
+    drawn: bool,
+}
+
+impl Progress {
+    pub fn new(name: &str, shell: &mut MultiShell) -> Progress {
+        Progress {
+            name: name.to_string(),
+            enabled: shell.err().is_tty(),
+            drawn: false,
+        }
+    }
+
+    /// Redraws the progress line with `cur` out of `max` units done, followed
+    /// by `msg` (truncated if it doesn't fit).
+    pub fn tick(&mut self, shell: &mut MultiShell, cur: uint, max: uint,
+                msg: &str) -> IoResult<()> {
+        if !self.enabled || max == 0 { return Ok(()) }
+
+        let filled = cmp::min(cur, max) * BAR_WIDTH / max;
+        let mut bar = String::new();
+        for i in range(0, BAR_WIDTH) {
+            bar.push(if i < filled {
+                '='
+            } else if i == filled && cur < max {
+                '>'
+            } else {
+                ' '
+            });
+        }
+        let mut line = format!("{:>12} [{}] {}/{}", self.name, bar, cur, max);
+        if msg.len() > 0 {
+            line.push_str(": ");
+            line.push_str(msg);
+        }
+        if line.len() > MAX_WIDTH {
+            line.truncate(MAX_WIDTH - 3);
+            line.push_str("...");
+        }
+
+        try!(self.clear(shell));
+        try!(shell.err().write_str(line.as_slice()));
+        try!(shell.err().flush());
+        self.drawn = true;
+        Ok(())
+    }
+
+    /// Erases the progress line, if one is currently drawn.
+    pub fn clear(&mut self, shell: &mut MultiShell) -> IoResult<()> {
+        if !self.drawn { return Ok(()) }
+        self.drawn = false;
+        let blank = String::from_char(MAX_WIDTH, ' ');
+        try!(shell.err().write_str(format!("\r{}\r", blank).as_slice()));
+        shell.err().flush()
+    }
+}
@@ -18,7 +18,11 @@ impl<'a> ToUrl for &'a Url {
 
 impl<'a> ToUrl for &'a str {
     fn to_url(self) -> Result<Url, String> {
-        UrlParser::new().scheme_type_mapper(mapper).parse(self).map_err(|s| {
+        // git accepts scp-like addresses such as `git@github.com:user/repo`,
+        // which are normalized to the equivalent `ssh://` URL.
+        let ssh = scp_to_ssh(self);
+        let url = ssh.as_ref().map(|s| s.as_slice()).unwrap_or(self);
+        UrlParser::new().scheme_type_mapper(mapper).parse(url).map_err(|s| {
             format!("invalid url `{}`: {}", self, s)
         })
     }
@@ -39,3 +43,64 @@ fn mapper(s: &str) -> url::SchemeType {
         s => url::whatwg_scheme_type_mapper(s),
     }
 }
+
+/// Converts an scp-like address, `user@host:path`, to the URL
+/// `ssh://user@host/path`. Anything else (including strings which already
+/// have a scheme) yields `None`.
+fn scp_to_ssh(s: &str) -> Option<String> {
+    if s.contains("://") { return None }
+    let colon = match s.find(':') {
+        Some(i) => i,
+        None => return None,
+    };
+    let (authority, path) = (s.slice_to(colon), s.slice_from(colon + 1));
+    let at = match authority.find('@') {
+        Some(i) => i,
+        None => return None,
+    };
+    if at == 0 || at == authority.len() - 1 || authority.contains_char('/') ||
+       path.len() == 0 {
+        return None
+    }
+    let path = if path.starts_with("/") {path.slice_from(1)} else {path};
+    Some(format!("ssh://{}/{}", authority, path))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::ToUrl;
+
+    #[test]
+    fn scp_addresses_become_ssh_urls() {
+        let url = "git@github.com:user/repo.git".to_url().unwrap();
+        assert_eq!(url.to_string().as_slice(),
+                   "ssh://git@github.com/user/repo.git");
+        let url = "git@example.com:/srv/repo".to_url().unwrap();
+        assert_eq!(url.to_string().as_slice(),
+                   "ssh://git@example.com/srv/repo");
+    }
+
+    #[test]
+    fn scp_and_ssh_forms_are_equal() {
+        assert_eq!("git@github.com:user/repo.git".to_url(),
+                   "ssh://git@github.com/user/repo.git".to_url());
+    }
+
+    #[test]
+    fn other_urls_are_untouched() {
+        let url = "ssh://git@example.com:2222/repo".to_url().unwrap();
+        assert_eq!(url.to_string().as_slice(),
+                   "ssh://git@example.com:2222/repo");
+        let url = "https://user@example.com/repo".to_url().unwrap();
+        assert_eq!(url.to_string().as_slice(),
+                   "https://user@example.com/repo");
+    }
+
+    #[test]
+    fn invalid_addresses_still_fail() {
+        assert!("github.com/user/repo".to_url().is_err());
+        assert!("@github.com:user/repo".to_url().is_err());
+        assert!("git@:user/repo".to_url().is_err());
+        assert!("git@github.com:".to_url().is_err());
+    }
+}
@@ -1,5 +1,7 @@
 use std::collections::HashMap;
+use std::collections::hash_map::{Occupied, Vacant};
 use std::fmt;
+use std::io::File;
 use std::io::fs::{mod, PathExtensions};
 use std::os;
 use std::slice;
@@ -7,6 +9,7 @@ use std::str;
 use std::default::Default;
 use toml;
 use semver;
+use glob;
 use serialize::{Decodable, Decoder};
 
 use core::SourceId;
@@ -14,7 +17,10 @@ use core::{Summary, Manifest, Target, Dependency, PackageId};
 use core::dependency::{Build, Development};
 use core::manifest::{LibKind, Lib, Dylib, Profile, ManifestMetadata};
 use core::package_id::Metadata;
-use util::{CargoResult, Require, human, ToUrl, ToSemver};
+use util::{CargoResult, Require, ChainError, human, internal, ToUrl, ToSemver};
+use util::{config, is_home_relative, human_of_kind, human_context};
+use util::{CargoError, InvalidManifest, normalize_license};
+use util::cfg::CfgExpr;
 
 /// Representation of the projects file layout.
 ///
@@ -28,6 +34,9 @@ pub struct Layout {
     examples: Vec<Path>,
     tests: Vec<Path>,
     benches: Vec<Path>,
+    /// Problems reading the directories and files targets are inferred from,
+    /// which become warnings of the manifest.
+    warnings: Vec<String>,
 }
 
 impl Layout {
@@ -47,12 +56,33 @@ fn try_add_file(files: &mut Vec<Path>, root: &Path, dir: &str) {
         files.push(p);
     }
 }
-fn try_add_files(files: &mut Vec<Path>, root: &Path, dir: &str) {
-    match fs::readdir(&root.join(dir)) {
-        Ok(new) => {
-            files.extend(new.into_iter().filter(|f| f.extension_str() == Some("rs")))
+
+/// Adds the `.rs` files in `dir`. A directory which doesn't exist adds
+/// nothing, but one which can't be read, or a file in it which can't be
+/// stat'd, is reported in `warnings` rather than silently skipped.
+fn try_add_files(files: &mut Vec<Path>, warnings: &mut Vec<String>,
+                 root: &Path, dir: &str) {
+    let path = root.join(dir);
+    let new = match fs::readdir(&path) {
+        Ok(new) => new,
+        Err(..) if !path.exists() => return,
+        Err(e) => {
+            warnings.push(format!("failed to read the directory `{}`, no \
+                                   targets were inferred from it: {}", dir, e));
+            return
+        }
+    };
+    for file in new.into_iter() {
+        if file.extension_str() != Some("rs") { continue }
+        match fs::stat(&file) {
+            Ok(..) => files.push(file),
+            Err(e) => {
+                let relative = file.path_relative_from(root).unwrap_or(file.clone());
+                warnings.push(format!("failed to read `{}`, no target was \
+                                       inferred from it: {}",
+                                      relative.display(), e));
+            }
         }
-        Err(_) => {/* just don't add anything if the directory doesn't exist, etc. */}
     }
 }
 
@@ -65,18 +95,19 @@ pub fn project_layout(root_path: &Path) -> Layout {
     let mut examples = vec!();
     let mut tests = vec!();
     let mut benches = vec!();
+    let mut warnings = vec!();
 
     if root_path.join("src/lib.rs").exists() {
         lib = Some(root_path.join("src/lib.rs"));
     }
 
     try_add_file(&mut bins, root_path, "src/main.rs");
-    try_add_files(&mut bins, root_path, "src/bin");
+    try_add_files(&mut bins, &mut warnings, root_path, "src/bin");
 
-    try_add_files(&mut examples, root_path, "examples");
+    try_add_files(&mut examples, &mut warnings, root_path, "examples");
 
-    try_add_files(&mut tests, root_path, "tests");
-    try_add_files(&mut benches, root_path, "benches");
+    try_add_files(&mut tests, &mut warnings, root_path, "tests");
+    try_add_files(&mut benches, &mut warnings, root_path, "benches");
 
     Layout {
         root: root_path.clone(),
@@ -85,9 +116,16 @@ pub fn project_layout(root_path: &Path) -> Layout {
         examples: examples,
         tests: tests,
         benches: benches,
+        warnings: warnings,
     }
 }
 
+/// Reads the manifest of the package rooted at `layout.root`.
+///
+/// Along with the manifest, the directories of the package's `path`
+/// dependencies are returned, or those of the members of a virtual workspace
+/// root. They're resolved against the directory of the manifest, sorted, and
+/// listed once each.
 pub fn to_manifest(contents: &[u8],
                    source_id: &SourceId,
                    layout: Layout)
@@ -97,52 +135,847 @@ pub fn to_manifest(contents: &[u8],
         Some(path) => path,
         None => manifest,
     };
+    to_named_manifest(contents, &manifest, source_id, layout)
+}
+
+/// Like `to_manifest`, but errors refer to the contents as `manifest`, which
+/// need not exist, e.g. `<stdin>` for contents which aren't in a file.
+pub fn to_named_manifest(contents: &[u8],
+                         manifest: &Path,
+                         source_id: &SourceId,
+                         layout: Layout)
+                         -> CargoResult<(Manifest, Vec<Path>)> {
+    let mut config = ManifestConfig::new(&layout.root);
+    if contents.len() > MAX_MANIFEST_SIZE {
+        let max_size = try!(config.limit("max-size", MAX_MANIFEST_SIZE));
+        if contents.len() > max_size {
+            return Err(human_of_kind(InvalidManifest,
+                                     format!("{} is {} bytes, which is larger \
+                                              than the limit of {} bytes\nthe \
+                                              limit can be raised with the \
+                                              `manifest.max-size` \
+                                              configuration key",
+                                             manifest.display(),
+                                             contents.len(), max_size)))
+        }
+    }
     let contents = try!(str::from_utf8(contents).require(|| {
-        human(format!("{} is not valid UTF-8", manifest.display()))
+        human_of_kind(InvalidManifest,
+                      format!("{} is not valid UTF-8", manifest.display()))
+    }));
+
+    // The TOML parser and decoder recurse into nested arrays and tables, so
+    // pathologically nested input is rejected up front.
+    let depth = nesting_depth(contents);
+    let mut max_depth = MAX_MANIFEST_DEPTH;
+    if depth > max_depth {
+        max_depth = try!(config.limit("max-depth", MAX_MANIFEST_DEPTH));
+        if depth > max_depth {
+            return Err(human_of_kind(InvalidManifest,
+                                     format!("{} nests arrays or tables {} \
+                                              levels deep, which is more than \
+                                              the limit of {}\nthe limit can \
+                                              be raised with the \
+                                              `manifest.max-depth` \
+                                              configuration key",
+                                             manifest.display(), depth,
+                                             max_depth)))
+        }
+    }
+    let root = try!(parse(contents, manifest));
+
+    // Values of the wrong type are all reported at once, before the decoder
+    // gives up on the first one with a less helpful message.
+    try!(check_types(&root).map_err(|e| {
+        invalid_manifest(manifest, human(e))
     }));
-    let root = try!(parse(contents, &manifest));
     let mut d = toml::Decoder::new(toml::Table(root));
     let toml_manifest: TomlManifest = match Decodable::decode(&mut d) {
         Ok(t) => t,
-        Err(e) => return Err(human(format!("{} is not a valid \
-                                            manifest\n\n{}",
-                                           manifest.display(), e)))
+        Err(e) => return Err(invalid_manifest(manifest, human(e))),
     };
 
     let pair = try!(toml_manifest.to_manifest(source_id, &layout).map_err(|err| {
-        human(format!("{} is not a valid manifest\n\n{}",
-                      manifest.display(), err))
+        invalid_manifest(manifest, err)
     }));
+    let path = manifest;
     let (mut manifest, paths) = pair;
     match d.toml {
-        Some(ref toml) => add_unused_keys(&mut manifest, toml, "".to_string()),
+        Some(ref toml) => {
+            try!(add_unused_keys(&mut manifest, toml, max_depth,
+                                 &mut config).map_err(|e| {
+                invalid_manifest(path, human(e))
+            }))
+        }
         None => {}
     }
-    if manifest.get_targets().len() == 0 {
-        return Err(human(format!("either a [lib] or [[bin]] section must \
-                                  be present")))
+    if manifest.get_targets().len() == 0 &&
+       !manifest.get_summary().is_workspace_root() {
+        return Err(human_of_kind(InvalidManifest,
+                                 format!("either a [lib] or [[bin]] section \
+                                          must be present")))
     }
     return Ok((manifest, paths));
 
-    fn add_unused_keys(m: &mut Manifest, toml: &toml::Value, key: String) {
-        match *toml {
+    // Walks the leftover keys with an explicit stack rather than recursion,
+    // and doesn't descend past `max_depth` levels. Commonly misused keys are
+    // explained once each, and are an error if the key can never work. Keys
+    // below one of `UNUSED_KEY_GROUPS` are reported per table, if at all,
+    // unless they look like a typo of a known key.
+    fn add_unused_keys(m: &mut Manifest, toml: &toml::Value, max_depth: uint,
+                       config: &mut ManifestConfig) -> Result<(), String> {
+        let mut explained = Vec::new();
+        let mut grouped = Vec::new();
+        let mut stack = vec![(toml, "".to_string(), 0u)];
+        loop {
+            let (toml, key, depth) = match stack.pop() {
+                Some(next) => next,
+                None => break,
+            };
+            if depth > max_depth { continue }
+            match *toml {
+                toml::Table(ref table) => {
+                    for (k, v) in table.iter() {
+                        let key = if key.len() == 0 {
+                            k.clone()
+                        } else {
+                            format!("{}.{}", key, k)
+                        };
+                        stack.push((v, key, depth + 1));
+                    }
+                }
+                toml::Array(ref arr) => {
+                    for v in arr.iter() {
+                        stack.push((v, key.clone(), depth + 1));
+                    }
+                }
+                _ => {
+                    match unused_key_group(key.as_slice()) {
+                        Some((group, table, rest))
+                                if suggest_key(key.as_slice()).is_none() => {
+                            if group.message.is_some() {
+                                grouped.push((table, group, rest));
+                            }
+                            continue
+                        }
+                        _ => {}
+                    }
+                    match misused_key(key.as_slice()) {
+                        Some(misused) if misused.error => {
+                            return Err(format!("unsupported manifest key: \
+                                                {}\n{}", key, misused.message))
+                        }
+                        Some(misused) => {
+                            if explained.contains(&key) { continue }
+                            m.add_warning(format!("unused manifest key: {}\n{}",
+                                                  key, misused.message));
+                            explained.push(key);
+                        }
+                        None => match suggest_key(key.as_slice()) {
+                            Some(known) => {
+                                m.add_warning(format!("unused manifest key: \
+                                                       {}\ndid you mean `{}`?",
+                                                      key, known))
+                            }
+                            None => {
+                                m.add_warning(format!("unused manifest key: {}",
+                                                      key))
+                            }
+                        },
+                    }
+                }
+            }
+        }
+
+        grouped.sort_by(|&(ref a, _, ref x), &(ref b, _, ref y)| {
+            (a, x).cmp(&(b, y))
+        });
+        let mut i = 0;
+        while i < grouped.len() {
+            let (ref table, group, _) = grouped[i];
+            let keys = grouped.slice_from(i).iter().take_while(|&&(ref t, _, _)| {
+                t == table
+            }).map(|&(_, _, ref key)| key.as_slice()).collect::<Vec<&str>>();
+            i += keys.len();
+
+            let enabled = match group.switch {
+                Some(switch) => try!(config.switch(switch, true).map_err(|e| {
+                    e.to_string()
+                })),
+                None => true,
+            };
+            if !enabled { continue }
+            let mut warning = format!("unused manifest keys in [{}]: {}\n{}",
+                                      table, keys.connect(", "),
+                                      group.message.unwrap());
+            match group.switch {
+                Some(switch) => {
+                    warning.push_str(format!(", set `manifest.{} = false` \
+                                              in .cargo/config to silence \
+                                              this warning",
+                                             switch).as_slice());
+                }
+                None => {}
+            }
+            m.add_warning(warning);
+        }
+        Ok(())
+    }
+}
+
+/// Keys of the `[package]` section which are carried over into a normalized
+/// manifest. `exclude` only matters when creating the package, so it's
+/// dropped along with any keys cargo doesn't know about.
+static NORMALIZED_PACKAGE_KEYS: &'static [&'static str] = &[
+    "name", "version", "authors", "build", "links", "build-per-profile",
+    "warn-unused-optional", "description", "homepage", "documentation",
+    "readme", "keywords", "license", "license-file", "repository",
+    "metadata",
+];
+
+/// Renders `contents`, the manifest of the package at `layout.root` which was
+/// loaded as `manifest`, in the self-contained form shipped in the package's
+/// tarball.
+///
+/// Every target is listed explicitly along with its path, and the dependencies
+/// of `manifest` are written in registry form: with the version they require
+/// but without a `path` or `git` key, so every one of them needs a version.
+/// Profiles, features and the package metadata are carried over as written.
+pub fn to_normalized_manifest(contents: &[u8], manifest: &Manifest,
+                              layout: &Layout) -> CargoResult<String> {
+    let path = layout.root.join("Cargo.toml");
+    let contents = try!(str::from_utf8(contents).require(|| {
+        human(format!("{} is not valid UTF-8", path.display()))
+    }));
+    let root = try!(parse(contents, &path));
+    let mut d = toml::Decoder::new(toml::Table(root.clone()));
+    let toml_manifest: TomlManifest = match Decodable::decode(&mut d) {
+        Ok(t) => t,
+        Err(e) => return Err(invalid_manifest(&path, human(e))),
+    };
+    let project = try!(toml_manifest.project.as_ref().or_else(|| {
+        toml_manifest.package.as_ref()
+    }).require(|| {
+        human("a virtual manifest has no package to normalize")
+    }));
+    let (targets, _) = try!(toml_manifest.targets(&**project, layout));
+
+    let mut out = HashMap::new();
+
+    let raw_project = root.get(&"project".to_string()).or_else(|| {
+        root.get(&"package".to_string())
+    }).and_then(|p| p.as_table()).unwrap();
+    let mut package = HashMap::new();
+    for (key, value) in raw_project.iter() {
+        let key = key.replace("_", "-");
+        if NORMALIZED_PACKAGE_KEYS.contains(&key.as_slice()) {
+            package.insert(key, value.clone());
+        }
+    }
+    package.insert("version".to_string(),
+                   toml::String(manifest.get_version().to_string()));
+    out.insert("package".to_string(), toml::Table(package));
+
+    for t in targets.lib.iter() {
+        out.insert("lib".to_string(), normalized_target(t, layout));
+    }
+    let sections = [("bin", &targets.bins), ("example", &targets.examples),
+                    ("test", &targets.tests), ("bench", &targets.benches)];
+    for &(name, list) in sections.iter() {
+        if list.is_empty() { continue }
+        out.insert(name.to_string(), toml::Array(list.iter().map(|t| {
+            normalized_target(t, layout)
+        }).collect()));
+    }
+
+    for key in ["features", "profile"].iter() {
+        match root.get(&key.to_string()) {
+            Some(value) => { out.insert(key.to_string(), value.clone()); }
+            None => {}
+        }
+    }
+
+    // Dependencies by platform, then by section
+    let mut deps: HashMap<Option<String>,
+                          HashMap<String, toml::TomlTable>> = HashMap::new();
+    for dep in manifest.get_dependencies().iter() {
+        let req = try!(dep.get_specified_req().require(|| {
+            human(format!("dependency `{}` does not specify a version, which \
+                           is required for every dependency of a package \
+                           uploaded to the registry", dep.get_name()))
+        }));
+        let mut table = HashMap::new();
+        table.insert("version".to_string(), toml::String(req.to_string()));
+        if dep.get_features().len() > 0 {
+            table.insert("features".to_string(),
+                         toml::Array(dep.get_features().iter().map(|f| {
+                             toml::String(f.clone())
+                         }).collect()));
+        }
+        if dep.is_optional() {
+            table.insert("optional".to_string(), toml::Boolean(true));
+        }
+        if !dep.uses_default_features() {
+            table.insert("default-features".to_string(), toml::Boolean(false));
+        }
+        let section = if dep.is_build() {
+            "build-dependencies"
+        } else if !dep.is_transitive() {
+            "dev-dependencies"
+        } else {
+            "dependencies"
+        };
+        let platform = dep.get_only_for_platform().map(|p| p.to_string());
+        let sections = match deps.entry(platform) {
+            Vacant(entry) => entry.set(HashMap::new()),
+            Occupied(entry) => entry.into_mut(),
+        };
+        let section = match sections.entry(section.to_string()) {
+            Vacant(entry) => entry.set(HashMap::new()),
+            Occupied(entry) => entry.into_mut(),
+        };
+        section.insert(dep.get_name().to_string(), toml::Table(table));
+    }
+    let mut platforms = HashMap::new();
+    for (platform, sections) in deps.into_iter() {
+        let sections = sections.into_iter().map(|(name, deps)| {
+            (name, toml::Table(deps))
+        });
+        match platform {
+            Some(platform) => {
+                platforms.insert(platform, toml::Table(sections.collect()));
+            }
+            None => out.extend(sections),
+        }
+    }
+    if platforms.len() > 0 {
+        out.insert("target".to_string(), toml::Table(platforms));
+    }
+
+    Ok(format!("# This manifest was normalized by cargo when the package was \
+                created.\n# The original is included as `Cargo.toml.orig`.\n\n\
+                {}", toml::Table(out)))
+}
+
+fn normalized_target(target: &TomlTarget, layout: &Layout) -> toml::Value {
+    let mut t = HashMap::new();
+    t.insert("name".to_string(), toml::String(target.name.clone()));
+    match target.path {
+        Some(ref path) => {
+            let path = layout.root.join(path.to_path());
+            let path = path.path_relative_from(&layout.root).unwrap_or(path);
+            t.insert("path".to_string(),
+                     toml::String(path.display().to_string()));
+        }
+        None => {}
+    }
+    match target.crate_type {
+        Some(ref kinds) => {
+            t.insert("crate-type".to_string(),
+                     toml::Array(kinds.iter().map(|k| {
+                         toml::String(k.clone())
+                     }).collect()));
+        }
+        None => {}
+    }
+    let flags = [("test", target.test), ("doctest", target.doctest),
+                 ("bench", target.bench), ("doc", target.doc),
+                 ("plugin", target.plugin), ("harness", target.harness)];
+    for &(key, value) in flags.iter() {
+        match value {
+            Some(b) => { t.insert(key.to_string(), toml::Boolean(b)); }
+            None => {}
+        }
+    }
+    toml::Table(t)
+}
+
+/// A key which is often written in the wrong place or misspelled, and which
+/// gets a tailored explanation instead of the generic unused-key warning.
+struct MisusedKey {
+    /// Tables the key is misused in, `*` standing for the table of any one
+    /// dependency.
+    tables: &'static [&'static str],
+    key: &'static str,
+    /// Whether the key is rejected outright rather than warned about.
+    error: bool,
+    message: &'static str,
+}
+
+static MISUSED_KEYS: &'static [MisusedKey] = &[
+    MisusedKey {
+        tables: &["lib", "bin", "example", "test", "bench"],
+        key: "features",
+        error: true,
+        message: "features are enabled for the whole package and can't be \
+                  set per target; declare them in the top-level [features] \
+                  section\nbuilding a target only when certain features are \
+                  enabled (`required-features`) is not supported yet",
+    },
+    MisusedKey {
+        tables: &["lib", "bin", "example", "test", "bench"],
+        key: "dependencies",
+        error: false,
+        message: "dependencies are shared by all targets of a package and \
+                  belong in the top-level [dependencies] section",
+    },
+    MisusedKey {
+        tables: &["package", "project"],
+        key: "dependencies",
+        error: false,
+        message: "dependencies belong in the top-level [dependencies] \
+                  section, not inside [package]",
+    },
+    MisusedKey {
+        tables: &["package", "project"],
+        key: "features",
+        error: false,
+        message: "features belong in the top-level [features] section, not \
+                  inside [package]",
+    },
+    MisusedKey { tables: &["*"], key: "vers", error: false,
+                 message: "did you mean `version`?" },
+    MisusedKey { tables: &["*"], key: "versions", error: false,
+                 message: "did you mean `version`?" },
+    MisusedKey { tables: &["*"], key: "verison", error: false,
+                 message: "did you mean `version`?" },
+    MisusedKey { tables: &["*"], key: "feature", error: false,
+                 message: "did you mean `features`?" },
+    MisusedKey { tables: &["*"], key: "paths", error: false,
+                 message: "did you mean `path`?" },
+];
+
+/// Keys below which unused keys aren't reported one by one.
+struct UnusedKeyGroup {
+    /// A dotted path from the root of the manifest, `*` standing for any one
+    /// key. The keys below each table it matches are reported together.
+    prefix: &'static str,
+    /// Reported along with the names of the unused keys of a table, which are
+    /// ignored altogether without one.
+    message: Option<&'static str>,
+    /// A boolean key of the `[manifest]` configuration which silences the
+    /// message when false.
+    switch: Option<&'static str>,
+}
+
+static UNUSED_KEY_GROUPS: &'static [UnusedKeyGroup] = &[
+    // Reserved for tools other than cargo to keep their settings in.
+    UnusedKeyGroup { prefix: "package.metadata", message: None, switch: None },
+    UnusedKeyGroup { prefix: "project.metadata", message: None, switch: None },
+    // Newer versions of cargo know more profile keys, and projects shared
+    // with them shouldn't flood older ones with warnings.
+    UnusedKeyGroup {
+        prefix: "profile.*",
+        message: Some("they may be supported by a newer version of cargo"),
+        switch: Some("warn-unknown-profile-keys"),
+    },
+];
+
+/// Finds the entry of `UNUSED_KEY_GROUPS` the unused `key` is below, along
+/// with the table of `key` it matched and the rest of `key`.
+fn unused_key_group(key: &str)
+                    -> Option<(&'static UnusedKeyGroup, String, String)> {
+    let parts = key.split('.').collect::<Vec<&str>>();
+    UNUSED_KEY_GROUPS.iter().filter_map(|group| {
+        let prefix = group.prefix.split('.').collect::<Vec<&str>>();
+        if parts.len() < prefix.len() { return None }
+        let matches = prefix.iter().zip(parts.iter()).all(|(p, part)| {
+            *p == "*" || p == part
+        });
+        if !matches { return None }
+        let (table, rest) = (parts.slice_to(prefix.len()),
+                             parts.slice_from(prefix.len()));
+        Some((group, table.connect("."), rest.connect(".")))
+    }).next()
+}
+
+/// Finds the entry of `MISUSED_KEYS` describing the unused `key`, a dotted
+/// path from the root of the manifest.
+fn misused_key(key: &str) -> Option<&'static MisusedKey> {
+    let parts = key.split('.').collect::<Vec<&str>>();
+    if parts.len() < 2 { return None }
+
+    // A key directly inside one of the top-level tables.
+    let (table, name) = (parts[0], parts[1]);
+    let found = MISUSED_KEYS.iter().find(|m| {
+        m.key == name && m.tables.contains(&table)
+    });
+    if found.is_some() { return found }
+
+    // A key inside the table of a single dependency, which may itself be
+    // nested in a `[target.<triple>]` section.
+    if parts.len() < 3 { return None }
+    let section = parts[parts.len() - 3];
+    if !section.ends_with("dependencies") { return None }
+    let name = parts[parts.len() - 1];
+    MISUSED_KEYS.iter().find(|m| m.key == name && m.tables.contains(&"*"))
+}
+
+/// The type of TOML value a manifest key may have.
+#[deriving(PartialEq)]
+enum ValueKind {
+    StringValue,
+    BooleanValue,
+    IntegerValue,
+    TableValue,
+    StringArray,
+    TableArray,
+}
+
+/// The keys of a manifest whose values are checked before decoding, with the
+/// types each may have.
+///
+/// Keys are dotted paths written with hyphens, which also match keys spelled
+/// with underscores. `*` stands for any one key, `<deps>` for any of the
+/// dependency tables and `<target>` for any of the target tables. The
+/// `[project]` table is checked like `[package]`, and the tables inside
+/// `[target.<triple>]` like the top-level ones.
+static MANIFEST_SCHEMA: &'static [(&'static str, &'static [ValueKind])] = &[
+    ("package", &[TableValue]),
+    ("package.name", &[StringValue]),
+    ("package.version", &[StringValue]),
+    ("package.authors", &[StringArray]),
+    ("package.build", &[StringValue, StringArray]),
+    ("package.links", &[StringValue]),
+    ("package.build-per-profile", &[BooleanValue]),
+    ("package.warn-unused-optional", &[BooleanValue]),
+    ("package.exclude", &[StringArray]),
+    ("package.test-dirs", &[StringArray]),
+    ("package.bench-dirs", &[StringArray]),
+    ("package.autobenches", &[BooleanValue]),
+    ("package.description", &[StringValue]),
+    ("package.homepage", &[StringValue]),
+    ("package.documentation", &[StringValue]),
+    ("package.readme", &[StringValue]),
+    ("package.keywords", &[StringArray]),
+    ("package.license", &[StringValue]),
+    ("package.license-file", &[StringValue]),
+    ("package.repository", &[StringValue]),
+
+    ("lib", &[TableValue, TableArray]),
+    ("bin", &[TableArray]),
+    ("example", &[TableArray]),
+    ("test", &[TableArray]),
+    ("bench", &[TableArray]),
+    ("<target>.name", &[StringValue]),
+    ("<target>.crate-type", &[StringArray]),
+    ("<target>.path", &[StringValue]),
+    ("<target>.test", &[BooleanValue]),
+    ("<target>.doctest", &[BooleanValue]),
+    ("<target>.bench", &[BooleanValue]),
+    ("<target>.doc", &[BooleanValue]),
+    ("<target>.plugin", &[BooleanValue]),
+    ("<target>.harness", &[BooleanValue]),
+
+    ("<deps>", &[TableValue]),
+    ("<deps>.*", &[StringValue, StringArray, TableValue]),
+    ("<deps>.*.version", &[StringValue, StringArray]),
+    ("<deps>.*.path", &[StringValue]),
+    ("<deps>.*.git", &[StringValue]),
+    ("<deps>.*.branch", &[StringValue]),
+    ("<deps>.*.tag", &[StringValue]),
+    ("<deps>.*.rev", &[StringValue]),
+    ("<deps>.*.features", &[StringArray]),
+    ("<deps>.*.optional", &[BooleanValue]),
+    ("<deps>.*.default-features", &[BooleanValue]),
+    ("<deps>.*.workspace", &[BooleanValue]),
+
+    ("features", &[TableValue]),
+    ("features.*", &[StringArray]),
+
+    ("profile", &[TableValue]),
+    ("profile.*", &[TableValue]),
+    ("profile.*.opt-level", &[IntegerValue]),
+    ("profile.*.codegen-units", &[IntegerValue]),
+    ("profile.*.debug", &[BooleanValue, IntegerValue]),
+    ("profile.*.rpath", &[BooleanValue]),
+
+    ("target", &[TableValue]),
+    ("target.*", &[TableValue]),
+
+    ("workspace", &[TableValue]),
+    ("workspace.members", &[StringArray]),
+    ("workspace.dependencies", &[TableValue]),
+    ("workspace.dependencies.*", &[StringValue, StringArray, TableValue]),
+];
+
+/// Splits a dotted manifest key into the segments it's matched against
+/// `MANIFEST_SCHEMA` with.
+fn schema_segments(key: &str) -> Vec<String> {
+    let mut parts = key.split('.').map(|part| {
+        part.replace("_", "-")
+    }).collect::<Vec<String>>();
+    if parts.len() > 2 && parts[0].as_slice() == "target" {
+        parts = parts.slice_from(2).to_vec();
+    }
+    if parts.len() > 0 && parts[0].as_slice() == "project" {
+        parts[0] = "package".to_string();
+    }
+    parts
+}
+
+fn schema_segment_matches(pattern: &str, part: &str) -> bool {
+    match pattern {
+        "*" => true,
+        "<deps>" => part == "dependencies" || part == "dev-dependencies" ||
+                    part == "build-dependencies",
+        "<target>" => match part {
+            "lib" | "bin" | "example" | "test" | "bench" => true,
+            _ => false,
+        },
+        pattern => pattern == part,
+    }
+}
+
+/// Returns the types the value of `key` may have, if it's a known key.
+fn expected_kinds(key: &str) -> Option<&'static [ValueKind]> {
+    let parts = schema_segments(key);
+    MANIFEST_SCHEMA.iter().find(|&&(pattern, _)| {
+        let pattern = pattern.split('.').collect::<Vec<&str>>();
+        pattern.len() == parts.len() &&
+            pattern.iter().zip(parts.iter()).all(|(p, part)| {
+                schema_segment_matches(*p, part.as_slice())
+            })
+    }).map(|&(_, kinds)| kinds)
+}
+
+/// Suggests a known key for the unused `key` when it looks like a typo of
+/// one in the same table.
+fn suggest_key(key: &str) -> Option<String> {
+    let parts = schema_segments(key);
+    let (name, table) = match parts.last() {
+        Some(name) => (name, parts.init()),
+        None => return None,
+    };
+    MANIFEST_SCHEMA.iter().filter_map(|&(pattern, _)| {
+        let pattern = pattern.split('.').collect::<Vec<&str>>();
+        let (known, known_table) = (*pattern.last().unwrap(), pattern.init());
+        if known_table.len() != table.len() || known.starts_with("<") ||
+           known == "*" {
+            return None
+        }
+        let same_table = known_table.iter().zip(table.iter()).all(|(p, part)| {
+            schema_segment_matches(*p, part.as_slice())
+        });
+        let distance = known.lev_distance(name.as_slice());
+        if same_table && distance > 0 && distance <= 2 {
+            Some((distance, known.to_string()))
+        } else {
+            None
+        }
+    }).min_by(|&(distance, _)| distance).map(|(_, known)| known)
+}
+
+fn value_matches(kind: &ValueKind, value: &toml::Value) -> bool {
+    match (kind, value) {
+        (&StringValue, &toml::String(..)) => true,
+        (&BooleanValue, &toml::Boolean(..)) => true,
+        (&IntegerValue, &toml::Integer(..)) => true,
+        (&TableValue, &toml::Table(..)) => true,
+        (&StringArray, &toml::Array(ref a)) => a.iter().all(|v| {
+            value_matches(&StringValue, v)
+        }),
+        (&TableArray, &toml::Array(ref a)) => a.iter().all(|v| {
+            value_matches(&TableValue, v)
+        }),
+        _ => false,
+    }
+}
+
+fn describe_kind(kind: &ValueKind) -> &'static str {
+    match *kind {
+        StringValue => "a string",
+        BooleanValue => "a boolean",
+        IntegerValue => "an integer",
+        TableValue => "a table",
+        StringArray => "an array of strings",
+        TableArray => "an array of tables",
+    }
+}
+
+fn describe_value(value: &toml::Value) -> String {
+    let name = |value: &toml::Value| match *value {
+        toml::String(..) => "string",
+        toml::Integer(..) => "integer",
+        toml::Float(..) => "float",
+        toml::Boolean(..) => "boolean",
+        toml::Datetime(..) => "datetime",
+        toml::Array(..) => "array",
+        toml::Table(..) => "table",
+    };
+    match *value {
+        toml::Array(ref a) => match a.head() {
+            None => "an empty array".to_string(),
+            Some(v) => format!("an array of {}s", name(v)),
+        },
+        toml::Integer(..) => "an integer".to_string(),
+        _ => format!("a {}", name(value)),
+    }
+}
+
+/// Checks that every known key of the manifest `root` has a value of a type
+/// it may have, e.g. that `package.authors` is an array of strings. All of
+/// the mismatches are reported, one per line.
+fn check_types(root: &toml::TomlTable) -> Result<(), String> {
+    let mut errors = Vec::new();
+    let mut stack = root.iter().map(|(k, v)| {
+        (v, k.clone())
+    }).collect::<Vec<(&toml::Value, String)>>();
+    loop {
+        let (value, key) = match stack.pop() {
+            Some(next) => next,
+            None => break,
+        };
+        match expected_kinds(key.as_slice()) {
+            Some(kinds) if !kinds.iter().any(|k| value_matches(k, value)) => {
+                let expected = kinds.iter().map(describe_kind)
+                                    .collect::<Vec<&str>>();
+                errors.push(format!("{}: expected {}, found {}", key,
+                                    expected.connect(" or "),
+                                    describe_value(value)));
+                continue
+            }
+            _ => {}
+        }
+        match *value {
             toml::Table(ref table) => {
                 for (k, v) in table.iter() {
-                    add_unused_keys(m, v, if key.len() == 0 {
-                        k.clone()
-                    } else {
-                        key + "." + k.as_slice()
-                    })
+                    stack.push((v, format!("{}.{}", key, k)));
                 }
             }
+            // The tables of an array of tables, such as `[[bin]]` sections,
+            // have their keys checked like those of a single table.
             toml::Array(ref arr) => {
                 for v in arr.iter() {
-                    add_unused_keys(m, v, key.clone());
+                    match *v {
+                        toml::Table(ref table) => {
+                            for (k, v) in table.iter() {
+                                stack.push((v, format!("{}.{}", key, k)));
+                            }
+                        }
+                        _ => {}
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+    if errors.is_empty() { return Ok(()) }
+    errors.sort();
+    Err(errors.connect("\n"))
+}
+
+/// Manifests larger than this many bytes are rejected unless the
+/// `manifest.max-size` configuration key allows them.
+static MAX_MANIFEST_SIZE: uint = 8 * 1024 * 1024;
+
+/// Manifests nesting arrays or tables deeper than this are rejected unless
+/// the `manifest.max-depth` configuration key allows them.
+static MAX_MANIFEST_DEPTH: uint = 128;
+
+/// The configuration which applies to a manifest, read from the directory
+/// of the manifest the first time one of its `[manifest]` keys is needed, so
+/// reading ordinary manifests never has to load it.
+struct ManifestConfig<'a> {
+    dir: &'a Path,
+    configs: Option<HashMap<String, config::ConfigValue>>,
+}
+
+impl<'a> ManifestConfig<'a> {
+    fn new(dir: &'a Path) -> ManifestConfig<'a> {
+        ManifestConfig { dir: dir, configs: None }
+    }
+
+    /// Returns the value of `manifest.<key>`, if it's configured.
+    fn get(&mut self, key: &str) -> CargoResult<Option<&config::ConfigValue>> {
+        if self.configs.is_none() {
+            self.configs = Some(try!(config::all_configs(self.dir.clone())));
+        }
+        let configs = self.configs.as_ref().unwrap();
+        let manifest = match configs.find_equiv("manifest") {
+            None => return Ok(None),
+            Some(manifest) => try!(manifest.table().chain_error(|| {
+                internal("invalid configuration for the key `manifest`")
+            })),
+        };
+        Ok(manifest.find_equiv(key))
+    }
+
+    /// Returns the limit configured under `manifest.<key>`, or `default`.
+    /// Limits which aren't integers or are negative are rejected.
+    fn limit(&mut self, key: &str, default: uint) -> CargoResult<uint> {
+        match try!(self.get(key)) {
+            None => Ok(default),
+            Some(limit) => {
+                let (limit, path) = try!(limit.integer().chain_error(|| {
+                    human(format!("invalid configuration for the key \
+                                   `manifest.{}`", key))
+                }));
+                if limit < 0 {
+                    return Err(human(format!("`manifest.{}` in {} must not be \
+                                              negative, but is {}", key,
+                                             path.display(), limit)))
                 }
+                Ok(limit as uint)
             }
-            _ => m.add_warning(format!("unused manifest key: {}", key)),
         }
     }
+
+    /// Reads the boolean `manifest.<key>`, or `default`.
+    fn switch(&mut self, key: &str, default: bool) -> CargoResult<bool> {
+        match try!(self.get(key)) {
+            None => Ok(default),
+            Some(switch) => {
+                let (switch, _) = try!(switch.boolean().chain_error(|| {
+                    internal(format!("invalid configuration for the key \
+                                      `manifest.{}`", key))
+                }));
+                Ok(switch)
+            }
+        }
+    }
+}
+
+/// Calculates how deeply arrays and tables are nested in the values of a
+/// manifest without parsing it.
+fn nesting_depth(contents: &str) -> uint {
+    let (mut depth, mut max) = (0u, 0u);
+    let mut quote = None;
+    let mut chars = contents.chars();
+    loop {
+        let c = match chars.next() { Some(c) => c, None => break };
+        match quote {
+            Some(q) => {
+                if c == '\\' && q == '"' {
+                    chars.next();
+                } else if c == q {
+                    quote = None;
+                }
+                continue
+            }
+            None => {}
+        }
+        match c {
+            '"' | '\'' => quote = Some(c),
+            '#' => loop {
+                match chars.next() {
+                    Some('\n') | None => break,
+                    Some(..) => {}
+                }
+            },
+            '[' | '{' => { depth += 1; if depth > max { max = depth } }
+            ']' | '}' => { if depth > 0 { depth -= 1 } }
+            _ => {}
+        }
+    }
+    max
+}
+
+/// The error for the manifest at `path`, which is invalid because of `cause`.
+fn invalid_manifest<E: CargoError + Send>(path: &Path,
+                                          cause: E) -> Box<CargoError + Send> {
+    human_context(InvalidManifest, path,
+                  format!("{} is not a valid manifest", path.display()), cause)
 }
 
 pub fn parse(toml: &str, file: &Path) -> CargoResult<toml::TomlTable> {
@@ -166,7 +999,7 @@ pub fn parse(toml: &str, file: &Path) -> CargoResult<toml::TomlTable> {
                                    },
                                    error.desc).as_slice());
     }
-    Err(human(error_str))
+    Err(human_of_kind(InvalidManifest, error_str))
 }
 
 type TomlLibTarget = TomlTarget;
@@ -175,20 +1008,31 @@ type TomlExampleTarget = TomlTarget;
 type TomlTestTarget = TomlTarget;
 type TomlBenchTarget = TomlTarget;
 
+/// The targets of a package once inferred ones have been filled in.
+struct TomlTargets {
+    lib: Vec<TomlLibTarget>,
+    bins: Vec<TomlBinTarget>,
+    examples: Vec<TomlExampleTarget>,
+    tests: Vec<TomlTestTarget>,
+    benches: Vec<TomlBenchTarget>,
+}
+
 /*
  * TODO: Make all struct fields private
  */
 
 #[deriving(Decodable)]
 pub enum TomlDependency {
-    SimpleDep(String),
+    SimpleDep(ManyOrOne<String>),
     DetailedDep(DetailedTomlDependency)
 }
 
 
 #[deriving(Decodable, Clone, Default)]
 pub struct DetailedTomlDependency {
-    version: Option<String>,
+    /// Either a single version requirement or an array of them, which must
+    /// all be satisfied.
+    version: Option<ManyOrOne<String>>,
     path: Option<String>,
     git: Option<String>,
     branch: Option<String>,
@@ -197,6 +1041,22 @@ pub struct DetailedTomlDependency {
     features: Option<Vec<String>>,
     optional: Option<bool>,
     default_features: Option<bool>,
+    /// Whether the dependency is declared in the `[workspace.dependencies]`
+    /// of the workspace root instead.
+    workspace: Option<bool>,
+}
+
+impl TomlDependency {
+    fn to_detailed(&self) -> DetailedTomlDependency {
+        match *self {
+            SimpleDep(ref version) => {
+                let mut d: DetailedTomlDependency = Default::default();
+                d.version = Some(version.clone());
+                d
+            }
+            DetailedDep(ref details) => details.clone(),
+        }
+    }
 }
 
 #[deriving(Decodable)]
@@ -214,6 +1074,16 @@ pub struct TomlManifest {
     build_dependencies: Option<HashMap<String, TomlDependency>>,
     features: Option<HashMap<String, Vec<String>>>,
     target: Option<HashMap<String, TomlPlatform>>,
+    workspace: Option<TomlWorkspace>,
+}
+
+/// The `[workspace]` section of a virtual manifest, listing the directories
+/// of the member packages as paths or globs relative to the manifest, and the
+/// dependencies which members can inherit with `workspace = true`.
+#[deriving(Decodable)]
+pub struct TomlWorkspace {
+    members: Vec<String>,
+    dependencies: Option<HashMap<String, TomlDependency>>,
 }
 
 #[deriving(Decodable, Clone, Default)]
@@ -229,11 +1099,44 @@ pub struct TomlProfiles {
 pub struct TomlProfile {
     opt_level: Option<uint>,
     codegen_units: Option<uint>,
-    debug: Option<bool>,
+    debug: Option<TomlDebugInfo>,
     rpath: Option<bool>,
 }
 
+/// The `debug` key of a profile: either a boolean, where `true` means full
+/// debug info, or a debuginfo level passed to rustc as `-C debuginfo=N`.
+#[deriving(Clone)]
+pub struct TomlDebugInfo {
+    level: uint,
+}
+
+/// The values the `debug` key can be written as, before they are checked.
 #[deriving(Decodable)]
+enum TomlDebugValue {
+    DebugFlag(bool),
+    DebugLevel(i64),
+}
+
+impl<E, D: Decoder<E>> Decodable<D, E> for TomlDebugInfo {
+    fn decode(d: &mut D) -> Result<TomlDebugInfo, E> {
+        let value: Result<TomlDebugValue, E> = Decodable::decode(d);
+        let level = match value {
+            Ok(DebugFlag(b)) => if b {2} else {0},
+            Ok(DebugLevel(level)) if level >= 0 && level <= 2 => level as uint,
+            Ok(DebugLevel(level)) => {
+                return Err(d.error(format!("invalid debuginfo level `{}`, \
+                                            the accepted values are `true`, \
+                                            `false`, 0, 1 or 2",
+                                           level).as_slice()))
+            }
+            Err(..) => return Err(d.error("expected a boolean or a debuginfo \
+                                           level (0, 1 or 2)")),
+        };
+        Ok(TomlDebugInfo { level: level })
+    }
+}
+
+#[deriving(Decodable, Clone, PartialEq)]
 pub enum ManyOrOne<T> {
     Many(Vec<T>),
     One(T),
@@ -255,7 +1158,12 @@ pub struct TomlProject {
     authors: Vec<String>,
     build: Option<TomlBuildCommandsList>,       // TODO: `String` instead
     links: Option<String>,
+    build_per_profile: Option<bool>,
+    warn_unused_optional: Option<bool>,
     exclude: Option<Vec<String>>,
+    test_dirs: Option<Vec<String>>,
+    bench_dirs: Option<Vec<String>>,
+    autobenches: Option<bool>,
 
     // package metadata
     description: Option<String>,
@@ -264,6 +1172,7 @@ pub struct TomlProject {
     readme: Option<String>,
     keywords: Option<Vec<String>>,
     license: Option<String>,
+    license_file: Option<String>,
     repository: Option<String>,
 }
 
@@ -288,8 +1197,120 @@ impl<E, D: Decoder<E>> Decodable<D, E> for TomlVersion {
     }
 }
 
+/// The longest name a package may have.
+pub static MAX_PACKAGE_NAME_LEN: uint = 64;
+
+/// Names which are valid identifiers but which cannot be used for a package as
+/// the resulting crate name would be a keyword or would clash with a crate
+/// shipped with rustc.
+static RESERVED_PACKAGE_NAMES: &'static [&'static str] = &[
+    "as", "box", "break", "const", "continue", "crate", "else", "enum",
+    "extern", "false", "fn", "for", "if", "impl", "in", "let", "loop",
+    "match", "mod", "move", "mut", "priv", "proc", "pub", "ref", "return",
+    "self", "static", "struct", "super", "test", "trait", "true", "type",
+    "unsafe", "use", "where", "while",
+];
+
+/// Checks that `name` is usable as the name of a package.
+///
+/// This is used when loading manifests as well as by `cargo new` so that new
+/// packages always load successfully.
+pub fn validate_package_name(name: &str) -> CargoResult<()> {
+    if name.is_empty() {
+        return Err(human("package names cannot be empty"))
+    }
+    for c in name.chars() {
+        if c.is_alphanumeric() || c == '_' || c == '-' { continue }
+        return Err(human(format!("invalid character `{}` in package name: \
+                                  `{}`, only alphanumeric characters, `-` \
+                                  and `_` are allowed", c, name)))
+    }
+    if name.char_at(0).is_digit() {
+        return Err(human(format!("package names cannot start with a digit: \
+                                  `{}`", name)))
+    }
+    if name.char_len() > MAX_PACKAGE_NAME_LEN {
+        return Err(human(format!("package name `{}` is too long, names may \
+                                  be at most {} characters", name,
+                                 MAX_PACKAGE_NAME_LEN)))
+    }
+    if RESERVED_PACKAGE_NAMES.contains(&name) {
+        return Err(human(format!("`{}` is a reserved name and cannot be used \
+                                  as a package name", name)))
+    }
+    Ok(())
+}
+
+/// Checks that `links` can name a native library.
+///
+/// The value ends up in the names of the `DEP_<LINKS>_*` environment variables
+/// passed to the build scripts of dependents, so it's limited to characters
+/// which survive the mangling.
+fn validate_links(links: &str) -> CargoResult<()> {
+    let valid = !links.is_empty() && links.chars().all(|c| {
+        c.is_alphanumeric() || c == '-' || c == '_' || c == '.'
+    });
+    if valid { return Ok(()) }
+    Err(human(format!("invalid value for `links`: `{}`, only alphanumeric \
+                       characters, `-`, `_` and `.` are allowed, for example \
+                       `z`, `git2` or `ssl`", links)))
+}
+
+/// Checks the names of the features a manifest declares and enables.
+///
+/// This covers the keys of the `[features]` table, the features each of them
+/// enables in a dependency (`dep/feature`) and the `features` list of each
+/// dependency. Only packages loaded from a path, which includes everything
+/// being packaged or published, are checked: packages already in a registry
+/// keep loading whatever names they were published with.
+fn validate_features(features: &HashMap<String, Vec<String>>,
+                     deps: &[Dependency]) -> CargoResult<()> {
+    for dep in deps.iter() {
+        for feature in dep.get_features().iter() {
+            try!(validate_feature_name(feature.as_slice()));
+        }
+    }
+    for (feature, list) in features.iter() {
+        try!(validate_feature_name(feature.as_slice()));
+        for dep in list.iter() {
+            if dep.as_slice() == "default" {
+                return Err(human(format!("Feature `{}` includes `default`, \
+                                          which only names the features \
+                                          enabled by default; list them \
+                                          instead", feature)))
+            }
+            match dep.as_slice().splitn(1, '/').nth(1) {
+                Some(name) => try!(validate_feature_name(name)),
+                None => {}
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Checks that `name` can be used as the name of a feature, as it's passed to
+/// rustc in a `--cfg feature="..."` flag.
+fn validate_feature_name(name: &str) -> CargoResult<()> {
+    if name.is_empty() {
+        return Err(human("feature names cannot be empty"))
+    }
+    for (i, c) in name.chars().enumerate() {
+        if i == 0 && c.is_digit() {
+            return Err(human(format!("feature name `{}` starts with the digit \
+                                      `{}`", name, c)))
+        }
+        if c.is_alphanumeric() || c == '_' || c == '-' { continue }
+        return Err(human(format!("invalid character `{}` at position {} of \
+                                  feature name `{}`, only alphanumeric \
+                                  characters, `-` and `_` are allowed",
+                                 c, i + 1, name)))
+    }
+    Ok(())
+}
+
 impl TomlProject {
     pub fn to_package_id(&self, source_id: &SourceId) -> CargoResult<PackageId> {
+        try!(validate_package_name(self.name.as_slice()));
         PackageId::new(self.name.as_slice(), self.version.version.clone(),
                        source_id)
     }
@@ -298,7 +1319,11 @@ impl TomlProject {
 struct Context<'a> {
     deps: &'a mut Vec<Dependency>,
     source_id: &'a SourceId,
-    nested_paths: &'a mut Vec<Path>
+    nested_paths: &'a mut Vec<Path>,
+    root: &'a Path,
+    /// The workspace the package is a member of, looked up the first time a
+    /// dependency is inherited from it.
+    workspace: Option<Option<WorkspaceRoot>>,
 }
 
 // These functions produce the equivalent of specific manifest entries. One
@@ -372,12 +1397,91 @@ fn inferred_bench_targets(layout: &Layout) -> Vec<TomlTarget> {
     }).collect()
 }
 
+/// Adds a target for each `.rs` file in the directories listed by the
+/// `test-dirs` or `bench-dirs` key, named after the file like the targets
+/// inferred from `tests` and `benches`. A target can't share its name with
+/// another one of the same kind.
+///
+/// `inferred` is the directory targets were already inferred from, if any,
+/// which is skipped when it's listed as well.
+fn add_dir_targets(targets: &mut Vec<TomlTarget>, kind: &str,
+                   dirs: &Option<Vec<String>>, inferred: Option<&str>,
+                   layout: &Layout) -> CargoResult<()> {
+    let dirs = match *dirs {
+        Some(ref dirs) => dirs,
+        None => return Ok(()),
+    };
+    for dir in dirs.iter() {
+        match inferred {
+            Some(inferred) if Path::new(dir.as_slice()) ==
+                              Path::new(inferred) => continue,
+            _ => {}
+        }
+        if !layout.root.join(dir.as_slice()).is_dir() {
+            return Err(human(format!("`{}-dirs` lists `{}`, which is not a \
+                                      directory", kind, dir)))
+        }
+        let (mut files, mut warnings) = (Vec::new(), Vec::new());
+        try_add_files(&mut files, &mut warnings, &layout.root, dir.as_slice());
+        match warnings.into_iter().next() {
+            Some(warning) => return Err(human(warning)),
+            None => {}
+        }
+        files.sort_by(|a, b| a.as_vec().cmp(b.as_vec()));
+
+        for file in files.into_iter() {
+            let name = match file.filestem_str() {
+                Some(name) => name.to_string(),
+                None => continue,
+            };
+            match targets.iter().find(|t| t.name == name) {
+                Some(other) => {
+                    let relative = |p: &Path| {
+                        p.path_relative_from(&layout.root).unwrap_or(p.clone())
+                    };
+                    let other = match other.path {
+                        Some(ref path) => {
+                            format!(" at `{}`",
+                                    relative(&path.to_path()).display())
+                        }
+                        None => String::new(),
+                    };
+                    return Err(human(format!("{} target `{}` at `{}` has the \
+                                              same name as the {} target{}",
+                                             kind, name,
+                                             relative(&file).display(), kind,
+                                             other)))
+                }
+                None => {}
+            }
+            targets.push(TomlTarget {
+                name: name,
+                path: Some(TomlPath(file)),
+                .. TomlTarget::new()
+            });
+        }
+    }
+    Ok(())
+}
+
 impl TomlManifest {
     pub fn to_manifest(&self, source_id: &SourceId, layout: &Layout)
         -> CargoResult<(Manifest, Vec<Path>)> {
         let mut nested_paths = vec!();
 
         let project = self.project.as_ref().or_else(|| self.package.as_ref());
+        match (project, self.workspace.as_ref()) {
+            (None, Some(workspace)) => {
+                return self.to_virtual_manifest(workspace, source_id, layout)
+            }
+            (Some(..), Some(..)) => {
+                return Err(human("a [workspace] section can only be used in \
+                                  a virtual manifest, which has no [package] \
+                                  section\nmove the package into a directory \
+                                  of its own and list it in `members`"))
+            }
+            _ => {}
+        }
         let project = try!(project.require(|| {
             human("No `package` or `project` section found.")
         }));
@@ -385,64 +1489,9 @@ impl TomlManifest {
         let pkgid = try!(project.to_package_id(source_id));
         let metadata = pkgid.generate_metadata();
 
-        // If we have no lib at all, use the inferred lib if available
-        // If we have a lib with a path, we're done
-        // If we have a lib with no path, use the inferred lib or_else package name
-
-        let mut used_deprecated_lib = false;
-        let lib = match self.lib {
-            Some(ref libs) => {
-                match *libs {
-                    Many(..) => used_deprecated_lib = true,
-                    _ => {}
-                }
-                libs.as_slice().iter().map(|t| {
-                    if layout.lib.is_some() && t.path.is_none() {
-                        TomlTarget {
-                            path: layout.lib.as_ref().map(|p| TomlPath(p.clone())),
-                            .. t.clone()
-                        }
-                    } else {
-                        t.clone()
-                    }
-                }).collect()
-            }
-            None => inferred_lib_target(project.name.as_slice(), layout),
-        };
-
-        let bins = match self.bin {
-            Some(ref bins) => {
-                let bin = layout.main();
-
-                bins.iter().map(|t| {
-                    if bin.is_some() && t.path.is_none() {
-                        TomlTarget {
-                            path: bin.as_ref().map(|&p| TomlPath(p.clone())),
-                            .. t.clone()
-                        }
-                    } else {
-                        t.clone()
-                    }
-                }).collect()
-            }
-            None => inferred_bin_targets(project.name.as_slice(), layout)
-        };
-
-        let examples = match self.example {
-            Some(ref examples) => examples.clone(),
-            None => inferred_example_targets(layout),
-        };
-
-        let tests = match self.test {
-            Some(ref tests) => tests.clone(),
-            None => inferred_test_targets(layout),
-        };
-
-        let benches = if self.bench.is_none() || self.bench.as_ref().unwrap().is_empty() {
-            inferred_bench_targets(layout)
-        } else {
-            self.bench.as_ref().unwrap().iter().map(|t| t.clone()).collect()
-        };
+        let (targets, used_deprecated_lib) = try!(self.targets(&**project,
+                                                               layout));
+        let TomlTargets { lib, bins, examples, tests, benches } = targets;
 
         // processing the custom build script
         let (new_build, old_build) = match project.build {
@@ -457,6 +1506,28 @@ impl TomlManifest {
             None => (None, Vec::new())
         };
 
+        let has_build_script = new_build.is_some();
+
+        // Explicitly listed paths are checked up front as rustc's error about
+        // a missing file is rather confusing.
+        try!(check_target_paths(&layout.root, "lib",
+                                self.lib.as_ref().map(|l| l.as_slice())));
+        try!(check_target_paths(&layout.root, "bin",
+                                self.bin.as_ref().map(|b| b.as_slice())));
+        try!(check_target_paths(&layout.root, "example",
+                                self.example.as_ref().map(|e| e.as_slice())));
+        try!(check_target_paths(&layout.root, "test",
+                                self.test.as_ref().map(|t| t.as_slice())));
+        try!(check_target_paths(&layout.root, "bench",
+                                self.bench.as_ref().map(|b| b.as_slice())));
+
+        for l in lib.iter() {
+            match l.crate_type {
+                Some(ref kinds) => { try!(LibKind::from_strs(kinds.clone())); }
+                None => {}
+            }
+        }
+
         // Get targets
         let profiles = self.profile.clone().unwrap_or(Default::default());
         let targets = normalize(lib.as_slice(),
@@ -479,7 +1550,9 @@ impl TomlManifest {
             let mut cx = Context {
                 deps: &mut deps,
                 source_id: source_id,
-                nested_paths: &mut nested_paths
+                nested_paths: &mut nested_paths,
+                root: &layout.root,
+                workspace: None,
             };
 
             // Collect the deps
@@ -492,6 +1565,10 @@ impl TomlManifest {
 
             if let Some(targets) = self.target.as_ref() {
                 for (name, platform) in targets.iter() {
+                    match CfgExpr::parse_platform(name.as_slice()) {
+                        Some(Err(e)) => return Err(e),
+                        Some(Ok(..)) | None => {}
+                    }
                     try!(process_dependencies(&mut cx,
                                               platform.dependencies.as_ref(),
                                               |dep| {
@@ -502,19 +1579,49 @@ impl TomlManifest {
         }
 
         let exclude = project.exclude.clone().unwrap_or(Vec::new());
+        match project.links {
+            Some(ref links) => try!(validate_links(links.as_slice())),
+            None => {}
+        }
 
         let has_old_build = old_build.len() >= 1;
 
-        let summary = try!(Summary::new(pkgid, deps,
-                                        self.features.clone()
-                                            .unwrap_or(HashMap::new())));
+        let features = self.features.clone().unwrap_or(HashMap::new());
+        if source_id.is_path() {
+            try!(validate_features(&features, deps.as_slice()));
+        }
+        let summary = try!(Summary::new(pkgid, deps, features));
+        // The license is checked against the SPDX list unless the package
+        // ships its own license text. Only publishing rejects unknown ones.
+        let mut license_warning = None;
+        let license = match (&project.license, &project.license_file) {
+            (&Some(ref license), &None) => {
+                match normalize_license(license.as_slice()) {
+                    Ok(normalized) => {
+                        if normalized != *license {
+                            license_warning = Some(format!("the license `{}` \
+                                                            was normalized to \
+                                                            `{}`", license,
+                                                           normalized));
+                        }
+                        Some(normalized)
+                    }
+                    Err(msg) => {
+                        license_warning = Some(msg);
+                        Some(license.clone())
+                    }
+                }
+            }
+            (license, _) => license.clone(),
+        };
         let metadata = ManifestMetadata {
             description: project.description.clone(),
             homepage: project.homepage.clone(),
             documentation: project.documentation.clone(),
             readme: project.readme.clone(),
             authors: project.authors.clone(),
-            license: project.license.clone(),
+            license: license,
+            license_file: project.license_file.clone(),
             repository: project.repository.clone(),
             keywords: project.keywords.clone().unwrap_or(Vec::new()),
         };
@@ -526,6 +1633,71 @@ impl TomlManifest {
                                          exclude,
                                          project.links.clone(),
                                          metadata);
+        match license_warning {
+            Some(warning) => manifest.add_warning(warning),
+            None => {}
+        }
+        if project.links.as_ref() == Some(&project.name) {
+            manifest.add_warning(format!("`links` is set to `{}`, the name of \
+                                          the package itself, but it should \
+                                          be the name of the native library \
+                                          the package links to, such as `z` \
+                                          for libz", project.name));
+        }
+        if project.warn_unused_optional.unwrap_or(true) {
+            let unused = manifest.get_summary()
+                                 .unreferenced_optional_dependencies()
+                                 .iter().map(|name| format!("`{}`", name))
+                                 .collect::<Vec<String>>();
+            if unused.len() > 0 {
+                manifest.add_warning(format!("optional dependencies not \
+                                              referenced by any feature: {}\n\
+                                              they can only be enabled with \
+                                              `--features` naming them \
+                                              directly; set \
+                                              `warn-unused-optional = false` \
+                                              if that is intended",
+                                             unused.connect(", ")));
+            }
+        }
+        match project.build_per_profile {
+            Some(per_profile) if has_build_script => {
+                manifest.set_build_per_profile(per_profile);
+            }
+            Some(..) => {
+                manifest.add_warning(format!("`build-per-profile` has no \
+                                              effect without a build script"));
+            }
+            None => {}
+        }
+        // Explicit target sections turn off inference, so point out any of the
+        // conventional source files which would silently not be built.
+        let hidden_main = match (self.bin.as_ref(), layout.main()) {
+            (Some(..), Some(main)) => {
+                !bins.iter().any(|t| target_covers(&layout.root, t, main))
+            }
+            _ => false,
+        };
+        if hidden_main {
+            manifest.add_warning(format!("src/main.rs is not built because \
+                                          [[bin]] sections are present, add a \
+                                          [[bin]] with `path = \"src/main.rs\"` \
+                                          to build it"));
+        }
+        let hidden_lib = match (self.lib.as_ref(), layout.lib.as_ref()) {
+            (Some(..), Some(lib_rs)) => {
+                !lib.iter().any(|t| target_covers(&layout.root, t, lib_rs))
+            }
+            _ => false,
+        };
+        if hidden_lib {
+            manifest.add_warning(format!("src/lib.rs is not built because the \
+                                          [lib] section points elsewhere, \
+                                          remove its `path` key to build it"));
+        }
+        for warning in layout.warnings.iter() {
+            manifest.add_warning(warning.clone());
+        }
         if used_deprecated_lib {
             manifest.add_warning(format!("the [[lib]] section has been \
                                           deprecated in favor of [lib]"));
@@ -538,7 +1710,303 @@ impl TomlManifest {
             manifest.add_warning(format!("         For more information, see \
                                           http://doc.crates.io/build-script.html"));
         }
-        Ok((manifest, nested_paths))
+        Ok((manifest, nested_dirs(&layout.root, nested_paths)))
+    }
+
+    /// Returns the targets of the package, with inferred ones filled in
+    /// wherever the manifest doesn't list them, and whether the deprecated
+    /// [[lib]] section was used.
+    fn targets(&self, project: &TomlProject, layout: &Layout)
+               -> CargoResult<(TomlTargets, bool)> {
+        // If we have no lib at all, use the inferred lib if available
+        // If we have a lib with a path, we're done
+        // If we have a lib with no path, use the inferred lib or_else package name
+
+        let mut used_deprecated_lib = false;
+        let lib = match self.lib {
+            Some(ref libs) => {
+                match *libs {
+                    Many(ref v) if v.len() > 1 => {
+                        let extra = v.slice_from(1).iter().map(|t| {
+                            format!("`{}`", t.name)
+                        }).collect::<Vec<String>>();
+                        return Err(human(format!("a package can only have one \
+                                                  library, but {} [[lib]] \
+                                                  sections were found (extra: \
+                                                  {})\nuse a single [lib] \
+                                                  section with `crate-type` to \
+                                                  produce several kinds of \
+                                                  library artifacts",
+                                                 v.len(), extra.connect(", "))))
+                    }
+                    Many(..) => used_deprecated_lib = true,
+                    _ => {}
+                }
+                libs.as_slice().iter().map(|t| {
+                    if layout.lib.is_some() && t.path.is_none() {
+                        TomlTarget {
+                            path: layout.lib.as_ref().map(|p| TomlPath(p.clone())),
+                            .. t.clone()
+                        }
+                    } else {
+                        t.clone()
+                    }
+                }).collect()
+            }
+            None => inferred_lib_target(project.name.as_slice(), layout),
+        };
+
+        let bins = match self.bin {
+            Some(ref bins) => {
+                let bin = layout.main();
+
+                bins.iter().map(|t| {
+                    if bin.is_some() && t.path.is_none() {
+                        TomlTarget {
+                            path: bin.as_ref().map(|&p| TomlPath(p.clone())),
+                            .. t.clone()
+                        }
+                    } else {
+                        t.clone()
+                    }
+                }).collect()
+            }
+            None => inferred_bin_targets(project.name.as_slice(), layout)
+        };
+
+        let examples = match self.example {
+            Some(ref examples) => examples.clone(),
+            None => inferred_example_targets(layout),
+        };
+
+        let (mut tests, inferred) = match self.test {
+            Some(ref tests) => (tests.clone(), None),
+            None => (inferred_test_targets(layout), Some("tests")),
+        };
+        try!(add_dir_targets(&mut tests, "test", &project.test_dirs, inferred,
+                             layout));
+
+        // Only the `benches` directory is subject to `autobenches`, the
+        // `[[bench]]` sections and `bench-dirs` are always honored.
+        let (mut benches, inferred) = match self.bench {
+            Some(ref benches) if !benches.is_empty() => (benches.clone(), None),
+            _ if project.autobenches.unwrap_or(true) => {
+                (inferred_bench_targets(layout), Some("benches"))
+            }
+            _ => (Vec::new(), None),
+        };
+        try!(add_dir_targets(&mut benches, "bench", &project.bench_dirs,
+                             inferred, layout));
+
+        Ok((TomlTargets {
+            lib: lib,
+            bins: bins,
+            examples: examples,
+            tests: tests,
+            benches: benches,
+        }, used_deprecated_lib))
+    }
+
+    /// Builds the manifest of a virtual workspace root, which has no package
+    /// of its own. The directories of the members are returned as the nested
+    /// paths, and the root is made to depend on the packages found there once
+    /// they have been read.
+    fn to_virtual_manifest(&self, workspace: &TomlWorkspace,
+                           source_id: &SourceId, layout: &Layout)
+                           -> CargoResult<(Manifest, Vec<Path>)> {
+        let sections = [
+            ("lib", self.lib.is_some()),
+            ("bin", self.bin.is_some()),
+            ("example", self.example.is_some()),
+            ("test", self.test.is_some()),
+            ("bench", self.bench.is_some()),
+            ("dependencies", self.dependencies.is_some()),
+            ("dev-dependencies", self.dev_dependencies.is_some()),
+            ("build-dependencies", self.build_dependencies.is_some()),
+            ("features", self.features.is_some()),
+            ("target", self.target.is_some()),
+            ("profile", self.profile.is_some()),
+        ];
+        match sections.iter().find(|&&(_, present)| present) {
+            Some(&(name, _)) => {
+                return Err(human(format!("a virtual manifest can only have a \
+                                          [workspace] section, but [{}] was \
+                                          found\nmove it into the manifest of \
+                                          the workspace member it belongs to",
+                                         name)))
+            }
+            None => {}
+        }
+
+        let members = try!(workspace_members(&layout.root,
+                                             workspace.members.as_slice()));
+        let pkgid = try!(PackageId::new(WORKSPACE_ROOT_NAME, "0.0.0",
+                                        source_id));
+        let summary = try!(Summary::new(pkgid, Vec::new(), HashMap::new()));
+        let metadata = ManifestMetadata {
+            description: None,
+            homepage: None,
+            documentation: None,
+            readme: None,
+            authors: Vec::new(),
+            license: None,
+            license_file: None,
+            repository: None,
+            keywords: Vec::new(),
+        };
+        let manifest = Manifest::new(summary.workspace_root(Vec::new()),
+                                     Vec::new(),
+                                     layout.root.join("target"),
+                                     layout.root.join("doc"),
+                                     Vec::new(),
+                                     Vec::new(),
+                                     None,
+                                     metadata);
+        Ok((manifest, nested_dirs(&layout.root, members)))
+    }
+}
+
+/// Resolves the `paths` a manifest in the directory `root` refers to against
+/// it, sorted and without duplicates.
+fn nested_dirs(root: &Path, paths: Vec<Path>) -> Vec<Path> {
+    let mut dirs = paths.into_iter().map(|p| root.join(p))
+                        .collect::<Vec<Path>>();
+    dirs.sort();
+    dirs.dedup();
+    dirs
+}
+
+/// The name of the package standing for a virtual workspace root, which can't
+/// be the name of a crate and so never clashes with one of the members.
+pub static WORKSPACE_ROOT_NAME: &'static str = "[workspace]";
+
+/// Expands the `members` of a `[workspace]` section into the directories of
+/// the member packages, relative to `root`.
+///
+/// A glob selects the directories it matches which contain a `Cargo.toml`,
+/// while a plain path must name such a directory.
+fn workspace_members(root: &Path, members: &[String]) -> CargoResult<Vec<Path>> {
+    let mut dirs = Vec::new();
+    for member in members.iter() {
+        let member = member.as_slice();
+        if is_home_relative(member) || Path::new(member).is_absolute() {
+            return Err(human(format!("workspace member `{}` must be a path \
+                                      relative to the manifest", member)))
+        }
+        let is_glob = member.chars().any(|c| c == '*' || c == '?' || c == '[');
+        if !is_glob {
+            let manifest = root.join(member).join("Cargo.toml");
+            if !manifest.exists() {
+                return Err(human(format!("workspace member `{}` is not a \
+                                          package, `{}` does not exist",
+                                         member, manifest.display())))
+            }
+            let dir = Path::new(member);
+            if !dirs.contains(&dir) { dirs.push(dir) }
+            continue
+        }
+
+        let pattern = root.join(member);
+        let pattern = pattern.as_str().unwrap_or("");
+        let mut matched = glob::glob(pattern).filter(|dir| {
+            *dir != *root && dir.join("Cargo.toml").exists()
+        }).collect::<Vec<Path>>();
+        if matched.len() == 0 {
+            return Err(human(format!("workspace member `{}` doesn't match \
+                                      any directory containing a Cargo.toml",
+                                     member)))
+        }
+        matched.sort();
+        for dir in matched.into_iter() {
+            let dir = dir.path_relative_from(root).unwrap_or(dir);
+            if !dirs.contains(&dir) { dirs.push(dir) }
+        }
+    }
+    if dirs.len() == 0 {
+        return Err(human("the [workspace] section has no `members`"))
+    }
+    Ok(dirs)
+}
+
+/// The virtual manifest of the workspace a package is a member of.
+pub struct WorkspaceRoot {
+    /// The path of the virtual manifest.
+    pub manifest: Path,
+    dependencies: HashMap<String, TomlDependency>,
+}
+
+/// Finds the virtual manifest which lists the package in `dir` among the
+/// members of its workspace, searching the parent directories of `dir`.
+pub fn find_workspace(dir: &Path) -> CargoResult<Option<WorkspaceRoot>> {
+    let mut current = dir.clone();
+    while current.pop() {
+        let manifest = current.join("Cargo.toml");
+        if !manifest.exists() { continue }
+        let contents = try!(File::open(&manifest).read_to_end());
+        if !is_virtual_manifest(contents.as_slice()) { continue }
+
+        let invalid = |e: String| invalid_manifest(&manifest, human(e));
+        let contents = str::from_utf8(contents.as_slice()).unwrap();
+        let root = try!(parse(contents, &manifest));
+        let mut d = toml::Decoder::new(toml::Table(root));
+        let toml_manifest: TomlManifest = match Decodable::decode(&mut d) {
+            Ok(t) => t,
+            Err(e) => return Err(invalid(e.to_string())),
+        };
+        let workspace = toml_manifest.workspace.unwrap();
+        let members = try!(workspace_members(&current,
+                                             workspace.members.as_slice())
+                               .map_err(|e| invalid(e.to_string())));
+        if members.iter().any(|member| current.join(member) == *dir) {
+            return Ok(Some(WorkspaceRoot {
+                manifest: manifest.clone(),
+                dependencies: workspace.dependencies.unwrap_or(HashMap::new()),
+            }))
+        }
+    }
+    Ok(None)
+}
+
+/// Returns whether `contents` are those of a virtual manifest, with a
+/// `[workspace]` section and no package. Contents which can't be parsed are
+/// not, the error is left to reading them as a package.
+fn is_virtual_manifest(contents: &[u8]) -> bool {
+    let contents = match str::from_utf8(contents) {
+        Some(contents) => contents,
+        None => return false,
+    };
+    match toml::Parser::new(contents).parse() {
+        Some(table) => {
+            table.contains_key(&"workspace".to_string()) &&
+                !table.contains_key(&"package".to_string()) &&
+                !table.contains_key(&"project".to_string())
+        }
+        None => false,
+    }
+}
+
+fn check_target_paths(root: &Path, kind: &str,
+                      targets: Option<&[TomlTarget]>) -> CargoResult<()> {
+    for target in targets.unwrap_or(&[]).iter() {
+        let path = match target.path {
+            Some(ref path) => path,
+            None => continue,
+        };
+        let abs = root.join(path.to_path());
+        if !abs.exists() {
+            return Err(human(format!("can't find {} `{}` at path `{}`, the \
+                                      file `{}` does not exist", kind,
+                                     target.name, path, abs.display())))
+        }
+    }
+    Ok(())
+}
+
+/// Returns whether the explicitly declared `target` is built from `file`.
+fn target_covers(root: &Path, target: &TomlTarget, file: &Path) -> bool {
+    match target.path {
+        Some(ref path) => root.join(path.to_path()) == *file,
+        None => false,
     }
 }
 
@@ -551,13 +2019,11 @@ fn process_dependencies<'a>(cx: &mut Context<'a>,
         None => return Ok(())
     };
     for (n, v) in dependencies.iter() {
-        let details = match *v {
-            SimpleDep(ref version) => {
-                let mut d: DetailedTomlDependency = Default::default();
-                d.version = Some(version.clone());
-                d
-            }
-            DetailedDep(ref details) => details.clone(),
+        let details = v.to_detailed();
+        let details = if details.workspace == Some(true) {
+            try!(inherit_dependency(cx, n.as_slice(), details))
+        } else {
+            details
         };
         let reference = details.branch.clone()
             .or_else(|| details.tag.clone())
@@ -571,17 +2037,39 @@ fn process_dependencies<'a>(cx: &mut Context<'a>,
                 }));
                 Some(SourceId::for_git(&loc, reference.as_slice()))
             }
-            None => {
-                details.path.as_ref().map(|path| {
+            None => match details.path {
+                // Manifests are meant to be machine-independent, so a path
+                // relative to the home directory is never expanded.
+                Some(ref path) if is_home_relative(path.as_slice()) => {
+                    return Err(human(format!("the path of dependency `{}` \
+                                              starts with `~`, which is not \
+                                              expanded in manifests\n\
+                                              it would refer to `{}`, use a \
+                                              path relative to the manifest \
+                                              instead", n,
+                                             cx.root.join(path.as_slice())
+                                               .display())))
+                }
+                Some(ref path) => {
                     cx.nested_paths.push(Path::new(path.as_slice()));
-                    cx.source_id.clone()
-                })
+                    Some(cx.source_id.clone())
+                }
+                None => None,
             }
         }.unwrap_or(try!(SourceId::for_central()));
 
+        // The requirements of an array are combined into a single one, which
+        // is also how it's recorded when the package is published.
+        let version = match details.version {
+            Some(ref v) if v.as_slice().is_empty() => {
+                return Err(human(format!("the version requirement of \
+                                          dependency `{}` is an empty array", n)))
+            }
+            Some(ref v) => Some(v.as_slice().connect(", ")),
+            None => None,
+        };
         let dep = try!(Dependency::parse(n.as_slice(),
-                                         details.version.as_ref()
-                                                .map(|v| v.as_slice()),
+                                         version.as_ref().map(|v| v.as_slice()),
                                          &new_source_id));
         let dep = f(dep)
                      .features(details.features.unwrap_or(Vec::new()))
@@ -593,6 +2081,82 @@ fn process_dependencies<'a>(cx: &mut Context<'a>,
     Ok(())
 }
 
+/// Returns the declaration of the dependency `name` in the
+/// `[workspace.dependencies]` of the package's workspace, along with the
+/// features the package's own entry `member` adds to it and whether it makes
+/// the dependency optional.
+fn inherit_dependency(cx: &mut Context, name: &str,
+                      member: DetailedTomlDependency)
+                      -> CargoResult<DetailedTomlDependency> {
+    if cx.workspace.is_none() {
+        cx.workspace = Some(try!(find_workspace(cx.root)));
+    }
+    let workspace = match cx.workspace {
+        Some(Some(ref workspace)) => workspace,
+        _ => {
+            return Err(human(format!("dependency `{}` has `workspace = true`, \
+                                      but the package is not a member of a \
+                                      workspace\nno virtual manifest listing \
+                                      `{}` among its members was found in the \
+                                      parent directories", name,
+                                     cx.root.display())))
+        }
+    };
+    let root_manifest = &workspace.manifest;
+    let root_manifest = root_manifest.path_relative_from(&os::getcwd())
+                                     .unwrap_or(root_manifest.clone());
+    let declared = match workspace.dependencies.get(&name.to_string()) {
+        Some(declared) => declared.to_detailed(),
+        None => {
+            return Err(human(format!("dependency `{}` has `workspace = true`, \
+                                      but it isn't declared in the \
+                                      [workspace.dependencies] of {}", name,
+                                     root_manifest.display())))
+        }
+    };
+
+    let conflicts = [
+        ("version", member.version.is_some() &&
+                    member.version != declared.version),
+        ("path", member.path.is_some()),
+        ("git", member.git.is_some() && member.git != declared.git),
+        ("branch", member.branch.is_some() && member.branch != declared.branch),
+        ("tag", member.tag.is_some() && member.tag != declared.tag),
+        ("rev", member.rev.is_some() && member.rev != declared.rev),
+        ("default-features",
+         member.default_features.is_some() &&
+             member.default_features != declared.default_features),
+    ];
+    match conflicts.iter().find(|&&(_, conflict)| conflict) {
+        Some(&(key, _)) => {
+            return Err(human(format!("dependency `{}` has `workspace = true`, \
+                                      but sets `{}` differently from its \
+                                      declaration in {}\nonly `features` and \
+                                      `optional` can be added to an inherited \
+                                      dependency", name, key,
+                                     root_manifest.display())))
+        }
+        None => {}
+    }
+
+    let mut features = declared.features.clone().unwrap_or(Vec::new());
+    for feature in member.features.unwrap_or(Vec::new()).into_iter() {
+        if !features.contains(&feature) { features.push(feature) }
+    }
+    // A path in the root manifest is relative to the root, not to the member.
+    let path = declared.path.as_ref().map(|path| {
+        let path = workspace.manifest.dir_path().join(path.as_slice());
+        path.path_relative_from(cx.root).unwrap_or(path).display().to_string()
+    });
+    Ok(DetailedTomlDependency {
+        path: path,
+        features: if features.len() > 0 {Some(features)} else {None},
+        optional: member.optional,
+        workspace: None,
+        .. declared
+    })
+}
+
 #[deriving(Decodable, Show, Clone)]
 struct TomlTarget {
     name: String,
@@ -672,10 +2236,11 @@ fn normalize(libs: &[TomlLibTarget],
         };
         let opt_level = toml.opt_level.unwrap_or(profile.get_opt_level());
         let codegen_units = toml.codegen_units;
-        let debug = toml.debug.unwrap_or(profile.get_debug());
+        let debuginfo = toml.debug.as_ref().map(|d| d.level)
+                            .unwrap_or(profile.get_debuginfo());
         let rpath = toml.rpath.unwrap_or(profile.get_rpath());
-        profile.opt_level(opt_level).codegen_units(codegen_units).debug(debug)
-               .rpath(rpath)
+        profile.opt_level(opt_level).codegen_units(codegen_units)
+               .debuginfo(debuginfo).rpath(rpath)
     }
 
     fn target_profiles(target: &TomlTarget, profiles: &TomlProfiles,
@@ -688,6 +2253,8 @@ fn normalize(libs: &[TomlLibTarget],
         match target.test {
             Some(true) | None => {
                 ret.push(merge(Profile::default_test(), &profiles.test));
+                ret.push(merge(Profile::default_release_test(),
+                               &profiles.bench));
             }
             Some(false) => {}
         }
@@ -716,6 +2283,8 @@ fn normalize(libs: &[TomlLibTarget],
                                &profiles.doc));
                 ret.push(merge(Profile::default_bench().test(false),
                                &profiles.bench));
+                ret.push(merge(Profile::default_release_test().test(false),
+                               &profiles.release));
             }
             _ => {}
         }
@@ -800,11 +2369,30 @@ fn normalize(libs: &[TomlLibTarget],
         for ex in examples.iter() {
             let path = ex.path.clone().unwrap_or_else(|| TomlString(default(ex)));
 
-            let profile = Profile::default_test().test(false);
+            let profile = Profile::default_test().test(false)
+                                                 .tested(ex.test != Some(false));
             let profile = merge(profile, &profiles.test);
             dst.push(Target::example_target(ex.name.as_slice(),
                                             &path.to_path(),
                                             &profile));
+
+            // `cargo test --release` builds them like `cargo build --release`
+            // would, rather than with the unoptimized test profile.
+            let profile = Profile::default_release_test().test(false)
+                                  .tested(ex.test != Some(false));
+            let profile = merge(profile, &profiles.release);
+            dst.push(Target::example_target(ex.name.as_slice(),
+                                            &path.to_path(),
+                                            &profile));
+
+            // Unlike other targets, examples are only documented on request.
+            if ex.doc == Some(true) {
+                let profile = Profile::default_doc().doctest(false);
+                let profile = merge(profile, &profiles.doc);
+                dst.push(Target::example_target(ex.name.as_slice(),
+                                                &path.to_path(),
+                                                &profile));
+            }
         }
     }
 
@@ -823,6 +2411,13 @@ fn normalize(libs: &[TomlLibTarget],
 
             let profile = Profile::default_test().harness(harness);
             let profile = merge(profile, &profiles.test);
+            dst.push(Target::test_target(test.name.as_slice(),
+                                         &path.to_path(),
+                                         &profile,
+                                         metadata.clone()));
+
+            let profile = Profile::default_release_test().harness(harness);
+            let profile = merge(profile, &profiles.bench);
             dst.push(Target::test_target(test.name.as_slice(),
                                          &path.to_path(),
                                          &profile,
@@ -845,6 +2440,15 @@ fn normalize(libs: &[TomlLibTarget],
 
             let profile = Profile::default_bench().harness(harness);
             let profile = merge(profile, &profiles.bench);
+            dst.push(Target::bench_target(bench.name.as_slice(),
+                                          &path.to_path(),
+                                          &profile,
+                                          metadata.clone()));
+
+            // A debug `cargo build --all-targets` compiles them as tests,
+            // which `cargo test` itself skips.
+            let profile = Profile::default_test().harness(harness).tested(false);
+            let profile = merge(profile, &profiles.test);
             dst.push(Target::bench_target(bench.name.as_slice(),
                                           &path.to_path(),
                                           &profile,
@@ -901,3 +2505,63 @@ fn normalize(libs: &[TomlLibTarget],
 
     ret
 }
+
+#[cfg(test)]
+mod tests {
+    use core::SourceId;
+    use super::{Layout, to_manifest};
+
+    fn nested_paths(root: &Path, manifest: &str) -> Vec<Path> {
+        let layout = Layout {
+            root: root.clone(),
+            lib: Some(root.join("src/lib.rs")),
+            bins: Vec::new(),
+            examples: Vec::new(),
+            tests: Vec::new(),
+            benches: Vec::new(),
+            warnings: Vec::new(),
+        };
+        let source_id = SourceId::for_path(root).unwrap();
+        to_manifest(manifest.as_bytes(), &source_id, layout).unwrap().val1()
+    }
+
+    #[test]
+    fn nested_paths_are_resolved_sorted_and_deduplicated() {
+        let root = Path::new("/work/foo");
+        let paths = nested_paths(&root, r#"
+            [package]
+            name = "foo"
+            version = "0.0.1"
+            authors = []
+
+            [dependencies.baz]
+            path = "vendor/baz"
+
+            [dependencies.bar]
+            path = "../bar"
+
+            [dev-dependencies.baz]
+            path = "vendor/baz"
+
+            [build-dependencies.bar]
+            path = "../bar/"
+        "#);
+        assert_eq!(paths, vec![Path::new("/work/bar"),
+                               Path::new("/work/foo/vendor/baz")]);
+    }
+
+    #[test]
+    fn nested_paths_include_platform_dependencies() {
+        let root = Path::new("/work/foo");
+        let paths = nested_paths(&root, r#"
+            [package]
+            name = "foo"
+            version = "0.0.1"
+            authors = []
+
+            [target.x86_64-pc-windows-gnu.dependencies.winapi]
+            path = "winapi"
+        "#);
+        assert_eq!(paths, vec![Path::new("/work/foo/winapi")]);
+    }
+}
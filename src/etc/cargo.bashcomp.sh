@@ -21,14 +21,15 @@ _cargo() {
     case "${cmd}" in
         bench|test)
             COMPREPLY=( $(compgen -W \
-                "--features --help --jobs --manifest-path --no-default-features \
-                 --no-run --package --target --verbose" \
+                "--all-features --features --help --jobs --manifest-path \
+                 --no-default-features --no-run --package --target --verbose" \
                  -- "${cur}") )
             return 0;;
         build)
             COMPREPLY=( $(compgen -W \
-                "--features --help --jobs --manifest-path --no-default-features \
-                 --package --release --target --verbose" \
+                "--all-features --bin --example --features --help --jobs --lib \
+                 --manifest-path --no-default-features --package --print-artifact \
+                 --release --target --verbose" \
                  -- "${cur}") )
             return 0;;
         clean)
@@ -48,8 +49,8 @@ _cargo() {
             return 0;;
         doc)
             COMPREPLY=( $(compgen -W \
-                "--features --help --jobs --manifest-path --no-default-features \
-                 --no-deps --open --verbose" \
+                "--all-features --features --help --jobs --manifest-path \
+                 --no-default-features --no-deps --open --verbose" \
                  -- "${cur}") )
             return 0;;
         fetch|generate-lockfile|package|pkgid|read-manifest|verify-project)
@@ -0,0 +1,17 @@
+# Prints the SPDX license identifiers in the format of
+# `src/etc/spdx-licenses.txt`, which is regenerated with:
+#
+#     python src/etc/print-spdx-licenses.py > src/etc/spdx-licenses.txt
+import json
+import urllib2
+
+url = 'http://spdx.org/licenses/licenses.json'
+licenses = json.load(urllib2.urlopen(url))['licenses']
+
+print("# SPDX license identifiers accepted in the `license` manifest key, one")
+print("# per line. Generated by src/etc/print-spdx-licenses.py, rerun it to pick up")
+print("# new ones:")
+print("#")
+print("#     python src/etc/print-spdx-licenses.py > src/etc/spdx-licenses.txt")
+for license in sorted(licenses, key=lambda l: l['licenseId'].lower()):
+    print(license['licenseId'])
@@ -2,6 +2,7 @@ extern crate curl;
 extern crate serialize;
 
 use std::fmt;
+use std::str;
 use std::io::{mod, fs, MemReader, MemWriter, File};
 use std::collections::HashMap;
 use std::io::util::ChainedReader;
@@ -25,6 +26,9 @@ pub enum Error {
     ApiErrors(Vec<String>),
     Unauthorized,
     IoError(io::IoError),
+    /// The version being published already exists, and the checksum of its
+    /// tarball is the one given.
+    AlreadyUploaded(String),
 }
 
 #[deriving(Encodable)]
@@ -40,6 +44,7 @@ pub struct NewCrate {
     pub readme: Option<String>,
     pub keywords: Vec<String>,
     pub license: Option<String>,
+    pub license_file: Option<String>,
     pub repository: Option<String>,
 }
 
@@ -56,6 +61,7 @@ pub struct NewCrateDependency {
 #[deriving(Decodable)] struct R { ok: bool }
 #[deriving(Decodable)] struct ApiErrorList { errors: Vec<ApiError> }
 #[deriving(Decodable)] struct ApiError { detail: String }
+#[deriving(Decodable)] struct Conflict { cksum: String }
 #[deriving(Encodable)] struct OwnersReq<'a> { users: &'a [&'a str] }
 
 impl Registry {
@@ -88,35 +94,40 @@ impl Registry {
         Ok(())
     }
 
-    pub fn publish(&mut self, krate: &NewCrate, tarball: &Path) -> Result<()> {
-        let json = json::encode(krate);
-        // Prepare the body. The format of the upload request is:
-        //
-        //      <le u32 of json>
-        //      <json request> (metadata for the package)
-        //      <le u32 of tarball>
-        //      <source tarball>
-        let stat = try!(fs::stat(tarball).map_err(IoError));
-        let header = {
-            let mut w = MemWriter::new();
-            w.write_le_u32(json.len() as u32).unwrap();
-            w.write_str(json.as_slice()).unwrap();
-            w.write_le_u32(stat.size as u32).unwrap();
-            MemReader::new(w.unwrap())
-        };
+    /// Uploads `tarball`, whose SHA-256 checksum is `cksum`, as the contents
+    /// of `krate`.
+    ///
+    /// The checksum is sent as the idempotency key of the request, so that
+    /// the registry can recognize an upload retried after a dropped
+    /// connection. If the version already exists the registry answers with a
+    /// `409 Conflict` whose body holds the checksum of the existing tarball,
+    /// which is returned as `AlreadyUploaded`.
+    pub fn publish(&mut self, krate: &NewCrate, tarball: &Path,
+                   cksum: &str) -> Result<()> {
+        let (header, size) = try!(publish_header(krate, tarball));
+        let header = MemReader::new(header);
         let tarball = try!(File::open(tarball).map_err(IoError));
-        let size = stat.size as uint + header.get_ref().len();
         let mut body = ChainedReader::new(vec![box header as Box<Reader>,
                                                box tarball as Box<Reader>].into_iter());
 
         let url = format!("{}/api/v1/crates/new", self.host);
-        let response = handle(self.handle.put(url, &mut body)
-                                         .content_length(size)
-                                         .header("Authorization",
-                                                 self.token.as_slice())
-                                         .header("Accept", "application/json")
-                                         .exec());
-        let _body = try!(response);
+        let response = try!(self.handle.put(url, &mut body)
+                                       .content_length(size)
+                                       .header("Authorization",
+                                               self.token.as_slice())
+                                       .header("Accept", "application/json")
+                                       .header("Idempotency-Key", cksum)
+                                       .exec().map_err(CurlError));
+        if response.get_code() == 409 {
+            let conflict = str::from_utf8(response.get_body()).and_then(|b| {
+                json::decode::<Conflict>(b).ok()
+            });
+            match conflict {
+                Some(conflict) => return Err(AlreadyUploaded(conflict.cksum)),
+                None => {}
+            }
+        }
+        let _body = try!(handle(Ok(response)));
         Ok(())
     }
 
@@ -155,6 +166,27 @@ impl Registry {
     }
 }
 
+/// Prepares the body of an upload request, returning everything which precedes
+/// the tarball along with the size of the entire body. The format of the
+/// upload request is:
+///
+///      <le u32 of json>
+///      <json request> (metadata for the package)
+///      <le u32 of tarball>
+///      <source tarball>
+pub fn publish_header(krate: &NewCrate, tarball: &Path)
+                      -> Result<(Vec<u8>, uint)> {
+    let json = json::encode(krate);
+    let stat = try!(fs::stat(tarball).map_err(IoError));
+    let mut w = MemWriter::new();
+    w.write_le_u32(json.len() as u32).unwrap();
+    w.write_str(json.as_slice()).unwrap();
+    w.write_le_u32(stat.size as u32).unwrap();
+    let header = w.unwrap();
+    let size = stat.size as uint + header.len();
+    Ok((header, size))
+}
+
 fn handle(response: result::Result<http::Response, curl::ErrCode>)
           -> Result<String> {
     let response = try!(response.map_err(CurlError));
@@ -192,6 +224,10 @@ impl fmt::Show for Error {
             }
             Unauthorized => write!(f, "unauthorized API access"),
             IoError(ref e) => write!(f, "io error: {}", e),
+            AlreadyUploaded(ref cksum) => {
+                write!(f, "the version was already uploaded with the checksum \
+                           {}", cksum)
+            }
         }
     }
 }
@@ -349,3 +349,46 @@ fn resolving_cycle() {
         dep_req("foo", "1"),
     ], &mut reg);
 }
+
+#[test]
+fn resolving_but_no_matching_version() {
+    let mut reg = registry(vec!(
+        pkg!(("foo", "1.0.0")),
+        pkg!(("foo", "1.1.0")),
+    ));
+
+    let res = resolve(pkg_id("root"), vec![
+        dep_req("foo", "2"),
+    ], &mut reg);
+
+    assert_eq!(res.to_string().as_slice(), "Err(\
+no matching version of `foo` found (required by `root`)
+location searched: registry http://example.com/
+version required: ^2
+versions found: 1.0.0, 1.1.0\
+)");
+}
+
+#[test]
+fn resolving_conflict_explains_requirements() {
+    let mut reg = registry(vec!(
+        pkg!(("a", "1.0.0") => [dep_req("shared", "=1.0.0")]),
+        pkg!(("b", "1.0.0") => [dep_req("shared", "^1.0")]),
+        pkg!(("c", "1.0.0") => [dep_req("shared", "=1.1.0")]),
+        pkg!(("d", "1.0.0") => [dep("c")]),
+        pkg!(("shared", "1.0.0")),
+        pkg!(("shared", "1.1.0")),
+    ));
+
+    let res = resolve(pkg_id("root"), vec![dep("a"), dep("b"), dep("d")],
+                      &mut reg);
+
+    assert_eq!(res.to_string().as_slice(), "Err(\
+failed to select a version for `shared` (required by `c`):
+all possible versions conflict with previously selected versions of `shared`
+  required by: root v1.0.0 -> d v1.0.0 (^1.0.0) -> c v1.0.0 (^1.0.0) -> shared (=1.1.0)
+  version 1.0.0 in use by: root v1.0.0 -> a v1.0.0 (^1.0.0) -> shared v1.0.0 (=1.0.0)
+  version 1.0.0 in use by: root v1.0.0 -> b v1.0.0 (^1.0.0) -> shared v1.0.0 (^1.0)
+  possible versions to select: 1.1.0\
+)");
+}
@@ -101,7 +101,8 @@ impl ProjectBuilder {
     pub fn url(&self) -> Url { path2url(self.root()) }
 
     pub fn bin(&self, b: &str) -> Path {
-        self.build_dir().join(format!("{}{}", b, os::consts::EXE_SUFFIX))
+        self.build_dir().join("debug").join(format!("{}{}", b,
+                                                    os::consts::EXE_SUFFIX))
     }
 
     pub fn release_bin(&self, b: &str) -> Path {
@@ -109,8 +110,8 @@ impl ProjectBuilder {
     }
 
     pub fn target_bin(&self, target: &str, b: &str) -> Path {
-        self.build_dir().join(target).join(format!("{}{}", b,
-                                                   os::consts::EXE_SUFFIX))
+        self.build_dir().join(target).join("debug")
+            .join(format!("{}{}", b, os::consts::EXE_SUFFIX))
     }
 
     pub fn build_dir(&self) -> Path {
@@ -516,3 +517,7 @@ pub static PACKAGING:   &'static str = "   Packaging";
 pub static DOWNLOADING: &'static str = " Downloading";
 pub static UPLOADING:   &'static str = "   Uploading";
 pub static VERIFYING:   &'static str = "   Verifying";
+pub static STRIPPING:   &'static str = "   Stripping";
+pub static ADDING:      &'static str = "      Adding";
+pub static REMOVING:    &'static str = "    Removing";
+pub static USING:       &'static str = "       Using";
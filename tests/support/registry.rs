@@ -47,8 +47,13 @@ pub fn mock_archive(name: &str, version: &str, deps: &[(&str, &str)]) {
             version = "{}"
         "#, dep, req).as_slice());
     }
+    mock_archive_manifest(name, version, manifest.as_slice());
+}
+
+/// Like `mock_archive`, but the archive's `Cargo.toml` is `manifest`.
+pub fn mock_archive_manifest(name: &str, version: &str, manifest: &str) {
     let p = project(name)
-        .file("Cargo.toml", manifest.as_slice())
+        .file("Cargo.toml", manifest)
         .file("src/lib.rs", "");
     p.build();
 
@@ -77,13 +82,18 @@ pub fn mock_pkg_yank(name: &str, version: &str, deps: &[(&str, &str)],
     let c = File::open(&mock_archive_dst(name, version)).read_to_end().unwrap();
     let line = pkg(name, version, deps, cksum(c.as_slice()).as_slice(), yanked);
 
-    let file = match name.len() {
+    publish(index_file(name).as_slice(), line.as_slice());
+}
+
+/// The path of the file listing the versions of `name`, relative to the root
+/// of an index.
+pub fn index_file(name: &str) -> String {
+    match name.len() {
         1 => format!("1/{}", name),
         2 => format!("2/{}", name),
         3 => format!("3/{}/{}", name.slice_to(1), name),
         _ => format!("{}/{}/{}", name.slice(0, 2), name.slice(2, 4), name),
-    };
-    publish(file.as_slice(), line.as_slice());
+    }
 }
 
 pub fn publish(file: &str, line: &str) {
@@ -72,3 +72,26 @@ test!(find_closest_dont_correct_nonsense {
 "));
 })
 
+
+#[cfg(unix)]
+test!(yes_reaches_external_subcommands {
+    let proj = project("external-yes");
+    let proj = fake_executable(proj, &Path::new("path-test"), "cargo-echo-yes");
+    let script = proj.root().join("path-test/cargo-echo-yes");
+    fs::File::create(&script).write_str("#!/bin/sh\n\
+                                         echo \"yes=$CARGO_ASSUME_YES\"\n")
+                             .assert();
+
+    let mut path = new_path();
+    path.push(proj.root().join("path-test"));
+    let path = os::join_paths(path.as_slice()).unwrap();
+    let pr = process(cargo_dir().join("cargo")).cwd(proj.root())
+                    .env("HOME", Some(paths::home()))
+                    .env("CARGO_ASSUME_YES", None::<&str>)
+                    .env("PATH", Some(path.as_slice()));
+
+    assert_that(pr.clone().arg("echo-yes"),
+                execs().with_status(0).with_stdout("yes=\n"));
+    assert_that(pr.arg("--yes").arg("echo-yes"),
+                execs().with_status(0).with_stdout("yes=1\n"));
+})
@@ -0,0 +1,102 @@
+use std::io::File;
+
+use support::{project, execs, cargo_dir, ResultTest};
+use support::ADDING;
+use hamcrest::assert_that;
+
+fn setup() {
+}
+
+static MANIFEST: &'static str = r#"
+    # A package which is about to grow some dependencies
+    [package]
+    name = "foo"
+    version = "0.0.1"     # bumped on release
+    authors = []
+
+    [dependencies]
+    # used for everything
+    bar = "0.1"
+
+    [features]
+    default = []
+"#;
+
+test!(add_dependency {
+    let p = project("foo")
+        .file("Cargo.toml", MANIFEST)
+        .file("src/lib.rs", "");
+
+    assert_that(p.cargo_process("add").arg("baz").arg("--vers").arg("0.2"),
+                execs().with_status(0).with_stdout(format!("\
+{adding} baz 0.2 to dependencies
+", adding = ADDING).as_slice()));
+
+    let contents = File::open(&p.root().join("Cargo.toml")).read_to_string();
+    assert_eq!(contents.assert(),
+               MANIFEST.replace("    bar = \"0.1\"\n",
+                                "    bar = \"0.1\"\nbaz = \"0.2\"\n"));
+})
+
+test!(add_dev_dependency {
+    let p = project("foo")
+        .file("Cargo.toml", MANIFEST)
+        .file("src/lib.rs", "");
+
+    assert_that(p.cargo_process("add").arg("baz").arg("--dev"),
+                execs().with_status(0).with_stdout(format!("\
+{adding} baz * to dev-dependencies
+", adding = ADDING).as_slice()));
+
+    let contents = File::open(&p.root().join("Cargo.toml")).read_to_string();
+    assert_eq!(contents.assert(),
+               format!("{}\n[dev-dependencies]\nbaz = \"*\"\n", MANIFEST));
+})
+
+test!(update_dependency {
+    let p = project("foo")
+        .file("Cargo.toml", MANIFEST)
+        .file("src/lib.rs", "");
+
+    assert_that(p.cargo_process("add").arg("bar").arg("--vers").arg(">= 0.2"),
+                execs().with_status(0));
+
+    let contents = File::open(&p.root().join("Cargo.toml")).read_to_string();
+    assert_eq!(contents.assert(),
+               MANIFEST.replace("bar = \"0.1\"", "bar = \">= 0.2\""));
+})
+
+test!(invalid_version_requirement {
+    let p = project("foo")
+        .file("Cargo.toml", MANIFEST)
+        .file("src/lib.rs", "");
+
+    assert_that(p.cargo_process("add").arg("baz").arg("--vers").arg("one"),
+                execs().with_status(1).with_stderr("\
+invalid version requirement `one`: [..]
+"));
+
+    let contents = File::open(&p.root().join("Cargo.toml")).read_to_string();
+    assert_eq!(contents.assert().as_slice(), MANIFEST);
+})
+
+test!(set_version {
+    let p = project("foo")
+        .file("Cargo.toml", MANIFEST)
+        .file("src/lib.rs", "");
+
+    assert_that(p.cargo_process("add").arg("--set-version").arg("0.1.0"),
+                execs().with_status(0).with_stdout("\
+    Updating package version to 0.1.0
+"));
+
+    let contents = File::open(&p.root().join("Cargo.toml")).read_to_string();
+    assert_eq!(contents.assert(),
+               MANIFEST.replace("version = \"0.0.1\"", "version = \"0.1.0\""));
+
+    assert_that(p.process(cargo_dir().join("cargo")).arg("add")
+                 .arg("--set-version").arg("1.0"),
+                execs().with_status(1).with_stderr("\
+invalid version `1.0`: [..]
+"));
+})
@@ -38,6 +38,7 @@ test!(cargo_bench_simple {
     assert_that(p.process(cargo_dir().join("cargo")).arg("bench"),
         execs().with_stdout(format!("\
 {} foo v0.5.0 ({})
+    Finished [..]
 {} target[..]release[..]foo-[..]
 
 running 1 test
@@ -45,6 +46,9 @@ test bench_hello ... bench:         0 ns/iter (+/- 0)
 
 test result: ok. 0 passed; 0 failed; 0 ignored; 1 measured
 
+
+     Summary PASS, all 1 test targets passed
+      Passed [..] (1 passed; 0 failed; 0 ignored)
 ",
         COMPILING, p.url(),
         RUNNING)));
@@ -75,6 +79,7 @@ test!(bench_target_name {
 
     let expected_stdout = format!("\
 {compiling} foo v0.0.1 ({dir})
+    Finished [..]
 {runnning} target[..]release[..]bin2[..]
 
 running 1 test
@@ -82,6 +87,9 @@ test run2 ... bench:         0 ns/iter (+/- 0)
 
 test result: ok. 0 passed; 0 failed; 0 ignored; 1 measured
 
+
+     Summary PASS, all 1 test targets passed
+      Passed bin2 (1 passed; 0 failed; 0 ignored)
 ",
        compiling = COMPILING,
        runnning = RUNNING,
@@ -103,7 +111,8 @@ test!(cargo_bench_verbose {
     assert_that(p.cargo_process("bench").arg("-v").arg("hello"),
         execs().with_stdout(format!("\
 {compiling} foo v0.5.0 ({url})
-{running} `rustc src[..]foo.rs [..]`
+{running} `[..]rustc src[..]foo.rs [..]`
+    Finished [..]
 {running} `[..]target[..]release[..]foo-[..] hello --bench`
 
 running 1 test
@@ -111,6 +120,9 @@ test bench_hello ... bench:         0 ns/iter (+/- 0)
 
 test result: ok. 0 passed; 0 failed; 0 ignored; 1 measured
 
+
+     Summary PASS, all 1 test targets passed
+      Passed [..] (1 passed; 0 failed; 0 ignored)
 ",
         compiling = COMPILING, url = p.url(), running = RUNNING)));
 })
@@ -175,17 +187,21 @@ test!(cargo_bench_failing_test {
     assert_that(p.process(cargo_dir().join("cargo")).arg("bench"),
         execs().with_stdout(format!("\
 {} foo v0.5.0 ({})
+    Finished [..]
 {} target[..]release[..]foo-[..]
 
 running 1 test
-test bench_hello ... ",
+test bench_hello ... 
+     Summary FAIL, 1 of 1 test targets failed
+      Failed foo (0 passed; 1 failed; 0 ignored)
+",
         COMPILING, p.url(), RUNNING))
               .with_stderr(format!("\
 task '<main>' panicked at 'assertion failed: \
     `(left == right) && (right == left)` (left: \
     `hello`, right: `nope`)', src{sep}foo.rs:13
 ", sep = path::SEP))
-              .with_status(101));
+              .with_status(2));
 })
 
 test!(bench_with_lib_dep {
@@ -226,6 +242,7 @@ test!(bench_with_lib_dep {
     assert_that(p.cargo_process("bench"),
         execs().with_stdout(format!("\
 {} foo v0.0.1 ({})
+    Finished [..]
 {running} target[..]release[..]baz-[..]
 
 running 1 test
@@ -240,6 +257,10 @@ test lib_bench ... bench:         0 ns/iter (+/- 0)
 
 test result: ok. 0 passed; 0 failed; 0 ignored; 1 measured
 
+
+     Summary PASS, all 2 test targets passed
+      Passed [..] (1 passed; 0 failed; 0 ignored)
+      Passed [..] (1 passed; 0 failed; 0 ignored)
 ",
         COMPILING, p.url(), running = RUNNING)))
 })
@@ -285,6 +306,7 @@ test!(bench_with_deep_lib_dep {
                        .with_stdout(format!("\
 {compiling} foo v0.0.1 ({dir})
 {compiling} bar v0.0.1 ({dir})
+    Finished [..]
 {running} target[..]
 
 running 1 test
@@ -292,6 +314,9 @@ test bar_bench ... bench:         0 ns/iter (+/- 0)
 
 test result: ok. 0 passed; 0 failed; 0 ignored; 1 measured
 
+
+     Summary PASS, all 1 test targets passed
+      Passed [..] (1 passed; 0 failed; 0 ignored)
 ",
                        compiling = COMPILING, running = RUNNING,
                        dir = p.url()).as_slice()));
@@ -327,6 +352,7 @@ test!(external_bench_explicit {
     assert_that(p.cargo_process("bench"),
         execs().with_stdout(format!("\
 {} foo v0.0.1 ({})
+    Finished [..]
 {running} target[..]release[..]bench-[..]
 
 running 1 test
@@ -341,6 +367,10 @@ test internal_bench ... bench:         0 ns/iter (+/- 0)
 
 test result: ok. 0 passed; 0 failed; 0 ignored; 1 measured
 
+
+     Summary PASS, all 2 test targets passed
+      Passed [..] (1 passed; 0 failed; 0 ignored)
+      Passed [..] (1 passed; 0 failed; 0 ignored)
 ",
         COMPILING, p.url(), running = RUNNING)))
 })
@@ -372,6 +402,7 @@ test!(external_bench_implicit {
     assert_that(p.cargo_process("bench"),
         execs().with_stdout(format!("\
 {} foo v0.0.1 ({})
+    Finished [..]
 {running} target[..]release[..]external-[..]
 
 running 1 test
@@ -386,6 +417,10 @@ test internal_bench ... bench:         0 ns/iter (+/- 0)
 
 test result: ok. 0 passed; 0 failed; 0 ignored; 1 measured
 
+
+     Summary PASS, all 2 test targets passed
+      Passed [..] (1 passed; 0 failed; 0 ignored)
+      Passed [..] (1 passed; 0 failed; 0 ignored)
 ",
         COMPILING, p.url(), running = RUNNING)))
 })
@@ -426,6 +461,7 @@ test!(pass_through_command_line {
                 execs().with_status(0)
                        .with_stdout(format!("\
 {compiling} foo v0.0.1 ({dir})
+    Finished [..]
 {running} target[..]release[..]foo-[..]
 
 running 1 test
@@ -433,6 +469,9 @@ test bar ... bench:         0 ns/iter (+/- 0)
 
 test result: ok. 0 passed; 0 failed; 0 ignored; 1 measured
 
+
+     Summary PASS, all 1 test targets passed
+      Passed [..] (1 passed; 0 failed; 0 ignored)
 ",
                        compiling = COMPILING, running = RUNNING,
                        dir = p.url()).as_slice()));
@@ -441,6 +480,7 @@ test result: ok. 0 passed; 0 failed; 0 ignored; 1 measured
                 execs().with_status(0)
                        .with_stdout(format!("\
 {compiling} foo v0.0.1 ({dir})
+    Finished [..]
 {running} target[..]release[..]foo-[..]
 
 running 1 test
@@ -448,6 +488,9 @@ test foo ... bench:         0 ns/iter (+/- 0)
 
 test result: ok. 0 passed; 0 failed; 0 ignored; 1 measured
 
+
+     Summary PASS, all 1 test targets passed
+      Passed [..] (1 passed; 0 failed; 0 ignored)
 ",
                        compiling = COMPILING, running = RUNNING,
                        dir = p.url()).as_slice()));
@@ -503,6 +546,7 @@ test!(lib_bin_same_name {
     assert_that(p.cargo_process("bench"),
         execs().with_stdout(format!("\
 {} foo v0.0.1 ({})
+    Finished [..]
 {running} target[..]release[..]foo-[..]
 
 running 1 test
@@ -517,6 +561,10 @@ test [..] ... bench:         0 ns/iter (+/- 0)
 
 test result: ok. 0 passed; 0 failed; 0 ignored; 1 measured
 
+
+     Summary PASS, all 2 test targets passed
+      Passed [..] (1 passed; 0 failed; 0 ignored)
+      Passed [..] (1 passed; 0 failed; 0 ignored)
 ",
         COMPILING, p.url(), running = RUNNING)))
 })
@@ -552,6 +600,7 @@ test!(lib_with_standard_name {
                 execs().with_status(0)
                        .with_stdout(format!("\
 {compiling} syntax v0.0.1 ({dir})
+    Finished [..]
 {running} target[..]release[..]bench-[..]
 
 running 1 test
@@ -566,6 +615,10 @@ test foo_bench ... bench:         0 ns/iter (+/- 0)
 
 test result: ok. 0 passed; 0 failed; 0 ignored; 1 measured
 
+
+     Summary PASS, all 2 test targets passed
+      Passed [..] (1 passed; 0 failed; 0 ignored)
+      Passed [..] (1 passed; 0 failed; 0 ignored)
 ",
                        compiling = COMPILING, running = RUNNING,
                        dir = p.url()).as_slice()));
@@ -601,6 +654,7 @@ test!(lib_with_standard_name2 {
                 execs().with_status(0)
                        .with_stdout(format!("\
 {compiling} syntax v0.0.1 ({dir})
+    Finished [..]
 {running} target[..]release[..]syntax-[..]
 
 running 1 test
@@ -608,6 +662,9 @@ test bench ... bench:         0 ns/iter (+/- 0)
 
 test result: ok. 0 passed; 0 failed; 0 ignored; 1 measured
 
+
+     Summary PASS, all 1 test targets passed
+      Passed [..] (1 passed; 0 failed; 0 ignored)
 ",
                        compiling = COMPILING, running = RUNNING,
                        dir = p.url()).as_slice()));
@@ -701,6 +758,7 @@ test!(bench_dylib {
 {running} [..] --opt-level 3 [..]
 {running} [..] --opt-level 3 [..]
 {running} [..] --opt-level 3 [..]
+    Finished [..]
 {running} [..]target[..]release[..]bench-[..]
 
 running 1 test
@@ -715,6 +773,10 @@ test foo ... bench:         0 ns/iter (+/- 0)
 
 test result: ok. 0 passed; 0 failed; 0 ignored; 1 measured
 
+
+     Summary PASS, all 2 test targets passed
+      Passed [..] (1 passed; 0 failed; 0 ignored)
+      Passed [..] (1 passed; 0 failed; 0 ignored)
 ",
                        compiling = COMPILING, running = RUNNING,
                        dir = p.url()).as_slice()));
@@ -724,6 +786,7 @@ test result: ok. 0 passed; 0 failed; 0 ignored; 1 measured
                        .with_stdout(format!("\
 {fresh} bar v0.0.1 ({dir})
 {fresh} foo v0.0.1 ({dir})
+    Finished [..]
 {running} [..]target[..]release[..]bench-[..]
 
 running 1 test
@@ -738,6 +801,10 @@ test foo ... bench:         0 ns/iter (+/- 0)
 
 test result: ok. 0 passed; 0 failed; 0 ignored; 1 measured
 
+
+     Summary PASS, all 2 test targets passed
+      Passed [..] (1 passed; 0 failed; 0 ignored)
+      Passed [..] (1 passed; 0 failed; 0 ignored)
 ",
                        fresh = FRESH, running = RUNNING,
                        dir = p.url()).as_slice()));
@@ -762,6 +829,7 @@ test!(bench_twice_with_build_cmd {
                 execs().with_status(0)
                        .with_stdout(format!("\
 {compiling} foo v0.0.1 ({dir})
+    Finished [..]
 {running} target[..]release[..]foo-[..]
 
 running 1 test
@@ -769,6 +837,9 @@ test foo ... bench:         0 ns/iter (+/- 0)
 
 test result: ok. 0 passed; 0 failed; 0 ignored; 1 measured
 
+
+     Summary PASS, all 1 test targets passed
+      Passed [..] (1 passed; 0 failed; 0 ignored)
 ",
                        compiling = COMPILING, running = RUNNING,
                        dir = p.url()).as_slice()));
@@ -776,6 +847,7 @@ test result: ok. 0 passed; 0 failed; 0 ignored; 1 measured
     assert_that(p.process(cargo_dir().join("cargo")).arg("bench"),
                 execs().with_status(0)
                        .with_stdout(format!("\
+    Finished [..]
 {running} target[..]release[..]foo-[..]
 
 running 1 test
@@ -783,6 +855,170 @@ test foo ... bench:         0 ns/iter (+/- 0)
 
 test result: ok. 0 passed; 0 failed; 0 ignored; 1 measured
 
+
+     Summary PASS, all 1 test targets passed
+      Passed [..] (1 passed; 0 failed; 0 ignored)
 ",
                        running = RUNNING)));
 })
+
+test!(bench_no_harness {
+    let p = project("foo")
+        .file("Cargo.toml", r#"
+            [package]
+            name = "foo"
+            version = "0.0.1"
+            authors = []
+
+            [[bench]]
+            name = "custom"
+            path = "benches/custom.rs"
+            harness = false
+        "#)
+        .file("src/lib.rs", "")
+        .file("benches/custom.rs", r#"
+            use std::os;
+            fn main() {
+                let args = os::args();
+                println!("args: {}", args.slice_from(1));
+                if args.iter().any(|a| a.as_slice() == "fail") {
+                    os::set_exit_status(3);
+                }
+            }
+        "#);
+
+    assert_that(p.cargo_process("bench").arg("--").arg("quick"),
+                execs().with_status(0)
+                       .with_stdout(format!("\
+{compiling} foo v0.0.1 ({dir})
+    Finished [..]
+{running} target[..]custom-[..]
+args: [quick]
+
+     Summary PASS, all 1 test targets passed
+      Passed custom (1 passed; 0 failed; 0 ignored)
+",
+                       compiling = COMPILING, running = RUNNING,
+                       dir = p.url()).as_slice()));
+
+    assert_that(p.process(cargo_dir().join("cargo")).arg("bench")
+                 .arg("--").arg("fail"),
+                execs().with_status(101)
+                       .with_stdout(format!("\
+    Finished [..]
+{running} target[..]custom-[..]
+args: [fail]
+
+[..]Summary FAIL, 1 of 1 test targets failed
+[..]Failed custom (0 passed; 1 failed; 0 ignored)
+",
+                       running = RUNNING).as_slice()));
+})
+
+test!(autobenches_false_skips_benches_dir {
+    let p = project("foo")
+        .file("Cargo.toml", r#"
+            [package]
+            name = "foo"
+            version = "0.0.1"
+            authors = []
+            autobenches = false
+        "#)
+        .file("src/lib.rs", r#"
+            extern crate test;
+
+            #[bench]
+            fn bench_lib(_b: &mut test::Bencher) {}
+        "#)
+        .file("benches/wip.rs", "this doesn't compile yet");
+
+    assert_that(p.cargo_process("bench"),
+                execs().with_status(0).with_stdout(format!("\
+{compiling} foo v0.0.1 ({dir})
+    Finished [..]
+{running} target[..]release[..]foo-[..]
+
+running 1 test
+test bench_lib ... bench:         0 ns/iter (+/- 0)
+
+test result: ok. 0 passed; 0 failed; 0 ignored; 1 measured
+
+
+     Summary PASS, all 1 test targets passed
+      Passed [..] (1 passed; 0 failed; 0 ignored)
+",
+                       compiling = COMPILING, running = RUNNING,
+                       dir = p.url()).as_slice()));
+})
+
+test!(lib_bench_false_leaves_nothing_to_run {
+    let p = project("foo")
+        .file("Cargo.toml", r#"
+            [package]
+            name = "foo"
+            version = "0.0.1"
+            authors = []
+            autobenches = false
+
+            [lib]
+            name = "foo"
+            bench = false
+        "#)
+        .file("src/lib.rs", r#"
+            extern crate test;
+
+            #[bench]
+            fn bench_lib(_b: &mut test::Bencher) {}
+        "#)
+        .file("benches/wip.rs", "this doesn't compile yet");
+
+    assert_that(p.cargo_process("bench"),
+                execs().with_status(0)
+                       .with_stderr("no benchmarks to run, no target of `foo` \
+                                     is benchmarked\n"));
+})
+
+test!(bin_bench_false_leaves_nothing_to_run {
+    let p = project("foo")
+        .file("Cargo.toml", r#"
+            [package]
+            name = "foo"
+            version = "0.0.1"
+            authors = []
+
+            [[bin]]
+            name = "foo"
+            bench = false
+        "#)
+        .file("src/main.rs", r#"
+            extern crate test;
+
+            fn main() {}
+
+            #[bench]
+            fn bench_bin(_b: &mut test::Bencher) {}
+        "#);
+
+    assert_that(p.cargo_process("bench"),
+                execs().with_status(0)
+                       .with_stderr("no benchmarks to run, no target of `foo` \
+                                     is benchmarked\n"));
+})
+
+test!(bench_name_matching_nothing {
+    let p = project("foo")
+        .file("Cargo.toml", basic_bin_manifest("foo").as_slice())
+        .file("src/foo.rs", r#"
+            extern crate test;
+
+            fn main() {}
+
+            #[bench]
+            fn bench_bin(_b: &mut test::Bencher) {}
+        "#);
+
+    assert_that(p.cargo_process("bench").arg("--name").arg("nope"),
+                execs().with_status(0)
+                       .with_stderr("no benchmarks to run, no benchmark \
+                                     matches the filter `nope`\n"));
+})
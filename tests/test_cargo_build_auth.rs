@@ -105,7 +105,7 @@ test!(http_auth_offered {
         .file("src/main.rs", "");
 
     assert_that(p.cargo_process("build").arg("-v"),
-                execs().with_status(101).with_stdout(format!("\
+                execs().with_status(4).with_stdout(format!("\
 {updating} git repository `http://{addr}/foo/bar`
 ",
         updating = UPDATING,
@@ -152,7 +152,7 @@ test!(https_something_happens {
         .file("src/main.rs", "");
 
     assert_that(p.cargo_process("build").arg("-v"),
-                execs().with_status(101).with_stdout(format!("\
+                execs().with_status(4).with_stdout(format!("\
 {updating} git repository `https://{addr}/foo/bar`
 ",
         updating = UPDATING,
@@ -205,7 +205,7 @@ test!(ssh_something_happens {
         .file("src/main.rs", "");
 
     assert_that(p.cargo_process("build").arg("-v"),
-                execs().with_status(101).with_stdout(format!("\
+                execs().with_status(4).with_stdout(format!("\
 {updating} git repository `ssh://{addr}/foo/bar`
 ",
         updating = UPDATING,
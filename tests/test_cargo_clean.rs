@@ -1,5 +1,5 @@
 use support::{project, execs, main_file, basic_bin_manifest, cargo_dir};
-use hamcrest::{assert_that, existing_dir, is_not};
+use hamcrest::{assert_that, existing_dir, existing_file, is_not};
 
 fn setup() {
 }
@@ -31,3 +31,68 @@ test!(different_dir {
                 execs().with_status(0).with_stdout(""));
     assert_that(&p.build_dir(), is_not(existing_dir()));
 })
+
+test!(clean_removes_doc_dir {
+    let p = project("foo")
+              .file("Cargo.toml", basic_bin_manifest("foo").as_slice())
+              .file("src/foo.rs", main_file(r#""i am foo""#, []).as_slice())
+              .file("doc/index.html", "");
+
+    assert_that(p.cargo_process("build"), execs().with_status(0));
+
+    assert_that(p.process(cargo_dir().join("cargo")).arg("clean"),
+                execs().with_status(0));
+    assert_that(&p.build_dir(), is_not(existing_dir()));
+    assert_that(&p.root().join("doc"), is_not(existing_dir()));
+})
+
+test!(clean_release {
+    let p = project("foo")
+              .file("Cargo.toml", basic_bin_manifest("foo").as_slice())
+              .file("src/foo.rs", main_file(r#""i am foo""#, []).as_slice());
+
+    assert_that(p.cargo_process("build"), execs().with_status(0));
+    assert_that(p.process(cargo_dir().join("cargo")).arg("build")
+                 .arg("--release"),
+                execs().with_status(0));
+    assert_that(&p.release_bin("foo"), existing_file());
+
+    assert_that(p.process(cargo_dir().join("cargo")).arg("clean")
+                 .arg("--release"),
+                execs().with_status(0));
+    assert_that(&p.build_dir().join("release"), is_not(existing_dir()));
+    assert_that(&p.bin("foo"), existing_file());
+})
+
+test!(clean_doc {
+    let p = project("foo")
+        .file("Cargo.toml", r#"
+            [package]
+            name = "foo"
+            version = "0.0.1"
+            authors = []
+        "#)
+        .file("src/lib.rs", "pub fn foo() {}");
+
+    assert_that(p.cargo_process("build"), execs().with_status(0));
+    assert_that(p.process(cargo_dir().join("cargo")).arg("doc"),
+                execs().with_status(0));
+    assert_that(&p.build_dir().join("doc").join("foo"), existing_dir());
+
+    assert_that(p.process(cargo_dir().join("cargo")).arg("clean")
+                 .arg("--doc"),
+                execs().with_status(0));
+    assert_that(&p.build_dir().join("doc"), is_not(existing_dir()));
+    assert_that(&p.build_dir(), existing_dir());
+})
+
+test!(clean_doc_and_release_conflict {
+    let p = project("foo")
+              .file("Cargo.toml", basic_bin_manifest("foo").as_slice())
+              .file("src/foo.rs", main_file(r#""i am foo""#, []).as_slice());
+
+    assert_that(p.cargo_process("clean").arg("--doc").arg("--release"),
+                execs().with_status(1).with_stderr("\
+--doc and --release cannot be specified together
+"));
+})
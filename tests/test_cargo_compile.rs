@@ -1,11 +1,14 @@
-use std::io::{mod, fs, TempDir, File};
+use std::io::{mod, fs, TempDir, File, MemWriter};
 use std::os;
 use std::path;
+use std::io::fs::PathExtensions;
+use std::str;
+use term::TerminfoTerminal;
 
 use support::{ResultTest, project, execs, main_file, basic_bin_manifest};
-use support::{COMPILING, RUNNING, cargo_dir, ProjectBuilder};
-use hamcrest::{assert_that, existing_file};
-use support::paths::PathExt;
+use support::{COMPILING, RUNNING, FRESH, cargo_dir, ProjectBuilder};
+use hamcrest::{assert_that, existing_file, is_not};
+use support::paths::{mod, PathExt};
 use cargo;
 use cargo::util::process;
 
@@ -43,7 +46,7 @@ test!(cargo_compile_with_invalid_manifest {
 
     assert_that(p.cargo_process("build"),
         execs()
-        .with_status(101)
+        .with_status(3)
         .with_stderr("Cargo.toml is not a valid manifest\n\n\
                       No `package` or `project` section found.\n"))
 })
@@ -57,7 +60,7 @@ test!(cargo_compile_with_invalid_manifest2 {
 
     assert_that(p.cargo_process("build"),
         execs()
-        .with_status(101)
+        .with_status(3)
         .with_stderr("could not parse input TOML\n\
                       Cargo.toml:3:19-3:20 expected a value\n\n"))
 })
@@ -75,11 +78,47 @@ test!(cargo_compile_with_invalid_manifest3 {
     assert_that(p.cargo_process("build").arg("--manifest-path")
                  .arg("src/Cargo.toml"),
         execs()
-        .with_status(101)
+        .with_status(3)
         .with_stderr("could not parse input TOML\n\
                       src[..]Cargo.toml:1:5-1:6 expected a value\n\n"))
 })
 
+test!(cargo_compile_with_mistyped_manifest_values {
+    let p = project("foo")
+        .file("Cargo.toml", r#"
+            [package]
+            name = "foo"
+            version = "0.0.1"
+            authors = "me"
+
+            [[bin]]
+            name = "foo"
+            test = "yes"
+
+            [dependencies.bar]
+            version = "*"
+            optional = 1
+            default_features = "no"
+
+            [features]
+            x = "bar"
+        "#)
+        .file("src/main.rs", "fn main() {}");
+
+    assert_that(p.cargo_process("build"),
+        execs()
+        .with_status(3)
+        .with_stderr("\
+Cargo.toml is not a valid manifest
+
+bin.test: expected a boolean, found a string
+dependencies.bar.default_features: expected a boolean, found a string
+dependencies.bar.optional: expected a boolean, found an integer
+features.x: expected an array of strings, found a string
+package.authors: expected an array of strings, found a string
+"))
+})
+
 test!(cargo_compile_with_invalid_version {
     let p = project("foo")
         .file("Cargo.toml", r#"
@@ -91,22 +130,94 @@ test!(cargo_compile_with_invalid_version {
 
     assert_that(p.cargo_process("build"),
                 execs()
-                .with_status(101)
+                .with_status(3)
                 .with_stderr("Cargo.toml is not a valid manifest\n\n\
                               cannot parse '1.0' as a semver for the key \
                               `project.version`\n"))
 
 })
 
+test!(cargo_compile_with_missing_target_path {
+    let p = project("foo")
+        .file("Cargo.toml", r#"
+            [package]
+            name = "foo"
+            version = "0.0.1"
+            authors = []
+
+            [[bin]]
+            name = "tool"
+            path = "src/bin/tool.rs"
+        "#)
+        .file("src/bin/tol.rs", "fn main() {}");
+
+    assert_that(p.cargo_process("build"),
+                execs()
+                .with_status(3)
+                .with_stderr(format!("\
+Cargo.toml is not a valid manifest
+
+can't find bin `tool` at path `src/bin/tool.rs`, the file `{}` does not exist
+", p.root().join("src/bin/tool.rs").display()).as_slice()));
+})
+
 test!(cargo_compile_without_manifest {
     let tmpdir = TempDir::new("cargo").unwrap();
     let p = ProjectBuilder::new("foo", tmpdir.path().clone());
 
+    let mut expected = String::from_str("Could not find Cargo.toml in this \
+                                         directory or any parent directory\n\
+                                         directories searched:\n");
+    let mut dir = p.root();
+    loop {
+        expected.push_str(format!("    {}\n", dir.display()).as_slice());
+        if !dir.pop() { break }
+    }
+    expected.push_str("To create a new package, run `cargo new`\n");
+
     assert_that(p.cargo_process("build"),
         execs()
         .with_status(102)
-        .with_stderr("Could not find Cargo.toml in this directory or any \
-                      parent directory\n"));
+        .with_stderr(expected));
+})
+
+test!(cargo_compile_with_lowercase_manifest {
+    let p = project("foo")
+        .file("cargo.toml", basic_bin_manifest("foo").as_slice())
+        .file("src/foo.rs", main_file(r#""i am foo""#, []).as_slice());
+    p.build();
+
+    if p.root().join("Cargo.toml").exists() {
+        // A case-insensitive filesystem finds the manifest anyway.
+        assert_that(p.process(cargo_dir().join("cargo")).arg("build"),
+                    execs().with_status(0).with_stderr("\
+the manifest of this package is named `cargo.toml`, rename it to `Cargo.toml` \
+so that it can be found on case-sensitive filesystems
+"));
+    } else {
+        assert_that(p.process(cargo_dir().join("cargo")).arg("build"),
+                    execs().with_status(102).with_stderr(format!("\
+Could not find `Cargo.toml` in `{}`, but found `cargo.toml`
+rename it to `Cargo.toml`, manifest file names are case sensitive on most \
+platforms
+", p.root().display())));
+    }
+})
+
+test!(cargo_compile_from_nested_directory {
+    let p = project("foo")
+        .file("Cargo.toml", basic_bin_manifest("foo").as_slice())
+        .file("src/foo.rs", main_file(r#""i am foo""#, []).as_slice())
+        .file("src/deep/dir/.keep", "");
+    p.build();
+
+    assert_that(p.process(cargo_dir().join("cargo")).arg("build")
+                 .cwd(p.root().join("src/deep/dir")),
+        execs()
+        .with_status(0)
+        .with_stdout(format!("{} foo v0.5.0 ({})\n    \
+                              Finished [..]\n", COMPILING, p.url())));
+    assert_that(&p.bin("foo"), existing_file());
 })
 
 test!(cargo_compile_with_invalid_code {
@@ -116,7 +227,7 @@ test!(cargo_compile_with_invalid_code {
 
     assert_that(p.cargo_process("build"),
         execs()
-        .with_status(101)
+        .with_status(2)
         .with_stderr(format!("\
 {filename}:1:1: 1:8 error: expected item[..]found `invalid`
 {filename}:1 invalid rust code!
@@ -150,7 +261,7 @@ test!(cargo_compile_with_invalid_code_in_deps {
         .file("src/lib.rs", "invalid rust code!");
     bar.build();
     baz.build();
-    assert_that(p.cargo_process("build"), execs().with_status(101));
+    assert_that(p.cargo_process("build"), execs().with_status(2));
 })
 
 test!(cargo_compile_with_warnings_in_the_root_package {
@@ -210,7 +321,8 @@ test!(cargo_compile_with_warnings_in_a_dep_package {
     assert_that(p.cargo_process("build"),
         execs()
         .with_stdout(format!("{} bar v0.5.0 ({})\n\
-                              {} foo v0.5.0 ({})\n",
+                              {} foo v0.5.0 ({})\n    \
+                              Finished [..]\n",
                              COMPILING, p.url(),
                              COMPILING, p.url()))
         .with_stderr("\
@@ -500,7 +612,7 @@ test!(cargo_compile_with_dep_name_mismatch {
         .file("bar/src/bar.rs", main_file(r#""i am bar""#, []).as_slice());
 
     assert_that(p.cargo_process("build"),
-                execs().with_status(101).with_stderr(format!(
+                execs().with_status(1).with_stderr(format!(
 r#"no package named `notquitebar` found (required by `foo`)
 location searched: {proj_dir}
 version required: *
@@ -578,7 +690,7 @@ test!(many_crate_types_old_style_lib_location {
     assert_that(p.cargo_process("build"),
                 execs().with_status(0));
 
-    let files = fs::readdir(&p.root().join("target")).assert();
+    let files = fs::readdir(&p.root().join("target/debug")).assert();
     let mut files: Vec<String> = files.iter().filter_map(|f| {
         match f.filename_str().unwrap() {
             "build" | "examples" | "deps" => None,
@@ -616,7 +728,7 @@ test!(many_crate_types_correct {
     assert_that(p.cargo_process("build"),
                 execs().with_status(0));
 
-    let files = fs::readdir(&p.root().join("target")).assert();
+    let files = fs::readdir(&p.root().join("target/debug")).assert();
     let mut files: Vec<String> = files.iter().filter_map(|f| {
         match f.filename_str().unwrap() {
             "build" | "examples" | "deps" => None,
@@ -653,7 +765,10 @@ test!(unused_keys {
         "#);
     assert_that(p.cargo_process("build"),
                 execs().with_status(0)
-                       .with_stderr("unused manifest key: project.bulid\n"));
+                       .with_stderr("\
+unused manifest key: project.bulid
+did you mean `build`?
+"));
 
     let mut p = project("bar");
     p = p
@@ -677,25 +792,205 @@ test!(unused_keys {
                        .with_stderr("unused manifest key: lib.build\n"));
 })
 
+test!(unknown_profile_keys_are_grouped {
+    let p = project("foo")
+        .file("Cargo.toml", r#"
+            [package]
+            name = "foo"
+            version = "0.0.1"
+            authors = []
+
+            [profile.dev]
+            panic = "abort"
+
+            [profile.release]
+            opt-level = 3
+            panic = "abort"
+            lto = true
+        "#)
+        .file("src/lib.rs", "");
+    assert_that(p.cargo_process("build"),
+                execs().with_status(0)
+                       .with_stderr("\
+unused manifest keys in [profile.dev]: panic
+they may be supported by a newer version of cargo, set \
+`manifest.warn-unknown-profile-keys = false` in .cargo/config to silence this \
+warning
+unused manifest keys in [profile.release]: lto, panic
+they may be supported by a newer version of cargo, set \
+`manifest.warn-unknown-profile-keys = false` in .cargo/config to silence this \
+warning
+"));
+})
+
+test!(unknown_profile_keys_can_be_silenced {
+    let p = project("foo")
+        .file("Cargo.toml", r#"
+            [package]
+            name = "foo"
+            version = "0.0.1"
+            authors = []
+
+            [profile.release]
+            lto = true
+        "#)
+        .file("src/lib.rs", "")
+        .file(".cargo/config", r#"
+            [manifest]
+            warn-unknown-profile-keys = false
+        "#);
+    assert_that(p.cargo_process("build"),
+                execs().with_status(0).with_stderr(""));
+})
+
+test!(misspelled_profile_keys_are_not_grouped {
+    let p = project("foo")
+        .file("Cargo.toml", r#"
+            [package]
+            name = "foo"
+            version = "0.0.1"
+            authors = []
+
+            [profile.release]
+            opt_levle = 3
+            lto = true
+        "#)
+        .file("src/lib.rs", "")
+        .file(".cargo/config", r#"
+            [manifest]
+            warn-unknown-profile-keys = false
+        "#);
+    assert_that(p.cargo_process("build"),
+                execs().with_status(0)
+                       .with_stderr("\
+unused manifest key: profile.release.opt_levle
+did you mean `opt-level`?
+"));
+})
+
+test!(unknown_profile_keys_use_the_config_of_the_manifest {
+    let p = project("foo")
+        .file("Cargo.toml", r#"
+            [package]
+            name = "foo"
+            version = "0.0.1"
+            authors = []
+
+            [profile.release]
+            lto = true
+        "#)
+        .file("src/lib.rs", "")
+        .file(".cargo/config", r#"
+            [manifest]
+            warn-unknown-profile-keys = false
+        "#);
+    p.build();
+    assert_that(p.process(cargo_dir().join("cargo")).arg("build")
+                 .arg("--manifest-path").arg(p.root().join("Cargo.toml"))
+                 .cwd(paths::root()),
+                execs().with_status(0).with_stderr(""));
+})
+
+test!(package_metadata_is_not_unused {
+    let p = project("foo")
+        .file("Cargo.toml", r#"
+            [package]
+            name = "foo"
+            version = "0.0.1"
+            authors = []
+
+            [package.metadata.some-tool]
+            setting = 1
+            list = ["a", "b"]
+        "#)
+        .file("src/lib.rs", "");
+    assert_that(p.cargo_process("build"),
+                execs().with_status(0).with_stderr(""));
+})
+
+test!(per_target_features_are_rejected {
+    let p = project("foo")
+        .file("Cargo.toml", r#"
+            [package]
+            name = "foo"
+            version = "0.0.1"
+            authors = []
+
+            [features]
+            cli = []
+
+            [[bin]]
+            name = "foo"
+            features = ["cli"]
+        "#)
+        .file("src/main.rs", "fn main() {}");
+    assert_that(p.cargo_process("build"),
+                execs().with_status(3).with_stderr("\
+Cargo.toml is not a valid manifest
+
+unsupported manifest key: bin.features
+features are enabled for the whole package and can't be set per target; \
+declare them in the top-level [features] section
+building a target only when certain features are enabled \
+(`required-features`) is not supported yet
+"));
+})
+
+test!(misused_keys_are_explained {
+    let p = project("foo")
+        .file("Cargo.toml", r#"
+            [package]
+            name = "foo"
+            version = "0.0.1"
+            authors = []
+
+            [dependencies.bar]
+            path = "bar"
+            vers = "0.0.1"
+
+            [[bin]]
+            name = "foo"
+
+            [bin.dependencies]
+            baz = "*"
+        "#)
+        .file("src/main.rs", "fn main() {}")
+        .file("bar/Cargo.toml", r#"
+            [package]
+            name = "bar"
+            version = "0.0.1"
+            authors = []
+        "#)
+        .file("bar/src/lib.rs", "");
+    assert_that(p.cargo_process("build"),
+                execs().with_status(0).with_stderr("\
+unused manifest key: dependencies.bar.vers
+did you mean `version`?
+unused manifest key: bin.dependencies.baz
+dependencies are shared by all targets of a package and belong in the \
+top-level [dependencies] section
+"));
+})
+
 test!(self_dependency {
     let mut p = project("foo");
     p = p
         .file("Cargo.toml", r#"
             [package]
 
-            name = "test"
+            name = "testing"
             version = "0.0.0"
             authors = []
 
-            [dependencies.test]
+            [dependencies.testing]
 
             path = "."
 
             [lib]
 
-            name = "test"
+            name = "testing"
         "#)
-        .file("src/test.rs", "fn main() {}");
+        .file("src/testing.rs", "fn main() {}");
     assert_that(p.cargo_process("build"),
                 execs().with_status(0));
 })
@@ -721,12 +1016,12 @@ test!(missing_lib_and_bin {
         .file("Cargo.toml", r#"
             [package]
 
-            name = "test"
+            name = "testing"
             version = "0.0.0"
             authors = []
         "#);
     assert_that(p.cargo_process("build"),
-                execs().with_status(101)
+                execs().with_status(3)
                        .with_stderr("either a [lib] or [[bin]] section \
                                      must be present\n"));
 })
@@ -737,21 +1032,22 @@ test!(verbose_build {
         .file("Cargo.toml", r#"
             [package]
 
-            name = "test"
+            name = "testing"
             version = "0.0.0"
             authors = []
         "#)
         .file("src/lib.rs", "");
     assert_that(p.cargo_process("build").arg("-v"),
                 execs().with_status(0).with_stdout(format!("\
-{compiling} test v0.0.0 ({url})
-{running} `rustc {dir}{sep}src{sep}lib.rs --crate-name test --crate-type lib -g \
+{compiling} testing v0.0.0 ({url})
+{running} `[..]rustc {dir}{sep}src{sep}lib.rs --crate-name testing --crate-type lib -C debuginfo=2 \
         -C metadata=[..] \
         -C extra-filename=-[..] \
-        --out-dir {dir}{sep}target \
+        --out-dir {dir}{sep}target{sep}debug{sep}deps \
         --dep-info [..] \
-        -L {dir}{sep}target \
-        -L {dir}{sep}target{sep}deps`
+        -L {dir}{sep}target{sep}debug \
+        -L {dir}{sep}target{sep}debug{sep}deps`
+    Finished [..]
 ",
 running = RUNNING, compiling = COMPILING, sep = path::SEP,
 dir = p.root().display(),
@@ -765,23 +1061,24 @@ test!(verbose_release_build {
         .file("Cargo.toml", r#"
             [package]
 
-            name = "test"
+            name = "testing"
             version = "0.0.0"
             authors = []
         "#)
         .file("src/lib.rs", "");
     assert_that(p.cargo_process("build").arg("-v").arg("--release"),
                 execs().with_status(0).with_stdout(format!("\
-{compiling} test v0.0.0 ({url})
-{running} `rustc {dir}{sep}src{sep}lib.rs --crate-name test --crate-type lib \
+{compiling} testing v0.0.0 ({url})
+{running} `[..]rustc {dir}{sep}src{sep}lib.rs --crate-name testing --crate-type lib \
         --opt-level 3 \
         --cfg ndebug \
         -C metadata=[..] \
         -C extra-filename=-[..] \
-        --out-dir {dir}{sep}target{sep}release \
+        --out-dir {dir}{sep}target{sep}release{sep}deps \
         --dep-info [..] \
         -L {dir}{sep}target{sep}release \
         -L {dir}{sep}target{sep}release{sep}deps`
+    Finished [..]
 ",
 running = RUNNING, compiling = COMPILING, sep = path::SEP,
 dir = p.root().display(),
@@ -795,7 +1092,7 @@ test!(verbose_release_build_deps {
         .file("Cargo.toml", r#"
             [package]
 
-            name = "test"
+            name = "testing"
             version = "0.0.0"
             authors = []
 
@@ -818,7 +1115,7 @@ test!(verbose_release_build_deps {
     assert_that(p.cargo_process("build").arg("-v").arg("--release"),
                 execs().with_status(0).with_stdout(format!("\
 {compiling} foo v0.0.0 ({url})
-{running} `rustc {dir}{sep}foo{sep}src{sep}lib.rs --crate-name foo \
+{running} `[..]rustc {dir}{sep}foo{sep}src{sep}lib.rs --crate-name foo \
         --crate-type dylib --crate-type rlib -C prefer-dynamic \
         --opt-level 3 \
         --cfg ndebug \
@@ -828,27 +1125,24 @@ test!(verbose_release_build_deps {
         --dep-info [..] \
         -L {dir}{sep}target{sep}release{sep}deps \
         -L {dir}{sep}target{sep}release{sep}deps`
-{compiling} test v0.0.0 ({url})
-{running} `rustc {dir}{sep}src{sep}lib.rs --crate-name test --crate-type lib \
+{compiling} testing v0.0.0 ({url})
+{running} `[..]rustc {dir}{sep}src{sep}lib.rs --crate-name testing --crate-type lib \
         --opt-level 3 \
         --cfg ndebug \
         -C metadata=[..] \
         -C extra-filename=-[..] \
-        --out-dir {dir}{sep}target{sep}release \
+        --out-dir {dir}{sep}target{sep}release{sep}deps \
         --dep-info [..] \
         -L {dir}{sep}target{sep}release \
         -L {dir}{sep}target{sep}release{sep}deps \
-        --extern foo={dir}{sep}target{sep}release{sep}deps/\
-                     {prefix}foo-[..]{suffix} \
         --extern foo={dir}{sep}target{sep}release{sep}deps/libfoo-[..].rlib`
+    Finished [..]
 ",
                     running = RUNNING,
                     compiling = COMPILING,
                     dir = p.root().display(),
                     url = p.url(),
-                    sep = path::SEP,
-                    prefix = os::consts::DLL_PREFIX,
-                    suffix = os::consts::DLL_SUFFIX).as_slice()));
+                    sep = path::SEP).as_slice()));
 })
 
 test!(explicit_examples {
@@ -1002,7 +1296,7 @@ test!(deletion_causes_failure {
             version = "0.0.1"
             authors = []
         "#);
-    assert_that(p.cargo_process("build"), execs().with_status(101));
+    assert_that(p.cargo_process("build"), execs().with_status(2));
 })
 
 test!(bad_cargo_toml_in_target_dir {
@@ -1042,6 +1336,7 @@ test!(lib_with_standard_name {
                 execs().with_status(0)
                        .with_stdout(format!("\
 {compiling} syntax v0.0.1 ({dir})
+    Finished [..]
 ",
                        compiling = COMPILING,
                        dir = p.url()).as_slice()));
@@ -1151,6 +1446,56 @@ test!(deprecated_lib {
 the [[lib]] section has been deprecated in favor of [lib]\n"));
 })
 
+test!(many_deprecated_libs {
+    let p = project("foo")
+        .file("Cargo.toml", r#"
+              [package]
+              name = "foo"
+              authors = []
+              version = "0.0.1"
+
+              [[lib]]
+              name = "foo"
+
+              [[lib]]
+              name = "bar"
+              path = "src/bar.rs"
+
+              [[lib]]
+              name = "baz"
+              path = "src/baz.rs"
+        "#)
+        .file("src/foo.rs", "")
+        .file("src/bar.rs", "")
+        .file("src/baz.rs", "");
+    assert_that(p.cargo_process("build"),
+                execs().with_status(3)
+                       .with_stderr("\
+Cargo.toml is not a valid manifest
+
+a package can only have one library, but 3 [[lib]] sections were found \
+(extra: `bar`, `baz`)
+use a single [lib] section with `crate-type` to produce several kinds of \
+library artifacts
+"));
+})
+
+test!(lib_table_has_no_warnings {
+    let p = project("foo")
+        .file("Cargo.toml", r#"
+              [package]
+              name = "foo"
+              authors = []
+              version = "0.0.1"
+
+              [lib]
+              name = "foo"
+        "#)
+        .file("src/foo.rs", "");
+    assert_that(p.cargo_process("build"),
+                execs().with_status(0).with_stderr(""));
+})
+
 test!(freshness_ignores_excluded {
     let foo = project("foo")
         .file("Cargo.toml", r#"
@@ -1169,20 +1514,21 @@ test!(freshness_ignores_excluded {
                 execs().with_status(0)
                        .with_stdout(format!("\
 {compiling} foo v0.0.0 ({url})
+    Finished [..]
 ", compiling = COMPILING, url = foo.url())));
 
     // Smoke test to make sure it doesn't compile again
     println!("first pass");
     assert_that(foo.process(cargo_dir().join("cargo")).arg("build"),
                 execs().with_status(0)
-                       .with_stdout(""));
+                       .with_stdout("    Finished [..]\n"));
 
     // Modify an ignored file and make sure we don't rebuild
     println!("second pass");
     File::create(&foo.root().join("src/bar.rs")).assert();
     assert_that(foo.process(cargo_dir().join("cargo")).arg("build"),
                 execs().with_status(0)
-                       .with_stdout(""));
+                       .with_stdout("    Finished [..]\n"));
 })
 
 test!(rebuild_preserves_out_dir {
@@ -1226,6 +1572,7 @@ test!(rebuild_preserves_out_dir {
                 execs().with_status(0)
                        .with_stdout(format!("\
 {compiling} foo v0.0.0 ({url})
+    Finished [..]
 ", compiling = COMPILING, url = foo.url())));
 
     File::create(&foo.root().join("src/bar.rs")).assert();
@@ -1233,6 +1580,7 @@ test!(rebuild_preserves_out_dir {
                 execs().with_status(0)
                        .with_stdout(format!("\
 {compiling} foo v0.0.0 ({url})
+    Finished [..]
 ", compiling = COMPILING, url = foo.url())));
 })
 
@@ -1256,7 +1604,7 @@ test!(dep_no_libs {
         "#)
         .file("bar/src/main.rs", "");
     assert_that(foo.cargo_process("build"),
-                execs().with_status(101)
+                execs().with_status(1)
                        .with_stderr("\
 Package `bar v0.0.0 ([..])` has no library targets"));
 })
@@ -1277,7 +1625,7 @@ test!(recompile_space_in_name {
     assert_that(foo.cargo_process("build"), execs().with_status(0));
     foo.root().move_into_the_past().assert();
     assert_that(foo.process(cargo_dir().join("cargo")).arg("build"),
-                execs().with_status(0).with_stdout(""));
+                execs().with_status(0).with_stdout("    Finished [..]\n"));
 })
 
 test!(ignore_bad_directories {
@@ -1309,7 +1657,7 @@ test!(bad_cargo_config {
               this is not valid toml
         "#);
     assert_that(foo.cargo_process("build").arg("-v"),
-                execs().with_status(101).with_stderr("\
+                execs().with_status(3).with_stderr("\
 Couldn't load Cargo configuration
 
 Caused by:
@@ -1396,7 +1744,7 @@ test!(cargo_platform_specific_dependency {
         "#);
 
     assert_that(p.cargo_process("build"),
-        execs().with_status(101));
+        execs().with_status(2));
 })
 
 test!(cargo_platform_specific_dependency_wrong_platform {
@@ -1439,3 +1787,1137 @@ test!(cargo_platform_specific_dependency_wrong_platform {
     let lockfile = File::open(&lockfile).read_to_string().assert();
     assert!(lockfile.as_slice().contains("bar"))
 })
+
+test!(color_never_is_plain_and_forwarded {
+    let p = project("foo")
+        .file("Cargo.toml", basic_bin_manifest("foo").as_slice())
+        .file("src/foo.rs", main_file(r#""i am foo""#, []).as_slice());
+
+    assert_that(p.cargo_process("build").arg("-v").arg("--color").arg("never"),
+                execs().with_status(0).with_stdout(format!("\
+{compiling} foo v0.5.0 ({url})
+{running} `[..]rustc src{sep}foo.rs [..] --color never [..]`
+    Finished [..]
+", compiling = COMPILING, running = RUNNING, url = p.url(),
+   sep = path::SEP).as_slice()));
+})
+
+test!(color_always_when_piped {
+    if TerminfoTerminal::new(MemWriter::new()).is_none() { return }
+
+    let p = project("foo")
+        .file("Cargo.toml", basic_bin_manifest("foo").as_slice())
+        .file("src/foo.rs", main_file(r#""i am foo""#, []).as_slice());
+
+    let output = p.cargo_process("build").arg("--color").arg("always")
+                  .exec_with_output().assert();
+    assert!(output.output.as_slice().contains(&0x1b));
+
+    let output = p.cargo_process("build").exec_with_output().assert();
+    assert!(!output.output.as_slice().contains(&0x1b));
+})
+
+test!(color_from_config {
+    if TerminfoTerminal::new(MemWriter::new()).is_none() { return }
+
+    let p = project("foo")
+        .file(".cargo/config", r#"
+            [term]
+            color = "always"
+        "#)
+        .file("Cargo.toml", basic_bin_manifest("foo").as_slice())
+        .file("src/foo.rs", main_file(r#""i am foo""#, []).as_slice());
+
+    let output = p.cargo_process("build").exec_with_output().assert();
+    assert!(output.output.as_slice().contains(&0x1b));
+
+    let output = p.cargo_process("build").arg("--color").arg("never")
+                  .exec_with_output().assert();
+    assert!(!output.output.as_slice().contains(&0x1b));
+
+    // A flag of the top-level command also wins over the configuration.
+    let output = p.process(cargo_dir().join("cargo")).arg("--color")
+                  .arg("never").arg("build")
+                  .exec_with_output().assert();
+    assert!(!output.output.as_slice().contains(&0x1b));
+})
+
+test!(color_invalid_value {
+    let p = project("foo")
+        .file("Cargo.toml", basic_bin_manifest("foo").as_slice())
+        .file("src/foo.rs", main_file(r#""i am foo""#, []).as_slice());
+
+    assert_that(p.cargo_process("build").arg("--color").arg("sometimes"),
+                execs().with_status(1).with_stderr("\
+argument for --color must be auto, always, or never, but found `sometimes`
+"));
+})
+
+test!(no_progress_when_not_a_tty {
+    let p = project("foo")
+        .file("Cargo.toml", r#"
+            [package]
+            name = "foo"
+            version = "0.5.0"
+            authors = []
+
+            [dependencies.bar]
+            path = "bar"
+        "#)
+        .file("src/main.rs", "extern crate bar; fn main() {}")
+        .file("bar/Cargo.toml", r#"
+            [package]
+            name = "bar"
+            version = "0.5.0"
+            authors = []
+            build = "build.rs"
+        "#)
+        .file("bar/build.rs", "fn main() {}")
+        .file("bar/src/lib.rs", "");
+
+    assert_that(p.cargo_process("build"),
+                execs().with_status(0).with_stderr(""));
+})
+
+test!(keep_going_builds_independent_deps {
+    let p = project("foo")
+        .file("Cargo.toml", r#"
+            [package]
+            name = "foo"
+            version = "0.5.0"
+            authors = []
+
+            [dependencies.good]
+            path = "good"
+            [dependencies.bad]
+            path = "bad"
+        "#)
+        .file("src/main.rs", "extern crate good; extern crate bad; fn main() {}")
+        .file("good/Cargo.toml", r#"
+            [package]
+            name = "good"
+            version = "0.5.0"
+            authors = []
+        "#)
+        .file("good/src/lib.rs", "pub fn good() {}")
+        .file("bad/Cargo.toml", r#"
+            [package]
+            name = "bad"
+            version = "0.5.0"
+            authors = []
+        "#)
+        .file("bad/src/lib.rs", "invalid rust code!");
+
+    assert_that(p.cargo_process("build").arg("--keep-going").arg("-j1"),
+                execs().with_status(2));
+
+    let deps = fs::readdir(&p.root().join("target/debug/deps")).assert();
+    assert!(deps.iter().any(|f| {
+        f.filename_str().unwrap().starts_with("libgood-")
+    }));
+    assert!(!deps.iter().any(|f| {
+        f.filename_str().unwrap().starts_with("libbad-")
+    }));
+    assert_that(&p.bin("foo"), is_not(existing_file()));
+})
+
+test!(keep_going_reports_failure_count {
+    let p = project("foo")
+        .file("Cargo.toml", r#"
+            [package]
+            name = "foo"
+            version = "0.5.0"
+            authors = []
+
+            [dependencies.bad]
+            path = "bad"
+        "#)
+        .file("src/main.rs", "extern crate bad; fn main() {}")
+        .file("bad/Cargo.toml", r#"
+            [package]
+            name = "bad"
+            version = "0.5.0"
+            authors = []
+        "#)
+        .file("bad/src/lib.rs", "invalid rust code!");
+
+    let output = match p.cargo_process("build").arg("--keep-going")
+                        .exec_with_output() {
+        Ok(..) => panic!("expected the build to fail"),
+        Err(e) => e.output.unwrap(),
+    };
+    let stderr = String::from_utf8(output.error).unwrap();
+    assert!(stderr.as_slice().contains("build failed; 1 job failed\n\n\
+                                        Could not compile `bad`.\n"),
+            "{}", stderr);
+
+    // The causes of the failure are kept.
+    let output = match p.process(cargo_dir().join("cargo")).arg("build")
+                        .arg("--keep-going").arg("-v")
+                        .exec_with_output() {
+        Ok(..) => panic!("expected the build to fail"),
+        Err(e) => e.output.unwrap(),
+    };
+    let stderr = String::from_utf8(output.error).unwrap();
+    assert!(stderr.as_slice().contains("Caused by:\n  Process didn't exit \
+                                        successfully: "),
+            "{}", stderr);
+})
+
+test!(plain_lib_names_for_top_level_package {
+    let p = project("foo")
+        .file("Cargo.toml", r#"
+            [package]
+            name = "foo"
+            version = "0.5.0"
+            authors = []
+        "#)
+        .file("src/lib.rs", "pub fn foo() {}")
+        .file("src/main.rs", "extern crate foo; fn main() { foo::foo() }");
+
+    assert_that(p.cargo_process("build"), execs().with_status(0));
+    assert_that(&p.root().join("target/debug/libfoo.rlib"), existing_file());
+    assert_that(&p.bin("foo"), existing_file());
+
+    let deps = fs::readdir(&p.root().join("target/debug/deps")).assert();
+    assert!(deps.iter().any(|f| {
+        let name = f.filename_str().unwrap();
+        name.starts_with("libfoo-") && name.ends_with(".rlib")
+    }));
+
+    // A fresh build keeps the plain copy around
+    assert_that(p.process(cargo_dir().join("cargo")).arg("build"),
+                execs().with_status(0).with_stdout("    Finished [..]\n"));
+    assert_that(&p.root().join("target/debug/libfoo.rlib"), existing_file());
+})
+
+test!(stale_binaries_removed_after_rename {
+    let p = project("foo")
+        .file("Cargo.toml", r#"
+            [package]
+            name = "foo"
+            version = "0.5.0"
+            authors = []
+
+            [[bin]]
+            name = "foo"
+            path = "src/main.rs"
+        "#)
+        .file("src/main.rs", "fn main() {}");
+
+    assert_that(p.cargo_process("build"), execs().with_status(0));
+    assert_that(&p.bin("foo"), existing_file());
+
+    File::create(&p.root().join("Cargo.toml")).write_str(r#"
+        [package]
+        name = "foo"
+        version = "0.5.0"
+        authors = []
+
+        [[bin]]
+        name = "bar"
+        path = "src/main.rs"
+    "#).assert();
+    assert_that(p.process(cargo_dir().join("cargo")).arg("build"),
+                execs().with_status(0));
+    assert_that(&p.bin("bar"), existing_file());
+    assert_that(&p.bin("foo"), is_not(existing_file()));
+    assert_that(process(p.bin("bar")), execs().with_status(0));
+})
+
+test!(all_crate_types_built_in_one_invocation {
+    let p = project("foo")
+        .file("Cargo.toml", r#"
+            [package]
+            name = "foo"
+            version = "0.5.0"
+            authors = []
+
+            [lib]
+            name = "foo"
+            crate-type = ["rlib", "dylib", "staticlib"]
+        "#)
+        .file("src/lib.rs", "pub fn foo() {}");
+
+    assert_that(p.cargo_process("build").arg("-v"),
+                execs().with_status(0).with_stdout(format!("\
+{compiling} foo v0.5.0 ({url})
+{running} `[..]rustc [..]lib.rs --crate-name foo --crate-type rlib \
+        --crate-type dylib --crate-type staticlib [..]`
+    Finished [..]
+",
+                    running = RUNNING, compiling = COMPILING,
+                    url = p.url()).as_slice()));
+
+    let target = p.root().join("target/debug");
+    assert_that(&target.join("libfoo.rlib"), existing_file());
+    assert_that(&target.join(format!("{}foo{}", os::consts::DLL_PREFIX,
+                                     os::consts::DLL_SUFFIX)),
+                existing_file());
+    assert_that(&target.join("libfoo.a"), existing_file());
+})
+
+test!(dependents_link_against_rlib {
+    let p = project("foo")
+        .file("Cargo.toml", r#"
+            [package]
+            name = "foo"
+            version = "0.5.0"
+            authors = []
+
+            [dependencies.bar]
+            path = "bar"
+        "#)
+        .file("src/main.rs", "extern crate bar; fn main() { bar::bar() }")
+        .file("bar/Cargo.toml", r#"
+            [package]
+            name = "bar"
+            version = "0.5.0"
+            authors = []
+
+            [lib]
+            name = "bar"
+            crate-type = ["dylib", "rlib"]
+        "#)
+        .file("bar/src/lib.rs", "pub fn bar() {}");
+
+    assert_that(p.cargo_process("build").arg("-v"),
+                execs().with_status(0).with_stdout(format!("\
+{compiling} bar v0.5.0 ({url})
+{running} `[..]rustc [..]lib.rs --crate-name bar --crate-type dylib \
+        --crate-type rlib [..]`
+{compiling} foo v0.5.0 ({url})
+{running} `[..]rustc [..]main.rs --crate-name foo --crate-type bin [..] \
+        --extern bar=[..]libbar-[..].rlib`
+    Finished [..]
+",
+                    running = RUNNING, compiling = COMPILING,
+                    url = p.url()).as_slice()));
+})
+
+test!(unknown_crate_type {
+    let p = project("foo")
+        .file("Cargo.toml", r#"
+            [package]
+            name = "foo"
+            version = "0.5.0"
+            authors = []
+
+            [lib]
+            name = "foo"
+            crate-type = ["rlib", "dynlib"]
+        "#)
+        .file("src/lib.rs", "");
+
+    assert_that(p.cargo_process("build"),
+                execs().with_status(3).with_stderr("\
+Cargo.toml is not a valid manifest
+
+unknown crate type `dynlib`, expected one of `lib`, `rlib`, `dylib`, `staticlib`
+"));
+})
+
+test!(warn_on_main_hidden_by_explicit_bin {
+    let p = project("foo")
+        .file("Cargo.toml", r#"
+            [package]
+            name = "foo"
+            version = "0.5.0"
+            authors = []
+
+            [[bin]]
+            name = "tool"
+            path = "src/tool.rs"
+        "#)
+        .file("src/main.rs", "fn main() {}")
+        .file("src/tool.rs", "fn main() {}");
+
+    assert_that(p.cargo_process("build"),
+                execs().with_status(0).with_stderr("\
+src/main.rs is not built because [[bin]] sections are present, add a [[bin]] \
+with `path = \"src/main.rs\"` to build it
+"));
+    assert_that(&p.bin("tool"), existing_file());
+    assert_that(&p.bin("foo"), is_not(existing_file()));
+})
+
+test!(no_warning_when_explicit_bin_covers_main {
+    let p = project("foo")
+        .file("Cargo.toml", r#"
+            [package]
+            name = "foo"
+            version = "0.5.0"
+            authors = []
+
+            [[bin]]
+            name = "foo"
+            path = "src/main.rs"
+
+            [[bin]]
+            name = "tool"
+            path = "src/tool.rs"
+        "#)
+        .file("src/main.rs", "fn main() {}")
+        .file("src/tool.rs", "fn main() {}");
+
+    assert_that(p.cargo_process("build"),
+                execs().with_status(0).with_stderr(""));
+    assert_that(&p.bin("foo"), existing_file());
+    assert_that(&p.bin("tool"), existing_file());
+})
+
+test!(warn_on_lib_hidden_by_explicit_lib {
+    let p = project("foo")
+        .file("Cargo.toml", r#"
+            [package]
+            name = "foo"
+            version = "0.5.0"
+            authors = []
+
+            [lib]
+            name = "foo"
+            path = "src/other.rs"
+        "#)
+        .file("src/lib.rs", "")
+        .file("src/other.rs", "");
+
+    assert_that(p.cargo_process("build"),
+                execs().with_status(0).with_stderr("\
+src/lib.rs is not built because the [lib] section points elsewhere, remove \
+its `path` key to build it
+"));
+})
+
+test!(release_and_debug_builds_do_not_clobber {
+    let p = project("foo")
+        .file("Cargo.toml", r#"
+            [package]
+            name = "foo"
+            version = "0.5.0"
+            authors = []
+
+            [dependencies.bar]
+            path = "bar"
+        "#)
+        .file("src/main.rs", "extern crate bar; fn main() { bar::bar() }")
+        .file("bar/Cargo.toml", r#"
+            [package]
+            name = "bar"
+            version = "0.5.0"
+            authors = []
+        "#)
+        .file("bar/src/lib.rs", "pub fn bar() {}");
+
+    assert_that(p.cargo_process("build"), execs().with_status(0));
+    assert_that(p.process(cargo_dir().join("cargo")).arg("build")
+                 .arg("--release"),
+                execs().with_status(0).with_stdout(format!("\
+{compiling} bar v0.5.0 ({url})
+{compiling} foo v0.5.0 ({url})
+    Finished [..]
+", compiling = COMPILING, url = p.url()).as_slice()));
+    assert_that(&p.bin("foo"), existing_file());
+    assert_that(&p.release_bin("foo"), existing_file());
+
+    assert_that(p.process(cargo_dir().join("cargo")).arg("build"),
+                execs().with_status(0).with_stdout("    Finished [..]\n"));
+    assert_that(p.process(cargo_dir().join("cargo")).arg("build")
+                 .arg("--release"),
+                execs().with_status(0).with_stdout("    Finished [..]\n"));
+})
+
+test!(invalid_package_name {
+    let p = project("foo")
+        .file("Cargo.toml", r#"
+            [package]
+            name = "foo bar"
+            version = "0.5.0"
+            authors = []
+        "#)
+        .file("src/lib.rs", "");
+
+    assert_that(p.cargo_process("build"),
+                execs().with_status(3).with_stderr("\
+Cargo.toml is not a valid manifest
+
+invalid character ` ` in package name: `foo bar`, only alphanumeric \
+characters, `-` and `_` are allowed
+"));
+})
+
+test!(reserved_package_name {
+    let p = project("foo")
+        .file("Cargo.toml", r#"
+            [package]
+            name = "test"
+            version = "0.5.0"
+            authors = []
+        "#)
+        .file("src/lib.rs", "");
+
+    assert_that(p.cargo_process("build"),
+                execs().with_status(3).with_stderr("\
+Cargo.toml is not a valid manifest
+
+`test` is a reserved name and cannot be used as a package name
+"));
+})
+
+test!(verbose_build_shows_cwd_and_env {
+    let p = project("foo")
+        .file("Cargo.toml", r#"
+            [package]
+            name = "foo"
+            version = "0.5.0"
+            authors = []
+        "#)
+        .file("src/lib.rs", "");
+
+    assert_that(p.cargo_process("build").arg("-v"),
+                execs().with_status(0).with_stdout(format!("\
+{compiling} foo v0.5.0 ({url})
+{running} `cd {dir} && [..]CARGO_MANIFEST_DIR={dir} \
+           CARGO_PKG_VERSION_MAJOR=0 [..]rustc {dir}{sep}src{sep}lib.rs [..]`
+    Finished [..]
+",
+running = RUNNING, compiling = COMPILING, sep = path::SEP,
+dir = p.root().display(), url = p.url())));
+})
+
+test!(build_all_targets {
+    let p = project("foo")
+        .file("Cargo.toml", r#"
+            [package]
+            name = "foo"
+            version = "0.0.1"
+            authors = []
+        "#)
+        .file("src/lib.rs", "pub fn foo() {} #[test] fn lib_test() {}")
+        .file("src/main.rs", "extern crate foo; fn main() { foo::foo() }")
+        .file("examples/ex.rs", "extern crate foo; fn main() { foo::foo() }")
+        .file("tests/it.rs", "extern crate foo; #[test] fn it() { foo::foo() }")
+        .file("benches/b.rs", r#"
+            extern crate test;
+            extern crate foo;
+            #[bench] fn b(_b: &mut test::Bencher) { foo::foo() }
+        "#);
+
+    let output = p.cargo_process("build").arg("--all-targets").arg("-v")
+                  .exec_with_output().assert();
+    let output = str::from_utf8(output.output.as_slice()).assert();
+    let rustcs = output.lines().filter(|l| {
+        l.starts_with(RUNNING) && l.contains("rustc ")
+    }).collect::<Vec<&str>>();
+    let count = |needle: &str| {
+        rustcs.iter().filter(|l| l.contains(needle)).count()
+    };
+
+    // The debug library is shared by the bin, tests and examples.
+    assert_eq!(count("--crate-name foo --crate-type lib -C debuginfo=2"), 1);
+    assert_eq!(count("--crate-name foo --crate-type bin"), 1);
+    assert_eq!(count("ex.rs --crate-name ex --crate-type bin"), 1);
+    assert_eq!(count("it.rs --crate-name it --crate-type bin"), 1);
+    assert_eq!(count("b.rs --crate-name b --crate-type bin"), 1);
+    assert!(!output.contains("running 1 test"), "{}", output);
+    assert!(!p.root().join("target/release").exists());
+})
+
+test!(build_all_targets_release {
+    let p = project("foo")
+        .file("Cargo.toml", r#"
+            [package]
+            name = "foo"
+            version = "0.0.1"
+            authors = []
+        "#)
+        .file("src/lib.rs", "pub fn foo() {} #[test] fn lib_test() {}")
+        .file("src/main.rs", "extern crate foo; fn main() { foo::foo() }")
+        .file("examples/ex.rs", "extern crate foo; fn main() { foo::foo() }")
+        .file("tests/it.rs", "extern crate foo; #[test] fn it() { foo::foo() }")
+        .file("benches/b.rs", r#"
+            extern crate test;
+            extern crate foo;
+            #[bench] fn b(_b: &mut test::Bencher) { foo::foo() }
+        "#);
+
+    let output = p.cargo_process("build").arg("--all-targets").arg("--release")
+                  .arg("-v").exec_with_output().assert();
+    let output = str::from_utf8(output.output.as_slice()).assert();
+    let rustcs = output.lines().filter(|l| {
+        l.starts_with(RUNNING) && l.contains("rustc ")
+    }).collect::<Vec<&str>>();
+    let count = |needle: &str| {
+        rustcs.iter().filter(|l| l.contains(needle)).count()
+    };
+
+    // Everything is optimized and links to the one release library.
+    assert_eq!(count("--crate-name foo --crate-type lib"), 1);
+    assert_eq!(count("-C debuginfo"), 0);
+    assert_eq!(count("--opt-level 3"), rustcs.len());
+    assert_eq!(count("--crate-name foo --crate-type bin"), 1);
+    assert_eq!(count("ex.rs --crate-name ex --crate-type bin"), 1);
+    assert_eq!(count("it.rs --crate-name it --crate-type bin"), 1);
+    assert_eq!(count("b.rs --crate-name b --crate-type bin"), 1);
+    assert_eq!(output.match_indices("    Finished").count(), 1);
+    assert!(!output.contains("running 1 test"), "{}", output);
+    assert!(!p.root().join("target/debug").exists());
+})
+
+test!(build_all_targets_but_one {
+    let p = project("foo")
+        .file("Cargo.toml", r#"
+            [package]
+            name = "foo"
+            version = "0.0.1"
+            authors = []
+        "#)
+        .file("src/lib.rs", "")
+        .file("tests/it.rs", "invalid rust code")
+        .file("examples/ex.rs", "fn main() {}");
+
+    assert_that(p.cargo_process("build").arg("--all-targets")
+                 .arg("--skip-target").arg("it"),
+                execs().with_status(0));
+    assert_that(&p.bin("examples/ex"), existing_file());
+})
+
+test!(build_all_targets_fails_on_broken_test {
+    let p = project("foo")
+        .file("Cargo.toml", r#"
+            [package]
+            name = "foo"
+            version = "0.0.1"
+            authors = []
+        "#)
+        .file("src/lib.rs", "")
+        .file("tests/it.rs", "invalid rust code");
+
+    assert_that(p.cargo_process("build"), execs().with_status(0));
+    assert_that(p.process(cargo_dir().join("cargo")).arg("build")
+                 .arg("--all-targets"),
+                execs().with_status(2));
+})
+
+test!(deeply_nested_manifest {
+    let nested = format!("{}1{}", "[".repeat(200), "]".repeat(200));
+    let p = project("foo")
+        .file("Cargo.toml", format!(r#"
+            [package]
+            name = "foo"
+            version = "0.0.1"
+            authors = []
+
+            [metadata]
+            nested = {}
+        "#, nested).as_slice())
+        .file("src/lib.rs", "");
+
+    assert_that(p.cargo_process("build"),
+                execs().with_status(3).with_stderr("\
+Cargo.toml nests arrays or tables 200 levels deep, which is more than the \
+limit of 128
+the limit can be raised with the `manifest.max-depth` configuration key
+"));
+
+    // Brackets in strings and comments don't count
+    let p = project("bar")
+        .file("Cargo.toml", format!(r#"
+            [package]
+            name = "bar"
+            version = "0.0.1"
+            authors = ["{}"] # {}
+        "#, nested, nested).as_slice())
+        .file("src/lib.rs", "");
+    assert_that(p.cargo_process("build"), execs().with_status(0));
+})
+
+test!(manifest_depth_limit_can_be_raised {
+    let nested = format!("{}1{}", "[".repeat(200), "]".repeat(200));
+    let p = project("foo")
+        .file("Cargo.toml", format!(r#"
+            [package]
+            name = "foo"
+            version = "0.0.1"
+            authors = []
+
+            [metadata]
+            nested = {}
+        "#, nested).as_slice())
+        .file("src/lib.rs", "")
+        .file(".cargo/config", r#"
+            [manifest]
+            max-depth = 256
+        "#);
+
+    assert_that(p.cargo_process("build"),
+                execs().with_status(0).with_stderr("\
+unused manifest key: metadata.nested
+"));
+})
+
+test!(negative_manifest_depth_limit {
+    let nested = format!("{}1{}", "[".repeat(200), "]".repeat(200));
+    let p = project("foo")
+        .file("Cargo.toml", format!(r#"
+            [package]
+            name = "foo"
+            version = "0.0.1"
+            authors = []
+
+            [metadata]
+            nested = {}
+        "#, nested).as_slice())
+        .file("src/lib.rs", "")
+        .file(".cargo/config", r#"
+            [manifest]
+            max-depth = -1
+        "#);
+
+    assert_that(p.cargo_process("build"),
+                execs().with_status(101).with_stderr("\
+`manifest.max-depth` in [..]config must not be negative, but is -1
+"));
+})
+
+test!(oversized_manifest {
+    let mut manifest = r#"
+        [package]
+        name = "foo"
+        version = "0.0.1"
+        authors = []
+    "#.to_string();
+    let filler = format!("# {}\n", "x".repeat(1022));
+    for _ in range(0u, 8 * 1024) {
+        manifest.push_str(filler.as_slice());
+    }
+    let p = project("foo")
+        .file("Cargo.toml", manifest.as_slice())
+        .file("src/lib.rs", "");
+
+    assert_that(p.cargo_process("build"),
+                execs().with_status(3).with_stderr(format!("\
+Cargo.toml is {} bytes, which is larger than the limit of 8388608 bytes
+the limit can be raised with the `manifest.max-size` configuration key
+", manifest.len()).as_slice()));
+})
+
+test!(timings_lists_every_unit {
+    let p = project("foo")
+        .file("Cargo.toml", r#"
+            [package]
+            name = "foo"
+            version = "0.5.0"
+            authors = []
+
+            [dependencies.bar]
+            path = "bar"
+        "#)
+        .file("src/main.rs", "extern crate bar; fn main() {}")
+        .file("bar/Cargo.toml", r#"
+            [package]
+            name = "bar"
+            version = "0.5.0"
+            authors = []
+        "#)
+        .file("bar/src/lib.rs", "");
+
+    assert_that(p.cargo_process("build").arg("--timings"),
+                execs().with_status(0).with_stdout(format!("\
+{compiling} bar v0.5.0 ({dir})
+{compiling} foo v0.5.0 ({dir})
+    Finished [..]
+Timings:
+[..]s  [..]  compile
+[..]s  [..]  compile
+total wall time: [..]s, sum of unit times: [..]s, critical path: [..]s
+", compiling = COMPILING, dir = p.url()).as_slice()));
+
+    let json = File::open(&p.root().join("target/cargo-timings.json"))
+                    .read_to_string().assert();
+    assert!(json.as_slice().contains(r#""package":"bar v0.5.0"#), "{}", json);
+    assert!(json.as_slice().contains(r#""target":"lib""#), "{}", json);
+    assert!(json.as_slice().contains(r#""target":"bin foo""#), "{}", json);
+
+    assert_that(p.process(cargo_dir().join("cargo")).arg("build")
+                 .arg("--timings"),
+                execs().with_status(0).with_stdout("\
+    Finished [..]
+Timings:
+[..]s  [..]  compile (fresh)
+[..]s  [..]  compile (fresh)
+total wall time: [..]s, sum of unit times: [..]s, critical path: [..]s
+"));
+})
+
+test!(timings_are_reported_once_for_all_targets {
+    let p = project("foo")
+        .file("Cargo.toml", r#"
+            [package]
+            name = "foo"
+            version = "0.5.0"
+            authors = []
+        "#)
+        .file("src/lib.rs", "")
+        .file("tests/t.rs", "#[test] fn t() {}")
+        .file("benches/b.rs", "");
+
+    let output = p.cargo_process("build").arg("--all-targets")
+                  .arg("--timings").exec_with_output().assert();
+    let output = str::from_utf8(output.output.as_slice()).unwrap();
+    assert_eq!(output.match_indices("Timings:").count(), 1);
+    assert_eq!(output.match_indices("total wall time").count(), 1);
+
+    // The file lists the units of every kind of target, with the benchmark
+    // compiled as a test.
+    let json = File::open(&p.root().join("target/cargo-timings.json"))
+                    .read_to_string().assert();
+    assert!(json.as_slice().contains(r#""target":"lib","profile":"compile""#),
+            "{}", json);
+    assert!(json.as_slice().contains(r#""target":"test t""#), "{}", json);
+    assert!(json.as_slice().contains(r#""target":"test b""#), "{}", json);
+    assert!(!json.as_slice().contains(r#""target":"bin"#), "{}", json);
+})
+
+test!(finished_line_summarizes_build {
+    let p = project("foo")
+        .file("Cargo.toml", basic_bin_manifest("foo").as_slice())
+        .file("src/foo.rs", main_file(r#""i am foo""#, []).as_slice());
+
+    assert_that(p.cargo_process("build"),
+                execs().with_status(0).with_stdout(format!("\
+{compiling} foo v0.5.0 ({url})
+    Finished debug [unoptimized + debuginfo] target(s) in [..]s (1 unit, 0 fresh)
+", compiling = COMPILING, url = p.url()).as_slice()));
+
+    assert_that(p.process(cargo_dir().join("cargo")).arg("build"),
+                execs().with_status(0).with_stdout("\
+    Finished debug [unoptimized + debuginfo] target(s) in [..]s (1 unit, 1 fresh)
+"));
+
+    assert_that(p.process(cargo_dir().join("cargo")).arg("build")
+                 .arg("--release"),
+                execs().with_status(0).with_stdout(format!("\
+{compiling} foo v0.5.0 ({url})
+    Finished release [optimized] target(s) in [..]s (1 unit, 0 fresh)
+", compiling = COMPILING, url = p.url()).as_slice()));
+})
+
+test!(print_artifact_prints_only_paths {
+    let p = project("foo")
+        .file("Cargo.toml", r#"
+            [package]
+            name = "foo"
+            version = "0.0.1"
+            authors = []
+        "#)
+        .file("src/lib.rs", "pub fn foo() {}")
+        .file("src/main.rs", "extern crate foo; fn main() { foo::foo() }")
+        .file("examples/ex.rs", "extern crate foo; fn main() { foo::foo() }");
+
+    let lib = p.build_dir().join("debug/libfoo.rlib");
+    assert_that(p.cargo_process("build").arg("--print-artifact"),
+                execs().with_status(0).with_stdout(format!("{}\n",
+                    lib.display()).as_slice()));
+
+    assert_that(p.process(cargo_dir().join("cargo")).arg("build")
+                 .arg("--print-artifact").arg("--release").arg("--bin")
+                 .arg("foo"),
+                execs().with_status(0).with_stdout(format!("{}\n",
+                    p.release_bin("foo").display()).as_slice()));
+
+    assert_that(p.process(cargo_dir().join("cargo")).arg("build")
+                 .arg("--print-artifact").arg("--lib").arg("--example")
+                 .arg("ex"),
+                execs().with_status(0).with_stdout(format!("{}\n{}\n",
+                    lib.display(),
+                    p.build_dir().join("debug/examples").join(format!("ex{}",
+                        os::consts::EXE_SUFFIX)).display()).as_slice()));
+
+    assert_that(p.process(cargo_dir().join("cargo")).arg("build")
+                 .arg("--print-artifact").arg("--bin").arg("bar"),
+                execs().with_status(1).with_stdout("").with_stderr("\
+no artifact was produced for binary `bar`
+"));
+
+    // A release example is built with its release profile, in the release
+    // directory.
+    assert_that(p.process(cargo_dir().join("cargo")).arg("build")
+                 .arg("--print-artifact").arg("--release").arg("--example")
+                 .arg("ex"),
+                execs().with_status(0).with_stdout(format!("{}\n",
+                    p.build_dir().join("release/examples").join(format!("ex{}",
+                        os::consts::EXE_SUFFIX)).display()).as_slice()));
+
+    // Without --print-artifact the selection is still checked.
+    assert_that(p.process(cargo_dir().join("cargo")).arg("build")
+                 .arg("--bin").arg("foo").arg("--example").arg("ex"),
+                execs().with_status(0));
+    assert_that(p.process(cargo_dir().join("cargo")).arg("build")
+                 .arg("--bin").arg("bar"),
+                execs().with_status(1).with_stderr("\
+no artifact was produced for binary `bar`
+"));
+})
+
+test!(renamed_targets_leave_no_stale_artifacts {
+    let p = project("foo")
+        .file("Cargo.toml", r#"
+            [package]
+            name = "foo"
+            version = "0.0.1"
+            authors = []
+
+            [[bin]]
+            name = "server"
+            path = "src/main.rs"
+        "#)
+        .file("src/main.rs", "fn main() {}")
+        .file("examples/ex.rs", "fn main() {}");
+
+    assert_that(p.cargo_process("build").arg("--all-targets"),
+                execs().with_status(0));
+    assert_that(&p.bin("server"), existing_file());
+    assert_that(&p.bin("examples/ex"), existing_file());
+
+    File::create(&p.root().join("Cargo.toml")).write_str(r#"
+        [package]
+        name = "foo"
+        version = "0.0.1"
+        authors = []
+
+        [[bin]]
+        name = "serverd"
+        path = "src/main.rs"
+    "#).unwrap();
+    fs::rename(&p.root().join("examples/ex.rs"),
+               &p.root().join("examples/ex2.rs")).unwrap();
+
+    assert_that(p.process(cargo_dir().join("cargo")).arg("build")
+                 .arg("--all-targets"),
+                execs().with_status(0));
+    assert_that(&p.bin("serverd"), existing_file());
+    assert_that(&p.bin("server"), is_not(existing_file()));
+    assert_that(&p.bin("examples/ex2"), existing_file());
+    assert_that(&p.bin("examples/ex"), is_not(existing_file()));
+})
+
+test!(rustc_version_is_probed_once {
+    if cfg!(windows) { return }
+
+    // A `rustc` wrapper earlier in PATH logs every version probe.
+    let log = paths::root().join("probes");
+    let bin = paths::root().join("fake-bin");
+    fs::mkdir_recursive(&bin, io::USER_DIR).assert();
+    let path = os::getenv("PATH").unwrap_or(String::new());
+    File::create(&bin.join("rustc")).write_str(format!(r#"#!/bin/sh
+        if [ "$1" = "-v" ]; then echo probe >> "{log}"; fi
+        PATH="{path}" exec rustc "$@"
+    "#, log = log.display(), path = path).as_slice()).assert();
+    fs::chmod(&bin.join("rustc"), io::USER_RWX).assert();
+
+    let mut manifest = String::from_str(r#"
+        [package]
+        name = "foo"
+        version = "0.0.1"
+        authors = []
+    "#);
+    let mut p = project("foo").file("src/main.rs", "fn main() {}");
+    for i in range(0u, 20) {
+        manifest.push_str(format!("[dependencies.dep{0}]\npath = \"dep{0}\"\n",
+                                  i).as_slice());
+        p = p.file(format!("dep{}/Cargo.toml", i).as_slice(), format!(r#"
+            [package]
+            name = "dep{}"
+            version = "0.0.1"
+            authors = []
+        "#, i).as_slice())
+             .file(format!("dep{}/src/lib.rs", i).as_slice(), "");
+    }
+    let p = p.file("Cargo.toml", manifest.as_slice());
+    p.build();
+
+    let path = format!("{}:{}", bin.display(), path);
+    assert_that(p.process(cargo_dir().join("cargo")).arg("build")
+                 .env("PATH", Some(path.as_slice())),
+                execs().with_status(0));
+    let probes = File::open(&log).read_to_string().assert();
+    assert_eq!(probes.as_slice().lines().count(), 1);
+})
+
+test!(build_writes_dep_info_for_artifacts {
+    let p = project("foo")
+        .file("Cargo.toml", r#"
+            [package]
+            name = "foo"
+            version = "0.5.0"
+            authors = []
+            build = "build.rs"
+
+            [dependencies.bar]
+            path = "bar"
+        "#)
+        .file("build.rs", r#"
+            fn main() {
+                println!("cargo:rerun-if-changed=include/foo.h");
+            }
+        "#)
+        .file("include/foo.h", "")
+        .file("src/lib.rs", r#"
+            extern crate bar;
+            #[path = "with space.rs"] mod spaced;
+            #[path = "odd$#.rs"] mod odd;
+        "#)
+        .file("src/with space.rs", "")
+        .file("src/odd$#.rs", "")
+        .file("bar/Cargo.toml", r#"
+            [package]
+            name = "bar"
+            version = "0.5.0"
+            authors = []
+        "#)
+        .file("bar/src/lib.rs", "");
+
+    assert_that(p.cargo_process("build"), execs().with_status(0));
+
+    let dep_info = p.root().join("target/debug/libfoo.d");
+    let contents = File::open(&dep_info).read_to_string().assert();
+    let pos = contents.as_slice().find_str(": ").unwrap();
+    let (artifacts, deps) = (contents.as_slice().slice_to(pos),
+                             contents.as_slice().slice_from(pos + 2).trim());
+    assert!(artifacts.contains("libfoo"), "artifacts: {}", artifacts);
+
+    let mut files = Vec::new();
+    let mut words = deps.split(' ');
+    loop {
+        let mut file = match words.next() {
+            Some(word) => word.to_string(),
+            None => break,
+        };
+        while file.as_slice().ends_with("\\") {
+            file.pop();
+            file.push(' ');
+            file.push_str(words.next().unwrap());
+        }
+        let file = file.replace("$$", "$").replace("\\#", "#");
+        files.push(Path::new(file));
+    }
+
+    for expected in ["src/lib.rs", "src/with space.rs", "src/odd$#.rs",
+                     "Cargo.toml", "build.rs", "include/foo.h",
+                     "bar/src/lib.rs", "bar/Cargo.toml"].iter() {
+        let expected = p.root().join(*expected);
+        assert!(files.contains(&expected), "`{}` missing from {}",
+                expected.display(), contents);
+    }
+})
+
+test!(config_link_args_apply_to_root_package {
+    let (_, target) = ::cargo::ops::rustc_version().unwrap();
+
+    let p = project("foo")
+        .file("Cargo.toml", r#"
+            [package]
+            name = "foo"
+            version = "0.5.0"
+            authors = []
+
+            [dependencies.bar]
+            path = "bar"
+        "#)
+        .file("src/lib.rs", "extern crate bar;")
+        .file("native/.keep", "")
+        .file(".cargo/config", format!(r#"
+            [target.{}]
+            rustc-link-lib = ["m"]
+            rustc-flags = "-L native"
+        "#, target).as_slice())
+        .file("bar/Cargo.toml", r#"
+            [package]
+            name = "bar"
+            version = "0.5.0"
+            authors = []
+        "#)
+        .file("bar/src/lib.rs", "");
+    p.build();
+
+    let output = p.process(cargo_dir().join("cargo")).arg("build").arg("-v")
+                  .exec_with_output().assert();
+    let output = str::from_utf8(output.output.as_slice()).assert();
+    let foo = output.lines().find(|l| l.contains("--crate-name foo")).unwrap();
+    let bar = output.lines().find(|l| l.contains("--crate-name bar")).unwrap();
+    // `-L native` is relative to the directory holding `.cargo/config`.
+    let native = format!("-L {} -l m", p.root().join("native").display());
+    assert!(foo.contains(native.as_slice()), "missing flags: {}", foo);
+    assert!(!bar.contains("native") && !bar.contains("-l m"),
+            "flags applied to a dependency: {}", bar);
+
+    // Changing the libraries rebuilds the root package, but not `bar`.
+    File::create(&p.root().join(".cargo/config")).write_str(format!(r#"
+        [target.{}]
+        rustc-link-lib = ["m", "dl"]
+    "#, target).as_slice()).assert();
+    assert_that(p.process(cargo_dir().join("cargo")).arg("build").arg("-v"),
+                execs().with_status(0).with_stdout(format!("\
+{fresh} bar v0.5.0 ({dir})
+{compiling} foo v0.5.0 ({dir})
+{running} `rustc src[..]lib.rs --crate-name foo [..] -l m -l dl`
+    Finished [..]
+", fresh = FRESH, compiling = COMPILING, running = RUNNING,
+   dir = p.url()).as_slice()));
+})
+
+test!(license_expressions {
+    let p = project("foo")
+        .file("Cargo.toml", r#"
+            [package]
+            name = "foo"
+            version = "0.5.0"
+            authors = []
+            license = "MIT/Apache-2.0 OR BSD-3-Clause"
+        "#)
+        .file("src/lib.rs", "");
+    assert_that(p.cargo_process("build"),
+                execs().with_status(0).with_stderr(""));
+
+    let p = project("bar")
+        .file("Cargo.toml", r#"
+            [package]
+            name = "bar"
+            version = "0.5.0"
+            authors = []
+            license = "mit or apache-2.0"
+        "#)
+        .file("src/lib.rs", "");
+    assert_that(p.cargo_process("build"),
+                execs().with_status(0).with_stderr("\
+the license `mit or apache-2.0` was normalized to `MIT OR Apache-2.0`
+"));
+
+    let p = project("baz")
+        .file("Cargo.toml", r#"
+            [package]
+            name = "baz"
+            version = "0.5.0"
+            authors = []
+            license = "MIT/Apache 2.0"
+        "#)
+        .file("src/lib.rs", "");
+    assert_that(p.cargo_process("build"),
+                execs().with_status(0).with_stderr("\
+`MIT/Apache 2.0` is not a valid SPDX license expression: `Apache 2.0` is not \
+a known license identifier, did you mean `Apache-2.0`?
+"));
+
+    let p = project("qux")
+        .file("Cargo.toml", r#"
+            [package]
+            name = "qux"
+            version = "0.5.0"
+            authors = []
+            license = "Proprietary"
+            license-file = "LICENSE"
+        "#)
+        .file("src/lib.rs", "");
+    assert_that(p.cargo_process("build"),
+                execs().with_status(0).with_stderr(""));
+})
@@ -1,9 +1,13 @@
-use std::io::File;
+use std::io::{fs, File};
+use std::io::process::ExitStatus;
+use std::io::timer;
+use std::str;
+use std::time::Duration;
 
 use support::{project, execs, cargo_dir};
-use support::{COMPILING, RUNNING, DOCTEST};
-use support::paths::PathExt;
-use hamcrest::{assert_that};
+use support::{COMPILING, RUNNING, DOCTEST, FRESH};
+use support::paths::{mod, PathExt};
+use hamcrest::{assert_that, existing_file};
 
 fn setup() {
 }
@@ -27,10 +31,10 @@ test!(custom_build_script_failed {
             }
         "#);
     assert_that(p.cargo_process("build").arg("-v"),
-                execs().with_status(101)
+                execs().with_status(2)
                        .with_stdout(format!("\
 {compiling} foo v0.5.0 ({url})
-{running} `rustc build.rs --crate-name build-script-build --crate-type bin [..]`
+{running} `[..]rustc build.rs --crate-name build-script-build --crate-type bin [..]`
 {running} `[..]build-script-build[..]`
 ",
 url = p.url(), compiling = COMPILING, running = RUNNING))
@@ -90,7 +94,10 @@ test!(custom_build_env_vars {
                 assert_eq!(opt.as_slice(), "0");
 
                 let opt = os::getenv("PROFILE").unwrap();
-                assert_eq!(opt.as_slice(), "compile");
+                assert_eq!(opt.as_slice(), "debug");
+
+                let host = os::getenv("HOST").unwrap();
+                assert_eq!(host, _target);
 
                 let debug = os::getenv("DEBUG").unwrap();
                 assert_eq!(debug.as_slice(), "true");
@@ -102,7 +109,7 @@ test!(custom_build_env_vars {
                 let _feat = os::getenv("CARGO_FEATURE_FOO").unwrap();
             }}
         "#,
-        p.root().join("target").join("build").display());
+        p.root().join("target").join("debug").join("build").display());
 
     let p = p.file("bar/build.rs", file_content);
 
@@ -131,7 +138,7 @@ test!(custom_build_script_wrong_rustc_flags {
         "#);
 
     assert_that(p.cargo_process("build"),
-                execs().with_status(101)
+                execs().with_status(1)
                        .with_stderr(format!("\
 Only `-l` and `-L` flags are allowed in build script of `foo v0.5.0 ({})`: \
 `-aaa -bbb`",
@@ -172,10 +179,10 @@ test!(custom_build_script_rustc_flags {
 
     // TODO: TEST FAILS BECAUSE OF WRONG STDOUT (but otherwise, the build works)
     assert_that(p.cargo_process("build").arg("--verbose"),
-                execs().with_status(101)
+                execs().with_status(2)
                        .with_stdout(format!("\
 {compiling} bar v0.5.0 ({url})
-{running} `rustc {dir}{sep}src{sep}lib.rs --crate-name test --crate-type lib -g \
+{running} `[..]rustc {dir}{sep}src{sep}lib.rs --crate-name test --crate-type lib -C debuginfo=2 \
         -C metadata=[..] \
         -C extra-filename=-[..] \
         --out-dir {dir}{sep}target \
@@ -202,13 +209,64 @@ test!(links_no_build_cmd {
         .file("src/lib.rs", "");
 
     assert_that(p.cargo_process("build"),
-                execs().with_status(101)
+                execs().with_status(1)
                        .with_stderr("\
 package `foo v0.5.0 (file://[..])` specifies that it links to `a` but does \
 not have a custom build script
 "));
 })
 
+test!(links_invalid_value {
+    let p = project("foo")
+        .file("Cargo.toml", r#"
+            [project]
+            name = "foo"
+            version = "0.5.0"
+            authors = []
+            links = "lib/a b"
+            build = "build.rs"
+        "#)
+        .file("src/lib.rs", "")
+        .file("build.rs", "fn main() {}");
+
+    assert_that(p.cargo_process("build"),
+                execs().with_status(3)
+                       .with_stderr("\
+Cargo.toml is not a valid manifest
+
+invalid value for `links`: `lib/a b`, only alphanumeric characters, `-`, `_` \
+and `.` are allowed, for example `z`, `git2` or `ssl`
+"));
+})
+
+test!(links_same_as_package_name_warns {
+    let p = project("foo")
+        .file("Cargo.toml", r#"
+            [project]
+            name = "foo"
+            version = "0.5.0"
+            authors = []
+            links = "foo"
+            build = "build.rs"
+        "#)
+        .file("src/lib.rs", "")
+        .file("build.rs", "fn main() {}");
+
+    assert_that(p.cargo_process("build"),
+                execs().with_status(0)
+                       .with_stderr("\
+`links` is set to `foo`, the name of the package itself, but it should be the \
+name of the native library the package links to, such as `z` for libz
+"));
+
+    assert_that(p.process(cargo_dir().join("cargo")).arg("read-manifest")
+                 .arg("--manifest-path").arg(p.root().join("Cargo.toml")),
+                execs().with_status(0)
+                       .with_stdout("\
+[..]\"links\":\"foo\",\"manifest_path\"[..]
+"));
+})
+
 test!(links_duplicates {
     let p = project("foo")
         .file("Cargo.toml", r#"
@@ -236,7 +294,7 @@ test!(links_duplicates {
         .file("a/build.rs", "");
 
     assert_that(p.cargo_process("build"),
-                execs().with_status(101)
+                execs().with_status(1)
                        .with_stderr("\
 native library `a` is being linked to by more than one package, and can only be \
 linked to by one package
@@ -289,11 +347,12 @@ test!(overrides_and_links {
                 execs().with_status(0)
                        .with_stdout(format!("\
 {compiling} foo v0.5.0 (file://[..])
-{running} `rustc build.rs [..]`
+{running} `[..]rustc build.rs [..]`
 {compiling} a v0.5.0 (file://[..])
-{running} `rustc [..] --crate-name a [..]`
+{running} `[..]rustc [..] --crate-name a [..]`
 {running} `[..]build-script-build[..]`
-{running} `rustc [..] --crate-name foo [..] -L foo -L bar[..]`
+{running} `[..]rustc [..] --crate-name foo [..] -L foo -L bar[..]`
+    Finished [..]
 ", compiling = COMPILING, running = RUNNING).as_slice()));
 })
 
@@ -361,13 +420,14 @@ test!(links_passes_env_vars {
                 execs().with_status(0)
                        .with_stdout(format!("\
 {compiling} [..] v0.5.0 (file://[..])
-{running} `rustc build.rs [..]`
+{running} `[..]rustc build.rs [..]`
 {compiling} [..] v0.5.0 (file://[..])
-{running} `rustc build.rs [..]`
+{running} `[..]rustc build.rs [..]`
 {running} `[..]`
 {running} `[..]`
 {running} `[..]`
-{running} `rustc [..] --crate-name foo [..]`
+{running} `[..]rustc [..] --crate-name foo [..]`
+    Finished [..]
 ", compiling = COMPILING, running = RUNNING).as_slice()));
 })
 
@@ -397,7 +457,50 @@ test!(only_rerun_build_script {
                        .with_stdout(format!("\
 {compiling} foo v0.5.0 (file://[..])
 {running} `[..]build-script-build[..]`
-{running} `rustc [..] --crate-name foo [..]`
+{running} `[..]rustc [..] --crate-name foo [..]`
+    Finished [..]
+", compiling = COMPILING, running = RUNNING).as_slice()));
+})
+
+test!(rerun_if_changed_outside_the_package {
+    let header = paths::root().join("header.h");
+    File::create(&header).write_str("").unwrap();
+
+    let p = project("foo")
+        .file("Cargo.toml", r#"
+            [project]
+            name = "foo"
+            version = "0.5.0"
+            authors = []
+            build = "build.rs"
+        "#)
+        .file("src/lib.rs", "")
+        .file("build.rs", format!(r#"
+            fn main() {{
+                println!("cargo:rerun-if-changed={}");
+            }}
+        "#, header.display()).as_slice());
+
+    assert_that(p.cargo_process("build").arg("-v"),
+                execs().with_status(0));
+    p.root().move_into_the_past().unwrap();
+    header.move_into_the_past().unwrap();
+
+    assert_that(p.process(cargo_dir().join("cargo")).arg("build").arg("-v"),
+                execs().with_status(0)
+                       .with_stdout(format!("\
+{fresh} foo v0.5.0 (file://[..])
+    Finished [..]
+", fresh = FRESH).as_slice()));
+
+    File::create(&header).write_str("#define FOO").unwrap();
+    assert_that(p.process(cargo_dir().join("cargo")).arg("build").arg("-v"),
+                execs().with_status(0)
+                       .with_stdout(format!("\
+{compiling} foo v0.5.0 (file://[..])
+{running} `[..]build-script-build[..]`
+{running} `[..]rustc [..] --crate-name foo [..]`
+    Finished [..]
 ", compiling = COMPILING, running = RUNNING).as_slice()));
 })
 
@@ -478,8 +581,9 @@ test!(testing_and_such {
                        .with_stdout(format!("\
 {compiling} foo v0.5.0 (file://[..])
 {running} `[..]build-script-build[..]`
-{running} `rustc [..] --crate-name foo [..]`
-{running} `rustc [..] --crate-name foo [..]`
+{running} `[..]rustc [..] --crate-name foo [..]`
+{running} `[..]rustc [..] --crate-name foo [..]`
+    Finished [..]
 {running} `[..]foo-[..]`
 
 running 0 tests
@@ -487,20 +591,25 @@ running 0 tests
 test result: ok. 0 passed; 0 failed; 0 ignored; 0 measured
 
 {doctest} foo
-{running} `rustdoc --test [..]`
+{running} `[..]rustdoc --test [..]`
 
 running 0 tests
 
 test result: ok. 0 passed; 0 failed; 0 ignored; 0 measured
 
+
+     Summary PASS, all 2 test targets passed
+      Passed [..] (0 passed; 0 failed; 0 ignored)
+      Passed [..] (0 passed; 0 failed; 0 ignored)
 ", compiling = COMPILING, running = RUNNING, doctest = DOCTEST).as_slice()));
 
     assert_that(p.process(cargo_dir().join("cargo")).arg("doc").arg("-v"),
                 execs().with_status(0)
                        .with_stdout(format!("\
 {compiling} foo v0.5.0 (file://[..])
-{running} `rustdoc [..]`
-{running} `rustc [..]`
+{running} `[..]rustdoc [..]`
+{running} `[..]rustc [..]`
+    Finished [..]
 ", compiling = COMPILING, running = RUNNING).as_slice()));
 
     File::create(&p.root().join("src/main.rs")).write_str("fn main() {}").unwrap();
@@ -508,6 +617,7 @@ test result: ok. 0 passed; 0 failed; 0 ignored; 0 measured
                 execs().with_status(0)
                        .with_stdout(format!("\
 {compiling} foo v0.5.0 (file://[..])
+    Finished [..]
 {running} `target[..]foo`
 ", compiling = COMPILING, running = RUNNING).as_slice()));
 })
@@ -560,13 +670,14 @@ test!(propagation_of_l_flags {
                 execs().with_status(0)
                        .with_stdout(format!("\
 {compiling} a v0.5.0 (file://[..])
-{running} `rustc build.rs [..]`
+{running} `[..]rustc build.rs [..]`
 {compiling} b v0.5.0 (file://[..])
-{running} `rustc [..] --crate-name b [..]-L foo[..]`
+{running} `[..]rustc [..] --crate-name b [..]-L foo[..]`
 {running} `[..]a-[..]build-script-build[..]`
-{running} `rustc [..] --crate-name a [..]-L bar[..]-L foo[..]`
+{running} `[..]rustc [..] --crate-name a [..]-L bar[..]-L foo[..]`
 {compiling} foo v0.5.0 (file://[..])
-{running} `rustc [..] --crate-name foo [..] -L bar -L foo`
+{running} `[..]rustc [..] --crate-name foo [..] -L bar -L foo`
+    Finished [..]
 ", compiling = COMPILING, running = RUNNING).as_slice()));
 })
 
@@ -598,11 +709,12 @@ test!(build_deps_simple {
                 execs().with_status(0)
                        .with_stdout(format!("\
 {compiling} a v0.5.0 (file://[..])
-{running} `rustc [..] --crate-name a [..]`
+{running} `[..]rustc [..] --crate-name a [..]`
 {compiling} foo v0.5.0 (file://[..])
-{running} `rustc build.rs [..] --extern a=[..]`
+{running} `[..]rustc build.rs [..] --extern a=[..]`
 {running} `[..]foo-[..]build-script-build[..]`
-{running} `rustc [..] --crate-name foo [..]`
+{running} `[..]rustc [..] --crate-name foo [..]`
+    Finished [..]
 ", compiling = COMPILING, running = RUNNING).as_slice()));
 })
 
@@ -632,7 +744,7 @@ test!(build_deps_not_for_normal {
         .file("a/src/lib.rs", "");
 
     assert_that(p.cargo_process("build").arg("-v").arg("--target").arg(target),
-                execs().with_status(101)
+                execs().with_status(2)
                        .with_stderr("\
 [..]lib.rs[..] error: can't find crate for `a`
 [..]lib.rs[..] extern crate a;
@@ -686,25 +798,26 @@ test!(build_cmd_with_a_build_cmd {
                 execs().with_status(0)
                        .with_stdout(format!("\
 {compiling} b v0.5.0 (file://[..])
-{running} `rustc [..] --crate-name b [..]`
+{running} `[..]rustc [..] --crate-name b [..]`
 {compiling} a v0.5.0 (file://[..])
-{running} `rustc build.rs [..] --extern b=[..]`
+{running} `[..]rustc build.rs [..] --extern b=[..]`
 {running} `[..]a-[..]build-script-build[..]`
-{running} `rustc [..]lib.rs --crate-name a --crate-type lib -g \
+{running} `[..]rustc [..]lib.rs --crate-name a --crate-type lib -C debuginfo=2 \
     -C metadata=[..] -C extra-filename=-[..] \
     --out-dir [..]target[..]deps --dep-info [..]fingerprint[..]dep-lib-a \
     -L [..]target[..]deps -L [..]target[..]deps`
 {compiling} foo v0.5.0 (file://[..])
-{running} `rustc build.rs --crate-name build-script-build --crate-type bin \
-    -C prefer-dynamic -g \
+{running} `[..]rustc build.rs --crate-name build-script-build --crate-type bin \
+    -C prefer-dynamic -C debuginfo=2 \
     --out-dir [..]build[..]foo-[..] --dep-info [..]fingerprint[..]dep-[..] \
-    -L [..]target -L [..]target[..]deps \
+    -L [..]target[..]debug -L [..]target[..]deps \
     --extern a=[..]liba-[..].rlib`
 {running} `[..]foo-[..]build-script-build[..]`
-{running} `rustc [..]lib.rs --crate-name foo --crate-type lib -g \
+{running} `[..]rustc [..]lib.rs --crate-name foo --crate-type lib -C debuginfo=2 \
     -C metadata=[..] -C extra-filename=-[..] \
-    --out-dir [..]target --dep-info [..]fingerprint[..]dep-lib-foo \
-    -L [..]target -L [..]target[..]deps`
+    --out-dir [..]target[..]deps --dep-info [..]fingerprint[..]dep-lib-foo \
+    -L [..]target[..]debug -L [..]target[..]deps`
+    Finished [..]
 ", compiling = COMPILING, running = RUNNING).as_slice()));
 })
 
@@ -772,12 +885,12 @@ test!(output_separate_lines {
             }
         "#);
     assert_that(p.cargo_process("build").arg("-v"),
-                execs().with_status(101)
+                execs().with_status(2)
                        .with_stdout(format!("\
 {compiling} foo v0.5.0 (file://[..])
-{running} `rustc build.rs [..]`
+{running} `[..]rustc build.rs [..]`
 {running} `[..]foo-[..]build-script-build[..]`
-{running} `rustc [..] --crate-name foo [..] -L foo -l foo:static`
+{running} `[..]rustc [..] --crate-name foo [..] -L foo -l foo:static`
 ", compiling = COMPILING, running = RUNNING).as_slice()));
 })
 
@@ -815,6 +928,7 @@ test!(code_generation {
                 execs().with_status(0)
                        .with_stdout(format!("\
 {compiling} foo v0.5.0 (file://[..])
+    Finished [..]
 {running} `target[..]foo`
 Hello, World!
 ", compiling = COMPILING, running = RUNNING).as_slice()));
@@ -837,3 +951,377 @@ test!(release_with_build_script {
     assert_that(p.cargo_process("build").arg("-v").arg("--release"),
                 execs().with_status(0));
 })
+
+#[cfg(unix)]
+test!(interrupted_build_is_resumed {
+    let p = project("foo")
+        .file("Cargo.toml", r#"
+            [project]
+
+            name = "foo"
+            version = "0.5.0"
+            authors = []
+            build = "build.rs"
+        "#)
+        .file("src/lib.rs", "")
+        .file("build.rs", r#"
+            use std::io::timer;
+            use std::os;
+            use std::time::Duration;
+            fn main() {
+                let dir = Path::new(os::getenv("CARGO_MANIFEST_DIR").unwrap());
+                if dir.join("slow").exists() {
+                    timer::sleep(Duration::milliseconds(60000));
+                }
+            }
+        "#)
+        .file("slow", "");
+
+    // Interrupt only cargo while the build script is running. Cargo forwards
+    // the interrupt to the script, waits for it to exit and then exits
+    // without compiling the library, long before the script would have
+    // finished sleeping.
+    let mut child = p.cargo_process("build").build_command().spawn().unwrap();
+    timer::sleep(Duration::milliseconds(1500));
+    child.signal(2 /* SIGINT */).unwrap();
+    child.set_timeout(Some(20000));
+    assert_eq!(child.wait().unwrap(), ExitStatus(130));
+    fs::unlink(&p.root().join("slow")).unwrap();
+
+    // The next build picks up where the last one left off, and the library is
+    // rebuilt as its fingerprint was never recorded.
+    assert_that(p.process(cargo_dir().join("cargo")).arg("build"),
+                execs().with_status(0)
+                       .with_stdout(format!("{compiling} foo v0.5.0 ({url})\n    \
+                                             Finished [..]\n",
+                                            compiling = COMPILING,
+                                            url = p.url())));
+})
+
+test!(build_script_sees_active_features {
+    let p = project("foo")
+        .file("Cargo.toml", r#"
+            [project]
+            name = "foo"
+            version = "0.5.0"
+            authors = []
+            build = "build.rs"
+
+            [dependencies.a]
+            path = "a"
+
+            [features]
+            extra-thing = []
+        "#)
+        .file("src/main.rs", "fn main() {}")
+        .file("build.rs", r#"
+            use std::io::File;
+            use std::os;
+
+            fn main() {
+                let out = Path::new(os::getenv("OUT_DIR").unwrap());
+                let active = os::getenv("CARGO_FEATURE_EXTRA_THING").is_some();
+                File::create(&out.join("features.txt")).write_str(
+                    active.to_string().as_slice()).unwrap();
+            }
+        "#)
+        .file("a/Cargo.toml", r#"
+            [project]
+            name = "a"
+            version = "0.5.0"
+            authors = []
+        "#)
+        .file("a/src/lib.rs", "");
+
+    let features_seen = || {
+        let build = p.root().join("target").join("debug").join("build");
+        let file = fs::walk_dir(&build).unwrap().find(|path| {
+            path.filename_str() == Some("features.txt")
+        }).unwrap();
+        File::open(&file).read_to_string().unwrap()
+    };
+
+    assert_that(p.cargo_process("build").arg("--features").arg("extra-thing"),
+                execs().with_status(0).with_stdout(format!("\
+{compiling} a v0.5.0 ({url})
+{compiling} foo v0.5.0 ({url})
+    Finished [..]
+", compiling = COMPILING, url = p.url())));
+    assert_eq!(features_seen().as_slice(), "true");
+
+    // Only the package whose features changed is rebuilt.
+    assert_that(p.process(cargo_dir().join("cargo")).arg("build"),
+                execs().with_status(0).with_stdout(format!("\
+{compiling} foo v0.5.0 ({url})
+    Finished [..]
+", compiling = COMPILING, url = p.url())));
+    assert_eq!(features_seen().as_slice(), "false");
+
+    assert_that(p.process(cargo_dir().join("cargo")).arg("build"),
+                execs().with_status(0).with_stdout("    Finished [..]\n"));
+})
+
+test!(build_script_sees_release_profile {
+    let p = project("foo")
+        .file("Cargo.toml", r#"
+            [project]
+            name = "foo"
+            version = "0.5.0"
+            authors = []
+            build = "build.rs"
+        "#)
+        .file("src/main.rs", "fn main() {}")
+        .file("build.rs", r#"
+            use std::os;
+
+            fn main() {
+                let release = os::getenv("EXPECT_RELEASE").is_some();
+                let profile = os::getenv("PROFILE").unwrap();
+                let opt = os::getenv("OPT_LEVEL").unwrap();
+                let debug = os::getenv("DEBUG").unwrap();
+                if release {
+                    assert_eq!(profile.as_slice(), "release");
+                    assert_eq!(opt.as_slice(), "3");
+                    assert_eq!(debug.as_slice(), "false");
+                } else {
+                    assert_eq!(profile.as_slice(), "debug");
+                    assert_eq!(opt.as_slice(), "0");
+                    assert_eq!(debug.as_slice(), "true");
+                }
+                assert!(os::getenv("HOST").is_some());
+                assert!(os::getenv("TARGET").is_some());
+                assert!(os::getenv("NUM_JOBS").is_some());
+                assert!(os::getenv("CARGO_MANIFEST_DIR").is_some());
+            }
+        "#);
+
+    assert_that(p.cargo_process("build"), execs().with_status(0));
+    assert_that(p.process(cargo_dir().join("cargo")).arg("build").arg("--release")
+                 .env("EXPECT_RELEASE", Some("1")),
+                execs().with_status(0));
+})
+
+test!(regenerated_code_rebuilds_binary {
+    let build = |value: &str| format!(r#"
+        use std::os;
+        use std::io::File;
+
+        fn main() {{
+            let dst = Path::new(os::getenv("OUT_DIR").unwrap());
+            let mut f = File::create(&dst.join("gen.rs")).unwrap();
+            f.write_str("pub static VALUE: &'static str = \"{}\";").unwrap();
+        }}
+    "#, value);
+    let p = project("foo")
+        .file("Cargo.toml", r#"
+            [project]
+            name = "foo"
+            version = "0.5.0"
+            authors = []
+            build = "build.rs"
+        "#)
+        .file("src/lib.rs", "pub fn foo() {}")
+        .file("src/main.rs", r#"
+            extern crate foo;
+            include!(concat!(env!("OUT_DIR"), "/gen.rs"))
+
+            fn main() {
+                foo::foo();
+                println!("{}", VALUE);
+            }
+        "#)
+        .file("build.rs", build("first").as_slice());
+    assert_that(p.cargo_process("build"), execs().with_status(0));
+    assert_that(p.process(p.bin("foo")),
+                execs().with_status(0).with_stdout("first\n"));
+
+    // The library doesn't change, but the binary must still pick up the newly
+    // generated code in the same build.
+    p.root().move_into_the_past().unwrap();
+    File::create(&p.root().join("build.rs"))
+         .write_str(build("second").as_slice()).unwrap();
+    assert_that(p.process(cargo_dir().join("cargo")).arg("build"),
+                execs().with_status(0));
+    assert_that(p.process(p.bin("foo")),
+                execs().with_status(0).with_stdout("second\n"));
+
+    // Nothing changed, so nothing is rebuilt.
+    assert_that(p.process(cargo_dir().join("cargo")).arg("build"),
+                execs().with_status(0).with_stdout("    Finished [..]\n"));
+})
+
+// Builds a package whose build script counts its runs, with `extra` added to
+// the `[package]` section, in debug, release and then debug mode again.
+// Returns the number of runs.
+fn build_script_runs_across_profiles(extra: &str) -> uint {
+    let p = project("foo")
+        .file("Cargo.toml", format!(r#"
+            [project]
+            name = "foo"
+            version = "0.5.0"
+            authors = []
+            build = "build.rs"
+            {}
+        "#, extra).as_slice())
+        .file("src/main.rs", r#"
+            include!(concat!(env!("OUT_DIR"), "/gen.rs"))
+            fn main() { println!("{}", VALUE) }
+        "#)
+        .file("build.rs", r#"
+            use std::os;
+            use std::io::{File, Append, Write};
+
+            fn main() {
+                let root = Path::new(os::getenv("CARGO_MANIFEST_DIR").unwrap());
+                File::open_mode(&root.join("runs"), Append, Write).unwrap()
+                     .write_str("ran\n").unwrap();
+                let dst = Path::new(os::getenv("OUT_DIR").unwrap());
+                File::create(&dst.join("gen.rs")).unwrap()
+                     .write_str("static VALUE: uint = 42;").unwrap();
+            }
+        "#);
+
+    assert_that(p.cargo_process("build"), execs().with_status(0));
+    assert_that(p.process(cargo_dir().join("cargo")).arg("build")
+                 .arg("--release"),
+                execs().with_status(0));
+    assert_that(p.process(cargo_dir().join("cargo")).arg("build"),
+                execs().with_status(0));
+    assert_that(p.process(p.release_bin("foo")),
+                execs().with_status(0).with_stdout("42\n"));
+
+    let runs = File::open(&p.root().join("runs")).read_to_string().unwrap();
+    runs.as_slice().lines().count()
+}
+
+test!(build_script_runs_per_profile {
+    assert_eq!(build_script_runs_across_profiles(""), 2);
+})
+
+test!(build_script_shared_between_profiles {
+    assert_eq!(build_script_runs_across_profiles("build-per-profile = false"),
+               1);
+})
+
+test!(build_script_cfgs_reach_rustc_and_rustdoc {
+    let p = project("foo")
+        .file("Cargo.toml", r#"
+            [project]
+            name = "foo"
+            version = "0.5.0"
+            authors = []
+            build = "build.rs"
+        "#)
+        .file("src/lib.rs", r#"
+            #[cfg(has_avx2)]
+            pub fn avx2() {}
+            #[cfg(has_sse)]
+            pub fn sse() {}
+
+            #[cfg(not(any(has_avx2, has_sse)))]
+            pub fn missing_cfg() { missing }
+        "#)
+        .file("cfg.txt", "has_avx2")
+        .file("build.rs", r#"
+            use std::io::File;
+            use std::os;
+
+            fn main() {
+                let dir = Path::new(os::getenv("CARGO_MANIFEST_DIR").unwrap());
+                let cfg = File::open(&dir.join("cfg.txt")).read_to_string()
+                              .unwrap();
+                println!("cargo:rustc-cfg={}", cfg.as_slice().trim());
+            }
+        "#);
+
+    assert_that(p.cargo_process("doc").arg("-v"),
+                execs().with_status(0)
+                       .with_stdout(format!("\
+{compiling} foo v0.5.0 (file://[..])
+{running} `[..]rustc build.rs [..]`
+{running} `[..]build-script-build[..]`
+{running} `[..]rustdoc [..] --cfg has_avx2`
+    Finished [..]
+", compiling = COMPILING, running = RUNNING).as_slice()));
+    assert_that(&p.root().join("target/doc/foo/fn.avx2.html"), existing_file());
+    p.root().move_into_the_past().unwrap();
+
+    // The build script reruns during a plain build, the documentation has to
+    // catch up with the new cfgs the next time it's generated.
+    File::create(&p.root().join("cfg.txt")).write_str("has_sse").unwrap();
+    p.root().move_into_the_past().unwrap();
+    assert_that(p.process(cargo_dir().join("cargo")).arg("build"),
+                execs().with_status(0));
+    assert_that(p.process(cargo_dir().join("cargo")).arg("doc"),
+                execs().with_status(0));
+    assert_that(&p.root().join("target/doc/foo/fn.sse.html"), existing_file());
+
+    assert_that(p.process(cargo_dir().join("cargo")).arg("doc"),
+                execs().with_status(0).with_stdout("    Finished [..]\n"));
+})
+
+test!(very_verbose_shows_build_script_output {
+    let p = project("foo")
+        .file("Cargo.toml", r#"
+            [project]
+            name = "foo"
+            version = "0.5.0"
+            authors = []
+            build = "build.rs"
+        "#)
+        .file("src/lib.rs", "")
+        .file("build.rs", r#"
+            fn main() {
+                println!("hello from the build script");
+            }
+        "#);
+
+    let output = p.cargo_process("build").arg("-vv")
+                  .exec_with_output().unwrap();
+    let output = str::from_utf8(output.output.as_slice()).unwrap();
+    assert!(output.lines().any(|l| {
+        l == "[foo 0.5.0] hello from the build script"
+    }), "{}", output);
+
+    // A single `-v` only shows the commands.
+    assert_that(p.process(cargo_dir().join("cargo")).arg("clean"),
+                execs().with_status(0));
+    let output = p.process(cargo_dir().join("cargo")).arg("build").arg("-v")
+                  .exec_with_output().unwrap();
+    let output = str::from_utf8(output.output.as_slice()).unwrap();
+    assert!(!output.contains("hello from the build script"), "{}", output);
+})
+
+test!(slow_build_script_warns {
+    let p = project("foo")
+        .file("Cargo.toml", r#"
+            [project]
+            name = "foo"
+            version = "0.5.0"
+            authors = []
+            build = "build.rs"
+        "#)
+        .file("src/lib.rs", "")
+        .file("build.rs", r#"
+            use std::io::timer;
+            use std::time::Duration;
+            fn main() {
+                timer::sleep(Duration::milliseconds(3000));
+            }
+        "#);
+    assert_that(p.cargo_process("build"),
+                execs().with_status(0)
+                       .with_stdout(format!("{compiling} foo v0.5.0 ({url})\n    \
+                                             Finished [..]\n",
+                                            compiling = COMPILING,
+                                            url = p.url()))
+                       .with_stderr(format!("the build script of `foo v0.5.0 \
+                                             ({url})` took [..]s to run, \
+                                             longer than the [..]s it took to \
+                                             compile the package\n",
+                                            url = p.url())));
+
+    // A fresh build script didn't run, so there is nothing to warn about
+    assert_that(p.process(cargo_dir().join("cargo")).arg("build"),
+                execs().with_status(0).with_stderr(""));
+})
@@ -121,7 +121,8 @@ test!(cargo_compile_simple_git_dep {
         execs()
         .with_stdout(format!("{} git repository `{}`\n\
                               {} dep1 v0.5.0 ({}#[..])\n\
-                              {} foo v0.5.0 ({})\n",
+                              {} foo v0.5.0 ({})\n    \
+                              Finished [..]\n",
                              UPDATING, path2url(git_root.clone()),
                              COMPILING, path2url(git_root),
                              COMPILING, path2url(root)))
@@ -188,7 +189,8 @@ test!(cargo_compile_git_dep_branch {
         execs()
         .with_stdout(format!("{} git repository `{}`\n\
                               {} dep1 v0.5.0 ({}?ref=branchy#[..])\n\
-                              {} foo v0.5.0 ({})\n",
+                              {} foo v0.5.0 ({})\n    \
+                              Finished [..]\n",
                              UPDATING, path2url(git_root.clone()),
                              COMPILING, path2url(git_root),
                              COMPILING, path2url(root)))
@@ -258,7 +260,8 @@ test!(cargo_compile_git_dep_tag {
         execs()
         .with_stdout(format!("{} git repository `{}`\n\
                               {} dep1 v0.5.0 ({}?ref=v0.1.0#[..])\n\
-                              {} foo v0.5.0 ({})\n",
+                              {} foo v0.5.0 ({})\n    \
+                              Finished [..]\n",
                              UPDATING, path2url(git_root.clone()),
                              COMPILING, path2url(git_root),
                              COMPILING, path2url(root)))
@@ -419,8 +422,8 @@ test!(cargo_compile_with_meta_package {
       execs().with_stdout("this is dep1 this is dep2\n"));
 })
 
-test!(cargo_compile_with_short_ssh_git {
-    let url = "git@github.com:a/dep";
+test!(cargo_compile_with_invalid_git_url {
+    let url = "github.com/a/dep";
 
     let project = project("project")
         .file("Cargo.toml", format!(r#"
@@ -558,14 +561,15 @@ test!(recompilation {
     assert_that(p.cargo_process("build"),
                 execs().with_stdout(format!("{} git repository `{}`\n\
                                              {} bar v0.5.0 ({}#[..])\n\
-                                             {} foo v0.5.0 ({})\n",
+                                             {} foo v0.5.0 ({})\n    \
+                                             Finished [..]\n",
                                             UPDATING, git_project.url(),
                                             COMPILING, git_project.url(),
                                             COMPILING, p.url())));
 
     // Don't recompile the second time
     assert_that(p.process(cargo_dir().join("cargo")).arg("build"),
-                execs().with_stdout(""));
+                execs().with_stdout("    Finished [..]\n"));
 
     // Modify a file manually, shouldn't trigger a recompile
     File::create(&git_project.root().join("src/bar.rs")).write_str(r#"
@@ -573,7 +577,7 @@ test!(recompilation {
     "#).assert();
 
     assert_that(p.process(cargo_dir().join("cargo")).arg("build"),
-                execs().with_stdout(""));
+                execs().with_stdout("    Finished [..]\n"));
 
     assert_that(p.process(cargo_dir().join("cargo")).arg("update"),
                 execs().with_stdout(format!("{} git repository `{}`",
@@ -581,7 +585,7 @@ test!(recompilation {
                                             git_project.url())));
 
     assert_that(p.process(cargo_dir().join("cargo")).arg("build"),
-                execs().with_stdout(""));
+                execs().with_stdout("    Finished [..]\n"));
 
     // Commit the changes and make sure we don't trigger a recompile because the
     // lockfile says not to change
@@ -591,18 +595,21 @@ test!(recompilation {
 
     println!("compile after commit");
     assert_that(p.process(cargo_dir().join("cargo")).arg("build"),
-                execs().with_stdout(""));
+                execs().with_stdout("    Finished [..]\n"));
     p.root().move_into_the_past().assert();
 
     // Update the dependency and carry on!
     assert_that(p.process(cargo_dir().join("cargo")).arg("update"),
-                execs().with_stdout(format!("{} git repository `{}`",
+                execs().with_stdout(format!("{} git repository `{}`\n\
+                                             {} bar v0.5.0 ([..]) -> ([..])",
                                             UPDATING,
-                                            git_project.url())));
+                                            git_project.url(),
+                                            UPDATING)));
     println!("going for the last compile");
     assert_that(p.process(cargo_dir().join("cargo")).arg("build"),
                 execs().with_stdout(format!("{} bar v0.5.0 ({}#[..])\n\
-                                             {} foo v0.5.0 ({})\n",
+                                             {} foo v0.5.0 ({})\n    \
+                                             Finished [..]\n",
                                             COMPILING, git_project.url(),
                                             COMPILING, p.url())));
 
@@ -611,7 +618,8 @@ test!(recompilation {
                  .arg("-p").arg("foo"),
                 execs().with_stdout(""));
     assert_that(p.process(cargo_dir().join("cargo")).arg("build"),
-                execs().with_stdout(format!("{} foo v0.5.0 ({})\n",
+                execs().with_stdout(format!("{} foo v0.5.0 ({})\n    \
+                                             Finished [..]\n",
                                             COMPILING, p.url())));
 })
 
@@ -680,7 +688,7 @@ test!(update_with_shared_deps {
 {compiling} bar v0.5.0 ({git}#[..])
 {compiling} [..] v0.5.0 ({dir})
 {compiling} [..] v0.5.0 ({dir})
-{compiling} foo v0.5.0 ({dir})\n",
+{compiling} foo v0.5.0 ({dir})\n    Finished [..]\n",
                     updating = UPDATING, git = git_project.url(),
                     compiling = COMPILING, dir = p.url())));
 
@@ -711,9 +719,11 @@ test!(update_with_shared_deps {
     println!("dep1 aggressive update");
     assert_that(p.process(cargo_dir().join("cargo")).arg("update").arg("dep1")
                  .arg("--aggressive"),
-                execs().with_stdout(format!("{} git repository `{}`",
+                execs().with_stdout(format!("{} git repository `{}`\n\
+                                             {} bar v0.5.0 ([..]) -> ([..])",
                                             UPDATING,
-                                            git_project.url())));
+                                            git_project.url(),
+                                            UPDATING)));
 
     // Make sure we still only compile one version of the git repo
     println!("build");
@@ -722,7 +732,7 @@ test!(update_with_shared_deps {
 {compiling} bar v0.5.0 ({git}#[..])
 {compiling} [..] v0.5.0 ({dir})
 {compiling} [..] v0.5.0 ({dir})
-{compiling} foo v0.5.0 ({dir})\n",
+{compiling} foo v0.5.0 ({dir})\n    Finished [..]\n",
                     git = git_project.url(),
                     compiling = COMPILING, dir = p.url())));
 
@@ -819,7 +829,8 @@ test!(two_deps_only_update_one {
                               {} git repository `[..]`\n\
                               {} [..] v0.5.0 ([..])\n\
                               {} [..] v0.5.0 ([..])\n\
-                              {} foo v0.5.0 ({})\n",
+                              {} foo v0.5.0 ({})\n    \
+                              Finished [..]\n",
                              UPDATING,
                              UPDATING,
                              COMPILING,
@@ -837,8 +848,9 @@ test!(two_deps_only_update_one {
     assert_that(project.process(cargo_dir().join("cargo")).arg("update")
                        .arg("-p").arg("dep1"),
         execs()
-        .with_stdout(format!("{} git repository `{}`\n",
-                             UPDATING, git1.url()))
+        .with_stdout(format!("{} git repository `{}`\n\
+                              {} dep1 v0.5.0 ([..]) -> ([..])\n",
+                             UPDATING, git1.url(), UPDATING))
         .with_stderr(""));
 })
 
@@ -908,6 +920,7 @@ test!(stale_cached_version {
 {updating} git repository `{bar}`
 {compiling} bar v0.0.0 ({bar}#[..])
 {compiling} foo v0.0.0 ({foo})
+    Finished [..]
 ", updating = UPDATING, compiling = COMPILING, bar = bar.url(), foo = foo.url())));
     assert_that(foo.process(foo.bin("foo")), execs().with_status(0));
 })
@@ -957,7 +970,8 @@ test!(dep_with_changed_submodule {
     assert_that(project.cargo_process("run"), execs()
                 .with_stdout(format!("{} git repository `[..]`\n\
                                       {} dep1 v0.5.0 ([..])\n\
-                                      {} foo v0.5.0 ([..])\n\
+                                      {} foo v0.5.0 ([..])\n    \
+                                      Finished [..]\n\
                                       {} `target[..]foo`\n\
                                       project2\
                                       ",
@@ -995,14 +1009,17 @@ test!(dep_with_changed_submodule {
     assert_that(project.process(cargo_dir().join("cargo")).arg("update").arg("-v"),
                 execs()
                 .with_stderr("")
-                .with_stdout(format!("{} git repository `{}`",
+                .with_stdout(format!("{} git repository `{}`\n\
+                                      {} dep1 v0.5.0 ([..]) -> ([..])",
                                      UPDATING,
-                                     git_project.url())));
+                                     git_project.url(),
+                                     UPDATING)));
 
     println!("last run");
     assert_that(project.process(cargo_dir().join("cargo")).arg("run"), execs()
                 .with_stdout(format!("{compiling} dep1 v0.5.0 ([..])\n\
-                                      {compiling} foo v0.5.0 ([..])\n\
+                                      {compiling} foo v0.5.0 ([..])\n    \
+                                      Finished [..]\n\
                                       {running} `target[..]foo`\n\
                                       project3\
                                       ",
@@ -1052,6 +1069,7 @@ test!(dev_deps_with_testing {
         execs().with_stdout(format!("\
 {updating} git repository `{bar}`
 {compiling} foo v0.5.0 ({url})
+    Finished [..]
 ", updating = UPDATING, compiling = COMPILING, url = p.url(), bar = p2.url())));
 
     // Make sure we use the previous resolution of `bar` instead of updating it
@@ -1060,6 +1078,7 @@ test!(dev_deps_with_testing {
         execs().with_stdout(format!("\
 {compiling} [..] v0.5.0 ([..])
 {compiling} [..] v0.5.0 ([..]
+    Finished [..]
 {running} target[..]foo-[..]
 
 running 1 test
@@ -1067,6 +1086,9 @@ test tests::foo ... ok
 
 test result: ok. 1 passed; 0 failed; 0 ignored; 0 measured
 
+
+     Summary PASS, all 1 test targets passed
+      Passed [..] (1 passed; 0 failed; 0 ignored)
 ", compiling = COMPILING, running = RUNNING)));
 })
 
@@ -1092,20 +1114,21 @@ test!(git_build_cmd_freshness {
                 execs().with_status(0)
                        .with_stdout(format!("\
 {compiling} foo v0.0.0 ({url})
+    Finished [..]
 ", compiling = COMPILING, url = foo.url())));
 
     // Smoke test to make sure it doesn't compile again
     println!("first pass");
     assert_that(foo.process(cargo_dir().join("cargo")).arg("build"),
                 execs().with_status(0)
-                       .with_stdout(""));
+                       .with_stdout("    Finished [..]\n"));
 
     // Modify an ignored file and make sure we don't rebuild
     println!("second pass");
     File::create(&foo.root().join("src/bar.rs")).assert();
     assert_that(foo.process(cargo_dir().join("cargo")).arg("build"),
                 execs().with_status(0)
-                       .with_stdout(""));
+                       .with_stdout("    Finished [..]\n"));
 })
 
 test!(git_name_not_always_needed {
@@ -1144,6 +1167,7 @@ test!(git_name_not_always_needed {
         execs().with_stdout(format!("\
 {updating} git repository `{bar}`
 {compiling} foo v0.5.0 ({url})
+    Finished [..]
 ", updating = UPDATING, compiling = COMPILING, url = p.url(), bar = p2.url())));
 })
 
@@ -1177,6 +1201,7 @@ test!(git_repo_changing_no_rebuild {
 {updating} git repository `{bar}`
 {compiling} [..]
 {compiling} [..]
+    Finished [..]
 ", updating = UPDATING, compiling = COMPILING, bar = bar.url())));
 
     // Make a commit to lock p2 to a different rev
@@ -1203,12 +1228,13 @@ test!(git_repo_changing_no_rebuild {
 {updating} git repository `{bar}`
 {compiling} [..]
 {compiling} [..]
+    Finished [..]
 ", updating = UPDATING, compiling = COMPILING, bar = bar.url())));
 
     // And now for the real test! Make sure that p1 doesn't get rebuilt
     // even though the git repo has changed.
     assert_that(p1.process(cargo_dir().join("cargo")).arg("build"),
-                execs().with_stdout(""));
+                execs().with_stdout("    Finished [..]\n"));
 })
 
 test!(git_dep_build_cmd {
@@ -1326,7 +1352,8 @@ test!(warnings_in_git_dep {
         execs()
         .with_stdout(format!("{} git repository `{}`\n\
                               {} bar v0.5.0 ({}#[..])\n\
-                              {} foo v0.5.0 ({})\n",
+                              {} foo v0.5.0 ({})\n    \
+                              Finished [..]\n",
                              UPDATING, bar.url(),
                              COMPILING, bar.url(),
                              COMPILING, p.url()))
@@ -1381,7 +1408,7 @@ test!(update_ambiguous {
     assert_that(p.cargo_process("generate-lockfile"), execs().with_status(0));
     assert_that(p.process(cargo_dir().join("cargo")).arg("update")
                  .arg("-p").arg("foo"),
-                execs().with_status(101)
+                execs().with_status(1)
                        .with_stderr("\
 There are multiple `foo` packages in your project, and the specification `foo` \
 is ambiguous.
@@ -1487,6 +1514,7 @@ Updating git repository `{}`
 {compiling} transitive [..]
 {compiling} dep [..]
 {compiling} project [..]
+    Finished [..]
 ", dep1.url(), transitive.url(), compiling = COMPILING)));
 
     // Update the dependency to point to the second repository, but this
@@ -1506,6 +1534,7 @@ Updating git repository `{}`
 Updating git repository `{}`
 {compiling} dep [..]
 {compiling} project [..]
+    Finished [..]
 ", dep2.url(), compiling = COMPILING)));
 })
 
@@ -1563,3 +1592,46 @@ test!(update_one_source_updates_all_packages_in_that_git_source {
     assert!(!lockfile.as_slice().contains(rev1.to_string().as_slice()),
             "{} in {}", rev1, lockfile);
 })
+
+test!(status_shows_short_revision {
+    let git_project = git_repo("bar", |project| {
+        project.file("Cargo.toml", r#"
+                [package]
+                name = "bar"
+                version = "0.5.0"
+                authors = []
+            "#)
+            .file("src/lib.rs", "")
+    }).assert();
+
+    let repo = git2::Repository::open(&git_project.root()).unwrap();
+    let rev = repo.revparse_single("HEAD").unwrap().id().to_string();
+
+    let p = project("foo")
+        .file("Cargo.toml", format!(r#"
+            [project]
+            name = "foo"
+            version = "0.5.0"
+            authors = []
+
+            [dependencies.bar]
+            git = '{}'
+        "#, git_project.url()))
+        .file("src/lib.rs", "");
+
+    assert_that(p.cargo_process("build"),
+                execs().with_status(0).with_stdout(format!("\
+{updating} git repository `{git}`
+{compiling} bar v0.5.0 ({git}#{rev})
+{compiling} foo v0.5.0 ({dir})
+    Finished [..]
+",
+                updating = UPDATING, compiling = COMPILING,
+                git = git_project.url(), rev = rev.as_slice().slice_to(7),
+                dir = p.url())));
+
+    // The lock file records the full revision
+    let lockfile = File::open(&p.root().join("Cargo.lock")).read_to_string()
+                                                              .unwrap();
+    assert!(lockfile.as_slice().contains(rev.as_slice()));
+})
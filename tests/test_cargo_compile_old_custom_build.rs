@@ -42,7 +42,8 @@ test!(old_custom_build {
         "#);
     assert_that(p.cargo_process("build"),
                 execs().with_status(0)
-                       .with_stdout(format!("   Compiling foo v0.5.0 ({})\n",
+                       .with_stdout(format!("   Compiling foo v0.5.0 ({})\n    \
+                                             Finished [..]\n",
                                             p.url()))
                        .with_stderr("warning: [..] deprecated.\n\
                                               [..]\n\
@@ -108,7 +109,8 @@ test!(old_custom_multiple_build {
         "#);
     assert_that(p.cargo_process("build"),
                 execs().with_status(0)
-                       .with_stdout(format!("   Compiling foo v0.5.0 ({})\n",
+                       .with_stdout(format!("   Compiling foo v0.5.0 ({})\n    \
+                                             Finished [..]\n",
                                             p.url())));
 })
 
@@ -148,7 +150,7 @@ test!(old_custom_build_failure {
             fn main() {}
         "#);
     assert_that(p.cargo_process("build"),
-                execs().with_status(101).with_stderr(format!("\
+                execs().with_status(2).with_stderr(format!("\
 warning: an arbitrary build command has now been deprecated.
          [..]
          [..]
@@ -214,7 +216,7 @@ test!(old_custom_second_build_failure {
             fn main() {}
         "#);
     assert_that(p.cargo_process("build"),
-                execs().with_status(101).with_stderr(format!("\
+                execs().with_status(2).with_stderr(format!("\
 warning: an arbitrary build command has now been deprecated.
          [..]
          [..]
@@ -284,7 +286,7 @@ test!(old_custom_build_env_vars {
                 assert!(p1 == p2, "{{}} != {{}}", p1.display(), p2.display());
             }}
         "#,
-        p.root().join("target").join("native").display()));
+        p.root().join("target").join("debug").join("native").display()));
     assert_that(build.cargo_process("build").arg("--features").arg("foo"),
                 execs().with_status(0));
 
@@ -335,7 +337,7 @@ test!(old_custom_build_in_dependency {
                            .starts_with(r"{}"));
             }}
         "#,
-        p.root().join("target/native/bar-").display()));
+        p.root().join("target/debug/native/bar-").display()));
     assert_that(build.cargo_process("build"), execs().with_status(0));
 
 
@@ -0,0 +1,85 @@
+use std::str;
+
+use support::{project, execs, cargo_dir, ResultTest};
+use hamcrest::assert_that;
+use cargo::util::process;
+use cargo::ops::rustc_version;
+
+fn setup() {
+}
+
+static COMMANDS: [&'static str, ..5] = ["build", "test", "bench", "doc", "run"];
+
+test!(every_command_documents_the_common_flags {
+    let flags = ["--package SPEC", "--jobs N", "--release",
+                 "--features FEATURES", "--no-default-features",
+                 "--all-features", "--target TRIPLE", "--manifest-path PATH",
+                 "--keep-going", "--timings", "--skip-target NAME",
+                 "--verbose", "--color WHEN"];
+
+    for command in COMMANDS.iter() {
+        let output = process(cargo_dir().join("cargo")).arg(*command).arg("-h")
+                                                        .exec_with_output()
+                                                        .assert();
+        let output = str::from_utf8(output.output.as_slice()).assert();
+        for flag in flags.iter() {
+            assert!(output.contains(*flag),
+                    "`cargo {} -h` doesn't document `{}`: {}",
+                    command, flag, output);
+        }
+    }
+})
+
+test!(every_command_accepts_the_common_flags {
+    let p = project("foo")
+        .file("Cargo.toml", r#"
+            [package]
+            name = "foo"
+            version = "0.0.1"
+            authors = []
+
+            [features]
+            extra = []
+        "#)
+        .file("src/main.rs", "fn main() {}");
+    p.build();
+
+    let manifest = p.root().join("Cargo.toml");
+    let (_, host) = rustc_version().assert();
+    let flags = [vec!["-p", "foo"], vec!["-j", "1"], vec!["--release"],
+                 vec!["--target", host.as_slice()],
+                 vec!["--features", "extra"], vec!["--no-default-features"],
+                 vec!["--all-features"],
+                 vec!["--manifest-path", manifest.as_str().unwrap()],
+                 vec!["--keep-going"], vec!["--timings"], vec!["-v"],
+                 vec!["--color", "never"]];
+
+    for command in COMMANDS.iter() {
+        for flag in flags.iter() {
+            assert_that(p.process(cargo_dir().join("cargo")).arg(*command)
+                         .args(flag.as_slice())
+                         .arg("--skip-target").arg("nope"),
+                        execs().with_status(1).with_stderr(format!("\
+no target named `nope` to skip in package `foo v0.0.1 ({dir})`
+", dir = p.url()).as_slice()));
+        }
+    }
+})
+
+test!(bench_and_doc_accept_no_deps {
+    let p = project("foo")
+        .file("Cargo.toml", r#"
+            [package]
+            name = "foo"
+            version = "0.0.1"
+            authors = []
+        "#)
+        .file("src/lib.rs", "");
+    p.build();
+
+    for command in ["bench", "doc"].iter() {
+        assert_that(p.process(cargo_dir().join("cargo")).arg(*command)
+                     .arg("--no-deps"),
+                    execs().with_status(0));
+    }
+})
@@ -1,7 +1,9 @@
 use std::io::{fs, File, USER_RWX};
+use std::io::fs::PathExtensions;
+use std::path;
 
 use support::{ResultTest, project, execs, main_file, cargo_dir};
-use support::{COMPILING, RUNNING};
+use support::{COMPILING, RUNNING, FRESH};
 use support::paths::{mod, PathExt};
 use hamcrest::{assert_that, existing_file};
 use cargo;
@@ -73,7 +75,7 @@ test!(cargo_compile_with_nested_deps_shorthand {
     assert_that(p.cargo_process("build"),
         execs().with_stdout(format!("{} baz v0.5.0 ({})\n\
                                      {} bar v0.5.0 ({})\n\
-                                     {} foo v0.5.0 ({})\n",
+                                     {} foo v0.5.0 ({})\n    Finished [..]\n",
                                     COMPILING, p.url(),
                                     COMPILING, p.url(),
                                     COMPILING, p.url())));
@@ -90,13 +92,15 @@ test!(cargo_compile_with_nested_deps_shorthand {
     println!("building baz");
     assert_that(p.process(cargo_dir().join("cargo")).arg("build")
                  .arg("-p").arg("baz"),
-                execs().with_stdout(format!("{} baz v0.5.0 ({})\n",
+                execs().with_stdout(format!("{} baz v0.5.0 ({})\n    \
+                                             Finished [..]\n",
                                             COMPILING, p.url())));
     println!("building foo");
     assert_that(p.process(cargo_dir().join("cargo")).arg("build")
                  .arg("-p").arg("foo"),
                 execs().with_stdout(format!("{} bar v0.5.0 ({})\n\
-                                             {} foo v0.5.0 ({})\n",
+                                             {} foo v0.5.0 ({})\n    \
+                                             Finished [..]\n",
                                             COMPILING, p.url(),
                                             COMPILING, p.url())));
 })
@@ -136,7 +140,7 @@ test!(cargo_compile_with_root_dev_deps {
 
     p2.build();
     assert_that(p.cargo_process("build"),
-                execs().with_status(101))
+                execs().with_status(2))
 })
 
 test!(cargo_compile_with_root_dev_deps_with_testing {
@@ -177,12 +181,16 @@ test!(cargo_compile_with_root_dev_deps_with_testing {
         execs().with_stdout(format!("\
 {compiling} [..] v0.5.0 ({url})
 {compiling} [..] v0.5.0 ({url})
+    Finished [..]
 {running} target[..]foo-[..]
 
 running 0 tests
 
 test result: ok. 0 passed; 0 failed; 0 ignored; 0 measured
 
+
+     Summary PASS, all 1 test targets passed
+      Passed [..] (0 passed; 0 failed; 0 ignored)
 ", compiling = COMPILING, url = p.url(), running = RUNNING)));
 })
 
@@ -229,7 +237,7 @@ test!(cargo_compile_with_transitive_dev_deps {
 
     assert_that(p.cargo_process("build"),
         execs().with_stdout(format!("{} bar v0.5.0 ({})\n\
-                                     {} foo v0.5.0 ({})\n",
+                                     {} foo v0.5.0 ({})\n    Finished [..]\n",
                                     COMPILING, p.url(),
                                     COMPILING, p.url())));
 
@@ -272,18 +280,20 @@ test!(no_rebuild_dependency {
     // First time around we should compile both foo and bar
     assert_that(p.cargo_process("build"),
                 execs().with_stdout(format!("{} bar v0.5.0 ({})\n\
-                                             {} foo v0.5.0 ({})\n",
+                                             {} foo v0.5.0 ({})\n    \
+                                             Finished [..]\n",
                                             COMPILING, p.url(),
                                             COMPILING, p.url())));
     // This time we shouldn't compile bar
     assert_that(p.process(cargo_dir().join("cargo")).arg("build"),
-                execs().with_stdout(""));
+                execs().with_stdout("    Finished [..]\n"));
     p.root().move_into_the_past().assert();
 
     p.build(); // rebuild the files (rewriting them in the process)
     assert_that(p.process(cargo_dir().join("cargo")).arg("build"),
                 execs().with_stdout(format!("{} bar v0.5.0 ({})\n\
-                                             {} foo v0.5.0 ({})\n",
+                                             {} foo v0.5.0 ({})\n    \
+                                             Finished [..]\n",
                                             COMPILING, p.url(),
                                             COMPILING, p.url())));
 })
@@ -339,12 +349,13 @@ test!(deep_dependencies_trigger_rebuild {
     assert_that(p.cargo_process("build"),
                 execs().with_stdout(format!("{} baz v0.5.0 ({})\n\
                                              {} bar v0.5.0 ({})\n\
-                                             {} foo v0.5.0 ({})\n",
+                                             {} foo v0.5.0 ({})\n    \
+                                             Finished [..]\n",
                                             COMPILING, p.url(),
                                             COMPILING, p.url(),
                                             COMPILING, p.url())));
     assert_that(p.process(cargo_dir().join("cargo")).arg("build"),
-                execs().with_stdout(""));
+                execs().with_stdout("    Finished [..]\n"));
 
     // Make sure an update to baz triggers a rebuild of bar
     //
@@ -357,7 +368,8 @@ test!(deep_dependencies_trigger_rebuild {
     assert_that(p.process(cargo_dir().join("cargo")).arg("build"),
                 execs().with_stdout(format!("{} baz v0.5.0 ({})\n\
                                              {} bar v0.5.0 ({})\n\
-                                             {} foo v0.5.0 ({})\n",
+                                             {} foo v0.5.0 ({})\n    \
+                                             Finished [..]\n",
                                             COMPILING, p.url(),
                                             COMPILING, p.url(),
                                             COMPILING, p.url())));
@@ -370,7 +382,8 @@ test!(deep_dependencies_trigger_rebuild {
     "#).assert();
     assert_that(p.process(cargo_dir().join("cargo")).arg("build"),
                 execs().with_stdout(format!("{} bar v0.5.0 ({})\n\
-                                             {} foo v0.5.0 ({})\n",
+                                             {} foo v0.5.0 ({})\n    \
+                                             Finished [..]\n",
                                             COMPILING, p.url(),
                                             COMPILING, p.url())));
 
@@ -428,13 +441,14 @@ test!(no_rebuild_two_deps {
     assert_that(p.cargo_process("build"),
                 execs().with_stdout(format!("{} baz v0.5.0 ({})\n\
                                              {} bar v0.5.0 ({})\n\
-                                             {} foo v0.5.0 ({})\n",
+                                             {} foo v0.5.0 ({})\n    \
+                                             Finished [..]\n",
                                             COMPILING, p.url(),
                                             COMPILING, p.url(),
                                             COMPILING, p.url())));
     assert_that(&p.bin("foo"), existing_file());
     assert_that(p.process(cargo_dir().join("cargo")).arg("build"),
-                execs().with_stdout(""));
+                execs().with_stdout("    Finished [..]\n"));
     assert_that(&p.bin("foo"), existing_file());
 })
 
@@ -474,7 +488,8 @@ test!(nested_deps_recompile {
 
     assert_that(p.cargo_process("build"),
                 execs().with_stdout(format!("{} bar v0.5.0 ({})\n\
-                                             {} foo v0.5.0 ({})\n",
+                                             {} foo v0.5.0 ({})\n    \
+                                             Finished [..]\n",
                                             COMPILING, bar,
                                             COMPILING, p.url())));
     p.root().move_into_the_past().assert();
@@ -485,7 +500,8 @@ test!(nested_deps_recompile {
 
     // This shouldn't recompile `bar`
     assert_that(p.process(cargo_dir().join("cargo")).arg("build"),
-                execs().with_stdout(format!("{} foo v0.5.0 ({})\n",
+                execs().with_stdout(format!("{} foo v0.5.0 ({})\n    \
+                                             Finished [..]\n",
                                             COMPILING, p.url())));
 })
 
@@ -510,10 +526,50 @@ test!(error_message_for_missing_manifest {
 
     assert_that(p.cargo_process("build"),
                 execs()
-                .with_status(101)
-                .with_stderr(format!("Could not find `Cargo.toml` in `{}`\n",
-                                     p.root().join_many(&["src", "bar"]).display())));
+                .with_status(1)
+                .with_stderr(format!("\
+failed to read the manifests of path dependencies:
+  foo -> bar: src{sep}bar{sep}Cargo.toml: Could not find `Cargo.toml` in `{dir}`
+", sep = path::SEP, dir = p.root().join_many(&["src", "bar"]).display())));
+
+})
+
+test!(error_message_for_lowercase_manifest {
+    let p = project("foo")
+        .file("Cargo.toml", r#"
+            [project]
+
+            name = "foo"
+            version = "0.5.0"
+            authors = []
 
+            [dependencies.bar]
+            path = "bar"
+        "#)
+        .file("src/lib.rs", "")
+        .file("bar/cargo.toml", r#"
+            [project]
+
+            name = "bar"
+            version = "0.5.0"
+            authors = []
+        "#)
+        .file("bar/src/lib.rs", "");
+    p.build();
+
+    // Only case-sensitive filesystems fail to find the manifest.
+    if p.root().join_many(&["bar", "Cargo.toml"]).exists() { return }
+
+    assert_that(p.process(cargo_dir().join("cargo")).arg("build"),
+                execs()
+                .with_status(1)
+                .with_stderr(format!("\
+failed to read the manifests of path dependencies:
+  foo -> bar: bar{sep}Cargo.toml: Could not find `Cargo.toml` in `{dir}`, but \
+found `cargo.toml`
+    rename it to `Cargo.toml`, manifest file names are case sensitive on most \
+platforms
+", sep = path::SEP, dir = p.root().join("bar").display())));
 })
 
 test!(override_relative {
@@ -670,7 +726,7 @@ test!(path_dep_build_cmd {
 
     assert_that(p.process(cargo_dir().join("cargo")).arg("build"),
         execs().with_stdout(format!("{} bar v0.5.0 ({})\n\
-                                     {} foo v0.5.0 ({})\n",
+                                     {} foo v0.5.0 ({})\n    Finished [..]\n",
                                     COMPILING, p.url(),
                                     COMPILING, p.url())));
 
@@ -688,7 +744,7 @@ test!(path_dep_build_cmd {
 
     assert_that(p.process(cargo_dir().join("cargo")).arg("build"),
         execs().with_stdout(format!("{} bar v0.5.0 ({})\n\
-                                     {} foo v0.5.0 ({})\n",
+                                     {} foo v0.5.0 ({})\n    Finished [..]\n",
                                     COMPILING, p.url(),
                                     COMPILING, p.url())));
 
@@ -728,7 +784,8 @@ test!(dev_deps_no_rebuild_lib {
     assert_that(p.process(cargo_dir().join("cargo")).arg("build")
                  .env("FOO", Some("bar")),
                 execs().with_status(0)
-                       .with_stdout(format!("{} foo v0.5.0 ({})\n",
+                       .with_stdout(format!("{} foo v0.5.0 ({})\n    \
+                                             Finished [..]\n",
                                             COMPILING, p.url())));
 
     assert_that(p.process(cargo_dir().join("cargo")).arg("test"),
@@ -736,11 +793,317 @@ test!(dev_deps_no_rebuild_lib {
                        .with_stdout(format!("\
 {} [..] v0.5.0 ({})
 {} [..] v0.5.0 ({})
+    Finished [..]
 Running target[..]foo-[..]
 
 running 0 tests
 
 test result: ok. 0 passed; 0 failed; 0 ignored; 0 measured
 
+
+     Summary PASS, all 1 test targets passed
+      Passed [..] (0 passed; 0 failed; 0 ignored)
 ", COMPILING, p.url(), COMPILING, p.url())));
 })
+
+test!(excluded_nested_manifest_is_ignored {
+    let p = project("foo")
+        .file("Cargo.toml", r#"
+            [package]
+            name = "foo"
+            version = "0.5.0"
+            authors = []
+            exclude = ["vendor/*"]
+        "#)
+        .file("src/main.rs", "fn main() {}")
+        .file("vendor/weird/Cargo.toml", "this is not a valid manifest")
+        .file("vendor/weird/src/lib.rs", "");
+
+    assert_that(p.cargo_process("build"),
+                execs().with_status(0)
+                       .with_stdout(format!("{} foo v0.5.0 ({})\n    \
+                                             Finished [..]\n",
+                                            COMPILING, p.url())));
+    assert_that(&p.bin("foo"), existing_file());
+})
+
+test!(nested_manifest_without_exclude_is_read {
+    let p = project("foo")
+        .file("Cargo.toml", r#"
+            [package]
+            name = "foo"
+            version = "0.5.0"
+            authors = []
+        "#)
+        .file("src/main.rs", "fn main() {}")
+        .file("vendor/weird/Cargo.toml", "this is not a valid manifest")
+        .file("vendor/weird/src/lib.rs", "");
+
+    assert_that(p.cargo_process("build"), execs().with_status(3));
+})
+
+test!(stray_manifest_in_dep_target_dir_is_ignored {
+    let p = project("foo")
+        .file("Cargo.toml", r#"
+            [package]
+            name = "foo"
+            version = "0.5.0"
+            authors = []
+
+            [dependencies.bar]
+            path = "bar"
+        "#)
+        .file("src/main.rs", "extern crate bar; fn main() { bar::bar() }")
+        .file("bar/Cargo.toml", r#"
+            [package]
+            name = "bar"
+            version = "0.5.0"
+            authors = []
+        "#)
+        .file("bar/src/lib.rs", "pub fn bar() {}")
+        // As left behind by verifying `bar` when packaging it.
+        .file("bar/target/package/bar-0.5.0/Cargo.toml",
+              "this is not a valid manifest")
+        .file("bar/target/package/bar-0.5.0/src/lib.rs", "");
+
+    assert_that(p.cargo_process("build"),
+                execs().with_status(0)
+                       .with_stdout(format!("\
+{compiling} bar v0.5.0 ({url})
+{compiling} foo v0.5.0 ({url})
+    Finished [..]
+", compiling = COMPILING, url = p.url())));
+})
+
+test!(marked_build_dir_is_ignored {
+    let p = project("foo")
+        .file("Cargo.toml", r#"
+            [package]
+            name = "foo"
+            version = "0.5.0"
+            authors = []
+        "#)
+        .file("src/main.rs", "fn main() {}")
+        .file("out/.cargo-build-dir", "")
+        .file("out/package/weird/Cargo.toml", "this is not a valid manifest")
+        .file("out/package/weird/src/lib.rs", "");
+
+    assert_that(p.cargo_process("build"),
+                execs().with_status(0)
+                       .with_stdout(format!("{} foo v0.5.0 ({})\n    \
+                                             Finished [..]\n",
+                                            COMPILING, p.url())));
+})
+
+test!(build_marks_target_dir {
+    let p = project("foo")
+        .file("Cargo.toml", r#"
+            [package]
+            name = "foo"
+            version = "0.5.0"
+            authors = []
+        "#)
+        .file("src/main.rs", "fn main() {}");
+
+    assert_that(p.cargo_process("build").arg("--release"),
+                execs().with_status(0));
+    assert_that(&p.root().join_many(&["target", ".cargo-build-dir"]),
+                existing_file());
+    assert_that(&p.root().join_many(&["target", "release", ".cargo-build-dir"]),
+                existing_file());
+})
+
+test!(identical_dep_artifact_does_not_rebuild_dependents {
+    let p = project("foo")
+        .file("Cargo.toml", r#"
+            [package]
+            name = "foo"
+            version = "0.5.0"
+            authors = []
+
+            [dependencies.bar]
+            path = "bar"
+        "#)
+        .file("src/main.rs", "extern crate bar; fn main() { bar::bar() }")
+        .file("bar/Cargo.toml", r#"
+            [package]
+            name = "bar"
+            version = "0.5.0"
+            authors = []
+
+            [dependencies.baz]
+            path = "../baz"
+        "#)
+        .file("bar/src/lib.rs", "extern crate baz; pub fn bar() { baz::baz() }")
+        .file("baz/Cargo.toml", r#"
+            [package]
+            name = "baz"
+            version = "0.5.0"
+            authors = []
+        "#)
+        .file("baz/src/lib.rs", "pub fn baz() {}\n");
+    assert_that(p.cargo_process("build"), execs().with_status(0));
+
+    // Only a trailing comment changes, so the rlib of baz stays the same
+    p.root().move_into_the_past().assert();
+    File::create(&p.root().join("baz/src/lib.rs")).write_str("\
+pub fn baz() {}
+// nothing to see here
+").assert();
+    assert_that(p.process(cargo_dir().join("cargo")).arg("build").arg("-v"),
+                execs().with_status(0).with_stdout(format!("\
+{compiling} baz v0.5.0 ({url})
+{running} `[..]rustc [..]`
+{fresh} bar v0.5.0 ({url})
+{fresh} foo v0.5.0 ({url})
+    Finished [..]
+", compiling = COMPILING, running = RUNNING, fresh = FRESH,
+   url = p.url()).as_slice()));
+
+    // A change to the interface of baz still rebuilds everything
+    p.root().move_into_the_past().assert();
+    File::create(&p.root().join("baz/src/lib.rs")).write_str("\
+pub fn baz() {}
+pub fn baz2() {}
+").assert();
+    assert_that(p.process(cargo_dir().join("cargo")).arg("build"),
+                execs().with_status(0).with_stdout(format!("\
+{compiling} baz v0.5.0 ({url})
+{compiling} bar v0.5.0 ({url})
+{compiling} foo v0.5.0 ({url})
+    Finished [..]
+", compiling = COMPILING, url = p.url()).as_slice()));
+})
+
+test!(home_relative_path_dep_is_rejected {
+    let p = project("foo")
+        .file("Cargo.toml", r#"
+            [package]
+            name = "foo"
+            version = "0.5.0"
+            authors = []
+
+            [dependencies.bar]
+            path = "~/src/bar"
+        "#)
+        .file("src/lib.rs", "");
+    assert_that(p.cargo_process("build"),
+                execs().with_status(3).with_stderr(format!("\
+Cargo.toml is not a valid manifest
+
+the path of dependency `bar` starts with `~`, which is not expanded in manifests
+it would refer to `{}`, use a path relative to the manifest instead
+", p.root().join("~/src/bar").display()).as_slice()));
+    assert!(!p.root().join("~").exists());
+})
+
+test!(broken_nested_manifests_are_all_reported {
+    let p = project("foo")
+        .file("Cargo.toml", r#"
+            [package]
+            name = "foo"
+            version = "0.5.0"
+            authors = []
+
+            [dependencies.a]
+            path = "a"
+            [dependencies.c]
+            path = "c"
+        "#)
+        .file("src/lib.rs", "")
+        .file("a/Cargo.toml", r#"
+            [package]
+            name = "a"
+            version = "0.5.0"
+            authors = []
+
+            [dependencies.b]
+            path = "../b"
+        "#)
+        .file("a/src/lib.rs", "")
+        .file("b/Cargo.toml", r#"
+            [package]
+            name = "b"
+            version = "0.5.0"
+            authors = []
+        "#)
+        .file("c/not-a-manifest", "");
+
+    assert_that(p.cargo_process("build"),
+                execs().with_status(3).with_stderr(format!("\
+failed to read the manifests of path dependencies:
+  foo -> a -> b: b{sep}Cargo.toml: either a [lib] or [[bin]] section must be \
+present
+  foo -> c: c{sep}Cargo.toml: Could not find `Cargo.toml` in `{dir}`
+", sep = path::SEP, dir = p.root().join("c").display()).as_slice()));
+})
+
+test!(duplicate_path_dependency_names_warn {
+    let p = project("foo")
+        .file("Cargo.toml", r#"
+            [package]
+            name = "foo"
+            version = "0.5.0"
+            authors = []
+
+            [dependencies.bar]
+            path = "bar"
+        "#)
+        .file("src/lib.rs", "")
+        .file("bar/Cargo.toml", r#"
+            [package]
+            name = "bar"
+            version = "0.5.0"
+            authors = []
+        "#)
+        .file("bar/src/lib.rs", "")
+        .file("vendor/bar/Cargo.toml", r#"
+            [package]
+            name = "bar"
+            version = "0.4.0"
+            authors = []
+        "#)
+        .file("vendor/bar/src/lib.rs", "");
+
+    assert_that(p.cargo_process("build"),
+                execs().with_status(0).with_stderr(format!("\
+package `bar` is found at both `bar` and `vendor{sep}bar`, only one of them \
+will be used
+", sep = path::SEP).as_slice()));
+})
+
+test!(duplicate_path_dependency_ids_error {
+    let p = project("foo")
+        .file("Cargo.toml", r#"
+            [package]
+            name = "foo"
+            version = "0.5.0"
+            authors = []
+
+            [dependencies.bar]
+            path = "bar"
+        "#)
+        .file("src/lib.rs", "")
+        .file("bar/Cargo.toml", r#"
+            [package]
+            name = "bar"
+            version = "0.5.0"
+            authors = []
+        "#)
+        .file("bar/src/lib.rs", "")
+        .file("vendor/bar/Cargo.toml", r#"
+            [package]
+            name = "bar"
+            version = "0.5.0"
+            authors = []
+        "#)
+        .file("vendor/bar/src/lib.rs", "");
+
+    assert_that(p.cargo_process("build"),
+                execs().with_status(3).with_stderr(format!("\
+package `bar v0.5.0` is declared by more than one manifest:
+  bar{sep}Cargo.toml
+  vendor{sep}bar{sep}Cargo.toml
+bump the version of one of them, or remove the stale copy
+", sep = path::SEP).as_slice()));
+})
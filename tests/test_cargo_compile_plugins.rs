@@ -111,7 +111,7 @@ test!(plugin_with_dynamic_native_dependency {
         "#);
     assert_that(build.cargo_process("build"),
                 execs().with_status(0).with_stderr(""));
-    let src = build.root().join("target");
+    let src = build.root().join("target/debug");
     let lib = fs::readdir(&src).unwrap().into_iter().find(|lib| {
         let lib = lib.filename_str().unwrap();
         lib.starts_with(os::consts::DLL_PREFIX) &&
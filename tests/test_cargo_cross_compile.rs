@@ -2,12 +2,15 @@
 // so we can only run these tests on those platforms
 #![cfg(any(target_os = "linux", target_os = "macos"))]
 
+use std::io::{fs, File, USER_RWX};
 use std::os;
 use std::path;
+use std::str;
 
 use support::{project, execs, basic_bin_manifest};
+use support::paths;
 use support::{RUNNING, COMPILING, DOCTEST, cargo_dir};
-use hamcrest::{assert_that, existing_file};
+use hamcrest::{assert_that, existing_file, is_not};
 use cargo::util::process;
 use cargo::ops::rustc_version;
 
@@ -293,16 +296,16 @@ test!(linker_and_ar {
 
     assert_that(p.cargo_process("build").arg("--target").arg(target)
                                               .arg("-v"),
-                execs().with_status(101)
+                execs().with_status(2)
                        .with_stdout(format!("\
 {compiling} foo v0.5.0 ({url})
-{running} `rustc src/foo.rs --crate-name foo --crate-type bin -g \
-    --out-dir {dir}{sep}target{sep}{target} \
+{running} `[..]rustc src/foo.rs --crate-name foo --crate-type bin -C debuginfo=2 \
+    --out-dir {dir}{sep}target{sep}{target}{sep}debug{sep}deps \
     --dep-info [..] \
     --target {target} \
     -C ar=my-ar-tool -C linker=my-linker-tool \
-    -L {dir}{sep}target{sep}{target} \
-    -L {dir}{sep}target{sep}{target}{sep}deps`
+    -L {dir}{sep}target{sep}{target}{sep}debug \
+    -L {dir}{sep}target{sep}{target}{sep}debug{sep}deps`
 ",
                             running = RUNNING,
                             compiling = COMPILING,
@@ -411,6 +414,7 @@ test!(cross_tests {
                 execs().with_status(0)
                        .with_stdout(format!("\
 {compiling} foo v0.0.0 ({foo})
+    Finished [..]
 {running} target[..]{triple}[..]bar-[..]
 
 running 1 test
@@ -431,6 +435,11 @@ running 0 tests
 
 test result: ok. 0 passed; 0 failed; 0 ignored; 0 measured
 
+
+     Summary PASS, all 3 test targets passed
+      Passed [..] (1 passed; 0 failed; 0 ignored)
+      Passed [..] (1 passed; 0 failed; 0 ignored)
+      Passed doc-tests foo (0 passed; 0 failed; 0 ignored)
 ", compiling = COMPILING, running = RUNNING, foo = p.url(), triple = target,
    doctest = DOCTEST)));
 })
@@ -471,7 +480,7 @@ test!(cross_but_no_dylibs {
         "#)
         .file("src/lib.rs", "");
     assert_that(p.cargo_process("build").arg("--target").arg("arm-apple-ios"),
-                execs().with_status(101)
+                execs().with_status(1)
                        .with_stderr("dylib outputs are not supported for \
                                      arm-apple-ios"));
 })
@@ -499,6 +508,8 @@ test!(cross_with_a_build_script {
                 path.pop();
                 assert_eq!(path.filename().unwrap(), b"build");
                 path.pop();
+                assert_eq!(path.filename().unwrap(), b"debug");
+                path.pop();
                 assert_eq!(path.filename().unwrap(), b"{0}");
                 path.pop();
                 assert_eq!(path.filename().unwrap(), b"target");
@@ -510,9 +521,10 @@ test!(cross_with_a_build_script {
                 execs().with_status(0)
                        .with_stdout(format!("\
 {compiling} foo v0.0.0 (file://[..])
-{running} `rustc build.rs [..] --out-dir {dir}{sep}target{sep}build{sep}foo-[..]`
-{running} `{dir}{sep}target{sep}build{sep}foo-[..]build-script-build`
-{running} `rustc {dir}{sep}src{sep}main.rs [..] --target {target} [..]`
+{running} `[..]rustc build.rs [..] --out-dir {dir}{sep}target{sep}debug{sep}build{sep}foo-[..]`
+{running} `[..]{dir}{sep}target{sep}debug{sep}build{sep}foo-[..]build-script-build`
+{running} `[..]rustc {dir}{sep}src{sep}main.rs [..] --target {target} [..]`
+    Finished [..]
 ", compiling = COMPILING, running = RUNNING, target = target,
    dir = p.root().display(), sep = path::SEP).as_slice()));
 })
@@ -579,22 +591,23 @@ test!(build_script_needed_for_host_and_target {
                 execs().with_status(0)
                        .with_stdout(format!("\
 {compiling} d1 v0.0.0 (file://{dir})
-{running} `rustc build.rs [..] --out-dir {dir}{sep}target{sep}build{sep}d1-[..]`
-{running} `{dir}{sep}target{sep}build{sep}d1-[..]build-script-build`
-{running} `{dir}{sep}target{sep}build{sep}d1-[..]build-script-build`
-{running} `rustc {dir}{sep}d1{sep}src{sep}lib.rs [..] --target {target} [..] \
+{running} `[..]rustc build.rs [..] --out-dir {dir}{sep}target{sep}debug{sep}build{sep}d1-[..]`
+{running} `[..]{dir}{sep}target{sep}debug{sep}build{sep}d1-[..]build-script-build`
+{running} `[..]{dir}{sep}target{sep}debug{sep}build{sep}d1-[..]build-script-build`
+{running} `[..]rustc {dir}{sep}d1{sep}src{sep}lib.rs [..] --target {target} [..] \
            -L /path/to/{target}`
-{running} `rustc {dir}{sep}d1{sep}src{sep}lib.rs [..] \
+{running} `[..]rustc {dir}{sep}d1{sep}src{sep}lib.rs [..] \
            -L /path/to/{host}`
 {compiling} d2 v0.0.0 (file://{dir})
-{running} `rustc {dir}{sep}d2{sep}src{sep}lib.rs [..] \
+{running} `[..]rustc {dir}{sep}d2{sep}src{sep}lib.rs [..] \
            -L /path/to/{host}`
 {compiling} foo v0.0.0 (file://{dir})
-{running} `rustc build.rs [..] --out-dir {dir}{sep}target{sep}build{sep}foo-[..] \
+{running} `[..]rustc build.rs [..] --out-dir {dir}{sep}target{sep}debug{sep}build{sep}foo-[..] \
            -L /path/to/{host}`
-{running} `{dir}{sep}target{sep}build{sep}foo-[..]build-script-build`
-{running} `rustc {dir}{sep}src{sep}main.rs [..] --target {target} [..] \
+{running} `[..]{dir}{sep}target{sep}debug{sep}build{sep}foo-[..]build-script-build`
+{running} `[..]rustc {dir}{sep}src{sep}main.rs [..] --target {target} [..] \
            -L /path/to/{target}`
+    Finished [..]
 ", compiling = COMPILING, running = RUNNING, target = target, host = host,
    dir = p.root().display(), sep = path::SEP).as_slice()));
 })
@@ -639,3 +652,551 @@ test!(build_deps_for_the_right_arch {
     assert_that(p.cargo_process("build").arg("--target").arg(&target).arg("-v"),
                 execs().with_status(0));
 })
+
+test!(build_target_from_config {
+    if disabled() { return }
+
+    let target = alternate();
+    let p = project("foo")
+        .file(".cargo/config", format!(r#"
+            [build]
+            target = "{}"
+        "#, target).as_slice())
+        .file("Cargo.toml", r#"
+            [package]
+            name = "foo"
+            version = "0.0.0"
+            authors = []
+            build = 'build.rs'
+        "#)
+        .file("build.rs", format!(r#"
+            use std::os;
+            fn main() {{
+                assert_eq!(os::getenv("TARGET").unwrap().as_slice(), "{}");
+            }}
+        "#, target).as_slice())
+        .file("src/main.rs", r#"
+            use std::os;
+            fn main() {
+                assert_eq!(os::consts::ARCH, "x86");
+            }
+        "#);
+
+    assert_that(p.cargo_process("build").arg("-v"),
+                execs().with_status(0)
+                       .with_stdout(format!("\
+{compiling} foo v0.0.0 (file://[..])
+{running} `[..]rustc build.rs [..] --out-dir {dir}{sep}target{sep}debug{sep}build{sep}foo-[..]`
+{running} `[..]{dir}{sep}target{sep}debug{sep}build{sep}foo-[..]build-script-build`
+{running} `[..]rustc {dir}{sep}src{sep}main.rs [..] --target {target} [..]`
+    Finished [..]
+", compiling = COMPILING, running = RUNNING, target = target,
+   dir = p.root().display(), sep = path::SEP).as_slice()));
+    assert_that(&p.target_bin(target, "foo"), existing_file());
+
+    assert_that(
+      process(p.target_bin(target, "foo")),
+      execs().with_status(0));
+})
+
+test!(build_target_flag_overrides_config {
+    if disabled() { return }
+
+    let (_, host) = rustc_version().unwrap();
+    let p = project("foo")
+        .file(".cargo/config", format!(r#"
+            [build]
+            target = "{}"
+        "#, alternate()).as_slice())
+        .file("Cargo.toml", basic_bin_manifest("foo").as_slice())
+        .file("src/foo.rs", "fn main() {}");
+
+    assert_that(p.cargo_process("build").arg("--target").arg(&host),
+                execs().with_status(0));
+    assert_that(&p.target_bin(host.as_slice(), "foo"), existing_file());
+    assert_that(&p.target_bin(alternate(), "foo"), is_not(existing_file()));
+})
+
+test!(build_target_from_config_filters_platform_deps {
+    if disabled() { return }
+
+    let target = alternate();
+    let p = project("foo")
+        .file(".cargo/config", format!(r#"
+            [build]
+            target = "{}"
+        "#, target).as_slice())
+        .file("Cargo.toml", format!(r#"
+            [package]
+            name = "foo"
+            version = "0.0.0"
+            authors = []
+
+            [target.{}.dependencies.bar]
+            path = "bar"
+        "#, target).as_slice())
+        .file("src/main.rs", r#"
+            extern crate bar;
+            fn main() { bar::bar(); }
+        "#)
+        .file("bar/Cargo.toml", r#"
+            [package]
+            name = "bar"
+            version = "0.0.0"
+            authors = []
+        "#)
+        .file("bar/src/lib.rs", "pub fn bar() {}");
+
+    assert_that(p.cargo_process("build"),
+                execs().with_status(0));
+    assert_that(&p.target_bin(target, "foo"), existing_file());
+})
+
+test!(linker_only_used_for_target_units {
+    if disabled() { return }
+
+    let target = alternate();
+    let p = project("foo")
+        .file(".cargo/config", format!(r#"
+            [target.{}]
+            linker = "{}"
+        "#, target, paths::root().join("foo/linker.sh").display()).as_slice())
+        .file("Cargo.toml", r#"
+            [package]
+            name = "foo"
+            version = "0.0.0"
+            authors = []
+            build = 'build.rs'
+        "#)
+        .file("build.rs", format!(r#"
+            use std::os;
+            fn main() {{
+                let linker = os::getenv("TARGET_LINKER").unwrap();
+                assert!(linker.as_slice().ends_with("linker.sh"));
+                assert_eq!(os::getenv("TARGET").unwrap().as_slice(), "{}");
+            }}
+        "#, target).as_slice())
+        .file("linker.sh", format!(r#"#!/bin/sh
+            echo "$@" >> {}
+            exec cc "$@"
+        "#, paths::root().join("foo/linker-invocations").display()).as_slice())
+        .file("src/main.rs", "fn main() {}");
+    p.build();
+    fs::chmod(&p.root().join("linker.sh"), USER_RWX).unwrap();
+
+    assert_that(p.process(cargo_dir().join("cargo")).arg("build")
+                 .arg("--target").arg(target),
+                execs().with_status(0));
+    assert_that(&p.target_bin(target, "foo"), existing_file());
+
+    let invocations = File::open(&p.root().join("linker-invocations"))
+                           .read_to_string().unwrap();
+    assert!(invocations.as_slice().contains("foo"));
+    assert!(!invocations.as_slice().contains("build-script-build"));
+})
+
+test!(changing_linker_dirties_fingerprint {
+    if disabled() { return }
+
+    let target = alternate();
+    let p = project("foo")
+        .file("Cargo.toml", basic_bin_manifest("foo").as_slice())
+        .file("src/foo.rs", "fn main() {}");
+
+    assert_that(p.cargo_process("build").arg("--target").arg(target),
+                execs().with_status(0));
+
+    fs::mkdir(&p.root().join(".cargo"), USER_RWX).unwrap();
+    File::create(&p.root().join(".cargo/config")).write_str(format!(r#"
+        [target.{}]
+        linker = "my-linker-tool"
+    "#, target).as_slice()).unwrap();
+
+    assert_that(p.process(cargo_dir().join("cargo")).arg("build")
+                 .arg("--target").arg(target),
+                execs().with_status(2)
+                       .with_stdout(format!("\
+{compiling} foo v0.5.0 ({url})
+", compiling = COMPILING, url = p.url()).as_slice()));
+})
+
+test!(host_target_dep_compiled_once {
+    let (_, host) = rustc_version().unwrap();
+    let p = project("foo")
+        .file("Cargo.toml", r#"
+            [package]
+            name = "foo"
+            version = "0.0.0"
+            authors = []
+            build = "build.rs"
+
+            [dependencies.d1]
+            path = "d1"
+            [build-dependencies.d1]
+            path = "d1"
+        "#)
+        .file("build.rs", "extern crate d1; fn main() { d1::d1(); }")
+        .file("src/lib.rs", "extern crate d1; pub fn foo() { d1::d1() }")
+        .file("d1/Cargo.toml", r#"
+            [package]
+            name = "d1"
+            version = "0.0.0"
+            authors = []
+        "#)
+        .file("d1/src/lib.rs", "pub fn d1() {}");
+
+    // With `--target` naming the host, `d1` is identical for the build script
+    // and for `foo` so it should only be compiled once.
+    let output = p.cargo_process("build").arg("--target").arg(&host).arg("-v")
+                  .exec_with_output().unwrap();
+    let stdout = String::from_utf8(output.output).unwrap();
+    let invocations = stdout.as_slice().lines().filter(|l| {
+        l.contains("--crate-name d1")
+    }).count();
+    assert_eq!(invocations, 1);
+})
+
+test!(custom_target_spec {
+    let (_, host) = rustc_version().unwrap();
+    if host.as_slice() != "x86_64-unknown-linux-gnu" { return }
+
+    // The host's target, named `custom`. There's no standard library for it so
+    // the library being built doesn't use one.
+    let spec = r#"{
+        "data-layout": "e-p:64:64:64-i1:8:8-i8:8:8-i16:16:16-i32:32:32-i64:64:64-f32:32:32-f64:64:64-v64:64:64-v128:128:128-a0:0:64-s0:64:64-f80:128:128-n8:16:32:64-S128",
+        "llvm-target": "x86_64-unknown-linux-gnu",
+        "target-endian": "little",
+        "target-word-size": "64",
+        "arch": "x86_64",
+        "os": "linux"
+    }"#;
+    let p = project("foo")
+        .file("Cargo.toml", r#"
+            [package]
+            name = "foo"
+            version = "0.0.0"
+            authors = []
+            build = "build.rs"
+
+            [target.custom.dependencies.bar]
+            path = "bar"
+        "#)
+        .file("build.rs", r#"
+            fn main() {
+                assert_eq!(std::os::getenv("TARGET").unwrap().as_slice(),
+                           "custom");
+            }
+        "#)
+        .file("src/lib.rs", r#"
+            #![feature(no_std)]
+            #![no_std]
+            extern crate bar;
+            pub fn foo() { bar::bar() }
+        "#)
+        .file("bar/Cargo.toml", r#"
+            [package]
+            name = "bar"
+            version = "0.0.0"
+            authors = []
+        "#)
+        .file("bar/src/lib.rs", r#"
+            #![feature(no_std)]
+            #![no_std]
+            pub fn bar() {}
+        "#)
+        .file("specs/custom.json", spec);
+
+    // A relative path is relative to the working directory, not the package.
+    assert_that(p.cargo_process("build").arg("--target")
+                 .arg("../specs/custom.json")
+                 .cwd(p.root().join("src")),
+                execs().with_status(0));
+    assert_that(&p.root().join_many(&["target", "custom", "libfoo.rlib"]),
+                existing_file());
+
+    assert_that(p.process(cargo_dir().join("cargo")).arg("build")
+                 .arg("--target").arg("specs/custom.json"),
+                execs().with_status(0).with_stdout("    Finished [..]\n"));
+
+    // Editing the specification rebuilds everything compiled for it.
+    File::create(&p.root().join_many(&["specs", "custom.json"]))
+         .write_str(spec.replace("\"os\"", "\"vendor\": \"none\", \"os\"")
+                        .as_slice()).unwrap();
+    assert_that(p.process(cargo_dir().join("cargo")).arg("build")
+                 .arg("--target").arg("specs/custom.json"),
+                execs().with_status(0)
+                       .with_stdout(format!("\
+{compiling} bar v0.0.0 ({url})
+{compiling} foo v0.0.0 ({url})
+    Finished [..]
+", compiling = COMPILING, url = p.url()).as_slice()));
+})
+
+test!(cfg_dependencies {
+    let p = project("foo")
+        .file("Cargo.toml", r#"
+            [package]
+            name = "foo"
+            version = "0.0.0"
+            authors = []
+
+            [features]
+            fast = []
+
+            [target.cfg(unix).dependencies.bar]
+            path = "bar"
+
+            [target.cfg(not(unix)).dependencies.baz]
+            path = "baz"
+
+            [target.cfg(all(unix,feature="fast")).dependencies.qux]
+            path = "qux"
+        "#)
+        .file("src/main.rs", r#"
+            extern crate bar;
+            #[cfg(feature = "fast")] extern crate qux;
+            fn main() { bar::bar(); }
+        "#)
+        .file("bar/Cargo.toml", r#"
+            [package]
+            name = "bar"
+            version = "0.0.0"
+            authors = []
+        "#)
+        .file("bar/src/lib.rs", "pub fn bar() {}")
+        .file("baz/Cargo.toml", r#"
+            [package]
+            name = "baz"
+            version = "0.0.0"
+            authors = []
+        "#)
+        .file("baz/src/lib.rs", "not rust")
+        .file("qux/Cargo.toml", r#"
+            [package]
+            name = "qux"
+            version = "0.0.0"
+            authors = []
+        "#)
+        .file("qux/src/lib.rs", "");
+
+    assert_that(p.cargo_process("build"),
+                execs().with_status(0).with_stdout(format!("\
+{compiling} bar v0.0.0 ({url})
+{compiling} foo v0.0.0 ({url})
+    Finished [..]
+", compiling = COMPILING, url = p.url()).as_slice()));
+
+    // The package's own features are part of the cfg values.
+    assert_that(p.process(cargo_dir().join("cargo")).arg("build")
+                 .arg("--features").arg("fast"),
+                execs().with_status(0).with_stdout(format!("\
+{compiling} qux v0.0.0 ({url})
+{compiling} foo v0.0.0 ({url})
+    Finished [..]
+", compiling = COMPILING, url = p.url()).as_slice()));
+})
+
+test!(cfg_dependencies_of_target {
+    if disabled() { return }
+
+    let target = alternate();
+    let p = project("foo")
+        .file("Cargo.toml", r#"
+            [package]
+            name = "foo"
+            version = "0.0.0"
+            authors = []
+
+            [target.cfg(target_pointer_width="32").dependencies.bar]
+            path = "bar"
+        "#)
+        .file("src/main.rs", r#"
+            extern crate bar;
+            fn main() { bar::bar(); }
+        "#)
+        .file("bar/Cargo.toml", r#"
+            [package]
+            name = "bar"
+            version = "0.0.0"
+            authors = []
+        "#)
+        .file("bar/src/lib.rs", "pub fn bar() {}");
+
+    assert_that(p.cargo_process("build").arg("--target").arg(target),
+                execs().with_status(0));
+    assert_that(&p.target_bin(target, "foo"), existing_file());
+})
+
+test!(invalid_cfg_dependencies {
+    let p = project("foo")
+        .file("Cargo.toml", r#"
+            [package]
+            name = "foo"
+            version = "0.0.0"
+            authors = []
+
+            [target.cfg(not(unix,windows)).dependencies.bar]
+            path = "bar"
+        "#)
+        .file("src/main.rs", "fn main() {}");
+
+    assert_that(p.cargo_process("build"),
+                execs().with_status(3).with_stderr("\
+Cargo.toml is not a valid manifest
+
+`cfg(not(unix,windows))` is not a valid cfg expression
+"));
+})
+
+test!(print_cfg {
+    let p = project("foo")
+        .file("Cargo.toml", r#"
+            [package]
+            name = "foo"
+            version = "0.0.0"
+            authors = []
+
+            [features]
+            default = ["fast"]
+            fast = []
+            slow = []
+        "#)
+        .file("src/main.rs", "fn main() {}");
+
+    let output = p.cargo_process("build").arg("--print").arg("cfg")
+                  .arg("--features").arg("slow")
+                  .exec_with_output().unwrap();
+    let output = str::from_utf8(output.output.as_slice()).unwrap();
+    let lines = output.lines().collect::<Vec<&str>>();
+    assert!(lines.contains(&"unix"), "{}", output);
+    assert!(lines.contains(&format!("target_os=\"{}\"",
+                                    os::consts::SYSNAME).as_slice()),
+            "{}", output);
+    // The features come last, in sorted order.
+    assert_eq!(lines.slice_from(lines.len() - 3),
+               ["feature=\"default\"", "feature=\"fast\"",
+                "feature=\"slow\""].as_slice());
+    assert_that(&p.root().join("target"), is_not(existing_file()));
+
+    // `--target` is honored, and so is the `build.target` configuration.
+    if disabled() { return }
+    let output = p.process(cargo_dir().join("cargo")).arg("build")
+                  .arg("--print").arg("cfg").arg("--target").arg(alternate())
+                  .exec_with_output().unwrap();
+    let output = str::from_utf8(output.output.as_slice()).unwrap();
+    assert!(output.lines().any(|l| l == "target_pointer_width=\"32\""),
+            "{}", output);
+
+    fs::mkdir(&p.root().join(".cargo"), USER_RWX).unwrap();
+    File::create(&p.root().join(".cargo/config")).write_str(format!(r#"
+        [build]
+        target = "{}"
+    "#, alternate()).as_slice()).unwrap();
+    let output = p.process(cargo_dir().join("cargo")).arg("build")
+                  .arg("--print").arg("cfg")
+                  .exec_with_output().unwrap();
+    let output = str::from_utf8(output.output.as_slice()).unwrap();
+    assert!(output.lines().any(|l| l == "target_pointer_width=\"32\""),
+            "{}", output);
+})
+
+test!(cfg_is_not_probed_without_cfg_dependencies {
+    // A `rustc` wrapper earlier in PATH refuses to print its cfg values.
+    let bin = paths::root().join("fake-bin");
+    fs::mkdir_recursive(&bin, USER_RWX).unwrap();
+    let path = os::getenv("PATH").unwrap_or(String::new());
+    File::create(&bin.join("rustc")).write_str(format!(r#"#!/bin/sh
+        if [ "$1" = "--print" ] && [ "$2" = "cfg" ]; then exit 1; fi
+        PATH="{path}" exec rustc "$@"
+    "#, path = path).as_slice()).unwrap();
+    fs::chmod(&bin.join("rustc"), USER_RWX).unwrap();
+
+    let p = project("foo")
+        .file("Cargo.toml", basic_bin_manifest("foo").as_slice())
+        .file("src/foo.rs", "fn main() {}");
+
+    let path = format!("{}:{}", bin.display(), path);
+    assert_that(p.cargo_process("build").env("PATH", Some(path.as_slice())),
+                execs().with_status(0));
+})
+
+test!(cfg_build_dependencies_use_the_host_cfg {
+    if disabled() { return }
+
+    // The host is 64-bit while the alternate target is 32-bit, so `bar` is
+    // only a build dependency if it's evaluated against the host.
+    let p = project("foo")
+        .file("Cargo.toml", r#"
+            [package]
+            name = "foo"
+            version = "0.0.0"
+            authors = []
+            build = "build.rs"
+
+            [target.'cfg(target_pointer_width = "64")'.build-dependencies.bar]
+            path = "bar"
+        "#)
+        .file("build.rs", r#"
+            extern crate bar;
+            fn main() { bar::bar(); }
+        "#)
+        .file("src/lib.rs", "")
+        .file("bar/Cargo.toml", r#"
+            [package]
+            name = "bar"
+            version = "0.0.0"
+            authors = []
+        "#)
+        .file("bar/src/lib.rs", "pub fn bar() {}");
+
+    assert_that(p.cargo_process("build").arg("--target").arg(alternate()),
+                execs().with_status(0));
+})
+
+test!(build_script_reruns_when_target_or_profile_changes {
+    if disabled() { return }
+
+    let p = project("foo")
+        .file("Cargo.toml", r#"
+            [package]
+            name = "foo"
+            version = "0.0.0"
+            authors = []
+            build = "build.rs"
+        "#)
+        .file("build.rs", r#"
+            use std::os;
+            use std::io::{File, Append, Write};
+
+            fn main() {
+                let root = Path::new(os::getenv("CARGO_MANIFEST_DIR").unwrap());
+                let mut f = File::open_mode(&root.join("runs"), Append,
+                                            Write).unwrap();
+                writeln!(f, "{} {}", os::getenv("TARGET").unwrap(),
+                         os::getenv("PROFILE").unwrap()).unwrap();
+            }
+        "#)
+        .file("src/lib.rs", "");
+    let runs = || {
+        File::open(&p.root().join("runs")).read_to_string().unwrap()
+    };
+    let (_, host) = rustc_version().unwrap();
+    let target = alternate();
+
+    // Building the same way twice runs the script once, while each new
+    // target or profile runs it again.
+    assert_that(p.cargo_process("build"), execs().with_status(0));
+    for _ in range(0u, 2) {
+        assert_that(p.process(cargo_dir().join("cargo")).arg("build")
+                     .arg("--target").arg(target),
+                    execs().with_status(0));
+    }
+    for _ in range(0u, 2) {
+        assert_that(p.process(cargo_dir().join("cargo")).arg("build")
+                     .arg("--target").arg(target).arg("--release"),
+                    execs().with_status(0));
+    }
+    assert_eq!(runs(), format!("{host} debug\n{target} debug\n\
+                                {target} release\n",
+                               host = host, target = target));
+})
@@ -1,6 +1,9 @@
-use support::{project, execs, cargo_dir, path2url};
-use support::COMPILING;
+use std::str;
+
+use support::{project, execs, cargo_dir, path2url, ResultTest};
+use support::{COMPILING, RUNNING};
 use hamcrest::{assert_that, existing_file, existing_dir, is_not};
+use cargo::ops::rustc_version;
 
 fn setup() {
 }
@@ -21,6 +24,7 @@ test!(simple {
     assert_that(p.cargo_process("doc"),
                 execs().with_status(0).with_stdout(format!("\
 {compiling} foo v0.0.1 ({dir})
+    Finished [..]
 ",
         compiling = COMPILING,
         dir = path2url(p.root())).as_slice()));
@@ -63,12 +67,13 @@ test!(doc_twice {
     assert_that(p.cargo_process("doc"),
                 execs().with_status(0).with_stdout(format!("\
 {compiling} foo v0.0.1 ({dir})
+    Finished [..]
 ",
         compiling = COMPILING,
         dir = path2url(p.root())).as_slice()));
 
     assert_that(p.process(cargo_dir().join("cargo")).arg("doc"),
-                execs().with_status(0).with_stdout(""))
+                execs().with_status(0).with_stdout("    Finished [..]\n"))
 })
 
 test!(doc_deps {
@@ -100,6 +105,7 @@ test!(doc_deps {
                 execs().with_status(0).with_stdout(format!("\
 {compiling} bar v0.0.1 ({dir})
 {compiling} foo v0.0.1 ({dir})
+    Finished [..]
 ",
         compiling = COMPILING,
         dir = path2url(p.root())).as_slice()));
@@ -110,7 +116,7 @@ test!(doc_deps {
 
     assert_that(p.process(cargo_dir().join("cargo")).arg("doc")
                  .env("RUST_LOG", Some("cargo::ops::cargo_rustc::fingerprint")),
-                execs().with_status(0).with_stdout(""));
+                execs().with_status(0).with_stdout("    Finished [..]\n"));
 
     assert_that(&p.root().join("target/doc"), existing_dir());
     assert_that(&p.root().join("target/doc/foo/index.html"), existing_file());
@@ -146,6 +152,7 @@ test!(doc_no_deps {
                 execs().with_status(0).with_stdout(format!("\
 {compiling} bar v0.0.1 ({dir})
 {compiling} foo v0.0.1 ({dir})
+    Finished [..]
 ",
         compiling = COMPILING,
         dir = path2url(p.root())).as_slice()));
@@ -200,9 +207,83 @@ test!(doc_lib_bin_same_name {
         .file("src/lib.rs", "fn foo() {}");
 
     assert_that(p.cargo_process("doc"),
-                execs().with_status(101)
+                execs().with_status(1)
                        .with_stderr("\
 Cannot document a package where a library and a binary have the same name. \
 Consider renaming one or marking the target as `doc = false`
 "));
 })
+
+test!(doc_example_opt_in {
+    let p = project("foo")
+        .file("Cargo.toml", r#"
+            [package]
+            name = "foo"
+            version = "0.0.1"
+            authors = []
+
+            [[example]]
+            name = "hello"
+            doc = true
+
+            [[example]]
+            name = "goodbye"
+        "#)
+        .file("src/lib.rs", "pub fn foo() {}")
+        .file("examples/hello.rs", "//! Says hello\nfn main() {}")
+        .file("examples/goodbye.rs", "//! Says goodbye\nfn main() {}");
+
+    assert_that(p.cargo_process("doc"),
+                execs().with_status(0));
+    assert_that(&p.root().join("target/doc/foo/index.html"), existing_file());
+    assert_that(&p.root().join("target/doc/hello/index.html"), existing_file());
+    assert_that(&p.root().join("target/doc/goodbye/index.html"),
+                is_not(existing_file()));
+
+    // Documenting an example doesn't compile it
+    assert_that(&p.root().join("target/debug/examples/hello"), is_not(existing_file()));
+})
+
+test!(doc_target {
+    let host = rustc_version().unwrap().val1();
+    let p = project("foo")
+        .file("Cargo.toml", r#"
+            [package]
+            name = "foo"
+            version = "0.0.1"
+            authors = []
+
+            [dependencies.bar]
+            path = "bar"
+        "#)
+        .file("src/lib.rs", r#"
+            extern crate bar;
+            pub fn foo() {}
+        "#)
+        .file("bar/Cargo.toml", r#"
+            [package]
+            name = "bar"
+            version = "0.0.1"
+            authors = []
+        "#)
+        .file("bar/src/lib.rs", r#"
+            pub fn bar() {}
+        "#);
+
+    let output = p.cargo_process("doc").arg("--target").arg(host.as_slice())
+                  .arg("-v").exec_with_output().assert();
+    let output = str::from_utf8(output.output.as_slice()).assert();
+    let target = format!("--target {}", host);
+    let rustdocs = output.lines().filter(|l| {
+        l.starts_with(RUNNING) && l.contains("rustdoc ")
+    }).collect::<Vec<&str>>();
+    assert_eq!(rustdocs.len(), 2);
+    for line in output.lines().filter(|l| l.starts_with(RUNNING)) {
+        assert!(line.contains(target.as_slice()), "{}", output);
+    }
+
+    let doc = p.root().join("target").join(host.as_slice()).join("doc");
+    assert_that(&doc.join("foo/index.html"), existing_file());
+    assert_that(&doc.join("bar/index.html"), existing_file());
+    assert_that(&p.root().join("target/doc"), is_not(existing_dir()));
+})
@@ -0,0 +1,112 @@
+use std::io::MemWriter;
+
+use support::project;
+use hamcrest::{assert_that, existing_file};
+
+use cargo::core::{Shell, MultiShell, ShellConfig};
+use cargo::core::shell::Auto;
+use cargo::ops;
+
+fn setup() {
+}
+
+fn shell() -> MultiShell {
+    let config = || ShellConfig { color_config: Auto, verbose: false, tty: false };
+    let out = Shell::create(box MemWriter::new() as Box<Writer + Send>, config());
+    let err = Shell::create(box MemWriter::new() as Box<Writer + Send>, config());
+    MultiShell::new(out, err, false)
+}
+
+test!(build_returns_artifact_paths {
+    let p = project("foo")
+        .file("Cargo.toml", r#"
+            [package]
+            name = "foo"
+            version = "0.0.1"
+            authors = []
+
+            [dependencies.bar]
+            path = "bar"
+        "#)
+        .file("src/lib.rs", "extern crate bar;")
+        .file("src/main.rs", "extern crate foo; fn main() {}")
+        .file("bar/Cargo.toml", r#"
+            [package]
+            name = "bar"
+            version = "0.0.1"
+            authors = []
+        "#)
+        .file("bar/src/lib.rs", "");
+    p.build();
+
+    let mut shell = shell();
+    let result = ops::facade::build(&p.root().join("Cargo.toml"),
+                                    &mut ops::CompileOptions {
+        env: "compile",
+        shell: &mut shell,
+        jobs: None,
+        target: None,
+        dev_deps: false,
+        features: [],
+        no_default_features: false,
+        all_features: false,
+        spec: None,
+        keep_going: false,
+        timings: false,
+        examples: ops::NoExamples,
+        all_targets: false,
+        skip_targets: &[],
+        extra_examples: &[],
+        only_targets: None,
+        target_rustc_args: None,
+        fs: None,
+    }).unwrap();
+
+    let foo = result.artifacts_of("foo");
+    assert_eq!(foo.len(), 2);
+    assert!(foo.contains(&&p.bin("foo")));
+    assert!(foo.contains(&&p.root().join("target/debug/libfoo.rlib")));
+
+    let bar = result.artifacts_of("bar");
+    assert_eq!(bar.len(), 1);
+    assert!(bar[0].dir_path() == p.root().join("target/debug/deps"),
+            "{}", bar[0].display());
+    let name = bar[0].filename_str().unwrap();
+    assert!(name.starts_with("libbar-") && name.ends_with(".rlib"), "{}", name);
+
+    for &(_, _, ref path) in result.artifacts.iter() {
+        assert_that(path, existing_file());
+    }
+    assert!(result.artifacts_of("baz").is_empty());
+})
+
+test!(resolve_lists_packages {
+    let p = project("foo")
+        .file("Cargo.toml", r#"
+            [package]
+            name = "foo"
+            version = "0.0.1"
+            authors = []
+
+            [dependencies.bar]
+            path = "bar"
+        "#)
+        .file("src/lib.rs", "")
+        .file("bar/Cargo.toml", r#"
+            [package]
+            name = "bar"
+            version = "0.0.2"
+            authors = []
+        "#)
+        .file("bar/src/lib.rs", "");
+    p.build();
+
+    let result = ops::facade::resolve(&p.root().join("Cargo.toml"),
+                                      &mut shell()).unwrap();
+    assert_eq!(result.root.get_name(), "foo");
+    let names = result.packages.iter().map(|id| {
+        format!("{} {}", id.get_name(), id.get_version())
+    }).collect::<Vec<String>>();
+    assert_eq!(names, vec!["bar 0.0.2".to_string(), "foo 0.0.1".to_string()]);
+    assert_that(&p.root().join("Cargo.lock"), existing_file());
+})
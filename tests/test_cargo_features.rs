@@ -1,7 +1,7 @@
 use std::io::File;
 
 use support::{project, execs, cargo_dir};
-use support::{COMPILING, FRESH};
+use support::{COMPILING, FRESH, RUNNING};
 use support::paths::PathExt;
 use hamcrest::assert_that;
 
@@ -22,7 +22,7 @@ test!(invalid1 {
         .file("src/main.rs", "");
 
     assert_that(p.cargo_process("build"),
-                execs().with_status(101).with_stderr(format!("\
+                execs().with_status(3).with_stderr(format!("\
 Cargo.toml is not a valid manifest
 
 Feature `bar` includes `baz` which is neither a dependency nor another feature
@@ -46,7 +46,7 @@ test!(invalid2 {
         .file("src/main.rs", "");
 
     assert_that(p.cargo_process("build"),
-                execs().with_status(101).with_stderr(format!("\
+                execs().with_status(3).with_stderr(format!("\
 Cargo.toml is not a valid manifest
 
 Features and dependencies cannot have the same name: `bar`
@@ -70,7 +70,7 @@ test!(invalid3 {
         .file("src/main.rs", "");
 
     assert_that(p.cargo_process("build"),
-                execs().with_status(101).with_stderr(format!("\
+                execs().with_status(3).with_stderr(format!("\
 Cargo.toml is not a valid manifest
 
 Feature `bar` depends on `baz` which is not an optional dependency.
@@ -100,7 +100,7 @@ test!(invalid4 {
         .file("bar/src/lib.rs", "");
 
     assert_that(p.cargo_process("build"),
-                execs().with_status(101).with_stderr(format!("\
+                execs().with_status(1).with_stderr(format!("\
 Package `bar v0.0.1 ([..])` does not have these features: `bar`
 ").as_slice()));
 
@@ -112,7 +112,7 @@ Package `bar v0.0.1 ([..])` does not have these features: `bar`
         "#);
 
     assert_that(p.cargo_process("build").arg("--features").arg("test"),
-                execs().with_status(101).with_stderr(format!("\
+                execs().with_status(1).with_stderr(format!("\
 Package `foo v0.0.1 ([..])` does not have these features: `test`
 ").as_slice()));
 })
@@ -132,7 +132,7 @@ test!(invalid5 {
         .file("src/main.rs", "");
 
     assert_that(p.cargo_process("build"),
-                execs().with_status(101).with_stderr(format!("\
+                execs().with_status(3).with_stderr(format!("\
 Cargo.toml is not a valid manifest
 
 Dev-dependencies are not allowed to be optional: `bar`
@@ -153,7 +153,7 @@ test!(invalid6 {
         .file("src/main.rs", "");
 
     assert_that(p.cargo_process("build").arg("--features").arg("foo"),
-                execs().with_status(101).with_stderr(format!("\
+                execs().with_status(3).with_stderr(format!("\
 Cargo.toml is not a valid manifest
 
 Feature `foo` requires `bar` which is not an optional dependency
@@ -175,7 +175,7 @@ test!(invalid7 {
         .file("src/main.rs", "");
 
     assert_that(p.cargo_process("build").arg("--features").arg("foo"),
-                execs().with_status(101).with_stderr(format!("\
+                execs().with_status(3).with_stderr(format!("\
 Cargo.toml is not a valid manifest
 
 Feature `foo` requires `bar` which is not an optional dependency
@@ -204,11 +204,67 @@ test!(invalid8 {
         .file("bar/src/lib.rs", "");
 
     assert_that(p.cargo_process("build").arg("--features").arg("foo"),
-                execs().with_status(101).with_stderr(format!("\
+                execs().with_status(1).with_stderr(format!("\
 features in dependencies cannot enable features in other dependencies: `foo/bar`
 ").as_slice()));
 })
 
+test!(invalid_feature_names {
+    let cases = [
+        ("[features]\n1st = []",
+         "feature name `1st` starts with the digit `1`"),
+        ("[features]\na/b = []",
+         "invalid character `/` at position 2 of feature name `a/b`, only \
+          alphanumeric characters, `-` and `_` are allowed"),
+        ("[features]\nfoo+ = []",
+         "invalid character `+` at position 4 of feature name `foo+`, only \
+          alphanumeric characters, `-` and `_` are allowed"),
+        ("[features]\nfoo = [\"bar/a b\"]\n\
+          [dependencies.bar]\npath = \"bar\"\noptional = true",
+         "invalid character ` ` at position 2 of feature name `a b`, only \
+          alphanumeric characters, `-` and `_` are allowed"),
+        ("[features]\nfoo = [\"bar/a/b\"]\n\
+          [dependencies.bar]\npath = \"bar\"\noptional = true",
+         "invalid character `/` at position 2 of feature name `a/b`, only \
+          alphanumeric characters, `-` and `_` are allowed"),
+        ("[features]\ndefault = [\"foo\"]\nfoo = []\nbar = [\"default\"]",
+         "Feature `bar` includes `default`, which only names the features \
+          enabled by default; list them instead"),
+        ("[dependencies.bar]\npath = \"bar\"\nfeatures = [\"\"]",
+         "feature names cannot be empty"),
+        ("[dependencies.bar]\npath = \"bar\"\nfeatures = [\"a\\\"b\"]",
+         "invalid character `\"` at position 2 of feature name `a\"b`, only \
+          alphanumeric characters, `-` and `_` are allowed"),
+    ];
+
+    for &(toml, error) in cases.iter() {
+        let p = project("foo")
+            .file("Cargo.toml", format!(r#"
+                [project]
+                name = "foo"
+                version = "0.0.1"
+                authors = []
+
+                {}
+            "#, toml).as_slice())
+            .file("src/main.rs", "")
+            .file("bar/Cargo.toml", r#"
+                [project]
+                name = "bar"
+                version = "0.0.1"
+                authors = []
+            "#)
+            .file("bar/src/lib.rs", "");
+
+        assert_that(p.cargo_process("build"),
+                    execs().with_status(3).with_stderr(format!("\
+Cargo.toml is not a valid manifest
+
+{}
+", error).as_slice()));
+    }
+})
+
 test!(no_feature_doesnt_build {
     let p = project("foo")
         .file("Cargo.toml", r#"
@@ -240,6 +296,7 @@ test!(no_feature_doesnt_build {
     assert_that(p.cargo_process("build"),
                 execs().with_status(0).with_stdout(format!("\
 {compiling} foo v0.0.1 ({dir})
+    Finished [..]
 ", compiling = COMPILING, dir = p.url()).as_slice()));
     assert_that(p.process(p.bin("foo")), execs().with_status(0).with_stdout(""));
 
@@ -248,11 +305,62 @@ test!(no_feature_doesnt_build {
                 execs().with_status(0).with_stdout(format!("\
 {compiling} bar v0.0.1 ({dir})
 {compiling} foo v0.0.1 ({dir})
+    Finished [..]
 ", compiling = COMPILING, dir = p.url()).as_slice()));
     assert_that(p.process(p.bin("foo")),
                 execs().with_status(0).with_stdout("bar\n"));
 })
 
+test!(unreferenced_optional_dependencies_warn {
+    let manifest = |extra: &str| format!(r#"
+        [project]
+        name = "foo"
+        version = "0.0.1"
+        authors = []
+        {}
+
+        [features]
+        default = ["baz"]
+
+        [dependencies.bar]
+        path = "bar"
+        optional = true
+        [dependencies.baz]
+        path = "baz"
+        optional = true
+    "#, extra);
+    let p = project("foo")
+        .file("Cargo.toml", manifest("").as_slice())
+        .file("src/main.rs", "fn main() {}")
+        .file("bar/Cargo.toml", r#"
+            [package]
+            name = "bar"
+            version = "0.0.1"
+            authors = []
+        "#)
+        .file("bar/src/lib.rs", "pub fn bar() {}")
+        .file("baz/Cargo.toml", r#"
+            [package]
+            name = "baz"
+            version = "0.0.1"
+            authors = []
+        "#)
+        .file("baz/src/lib.rs", "pub fn baz() {}");
+
+    assert_that(p.cargo_process("build"),
+                execs().with_status(0).with_stderr("\
+optional dependencies not referenced by any feature: `bar`
+they can only be enabled with `--features` naming them directly; set \
+`warn-unused-optional = false` if that is intended
+"));
+
+    File::create(&p.root().join("Cargo.toml"))
+         .write_str(manifest("warn-unused-optional = false").as_slice())
+         .unwrap();
+    assert_that(p.process(cargo_dir().join("cargo")).arg("build"),
+                execs().with_status(0).with_stderr(""));
+})
+
 test!(default_feature_pulled_in {
     let p = project("foo")
         .file("Cargo.toml", r#"
@@ -288,6 +396,7 @@ test!(default_feature_pulled_in {
                 execs().with_status(0).with_stdout(format!("\
 {compiling} bar v0.0.1 ({dir})
 {compiling} foo v0.0.1 ({dir})
+    Finished [..]
 ", compiling = COMPILING, dir = p.url()).as_slice()));
     assert_that(p.process(p.bin("foo")),
                 execs().with_status(0).with_stdout("bar\n"));
@@ -296,6 +405,7 @@ test!(default_feature_pulled_in {
                  .arg("--no-default-features"),
                 execs().with_status(0).with_stdout(format!("\
 {compiling} foo v0.0.1 ({dir})
+    Finished [..]
 ", compiling = COMPILING, dir = p.url()).as_slice()));
     assert_that(p.process(p.bin("foo")), execs().with_status(0).with_stdout(""));
 })
@@ -314,7 +424,7 @@ test!(cyclic_feature {
         .file("src/main.rs", "");
 
     assert_that(p.cargo_process("build"),
-                execs().with_status(101).with_stderr("\
+                execs().with_status(1).with_stderr("\
 Cyclic feature dependency: feature `default` depends on itself
 "));
 })
@@ -334,7 +444,7 @@ test!(cyclic_feature2 {
         .file("src/main.rs", "");
 
     assert_that(p.cargo_process("build"),
-                execs().with_status(101).with_stderr("\
+                execs().with_status(1).with_stderr("\
 Cyclic feature dependency: feature `[..]` depends on itself
 "));
 })
@@ -390,6 +500,7 @@ test!(groups_on_groups_on_groups {
 {compiling} ba[..] v0.0.1 ({dir})
 {compiling} ba[..] v0.0.1 ({dir})
 {compiling} foo v0.0.1 ({dir})
+    Finished [..]
 ", compiling = COMPILING, dir = p.url()).as_slice()));
 })
 
@@ -434,6 +545,7 @@ test!(many_cli_features {
 {compiling} ba[..] v0.0.1 ({dir})
 {compiling} ba[..] v0.0.1 ({dir})
 {compiling} foo v0.0.1 ({dir})
+    Finished [..]
 ", compiling = COMPILING, dir = p.url()).as_slice()));
 })
 
@@ -495,6 +607,7 @@ test!(union_features {
 {compiling} d2 v0.0.1 ({dir})
 {compiling} d1 v0.0.1 ({dir})
 {compiling} foo v0.0.1 ({dir})
+    Finished [..]
 ", compiling = COMPILING, dir = p.url()).as_slice()));
 })
 
@@ -528,13 +641,15 @@ test!(many_features_no_rebuilds {
                 execs().with_status(0).with_stdout(format!("\
 {compiling} a v0.1.0 ({dir})
 {compiling} b v0.1.0 ({dir})
+    Finished [..]
 ", compiling = COMPILING, dir = p.url()).as_slice()));
     p.root().move_into_the_past().unwrap();
 
     assert_that(p.process(cargo_dir().join("cargo")).arg("build").arg("-v"),
                 execs().with_status(0).with_stdout(format!("\
-{fresh} a v0.1.0 ([..])
+{fresh} a v0.1.0 ([..]) (features: fall, ftest, ftest2)
 {fresh} b v0.1.0 ([..])
+    Finished [..]
 ", fresh = FRESH).as_slice()));
 })
 
@@ -647,3 +762,151 @@ test!(everything_in_the_lockfile {
     assert!(lockfile.contains(r#"name = "d2""#), "d2 not found\n{}", lockfile);
     assert!(lockfile.contains(r#"name = "d3""#), "d3 not found\n{}", lockfile);
 })
+
+test!(feature_cfgs_are_sorted {
+    let p = project("foo")
+        .file("Cargo.toml", r#"
+            [package]
+            name = "foo"
+            version = "0.0.1"
+            authors = []
+
+            [features]
+            default = ["c", "a", "d", "b"]
+            a = []
+            b = []
+            c = []
+            d = []
+        "#)
+        .file("src/main.rs", "fn main() {}");
+
+    assert_that(p.cargo_process("build").arg("-v"),
+                execs().with_status(0).with_stdout(format!("\
+{compiling} foo v0.0.1 ({dir}) (features: a, b, c, d, default)
+{running} `[..]rustc [..]main.rs [..]--cfg 'feature=\"a\"' --cfg 'feature=\"b\"' \
+--cfg 'feature=\"c\"' --cfg 'feature=\"d\"' --cfg 'feature=\"default\"' [..]`
+    Finished [..]
+", compiling = COMPILING, running = RUNNING, dir = p.url()).as_slice()));
+})
+
+test!(verbose_output_lists_unified_features {
+    let p = project("foo")
+        .file("Cargo.toml", r#"
+            [package]
+            name = "foo"
+            version = "0.0.1"
+            authors = []
+
+            [dependencies.bar]
+            path = "bar"
+            features = ["baz"]
+
+            [dependencies.qux]
+            path = "qux"
+        "#)
+        .file("src/main.rs", "extern crate bar; extern crate qux; fn main() {}")
+        .file("bar/Cargo.toml", r#"
+            [package]
+            name = "bar"
+            version = "0.0.1"
+            authors = []
+
+            [features]
+            default = []
+            baz = []
+            extra = []
+        "#)
+        .file("bar/src/lib.rs", "")
+        .file("qux/Cargo.toml", r#"
+            [package]
+            name = "qux"
+            version = "0.0.1"
+            authors = []
+
+            [dependencies.bar]
+            path = "../bar"
+            features = ["extra"]
+            default-features = false
+        "#)
+        .file("qux/src/lib.rs", "extern crate bar;");
+
+    assert_that(p.cargo_process("build").arg("-v"),
+                execs().with_status(0).with_stdout(format!("\
+{compiling} bar v0.0.1 ({dir}) (features: baz, default, extra)
+{running} `[..]rustc [..]`
+{compiling} qux v0.0.1 ({dir})
+{running} `[..]rustc [..]`
+{compiling} foo v0.0.1 ({dir})
+{running} `[..]rustc [..]`
+    Finished [..]
+", compiling = COMPILING, running = RUNNING, dir = p.url()).as_slice()));
+
+    // Features aren't listed without `-v`
+    assert_that(p.process(cargo_dir().join("cargo")).arg("clean"),
+                execs().with_status(0));
+    assert_that(p.process(cargo_dir().join("cargo")).arg("build"),
+                execs().with_status(0).with_stdout(format!("\
+{compiling} bar v0.0.1 ({dir})
+{compiling} qux v0.0.1 ({dir})
+{compiling} foo v0.0.1 ({dir})
+    Finished [..]
+", compiling = COMPILING, dir = p.url()).as_slice()));
+})
+
+test!(all_features_activates_everything {
+    let p = project("foo")
+        .file("Cargo.toml", r#"
+            [package]
+            name = "foo"
+            version = "0.0.1"
+            authors = []
+
+            [features]
+            a = []
+            b = []
+
+            [dependencies.bar]
+            path = "bar"
+            optional = true
+        "#)
+        .file("src/main.rs", r#"
+            #[cfg(feature = "a")] mod a { pub fn a() {} }
+            #[cfg(feature = "b")] mod b { pub fn b() {} }
+            #[cfg(feature = "bar")] extern crate bar;
+
+            fn main() {
+                a::a();
+                b::b();
+                bar::bar();
+            }
+        "#)
+        .file("bar/Cargo.toml", r#"
+            [package]
+            name = "bar"
+            version = "0.0.1"
+            authors = []
+        "#)
+        .file("bar/src/lib.rs", "pub fn bar() {}");
+
+    assert_that(p.cargo_process("build").arg("--all-features").arg("-v"),
+                execs().with_status(0).with_stdout(format!("\
+{compiling} bar v0.0.1 ({dir})
+{running} `[..]rustc [..]`
+{compiling} foo v0.0.1 ({dir}) (features: a, b, bar)
+{running} `[..]rustc [..]`
+    Finished [..]
+", compiling = COMPILING, running = RUNNING, dir = p.url()).as_slice()));
+
+    assert_that(p.process(cargo_dir().join("cargo")).arg("build")
+                 .arg("--all-features").arg("--features").arg("a"),
+                execs().with_status(1).with_stderr("\
+`--all-features` cannot be combined with `--features` or \
+`--no-default-features`
+"));
+    assert_that(p.process(cargo_dir().join("cargo")).arg("build")
+                 .arg("--all-features").arg("--no-default-features"),
+                execs().with_status(1).with_stderr("\
+`--all-features` cannot be combined with `--features` or \
+`--no-default-features`
+"));
+})
@@ -1,10 +1,17 @@
-use std::io::{fs, File};
+use std::io::{fs, File, FileStat, IoResult, MemWriter};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicUint, SeqCst};
 
 use support::{project, execs, path2url};
-use support::{COMPILING, cargo_dir, ResultTest};
+use support::{COMPILING, cargo_dir, ResultTest, ProjectBuilder};
 use support::paths::PathExt;
 use hamcrest::{assert_that, existing_file};
 
+use cargo::core::{Shell, MultiShell, ShellConfig, Source, SourceId, Package};
+use cargo::core::shell::Auto;
+use cargo::ops::{mod, FreshnessFs, RealFs};
+use cargo::util::CargoResult;
+
 fn setup() {}
 
 test!(modifying_and_moving {
@@ -23,21 +30,23 @@ test!(modifying_and_moving {
     assert_that(p.cargo_process("build"),
                 execs().with_status(0).with_stdout(format!("\
 {compiling} foo v0.0.1 ({dir})
+    Finished [..]
 ", compiling = COMPILING, dir = path2url(p.root()))));
 
     assert_that(p.process(cargo_dir().join("cargo")).arg("build"),
-                execs().with_status(0).with_stdout(""));
+                execs().with_status(0).with_stdout("    Finished [..]\n"));
     p.root().move_into_the_past().assert();
 
     File::create(&p.root().join("src/a.rs")).write_str("fn main() {}").assert();
     assert_that(p.process(cargo_dir().join("cargo")).arg("build"),
                 execs().with_status(0).with_stdout(format!("\
 {compiling} foo v0.0.1 ({dir})
+    Finished [..]
 ", compiling = COMPILING, dir = path2url(p.root()))));
 
     fs::rename(&p.root().join("src/a.rs"), &p.root().join("src/b.rs")).assert();
     assert_that(p.process(cargo_dir().join("cargo")).arg("build"),
-                execs().with_status(101));
+                execs().with_status(2));
 })
 
 test!(modify_only_some_files {
@@ -60,6 +69,7 @@ test!(modify_only_some_files {
     assert_that(p.cargo_process("build"),
                 execs().with_status(0).with_stdout(format!("\
 {compiling} foo v0.0.1 ({dir})
+    Finished [..]
 ", compiling = COMPILING, dir = path2url(p.root()))));
     assert_that(p.process(cargo_dir().join("cargo")).arg("test"),
                 execs().with_status(0));
@@ -80,6 +90,189 @@ test!(modify_only_some_files {
                  .env("RUST_LOG", Some("cargo::ops::cargo_rustc::fingerprint")),
                 execs().with_status(0).with_stdout(format!("\
 {compiling} foo v0.0.1 ({dir})
+    Finished [..]
 ", compiling = COMPILING, dir = path2url(p.root()))));
     assert_that(&p.bin("foo"), existing_file());
 })
+
+test!(metadata_changes_do_not_rebuild {
+    let manifest = |description: &str, req: &str| {
+        format!(r#"
+            [package]
+            name = "foo"
+            authors = []
+            version = "0.0.1"
+            description = "{}"
+
+            [dependencies.bar]
+            path = "bar"
+            version = "{}"
+        "#, description, req)
+    };
+    let p = project("foo")
+        .file("Cargo.toml", manifest("first", "*").as_slice())
+        .file("src/main.rs", "extern crate bar; fn main() {}")
+        .file("bar/Cargo.toml", r#"
+            [package]
+            name = "bar"
+            authors = []
+            version = "0.0.1"
+        "#)
+        .file("bar/src/lib.rs", "");
+
+    assert_that(p.cargo_process("build"),
+                execs().with_status(0).with_stdout(format!("\
+{compiling} bar v0.0.1 ({dir})
+{compiling} foo v0.0.1 ({dir})
+    Finished [..]
+", compiling = COMPILING, dir = path2url(p.root()))));
+    p.root().move_into_the_past().assert();
+
+    File::create(&p.root().join("Cargo.toml"))
+         .write_str(manifest("second", "*").as_slice()).assert();
+    assert_that(p.process(cargo_dir().join("cargo")).arg("build"),
+                execs().with_status(0).with_stdout("    Finished [..]\n"));
+
+    File::create(&p.root().join("Cargo.toml"))
+         .write_str(manifest("second", "0.0.1").as_slice()).assert();
+    assert_that(p.process(cargo_dir().join("cargo")).arg("build"),
+                execs().with_status(0).with_stdout(format!("\
+{compiling} foo v0.0.1 ({dir})
+    Finished [..]
+", compiling = COMPILING, dir = path2url(p.root()))));
+})
+
+// Counts the filesystem operations deciding whether a build is fresh.
+struct CountingFs {
+    read: Arc<AtomicUint>,
+    listed: Arc<AtomicUint>,
+    stats: Arc<AtomicUint>,
+}
+
+impl FreshnessFs for CountingFs {
+    fn read_packages(&self, path: &Path, source_id: &SourceId)
+                     -> CargoResult<Vec<Package>> {
+        self.read.fetch_add(1, SeqCst);
+        RealFs.read_packages(path, source_id)
+    }
+
+    fn list_files(&self, source: &Source, pkg: &Package)
+                  -> CargoResult<Vec<Path>> {
+        self.listed.fetch_add(1, SeqCst);
+        RealFs.list_files(source, pkg)
+    }
+
+    fn stat(&self, path: &Path) -> IoResult<FileStat> {
+        self.stats.fetch_add(1, SeqCst);
+        RealFs.stat(path)
+    }
+}
+
+// Builds `p` through the library, returning how many times the packages of
+// its directory were read, how many times a package was walked to check its
+// build script and how many files were stat'd for that.
+fn build_counting(p: &ProjectBuilder) -> (uint, uint, uint) {
+    let read = Arc::new(AtomicUint::new(0));
+    let listed = Arc::new(AtomicUint::new(0));
+    let stats = Arc::new(AtomicUint::new(0));
+    let fs = box CountingFs {
+        read: read.clone(),
+        listed: listed.clone(),
+        stats: stats.clone(),
+    } as Box<FreshnessFs>;
+
+    let config = || ShellConfig { color_config: Auto, verbose: false, tty: false };
+    let out = Shell::create(box MemWriter::new() as Box<Writer + Send>, config());
+    let err = Shell::create(box MemWriter::new() as Box<Writer + Send>, config());
+    let mut shell = MultiShell::new(out, err, false);
+    ops::compile(&p.root().join("Cargo.toml"), &mut ops::CompileOptions {
+        env: "compile",
+        shell: &mut shell,
+        jobs: None,
+        target: None,
+        dev_deps: false,
+        features: [],
+        no_default_features: false,
+        all_features: false,
+        spec: None,
+        keep_going: false,
+        timings: false,
+        examples: ops::NoExamples,
+        all_targets: false,
+        skip_targets: &[],
+        extra_examples: &[],
+        only_targets: None,
+        target_rustc_args: None,
+        fs: Some(Arc::new(fs)),
+    }).assert();
+    (read.load(SeqCst), listed.load(SeqCst), stats.load(SeqCst))
+}
+
+test!(fresh_build_script_does_not_walk_package {
+    let p = project("foo")
+        .file("Cargo.toml", r#"
+            [package]
+            name = "foo"
+            authors = []
+            version = "0.0.1"
+            build = "build.rs"
+        "#)
+        .file("build.rs", "fn main() {}")
+        .file("src/lib.rs", "");
+    p.build();
+
+    let (read, listed, _) = build_counting(&p);
+    assert_eq!((read, listed), (1, 1));
+
+    // A warm build reads the packages of the directory once, and stats the
+    // listed inputs (build.rs, src/lib.rs and the two directories they are
+    // in) instead of walking the package again.
+    assert_eq!(build_counting(&p), (1, 0, 4));
+
+    // Adding a file is still noticed, through the mtime of its directory.
+    File::create(&p.root().join("src/new.rs")).assert();
+    let (read, listed, _) = build_counting(&p);
+    assert_eq!((read, listed), (1, 1));
+    assert_eq!(build_counting(&p), (1, 0, 5));
+})
+
+test!(file_changed_while_build_script_runs_reruns_it {
+    let p = project("foo")
+        .file("Cargo.toml", r#"
+            [package]
+            name = "foo"
+            authors = []
+            version = "0.0.1"
+            build = "build.rs"
+        "#)
+        .file("build.rs", r#"
+            use std::io::File;
+            use std::io::fs::PathExtensions;
+            use std::os;
+
+            fn main() {
+                let dir = Path::new(os::getenv("CARGO_MANIFEST_DIR").unwrap());
+                let generated = dir.join("src/generated.txt");
+                if !generated.exists() {
+                    File::create(&generated).unwrap();
+                }
+            }
+        "#)
+        .file("src/lib.rs", "");
+
+    assert_that(p.cargo_process("build"),
+                execs().with_status(0).with_stdout(format!("\
+{compiling} foo v0.0.1 ({dir})
+    Finished [..]
+", compiling = COMPILING, dir = path2url(p.root()))));
+
+    // The script added a file after its inputs were listed, so it runs again.
+    assert_that(p.process(cargo_dir().join("cargo")).arg("build"),
+                execs().with_status(0).with_stdout(format!("\
+{compiling} foo v0.0.1 ({dir})
+    Finished [..]
+", compiling = COMPILING, dir = path2url(p.root()))));
+
+    assert_that(p.process(cargo_dir().join("cargo")).arg("build"),
+                execs().with_status(0).with_stdout("    Finished [..]\n"));
+})
@@ -1,6 +1,7 @@
 use std::io::File;
 
 use support::{project, execs, cargo_dir, ResultTest};
+use support::{ADDING, REMOVING, UPDATING};
 use hamcrest::assert_that;
 
 fn setup() {}
@@ -138,3 +139,117 @@ foo = "bar"
     let lock = File::open(&lockfile).read_to_string().assert();
     assert!(lock.as_slice().contains(metadata.trim()), "{}", lock);
 })
+
+test!(reports_lockfile_changes {
+    let p = project("foo")
+        .file("Cargo.toml", r#"
+            [package]
+            name = "foo"
+            authors = []
+            version = "0.0.1"
+        "#)
+        .file("src/main.rs", "fn main() {}")
+        .file("bar/Cargo.toml", r#"
+            [package]
+            name = "bar"
+            authors = []
+            version = "0.0.1"
+        "#)
+        .file("bar/src/lib.rs", "");
+
+    // Nothing is reported when there was no previous lock file
+    assert_that(p.cargo_process("generate-lockfile"),
+                execs().with_status(0).with_stdout(""));
+
+    let toml = p.root().join("Cargo.toml");
+    File::create(&toml).write_str(r#"
+        [package]
+        name = "foo"
+        authors = []
+        version = "0.0.1"
+
+        [dependencies.bar]
+        path = "bar"
+    "#).assert();
+    assert_that(p.process(cargo_dir().join("cargo")).arg("generate-lockfile"),
+                execs().with_status(0).with_stdout(format!("\
+{adding} bar v0.0.1 ([..])
+", adding = ADDING).as_slice()));
+
+    File::create(&p.root().join("bar/Cargo.toml")).write_str(r#"
+        [package]
+        name = "bar"
+        authors = []
+        version = "0.0.2"
+    "#).assert();
+    assert_that(p.process(cargo_dir().join("cargo")).arg("generate-lockfile"),
+                execs().with_status(0).with_stdout(format!("\
+{updating} bar v0.0.1 -> v0.0.2
+", updating = UPDATING).as_slice()));
+
+    File::create(&toml).write_str(r#"
+        [package]
+        name = "foo"
+        authors = []
+        version = "0.0.1"
+    "#).assert();
+    assert_that(p.process(cargo_dir().join("cargo")).arg("generate-lockfile"),
+                execs().with_status(0).with_stdout(format!("\
+{removing} bar v0.0.2 ([..])
+", removing = REMOVING).as_slice()));
+})
+
+test!(unversioned_lockfile_is_upgraded {
+    let p = project("foo")
+        .file("Cargo.toml", r#"
+            [package]
+            name = "foo"
+            authors = []
+            version = "0.0.1"
+        "#)
+        .file("src/main.rs", "fn main() {}");
+
+    assert_that(p.cargo_process("build"),
+                execs().with_status(0));
+
+    let lockfile = p.root().join("Cargo.lock");
+    let lock = File::open(&lockfile).read_to_string().assert();
+    assert!(lock.as_slice().starts_with("version = 1\n\n[root]\n"), "{}", lock);
+
+    // Lockfiles written before the format was versioned have no header.
+    let old = lock.as_slice().slice_from("version = 1\n\n".len());
+    File::create(&lockfile).write_str(old).assert();
+    assert_that(p.process(cargo_dir().join("cargo")).arg("build"),
+                execs().with_status(0));
+    let upgraded = File::open(&lockfile).read_to_string().assert();
+    assert_eq!(upgraded, lock);
+
+    assert_that(p.process(cargo_dir().join("cargo")).arg("build"),
+                execs().with_status(0));
+})
+
+test!(newer_lockfile_version_is_rejected {
+    let p = project("foo")
+        .file("Cargo.toml", r#"
+            [package]
+            name = "foo"
+            authors = []
+            version = "0.0.1"
+        "#)
+        .file("src/main.rs", "fn main() {}")
+        .file("Cargo.lock", r#"
+            version = 2
+
+            [root]
+            name = "foo"
+            version = "0.0.1"
+            some-new-key = true
+        "#);
+
+    assert_that(p.cargo_process("build"),
+                execs().with_status(3).with_stderr("\
+lockfile at [..]Cargo.lock was created by a newer cargo (format version 2, \
+but only versions up to 1 are supported)
+upgrade cargo or delete the lockfile to regenerate it
+"));
+})
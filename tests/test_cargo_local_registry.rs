@@ -0,0 +1,241 @@
+use std::io::{mod, fs, File};
+
+use support::{project, execs, cargo_dir, ResultTest};
+use support::{COMPILING, UPDATING, REMOVING, USING};
+use support::paths;
+use support::registry as r;
+
+use hamcrest::assert_that;
+
+fn setup() {
+    let config = paths::root().join(".cargo/config");
+    fs::mkdir_recursive(&config.dir_path(), io::USER_DIR).assert();
+    File::create(&config).write_str(r#"
+        [registry]
+            local = "local"
+    "#).assert();
+    fs::mkdir_recursive(&local_path().join("index"), io::USER_DIR).assert();
+}
+
+fn local_path() -> Path { paths::root().join("local") }
+
+// Packages a crate into the local registry and lists it in its index, with
+// `cksum` overriding the checksum of the tarball if given.
+fn local_pkg(name: &str, vers: &str, deps: &[(&str, &str)],
+             cksum: Option<&str>) {
+    r::mock_archive(name, vers, deps);
+    let dst = local_path().join(format!("{}-{}.crate", name, vers));
+    fs::copy(&r::mock_archive_dst(name, vers), &dst).assert();
+    let contents = File::open(&dst).read_to_end().assert();
+    let actual = r::cksum(contents.as_slice());
+    let line = r::pkg(name, vers, deps, cksum.unwrap_or(actual.as_slice()),
+                      false);
+
+    let index = local_path().join("index").join(r::index_file(name));
+    fs::mkdir_recursive(&index.dir_path(), io::USER_DIR).assert();
+    let prev = File::open(&index).read_to_string().unwrap_or(String::new());
+    File::create(&index).write_str((prev + line + "\n").as_slice()).assert();
+}
+
+test!(simple {
+    let p = project("foo")
+        .file("Cargo.toml", r#"
+            [project]
+            name = "foo"
+            version = "0.0.1"
+            authors = []
+
+            [dependencies]
+            bar = "*"
+        "#)
+        .file("src/main.rs", "extern crate bar; fn main() {}");
+    p.build();
+
+    local_pkg("baz", "0.0.1", [], None);
+    local_pkg("bar", "0.0.1", [("baz", "*")], None);
+
+    assert_that(p.process(cargo_dir().join("cargo")).arg("build"),
+                execs().with_status(0).with_stdout(format!("\
+{using} local registry `{reg}` for registry `https://example.com/`
+{compiling} baz v0.0.1 (the package registry)
+{compiling} bar v0.0.1 (the package registry)
+{compiling} foo v0.0.1 ({dir})
+    Finished [..]
+", using = USING, compiling = COMPILING, reg = local_path().display(),
+   dir = p.url()).as_slice()));
+
+    // The lockfile names the registry which was replaced, not the directory
+    // which replaced it.
+    let lock = File::open(&p.root().join("Cargo.lock")).read_to_string()
+                    .assert();
+    assert!(lock.as_slice().contains("registry+https://example.com/"),
+            "{}", lock);
+    assert!(!lock.as_slice().contains(local_path().display().to_string()
+                                                  .as_slice()), "{}", lock);
+
+    // Building again from the lockfile reads the same tarballs.
+    assert_that(p.process(cargo_dir().join("cargo")).arg("clean"),
+                execs().with_status(0));
+    assert_that(p.process(cargo_dir().join("cargo")).arg("build"),
+                execs().with_status(0).with_stdout(format!("\
+{compiling} baz v0.0.1 (the package registry)
+{compiling} bar v0.0.1 (the package registry)
+{compiling} foo v0.0.1 ({dir})
+    Finished [..]
+", compiling = COMPILING, dir = p.url()).as_slice()));
+})
+
+test!(bad_cksum {
+    let p = project("foo")
+        .file("Cargo.toml", r#"
+            [project]
+            name = "foo"
+            version = "0.0.1"
+            authors = []
+
+            [dependencies]
+            bar = "*"
+        "#)
+        .file("src/main.rs", "fn main() {}");
+    p.build();
+
+    local_pkg("bar", "0.0.1", [], Some("0000"));
+
+    assert_that(p.process(cargo_dir().join("cargo")).arg("build"),
+                execs().with_status(1).with_stderr(format!("\
+Unable to get packages from source
+
+Caused by:
+  Failed to verify the checksum of `bar v0.0.1 (the package registry)`
+"));
+})
+
+test!(missing_index {
+    let p = project("foo")
+        .file("Cargo.toml", r#"
+            [project]
+            name = "foo"
+            version = "0.0.1"
+            authors = []
+
+            [dependencies]
+            bar = "*"
+        "#)
+        .file("src/main.rs", "fn main() {}");
+    p.build();
+    fs::rmdir_recursive(&local_path()).assert();
+
+    assert_that(p.process(cargo_dir().join("cargo")).arg("build"),
+                execs().with_status(1).with_stderr(format!("\
+local registry `{reg}` has no index directory at `{reg}/index`
+", reg = local_path().display()).as_slice()));
+})
+
+test!(update_does_not_touch_network {
+    let p = project("foo")
+        .file("Cargo.toml", r#"
+            [project]
+            name = "foo"
+            version = "0.0.1"
+            authors = []
+
+            [dependencies]
+            bar = "*"
+        "#)
+        .file("src/main.rs", "fn main() {}");
+    p.build();
+
+    local_pkg("bar", "0.0.1", [], None);
+
+    assert_that(p.process(cargo_dir().join("cargo")).arg("fetch"),
+                execs().with_status(0).with_stdout(format!("\
+{using} local registry `{reg}` for registry `https://example.com/`
+", using = USING, reg = local_path().display()).as_slice()));
+})
+
+test!(update_dry_run {
+    let p = project("foo")
+        .file("Cargo.toml", r#"
+            [project]
+            name = "foo"
+            version = "0.0.1"
+            authors = []
+
+            [dependencies]
+            bar = "*"
+            baz = "*"
+        "#)
+        .file("src/main.rs", "fn main() {}");
+    p.build();
+
+    local_pkg("dep", "0.0.1", [], None);
+    local_pkg("bar", "0.0.1", [("dep", "*")], None);
+    local_pkg("baz", "0.0.1", [], None);
+    assert_that(p.process(cargo_dir().join("cargo")).arg("generate-lockfile"),
+                execs().with_status(0));
+    let lock = File::open(&p.root().join("Cargo.lock")).read_to_string()
+                    .assert();
+
+    local_pkg("dep", "0.0.2", [], None);
+    local_pkg("bar", "0.0.2", [], None);
+    local_pkg("baz", "0.0.2", [], None);
+
+    // Conservatively updating `bar` leaves its dependencies alone...
+    assert_that(p.process(cargo_dir().join("cargo")).arg("update")
+                 .arg("-p").arg("bar").arg("--dry-run"),
+                execs().with_status(0).with_stdout(format!("\
+{using} local registry `{reg}` for registry `https://example.com/`
+{updating} bar v0.0.1 -> v0.0.2
+{removing} dep v0.0.1 (the package registry)
+", using = USING, updating = UPDATING, removing = REMOVING,
+   reg = local_path().display()).as_slice())
+                       .with_stderr("\
+not updating Cargo.lock, this was a dry run
+"));
+
+    // ... while doing so aggressively unpins them as well.
+    local_pkg("bar", "0.0.3", [("dep", "*")], None);
+    assert_that(p.process(cargo_dir().join("cargo")).arg("update")
+                 .arg("-p").arg("bar").arg("--aggressive").arg("--dry-run"),
+                execs().with_status(0).with_stdout(format!("\
+{using} local registry `{reg}` for registry `https://example.com/`
+{updating} bar v0.0.1 -> v0.0.3
+{updating} dep v0.0.1 -> v0.0.2
+", using = USING, updating = UPDATING,
+   reg = local_path().display()).as_slice()));
+
+    let after = File::open(&p.root().join("Cargo.lock")).read_to_string()
+                     .assert();
+    assert_eq!(lock, after);
+
+    assert_that(p.process(cargo_dir().join("cargo")).arg("update")
+                 .arg("--dry-run"),
+                execs().with_status(0).with_stdout(format!("\
+{using} local registry `{reg}` for registry `https://example.com/`
+{updating} bar v0.0.1 -> v0.0.3
+{updating} baz v0.0.1 -> v0.0.2
+{updating} dep v0.0.1 -> v0.0.2
+", using = USING, updating = UPDATING,
+   reg = local_path().display()).as_slice()));
+})
+
+test!(login_leaves_local_registry_out_of_global_config {
+    let p = project("foo")
+        .file("Cargo.toml", r#"
+            [project]
+            name = "foo"
+            version = "0.0.1"
+            authors = []
+        "#)
+        .file("src/main.rs", "fn main() {}");
+    p.build();
+
+    assert_that(p.process(cargo_dir().join("cargo")).arg("login")
+                 .arg("api-token"),
+                execs().with_status(0));
+
+    let config = File::open(&paths::home().join(".cargo/config"))
+                      .read_to_string().assert();
+    assert!(config.as_slice().contains("api-token"), "{}", config);
+    assert!(!config.as_slice().contains("local"), "{}", config);
+})
@@ -47,7 +47,7 @@ test!(simple_bin {
 
     assert_that(cargo_process("build").cwd(paths::root().join("foo")),
                 execs().with_status(0));
-    assert_that(&paths::root().join(format!("foo/target/foo{}",
+    assert_that(&paths::root().join(format!("foo/target/debug/foo{}",
                                             os::consts::EXE_SUFFIX)),
                 existing_file());
 })
@@ -96,15 +96,91 @@ test!(existing {
     let dst = paths::root().join("foo");
     fs::mkdir(&dst, USER_RWX).assert();
     assert_that(cargo_process("new").arg("foo"),
-                execs().with_status(101)
+                execs().with_status(1)
+                       .with_stderr(format!("Destination `{}` already exists
+
+pass `--yes` to create the package in it anyway, keeping any files which are already there
+", dst.display())));
+    assert_that(&dst.join("Cargo.toml"), is_not(existing_file()));
+})
+
+test!(existing_with_yes {
+    os::setenv("USER", "foo");
+    let dst = paths::root().join("foo");
+    fs::mkdir(&dst, USER_RWX).assert();
+    File::create(&dst.join("README")).write_str("keep me").assert();
+    fs::mkdir(&dst.join("src"), USER_RWX).assert();
+    File::create(&dst.join("src/lib.rs")).write_str("// keep me").assert();
+    assert_that(cargo_process("new").arg("foo").arg("--no-git").arg("--yes"),
+                execs().with_status(0));
+
+    assert_that(&dst.join("Cargo.toml"), existing_file());
+    assert_eq!(File::open(&dst.join("README")).read_to_string().assert(),
+               "keep me".to_string());
+    assert_eq!(File::open(&dst.join("src/lib.rs")).read_to_string().assert(),
+               "// keep me".to_string());
+
+    let dst = paths::root().join("bar");
+    fs::mkdir(&dst, USER_RWX).assert();
+    assert_that(cargo_process("new").arg("bar").arg("--no-git")
+                                    .arg("--no-confirm"),
+                execs().with_status(0));
+    assert_that(&dst.join("Cargo.toml"), existing_file());
+})
+
+test!(existing_package_with_yes {
+    os::setenv("USER", "foo");
+    let dst = paths::root().join("foo");
+    fs::mkdir(&dst, USER_RWX).assert();
+    File::create(&dst.join("Cargo.toml")).write_str("keep me").assert();
+    assert_that(cargo_process("new").arg("foo").arg("--no-git").arg("--yes"),
+                execs().with_status(1)
+                       .with_stderr(format!("Destination `{}` already exists\n",
+                                            dst.display())));
+    assert_eq!(File::open(&dst.join("Cargo.toml")).read_to_string().assert(),
+               "keep me".to_string());
+})
+
+test!(existing_with_top_level_yes {
+    os::setenv("USER", "foo");
+    let dst = paths::root().join("foo");
+    fs::mkdir(&dst, USER_RWX).assert();
+    let cargo = process(cargo_dir().join("cargo")).cwd(paths::root())
+                    .env("HOME", Some(paths::home()));
+    assert_that(cargo.arg("--yes").arg("new").arg("foo").arg("--no-git"),
+                execs().with_status(0));
+    assert_that(&dst.join("Cargo.toml"), existing_file());
+})
+
+test!(existing_file_with_yes {
+    let dst = paths::root().join("foo");
+    File::create(&dst).assert();
+    assert_that(cargo_process("new").arg("foo").arg("--yes"),
+                execs().with_status(1)
                        .with_stderr(format!("Destination `{}` already exists\n",
                                             dst.display())));
 })
 
 test!(invalid_characters {
     assert_that(cargo_process("new").arg("foo.rs"),
-                execs().with_status(101)
-                       .with_stderr("Invalid character `.` in crate name: `foo.rs`"));
+                execs().with_status(1)
+                       .with_stderr("invalid character `.` in package name: \
+                                     `foo.rs`, only alphanumeric characters, \
+                                     `-` and `_` are allowed"));
+})
+
+test!(reserved_name {
+    assert_that(cargo_process("new").arg("test"),
+                execs().with_status(1)
+                       .with_stderr("`test` is a reserved name and cannot be \
+                                     used as a package name"));
+})
+
+test!(leading_digit {
+    assert_that(cargo_process("new").arg("1foo"),
+                execs().with_status(1)
+                       .with_stderr("package names cannot start with a digit: \
+                                     `1foo`"));
 })
 
 test!(finds_author_user {
@@ -202,3 +278,118 @@ test!(subpackage_no_git {
     assert_that(&paths::root().join("foo/components/subcomponent/.gitignore"),
                  is_not(existing_file()));
 })
+
+test!(author_prefers_env_over_git {
+    my_process("git").args(["config", "--global", "user.name", "bar"])
+                     .exec().assert();
+    my_process("git").args(["config", "--global", "user.email", "baz"])
+                     .exec().assert();
+    assert_that(cargo_process("new").arg("foo").env("USER", Some("foo"))
+                                    .env("CARGO_NAME", Some("env-foo"))
+                                    .env("CARGO_EMAIL", Some("env-bar")),
+                execs().with_status(0));
+
+    let toml = paths::root().join("foo/Cargo.toml");
+    let toml = File::open(&toml).read_to_string().assert();
+    assert!(toml.as_slice().contains(r#"authors = ["env-foo <env-bar>"]"#));
+})
+
+test!(author_prefers_cargo_over_env {
+    let root = paths::root();
+    fs::mkdir(&root.join(".cargo"), USER_RWX).assert();
+    File::create(&root.join(".cargo/config")).write_str(r#"
+        [cargo-new]
+        name = "new-foo"
+        email = "new-bar"
+    "#).assert();
+
+    assert_that(cargo_process("new").arg("foo").env("USER", Some("foo"))
+                                    .env("CARGO_NAME", Some("env-foo"))
+                                    .env("CARGO_EMAIL", Some("env-bar")),
+                execs().with_status(0));
+
+    let toml = paths::root().join("foo/Cargo.toml");
+    let toml = File::open(&toml).read_to_string().assert();
+    assert!(toml.as_slice().contains(r#"authors = ["new-foo <new-bar>"]"#));
+})
+
+test!(author_name_and_email_are_found_separately {
+    my_process("git").args(["config", "--global", "user.email", "baz"])
+                     .exec().assert();
+    let root = paths::root();
+    fs::mkdir(&root.join(".cargo"), USER_RWX).assert();
+    File::create(&root.join(".cargo/config")).write_str(r#"
+        [cargo-new]
+        name = "new-foo"
+    "#).assert();
+
+    assert_that(cargo_process("new").arg("foo").env("USER", Some("foo")),
+                execs().with_status(0));
+    let toml = paths::root().join("foo/Cargo.toml");
+    let toml = File::open(&toml).read_to_string().assert();
+    assert!(toml.as_slice().contains(r#"authors = ["new-foo <baz>"]"#));
+
+    assert_that(cargo_process("new").arg("bar").env("USER", Some("foo"))
+                                    .env("CARGO_EMAIL", Some("env-bar")),
+                execs().with_status(0));
+    let toml = paths::root().join("bar/Cargo.toml");
+    let toml = File::open(&toml).read_to_string().assert();
+    assert!(toml.as_slice().contains(r#"authors = ["new-foo <env-bar>"]"#));
+})
+
+test!(vcs_none {
+    let td = TempDir::new("cargo").unwrap();
+    assert_that(cargo_process("new").arg("foo").arg("--vcs").arg("none")
+                                    .env("USER", Some("foo"))
+                                    .cwd(td.path().clone()),
+                execs().with_status(0));
+
+    assert_that(&td.path().join("foo/Cargo.toml"), existing_file());
+    assert_that(&td.path().join("foo/.git"), is_not(existing_dir()));
+    assert_that(&td.path().join("foo/.gitignore"), is_not(existing_file()));
+})
+
+test!(vcs_git_inside_existing_repo {
+    os::setenv("USER", "foo");
+    assert_that(cargo_process("new").arg("foo"), execs().with_status(0));
+    assert_that(cargo_process("new").arg("foo/sub").arg("--vcs").arg("git"),
+                execs().with_status(0));
+
+    assert_that(&paths::root().join("foo/sub/.git"), existing_dir());
+    assert_that(&paths::root().join("foo/sub/.gitignore"), existing_file());
+})
+
+test!(vcs_unknown {
+    assert_that(cargo_process("new").arg("foo").arg("--vcs").arg("svn"),
+                execs().with_status(1)
+                       .with_stderr("unknown version control system `svn`, \
+                                     expected one of `git`, `hg` or `none`\n"));
+    assert_that(&paths::root().join("foo"), is_not(existing_dir()));
+})
+
+test!(vcs_conflicts_with_flags {
+    assert_that(cargo_process("new").arg("foo").arg("--vcs").arg("git")
+                                    .arg("--no-git"),
+                execs().with_status(1)
+                       .with_stderr("only one of --vcs, --git, --hg and \
+                                     --no-git may be given\n"));
+})
+
+test!(explicit_name {
+    os::setenv("USER", "foo");
+    assert_that(cargo_process("new").arg("foo-dir").arg("--name").arg("bar")
+                                    .arg("--vcs").arg("none"),
+                execs().with_status(0));
+
+    let toml = paths::root().join("foo-dir/Cargo.toml");
+    let toml = File::open(&toml).read_to_string().assert();
+    assert!(toml.as_slice().contains(r#"name = "bar""#));
+})
+
+test!(explicit_name_is_validated {
+    assert_that(cargo_process("new").arg("foo").arg("--name").arg("bar.rs"),
+                execs().with_status(1)
+                       .with_stderr("invalid character `.` in package name: \
+                                     `bar.rs`, only alphanumeric characters, \
+                                     `-` and `_` are allowed"));
+})
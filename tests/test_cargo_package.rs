@@ -4,8 +4,8 @@ use tar::Archive;
 use flate2::reader::GzDecoder;
 
 use support::{project, execs, cargo_dir, ResultTest};
-use support::{PACKAGING, VERIFYING, COMPILING};
-use hamcrest::{assert_that, existing_file};
+use support::{PACKAGING, VERIFYING, COMPILING, STRIPPING};
+use hamcrest::{assert_that, existing_file, is_not};
 
 fn setup() {
 }
@@ -29,6 +29,7 @@ test!(simple {
 {packaging} foo v0.0.1 ({dir})
 {verifying} foo v0.0.1 ({dir})
 {compiling} foo v0.0.1 ({dir}[..])
+    Finished [..]
 ",
         packaging = PACKAGING,
         verifying = VERIFYING,
@@ -46,7 +47,247 @@ test!(simple {
         let f = f.assert();
         let fname = f.filename_bytes();
         assert!(fname == Path::new("foo-0.0.1/Cargo.toml").as_vec() ||
+                fname == Path::new("foo-0.0.1/Cargo.toml.orig").as_vec() ||
                 fname == Path::new("foo-0.0.1/src/main.rs").as_vec(),
                 "unexpected filename: {}", f.filename())
     }
 })
+
+test!(path_dev_dependencies_are_stripped {
+    let p = project("foo")
+        .file("Cargo.toml", r#"
+            [project]
+            name = "foo"
+            version = "0.0.1"
+            authors = []
+
+            [dev-dependencies]
+            bar = { path = "bar" }
+
+            [dev-dependencies.baz]
+            path = "baz"
+            version = "0.1.0"
+        "#)
+        .file("src/lib.rs", "")
+        .file("tests/integration.rs", "extern crate bar;")
+        .file("bar/Cargo.toml", r#"
+            [project]
+            name = "bar"
+            version = "0.1.0"
+            authors = []
+        "#)
+        .file("bar/src/lib.rs", "")
+        .file("baz/Cargo.toml", r#"
+            [project]
+            name = "baz"
+            version = "0.1.0"
+            authors = []
+        "#)
+        .file("baz/src/lib.rs", "");
+
+    assert_that(p.cargo_process("package"),
+                execs().with_status(0).with_stdout(format!("\
+{packaging} foo v0.0.1 ({dir})
+{stripping} dev-dependencies without a version: `bar`
+{verifying} foo v0.0.1 ({dir})
+{compiling} foo v0.0.1 ({dir}[..])
+    Finished [..]
+",
+        packaging = PACKAGING,
+        stripping = STRIPPING,
+        verifying = VERIFYING,
+        compiling = COMPILING,
+        dir = p.url()).as_slice()));
+
+    let f = File::open(&p.root().join("target/package/foo-0.0.1.crate")).assert();
+    let mut rdr = GzDecoder::new(f);
+    let contents = rdr.read_to_end().assert();
+    let ar = Archive::new(MemReader::new(contents));
+    let mut manifest = None;
+    for f in ar.files().assert() {
+        let mut f = f.assert();
+        if f.filename_bytes() == Path::new("foo-0.0.1/Cargo.toml").as_vec() {
+            manifest = Some(f.read_to_string().assert());
+        }
+    }
+    let manifest = manifest.expect("no Cargo.toml in the tarball");
+    assert!(!manifest.as_slice().contains("bar"), "{}", manifest);
+    assert!(manifest.as_slice().contains("dev-dependencies.baz"),
+            "{}", manifest);
+    assert_that(&p.root().join("target/package/foo-0.0.1.toml"),
+                is_not(existing_file()));
+})
+
+test!(packaged_manifest_is_normalized {
+    let manifest = r#"
+            [package]
+            name = "foo"
+            version = "0.0.1"
+            authors = []
+            description = "normalized"
+            exclude = ["*.txt"]
+
+            [package.metadata.docs]
+            theme = "dark"
+
+            [dependencies.bar]
+            path = "bar"
+            version = "0.1.0"
+            features = ["fast"]
+
+            [dev-dependencies.baz]
+            path = "baz"
+
+            [profile.release]
+            opt-level = 2
+        "#;
+    let p = project("foo")
+        .file("Cargo.toml", manifest)
+        .file("src/lib.rs", "")
+        .file("src/main.rs", "fn main() {}")
+        .file("bar/Cargo.toml", r#"
+            [package]
+            name = "bar"
+            version = "0.1.0"
+            authors = []
+
+            [features]
+            fast = []
+        "#)
+        .file("bar/src/lib.rs", "")
+        .file("baz/Cargo.toml", r#"
+            [package]
+            name = "baz"
+            version = "0.1.0"
+            authors = []
+        "#)
+        .file("baz/src/lib.rs", "");
+
+    assert_that(p.cargo_process("package").arg("--no-verify"),
+                execs().with_status(0));
+
+    let f = File::open(&p.root().join("target/package/foo-0.0.1.crate")).assert();
+    let mut rdr = GzDecoder::new(f);
+    let contents = rdr.read_to_end().assert();
+    let mut ar = Archive::new(MemReader::new(contents));
+    ar.unpack(&p.root().join("unpacked")).assert();
+
+    let dir = p.root().join("unpacked/foo-0.0.1");
+    let orig = File::open(&dir.join("Cargo.toml.orig")).read_to_string()
+                    .assert();
+    assert_eq!(orig.as_slice(), manifest);
+
+    let normalized = File::open(&dir.join("Cargo.toml")).read_to_string()
+                          .assert();
+    let normalized = normalized.as_slice();
+    assert!(normalized.contains("[lib]"), "{}", normalized);
+    assert!(normalized.contains("[[bin]]"), "{}", normalized);
+    assert!(normalized.contains("path = \"src/main.rs\""), "{}", normalized);
+    assert!(normalized.contains("[dependencies.bar]"), "{}", normalized);
+    assert!(normalized.contains("version = \"0.1.0\""), "{}", normalized);
+    assert!(normalized.contains("[profile.release]"), "{}", normalized);
+    assert!(normalized.contains("description = \"normalized\""),
+            "{}", normalized);
+    assert!(normalized.contains("[package.metadata.docs]"), "{}", normalized);
+    assert!(normalized.contains("theme = \"dark\""), "{}", normalized);
+    assert!(!normalized.contains("path = \"bar\""), "{}", normalized);
+    assert!(!normalized.contains("baz"), "{}", normalized);
+    assert!(!normalized.contains("exclude"), "{}", normalized);
+
+    // The normalized manifest loads on its own, without any warnings
+    assert_that(p.process(cargo_dir().join("cargo")).arg("read-manifest")
+                 .arg("--manifest-path").arg(dir.join("Cargo.toml")),
+                execs().with_status(0).with_stderr(""));
+})
+
+test!(exclude_matching_nothing_warns {
+    let p = project("foo")
+        .file("Cargo.toml", r#"
+            [project]
+            name = "foo"
+            version = "0.0.1"
+            authors = []
+            exclude = ["*.txt", "srcdata/*"]
+        "#)
+        .file("src/main.rs", "fn main() {}")
+        .file("notes.txt", "");
+
+    assert_that(p.cargo_process("package").arg("--no-verify"),
+                execs().with_status(0).with_stdout(format!("\
+{packaging} foo v0.0.1 ({dir})
+", packaging = PACKAGING, dir = p.url()).as_slice()).with_stderr("\
+the `exclude` pattern `srcdata/*` doesn't match any file
+"));
+})
+
+test!(exclude_overlapping_patterns_do_not_warn {
+    let p = project("foo")
+        .file("Cargo.toml", r#"
+            [project]
+            name = "foo"
+            version = "0.0.1"
+            authors = []
+            exclude = ["*.txt", "notes.txt"]
+        "#)
+        .file("src/main.rs", "fn main() {}")
+        .file("notes.txt", "");
+
+    assert_that(p.cargo_process("package").arg("--no-verify"),
+                execs().with_status(0).with_stdout(format!("\
+{packaging} foo v0.0.1 ({dir})
+", packaging = PACKAGING, dir = p.url()).as_slice()).with_stderr(""));
+})
+
+test!(exclude_of_target_source_is_an_error {
+    let p = project("foo")
+        .file("Cargo.toml", r#"
+            [project]
+            name = "foo"
+            version = "0.0.1"
+            authors = []
+            exclude = ["src/*.rs"]
+        "#)
+        .file("src/lib.rs", "");
+
+    assert_that(p.cargo_process("package").arg("--no-verify"),
+                execs().with_status(1).with_stderr("\
+`src/lib.rs`, the source of target `foo`, would be left out of the package: \
+it matches the `exclude` pattern `src/*.rs`
+"));
+    assert_that(&p.root().join("target/package/foo-0.0.1.crate"),
+                is_not(existing_file()));
+})
+
+test!(path_dependency_without_version_fails_without_verifying {
+    let p = project("foo")
+        .file("Cargo.toml", r#"
+            [project]
+            name = "foo"
+            version = "0.0.1"
+            authors = []
+
+            [dependencies.bar]
+            path = "bar"
+        "#)
+        .file("src/main.rs", "fn main() {}")
+        .file("bar/Cargo.toml", r#"
+            [project]
+            name = "bar"
+            version = "0.0.1"
+            authors = []
+        "#)
+        .file("bar/src/lib.rs", "");
+
+    // The tarball's manifest can't be written without a version for `bar`,
+    // whether or not the package is verified afterwards.
+    assert_that(p.cargo_process("package").arg("--no-verify"),
+                execs().with_status(1).with_stderr("\
+failed to prepare local package for uploading
+
+Caused by:
+  dependency `bar` does not specify a version, which is required for every \
+dependency of a package uploaded to the registry
+"));
+    assert_that(&p.root().join("target/package/foo-0.0.1.crate"),
+                is_not(existing_file()));
+})
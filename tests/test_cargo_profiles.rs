@@ -1,4 +1,3 @@
-use std::os;
 use std::path;
 
 use support::{project, execs};
@@ -14,7 +13,7 @@ test!(profile_overrides {
         .file("Cargo.toml", r#"
             [package]
 
-            name = "test"
+            name = "testing"
             version = "0.0.0"
             authors = []
 
@@ -26,17 +25,18 @@ test!(profile_overrides {
         .file("src/lib.rs", "");
     assert_that(p.cargo_process("build").arg("-v"),
                 execs().with_status(0).with_stdout(format!("\
-{compiling} test v0.0.0 ({url})
-{running} `rustc {dir}{sep}src{sep}lib.rs --crate-name test --crate-type lib \
+{compiling} testing v0.0.0 ({url})
+{running} `[..]rustc {dir}{sep}src{sep}lib.rs --crate-name testing --crate-type lib \
         --opt-level 1 \
         --cfg ndebug \
         -C metadata=[..] \
         -C extra-filename=-[..] \
         -C rpath \
-        --out-dir {dir}{sep}target \
+        --out-dir {dir}{sep}target{sep}debug{sep}deps \
         --dep-info [..] \
-        -L {dir}{sep}target \
-        -L {dir}{sep}target{sep}deps`
+        -L {dir}{sep}target{sep}debug \
+        -L {dir}{sep}target{sep}debug{sep}deps`
+    Finished [..]
 ",
 running = RUNNING, compiling = COMPILING, sep = path::SEP,
 dir = p.root().display(),
@@ -50,7 +50,7 @@ test!(top_level_overrides_deps {
         .file("Cargo.toml", r#"
             [package]
 
-            name = "test"
+            name = "testing"
             version = "0.0.0"
             authors = []
 
@@ -81,35 +81,159 @@ test!(top_level_overrides_deps {
     assert_that(p.cargo_process("build").arg("-v").arg("--release"),
                 execs().with_status(0).with_stdout(format!("\
 {compiling} foo v0.0.0 ({url})
-{running} `rustc {dir}{sep}foo{sep}src{sep}lib.rs --crate-name foo \
+{running} `[..]rustc {dir}{sep}foo{sep}src{sep}lib.rs --crate-name foo \
         --crate-type dylib --crate-type rlib -C prefer-dynamic \
         --opt-level 1 \
-        -g \
+        -C debuginfo=2 \
         -C metadata=[..] \
         -C extra-filename=-[..] \
         --out-dir {dir}{sep}target{sep}release{sep}deps \
         --dep-info [..] \
         -L {dir}{sep}target{sep}release{sep}deps \
         -L {dir}{sep}target{sep}release{sep}deps`
-{compiling} test v0.0.0 ({url})
-{running} `rustc {dir}{sep}src{sep}lib.rs --crate-name test --crate-type lib \
+{compiling} testing v0.0.0 ({url})
+{running} `[..]rustc {dir}{sep}src{sep}lib.rs --crate-name testing --crate-type lib \
         --opt-level 1 \
-        -g \
+        -C debuginfo=2 \
         -C metadata=[..] \
         -C extra-filename=-[..] \
-        --out-dir {dir}{sep}target{sep}release \
+        --out-dir {dir}{sep}target{sep}release{sep}deps \
         --dep-info [..] \
         -L {dir}{sep}target{sep}release \
         -L {dir}{sep}target{sep}release{sep}deps \
-        --extern foo={dir}{sep}target{sep}release{sep}deps/\
-                     {prefix}foo-[..]{suffix} \
         --extern foo={dir}{sep}target{sep}release{sep}deps/libfoo-[..].rlib`
+    Finished [..]
 ",
                     running = RUNNING,
                     compiling = COMPILING,
                     dir = p.root().display(),
                     url = p.url(),
-                    sep = path::SEP,
-                    prefix = os::consts::DLL_PREFIX,
-                    suffix = os::consts::DLL_SUFFIX).as_slice()));
+                    sep = path::SEP).as_slice()));
+})
+
+test!(debuginfo_level {
+    let mut p = project("foo");
+    p = p
+        .file("Cargo.toml", r#"
+            [package]
+
+            name = "testing"
+            version = "0.0.0"
+            authors = []
+
+            [profile.dev]
+            debug = 1
+        "#)
+        .file("src/lib.rs", "");
+    assert_that(p.cargo_process("build").arg("-v"),
+                execs().with_status(0).with_stdout(format!("\
+{compiling} testing v0.0.0 ({url})
+{running} `[..]rustc {dir}{sep}src{sep}lib.rs --crate-name testing --crate-type lib \
+        -C debuginfo=1 \
+        -C metadata=[..] \
+        -C extra-filename=-[..] \
+        --out-dir {dir}{sep}target{sep}debug{sep}deps \
+        --dep-info [..] \
+        -L {dir}{sep}target{sep}debug \
+        -L {dir}{sep}target{sep}debug{sep}deps`
+    Finished [..]
+",
+running = RUNNING, compiling = COMPILING, sep = path::SEP,
+dir = p.root().display(),
+url = p.url(),
+)));
+})
+
+test!(invalid_debuginfo_level {
+    let mut p = project("foo");
+    p = p
+        .file("Cargo.toml", r#"
+            [package]
+
+            name = "testing"
+            version = "0.0.0"
+            authors = []
+
+            [profile.dev]
+            debug = 3
+        "#)
+        .file("src/lib.rs", "");
+    assert_that(p.cargo_process("build"),
+                execs().with_status(3).with_stderr("\
+Cargo.toml is not a valid manifest
+
+[..]invalid debuginfo level `3`, the accepted values are `true`, `false`, \
+0, 1 or 2[..]
+"));
+})
+
+test!(negative_debuginfo_level {
+    let p = project("foo")
+        .file("Cargo.toml", r#"
+            [package]
+            name = "testing"
+            version = "0.0.0"
+            authors = []
+
+            [profile.dev]
+            debug = -1
+        "#)
+        .file("src/lib.rs", "");
+    assert_that(p.cargo_process("build"),
+                execs().with_status(3).with_stderr("\
+Cargo.toml is not a valid manifest
+
+[..]invalid debuginfo level `-1`, the accepted values are `true`, `false`, \
+0, 1 or 2[..]
+"));
+})
+
+test!(build_with_test_profile {
+    let p = project("foo")
+        .file("Cargo.toml", r#"
+            [package]
+            name = "foo"
+            version = "0.0.0"
+            authors = []
+        "#)
+        .file("src/lib.rs", r#"
+            #[test]
+            fn fails() { panic!() }
+        "#)
+        .file("tests/it.rs", r#"
+            #[test]
+            fn fails() { panic!() }
+        "#);
+
+    // The failing tests aren't run, only built.
+    assert_that(p.cargo_process("build").arg("--profile").arg("test"),
+                execs().with_status(0).with_stdout(format!("\
+{compiling} foo v0.0.0 ({url})
+    Finished [..]
+  Executable {dir}{sep}target{sep}[..]foo-[..]
+  Executable {dir}{sep}target{sep}[..]it-[..]
+",
+        compiling = COMPILING, url = p.url(), dir = p.root().display(),
+        sep = path::SEP).as_slice()));
+})
+
+test!(build_with_unknown_profile {
+    let p = project("foo")
+        .file("Cargo.toml", r#"
+            [package]
+            name = "foo"
+            version = "0.0.0"
+            authors = []
+        "#)
+        .file("src/lib.rs", "");
+
+    assert_that(p.cargo_process("build").arg("--profile").arg("debug"),
+                execs().with_status(1).with_stderr("\
+unknown profile `debug`, expected one of: dev, release, test, bench, doc
+"));
+    assert_that(p.cargo_process("build").arg("--profile").arg("test")
+                 .arg("--release"),
+                execs().with_status(1).with_stderr("\
+`--release` can't be combined with `--profile test`
+"));
 })
@@ -1,4 +1,9 @@
+use std::collections::HashMap;
 use std::io::{mod, fs, File, MemReader};
+use std::io::{TcpListener, TcpStream, Listener, Acceptor, BufferedStream};
+use std::io::fs::PathExtensions;
+use std::io::net::tcp::TcpAcceptor;
+use std::os;
 
 use flate2::reader::GzDecoder;
 use tar::Archive;
@@ -7,6 +12,7 @@ use url::Url;
 use support::{ResultTest, project, execs};
 use support::{UPDATING, PACKAGING, UPLOADING};
 use support::paths;
+use support::registry as r;
 use support::git::repo;
 
 use hamcrest::assert_that;
@@ -89,7 +95,7 @@ test!(git_deps {
         .file("src/main.rs", "fn main() {}");
 
     assert_that(p.cargo_process("publish").arg("-v").arg("--no-verify"),
-                execs().with_status(101).with_stderr("\
+                execs().with_status(1).with_stderr("\
 all dependencies must come from the same registry.
 dependency `foo` comes from git://path/to/nowhere instead
 "));
@@ -116,9 +122,299 @@ test!(path_dependency_no_version {
         .file("bar/src/lib.rs", "");
 
     assert_that(p.cargo_process("publish"),
-                execs().with_status(101).with_stderr("\
+                execs().with_status(1).with_stderr("\
 all path dependencies must have a version specified when being uploaded \
 to the registry.
 dependency `bar` does not specify a version
 "));
 })
+
+test!(name_must_start_with_letter {
+    let p = project("foo")
+        .file("Cargo.toml", r#"
+            [project]
+            name = "_foo"
+            version = "0.0.1"
+            authors = []
+        "#)
+        .file("src/main.rs", "fn main() {}");
+
+    assert_that(p.cargo_process("publish").arg("--no-verify"),
+                execs().with_status(1).with_stderr("\
+package names must start with a letter to be uploaded to the registry: `_foo`
+"));
+})
+
+test!(unknown_license {
+    let p = project("foo")
+        .file("Cargo.toml", r#"
+            [project]
+            name = "foo"
+            version = "0.0.1"
+            authors = []
+            license = "Apache License 2.0"
+        "#)
+        .file("src/main.rs", "fn main() {}");
+
+    assert_that(p.cargo_process("publish").arg("--no-verify"),
+                execs().with_status(1).with_stderr("\
+`Apache License 2.0` is not a valid SPDX license expression: `Apache License \
+2.0` is not a known license identifier, did you mean `[..]`?
+use `license-file` instead for a license which isn't on the SPDX list
+"));
+})
+
+test!(dry_run {
+    let p = project("foo")
+        .file("Cargo.toml", r#"
+            [project]
+            name = "foo"
+            version = "0.0.1"
+            authors = []
+        "#)
+        .file("src/main.rs", "fn main() {}");
+
+    assert_that(p.cargo_process("publish").arg("--no-verify").arg("--dry-run"),
+                execs().with_status(0).with_stdout(format!("\
+{updating} registry `{reg}`
+{packaging} foo v0.0.1 ({dir})
+     Dry run not uploading foo v0.0.1: 2 files, [..] byte tarball, \
+[..] byte request
+",
+        updating = UPDATING,
+        packaging = PACKAGING,
+        dir = p.url(),
+        reg = registry()).as_slice()));
+
+    // Nothing was sent to the registry
+    assert!(!upload_path().join("api/v1/crates/new").exists());
+})
+
+test!(dry_run_still_validates {
+    let p = project("foo")
+        .file("Cargo.toml", r#"
+            [project]
+            name = "foo"
+            version = "0.0.1"
+            authors = []
+
+            [dependencies.foo]
+            git = "git://path/to/nowhere"
+        "#)
+        .file("src/main.rs", "fn main() {}");
+
+    assert_that(p.cargo_process("publish").arg("--no-verify").arg("--dry-run"),
+                execs().with_status(1).with_stderr("\
+all dependencies must come from the same registry.
+dependency `foo` comes from git://path/to/nowhere instead
+"));
+    assert!(!upload_path().join("api/v1/crates/new").exists());
+})
+
+test!(already_published {
+    let p = project("foo")
+        .file("Cargo.toml", r#"
+            [project]
+            name = "foo"
+            version = "0.0.1"
+            authors = []
+        "#)
+        .file("src/main.rs", "fn main() {}");
+    r::publish("3/f/foo", r::pkg("foo", "0.0.1", [], "", false).as_slice());
+
+    assert_that(p.cargo_process("publish").arg("--no-verify"),
+                execs().with_status(1).with_stderr("\
+version 0.0.1 of foo is already published
+"));
+    assert!(!upload_path().join("api/v1/crates/new").exists());
+    assert!(!p.root().join("target/package").exists());
+})
+
+test!(skip_if_published {
+    let p = project("foo")
+        .file("Cargo.toml", r#"
+            [project]
+            name = "foo"
+            version = "0.0.1"
+            authors = []
+        "#)
+        .file("src/main.rs", "fn main() {}");
+    r::publish("3/f/foo", r::pkg("foo", "0.0.1", [], "", true).as_slice());
+
+    assert_that(p.cargo_process("publish").arg("--skip-if-published"),
+                execs().with_status(0).with_stdout(format!("\
+{updating} registry `{reg}`
+    Skipping foo v0.0.1 ({dir}), it is already published
+",
+        updating = UPDATING,
+        dir = p.url(),
+        reg = registry()).as_slice()));
+    assert!(!upload_path().join("api/v1/crates/new").exists());
+})
+
+struct Closer { a: TcpAcceptor }
+
+impl Drop for Closer {
+    fn drop(&mut self) {
+        let _ = self.a.close_accept();
+    }
+}
+
+// Starts a registry whose API is served by `serve`, which is handed each
+// connection in turn, and returns its index.
+fn http_registry(serve: proc(TcpAcceptor):Send) -> (Url, Closer) {
+    let mut listener = TcpListener::bind("127.0.0.1:0").assert();
+    let addr = listener.socket_name().assert();
+    let a = listener.listen().unwrap();
+    let closer = Closer { a: a.clone() };
+    spawn(proc() serve(a));
+
+    let index = paths::root().join("http-registry");
+    repo(&index)
+        .file("config.json", format!(r#"{{
+            "dl": "{0}",
+            "api": "http://{1}"
+        }}"#, upload(), addr))
+        .build();
+    (Url::from_file_path(&index).unwrap(), closer)
+}
+
+// Reads the headers of an upload request, and the body unless `body` is
+// false.
+fn read_upload(s: &mut BufferedStream<TcpStream>,
+               body: bool) -> HashMap<String, String> {
+    let mut headers = HashMap::new();
+    loop {
+        let line = s.read_line().unwrap();
+        let line = line.as_slice().trim();
+        if line.len() == 0 { break }
+        match line.find_str(": ") {
+            Some(i) => {
+                headers.insert(line.slice_to(i).to_string(),
+                               line.slice_from(i + 2).to_string());
+            }
+            None => {}
+        }
+    }
+    if !body { return headers }
+    if headers.get("Expect").map(|s| s.as_slice()) == Some("100-continue") {
+        s.write(b"HTTP/1.1 100 Continue\r\n\r\n").unwrap();
+        s.flush().unwrap();
+    }
+    let len = from_str::<uint>(headers["Content-Length".to_string()]
+                                   .as_slice()).unwrap();
+    s.read_exact(len).unwrap();
+    headers
+}
+
+fn respond(s: &mut BufferedStream<TcpStream>, status: &str, body: &str) {
+    s.write_str(format!("HTTP/1.1 {}\r\nContent-Length: {}\r\n\r\n{}",
+                        status, body.len(), body).as_slice()).unwrap();
+    s.flush().unwrap();
+}
+
+test!(upload_is_retried_after_dropped_connection {
+    let (tx, rx) = channel();
+    let (index, _c) = http_registry(proc(mut a) {
+        // The connection drops before the registry answers, though it
+        // received the upload...
+        let mut s = BufferedStream::new(a.accept().unwrap());
+        let first = read_upload(&mut s, false);
+        drop(s);
+
+        // ...so the retry finds the version already there.
+        let mut s = BufferedStream::new(a.accept().unwrap());
+        let second = read_upload(&mut s, true);
+        let key = second["Idempotency-Key".to_string()].clone();
+        respond(&mut s, "409 Conflict",
+                format!(r#"{{"cksum":"{}"}}"#, key).as_slice());
+        tx.send((first["Idempotency-Key".to_string()].clone(), key));
+    });
+
+    let p = project("foo")
+        .file("Cargo.toml", r#"
+            [project]
+            name = "foo"
+            version = "0.0.1"
+            authors = []
+        "#)
+        .file("src/main.rs", "fn main() {}");
+
+    assert_that(p.cargo_process("publish").arg("--no-verify")
+                 .arg("--host").arg(index.to_string()),
+                execs().with_status(0).with_stdout(format!("\
+{updating} registry `{reg}`
+{packaging} foo v0.0.1 ({dir})
+{uploading} foo v0.0.1 ({dir})
+    Skipping foo v0.0.1 ({dir}), it was already uploaded with the same \
+contents
+",
+        updating = UPDATING,
+        uploading = UPLOADING,
+        packaging = PACKAGING,
+        dir = p.url(),
+        reg = index).as_slice())
+                       .with_stderr(format!("\
+failed to upload foo v0.0.1 ({dir}): http error: [..]
+retrying (attempt 2 of 3)
+", dir = p.url()).as_slice()));
+
+    let (first, second) = rx.recv();
+    assert_eq!(first.len(), 64);
+    assert_eq!(first, second);
+})
+
+test!(upload_conflicting_with_different_contents {
+    let (index, _c) = http_registry(proc(mut a) {
+        let mut s = BufferedStream::new(a.accept().unwrap());
+        read_upload(&mut s, true);
+        respond(&mut s, "409 Conflict", r#"{"cksum":"0123456789abcdef"}"#);
+    });
+
+    let p = project("foo")
+        .file("Cargo.toml", r#"
+            [project]
+            name = "foo"
+            version = "0.0.1"
+            authors = []
+        "#)
+        .file("src/main.rs", "fn main() {}");
+
+    assert_that(p.cargo_process("publish").arg("--no-verify")
+                 .arg("--host").arg(index.to_string()),
+                execs().with_status(1).with_stderr("\
+version 0.0.1 of foo is already published with different contents
+"));
+})
+
+test!(rustc_version_is_probed_once_per_process {
+    if cfg!(windows) { return }
+
+    // Publishing creates a `Config` to check the index, another to update it
+    // and a third for the verification build. A `rustc` wrapper earlier in
+    // PATH logs every version probe.
+    let log = paths::root().join("probes");
+    let bin = paths::root().join("fake-bin");
+    fs::mkdir_recursive(&bin, io::USER_DIR).assert();
+    let path = os::getenv("PATH").unwrap_or(String::new());
+    File::create(&bin.join("rustc")).write_str(format!(r#"#!/bin/sh
+        if [ "$1" = "-v" ]; then echo probe >> "{log}"; fi
+        PATH="{path}" exec rustc "$@"
+    "#, log = log.display(), path = path).as_slice()).assert();
+    fs::chmod(&bin.join("rustc"), io::USER_RWX).assert();
+
+    let p = project("foo")
+        .file("Cargo.toml", r#"
+            [project]
+            name = "foo"
+            version = "0.0.1"
+            authors = []
+        "#)
+        .file("src/main.rs", "fn main() {}");
+
+    let path = format!("{}:{}", bin.display(), path);
+    assert_that(p.cargo_process("publish").env("PATH", Some(path.as_slice())),
+                execs().with_status(0));
+    let probes = File::open(&log).read_to_string().assert();
+    assert_eq!(probes.as_slice().lines().count(), 1);
+})
@@ -0,0 +1,190 @@
+use std::io::{mod, fs, File};
+
+use support::{project, execs, cargo_dir, ResultTest, ProjectBuilder};
+use support::paths;
+use hamcrest::assert_that;
+use cargo::util::ProcessBuilder;
+
+fn setup() {}
+
+// Runs `cargo read-manifest --stdin` for the project `p`, with standard input
+// read from `input`.
+fn read_stdin(p: &ProjectBuilder, input: &Path) -> ProcessBuilder {
+    let cmd = format!("'{}' read-manifest --stdin --root '{}' < '{}'",
+                      cargo_dir().join("cargo").display(), p.root().display(),
+                      input.display());
+    p.process("sh").arg("-c").arg(cmd)
+}
+
+test!(manifest_path {
+    let p = project("foo")
+        .file("Cargo.toml", r#"
+            [package]
+            name = "foo"
+            version = "0.5.0"
+            authors = []
+        "#)
+        .file("src/main.rs", "fn main() {}");
+
+    assert_that(p.cargo_process("read-manifest").arg("--manifest-path")
+                 .arg(p.root().join("Cargo.toml")),
+                execs().with_status(0).with_stdout(format!("\
+{{\"name\":\"foo\",\"version\":\"0.5.0\",\"dependencies\":[],\"targets\":[..],\
+\"links\":null,\"manifest_path\":\"{}\"}}
+", p.root().join("Cargo.toml").display()).as_slice()));
+})
+
+test!(stdin_matches_manifest_path {
+    if cfg!(windows) { return }
+
+    let p = project("foo")
+        .file("Cargo.toml", r#"
+            [package]
+            name = "foo"
+            version = "0.5.0"
+            authors = []
+        "#)
+        .file("src/main.rs", "fn main() {}")
+        .file("bar/Cargo.toml", r#"
+            [package]
+            name = "bar"
+            version = "0.5.0"
+            authors = []
+        "#)
+        .file("bar/src/lib.rs", "");
+    p.build();
+
+    // The unsaved manifest adds a dependency and isn't written to the project.
+    let input = paths::root().join("unsaved.toml");
+    File::create(&input).write_str(r#"
+        [package]
+        name = "foo"
+        version = "0.5.0"
+        authors = []
+
+        [dependencies.bar]
+        path = "bar"
+    "#).assert();
+
+    assert_that(read_stdin(&p, &input),
+                execs().with_status(0).with_stdout(format!("\
+{{\"name\":\"foo\",\"version\":\"0.5.0\",\"dependencies\":[{{\"name\":\"bar\",\
+[..]}}],\"targets\":[{{[..]\"name\":\"foo\"[..]}}],\
+\"links\":null,\"manifest_path\":\"{}\"}}
+", p.root().join("Cargo.toml").display()).as_slice()));
+})
+
+test!(stdin_errors_name_stdin {
+    if cfg!(windows) { return }
+
+    let p = project("foo").file("src/main.rs", "fn main() {}");
+    p.build();
+    let input = paths::root().join("unsaved.toml");
+    File::create(&input).write_str("[package]\na = bar\n").assert();
+
+    assert_that(read_stdin(&p, &input),
+                execs().with_status(3).with_stderr("\
+could not parse input TOML
+<stdin>:2:5-2:6 expected a value
+
+"));
+})
+
+test!(stdin_warnings {
+    if cfg!(windows) { return }
+
+    let p = project("foo").file("src/main.rs", "fn main() {}");
+    p.build();
+    let input = paths::root().join("unsaved.toml");
+    File::create(&input).write_str(r#"
+        [package]
+        name = "foo"
+        version = "0.5.0"
+        authors = []
+        unused = true
+    "#).assert();
+
+    assert_that(read_stdin(&p, &input),
+                execs().with_status(0).with_stderr("\
+unused manifest key: package.unused
+"));
+})
+
+// Makes the directory `path` accessible again once the test is done with it,
+// even if it fails, so the project can still be removed.
+struct RestorePermissions {
+    path: Path,
+}
+
+impl Drop for RestorePermissions {
+    fn drop(&mut self) {
+        let _ = fs::chmod(&self.path, io::USER_DIR);
+    }
+}
+
+#[cfg(unix)]
+fn running_as_root() -> bool {
+    unsafe { ::libc::funcs::posix88::unistd::geteuid() == 0 }
+}
+
+#[cfg(not(unix))]
+fn running_as_root() -> bool { false }
+
+test!(unreadable_tests_dir_warns {
+    // Permissions don't stop root from reading the directory.
+    if cfg!(windows) || running_as_root() { return }
+
+    let p = project("foo")
+        .file("Cargo.toml", r#"
+            [package]
+            name = "foo"
+            version = "0.5.0"
+            authors = []
+        "#)
+        .file("src/main.rs", "fn main() {}")
+        .file("tests/foo.rs", "");
+    p.build();
+    let tests = p.root().join("tests");
+    fs::chmod(&tests, io::FilePermission::empty()).assert();
+    let _restore = RestorePermissions { path: tests };
+
+    assert_that(p.process(cargo_dir().join("cargo")).arg("read-manifest")
+                 .arg("--manifest-path").arg(p.root().join("Cargo.toml")),
+                execs().with_status(0).with_stderr("\
+failed to read the directory `tests`, no targets were inferred from it: [..]
+"));
+})
+
+test!(invalid_manifest_error_chain {
+    let p = project("foo")
+        .file("Cargo.toml", r#"
+            [package]
+            name = "foo"
+            version = "0.5.0"
+            authors = []
+
+            [dependencies.bar]
+            git = "github.com/a/bar"
+        "#)
+        .file("src/main.rs", "fn main() {}");
+    p.build();
+
+    assert_that(p.process(cargo_dir().join("cargo")).arg("read-manifest")
+                 .arg("--manifest-path").arg(p.root().join("Cargo.toml")),
+                execs().with_status(3).with_stdout("").with_stderr("\
+Cargo.toml is not a valid manifest
+
+invalid url `github.com/a/bar`: Relative URL without a base
+"));
+
+    assert_that(p.process(cargo_dir().join("cargo")).arg("read-manifest")
+                 .arg("--manifest-path").arg(p.root().join("Cargo.toml"))
+                 .arg("--message-format").arg("json"),
+                execs().with_status(3).with_stderr("").with_stdout("\
+{\"error\":[\
+{\"message\":\"Cargo.toml is not a valid manifest\",\
+\"file\":\"Cargo.toml\",\"kind\":\"invalid-manifest\"},\
+{\"message\":\"invalid url `github.com/a/bar`: Relative URL without a base\",\
+\"file\":null,\"kind\":null}]}
+"));
+})
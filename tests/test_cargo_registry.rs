@@ -32,6 +32,7 @@ test!(simple {
 {downloading} bar v0.0.1 (the package registry)
 {compiling} bar v0.0.1 (the package registry)
 {compiling} foo v0.0.1 ({dir})
+    Finished [..]
 ",
         updating = UPDATING,
         downloading = DOWNLOADING,
@@ -45,6 +46,7 @@ test!(simple {
 {updating} registry `{reg}`
 [..] bar v0.0.1 (the package registry)
 [..] foo v0.0.1 ({dir})
+    Finished [..]
 ",
         updating = UPDATING,
         dir = p.url(),
@@ -75,6 +77,7 @@ test!(deps {
 {compiling} baz v0.0.1 (the package registry)
 {compiling} bar v0.0.1 (the package registry)
 {compiling} foo v0.0.1 ({dir})
+    Finished [..]
 ",
         updating = UPDATING,
         downloading = DOWNLOADING,
@@ -97,13 +100,64 @@ test!(nonexistent {
         .file("src/main.rs", "fn main() {}");
 
     assert_that(p.cargo_process("build"),
-                execs().with_status(101).with_stderr("\
+                execs().with_status(1).with_stderr("\
 no package named `nonexistent` found (required by `foo`)
 location searched: the package registry
 version required: >= 0.0.0
 "));
 })
 
+test!(misspelled_dependency_suggests_similar_name {
+    let p = project("foo")
+        .file("Cargo.toml", r#"
+            [project]
+            name = "foo"
+            version = "0.0.1"
+            authors = []
+
+            [dependencies]
+            Bar-Baz = "*"
+        "#)
+        .file("src/main.rs", "fn main() {}");
+    p.build();
+
+    r::mock_pkg("bar_baz", "0.0.1", []);
+
+    assert_that(p.process(cargo_dir().join("cargo")).arg("build"),
+                execs().with_status(1).with_stderr("\
+no package named `Bar-Baz` found (required by `foo`)
+location searched: the package registry
+version required: *
+did you mean `bar_baz`?
+"));
+})
+
+test!(update_spec_suggests_similar_name {
+    let p = project("foo")
+        .file("Cargo.toml", r#"
+            [project]
+            name = "foo"
+            version = "0.0.1"
+            authors = []
+
+            [dependencies]
+            bar-baz = "*"
+        "#)
+        .file("src/main.rs", "fn main() {}");
+    p.build();
+
+    r::mock_pkg("bar-baz", "0.0.1", []);
+
+    assert_that(p.process(cargo_dir().join("cargo")).arg("build"),
+                execs().with_status(0));
+    assert_that(p.process(cargo_dir().join("cargo")).arg("update")
+                 .arg("-p").arg("bar_baz"),
+                execs().with_status(1).with_stderr("\
+package id specification `bar_baz` matched no packages
+did you mean `bar-baz`?
+"));
+})
+
 test!(bad_cksum {
     let p = project("foo")
         .file("Cargo.toml", r#"
@@ -121,7 +175,7 @@ test!(bad_cksum {
     File::create(&r::mock_archive_dst("bad-cksum", "0.0.1")).unwrap();
 
     assert_that(p.cargo_process("build").arg("-v"),
-                execs().with_status(101).with_stderr("\
+                execs().with_status(1).with_stderr("\
 Unable to get packages from source
 
 Caused by:
@@ -146,7 +200,7 @@ test!(update_registry {
         .file("src/main.rs", "fn main() {}");
 
     assert_that(p.cargo_process("build"),
-                execs().with_status(101).with_stderr("\
+                execs().with_status(1).with_stderr("\
 no package named `notyet` found (required by `foo`)
 location searched: the package registry
 version required: >= 0.0.0
@@ -160,6 +214,7 @@ version required: >= 0.0.0
 {downloading} notyet v0.0.1 (the package registry)
 {compiling} notyet v0.0.1 (the package registry)
 {compiling} foo v0.0.1 ({dir})
+    Finished [..]
 ",
         updating = UPDATING,
         downloading = DOWNLOADING,
@@ -191,7 +246,7 @@ test!(package_with_path_deps {
     p.build();
 
     assert_that(p.process(cargo_dir().join("cargo")).arg("package").arg("-v"),
-                execs().with_status(101).with_stderr("\
+                execs().with_status(1).with_stderr("\
 failed to verify package tarball
 
 Caused by:
@@ -210,6 +265,7 @@ version required: ^0.0.1
 {downloading} notyet v0.0.1 (the package registry)
 {compiling} notyet v0.0.1 (the package registry)
 {compiling} foo v0.0.1 ({dir}[..])
+    Finished [..]
 ",
     packaging = PACKAGING,
     verifying = VERIFYING,
@@ -242,6 +298,7 @@ test!(lockfile_locks {
 {downloading} bar v0.0.1 (the package registry)
 {compiling} bar v0.0.1 (the package registry)
 {compiling} foo v0.0.1 ({dir})
+    Finished [..]
 ", updating = UPDATING, downloading = DOWNLOADING, compiling = COMPILING,
    dir = p.url()).as_slice()));
 
@@ -249,7 +306,7 @@ test!(lockfile_locks {
     r::mock_pkg("bar", "0.0.2", []);
 
     assert_that(p.process(cargo_dir().join("cargo")).arg("build"),
-                execs().with_status(0).with_stdout(""));
+                execs().with_status(0).with_stdout("    Finished [..]\n"));
 })
 
 test!(lockfile_locks_transitively {
@@ -277,6 +334,7 @@ test!(lockfile_locks_transitively {
 {compiling} baz v0.0.1 (the package registry)
 {compiling} bar v0.0.1 (the package registry)
 {compiling} foo v0.0.1 ({dir})
+    Finished [..]
 ", updating = UPDATING, downloading = DOWNLOADING, compiling = COMPILING,
    dir = p.url()).as_slice()));
 
@@ -285,7 +343,7 @@ test!(lockfile_locks_transitively {
     r::mock_pkg("bar", "0.0.2", [("baz", "*")]);
 
     assert_that(p.process(cargo_dir().join("cargo")).arg("build"),
-                execs().with_status(0).with_stdout(""));
+                execs().with_status(0).with_stdout("    Finished [..]\n"));
 })
 
 test!(yanks_are_not_used {
@@ -315,6 +373,7 @@ test!(yanks_are_not_used {
 {compiling} baz v0.0.1 (the package registry)
 {compiling} bar v0.0.1 (the package registry)
 {compiling} foo v0.0.1 ({dir})
+    Finished [..]
 ", updating = UPDATING, downloading = DOWNLOADING, compiling = COMPILING,
    dir = p.url()).as_slice()));
 })
@@ -338,7 +397,7 @@ test!(relying_on_a_yank_is_bad {
     r::mock_pkg("bar", "0.0.1", [("baz", "=0.0.2")]);
 
     assert_that(p.process(cargo_dir().join("cargo")).arg("build"),
-                execs().with_status(101).with_stderr("\
+                execs().with_status(1).with_stderr("\
 no package named `baz` found (required by `bar`)
 location searched: the package registry
 version required: = 0.0.2
@@ -369,10 +428,10 @@ test!(yanks_in_lockfiles_are_ok {
     r::mock_pkg_yank("bar", "0.0.1", [], true);
 
     assert_that(p.process(cargo_dir().join("cargo")).arg("build"),
-                execs().with_status(0).with_stdout(""));
+                execs().with_status(0).with_stdout("    Finished [..]\n"));
 
     assert_that(p.process(cargo_dir().join("cargo")).arg("update"),
-                execs().with_status(101).with_stderr("\
+                execs().with_status(1).with_stderr("\
 no package named `bar` found (required by `foo`)
 location searched: the package registry
 version required: *
@@ -403,6 +462,7 @@ test!(update_with_lockfile_if_packages_missing {
                 execs().with_status(0).with_stdout(format!("\
 {updating} registry `[..]`
 {downloading} bar v0.0.1 (the package registry)
+    Finished [..]
 ", updating = UPDATING, downloading = DOWNLOADING).as_slice()));
 })
 
@@ -430,6 +490,7 @@ test!(update_lockfile {
                  .arg("-p").arg("bar"),
                 execs().with_status(0).with_stdout(format!("\
 {updating} registry `[..]`
+{updating} bar v0.0.1 -> v0.0.2
 ", updating = UPDATING).as_slice()));
 
     assert_that(p.process(cargo_dir().join("cargo")).arg("build"),
@@ -437,6 +498,262 @@ test!(update_lockfile {
 {downloading} [..] v0.0.2 (the package registry)
 {compiling} bar v0.0.2 (the package registry)
 {compiling} foo v0.0.1 ({dir})
+    Finished [..]
 ", downloading = DOWNLOADING, compiling = COMPILING,
    dir = p.url()).as_slice()));
 })
+
+test!(adding_dep_keeps_locked_versions {
+    let p = project("foo")
+        .file("Cargo.toml", r#"
+            [project]
+            name = "foo"
+            version = "0.0.1"
+            authors = []
+
+            [dependencies]
+            baz = "*"
+        "#)
+        .file("src/main.rs", "fn main() {}");
+    p.build();
+
+    r::mock_pkg("bar", "0.0.1", []);
+    r::mock_pkg("baz", "0.0.1", [("bar", "*")]);
+    assert_that(p.process(cargo_dir().join("cargo")).arg("build"),
+                execs().with_status(0));
+    let lockfile = p.root().join("Cargo.lock");
+    let lock1 = File::open(&lockfile).read_to_string().unwrap();
+
+    // Newer versions are available, but neither the existing dependency nor
+    // the one the manifest now names directly should move.
+    r::mock_pkg("bar", "0.0.2", []);
+    r::mock_pkg("baz", "0.0.2", [("bar", "*")]);
+    r::mock_pkg("qux", "0.1.0", []);
+    File::create(&p.root().join("Cargo.toml")).write_str(r#"
+        [project]
+        name = "foo"
+        version = "0.0.1"
+        authors = []
+
+        [dependencies]
+        bar = "*"
+        baz = "*"
+        qux = "*"
+    "#).unwrap();
+    assert_that(p.process(cargo_dir().join("cargo")).arg("build"),
+                execs().with_status(0));
+    let lock2 = File::open(&lockfile).read_to_string().unwrap();
+
+    assert!(!lock2.as_slice().contains("0.0.2"), "{}", lock2);
+    assert!(lock2.as_slice().contains("qux 0.1.0"), "{}", lock2);
+
+    // The entries for the untouched packages are unchanged.
+    let packages = |lock: &str| {
+        lock.split_str("[[package]]").skip(1).filter(|s| !s.contains("qux"))
+            .map(|s| s.trim().to_string()).collect::<Vec<String>>()
+    };
+    assert_eq!(packages(lock1.as_slice()), packages(lock2.as_slice()));
+})
+
+test!(changed_requirement_is_re_resolved {
+    let p = project("foo")
+        .file("Cargo.toml", r#"
+            [project]
+            name = "foo"
+            version = "0.0.1"
+            authors = []
+
+            [dependencies]
+            bar = "*"
+            baz = "*"
+        "#)
+        .file("src/main.rs", "fn main() {}");
+    p.build();
+
+    r::mock_pkg("bar", "0.0.1", []);
+    r::mock_pkg("baz", "0.0.1", []);
+    assert_that(p.process(cargo_dir().join("cargo")).arg("build"),
+                execs().with_status(0));
+
+    // Only the dependency whose requirement changed moves, even though a
+    // newer version of the other one is available.
+    r::mock_pkg("bar", "0.0.2", []);
+    r::mock_pkg("baz", "0.0.2", []);
+    File::create(&p.root().join("Cargo.toml")).write_str(r#"
+        [project]
+        name = "foo"
+        version = "0.0.1"
+        authors = []
+
+        [dependencies]
+        bar = "0.0.2"
+        baz = "*"
+    "#).unwrap();
+    assert_that(p.process(cargo_dir().join("cargo")).arg("build"),
+                execs().with_status(0).with_stderr("\
+dependencies changed since Cargo.lock was written, re-resolving:
+  `bar` changed from `*` to `^0.0.2`
+"));
+    let lockfile = p.root().join("Cargo.lock");
+    let lock = File::open(&lockfile).read_to_string().unwrap();
+    assert!(lock.as_slice().contains("bar 0.0.2"), "{}", lock);
+    assert!(lock.as_slice().contains("baz 0.0.1"), "{}", lock);
+
+    // Removing a dependency prunes it from the lock file.
+    File::create(&p.root().join("Cargo.toml")).write_str(r#"
+        [project]
+        name = "foo"
+        version = "0.0.1"
+        authors = []
+
+        [dependencies]
+        baz = "*"
+    "#).unwrap();
+    assert_that(p.process(cargo_dir().join("cargo")).arg("build"),
+                execs().with_status(0).with_stderr("\
+dependencies changed since Cargo.lock was written, re-resolving:
+  `bar` was removed
+"));
+    let lock = File::open(&lockfile).read_to_string().unwrap();
+    assert!(!lock.as_slice().contains("bar"), "{}", lock);
+    assert!(lock.as_slice().contains("baz 0.0.1"), "{}", lock);
+})
+
+test!(truncated_cached_tarball_is_downloaded_again {
+    let p = project("foo")
+        .file("Cargo.toml", r#"
+            [project]
+            name = "foo"
+            version = "0.0.1"
+            authors = []
+
+            [dependencies]
+            bar = "*"
+        "#)
+        .file("src/main.rs", "fn main() {}");
+    p.build();
+
+    r::mock_pkg("bar", "0.0.1", []);
+    assert_that(p.process(cargo_dir().join("cargo")).arg("build"),
+                execs().with_status(0));
+    p.root().move_into_the_past().unwrap();
+
+    // Simulate an interrupted download from before tarballs were written
+    // atomically, and drop the unpacked source so it has to be unpacked again.
+    let registry = paths::home().join(".cargo/registry");
+    let tarball = fs::walk_dir(&registry.join("cache")).unwrap().find(|p| {
+        p.filename_str() == Some("bar-0.0.1.crate")
+    }).unwrap();
+    let contents = File::open(&tarball).read_to_end().unwrap();
+    File::create(&tarball).write(contents.slice_to(10)).unwrap();
+    fs::rmdir_recursive(&registry.join("src")).unwrap();
+
+    assert_that(p.process(cargo_dir().join("cargo")).arg("build"),
+                execs().with_status(0).with_stdout(format!("\
+{updating} registry `[..]`
+{downloading} bar v0.0.1 (the package registry)
+    Finished [..]
+", updating = UPDATING, downloading = DOWNLOADING).as_slice())
+                       .with_stderr("\
+failed to unpack the cached tarball of `bar v0.0.1 (the package registry)`, \
+downloading it again
+"));
+    assert_eq!(File::open(&tarball).read_to_end().unwrap(), contents);
+})
+
+test!(version_requirement_arrays {
+    let p = project("foo")
+        .file("Cargo.toml", r#"
+            [project]
+            name = "foo"
+            version = "0.0.1"
+            authors = []
+
+            [dependencies]
+            bar = [">= 0.4", "< 0.6"]
+
+            [dependencies.baz]
+            version = [">= 0.1.0", "< 0.1.2"]
+        "#)
+        .file("src/main.rs", "fn main() {}");
+
+    r::mock_pkg("bar", "0.3.0", []);
+    r::mock_pkg("bar", "0.5.2", []);
+    r::mock_pkg("bar", "0.6.0", []);
+    r::mock_pkg("baz", "0.1.1", []);
+    r::mock_pkg("baz", "0.1.2", []);
+    r::mock_pkg("baz", "0.2.0", []);
+
+    assert_that(p.cargo_process("build"),
+                execs().with_status(0).with_stdout(format!("\
+{updating} registry `{reg}`
+{downloading} [..] v[..] (the package registry)
+{downloading} [..] v[..] (the package registry)
+{compiling} [..] v[..] (the package registry)
+{compiling} [..] v[..] (the package registry)
+{compiling} foo v0.0.1 ({dir})
+    Finished [..]
+",
+        updating = UPDATING,
+        downloading = DOWNLOADING,
+        compiling = COMPILING,
+        dir = p.url(),
+        reg = r::registry()).as_slice()));
+
+    let lock = File::open(&p.root().join("Cargo.lock")).read_to_string()
+                    .unwrap();
+    assert!(lock.as_slice().contains("bar 0.5.2"), "{}", lock);
+    assert!(lock.as_slice().contains("baz 0.1.1"), "{}", lock);
+
+    File::create(&p.root().join("Cargo.toml")).write_str(r#"
+        [project]
+        name = "foo"
+        version = "0.0.1"
+        authors = []
+
+        [dependencies]
+        bar = []
+    "#).unwrap();
+    assert_that(p.process(cargo_dir().join("cargo")).arg("build"),
+                execs().with_status(3).with_stderr("\
+Cargo.toml is not a valid manifest
+
+the version requirement of dependency `bar` is an empty array
+"));
+})
+
+test!(index_feature_names_are_not_validated {
+    let p = project("foo")
+        .file("Cargo.toml", r#"
+            [project]
+            name = "foo"
+            version = "0.0.1"
+            authors = []
+
+            [dependencies]
+            bar = "*"
+        "#)
+        .file("src/main.rs", "fn main() {}");
+    p.build();
+
+    // Only local packages are held to the rules for feature names, packages
+    // which are already published keep loading.
+    r::mock_archive_manifest("bar", "0.0.1", r#"
+        [package]
+        name = "bar"
+        version = "0.0.1"
+        authors = []
+
+        [features]
+        "c++" = []
+    "#);
+    let tarball = File::open(&r::mock_archive_dst("bar", "0.0.1"))
+                       .read_to_end().unwrap();
+    r::publish(r::index_file("bar").as_slice(), format!("\
+{{\"name\":\"bar\",\"vers\":\"0.0.1\",\"deps\":[],\"cksum\":\"{}\",\
+  \"features\":{{\"c++\":[]}},\"yanked\":false}}",
+        r::cksum(tarball.as_slice())).as_slice());
+
+    assert_that(p.process(cargo_dir().join("cargo")).arg("build"),
+                execs().with_status(0));
+})
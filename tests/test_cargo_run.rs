@@ -1,8 +1,11 @@
+use std::dynamic_lib::DynamicLibrary;
 use std::path;
 
 use support::{project, cargo_dir, execs, path2url};
+use support::paths;
 use support::{COMPILING, RUNNING};
 use hamcrest::{assert_that, existing_file};
+use cargo::util::process;
 
 fn setup() {
 }
@@ -22,7 +25,8 @@ test!(simple {
     assert_that(p.cargo_process("run"),
                 execs().with_status(0).with_stdout(format!("\
 {compiling} foo v0.0.1 ({dir})
-{running} `target{sep}foo`
+    Finished [..]
+{running} `target{sep}debug{sep}foo`
 hello
 ",
         compiling = COMPILING,
@@ -78,7 +82,7 @@ test!(no_main_file {
         .file("src/lib.rs", "");
 
     assert_that(p.cargo_process("run"),
-                execs().with_status(101)
+                execs().with_status(1)
                        .with_stderr("a bin target must be available \
                                      for `cargo run`\n"));
 })
@@ -96,7 +100,7 @@ test!(too_many_bins {
         .file("src/bin/b.rs", "");
 
     assert_that(p.cargo_process("run"),
-                execs().with_status(101)
+                execs().with_status(1)
                        .with_stderr("`cargo run` requires that a project only \
                                      have one executable. Use the `--name` option \
                                      to specify which one to run\n"));
@@ -123,7 +127,8 @@ test!(specify_name {
     assert_that(p.cargo_process("run").arg("--name").arg("a"),
                 execs().with_status(0).with_stdout(format!("\
 {compiling} foo v0.0.1 ({dir})
-{running} `target{sep}a`
+    Finished [..]
+{running} `target{sep}debug{sep}a`
 hello a.rs
 ",
         compiling = COMPILING,
@@ -133,7 +138,8 @@ hello a.rs
 
     assert_that(p.process(cargo_dir().join("cargo")).arg("run").arg("--name").arg("b"),
                 execs().with_status(0).with_stdout(format!("\
-{running} `target{sep}b`
+    Finished [..]
+{running} `target{sep}debug{sep}b`
 hello b.rs
 ",
         running = RUNNING,
@@ -159,7 +165,8 @@ test!(run_example {
     assert_that(p.cargo_process("run").arg("--example").arg("a"),
                 execs().with_status(0).with_stdout(format!("\
 {compiling} foo v0.0.1 ({dir})
-{running} `target{sep}examples{sep}a`
+    Finished [..]
+{running} `target{sep}debug{sep}examples{sep}a`
 example
 ",
         compiling = COMPILING,
@@ -211,7 +218,8 @@ test!(one_bin_multiple_examples {
     assert_that(p.cargo_process("run"),
                 execs().with_status(0).with_stdout(format!("\
 {compiling} foo v0.0.1 ({dir})
-{running} `target{sep}main`
+    Finished [..]
+{running} `target{sep}debug{sep}main`
 hello main.rs
 ",
         compiling = COMPILING,
@@ -266,6 +274,7 @@ test!(release_works {
     assert_that(p.cargo_process("run").arg("--release"),
                 execs().with_status(0).with_stdout(format!("\
 {compiling} foo v0.0.1 ({dir})
+    Finished [..]
 {running} `target{sep}release{sep}foo`
 ",
         compiling = COMPILING,
@@ -292,3 +301,90 @@ test!(run_bin_different_name {
 
     assert_that(p.cargo_process("run"), execs().with_status(0));
 })
+
+test!(run_with_dylib_dependency {
+    let p = project("foo")
+        .file("Cargo.toml", r#"
+            [package]
+            name = "foo"
+            version = "0.0.1"
+            authors = []
+
+            [dependencies.bar]
+            path = "bar"
+        "#)
+        .file("src/main.rs", r#"
+            extern crate bar;
+            fn main() { bar::bar(); }
+        "#)
+        .file("bar/Cargo.toml", r#"
+            [package]
+            name = "bar"
+            version = "0.0.1"
+            authors = []
+
+            [lib]
+            name = "bar"
+            crate_type = ["dylib"]
+        "#)
+        .file("bar/src/lib.rs", r#"
+            pub fn bar() { println!("bar"); }
+        "#);
+
+    assert_that(p.cargo_process("run"),
+                execs().with_status(0).with_stdout(format!("\
+{compiling} bar v0.0.1 ({dir})
+{compiling} foo v0.0.1 ({dir})
+    Finished [..]
+{running} `target{sep}debug{sep}foo`
+bar
+",
+                       compiling = COMPILING, running = RUNNING,
+                       dir = p.url(), sep = path::SEP).as_slice()));
+
+    // Without cargo setting up the search path the dylib isn't found.
+    let bare = process(p.bin("foo")).env(DynamicLibrary::envvar(), None::<&str>)
+                                    .exec_with_output();
+    assert!(bare.is_err());
+})
+
+test!(run_keeps_the_invocation_directory {
+    let p = project("foo")
+        .file("Cargo.toml", r#"
+            [project]
+            name = "foo"
+            version = "0.0.1"
+            authors = []
+        "#)
+        .file("src/main.rs", r#"
+            use std::os;
+            fn main() {
+                println!("cwd={}", os::getcwd().display());
+                println!("dir={}", os::getenv("CARGO_MANIFEST_DIR").unwrap());
+            }
+        "#);
+
+    // From a subdirectory of the package the program runs there as well.
+    assert_that(p.cargo_process("run").cwd(p.root().join("src")),
+                execs().with_status(0).with_stdout(format!("\
+[..]
+    Finished [..]
+[..]
+cwd={sub}
+dir={root}
+", sub = p.root().join("src").display(),
+   root = p.root().display()).as_slice()));
+
+    // With a relative `--manifest-path` from elsewhere, the manifest directory
+    // is still absolute.
+    assert_that(p.process(cargo_dir().join("cargo")).arg("run")
+                 .arg("--manifest-path").arg("foo/Cargo.toml")
+                 .cwd(paths::root()),
+                execs().with_status(0).with_stdout(format!("\
+    Finished [..]
+[..]
+cwd={cwd}
+dir={root}
+", cwd = paths::root().display(),
+   root = p.root().display()).as_slice()));
+})
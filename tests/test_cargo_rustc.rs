@@ -0,0 +1,148 @@
+use std::str;
+
+use support::{project, execs, cargo_dir, ResultTest};
+use support::{COMPILING, RUNNING};
+use hamcrest::assert_that;
+
+fn setup() {
+}
+
+test!(extra_args_only_reach_selected_target {
+    let p = project("foo")
+        .file("Cargo.toml", r#"
+            [package]
+            name = "foo"
+            version = "0.0.1"
+            authors = []
+
+            [dependencies.bar]
+            path = "bar"
+        "#)
+        .file("src/lib.rs", r#"
+            #[cfg(extra_cfg)]
+            pub fn foo() {}
+        "#)
+        .file("src/main.rs", r#"
+            extern crate foo;
+            fn main() { foo::foo() }
+        "#)
+        .file("bar/Cargo.toml", r#"
+            [package]
+            name = "bar"
+            version = "0.0.1"
+            authors = []
+        "#)
+        .file("bar/src/lib.rs", "");
+
+    let output = p.cargo_process("rustc").arg("-v").arg("--").arg("--cfg")
+                  .arg("extra_cfg").exec_with_output().assert();
+    let output = str::from_utf8(output.output.as_slice()).assert();
+    let rustcs = output.lines().filter(|l| {
+        l.starts_with(RUNNING)
+    }).collect::<Vec<&str>>();
+    assert_eq!(rustcs.len(), 2);
+    assert!(rustcs[0].contains("--crate-name bar"), "{}", output);
+    assert!(!rustcs[0].contains("extra_cfg"), "{}", output);
+    assert!(rustcs[1].contains("--crate-name foo --crate-type lib"), "{}", output);
+    assert!(rustcs[1].ends_with("--cfg extra_cfg`"), "{}", output);
+})
+
+test!(select_bin {
+    let p = project("foo")
+        .file("Cargo.toml", r#"
+            [package]
+            name = "foo"
+            version = "0.0.1"
+            authors = []
+        "#)
+        .file("src/lib.rs", "")
+        .file("src/bin/a.rs", "fn main() {}")
+        .file("src/bin/b.rs", r#"
+            #[cfg(extra_cfg)]
+            fn main() {}
+        "#);
+
+    assert_that(p.cargo_process("rustc").arg("--bin").arg("b").arg("-v")
+                 .arg("--").arg("--cfg").arg("extra_cfg"),
+                execs().with_status(0).with_stdout(format!("\
+{compiling} foo v0.0.1 ({url})
+{running} `[..]rustc [..]src[..]lib.rs --crate-name foo --crate-type lib [..]`
+{running} `[..]rustc [..]src[..]bin[..]b.rs --crate-name b --crate-type bin \
+[..] --cfg extra_cfg`
+    Finished [..]
+", compiling = COMPILING, running = RUNNING, url = p.url())));
+})
+
+test!(ambiguous_binaries_are_rejected {
+    let p = project("foo")
+        .file("Cargo.toml", r#"
+            [package]
+            name = "foo"
+            version = "0.0.1"
+            authors = []
+        "#)
+        .file("src/bin/a.rs", "fn main() {}")
+        .file("src/bin/b.rs", "fn main() {}");
+
+    assert_that(p.cargo_process("rustc").arg("--").arg("-Z").arg("time-passes"),
+                execs().with_status(1).with_stderr("\
+extra arguments to `rustc` can only be passed to one target, select one with \
+`--bin NAME`
+candidates: `a` ([..]a.rs), `b` ([..]b.rs)
+"));
+})
+
+test!(unknown_target_is_rejected {
+    let p = project("foo")
+        .file("Cargo.toml", r#"
+            [package]
+            name = "foo"
+            version = "0.0.1"
+            authors = []
+        "#)
+        .file("src/lib.rs", "");
+
+    assert_that(p.cargo_process("rustc").arg("--test").arg("nope"),
+                execs().with_status(1).with_stderr("\
+no test target named `nope`
+"));
+})
+
+test!(extra_args_are_part_of_fingerprint {
+    let p = project("foo")
+        .file("Cargo.toml", r#"
+            [package]
+            name = "foo"
+            version = "0.0.1"
+            authors = []
+
+            [dependencies.bar]
+            path = "bar"
+        "#)
+        .file("src/lib.rs", "extern crate bar;")
+        .file("bar/Cargo.toml", r#"
+            [package]
+            name = "bar"
+            version = "0.0.1"
+            authors = []
+        "#)
+        .file("bar/src/lib.rs", "");
+
+    assert_that(p.cargo_process("rustc").arg("--").arg("--cfg").arg("a"),
+                execs().with_status(0).with_stdout(format!("\
+{compiling} bar v0.0.1 ({url})
+{compiling} foo v0.0.1 ({url})
+    Finished [..]
+", compiling = COMPILING, url = p.url())));
+
+    assert_that(p.process(cargo_dir().join("cargo")).arg("rustc")
+                 .arg("--").arg("--cfg").arg("a"),
+                execs().with_status(0).with_stdout("    Finished [..]\n"));
+
+    assert_that(p.process(cargo_dir().join("cargo")).arg("rustc")
+                 .arg("--").arg("--cfg").arg("b"),
+                execs().with_status(0).with_stdout(format!("\
+{compiling} foo v0.0.1 ({url})
+    Finished [..]
+", compiling = COMPILING, url = p.url())));
+})
@@ -1,10 +1,11 @@
+use std::io::{fs, USER_DIR};
 use std::path;
 use std::str;
 
 use support::{project, execs, basic_bin_manifest, basic_lib_manifest};
 use support::{COMPILING, cargo_dir, ResultTest, RUNNING, DOCTEST};
-use support::paths::PathExt;
-use hamcrest::{assert_that, existing_file};
+use support::paths::{mod, PathExt};
+use hamcrest::{assert_that, existing_file, existing_dir, is_not};
 use cargo::util::process;
 
 fn setup() {}
@@ -36,6 +37,7 @@ test!(cargo_test_simple {
     assert_that(p.process(cargo_dir().join("cargo")).arg("test"),
         execs().with_stdout(format!("\
 {} foo v0.5.0 ({})
+    Finished [..]
 {} target[..]foo-[..]
 
 running 1 test
@@ -43,6 +45,9 @@ test test_hello ... ok
 
 test result: ok. 1 passed; 0 failed; 0 ignored; 0 measured
 
+
+     Summary PASS, all 1 test targets passed
+      Passed [..] (1 passed; 0 failed; 0 ignored)
 ",
         COMPILING, p.url(),
         RUNNING)));
@@ -59,7 +64,8 @@ test!(cargo_test_verbose {
     assert_that(p.cargo_process("test").arg("-v").arg("hello"),
         execs().with_stdout(format!("\
 {compiling} foo v0.5.0 ({url})
-{running} `rustc src[..]foo.rs [..]`
+{running} `[..]rustc src[..]foo.rs [..]`
+    Finished [..]
 {running} `[..]target[..]foo-[..] hello`
 
 running 1 test
@@ -67,6 +73,9 @@ test test_hello ... ok
 
 test result: ok. 1 passed; 0 failed; 0 ignored; 0 measured
 
+
+     Summary PASS, all 1 test targets passed
+      Passed [..] (1 passed; 0 failed; 0 ignored)
 ",
         compiling = COMPILING, url = p.url(), running = RUNNING)));
 })
@@ -127,6 +136,7 @@ test!(cargo_test_failing_test {
     assert_that(p.process(cargo_dir().join("cargo")).arg("test"),
         execs().with_stdout(format!("\
 {} foo v0.5.0 ({})
+    Finished [..]
 {} target[..]foo-[..]
 
 running 1 test
@@ -146,13 +156,17 @@ failures:
 
 test result: FAILED. 0 passed; 1 failed; 0 ignored; 0 measured
 
+
+     Summary FAIL, 1 of 1 test targets failed
+      Failed foo (0 passed; 1 failed; 0 ignored)
+     Failing foo: test_hello
 ",
         COMPILING, p.url(), RUNNING,
         sep = path::SEP))
               .with_stderr(format!("\
 task '<main>' panicked at 'Some tests failed', [..]
 "))
-              .with_status(101));
+              .with_status(2));
 })
 
 test!(test_with_lib_dep {
@@ -191,6 +205,7 @@ test!(test_with_lib_dep {
     assert_that(p.cargo_process("test"),
         execs().with_stdout(format!("\
 {} foo v0.0.1 ({})
+    Finished [..]
 {running} target[..]baz-[..]
 
 running 1 test
@@ -212,6 +227,11 @@ test foo_0 ... ok
 
 test result: ok. 1 passed; 0 failed; 0 ignored; 0 measured
 
+
+     Summary PASS, all 3 test targets passed
+      Passed [..] (1 passed; 0 failed; 0 ignored)
+      Passed [..] (1 passed; 0 failed; 0 ignored)
+      Passed doc-tests foo (1 passed; 0 failed; 0 ignored)
 ",
         COMPILING, p.url(), running = RUNNING, doctest = DOCTEST)))
 })
@@ -259,6 +279,7 @@ test!(test_with_deep_lib_dep {
                        .with_stdout(format!("\
 {compiling} foo v0.0.1 ({dir})
 {compiling} bar v0.0.1 ({dir})
+    Finished [..]
 {running} target[..]
 
 running 1 test
@@ -273,6 +294,10 @@ test bar_0 ... ok
 
 test result: ok. 1 passed; 0 failed; 0 ignored; 0 measured
 
+
+     Summary PASS, all 2 test targets passed
+      Passed [..] (1 passed; 0 failed; 0 ignored)
+      Passed doc-tests bar (1 passed; 0 failed; 0 ignored)
 ",
                        compiling = COMPILING, running = RUNNING,
                        doctest = DOCTEST,
@@ -307,6 +332,7 @@ test!(external_test_explicit {
     assert_that(p.cargo_process("test"),
         execs().with_stdout(format!("\
 {} foo v0.0.1 ({})
+    Finished [..]
 {running} target[..]foo-[..]
 
 running 1 test
@@ -327,6 +353,11 @@ running 0 tests
 
 test result: ok. 0 passed; 0 failed; 0 ignored; 0 measured
 
+
+     Summary PASS, all 3 test targets passed
+      Passed [..] (1 passed; 0 failed; 0 ignored)
+      Passed [..] (1 passed; 0 failed; 0 ignored)
+      Passed doc-tests foo (0 passed; 0 failed; 0 ignored)
 ",
         COMPILING, p.url(), running = RUNNING, doctest = DOCTEST)))
 })
@@ -355,6 +386,7 @@ test!(external_test_implicit {
     assert_that(p.cargo_process("test"),
         execs().with_stdout(format!("\
 {} foo v0.0.1 ({})
+    Finished [..]
 {running} target[..]external-[..]
 
 running 1 test
@@ -375,6 +407,11 @@ running 0 tests
 
 test result: ok. 0 passed; 0 failed; 0 ignored; 0 measured
 
+
+     Summary PASS, all 3 test targets passed
+      Passed [..] (1 passed; 0 failed; 0 ignored)
+      Passed [..] (1 passed; 0 failed; 0 ignored)
+      Passed doc-tests foo (0 passed; 0 failed; 0 ignored)
 ",
         COMPILING, p.url(), running = RUNNING, doctest = DOCTEST)))
 })
@@ -396,6 +433,44 @@ test!(dont_run_examples {
                 execs().with_status(0));
 })
 
+test!(broken_example_fails_test {
+    let p = project("foo")
+        .file("Cargo.toml", r#"
+            [project]
+            name = "foo"
+            version = "0.0.1"
+            authors = []
+        "#)
+        .file("src/lib.rs", "")
+        .file("examples/broken.rs", "fn main() { not_a_function() }");
+
+    assert_that(p.cargo_process("build"), execs().with_status(0));
+    assert_that(p.process(cargo_dir().join("cargo")).arg("test"),
+                execs().with_status(2));
+    assert_that(p.process(cargo_dir().join("cargo")).arg("test")
+                 .arg("--no-examples"),
+                execs().with_status(0));
+})
+
+test!(example_opts_out_of_test {
+    let p = project("foo")
+        .file("Cargo.toml", r#"
+            [project]
+            name = "foo"
+            version = "0.0.1"
+            authors = []
+
+            [[example]]
+            name = "broken"
+            test = false
+        "#)
+        .file("src/lib.rs", "")
+        .file("examples/broken.rs", "fn main() { not_a_function() }");
+
+    assert_that(p.cargo_process("test"), execs().with_status(0));
+    assert_that(&p.bin("examples/broken"), is_not(existing_file()));
+})
+
 test!(pass_through_command_line {
     let p = project("foo")
         .file("Cargo.toml", r#"
@@ -413,6 +488,7 @@ test!(pass_through_command_line {
                 execs().with_status(0)
                        .with_stdout(format!("\
 {compiling} foo v0.0.1 ({dir})
+    Finished [..]
 {running} target[..]foo-[..]
 
 running 1 test
@@ -426,6 +502,10 @@ running 0 tests
 
 test result: ok. 0 passed; 0 failed; 0 ignored; 0 measured
 
+
+     Summary PASS, all 2 test targets passed
+      Passed [..] (1 passed; 0 failed; 0 ignored)
+      Passed doc-tests foo (0 passed; 0 failed; 0 ignored)
 ",
                        compiling = COMPILING, running = RUNNING,
                        doctest = DOCTEST,
@@ -435,6 +515,7 @@ test result: ok. 0 passed; 0 failed; 0 ignored; 0 measured
                 execs().with_status(0)
                        .with_stdout(format!("\
 {compiling} foo v0.0.1 ({dir})
+    Finished [..]
 {running} target[..]foo-[..]
 
 running 1 test
@@ -448,6 +529,10 @@ running 0 tests
 
 test result: ok. 0 passed; 0 failed; 0 ignored; 0 measured
 
+
+     Summary PASS, all 2 test targets passed
+      Passed [..] (1 passed; 0 failed; 0 ignored)
+      Passed doc-tests foo (0 passed; 0 failed; 0 ignored)
 ",
                        compiling = COMPILING, running = RUNNING,
                        doctest = DOCTEST,
@@ -500,6 +585,7 @@ test!(lib_bin_same_name {
     assert_that(p.cargo_process("test"),
         execs().with_stdout(format!("\
 {} foo v0.0.1 ({})
+    Finished [..]
 {running} target[..]foo-[..]
 
 running 1 test
@@ -520,6 +606,11 @@ running 0 tests
 
 test result: ok. 0 passed; 0 failed; 0 ignored; 0 measured
 
+
+     Summary PASS, all 3 test targets passed
+      Passed [..] (1 passed; 0 failed; 0 ignored)
+      Passed [..] (1 passed; 0 failed; 0 ignored)
+      Passed doc-tests foo (0 passed; 0 failed; 0 ignored)
 ",
         COMPILING, p.url(), running = RUNNING, doctest = DOCTEST)))
 })
@@ -552,6 +643,7 @@ test!(lib_with_standard_name {
                 execs().with_status(0)
                        .with_stdout(format!("\
 {compiling} syntax v0.0.1 ({dir})
+    Finished [..]
 {running} target[..]syntax-[..]
 
 running 1 test
@@ -573,6 +665,11 @@ test foo_0 ... ok
 
 test result: ok. 1 passed; 0 failed; 0 ignored; 0 measured
 
+
+     Summary PASS, all 3 test targets passed
+      Passed [..] (1 passed; 0 failed; 0 ignored)
+      Passed [..] (1 passed; 0 failed; 0 ignored)
+      Passed doc-tests syntax (1 passed; 0 failed; 0 ignored)
 ",
                        compiling = COMPILING, running = RUNNING,
                        doctest = DOCTEST, dir = p.url()).as_slice()));
@@ -607,6 +704,7 @@ test!(lib_with_standard_name2 {
                 execs().with_status(0)
                        .with_stdout(format!("\
 {compiling} syntax v0.0.1 ({dir})
+    Finished [..]
 {running} target[..]syntax-[..]
 
 running 1 test
@@ -614,6 +712,9 @@ test test ... ok
 
 test result: ok. 1 passed; 0 failed; 0 ignored; 0 measured
 
+
+     Summary PASS, all 1 test targets passed
+      Passed [..] (1 passed; 0 failed; 0 ignored)
 ",
                        compiling = COMPILING, running = RUNNING,
                        dir = p.url()).as_slice()));
@@ -635,7 +736,7 @@ test!(bin_there_for_integration {
             use std::io::Command;
             #[test]
             fn test_test() {
-                let status = Command::new("target/foo").status().unwrap();
+                let status = Command::new("target/debug/foo").status().unwrap();
                 assert!(status.matches_exit_status(1));
             }
         "#);
@@ -694,6 +795,7 @@ test!(test_dylib {
                        .with_stdout(format!("\
 {compiling} bar v0.0.1 ({dir})
 {compiling} foo v0.0.1 ({dir})
+    Finished [..]
 {running} target[..]foo-[..]
 
 running 1 test
@@ -714,6 +816,11 @@ running 0 tests
 
 test result: ok. 0 passed; 0 failed; 0 ignored; 0 measured
 
+
+     Summary PASS, all 3 test targets passed
+      Passed [..] (1 passed; 0 failed; 0 ignored)
+      Passed [..] (1 passed; 0 failed; 0 ignored)
+      Passed doc-tests foo (0 passed; 0 failed; 0 ignored)
 ",
                        compiling = COMPILING, running = RUNNING,
                        doctest = DOCTEST,
@@ -722,6 +829,7 @@ test result: ok. 0 passed; 0 failed; 0 ignored; 0 measured
     assert_that(p.process(cargo_dir().join("cargo")).arg("test"),
                 execs().with_status(0)
                        .with_stdout(format!("\
+    Finished [..]
 {running} target[..]foo-[..]
 
 running 1 test
@@ -742,6 +850,11 @@ running 0 tests
 
 test result: ok. 0 passed; 0 failed; 0 ignored; 0 measured
 
+
+     Summary PASS, all 3 test targets passed
+      Passed [..] (1 passed; 0 failed; 0 ignored)
+      Passed [..] (1 passed; 0 failed; 0 ignored)
+      Passed doc-tests foo (0 passed; 0 failed; 0 ignored)
 ",
                        running = RUNNING,
                        doctest = DOCTEST)));
@@ -766,6 +879,7 @@ test!(test_twice_with_build_cmd {
                 execs().with_status(0)
                        .with_stdout(format!("\
 {compiling} foo v0.0.1 ({dir})
+    Finished [..]
 {running} target[..]foo-[..]
 
 running 1 test
@@ -779,6 +893,10 @@ running 0 tests
 
 test result: ok. 0 passed; 0 failed; 0 ignored; 0 measured
 
+
+     Summary PASS, all 2 test targets passed
+      Passed [..] (1 passed; 0 failed; 0 ignored)
+      Passed doc-tests foo (0 passed; 0 failed; 0 ignored)
 ",
                        compiling = COMPILING, running = RUNNING,
                        doctest = DOCTEST,
@@ -787,6 +905,7 @@ test result: ok. 0 passed; 0 failed; 0 ignored; 0 measured
     assert_that(p.process(cargo_dir().join("cargo")).arg("test"),
                 execs().with_status(0)
                        .with_stdout(format!("\
+    Finished [..]
 {running} target[..]foo-[..]
 
 running 1 test
@@ -800,6 +919,10 @@ running 0 tests
 
 test result: ok. 0 passed; 0 failed; 0 ignored; 0 measured
 
+
+     Summary PASS, all 2 test targets passed
+      Passed [..] (1 passed; 0 failed; 0 ignored)
+      Passed doc-tests foo (0 passed; 0 failed; 0 ignored)
 ",
                        running = RUNNING,
                        doctest = DOCTEST)));
@@ -822,6 +945,7 @@ test!(test_then_build {
                 execs().with_status(0)
                        .with_stdout(format!("\
 {compiling} foo v0.0.1 ({dir})
+    Finished [..]
 {running} target[..]foo-[..]
 
 running 1 test
@@ -835,6 +959,10 @@ running 0 tests
 
 test result: ok. 0 passed; 0 failed; 0 ignored; 0 measured
 
+
+     Summary PASS, all 2 test targets passed
+      Passed [..] (1 passed; 0 failed; 0 ignored)
+      Passed doc-tests foo (0 passed; 0 failed; 0 ignored)
 ",
                        compiling = COMPILING, running = RUNNING,
                        doctest = DOCTEST,
@@ -842,7 +970,7 @@ test result: ok. 0 passed; 0 failed; 0 ignored; 0 measured
 
     assert_that(p.process(cargo_dir().join("cargo")).arg("build"),
                 execs().with_status(0)
-                       .with_stdout(""));
+                       .with_stdout("    Finished [..]\n"));
 })
 
 test!(test_no_run {
@@ -862,6 +990,7 @@ test!(test_no_run {
                 execs().with_status(0)
                        .with_stdout(format!("\
 {compiling} foo v0.0.1 ({dir})
+    Finished [..]
 ",
                        compiling = COMPILING,
                        dir = p.url()).as_slice()));
@@ -888,6 +1017,7 @@ test!(test_run_specific_bin_target {
 
     let expected_stdout = format!("\
 {compiling} foo v0.0.1 ({dir})
+    Finished [..]
 {running} target[..]bin2-[..]
 
 running 1 test
@@ -895,6 +1025,9 @@ test test2 ... ok
 
 test result: ok. 1 passed; 0 failed; 0 ignored; 0 measured
 
+
+     Summary PASS, all 1 test targets passed
+      Passed bin2 (1 passed; 0 failed; 0 ignored)
 ",
        compiling = COMPILING,
        running = RUNNING,
@@ -919,6 +1052,7 @@ test!(test_run_specific_test_target {
 
     let expected_stdout = format!("\
 {compiling} foo v0.0.1 ({dir})
+    Finished [..]
 {running} target[..]b-[..]
 
 running 1 test
@@ -933,6 +1067,10 @@ test test_b ... ok
 
 test result: ok. 1 passed; 0 failed; 0 ignored; 0 measured
 
+
+     Summary PASS, all 2 test targets passed
+      Passed [..] (1 passed; 0 failed; 0 ignored)
+      Passed [..] (1 passed; 0 failed; 0 ignored)
 ",
        compiling = COMPILING,
        running = RUNNING,
@@ -966,7 +1104,11 @@ test!(test_no_harness {
                 execs().with_status(0)
                        .with_stdout(format!("\
 {compiling} foo v0.0.1 ({dir})
+    Finished [..]
 {running} target[..]bar-[..]
+
+     Summary PASS, all 1 test targets passed
+      Passed bar (1 passed; 0 failed; 0 ignored)
 ",
                        compiling = COMPILING, running = RUNNING,
                        dir = p.url()).as_slice()));
@@ -1021,11 +1163,15 @@ test!(selective_testing {
                        .with_stderr("")
                        .with_stdout(format!("\
 {compiling} d1 v0.0.1 ({dir})
+    Finished [..]
 {running} target[..]d1-[..]
 
 running 0 tests
 
 test result: ok. 0 passed; 0 failed; 0 ignored; 0 measured\n
+
+     Summary PASS, all 1 test targets passed
+      Passed [..] (0 passed; 0 failed; 0 ignored)
 ", compiling = COMPILING, running = RUNNING,
    dir = p.url()).as_slice()));
 
@@ -1036,11 +1182,15 @@ test result: ok. 0 passed; 0 failed; 0 ignored; 0 measured\n
                        .with_stderr("")
                        .with_stdout(format!("\
 {compiling} d2 v0.0.1 ({dir})
+    Finished [..]
 {running} target[..]d2-[..]
 
 running 0 tests
 
 test result: ok. 0 passed; 0 failed; 0 ignored; 0 measured\n
+
+     Summary PASS, all 1 test targets passed
+      Passed [..] (0 passed; 0 failed; 0 ignored)
 ", compiling = COMPILING, running = RUNNING,
    dir = p.url()).as_slice()));
 
@@ -1050,11 +1200,15 @@ test result: ok. 0 passed; 0 failed; 0 ignored; 0 measured\n
                        .with_stderr("")
                        .with_stdout(format!("\
 {compiling} foo v0.0.1 ({dir})
+    Finished [..]
 {running} target[..]foo-[..]
 
 running 0 tests
 
 test result: ok. 0 passed; 0 failed; 0 ignored; 0 measured\n
+
+     Summary PASS, all 1 test targets passed
+      Passed [..] (0 passed; 0 failed; 0 ignored)
 ", compiling = COMPILING, running = RUNNING,
    dir = p.url()).as_slice()));
 })
@@ -1210,6 +1364,7 @@ test!(selective_testing_with_docs {
                 execs().with_status(0)
                        .with_stdout(format!("\
 {compiling} d1 v0.0.1 ({dir})
+    Finished [..]
 {running} target[..]deps[..]d1[..]
 
 running 0 tests
@@ -1222,6 +1377,10 @@ running 0 tests
 
 test result: ok. 0 passed; 0 failed; 0 ignored; 0 measured
 
+
+     Summary PASS, all 2 test targets passed
+      Passed [..] (0 passed; 0 failed; 0 ignored)
+      Passed doc-tests d1 (0 passed; 0 failed; 0 ignored)
 ", compiling = COMPILING, running = RUNNING, dir = p.url(),
    doctest = DOCTEST).as_slice()));
 })
@@ -1241,9 +1400,10 @@ test!(example_bin_same_name {
                 execs().with_status(0)
                        .with_stdout(format!("\
 {compiling} foo v0.0.1 ({dir})
-{running} `rustc [..]bin[..]foo.rs [..] --test [..]`
-{running} `rustc [..]bin[..]foo.rs [..]`
-{running} `rustc [..]examples[..]foo.rs [..]`
+{running} `[..]rustc [..]bin[..]foo.rs [..] --test [..]`
+{running} `[..]rustc [..]bin[..]foo.rs [..]`
+{running} `[..]rustc [..]examples[..]foo.rs [..]`
+    Finished [..]
 ", compiling = COMPILING, running = RUNNING, dir = p.url()).as_slice()));
 
     assert_that(&p.bin("foo"), existing_file());
@@ -1275,3 +1435,492 @@ test!(test_with_example_twice {
                 execs().with_status(0));
     assert_that(&p.bin("examples/foo"), existing_file());
 })
+
+test!(doctest_uses_dependencies_and_features {
+    let p = project("foo")
+        .file("Cargo.toml", r#"
+            [package]
+            name = "foo"
+            version = "0.0.1"
+            authors = []
+
+            [dependencies.bar]
+            path = "bar"
+
+            [features]
+            default = ["baz"]
+            baz = []
+        "#)
+        .file("src/lib.rs", r#"
+            extern crate bar;
+
+            /// ```
+            /// extern crate bar;
+            /// extern crate foo;
+            /// fn main() { assert_eq!(foo::baz(), bar::bar()); }
+            /// ```
+            #[cfg(feature = "baz")]
+            pub fn baz() -> int { bar::bar() }
+        "#)
+        .file("bar/Cargo.toml", r#"
+            [package]
+            name = "bar"
+            version = "0.0.1"
+            authors = []
+        "#)
+        .file("bar/src/lib.rs", r#"
+            pub fn bar() -> int { 1 }
+        "#);
+
+    assert_that(p.cargo_process("test"),
+                execs().with_status(0).with_stdout(format!("\
+{compiling} bar v0.0.1 ({dir})
+{compiling} foo v0.0.1 ({dir})
+    Finished [..]
+{running} target[..]foo-[..]
+
+running 0 tests
+
+test result: ok. 0 passed; 0 failed; 0 ignored; 0 measured
+
+{doctest} foo
+
+running 1 test
+test baz_0 ... ok
+
+test result: ok. 1 passed; 0 failed; 0 ignored; 0 measured
+
+
+     Summary PASS, all 2 test targets passed
+      Passed [..] (0 passed; 0 failed; 0 ignored)
+      Passed doc-tests foo (1 passed; 0 failed; 0 ignored)
+",
+                       compiling = COMPILING, running = RUNNING,
+                       doctest = DOCTEST, dir = p.url()).as_slice()));
+})
+
+test!(failing_targets_are_summarized {
+    let p = project("foo")
+        .file("Cargo.toml", r#"
+            [package]
+            name = "foo"
+            version = "0.0.1"
+            authors = []
+
+            [[test]]
+            name = "c"
+            harness = false
+        "#)
+        .file("src/lib.rs", "
+            #[test] fn lib_test() {}
+            #[test] #[ignore] fn lib_ignored() {}
+        ")
+        .file("tests/a.rs", "
+            #[test] fn a_passes() {}
+            #[test] fn a_fails() { panic!() }
+        ")
+        .file("tests/b.rs", "
+            #[test] fn b_passes() {}
+        ")
+        .file("tests/c.rs", "
+            fn main() { std::os::set_exit_status(1) }
+        ");
+
+    let output = match p.cargo_process("test").exec_with_output() {
+        Ok(..) => panic!("expected `cargo test` to fail"),
+        Err(e) => e.output.unwrap(),
+    };
+    let output = str::from_utf8(output.output.as_slice()).assert();
+    let summary = output.find_str("     Summary").expect(output);
+    assert_eq!(output.slice_from(summary), "\
+     Summary FAIL, 2 of 5 test targets failed
+      Failed a (1 passed; 1 failed; 0 ignored)
+      Passed b (1 passed; 0 failed; 0 ignored)
+      Failed c (0 passed; 1 failed; 0 ignored)
+      Passed foo (1 passed; 0 failed; 1 ignored)
+      Passed doc-tests foo (0 passed; 0 failed; 0 ignored)
+     Failing a: a_fails
+");
+})
+
+test!(select_test_phases {
+    let p = project("foo")
+        .file("Cargo.toml", r#"
+            [package]
+            name = "foo"
+            version = "0.0.1"
+            authors = []
+        "#)
+        .file("src/lib.rs", "
+            /// ```
+            /// foo::foo()
+            /// ```
+            pub fn foo() {}
+            #[test] fn lib_test() {}
+        ")
+        .file("tests/it.rs", "
+            extern crate foo;
+            #[test] fn it_test() { foo::foo() }
+        ");
+    assert_that(p.cargo_process("build"), execs().with_status(0));
+
+    let run = |args: &[&str]| {
+        let output = p.process(cargo_dir().join("cargo")).arg("test").args(args)
+                      .exec_with_output().assert();
+        str::from_utf8(output.output.as_slice()).assert().to_string()
+    };
+
+    let output = run(&[]);
+    assert!(output.contains("test lib_test"), "lib_test missing\n{}", output);
+    assert!(output.contains("test it_test"), "it_test missing\n{}", output);
+    assert!(output.contains("Doc-tests foo"), "doctests missing\n{}", output);
+
+    let output = run(&["--lib"]);
+    assert!(output.contains("test lib_test"), "lib_test missing\n{}", output);
+    assert!(!output.contains("test it_test"), "it_test ran\n{}", output);
+    assert!(!output.contains("Doc-tests"), "doctests ran\n{}", output);
+
+    let output = run(&["--name", "it"]);
+    assert!(!output.contains("test lib_test"), "lib_test ran\n{}", output);
+    assert!(output.contains("test it_test"), "it_test missing\n{}", output);
+    assert!(!output.contains("Doc-tests"), "doctests ran\n{}", output);
+
+    let output = run(&["--test", "it"]);
+    assert!(!output.contains("test lib_test"), "lib_test ran\n{}", output);
+    assert!(output.contains("test it_test"), "it_test missing\n{}", output);
+    assert!(!output.contains("Doc-tests"), "doctests ran\n{}", output);
+
+    let output = run(&["--doc"]);
+    assert!(!output.contains("test lib_test"), "lib_test ran\n{}", output);
+    assert!(!output.contains("test it_test"), "it_test ran\n{}", output);
+    assert!(output.contains("Doc-tests foo"), "doctests missing\n{}", output);
+
+    let output = run(&["--lib", "--doc"]);
+    assert!(output.contains("test lib_test"), "lib_test missing\n{}", output);
+    assert!(!output.contains("test it_test"), "it_test ran\n{}", output);
+    assert!(output.contains("Doc-tests foo"), "doctests missing\n{}", output);
+})
+
+test!(selected_phases_build_only_what_they_need {
+    let p = project("foo")
+        .file("Cargo.toml", r#"
+            [package]
+            name = "foo"
+            version = "0.0.1"
+            authors = []
+        "#)
+        .file("src/lib.rs", "
+            /// ```
+            /// foo::foo()
+            /// ```
+            pub fn foo() {}
+        ")
+        .file("src/main.rs", "invalid rust code")
+        .file("tests/it.rs", "
+            extern crate foo;
+            #[test] fn it_test() { foo::foo() }
+        ")
+        .file("tests/broken.rs", "invalid rust code");
+
+    // Only the library is built for its documentation tests
+    assert_that(p.cargo_process("test").arg("--doc"),
+                execs().with_status(0)
+                       .with_stdout(format!("\
+{compiling} foo v0.0.1 ({dir})
+    Finished [..]
+{doctest} foo
+
+running 1 test
+test foo_0 ... ok
+
+test result: ok. 1 passed; 0 failed; 0 ignored; 0 measured
+
+
+     Summary PASS, all 1 test targets passed
+      Passed doc-tests foo (1 passed; 0 failed; 0 ignored)
+", compiling = COMPILING, doctest = DOCTEST, dir = p.url()).as_slice()));
+
+    // and the selected integration test along with it
+    assert_that(p.process(cargo_dir().join("cargo")).arg("test")
+                 .arg("--test").arg("it"),
+                execs().with_status(0));
+
+    assert_that(p.process(cargo_dir().join("cargo")).arg("test")
+                 .arg("--test").arg("nope"),
+                execs().with_status(101)
+                       .with_stderr("no test target named `nope`\n"));
+})
+
+test!(doc_flag_respects_doctest_false {
+    let p = project("foo")
+        .file("Cargo.toml", r#"
+            [package]
+            name = "foo"
+            version = "0.0.1"
+            authors = []
+
+            [lib]
+            name = "foo"
+            doctest = false
+        "#)
+        .file("src/lib.rs", "
+            /// ```
+            /// foo::foo()
+            /// ```
+            pub fn foo() {}
+            #[test] fn lib_test() {}
+        ");
+
+    assert_that(p.cargo_process("test").arg("--doc"),
+                execs().with_status(0)
+                       .with_stdout(format!("\
+{compiling} foo v0.0.1 ({dir})
+    Finished [..]
+",
+                       compiling = COMPILING, dir = p.url()).as_slice()));
+})
+
+test!(skip_targets {
+    let p = project("foo")
+        .file("Cargo.toml", r#"
+            [package]
+            name = "foo"
+            version = "0.0.1"
+            authors = []
+        "#)
+        .file("src/main.rs", "fn main() {} #[test] fn main_test() {}")
+        .file("tests/fast.rs", "#[test] fn fast() {}")
+        .file("tests/slow.rs", "invalid rust code");
+
+    assert_that(p.cargo_process("test").arg("--skip-target").arg("slow")
+                 .arg("--skip-target").arg("foo"),
+                execs().with_status(0).with_stdout(format!("\
+{compiling} foo v0.0.1 ({dir})
+    Finished [..]
+{running} target[..]fast-[..]
+
+running 1 test
+test fast ... ok
+
+test result: ok. 1 passed; 0 failed; 0 ignored; 0 measured
+
+
+     Summary PASS, all 1 test targets passed
+      Passed [..] (1 passed; 0 failed; 0 ignored)
+",
+                       compiling = COMPILING, running = RUNNING,
+                       dir = p.url()).as_slice()));
+})
+
+test!(skip_unknown_target {
+    let p = project("foo")
+        .file("Cargo.toml", r#"
+            [package]
+            name = "foo"
+            version = "0.0.1"
+            authors = []
+        "#)
+        .file("src/main.rs", "fn main() {}")
+        .file("tests/slow.rs", "");
+
+    assert_that(p.cargo_process("test").arg("--skip-target").arg("slwo"),
+                execs().with_status(1).with_stderr(format!("\
+no target named `slwo` to skip in package `foo v0.0.1 ({dir})`
+", dir = p.url()).as_slice()));
+})
+
+test!(tests_run_in_the_package_root {
+    let p = project("foo")
+        .file("Cargo.toml", r#"
+            [project]
+            name = "foo"
+            version = "0.0.1"
+            authors = []
+        "#)
+        .file("src/lib.rs", "")
+        .file("fixtures/data.txt", "data")
+        .file("tests/cwd.rs", r#"
+            use std::io::File;
+            use std::os;
+
+            #[test]
+            fn cwd_is_package_root() {
+                let dir = os::getenv("CARGO_MANIFEST_DIR").unwrap();
+                let dir = Path::new(dir);
+                assert!(dir.is_absolute());
+                assert_eq!(os::getcwd(), dir);
+                assert_eq!(File::open(&Path::new("fixtures/data.txt"))
+                                .read_to_string().unwrap().as_slice(), "data");
+            }
+        "#);
+
+    assert_that(p.cargo_process("test").cwd(p.root().join("tests")),
+                execs().with_status(0));
+
+    assert_that(p.process(cargo_dir().join("cargo")).arg("test")
+                 .arg("--manifest-path").arg("foo/Cargo.toml")
+                 .cwd(paths::root()),
+                execs().with_status(0));
+})
+
+test!(test_dirs_add_a_test_per_file {
+    let p = project("foo")
+        .file("Cargo.toml", r#"
+            [package]
+            name = "foo"
+            version = "0.0.1"
+            authors = []
+            test-dirs = ["integration"]
+        "#)
+        .file("src/lib.rs", "")
+        .file("tests/inferred.rs", "#[test] fn test_inferred() {}")
+        .file("integration/a.rs", "#[test] fn test_a() {}")
+        .file("integration/b.rs", "#[test] fn test_b() {}")
+        .file("integration/c.rs", "#[test] fn test_c() {}")
+        .file("integration/data.txt", "");
+    p.build();
+
+    let output = p.process(cargo_dir().join("cargo")).arg("test")
+                  .exec_with_output().assert();
+    let output = str::from_utf8(output.output.as_slice()).assert();
+    for name in ["inferred", "a", "b", "c"].iter() {
+        assert!(output.contains(format!("test test_{} ... ok",
+                                        name).as_slice()),
+                "test_{} didn't run: {}", name, output);
+    }
+
+    assert_that(p.process(cargo_dir().join("cargo")).arg("test")
+                 .arg("--name").arg("b"),
+                execs().with_status(0).with_stdout(format!("\
+    Finished [..]
+{running} target[..]b-[..]
+
+running 1 test
+test test_b ... ok
+
+test result: ok. 1 passed; 0 failed; 0 ignored; 0 measured
+
+
+     Summary PASS, all 1 test targets passed
+      Passed [..] (1 passed; 0 failed; 0 ignored)
+", running = RUNNING).as_slice()));
+})
+
+test!(test_dirs_collide_with_declared_test {
+    let p = project("foo")
+        .file("Cargo.toml", r#"
+            [package]
+            name = "foo"
+            version = "0.0.1"
+            authors = []
+            test-dirs = ["integration"]
+
+            [[test]]
+            name = "a"
+            path = "a.rs"
+        "#)
+        .file("src/lib.rs", "")
+        .file("a.rs", "")
+        .file("integration/a.rs", "");
+
+    assert_that(p.cargo_process("test"),
+                execs().with_status(3).with_stderr(format!("\
+Cargo.toml is not a valid manifest
+
+test target `a` at `integration{sep}a.rs` has the same name as the test target \
+at `a.rs`
+", sep = path::SEP).as_slice()));
+})
+
+test!(test_dirs_listing_tests_dir {
+    let p = project("foo")
+        .file("Cargo.toml", r#"
+            [package]
+            name = "foo"
+            version = "0.0.1"
+            authors = []
+            test-dirs = ["tests", "integration"]
+        "#)
+        .file("src/lib.rs", "")
+        .file("tests/inferred.rs", "#[test] fn test_inferred() {}")
+        .file("integration/a.rs", "#[test] fn test_a() {}");
+    p.build();
+
+    let output = p.process(cargo_dir().join("cargo")).arg("test")
+                  .exec_with_output().assert();
+    let output = str::from_utf8(output.output.as_slice()).assert();
+    for name in ["inferred", "a"].iter() {
+        assert!(output.contains(format!("test test_{} ... ok",
+                                        name).as_slice()),
+                "test_{} didn't run: {}", name, output);
+    }
+    // `tests` is only searched once, so its test runs just once.
+    assert_eq!(output.split_str("test test_inferred ... ok").count(), 2);
+})
+
+test!(release_builds_tests_with_optimizations {
+    let p = project("foo")
+        .file("Cargo.toml", r#"
+            [package]
+            name = "foo"
+            version = "0.0.1"
+            authors = []
+        "#)
+        .file("src/lib.rs", "pub fn is_debug() -> bool { !cfg!(ndebug) }")
+        .file("tests/debug.rs", r#"
+            extern crate foo;
+
+            #[test]
+            fn debug_assertions() {
+                assert!(!cfg!(ndebug));
+                assert!(foo::is_debug());
+            }
+        "#);
+
+    assert_that(p.cargo_process("test"), execs().with_status(0));
+
+    assert_that(p.process(cargo_dir().join("cargo")).arg("test")
+                 .arg("--release").arg("--no-run"),
+                execs().with_status(0).with_stdout(format!("\
+{compiling} foo v0.0.1 ({dir})
+    Finished release [optimized] target(s) in [..]
+", compiling = COMPILING, dir = p.url()).as_slice()));
+    assert_that(&p.root().join("target/release"), existing_dir());
+
+    assert_that(p.process(cargo_dir().join("cargo")).arg("test")
+                 .arg("--release"),
+                execs().with_status(2));
+})
+
+test!(doctest_counts_survive_whitespace_in_tmpdir {
+    let p = project("foo")
+        .file("Cargo.toml", r#"
+            [package]
+            name = "foo"
+            version = "0.0.1"
+            authors = []
+        "#)
+        .file("src/lib.rs", "
+            /// ```
+            /// foo::foo()
+            /// ```
+            ///
+            /// ```ignore
+            /// not run
+            /// ```
+            pub fn foo() {}
+        ");
+    let tmp = paths::root().join("tmp dir");
+    fs::mkdir_recursive(&tmp, USER_DIR).assert();
+
+    // The log can't be passed through rustdoc, so the counts are read from
+    // the output of the tests instead.
+    let output = p.cargo_process("test").arg("--doc")
+                  .env("TMPDIR", Some(tmp.display().to_string()))
+                  .exec_with_output().assert();
+    let output = str::from_utf8(output.output.as_slice()).assert();
+    assert!(output.contains("test foo_0 ... ok"), "{}", output);
+    assert!(output.contains("\
+      Passed doc-tests foo (1 passed; 0 failed; 1 ignored)
+"), "{}", output);
+})
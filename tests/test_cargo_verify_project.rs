@@ -0,0 +1,51 @@
+use support::{project, execs, cargo_dir};
+use hamcrest::assert_that;
+
+fn setup() {}
+
+test!(valid_project_in_parent_directory {
+    let p = project("foo")
+        .file("Cargo.toml", r#"
+            [package]
+            name = "foo"
+            version = "0.5.0"
+            authors = []
+        "#)
+        .file("src/main.rs", "fn main() {}");
+    p.build();
+
+    assert_that(p.process(cargo_dir().join("cargo")).arg("verify-project")
+                 .cwd(p.root().join("src")),
+                execs().with_status(0).with_stdout("{\"success\":\"true\"}\n"));
+})
+
+test!(toml_syntax_error {
+    let p = project("foo")
+        .file("Cargo.toml", r#"
+            [package]
+            name = "foo
+        "#)
+        .file("src/main.rs", "fn main() {}");
+
+    assert_that(p.cargo_process("verify-project").arg("--manifest-path")
+                 .arg(p.root().join("Cargo.toml")),
+                execs().with_status(1).with_stdout("\
+{\"invalid\":\"[..]could not parse input TOML[..]\"}
+"));
+})
+
+test!(no_targets {
+    let p = project("foo")
+        .file("Cargo.toml", r#"
+            [package]
+            name = "foo"
+            version = "0.5.0"
+            authors = []
+        "#);
+
+    assert_that(p.cargo_process("verify-project"),
+                execs().with_status(1).with_stdout("\
+{\"invalid\":\"no targets specified in the manifest\\neither src/lib.rs, \
+src/main.rs, a [lib] section, or [[bin]] section must be present\"}
+"));
+})
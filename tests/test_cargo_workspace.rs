@@ -0,0 +1,317 @@
+use support::{project, execs, main_file, cargo_dir, ProjectBuilder};
+use support::{COMPILING, RUNNING};
+use hamcrest::{assert_that, existing_file, existing_dir, is_not};
+use cargo::util::process;
+
+fn setup() {
+}
+
+fn workspace() -> ProjectBuilder {
+    project("foo")
+        .file("Cargo.toml", r#"
+            [workspace]
+            members = ["crates/*"]
+        "#)
+        .file("crates/a/Cargo.toml", r#"
+            [package]
+            name = "a"
+            version = "0.1.0"
+            authors = []
+
+            [dependencies.b]
+            path = "../b"
+        "#)
+        .file("crates/a/src/main.rs",
+              main_file(r#""{}", b::gimme()"#, ["b"]).as_slice())
+        .file("crates/b/Cargo.toml", r#"
+            [package]
+            name = "b"
+            version = "0.1.0"
+            authors = []
+        "#)
+        .file("crates/b/src/lib.rs", r#"
+            pub fn gimme() -> &'static str { "from b" }
+        "#)
+        .file("crates/c/Cargo.toml", r#"
+            [package]
+            name = "c"
+            version = "0.1.0"
+            authors = []
+        "#)
+        .file("crates/c/src/main.rs", "fn main() {}")
+}
+
+test!(virtual_root_builds_every_member {
+    let p = workspace();
+
+    // All the members are built together, so none of them moves aside what
+    // another one built.
+    assert_that(p.cargo_process("build"),
+                execs().with_status(0)
+                       .with_stdout(format!("\
+{compiling} b v0.1.0 ({dir})
+{compiling} [..] v0.1.0 ({dir})
+{compiling} [..] v0.1.0 ({dir})
+    Finished [..]
+",
+                       compiling = COMPILING, dir = p.url()).as_slice()));
+
+    assert_that(&p.bin("a"), existing_file());
+    assert_that(&p.bin("c"), existing_file());
+    assert_that(&p.root().join("Cargo.lock"), existing_file());
+    assert_that(&p.root().join("crates/a/Cargo.lock"), is_not(existing_file()));
+    assert_that(&p.root().join("crates/a/target"), is_not(existing_dir()));
+
+    assert_that(process(p.bin("a")),
+                execs().with_status(0).with_stdout("from b\n"));
+})
+
+test!(member_builds_in_the_shared_target_dir {
+    let p = workspace();
+    p.build();
+
+    assert_that(p.process(cargo_dir().join("cargo")).arg("build")
+                 .cwd(p.root().join("crates/a")),
+                execs().with_status(0)
+                       .with_stdout(format!("\
+{compiling} b v0.1.0 ({dir})
+{compiling} a v0.1.0 ({dir})
+    Finished [..]
+",
+                       compiling = COMPILING, dir = p.url()).as_slice()));
+
+    assert_that(&p.bin("a"), existing_file());
+    assert_that(&p.bin("c"), is_not(existing_file()));
+    assert_that(&p.root().join("Cargo.lock"), existing_file());
+    assert_that(&p.root().join("crates/a/Cargo.lock"), is_not(existing_file()));
+    assert_that(&p.root().join("crates/a/target"), is_not(existing_dir()));
+
+    assert_that(p.process(cargo_dir().join("cargo")).arg("run")
+                 .cwd(p.root().join("crates/a")),
+                execs().with_status(0)
+                       .with_stdout(format!("\
+    Finished [..]
+{running} `[..]target[..]debug[..]a[..]`
+from b
+",
+                       running = RUNNING).as_slice()));
+})
+
+test!(member_dev_dependencies_are_resolved {
+    let p = project("foo")
+        .file("Cargo.toml", r#"
+            [workspace]
+            members = ["a", "b"]
+        "#)
+        .file("a/Cargo.toml", r#"
+            [package]
+            name = "a"
+            version = "0.1.0"
+            authors = []
+
+            [dev-dependencies.b]
+            path = "../b"
+        "#)
+        .file("a/src/lib.rs", "")
+        .file("a/tests/uses_b.rs", r#"
+            extern crate b;
+            #[test] fn it_works() { assert_eq!(b::gimme(), 1) }
+        "#)
+        .file("b/Cargo.toml", r#"
+            [package]
+            name = "b"
+            version = "0.1.0"
+            authors = []
+        "#)
+        .file("b/src/lib.rs", "pub fn gimme() -> int { 1 }");
+    p.build();
+
+    assert_that(p.process(cargo_dir().join("cargo")).arg("test")
+                 .cwd(p.root().join("a")),
+                execs().with_status(0));
+})
+
+test!(virtual_manifest_with_package_sections {
+    let p = project("foo")
+        .file("Cargo.toml", r#"
+            [workspace]
+            members = ["a"]
+
+            [dependencies]
+            b = "0.1"
+        "#)
+        .file("a/Cargo.toml", r#"
+            [package]
+            name = "a"
+            version = "0.1.0"
+            authors = []
+        "#)
+        .file("a/src/lib.rs", "");
+
+    assert_that(p.cargo_process("build"),
+                execs().with_status(3).with_stderr("\
+Cargo.toml is not a valid manifest
+
+a virtual manifest can only have a [workspace] section, but [dependencies] \
+was found
+move it into the manifest of the workspace member it belongs to
+"));
+})
+
+test!(workspace_member_without_manifest {
+    let p = project("foo")
+        .file("Cargo.toml", r#"
+            [workspace]
+            members = ["a", "crates/*"]
+        "#)
+        .file("a/src/lib.rs", "");
+
+    assert_that(p.cargo_process("build"),
+                execs().with_status(3).with_stderr("\
+Cargo.toml is not a valid manifest
+
+workspace member `a` is not a package, `[..]a[..]Cargo.toml` does not exist
+"));
+})
+
+test!(member_inherits_workspace_dependency {
+    let p = project("foo")
+        .file("Cargo.toml", r#"
+            [workspace]
+            members = ["crates/*"]
+
+            [workspace.dependencies.util]
+            version = "0.1.0"
+            path = "util"
+            features = ["a"]
+        "#)
+        .file("crates/a/Cargo.toml", r#"
+            [package]
+            name = "a"
+            version = "0.1.0"
+            authors = []
+
+            [dependencies.util]
+            workspace = true
+            features = ["b"]
+        "#)
+        .file("crates/a/src/main.rs", r#"
+            extern crate util;
+            fn main() { util::a(); util::b(); }
+        "#)
+        .file("util/Cargo.toml", r#"
+            [package]
+            name = "util"
+            version = "0.1.0"
+            authors = []
+
+            [features]
+            a = []
+            b = []
+        "#)
+        .file("util/src/lib.rs", r#"
+            #[cfg(feature = "a")] pub fn a() {}
+            #[cfg(feature = "b")] pub fn b() {}
+        "#);
+    p.build();
+
+    assert_that(p.process(cargo_dir().join("cargo")).arg("build")
+                 .cwd(p.root().join("crates/a")),
+                execs().with_status(0)
+                       .with_stdout(format!("\
+{compiling} util v0.1.0 ({dir})
+{compiling} a v0.1.0 ({dir})
+    Finished [..]
+",
+                       compiling = COMPILING, dir = p.url()).as_slice()));
+})
+
+test!(member_inherits_undeclared_dependency {
+    let p = project("foo")
+        .file("Cargo.toml", r#"
+            [workspace]
+            members = ["crates/*"]
+        "#)
+        .file("crates/a/Cargo.toml", r#"
+            [package]
+            name = "a"
+            version = "0.1.0"
+            authors = []
+
+            [dependencies.serde]
+            workspace = true
+        "#)
+        .file("crates/a/src/lib.rs", "");
+    p.build();
+
+    assert_that(p.process(cargo_dir().join("cargo")).arg("build")
+                 .cwd(p.root().join("crates/a")),
+                execs().with_status(3).with_stderr("\
+Cargo.toml is not a valid manifest
+
+dependency `serde` has `workspace = true`, but it isn't declared in the \
+[workspace.dependencies] of ..[..]..[..]Cargo.toml
+"));
+})
+
+test!(standalone_package_inherits_dependency {
+    let p = project("foo")
+        .file("Cargo.toml", r#"
+            [package]
+            name = "foo"
+            version = "0.1.0"
+            authors = []
+
+            [dependencies.serde]
+            workspace = true
+        "#)
+        .file("src/lib.rs", "");
+
+    assert_that(p.cargo_process("build"),
+                execs().with_status(3).with_stderr("\
+Cargo.toml is not a valid manifest
+
+dependency `serde` has `workspace = true`, but the package is not a member \
+of a workspace
+no virtual manifest listing `[..]foo` among its members was found in the \
+parent directories
+"));
+})
+
+test!(member_tests_run_in_the_member_directory {
+    let p = project("foo")
+        .file("Cargo.toml", r#"
+            [workspace]
+            members = ["crates/a"]
+        "#)
+        .file("crates/a/Cargo.toml", r#"
+            [package]
+            name = "a"
+            version = "0.1.0"
+            authors = []
+        "#)
+        .file("crates/a/data.txt", "fixture")
+        .file("crates/a/src/lib.rs", r#"
+            //! ```
+            //! std::io::File::create(&Path::new("doctest-ran")).unwrap();
+            //! ```
+        "#)
+        .file("crates/a/tests/fixture.rs", r#"
+            use std::io::File;
+            use std::io::fs::PathExtensions;
+            use std::os;
+
+            #[test]
+            fn reads_fixture() {
+                let contents = File::open(&Path::new("data.txt"))
+                                    .read_to_string().unwrap();
+                assert_eq!(contents.as_slice(), "fixture");
+                let dir = Path::new(os::getenv("CARGO_MANIFEST_DIR").unwrap());
+                assert!(dir.join("data.txt").exists());
+            }
+        "#);
+
+    assert_that(p.cargo_process("test"), execs().with_status(0));
+    // The doc tests of the member ran, in its directory too.
+    assert_that(&p.root().join("crates/a/doctest-ran"), existing_file());
+})
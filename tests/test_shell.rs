@@ -3,6 +3,7 @@ use term::{Terminal, TerminfoTerminal, color};
 use hamcrest::{assert_that};
 
 use cargo::core::shell::{Shell,ShellConfig};
+use cargo::core::shell::{Auto,Always,Never};
 
 use support::{ResultTest,Tap,shell_writes};
 
@@ -15,7 +16,7 @@ fn pair() -> (ChanWriter, ChanReader) {
 }
 
 test!(non_tty {
-    let config = ShellConfig { color: true, verbose: true, tty: false };
+    let config = ShellConfig { color_config: Auto, verbose: true, tty: false };
     let (tx, mut rx) = pair();
 
     Shell::create(box tx, config).tap(|shell| {
@@ -27,7 +28,7 @@ test!(non_tty {
 })
 
 test!(color_explicitly_disabled {
-    let config = ShellConfig { color: false, verbose: true, tty: true };
+    let config = ShellConfig { color_config: Never, verbose: true, tty: true };
     let (tx, mut rx) = pair();
 
     Shell::create(box tx, config).tap(|shell| {
@@ -41,7 +42,7 @@ test!(colored_shell {
     let term = TerminfoTerminal::new(MemWriter::new());
     if term.is_none() { return }
 
-    let config = ShellConfig { color: true, verbose: true, tty: true };
+    let config = ShellConfig { color_config: Auto, verbose: true, tty: true };
     let (tx, mut rx) = pair();
 
     Shell::create(box tx, config).tap(|shell| {
@@ -53,6 +54,39 @@ test!(colored_shell {
                                             color::RED).assert()));
 })
 
+test!(color_forced_on_non_tty {
+    let term = TerminfoTerminal::new(MemWriter::new());
+    if term.is_none() { return }
+
+    let config = ShellConfig { color_config: Always, verbose: true, tty: false };
+    let (tx, mut rx) = pair();
+
+    Shell::create(box tx, config).tap(|shell| {
+        shell.say("Hey Alex", color::RED).assert();
+    });
+    let buf = rx.read_to_end().unwrap();
+    assert_that(buf.as_slice(),
+                shell_writes(colored_output("Hey Alex\n",
+                                            color::RED).assert()));
+})
+
+test!(color_enabled_after_creation {
+    let term = TerminfoTerminal::new(MemWriter::new());
+    if term.is_none() { return }
+
+    let config = ShellConfig { color_config: Never, verbose: true, tty: false };
+    let (tx, mut rx) = pair();
+
+    Shell::create(box tx, config).tap(|shell| {
+        shell.set_color_config(Always);
+        shell.say("Hey Alex", color::RED).assert();
+    });
+    let buf = rx.read_to_end().unwrap();
+    assert_that(buf.as_slice(),
+                shell_writes(colored_output("Hey Alex\n",
+                                            color::RED).assert()));
+})
+
 fn colored_output<S: Str>(string: S, color: color::Color) -> IoResult<String> {
     let mut term = TerminfoTerminal::new(MemWriter::new()).unwrap();
     try!(term.reset());
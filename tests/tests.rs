@@ -5,6 +5,7 @@ extern crate cargo;
 extern crate flate2;
 extern crate git2;
 extern crate hamcrest;
+extern crate libc;
 extern crate serialize;
 extern crate tar;
 extern crate term;
@@ -27,6 +28,7 @@ macro_rules! test(
 
 mod test_cargo;
 mod test_cargo_bench;
+mod test_cargo_add;
 mod test_cargo_clean;
 mod test_cargo_compile;
 mod test_cargo_compile_custom_build;
@@ -37,16 +39,23 @@ mod test_cargo_test;
 mod test_shell;
 mod test_cargo_cross_compile;
 mod test_cargo_run;
+mod test_cargo_rustc;
 mod test_cargo_version;
 mod test_cargo_new;
 mod test_cargo_compile_plugins;
 mod test_cargo_doc;
+mod test_cargo_facade;
 mod test_cargo_features;
 mod test_cargo_freshness;
 mod test_cargo_generate_lockfile;
+mod test_cargo_local_registry;
 mod test_cargo_profiles;
+mod test_cargo_read_manifest;
 mod test_cargo_package;
 mod test_cargo_build_auth;
 mod test_cargo_registry;
 mod test_cargo_publish;
 mod test_cargo_fetch;
+mod test_cargo_workspace;
+mod test_cargo_compile_options;
+mod test_cargo_verify_project;